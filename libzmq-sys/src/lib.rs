@@ -12,6 +12,11 @@ include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
 pub mod errno;
 
+// Draft options of libzmq 4.3.3 that the headers of the vendored 4.3.2 do
+// not define. These must be removed once the bindings define them.
+pub const ZMQ_ZERO_COPY_RECV: u32 = 10;
+pub const ZMQ_RECONNECT_STOP: u32 = 109;
+
 #[cfg(test)]
 mod test {
     #[test]
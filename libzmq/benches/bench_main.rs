@@ -1,7 +1,15 @@
 use criterion::{criterion_group, criterion_main};
 
 mod curve;
+mod msg;
 mod socket;
 
-criterion_group!(benches, socket::bench, curve::bench);
+criterion_group!(
+    benches,
+    socket::bench,
+    socket::bench_batch,
+    socket::bench_stats,
+    curve::bench,
+    msg::bench
+);
 criterion_main!(benches);
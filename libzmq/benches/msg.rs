@@ -0,0 +1,37 @@
+use criterion::{black_box, Benchmark, Criterion, Throughput};
+
+use libzmq::*;
+
+use lazy_static::lazy_static;
+
+const SLICE_AMOUNT: usize = 8;
+const SLICE_SIZE: usize = 64;
+
+lazy_static! {
+    static ref DATASET: Vec<Vec<u8>> = (0..SLICE_AMOUNT)
+        .map(|i| vec![i as u8; SLICE_SIZE])
+        .collect();
+    static ref SLICES: Vec<&'static [u8]> =
+        DATASET.iter().map(Vec::as_slice).collect();
+}
+
+pub(crate) fn bench(c: &mut Criterion) {
+    c.bench(
+        &"msg from 8 64u8 slices".to_owned(),
+        Benchmark::new("Vec::extend_from_slice (control)", move |b| {
+            b.iter(|| {
+                let mut vec = Vec::new();
+                for slice in SLICES.iter() {
+                    vec.extend_from_slice(slice);
+                }
+                black_box(Msg::from(vec));
+            });
+        })
+        .with_function("Msg::from_slices", move |b| {
+            b.iter(|| {
+                black_box(Msg::from_slices(&SLICES));
+            });
+        })
+        .throughput(Throughput::Bytes((SLICE_AMOUNT * SLICE_SIZE) as u32)),
+    );
+}
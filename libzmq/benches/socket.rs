@@ -77,6 +77,99 @@ pub(crate) fn bench(c: &mut Criterion) {
                 }
             });
         })
+        .with_function("radio transmit", move |b| {
+            let producer = RadioBuilder::new().bind(&*ADDR).build().unwrap();
+
+            let bound = producer.last_endpoint().unwrap().unwrap();
+            let consumer = DishBuilder::new().connect(bound).build().unwrap();
+
+            let mut msg = Msg::new();
+
+            b.iter(|| {
+                let dataset = gen_dataset(MSG_AMOUNT, MSG_SIZE);
+                for data in dataset {
+                    producer.transmit(*GROUP, data).unwrap();
+                    let _ = consumer.try_recv(&mut msg);
+                }
+            });
+        })
+        .throughput(Throughput::Bytes((MSG_AMOUNT * MSG_SIZE) as u32))
+        .sample_size(30),
+    );
+}
+
+pub(crate) fn bench_batch(c: &mut Criterion) {
+    c.bench(
+        &"50u8 msg on inproc".to_owned(),
+        Benchmark::new("per-message", move |b| {
+            let addr = InprocAddr::new_unique();
+            let producer = ClientBuilder::new().bind(&addr).build().unwrap();
+            let consumer = ClientBuilder::new().connect(&addr).build().unwrap();
+
+            let mut msg = Msg::new();
+
+            b.iter(|| {
+                let dataset = gen_dataset(MSG_AMOUNT, MSG_SIZE);
+                for data in dataset {
+                    producer.send(data).unwrap();
+                }
+                for _ in 0..MSG_AMOUNT {
+                    consumer.recv(&mut msg).unwrap();
+                }
+            });
+        })
+        .with_function("batched", move |b| {
+            let addr = InprocAddr::new_unique();
+            let producer = ClientBuilder::new().bind(&addr).build().unwrap();
+            let consumer = ClientBuilder::new().connect(&addr).build().unwrap();
+
+            let mut buf = Vec::with_capacity(MSG_AMOUNT);
+
+            b.iter(|| {
+                let dataset = gen_dataset(MSG_AMOUNT, MSG_SIZE);
+                producer
+                    .send_batch(dataset.into_iter().map(Msg::from))
+                    .unwrap();
+
+                buf.clear();
+                while buf.len() < MSG_AMOUNT {
+                    consumer.recv_batch(&mut buf, MSG_AMOUNT).unwrap();
+                }
+            });
+        })
+        .throughput(Throughput::Bytes((MSG_AMOUNT * MSG_SIZE) as u32))
+        .sample_size(30),
+    );
+}
+
+// Run without features, with `stats` and with `stats-activity` to measure
+// the overhead of the counters and of the clock read of the last activity.
+pub(crate) fn bench_stats(c: &mut Criterion) {
+    let name = if cfg!(feature = "stats-activity") {
+        "with stats and activity"
+    } else if cfg!(feature = "stats") {
+        "with stats"
+    } else {
+        "without stats"
+    };
+
+    c.bench(
+        &"50u8 msg round trip on inproc".to_owned(),
+        Benchmark::new(name, move |b| {
+            let addr = InprocAddr::new_unique();
+            let producer = ClientBuilder::new().bind(&addr).build().unwrap();
+            let consumer = ClientBuilder::new().connect(&addr).build().unwrap();
+
+            let mut msg = Msg::new();
+
+            b.iter(|| {
+                let dataset = gen_dataset(MSG_AMOUNT, MSG_SIZE);
+                for data in dataset {
+                    producer.send(data).unwrap();
+                    consumer.recv(&mut msg).unwrap();
+                }
+            });
+        })
         .throughput(Throughput::Bytes((MSG_AMOUNT * MSG_SIZE) as u32))
         .sample_size(30),
     );
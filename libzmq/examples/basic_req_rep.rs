@@ -2,7 +2,7 @@ use libzmq::{prelude::*, *};
 
 use std::thread;
 
-fn main() -> Result<(), failure::Error> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let addr: InprocAddr = InprocAddr::new_unique();
 
     let server = ServerBuilder::new().bind(&addr).build()?;
@@ -10,11 +10,10 @@ fn main() -> Result<(), failure::Error> {
     // Spawn the server thread.
     let handle = thread::spawn(move || -> Result<(), Error> {
         loop {
-            let request = server.recv_msg()?;
+            // The routing_id is used to route the reply to the client.
+            let (id, request) = server.recv_from()?;
             assert_eq!(request.to_str(), Ok("ping"));
 
-            // Retrieve the routing_id to route the reply to the client.
-            let id = request.routing_id().unwrap();
             let mut reply: Msg = "pong".into();
             reply.set_routing_id(id);
             // We cast the Error<Msg> to Error<()>. This drops the Msg.
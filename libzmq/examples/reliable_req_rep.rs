@@ -2,7 +2,7 @@ use libzmq::{prelude::*, *};
 
 use std::{convert::TryInto, thread, time::Duration};
 
-fn main() -> Result<(), failure::Error> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // We use a system assigned port here.
     let addr: TcpAddr = "127.0.0.1:*".try_into()?;
     let duration = Duration::from_millis(300);
@@ -25,11 +25,10 @@ fn main() -> Result<(), failure::Error> {
     let handle = thread::spawn(move || -> Result<(), Error> {
         use ErrorKind::*;
         loop {
-            let request = server.recv_msg()?;
+            // The routing_id is used to route the reply to the client.
+            let (id, request) = server.recv_from()?;
             assert_eq!(request.to_str(), Ok("ping"));
 
-            // Retrieve the routing_id to route the reply to the client.
-            let id = request.routing_id().unwrap();
             let mut reply: Msg = "pong".into();
             reply.set_routing_id(id);
 
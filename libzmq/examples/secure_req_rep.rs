@@ -25,7 +25,7 @@ fn read_file(name: &Path) -> std::io::Result<Vec<u8>> {
     Ok(buf)
 }
 
-fn main() -> Result<(), failure::Error> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let path = PathBuf::from("examples").join(CONFIG_FILE);
 
     let config: Config =
@@ -47,11 +47,10 @@ fn main() -> Result<(), failure::Error> {
     let handle = thread::spawn(move || -> Result<(), Error> {
         use ErrorKind::*;
         loop {
-            let request = server.recv_msg()?;
+            // The routing_id is used to route the reply to the client.
+            let (id, request) = server.recv_from()?;
             assert_eq!(request.to_str(), Ok("ping"));
 
-            // Retrieve the routing_id to route the reply to the client.
-            let id = request.routing_id().unwrap();
             let mut reply: Msg = "pong".into();
             reply.set_routing_id(id);
 
@@ -0,0 +1,72 @@
+//! Helpers for the runtimes built on `async-io`, such as `smol` and
+//! `async-std`.
+//!
+//! This module requires the `async-io` feature.
+
+use super::elapsed;
+use crate::error::Error;
+
+use async_io::Timer;
+
+use std::{
+    future::{self, Future},
+    pin::Pin,
+    task::Poll,
+    time::Duration,
+};
+
+/// Awaits the future of an asynchronous socket for at most `duration`.
+///
+/// This is the asynchronous counterpart of the `send_timeout` and
+/// `recv_timeout` socket options. Once the duration expires, the future is
+/// dropped, which never loses a message that was already received. However
+/// the message of a dropped [`SendFuture`] is dropped along with it.
+///
+/// Since the timer is driven by the `async-io` reactor, this works on any
+/// executor.
+///
+/// # Returned Error Variants
+/// * [`WouldBlock`] (if the duration expired)
+/// * Any error returned by the future.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{aio::{compat::async_io::timeout, *}, *};
+/// use std::time::Duration;
+///
+/// let server: AsyncServer = Server::new()?.into();
+///
+/// let err = smol::block_on(timeout(Duration::from_millis(10), server.recv()))
+///     .unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::WouldBlock);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`SendFuture`]: ../../struct.SendFuture.html
+/// [`WouldBlock`]: ../../../enum.ErrorKind.html#variant.WouldBlock
+pub async fn timeout<F, T, C>(
+    duration: Duration,
+    future: F,
+) -> Result<T, Error<C>>
+where
+    F: Future<Output = Result<T, Error<C>>>,
+{
+    let mut future = Box::pin(future);
+    let mut timer = Timer::after(duration);
+
+    future::poll_fn(|cx| {
+        if let Poll::Ready(result) = future.as_mut().poll(cx) {
+            return Poll::Ready(result);
+        }
+        match Pin::new(&mut timer).poll(cx) {
+            Poll::Ready(_) => Poll::Ready(Err(elapsed())),
+            Poll::Pending => Poll::Pending,
+        }
+    })
+    .await
+}
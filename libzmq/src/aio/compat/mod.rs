@@ -0,0 +1,22 @@
+//! Executor-specific helpers for the asynchronous sockets.
+//!
+//! The asynchronous sockets themselves work on any executor. Only these
+//! helpers, which rely on the timers of a specific runtime, require one:
+//! * [`tokio`] requires the `tokio` feature.
+//! * [`async_io`] requires the `async-io` feature and works with the
+//!   runtimes built on the `async-io` crate, such as `smol` and
+//!   `async-std`.
+//!
+//! [`tokio`]: tokio/index.html
+//! [`async_io`]: async_io/index.html
+
+#[cfg(feature = "async-io")]
+pub mod async_io;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+use crate::error::{Error, ErrorKind};
+
+fn elapsed<C>() -> Error<C> {
+    Error::new(ErrorKind::WouldBlock)
+}
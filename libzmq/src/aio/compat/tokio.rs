@@ -0,0 +1,56 @@
+//! Helpers for the `tokio` runtime.
+//!
+//! This module requires the `tokio` feature.
+
+use super::elapsed;
+use crate::error::Error;
+
+use std::{future::Future, time::Duration};
+
+/// Awaits the future of an asynchronous socket for at most `duration`.
+///
+/// This is the asynchronous counterpart of the `send_timeout` and
+/// `recv_timeout` socket options. Once the duration expires, the future is
+/// dropped, which never loses a message that was already received. However
+/// the message of a dropped [`SendFuture`] is dropped along with it.
+///
+/// This must be called within a `tokio` runtime with the timers enabled.
+///
+/// # Returned Error Variants
+/// * [`WouldBlock`] (if the duration expired)
+/// * Any error returned by the future.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{aio::{compat::tokio::timeout, *}, *};
+/// use std::time::Duration;
+///
+/// let server: AsyncServer = Server::new()?.into();
+///
+/// let runtime = tokio::runtime::Runtime::new()?;
+/// let err = runtime
+///     .block_on(timeout(Duration::from_millis(10), server.recv()))
+///     .unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::WouldBlock);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`SendFuture`]: ../../struct.SendFuture.html
+/// [`WouldBlock`]: ../../../enum.ErrorKind.html#variant.WouldBlock
+pub async fn timeout<F, T, C>(
+    duration: Duration,
+    future: F,
+) -> Result<T, Error<C>>
+where
+    F: Future<Output = Result<T, Error<C>>>,
+{
+    match ::tokio::time::timeout(duration, future).await {
+        Ok(result) => result,
+        Err(_) => Err(elapsed()),
+    }
+}
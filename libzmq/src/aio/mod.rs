@@ -0,0 +1,729 @@
+//! Asynchronous counterparts of the thread-safe socket types.
+//!
+//! This module requires the `async` feature.
+//!
+//! The sockets are polled by a background thread on behalf of the pending
+//! futures, which are woken up once their socket is ready. This means that
+//! the futures can be used with any async runtime.
+//!
+//! # Executors
+//! The socket types only rely on the `std::future` traits, so they work on
+//! `tokio`, `smol`, `async-std` or any other executor without a feature.
+//! The helpers that need the timers of a specific runtime, such as a
+//! timeout for a future, are in the [`compat`] module behind the `tokio`
+//! and `async-io` features.
+//!
+//! # Cancellation
+//! A message is only dequeued from the socket when the [`RecvFuture`]
+//! completes, so dropping a pending future never loses a message.
+//! Similarly, a message is only queued when the [`SendFuture`] completes.
+//!
+//! # Streams and Sinks
+//! The receiving sockets implement `Stream` and the sending sockets
+//! implement `Sink`, so that they compose with the combinators of the
+//! `futures` crate. As with the futures, a message is only dequeued once
+//! it is yielded by the stream, so dropping a stream never loses one.
+//! The stream ends once the context of the socket is terminated.
+//!
+//! The sink is ready once a send would not block, that is once the high
+//! water mark of the socket is not reached. Since the socket queues the
+//! messages itself, a message is only held by the sink if it could not
+//! be queued after all, which flushing retries. Thus the sink should be
+//! flushed or closed before it is dropped.
+//!
+//! The state of the stream and the sink is specific to each wrapper, so
+//! that a clone starts afresh.
+//!
+//! # Example
+//! ```
+//! # use std::error::Error;
+//! #
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use libzmq::{aio::*, *};
+//!
+//! let addr = InprocAddr::new_unique();
+//!
+//! let server: AsyncServer = ServerBuilder::new().bind(&addr).build()?.into();
+//! let client: AsyncClient = ClientBuilder::new().connect(&addr).build()?.into();
+//!
+//! let runtime = tokio::runtime::Runtime::new()?;
+//! runtime.block_on(async {
+//!     client.send("ping").await?;
+//!
+//!     let msg = server.recv().await?;
+//!     assert_eq!(msg.to_str()?, "ping");
+//!
+//!     let mut reply: Msg = "pong".into();
+//!     reply.set_routing_id(msg.routing_id().unwrap());
+//!     server.send(reply).await?;
+//!
+//!     let msg = client.recv().await?;
+//!     assert_eq!(msg.to_str()?, "pong");
+//!     Ok::<(), Error>(())
+//! })
+//! # }
+//! ```
+//!
+//! [`compat`]: compat/index.html
+//! [`RecvFuture`]: struct.RecvFuture.html
+//! [`SendFuture`]: struct.SendFuture.html
+
+pub mod compat;
+mod reactor;
+
+use crate::{
+    aio::reactor::Interest,
+    core::{GetRawSocket, RawSocket, RecvMsg, SendMsg},
+    error::{Error, ErrorKind},
+    poll::{READABLE, WRITABLE},
+    socket::{Client, Dish, Gather, Radio, Scatter, Server},
+    Msg,
+};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// A future that receives a message from a socket.
+///
+/// It is created by the `recv` method of the asynchronous socket types.
+#[must_use = "futures do nothing unless polled"]
+pub struct RecvFuture<'a, S>
+where
+    S: RecvMsg,
+{
+    socket: &'a S,
+    interest: Interest,
+}
+
+impl<'a, S> Future for RecvFuture<'a, S>
+where
+    S: RecvMsg,
+{
+    type Output = Result<Msg, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut msg = Msg::new();
+
+        loop {
+            match this.socket.try_recv(&mut msg) {
+                Ok(()) => {
+                    this.interest.cancel(this.socket.raw_socket());
+                    return Poll::Ready(Ok(msg));
+                }
+                Err(err) => match err.kind() {
+                    ErrorKind::WouldBlock => {
+                        this.interest.register(
+                            this.socket.raw_socket(),
+                            READABLE,
+                            cx.waker(),
+                        );
+                        return Poll::Pending;
+                    }
+                    ErrorKind::Interrupted => continue,
+                    _ => {
+                        this.interest.cancel(this.socket.raw_socket());
+                        return Poll::Ready(Err(err));
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl<'a, S> Drop for RecvFuture<'a, S>
+where
+    S: RecvMsg,
+{
+    fn drop(&mut self) {
+        self.interest.cancel(self.socket.raw_socket());
+    }
+}
+
+/// A future that sends a message to a socket.
+///
+/// It is created by the `send` method of the asynchronous socket types.
+///
+/// In case of an error, the ownership of the message is returned. It can
+/// be reclaimed using [`take_content`].
+///
+/// [`take_content`]: ../struct.Error.html#method.take_content
+#[must_use = "futures do nothing unless polled"]
+pub struct SendFuture<'a, S>
+where
+    S: SendMsg,
+{
+    socket: &'a S,
+    msg: Option<Msg>,
+    interest: Interest,
+}
+
+impl<'a, S> Future for SendFuture<'a, S>
+where
+    S: SendMsg,
+{
+    type Output = Result<(), Error<Msg>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        loop {
+            let msg = this.msg.take().expect("future polled after completion");
+            match this.socket.try_send(msg) {
+                Ok(()) => {
+                    this.interest.cancel(this.socket.raw_socket());
+                    return Poll::Ready(Ok(()));
+                }
+                Err(mut err) => match err.kind() {
+                    ErrorKind::WouldBlock => {
+                        this.msg = err.take_content();
+                        this.interest.register(
+                            this.socket.raw_socket(),
+                            WRITABLE,
+                            cx.waker(),
+                        );
+                        return Poll::Pending;
+                    }
+                    ErrorKind::Interrupted => {
+                        this.msg = err.take_content();
+                    }
+                    _ => {
+                        this.interest.cancel(this.socket.raw_socket());
+                        return Poll::Ready(Err(err));
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl<'a, S> Drop for SendFuture<'a, S>
+where
+    S: SendMsg,
+{
+    fn drop(&mut self) {
+        self.interest.cancel(self.socket.raw_socket());
+    }
+}
+
+fn recv_future<S>(socket: &S) -> RecvFuture<S>
+where
+    S: RecvMsg,
+{
+    RecvFuture {
+        socket,
+        interest: Interest::default(),
+    }
+}
+
+fn send_future<S, M>(socket: &S, msg: M) -> SendFuture<S>
+where
+    S: SendMsg,
+    M: Into<Msg>,
+{
+    SendFuture {
+        socket,
+        msg: Some(msg.into()),
+        interest: Interest::default(),
+    }
+}
+
+// The state of the `Stream` and `Sink` implementations of a wrapper.
+struct PollState {
+    socket: Arc<RawSocket>,
+    recv: Interest,
+    send: Interest,
+    // The message that the sink could not queue yet.
+    pending: Option<Msg>,
+}
+
+impl PollState {
+    fn new(socket: &Arc<RawSocket>) -> Self {
+        Self {
+            socket: Arc::clone(socket),
+            recv: Interest::default(),
+            send: Interest::default(),
+            pending: None,
+        }
+    }
+}
+
+// Only created from the thread-safe socket types.
+unsafe impl Send for PollState {}
+unsafe impl Sync for PollState {}
+
+impl Drop for PollState {
+    fn drop(&mut self) {
+        self.recv.cancel(&self.socket);
+        self.send.cancel(&self.socket);
+    }
+}
+
+fn poll_next<S>(
+    socket: &S,
+    state: &mut PollState,
+    cx: &mut Context,
+) -> Poll<Option<Result<Msg, Error>>>
+where
+    S: RecvMsg,
+{
+    let mut msg = Msg::new();
+
+    loop {
+        match socket.try_recv(&mut msg) {
+            Ok(()) => {
+                state.recv.cancel(socket.raw_socket());
+                return Poll::Ready(Some(Ok(msg)));
+            }
+            Err(err) => match err.kind() {
+                ErrorKind::WouldBlock => {
+                    state.recv.register(
+                        socket.raw_socket(),
+                        READABLE,
+                        cx.waker(),
+                    );
+                    return Poll::Pending;
+                }
+                ErrorKind::Interrupted => continue,
+                ErrorKind::CtxTerminated => {
+                    state.recv.cancel(socket.raw_socket());
+                    return Poll::Ready(None);
+                }
+                _ => {
+                    state.recv.cancel(socket.raw_socket());
+                    return Poll::Ready(Some(Err(err)));
+                }
+            },
+        }
+    }
+}
+
+// Queues the pending message of the sink, if any.
+fn poll_flush<S>(
+    socket: &S,
+    state: &mut PollState,
+    cx: &mut Context,
+) -> Poll<Result<(), Error>>
+where
+    S: SendMsg,
+{
+    while let Some(msg) = state.pending.take() {
+        match socket.try_send(msg) {
+            Ok(()) => state.send.cancel(socket.raw_socket()),
+            Err(mut err) => match err.kind() {
+                ErrorKind::WouldBlock => {
+                    state.pending = err.take_content();
+                    state.send.register(
+                        socket.raw_socket(),
+                        WRITABLE,
+                        cx.waker(),
+                    );
+                    return Poll::Pending;
+                }
+                ErrorKind::Interrupted => state.pending = err.take_content(),
+                _ => {
+                    state.send.cancel(socket.raw_socket());
+                    return Poll::Ready(Err(err.cast()));
+                }
+            },
+        }
+    }
+
+    Poll::Ready(Ok(()))
+}
+
+fn poll_ready<S>(
+    socket: &S,
+    state: &mut PollState,
+    cx: &mut Context,
+) -> Poll<Result<(), Error>>
+where
+    S: SendMsg,
+{
+    match poll_flush(socket, state, cx) {
+        Poll::Ready(Ok(())) => (),
+        poll => return poll,
+    }
+
+    match socket.raw_socket().events() {
+        Ok(flags) if flags.contains(WRITABLE) => {
+            state.send.cancel(socket.raw_socket());
+            Poll::Ready(Ok(()))
+        }
+        Ok(_) => {
+            state
+                .send
+                .register(socket.raw_socket(), WRITABLE, cx.waker());
+            Poll::Pending
+        }
+        Err(err) => Poll::Ready(Err(err)),
+    }
+}
+
+fn start_send<S>(
+    socket: &S,
+    state: &mut PollState,
+    msg: Msg,
+) -> Result<(), Error>
+where
+    S: SendMsg,
+{
+    assert!(state.pending.is_none(), "sink is not ready");
+
+    match socket.try_send(msg) {
+        Ok(()) => Ok(()),
+        Err(mut err) => match err.kind() {
+            // Another handle to the socket filled up its queue in the
+            // meantime.
+            ErrorKind::WouldBlock | ErrorKind::Interrupted => {
+                state.pending = err.take_content();
+                Ok(())
+            }
+            _ => Err(err.cast()),
+        },
+    }
+}
+
+macro_rules! impl_async_socket {
+    ($name:ident, $inner:ident) => {
+        impl $name {
+            /// Returns a reference to the underlying socket.
+            pub fn get_ref(&self) -> &$inner {
+                &self.inner
+            }
+
+            /// Consumes the wrapper, returning the underlying socket.
+            pub fn into_inner(self) -> $inner {
+                self.inner
+            }
+        }
+
+        impl From<$inner> for $name {
+            fn from(inner: $inner) -> Self {
+                let state = PollState::new(inner.raw_socket());
+                Self { inner, state }
+            }
+        }
+
+        impl Clone for $name {
+            fn clone(&self) -> Self {
+                self.inner.clone().into()
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_struct(stringify!($name))
+                    .field("inner", &self.inner)
+                    .finish()
+            }
+        }
+    };
+}
+
+macro_rules! impl_async_recv {
+    ($name:ident, $inner:ident) => {
+        impl $name {
+            /// Receive a message from the socket.
+            ///
+            /// The future completes once a message is available.
+            pub fn recv(&self) -> RecvFuture<$inner> {
+                recv_future(&self.inner)
+            }
+        }
+
+        impl Stream for $name {
+            type Item = Result<Msg, Error>;
+
+            fn poll_next(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context,
+            ) -> Poll<Option<Self::Item>> {
+                let this = &mut *self;
+                poll_next(&this.inner, &mut this.state, cx)
+            }
+        }
+    };
+}
+
+macro_rules! impl_async_send {
+    ($name:ident, $inner:ident) => {
+        impl $name {
+            /// Push a message into the outgoing socket queue.
+            ///
+            /// The future completes once the message was queued.
+            pub fn send<M>(&self, msg: M) -> SendFuture<$inner>
+            where
+                M: Into<Msg>,
+            {
+                send_future(&self.inner, msg)
+            }
+        }
+
+        impl Sink<Msg> for $name {
+            type Error = Error;
+
+            fn poll_ready(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context,
+            ) -> Poll<Result<(), Error>> {
+                let this = &mut *self;
+                poll_ready(&this.inner, &mut this.state, cx)
+            }
+
+            fn start_send(
+                mut self: Pin<&mut Self>,
+                msg: Msg,
+            ) -> Result<(), Error> {
+                let this = &mut *self;
+                start_send(&this.inner, &mut this.state, msg)
+            }
+
+            fn poll_flush(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context,
+            ) -> Poll<Result<(), Error>> {
+                let this = &mut *self;
+                poll_flush(&this.inner, &mut this.state, cx)
+            }
+
+            fn poll_close(
+                self: Pin<&mut Self>,
+                cx: &mut Context,
+            ) -> Poll<Result<(), Error>> {
+                self.poll_flush(cx)
+            }
+        }
+    };
+}
+
+/// An asynchronous [`Client`].
+///
+/// [`Client`]: ../struct.Client.html
+pub struct AsyncClient {
+    inner: Client,
+    state: PollState,
+}
+
+impl_async_socket!(AsyncClient, Client);
+impl_async_recv!(AsyncClient, Client);
+impl_async_send!(AsyncClient, Client);
+
+impl PartialEq for AsyncClient {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for AsyncClient {}
+
+/// An asynchronous [`Server`].
+///
+/// [`Server`]: ../struct.Server.html
+pub struct AsyncServer {
+    inner: Server,
+    state: PollState,
+}
+
+impl_async_socket!(AsyncServer, Server);
+impl_async_recv!(AsyncServer, Server);
+impl_async_send!(AsyncServer, Server);
+
+/// An asynchronous [`Radio`].
+///
+/// [`Radio`]: ../struct.Radio.html
+pub struct AsyncRadio {
+    inner: Radio,
+    state: PollState,
+}
+
+impl_async_socket!(AsyncRadio, Radio);
+impl_async_send!(AsyncRadio, Radio);
+
+/// An asynchronous [`Dish`].
+///
+/// [`Dish`]: ../struct.Dish.html
+pub struct AsyncDish {
+    inner: Dish,
+    state: PollState,
+}
+
+impl_async_socket!(AsyncDish, Dish);
+impl_async_recv!(AsyncDish, Dish);
+
+/// An asynchronous [`Scatter`].
+///
+/// [`Scatter`]: ../struct.Scatter.html
+pub struct AsyncScatter {
+    inner: Scatter,
+    state: PollState,
+}
+
+impl_async_socket!(AsyncScatter, Scatter);
+impl_async_send!(AsyncScatter, Scatter);
+
+/// An asynchronous [`Gather`].
+///
+/// [`Gather`]: ../struct.Gather.html
+pub struct AsyncGather {
+    inner: Gather,
+    state: PollState,
+}
+
+impl_async_socket!(AsyncGather, Gather);
+impl_async_recv!(AsyncGather, Gather);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, test_util::*, *};
+
+    use futures::{StreamExt, TryStreamExt};
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_req_rep() {
+        let addr = InprocAddr::new_unique();
+
+        let server: AsyncServer =
+            ServerBuilder::new().bind(&addr).build().unwrap().into();
+
+        let server_task = tokio::spawn(async move {
+            for _ in 0..32 {
+                let msg = server.recv().await.unwrap();
+                let mut reply: Msg =
+                    format!("re: {}", msg.to_str().unwrap()).into();
+                reply.set_routing_id(msg.routing_id().unwrap());
+                server.send(reply).await.unwrap();
+            }
+        });
+
+        let mut tasks = Vec::new();
+        for i in 0..32 {
+            let client: AsyncClient =
+                ClientBuilder::new().connect(&addr).build().unwrap().into();
+
+            tasks.push(tokio::spawn(async move {
+                client.send(format!("{}", i)).await.unwrap();
+                let msg = client.recv().await.unwrap();
+                assert_eq!(msg.to_str().unwrap(), format!("re: {}", i));
+            }));
+        }
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancelled_recv() {
+        let addr = InprocAddr::new_unique();
+
+        let server: AsyncServer =
+            ServerBuilder::new().bind(&addr).build().unwrap().into();
+        let client: AsyncClient =
+            ClientBuilder::new().connect(&addr).build().unwrap().into();
+
+        let timeout = Duration::from_millis(50);
+        let result = tokio::time::timeout(timeout, server.recv()).await;
+        assert!(result.is_err());
+
+        // The cancelled future must not have consumed the message.
+        client.send("msg").await.unwrap();
+        let msg = server.recv().await.unwrap();
+        assert_eq!(msg.to_str().unwrap(), "msg");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_send_backpressure() {
+        let addr = InprocAddr::new_unique();
+
+        let server: AsyncServer = ServerBuilder::new()
+            .bind(&addr)
+            .recv_high_water_mark(1)
+            .build()
+            .unwrap()
+            .into();
+        let client = ClientBuilder::new()
+            .connect(&addr)
+            .send_high_water_mark(1)
+            .build()
+            .unwrap();
+
+        // Fill up the queue.
+        let mut queued = 0;
+        while client.try_send("").is_ok() {
+            queued += 1;
+        }
+
+        let client: AsyncClient = client.into();
+        let send = tokio::spawn(async move {
+            client.send("").await.unwrap();
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!send.is_finished());
+
+        for _ in 0..queued {
+            server.recv().await.unwrap();
+        }
+        send.await.unwrap();
+        server.recv().await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stream_sink_proxy() {
+        let (radio, dish) = radio_dish_pair(&["in"]);
+        let (radio_out, dish_out) = radio_dish_pair(&["out"]);
+
+        let dish: AsyncDish = dish.into();
+        let radio_out: AsyncRadio = radio_out.into();
+        let proxy = tokio::spawn(async move {
+            dish.take(3)
+                .map_ok(|mut msg| {
+                    msg.set_group(group!("out"));
+                    msg
+                })
+                .forward(radio_out)
+                .await
+        });
+
+        for i in 0..3 {
+            let mut msg: Msg = format!("{}", i).into();
+            msg.set_group(group!("in"));
+            radio.send(msg).unwrap();
+        }
+        proxy.await.unwrap().unwrap();
+
+        for i in 0..3 {
+            let msg = dish_out.recv_msg().unwrap();
+            assert_eq!(msg.group().unwrap(), "out");
+            assert_eq!(msg.to_str().unwrap(), format!("{}", i));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dropped_stream() {
+        let (client, server) = client_server_pair();
+        let mut stream: AsyncServer = server.into();
+
+        let timeout = Duration::from_millis(50);
+        let result = tokio::time::timeout(timeout, stream.next()).await;
+        assert!(result.is_err());
+        let server = stream.into_inner();
+
+        // The dropped stream must not have consumed the message.
+        client.send("msg").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "msg");
+    }
+}
@@ -0,0 +1,283 @@
+//! The reactor-agnostic core of the asynchronous sockets.
+//!
+//! A background thread polls the sockets on behalf of the pending futures
+//! and wakes them up through their `std::task::Waker` once their socket is
+//! ready. Nothing here depends on a specific executor.
+
+use crate::{
+    core::RawSocket,
+    error::ErrorKind,
+    poll::{self, Events, Flags, Poller},
+};
+
+use lazy_static::lazy_static;
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    mem,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    task, thread,
+};
+
+lazy_static! {
+    static ref REACTOR: Reactor = Reactor::spawn();
+}
+
+// A handle to a thread-safe socket.
+struct Handle(Arc<RawSocket>);
+
+// Only created from the thread-safe socket types.
+unsafe impl Send for Handle {}
+
+impl Handle {
+    fn key(&self) -> usize {
+        self.0.as_mut_ptr() as usize
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Token {
+    Wake,
+    Socket(usize),
+}
+
+enum Command {
+    Register {
+        socket: Handle,
+        key: usize,
+        flags: Flags,
+        waker: task::Waker,
+    },
+    Cancel {
+        socket: usize,
+        key: usize,
+    },
+}
+
+struct Waiter {
+    key: usize,
+    flags: Flags,
+    waker: task::Waker,
+}
+
+// The futures waiting on a socket.
+struct Registration {
+    socket: Handle,
+    waiters: Vec<Waiter>,
+}
+
+impl Registration {
+    fn flags(&self) -> Flags {
+        self.waiters
+            .iter()
+            .fold(Flags::empty(), |flags, waiter| flags | waiter.flags)
+    }
+}
+
+struct Reactor {
+    commands: Arc<Mutex<Vec<Command>>>,
+    waker: poll::Waker,
+    next_key: AtomicUsize,
+}
+
+impl Reactor {
+    fn spawn() -> Self {
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let (sender, receiver) = mpsc::channel();
+
+        let shared = Arc::clone(&commands);
+        thread::Builder::new()
+            .name("libzmq-reactor".to_owned())
+            .spawn(move || {
+                let mut poller = Poller::new();
+                let waker = poller.add_waker(Token::Wake).unwrap();
+                sender.send(waker).unwrap();
+
+                run(poller, &shared);
+            })
+            .unwrap();
+
+        Self {
+            commands,
+            waker: receiver.recv().unwrap(),
+            next_key: AtomicUsize::new(0),
+        }
+    }
+
+    // Registers interest in the socket, replacing the previous interest
+    // if any. Returns the key of the new interest.
+    fn register(
+        &self,
+        socket: &Arc<RawSocket>,
+        flags: Flags,
+        waker: &task::Waker,
+        previous: Option<usize>,
+    ) -> usize {
+        let socket = Handle(Arc::clone(socket));
+        let key = self.next_key.fetch_add(1, Ordering::Relaxed);
+
+        {
+            let mut commands = self.commands.lock().unwrap();
+            if let Some(previous) = previous {
+                commands.push(Command::Cancel {
+                    socket: socket.key(),
+                    key: previous,
+                });
+            }
+            commands.push(Command::Register {
+                socket,
+                key,
+                flags,
+                waker: waker.clone(),
+            });
+        }
+
+        // Only fails if the global context was terminated.
+        let _ = self.waker.wake();
+        key
+    }
+
+    fn cancel(&self, socket: &Arc<RawSocket>, key: usize) {
+        self.commands.lock().unwrap().push(Command::Cancel {
+            socket: socket.as_mut_ptr() as usize,
+            key,
+        });
+        let _ = self.waker.wake();
+    }
+}
+
+fn run(mut poller: Poller<Token>, commands: &Mutex<Vec<Command>>) {
+    let mut registry: HashMap<usize, Registration> = HashMap::new();
+    let mut events = Events::new();
+
+    loop {
+        let pending = mem::take(&mut *commands.lock().unwrap());
+        for command in pending {
+            match command {
+                Command::Register {
+                    socket,
+                    key,
+                    flags,
+                    waker,
+                } => {
+                    let id = socket.key();
+                    let waiter = Waiter { key, flags, waker };
+                    match registry.entry(id) {
+                        Entry::Occupied(entry) => {
+                            let registration = entry.into_mut();
+                            registration.waiters.push(waiter);
+                            poller
+                                .modify(
+                                    &registration.socket.0,
+                                    registration.flags(),
+                                )
+                                .unwrap();
+                        }
+                        Entry::Vacant(entry) => {
+                            poller
+                                .add(&socket.0, Token::Socket(id), flags)
+                                .unwrap();
+                            entry.insert(Registration {
+                                socket,
+                                waiters: vec![waiter],
+                            });
+                        }
+                    }
+                }
+                Command::Cancel { socket, key } => {
+                    if let Some(registration) = registry.get_mut(&socket) {
+                        registration.waiters.retain(|w| w.key != key);
+                    }
+                    update(&mut poller, &mut registry, socket);
+                }
+            }
+        }
+
+        if let Err(err) = poller.wait(&mut events, None) {
+            match err.kind() {
+                ErrorKind::Interrupted => continue,
+                // The context of a socket was terminated. Wake up all the
+                // futures so that they get the error and release the socket.
+                _ => {
+                    if registry.is_empty() {
+                        // The reactor itself can no longer be woken up.
+                        return;
+                    }
+                    for (_, registration) in registry.drain() {
+                        poller.remove(&registration.socket.0).unwrap();
+                        for waiter in registration.waiters {
+                            waiter.waker.wake();
+                        }
+                    }
+                    continue;
+                }
+            }
+        }
+
+        for event in &events {
+            if let Token::Socket(socket) = event.id() {
+                if let Some(registration) = registry.get_mut(&socket) {
+                    let (ready, waiting) = registration
+                        .waiters
+                        .drain(..)
+                        .partition(|w| w.flags.intersects(event.flags()));
+                    registration.waiters = waiting;
+
+                    for waiter in ready {
+                        waiter.waker.wake();
+                    }
+                }
+                update(&mut poller, &mut registry, socket);
+            }
+        }
+    }
+}
+
+// Updates the poller registration of the socket to match its waiters.
+fn update(
+    poller: &mut Poller<Token>,
+    registry: &mut HashMap<usize, Registration>,
+    socket: usize,
+) {
+    let empty = match registry.get(&socket) {
+        Some(registration) => registration.waiters.is_empty(),
+        None => return,
+    };
+
+    if empty {
+        let registration = registry.remove(&socket).unwrap();
+        poller.remove(&registration.socket.0).unwrap();
+    } else {
+        let registration = &registry[&socket];
+        poller
+            .modify(&registration.socket.0, registration.flags())
+            .unwrap();
+    }
+}
+
+// The pending interest of a future in a socket.
+#[derive(Default)]
+pub(super) struct Interest {
+    key: Option<usize>,
+}
+
+impl Interest {
+    pub(super) fn register(
+        &mut self,
+        socket: &Arc<RawSocket>,
+        flags: Flags,
+        waker: &task::Waker,
+    ) {
+        self.key =
+            Some(REACTOR.register(socket, flags, waker, self.key.take()));
+    }
+
+    pub(super) fn cancel(&mut self, socket: &Arc<RawSocket>) {
+        if let Some(key) = self.key.take() {
+            REACTOR.cancel(socket, key);
+        }
+    }
+}
@@ -1,9 +1,15 @@
-use super::{server::COMMAND_ENDPOINT, *};
-use crate::{addr::IntoIpAddrs, prelude::*, socket::*, *};
+use super::{
+    server::{PlainHandler, COMMAND_ENDPOINT},
+    *,
+};
+use crate::{addr::IntoIpAddrs, error::option_step, prelude::*, socket::*, *};
 
 use serde::{Deserialize, Serialize};
 
-use std::net::{IpAddr, Ipv6Addr};
+use std::{
+    convert::TryInto,
+    net::{IpAddr, Ipv6Addr},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub(crate) enum AuthRequest {
@@ -13,6 +19,12 @@ pub(crate) enum AuthRequest {
     AddWhitelist(Ipv6Addr),
     RemoveWhitelist(Ipv6Addr),
     SetWhitelist(Vec<Ipv6Addr>),
+    AddAllow(IpNet),
+    RemoveAllow(IpNet),
+    SetAllow(Vec<IpNet>),
+    AddDeny(IpNet),
+    RemoveDeny(IpNet),
+    SetDeny(Vec<IpNet>),
     AddPlainRegistry(PlainClientCreds),
     RemovePlainRegistry(String),
     SetPlainRegistry(Vec<PlainClientCreds>),
@@ -20,6 +32,12 @@ pub(crate) enum AuthRequest {
     RemoveCurveRegistry(CurvePublicKey),
     SetCurveRegistry(Vec<CurvePublicKey>),
     SetCurveAuth(bool),
+    AddGssapiRegistry(String),
+    RemoveGssapiRegistry(String),
+    SetGssapiRegistry(Vec<String>),
+    SetGssapiAuth(bool),
+    SetDefaultPolicy(AuthPolicy),
+    SetDomainPolicy(String, Option<AuthPolicy>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -41,9 +59,9 @@ fn into_ipv6(ip: IpAddr) -> Ipv6Addr {
 /// # Example
 ///
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, auth::*, *};
 /// use std::{time::Duration, convert::TryInto};
 ///
@@ -219,6 +237,134 @@ impl AuthClient {
         self.request(&AuthRequest::SetWhitelist(ips))
     }
 
+    /// Allow the address or range of addresses in the CIDR notation,
+    /// such as `"10.0.0.0/8"`.
+    ///
+    /// The allowed and denied ranges filter the handshakes of every
+    /// mechanism by the address of the peer, before any authentication
+    /// takes place:
+    /// * an address in a denied range is denied, even if it is also in an
+    ///     allowed range.
+    /// * otherwise, if there are allowed ranges, only the addresses in one
+    ///     of them are allowed.
+    /// * otherwise, all addresses are allowed.
+    ///
+    /// Unlike the whitelist, which takes precedence over the blacklist as
+    /// in *czmq*, the denied ranges always win. This allows to carve out
+    /// exceptions of an allowed range. Both are applied, so an address must
+    /// pass the whitelist and the blacklist as well.
+    ///
+    /// IPv4 addresses and their IPv4-mapped IPv6 form, such as
+    /// `::ffff:10.0.0.1`, are considered the same.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the range is invalid)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{auth::*, Ctx};
+    ///
+    /// let client = AuthClient::with_ctx(Ctx::new())?;
+    /// // Allow the local network, except for a single host.
+    /// client.allow("192.168.0.0/16")?;
+    /// client.deny("192.168.1.1")?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn allow<N>(&self, net: N) -> Result<(), Error>
+    where
+        N: TryInto<IpNet>,
+        N::Error: Into<Error>,
+    {
+        let net = net.try_into().map_err(Into::into)?;
+        self.request(&AuthRequest::AddAllow(net))
+    }
+
+    /// Remove the address or range from the allowed ranges, if it is
+    /// present.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the range is invalid)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn remove_allow<N>(&self, net: N) -> Result<(), Error>
+    where
+        N: TryInto<IpNet>,
+        N::Error: Into<Error>,
+    {
+        let net = net.try_into().map_err(Into::into)?;
+        self.request(&AuthRequest::RemoveAllow(net))
+    }
+
+    /// Set the allowed ranges of addresses.
+    ///
+    /// See [`allow`] for how the ranges are applied.
+    ///
+    /// [`allow`]: #method.allow
+    pub fn set_allowed<I, E>(&self, nets: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<IpNet>,
+    {
+        let nets: Vec<IpNet> = nets.into_iter().map(E::into).collect();
+        self.request(&AuthRequest::SetAllow(nets))
+    }
+
+    /// Deny the address or range of addresses in the CIDR notation,
+    /// such as `"10.0.0.0/8"`.
+    ///
+    /// See [`allow`] for how the ranges are applied.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the range is invalid)
+    ///
+    /// [`allow`]: #method.allow
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn deny<N>(&self, net: N) -> Result<(), Error>
+    where
+        N: TryInto<IpNet>,
+        N::Error: Into<Error>,
+    {
+        let net = net.try_into().map_err(Into::into)?;
+        self.request(&AuthRequest::AddDeny(net))
+    }
+
+    /// Remove the address or range from the denied ranges, if it is
+    /// present.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the range is invalid)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn remove_deny<N>(&self, net: N) -> Result<(), Error>
+    where
+        N: TryInto<IpNet>,
+        N::Error: Into<Error>,
+    {
+        let net = net.try_into().map_err(Into::into)?;
+        self.request(&AuthRequest::RemoveDeny(net))
+    }
+
+    /// Set the denied ranges of addresses.
+    ///
+    /// See [`allow`] for how the ranges are applied.
+    ///
+    /// [`allow`]: #method.allow
+    pub fn set_denied<I, E>(&self, nets: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<IpNet>,
+    {
+        let nets: Vec<IpNet> = nets.into_iter().map(E::into).collect();
+        self.request(&AuthRequest::SetDeny(nets))
+    }
+
     /// Add the credentials to the `AuthServer`'s plain registry.
     ///
     /// Only credentials present in the registry can successfully authenticate.
@@ -332,6 +478,214 @@ impl AuthClient {
     pub fn set_curve_auth(&self, enabled: bool) -> Result<(), Error> {
         self.request(&AuthRequest::SetCurveAuth(enabled))
     }
+
+    /// Add the principals to the `AuthServer`'s gssapi registry.
+    ///
+    /// The registry only applies once enabled by [`set_gssapi_auth`].
+    ///
+    /// [`set_gssapi_auth`]: #method.set_gssapi_auth
+    pub fn add_gssapi_registry<I, E>(
+        &self,
+        principals: I,
+    ) -> Result<(), Error<usize>>
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<String>,
+    {
+        let mut count = 0;
+
+        for principal in principals.into_iter().map(E::into) {
+            self.request(&AuthRequest::AddGssapiRegistry(principal))
+                .map_err(|err| Error::with_content(err.kind(), count))?;
+
+            count += 1;
+        }
+        Ok(())
+    }
+
+    /// Remove the given principals from the `AuthServer`'s gssapi registry
+    /// if they are present.
+    pub fn remove_gssapi_registry<I, E>(
+        &self,
+        principals: I,
+    ) -> Result<(), Error<usize>>
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<String>,
+    {
+        let mut count = 0;
+
+        for principal in principals.into_iter().map(E::into) {
+            self.request(&AuthRequest::RemoveGssapiRegistry(principal))
+                .map_err(|err| Error::with_content(err.kind(), count))?;
+
+            count += 1;
+        }
+        Ok(())
+    }
+
+    /// Set the principals in the `AuthServer`'s gssapi registry.
+    ///
+    /// The registry only applies once enabled by [`set_gssapi_auth`].
+    ///
+    /// [`set_gssapi_auth`]: #method.set_gssapi_auth
+    pub fn set_gssapi_registry<I, E>(&self, principals: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<String>,
+    {
+        let principals: Vec<String> =
+            principals.into_iter().map(E::into).collect();
+
+        self.request(&AuthRequest::SetGssapiRegistry(principals))
+    }
+
+    /// Sets whether to use authentication for the `GSSAPI` mechanism.
+    ///
+    /// If it is set to `true`, then only the principals present in the
+    /// gssapi registry will be allowed to authenticate. Otherwise every
+    /// principal that completes the `GSSAPI` handshake authenticates
+    /// successfully.
+    ///
+    /// # Default Value
+    /// `false`
+    pub fn set_gssapi_auth(&self, enabled: bool) -> Result<(), Error> {
+        self.request(&AuthRequest::SetGssapiAuth(enabled))
+    }
+
+    /// Sets the callback that authenticates the `PLAIN` credentials which
+    /// are not in the plain registry, replacing the previous one.
+    ///
+    /// The callback returns `true` to allow the credentials. It is called
+    /// from the thread of the `AuthServer` and should thus not block.
+    ///
+    /// The callback is unscoped, so it does not apply to the `ZAP` domains
+    /// that have a callback set by [`set_domain_plain_handler`] or a scope
+    /// in the `PlainRegistry`, and neither does the plain registry.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{auth::*, *};
+    ///
+    /// let client = AuthClient::new()?;
+    /// // Allows every guest user.
+    /// client.set_plain_handler(|creds| creds.username().starts_with("guest"));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_domain_plain_handler`]: #method.set_domain_plain_handler
+    pub fn set_plain_handler<F>(&self, handler: F)
+    where
+        F: Fn(&PlainClientCreds) -> bool + Send + Sync + 'static,
+    {
+        self.client
+            .ctx()
+            .set_plain_handler(Some(PlainHandler::new(handler)));
+    }
+
+    /// Removes the callback set by [`set_plain_handler`], if any.
+    ///
+    /// [`set_plain_handler`]: #method.set_plain_handler
+    pub fn clear_plain_handler(&self) {
+        self.client.ctx().set_plain_handler(None);
+    }
+
+    /// Sets the callback that authenticates the `PLAIN` credentials of the
+    /// handshakes of the `ZAP` domain, replacing the previous one.
+    ///
+    /// The unscoped credentials, namely the plain registry and the callback
+    /// set by [`set_plain_handler`], no longer apply to the domain. The
+    /// scope of the domain in the `PlainRegistry`, if any, still applies.
+    ///
+    /// [`set_plain_handler`]: #method.set_plain_handler
+    pub fn set_domain_plain_handler<S, F>(&self, domain: S, handler: F)
+    where
+        S: Into<String>,
+        F: Fn(&PlainClientCreds) -> bool + Send + Sync + 'static,
+    {
+        self.client.ctx().set_domain_plain_handler(
+            domain.into(),
+            Some(PlainHandler::new(handler)),
+        );
+    }
+
+    /// Removes the callback of the `ZAP` domain set by
+    /// [`set_domain_plain_handler`], if any.
+    ///
+    /// [`set_domain_plain_handler`]: #method.set_domain_plain_handler
+    pub fn clear_domain_plain_handler<S>(&self, domain: S)
+    where
+        S: Into<String>,
+    {
+        self.client
+            .ctx()
+            .set_domain_plain_handler(domain.into(), None);
+    }
+
+    /// Installs the `PlainRegistry` of the `AuthServer`, replacing the
+    /// previous one.
+    ///
+    /// The registry is consulted for the `PLAIN` credentials that the
+    /// plain registry and the callback set by [`set_plain_handler`] deny.
+    ///
+    /// [`set_plain_handler`]: #method.set_plain_handler
+    pub fn use_plain(&self, registry: PlainRegistry) {
+        self.client.ctx().set_plain_registry(Some(registry));
+    }
+
+    /// Removes the `PlainRegistry` installed by [`use_plain`], if any.
+    ///
+    /// [`use_plain`]: #method.use_plain
+    pub fn clear_plain(&self) {
+        self.client.ctx().set_plain_registry(None);
+    }
+
+    /// Sets the policy of the `ZAP` domains that have no policy of their
+    /// own.
+    ///
+    /// # Default Value
+    /// `AuthPolicy::Registry`
+    pub fn set_default_policy(&self, policy: AuthPolicy) -> Result<(), Error> {
+        self.request(&AuthRequest::SetDefaultPolicy(policy))
+    }
+
+    /// Sets the policy of the given `ZAP` domain, overriding the default
+    /// policy. A policy of `None` reverts to the default policy.
+    ///
+    /// The handshakes with no `ZAP` domain have the empty domain `""`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::auth::*;
+    ///
+    /// let client = AuthClient::new()?;
+    /// // Deny everything but the loopback domain.
+    /// client.set_default_policy(AuthPolicy::DenyAll)?;
+    /// client.set_domain_policy("loopback", Some(AuthPolicy::AllowAll))?;
+    /// # client.set_default_policy(AuthPolicy::Registry)?;
+    /// # client.set_domain_policy("loopback", None)?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn set_domain_policy<S>(
+        &self,
+        domain: S,
+        policy: Option<AuthPolicy>,
+    ) -> Result<(), Error>
+    where
+        S: Into<String>,
+    {
+        self.request(&AuthRequest::SetDomainPolicy(domain.into(), policy))
+    }
 }
 
 /// A Configuration of the `AuthServer`.
@@ -342,9 +696,15 @@ impl AuthClient {
 pub struct AuthConfig {
     blacklist: Option<Vec<IpAddr>>,
     whitelist: Option<Vec<IpAddr>>,
+    allowed: Option<Vec<IpNet>>,
+    denied: Option<Vec<IpNet>>,
     plain_registry: Option<Vec<PlainClientCreds>>,
     curve_registry: Option<Vec<CurvePublicKey>>,
     curve_auth: Option<bool>,
+    gssapi_registry: Option<Vec<String>>,
+    gssapi_auth: Option<bool>,
+    default_policy: Option<AuthPolicy>,
+    domain_policies: Option<Vec<(String, AuthPolicy)>>,
 }
 
 impl AuthConfig {
@@ -375,19 +735,57 @@ impl AuthConfig {
     /// it to its associated `AuthServer`.
     pub fn apply(&self, client: &AuthClient) -> Result<(), Error> {
         if let Some(ref blacklist) = self.blacklist {
-            client.set_blacklist(blacklist)?;
+            client
+                .set_blacklist(blacklist)
+                .map_err(option_step("blacklist"))?;
         }
         if let Some(ref whitelist) = self.whitelist {
-            client.set_whitelist(whitelist)?;
+            client
+                .set_whitelist(whitelist)
+                .map_err(option_step("whitelist"))?;
+        }
+        if let Some(ref nets) = self.allowed {
+            client.set_allowed(nets).map_err(option_step("allowed"))?;
+        }
+        if let Some(ref nets) = self.denied {
+            client.set_denied(nets).map_err(option_step("denied"))?;
         }
         if let Some(ref creds) = self.plain_registry {
-            client.set_plain_registry(creds)?;
+            client
+                .set_plain_registry(creds)
+                .map_err(option_step("plain_registry"))?;
         }
         if let Some(ref keys) = self.curve_registry {
-            client.set_curve_registry(keys)?;
+            client
+                .set_curve_registry(keys)
+                .map_err(option_step("curve_registry"))?;
         }
         if let Some(enabled) = self.curve_auth {
-            client.set_curve_auth(enabled).map_err(Error::cast)?;
+            client
+                .set_curve_auth(enabled)
+                .map_err(option_step("curve_auth"))?;
+        }
+        if let Some(ref principals) = self.gssapi_registry {
+            client
+                .set_gssapi_registry(principals)
+                .map_err(option_step("gssapi_registry"))?;
+        }
+        if let Some(enabled) = self.gssapi_auth {
+            client
+                .set_gssapi_auth(enabled)
+                .map_err(option_step("gssapi_auth"))?;
+        }
+        if let Some(policy) = self.default_policy {
+            client
+                .set_default_policy(policy)
+                .map_err(option_step("default_policy"))?;
+        }
+        if let Some(ref policies) = self.domain_policies {
+            for (domain, policy) in policies {
+                client
+                    .set_domain_policy(domain.as_str(), Some(*policy))
+                    .map_err(option_step("domain_policies"))?;
+            }
         }
 
         Ok(())
@@ -411,6 +809,26 @@ impl AuthConfig {
         self.whitelist = maybe;
     }
 
+    pub fn set_allowed<I, E>(&mut self, maybe: Option<I>)
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<IpNet>,
+    {
+        let maybe: Option<Vec<IpNet>> =
+            maybe.map(|e| e.into_iter().map(E::into).collect());
+        self.allowed = maybe;
+    }
+
+    pub fn set_denied<I, E>(&mut self, maybe: Option<I>)
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<IpNet>,
+    {
+        let maybe: Option<Vec<IpNet>> =
+            maybe.map(|e| e.into_iter().map(E::into).collect());
+        self.denied = maybe;
+    }
+
     pub fn set_plain_registry<I, E>(&mut self, maybe: Option<I>)
     where
         I: IntoIterator<Item = E>,
@@ -434,6 +852,37 @@ impl AuthConfig {
     pub fn set_curve_auth(&mut self, maybe: Option<bool>) {
         self.curve_auth = maybe;
     }
+
+    pub fn set_gssapi_registry<I, E>(&mut self, maybe: Option<I>)
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<String>,
+    {
+        let maybe: Option<Vec<String>> =
+            maybe.map(|e| e.into_iter().map(E::into).collect());
+        self.gssapi_registry = maybe;
+    }
+
+    pub fn set_gssapi_auth(&mut self, maybe: Option<bool>) {
+        self.gssapi_auth = maybe;
+    }
+
+    pub fn set_default_policy(&mut self, maybe: Option<AuthPolicy>) {
+        self.default_policy = maybe;
+    }
+
+    pub fn set_domain_policies<I, S>(&mut self, maybe: Option<I>)
+    where
+        I: IntoIterator<Item = (S, AuthPolicy)>,
+        S: Into<String>,
+    {
+        let maybe: Option<Vec<(String, AuthPolicy)>> = maybe.map(|i| {
+            i.into_iter()
+                .map(|(domain, policy)| (domain.into(), policy))
+                .collect()
+        });
+        self.domain_policies = maybe;
+    }
 }
 
 /// A builder for a `AuthClient`.
@@ -477,6 +926,24 @@ impl AuthBuilder {
         self
     }
 
+    pub fn allow<N>(&mut self, net: N) -> &mut Self
+    where
+        N: Into<IpNet>,
+    {
+        let nets = self.inner.allowed.get_or_insert_with(Vec::new);
+        nets.push(net.into());
+        self
+    }
+
+    pub fn deny<N>(&mut self, net: N) -> &mut Self
+    where
+        N: Into<IpNet>,
+    {
+        let nets = self.inner.denied.get_or_insert_with(Vec::new);
+        nets.push(net.into());
+        self
+    }
+
     pub fn plain_registry<I, E>(&mut self, iter: I) -> &mut Self
     where
         I: IntoIterator<Item = E>,
@@ -499,15 +966,117 @@ impl AuthBuilder {
         self.inner.set_curve_auth(Some(false));
         self
     }
+
+    pub fn gssapi_registry<I, E>(&mut self, principals: I) -> &mut Self
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<String>,
+    {
+        self.inner.set_gssapi_registry(Some(principals));
+        self
+    }
+
+    pub fn gssapi_auth(&mut self) -> &mut Self {
+        self.inner.set_gssapi_auth(Some(true));
+        self
+    }
+
+    pub fn default_policy(&mut self, policy: AuthPolicy) -> &mut Self {
+        self.inner.set_default_policy(Some(policy));
+        self
+    }
+
+    pub fn domain_policy<S>(
+        &mut self,
+        domain: S,
+        policy: AuthPolicy,
+    ) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        let policies = self.inner.domain_policies.get_or_insert_with(Vec::new);
+        policies.push((domain.into(), policy));
+        self
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::Client;
+    use crate::{
+        core::GetRawSocket,
+        old::{OldSocket, OldSocketType},
+        poll::*,
+        socket::{monitor, recv_event},
+        Client,
+    };
+    use libzmq_sys as sys;
 
     use std::{convert::TryInto, time::Duration};
 
+    const HANDSHAKE_EVENTS: u32 = sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED
+        | sys::ZMQ_EVENT_HANDSHAKE_FAILED_NO_DETAIL
+        | sys::ZMQ_EVENT_HANDSHAKE_FAILED_PROTOCOL
+        | sys::ZMQ_EVENT_HANDSHAKE_FAILED_AUTH;
+
+    // Returns a monitor of the outcome of the handshakes of the socket.
+    fn handshakes<S>(socket: &S) -> OldSocket
+    where
+        S: GetRawSocket,
+    {
+        monitor(socket.raw_socket(), HANDSHAKE_EVENTS).unwrap()
+    }
+
+    // Returns the outcome of the next handshake along with its value.
+    fn next_handshake(handshakes: &mut OldSocket) -> (u32, u32) {
+        recv_event(handshakes, Duration::from_secs(1)).expect("no handshake")
+    }
+
+    // A handshake denied by the `AuthServer`.
+    const DENIED: (u32, u32) = (sys::ZMQ_EVENT_HANDSHAKE_FAILED_AUTH, 400);
+
+    // Binds a `ZAP` handler by hand, which requires a context without an
+    // `AuthServer`.
+    fn zap_handler(ctx: &Ctx) -> OldSocket {
+        let mut handler =
+            OldSocket::with_ctx(OldSocketType::Router, ctx).unwrap();
+        let addr: InprocAddr = "zeromq.zap.01".try_into().unwrap();
+        handler.bind(&addr).unwrap();
+        handler
+    }
+
+    // Replies to the next `ZAP` request with the status code and returns
+    // its frames, without the routing id and the delimiter.
+    fn reply_zap(handler: &mut OldSocket, status_code: &str) -> Vec<String> {
+        let mut poller = Poller::new();
+        poller.add(&*handler, PollId(0), READABLE).unwrap();
+        let mut events = Events::new();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+
+        let mut parts = handler.try_recv_msg_multipart().expect("no request");
+        let routing_id = parts.remove(0);
+        assert!(parts.remove(0).is_empty());
+
+        let reply: Vec<Msg> = vec![
+            "1.0".into(),
+            parts[1].clone(),
+            status_code.into(),
+            "".into(),
+            "".into(),
+            "".into(),
+        ];
+        handler.send(routing_id, true).unwrap();
+        handler.send("", true).unwrap();
+        handler.send_multipart(reply).unwrap();
+
+        parts
+            .iter()
+            .map(|part| part.to_str().unwrap().to_owned())
+            .collect()
+    }
+
     #[test]
     fn test_blacklist() {
         // Create a new context use a disctinct auth handler.
@@ -556,6 +1125,100 @@ mod test {
         server.recv_msg().unwrap();
     }
 
+    #[test]
+    fn test_allow_deny() {
+        // Create a new context use a disctinct auth handler.
+        let ctx = Ctx::new();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+        let mut events = handshakes(&server);
+
+        // The denied range wins over the allowed address. The denied
+        // clients are dropped so that their reconnections do not report
+        // more handshakes.
+        let auth = AuthBuilder::new()
+            .allow("127.0.0.1".parse::<IpNet>().unwrap())
+            .with_ctx(&ctx)
+            .unwrap();
+        auth.deny("127.0.0.0/8").unwrap();
+
+        let client =
+            ClientBuilder::new().connect(&bound).with_ctx(&ctx).unwrap();
+        assert_eq!(next_handshake(&mut events), DENIED);
+        drop(client);
+
+        // The IPv4-mapped form matches as well.
+        auth.remove_deny("127.0.0.0/8").unwrap();
+        auth.deny("::ffff:127.0.0.0/104").unwrap();
+
+        let client =
+            ClientBuilder::new().connect(&bound).with_ctx(&ctx).unwrap();
+        assert_eq!(next_handshake(&mut events), DENIED);
+        drop(client);
+
+        auth.set_denied(Vec::<IpNet>::new()).unwrap();
+
+        let client =
+            ClientBuilder::new().connect(&bound).with_ctx(&ctx).unwrap();
+        let (event, _) = next_handshake(&mut events);
+        assert_eq!(event, sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED);
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_config_ranges() {
+        let toml = r#"
+            allowed = ["127.0.0.1"]
+            denied = ["10.0.0.0/8", "fe80::/10"]
+        "#;
+        let config: AuthConfig = toml::from_str(toml).unwrap();
+
+        let mut expected = AuthConfig::new();
+        expected.set_allowed(Some(vec!["127.0.0.1".parse::<IpNet>().unwrap()]));
+        expected.set_denied(Some(vec![
+            "10.0.0.0/8".parse::<IpNet>().unwrap(),
+            "fe80::/10".parse().unwrap(),
+        ]));
+        assert_eq!(config, expected);
+
+        let ser = toml::to_string(&config).unwrap();
+        let de: AuthConfig = toml::from_str(&ser).unwrap();
+        assert_eq!(de, config);
+
+        let invalid = r#"denied = ["10.0.0.0/33"]"#;
+        assert!(toml::from_str::<AuthConfig>(invalid).is_err());
+    }
+
+    #[test]
+    fn test_allow_other_range() {
+        let ctx = Ctx::new();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        // Only the addresses in the allowed ranges can connect.
+        let auth = AuthClient::with_ctx(&ctx).unwrap();
+        auth.allow("10.0.0.0/8").unwrap();
+        assert!(auth.allow("10.0.0.0/33").is_err());
+
+        let mut events = handshakes(&server);
+        let _client =
+            ClientBuilder::new().connect(&bound).with_ctx(&ctx).unwrap();
+        assert_eq!(next_handshake(&mut events), DENIED);
+    }
+
     #[test]
     fn test_null() {
         let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
@@ -629,20 +1292,150 @@ mod test {
     }
 
     #[test]
-    fn test_curve() {
+    fn test_zap_domain() {
         let ctx = Ctx::new();
 
-        let server_cert = CurveCert::new_unique();
-        let client_cert = CurveCert::new_unique();
-
+        let creds = PlainClientCreds::new("user", "pwd");
         let _ = AuthBuilder::new()
-            .curve_registry(client_cert.public())
+            .plain_registry(&creds)
             .with_ctx(&ctx)
             .unwrap();
 
         let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
 
-        let server_creds = CurveServerCreds {
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .zap_domain("internal")
+            .enforce_zap_domain(true)
+            .plain_server(true)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+
+        assert_eq!(server.zap_domain().unwrap(), "internal");
+        assert!(server.enforce_zap_domain().unwrap());
+
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(bound)
+            .mechanism(creds)
+            .with_ctx(&ctx)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_zap_domain_request() {
+        let ctx = Ctx::without_auth();
+        let mut handler = zap_handler(&ctx);
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .zap_domain("internal")
+            .recv_timeout(Duration::from_secs(1))
+            .with_ctx(&ctx)
+            .unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let client =
+            ClientBuilder::new().connect(&bound).with_ctx(&ctx).unwrap();
+
+        // The domain follows the version and the request id.
+        let request = reply_zap(&mut handler, "200");
+        assert_eq!(request[0], "1.0");
+        assert_eq!(&request[2..], &["internal", "127.0.0.1", "", "NULL"]);
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_zap_domain_invalid() {
+        for domain in &["", "a\0b"] {
+            let err =
+                ServerBuilder::new().zap_domain(domain).build().unwrap_err();
+            match err.kind() {
+                ErrorKind::InvalidInput { .. } => (),
+                _ => panic!("unexpected error"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_plain_zap_request() {
+        let ctx = Ctx::without_auth();
+        let mut handler = zap_handler(&ctx);
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .plain_server(true)
+            .recv_timeout(Duration::from_secs(1))
+            .with_ctx(&ctx)
+            .unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .plain_credentials("user", "pwd")
+            .with_ctx(&ctx)
+            .unwrap();
+
+        // The version, request id, domain, address, identity, mechanism
+        // and the credentials.
+        let request = reply_zap(&mut handler, "200");
+        assert_eq!(request[0], "1.0");
+        assert_eq!(
+            &request[2..],
+            &["", "127.0.0.1", "", "PLAIN", "user", "pwd"]
+        );
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_plain_invalid_creds() {
+        let long = "a".repeat(256);
+
+        let err = ClientBuilder::new()
+            .plain_credentials(long.as_str(), "pwd")
+            .build()
+            .unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+
+        let err = ClientBuilder::new()
+            .plain_credentials("user", "p\0wd")
+            .build()
+            .unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_curve() {
+        let ctx = Ctx::new();
+
+        let server_cert = CurveCert::new_unique();
+        let client_cert = CurveCert::new_unique();
+
+        let _ = AuthBuilder::new()
+            .curve_registry(client_cert.public())
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server_creds = CurveServerCreds {
             secret: server_cert.secret().to_owned(),
         };
 
@@ -668,6 +1461,236 @@ mod test {
         server.recv_msg().unwrap();
     }
 
+    #[test]
+    fn test_security_conflicts() {
+        let cert = CurveCert::new_unique();
+        let plain = PlainClientCreds::new("user", "pass");
+        let curve = CurveClientCreds::new(cert.public());
+
+        let assert_conflict = |result: Result<Client, Error>| {
+            let err = result.unwrap_err();
+            match err.kind() {
+                ErrorKind::InvalidInput { .. } => (),
+                _ => panic!("unexpected error"),
+            }
+            assert_eq!(
+                err.build_step(),
+                Some(BuildStep::Option { name: "mechanism" })
+            );
+        };
+
+        assert_conflict(
+            ClientBuilder::new()
+                .plain_server(true)
+                .curve_server(cert.secret())
+                .build(),
+        );
+        assert_conflict(
+            ClientBuilder::new()
+                .plain_credentials("user", "pass")
+                .curve_client(&curve)
+                .build(),
+        );
+        assert_conflict(
+            ClientBuilder::new()
+                .security(&plain)
+                .plain_server(true)
+                .build(),
+        );
+        assert_conflict(
+            ClientBuilder::new()
+                .curve_server(cert.secret())
+                .security(&curve)
+                .build(),
+        );
+        // Resetting the mechanism afterwards does not hide the conflict.
+        assert_conflict(
+            ClientBuilder::new()
+                .security(&plain)
+                .security(&curve)
+                .security(Mechanism::Null)
+                .build(),
+        );
+
+        // Mechanisms of the same kind replace each other.
+        let client = ClientBuilder::new()
+            .plain_credentials("other", "pass")
+            .security(&plain)
+            .build()
+            .unwrap();
+        assert_eq!(client.mechanism(), Mechanism::PlainClient(plain.clone()));
+
+        // The `Null` mechanism resets the previous one.
+        let client = ClientBuilder::new()
+            .security(&plain)
+            .security(Mechanism::Null)
+            .curve_server(cert.secret())
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.mechanism(),
+            Mechanism::CurveServer(CurveServerCreds::new(cert.secret()))
+        );
+    }
+
+    #[test]
+    fn test_security_handshakes() {
+        let ctx = Ctx::new();
+
+        let server_cert = CurveCert::new_unique();
+        let client_cert = CurveCert::new_unique();
+        let plain = PlainClientCreds::new("user", "pass");
+
+        let _ = AuthBuilder::new()
+            .plain_registry(&plain)
+            .curve_registry(client_cert.public())
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let curve =
+            CurveClientCreds::new(server_cert.public()).add_cert(client_cert);
+        let mechanisms = vec![
+            (Mechanism::Null, Mechanism::Null),
+            (Mechanism::PlainServer, plain.into()),
+            (
+                CurveServerCreds::new(server_cert.secret()).into(),
+                curve.into(),
+            ),
+        ];
+
+        for (server_mechanism, client_mechanism) in mechanisms {
+            let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+            let server = ServerBuilder::new()
+                .bind(&addr)
+                .security(server_mechanism)
+                .recv_timeout(Duration::from_millis(200))
+                .with_ctx(&ctx)
+                .unwrap();
+            let bound = server.last_endpoint().unwrap().unwrap();
+
+            let client = ClientBuilder::new()
+                .security(client_mechanism)
+                .connect(bound)
+                .with_ctx(&ctx)
+                .unwrap();
+
+            client.try_send("").unwrap();
+            server.recv_msg().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_curve_builder_round_trip() {
+        let ctx = Ctx::new();
+
+        let server_cert = CurveCert::new_unique();
+        let client_cert = CurveCert::new_unique();
+
+        let _ = AuthBuilder::new()
+            .curve_registry(client_cert.public())
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .curve_server(server_cert.secret())
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let client_creds =
+            CurveClientCreds::new(server_cert.public()).add_cert(client_cert);
+        let client = ClientBuilder::new()
+            .curve_client(client_creds)
+            .connect(bound)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+
+        client.try_send("request").unwrap();
+        let mut msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "request");
+
+        let id = msg.routing_id().unwrap();
+        msg = "reply".into();
+        msg.set_routing_id(id);
+        server.send(msg).unwrap();
+
+        let msg = client.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "reply");
+    }
+
+    #[test]
+    fn test_curve_wrong_server_key() {
+        let ctx = Ctx::new();
+
+        let server_cert = CurveCert::new_unique();
+        let client_cert = CurveCert::new_unique();
+        let wrong_cert = CurveCert::new_unique();
+
+        let _ = AuthBuilder::new()
+            .curve_registry(client_cert.public())
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .curve_server(server_cert.secret())
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let mut events = handshakes(&server);
+
+        let client_creds =
+            CurveClientCreds::new(wrong_cert.public()).add_cert(client_cert);
+        let _client = ClientBuilder::new()
+            .curve_client(client_creds)
+            .connect(bound)
+            .with_ctx(&ctx)
+            .unwrap();
+
+        // The server cannot decrypt the hello of the client.
+        let failed = (
+            sys::ZMQ_EVENT_HANDSHAKE_FAILED_PROTOCOL,
+            sys::ZMQ_PROTOCOL_ERROR_ZMTP_CRYPTOGRAPHIC,
+        );
+        assert_eq!(next_handshake(&mut events), failed);
+    }
+
+    #[test]
+    fn test_curve_invalid_cert() {
+        let cert = CurveCert::new_unique();
+        let other = CurveCert::new_unique();
+
+        // A certificate with mismatched keys can come from a config file.
+        let ron = format!(
+            "(public: {:?}, secret: {:?})",
+            other.public().as_str(),
+            cert.secret().as_str()
+        );
+        let invalid: CurveCert = ron::de::from_str(&ron).unwrap();
+
+        let creds = CurveClientCreds::new(other.public()).add_cert(invalid);
+        let err = ClientBuilder::new()
+            .curve_client(creds)
+            .build()
+            .unwrap_err();
+
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
     #[test]
     fn test_curve_denied() {
         let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
@@ -725,4 +1748,403 @@ mod test {
         client.try_send("").unwrap();
         server.recv_msg().unwrap();
     }
+
+    #[test]
+    fn test_curve_revoked() {
+        let ctx = Ctx::new();
+
+        let server_cert = CurveCert::new_unique();
+        let client_cert = CurveCert::new_unique();
+
+        let auth = AuthBuilder::new()
+            .curve_registry(client_cert.public())
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .curve_server(server_cert.secret())
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap().unwrap();
+        let mut events = handshakes(&server);
+
+        let client_creds =
+            CurveClientCreds::new(server_cert.public()).add_cert(client_cert);
+        let client = ClientBuilder::new()
+            .curve_client(&client_creds)
+            .connect(&bound)
+            .with_ctx(&ctx)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+        let (event, _) = next_handshake(&mut events);
+        assert_eq!(event, sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED);
+
+        // The revocation only affects the new connections.
+        auth.remove_curve_registry(client_creds.cert().unwrap().public())
+            .unwrap();
+
+        let _client = ClientBuilder::new()
+            .curve_client(&client_creds)
+            .connect(&bound)
+            .with_ctx(&ctx)
+            .unwrap();
+
+        assert_eq!(next_handshake(&mut events), DENIED);
+    }
+
+    #[test]
+    fn test_plain_handler() {
+        let ctx = Ctx::new();
+
+        let auth = AuthClient::with_ctx(&ctx).unwrap();
+        auth.set_plain_handler(|creds| creds.password() == "secret");
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .plain_server(true)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap().unwrap();
+        let mut events = handshakes(&server);
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .plain_credentials("user", "secret")
+            .with_ctx(&ctx)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+        let (event, _) = next_handshake(&mut events);
+        assert_eq!(event, sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED);
+
+        // Drop the denied clients so that their reconnections do not
+        // report more handshakes.
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .plain_credentials("user", "guess")
+            .with_ctx(&ctx)
+            .unwrap();
+
+        assert_eq!(next_handshake(&mut events), DENIED);
+        drop(client);
+
+        // Without the handler, only the registry is checked.
+        auth.clear_plain_handler();
+
+        let _client = ClientBuilder::new()
+            .connect(&bound)
+            .plain_credentials("user", "secret")
+            .with_ctx(&ctx)
+            .unwrap();
+
+        assert_eq!(next_handshake(&mut events), DENIED);
+    }
+
+    #[test]
+    fn test_plain_registry() {
+        let ctx = Ctx::new();
+
+        let mut registry = PlainRegistry::new();
+        registry
+            .add_user("alice", "secret")
+            .set_verifier(|username, password| username == password)
+            .add_domain_user("admin", "root", "root-secret");
+
+        let auth = AuthClient::with_ctx(&ctx).unwrap();
+        auth.use_plain(registry);
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .plain_server(true)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let connect = |username: &str, password: &str| {
+            let client = ClientBuilder::new()
+                .connect(&bound)
+                .plain_credentials(username, password)
+                .with_ctx(&ctx)
+                .unwrap();
+            client.try_send("").unwrap();
+            client
+        };
+
+        // The username is the user id of the peer.
+        let _client = connect("alice", "secret");
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.property("User-Id"), Some("alice"));
+
+        let _client = connect("bob", "bob");
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.property("User-Id"), Some("bob"));
+
+        let _client = connect("alice", "guess");
+        assert!(server.recv_msg().is_err());
+
+        // The `admin` domain users are scoped to the domain.
+        let _client = connect("root", "root-secret");
+        assert!(server.recv_msg().is_err());
+
+        auth.clear_plain();
+
+        let _client = connect("alice", "secret");
+        assert!(server.recv_msg().is_err());
+    }
+
+    #[test]
+    fn test_peer_identity() {
+        let ctx = Ctx::new();
+
+        let server_cert = CurveCert::new_unique();
+        let client_cert = CurveCert::new_unique();
+
+        let mut registry = PlainRegistry::new();
+        registry.add_user("alice", "secret");
+        let auth = AuthBuilder::new()
+            .curve_registry(client_cert.public())
+            .with_ctx(&ctx)
+            .unwrap();
+        auth.use_plain(registry);
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let bind = |mechanism: Mechanism| {
+            let server = ServerBuilder::new()
+                .bind(&addr)
+                .mechanism(mechanism)
+                .recv_timeout(Duration::from_millis(200))
+                .with_ctx(&ctx)
+                .unwrap();
+            let bound = server.last_endpoint().unwrap().unwrap();
+            (server, bound)
+        };
+        let connect = |bound: &addr::Endpoint, mechanism: Mechanism| {
+            let client = ClientBuilder::new()
+                .connect(bound)
+                .mechanism(mechanism)
+                .with_ctx(&ctx)
+                .unwrap();
+            client.try_send("").unwrap();
+            client
+        };
+
+        // The unauthenticated peers have no identity.
+        let (server, bound) = bind(Mechanism::Null);
+        let _client = connect(&bound, Mechanism::Null);
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.user_id(), None);
+        assert_eq!(msg.mechanism(), None);
+        assert_eq!(msg.peer_curve_key(), None);
+
+        let (server, bound) = bind(Mechanism::PlainServer);
+        let creds = PlainClientCreds::new("alice", "secret");
+        let _client = connect(&bound, creds.into());
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.user_id(), Some("alice"));
+        assert_eq!(msg.mechanism(), Some(MechanismName::Plain));
+        assert_eq!(msg.peer_curve_key(), None);
+
+        let server_creds = CurveServerCreds::new(server_cert.secret());
+        let (server, bound) = bind(server_creds.into());
+        let client_creds = CurveClientCreds::new(server_cert.public())
+            .add_cert(client_cert.clone());
+        let _client = connect(&bound, client_creds.into());
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.user_id(), Some(client_cert.public().as_str()));
+        assert_eq!(msg.mechanism(), Some(MechanismName::Curve));
+        assert_eq!(msg.peer_curve_key().as_ref(), Some(client_cert.public()));
+    }
+
+    #[test]
+    fn test_plain_registry_domain() {
+        let ctx = Ctx::new();
+
+        let mut registry = PlainRegistry::new();
+        registry.add_user("alice", "secret");
+        registry.add_domain_user("admin", "root", "root-secret");
+        AuthClient::with_ctx(&ctx).unwrap().use_plain(registry);
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .zap_domain("admin")
+            .plain_server(true)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .plain_credentials("root", "root-secret")
+            .with_ctx(&ctx)
+            .unwrap();
+        client.try_send("").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.property("User-Id"), Some("root"));
+
+        let client = ClientBuilder::new()
+            .connect(&bound)
+            .plain_credentials("alice", "secret")
+            .with_ctx(&ctx)
+            .unwrap();
+        client.try_send("").unwrap();
+        assert!(server.recv_msg().is_err());
+    }
+
+    #[test]
+    fn test_plain_domain_scopes() {
+        let ctx = Ctx::new();
+
+        let auth = AuthBuilder::new()
+            .plain_registry(&PlainClientCreds::new("alice", "secret"))
+            .with_ctx(&ctx)
+            .unwrap();
+        auth.set_plain_handler(|creds| creds.username() == "guest");
+        auth.set_domain_plain_handler("ops", |creds| creds.password() == "ops");
+
+        let mut registry = PlainRegistry::new();
+        registry.add_domain_user("admin", "root", "root-secret");
+        auth.use_plain(registry);
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let bind = |domain: Option<&str>| {
+            let mut builder = ServerBuilder::new();
+            if let Some(domain) = domain {
+                builder.zap_domain(domain);
+            }
+            builder
+                .bind(&addr)
+                .plain_server(true)
+                .recv_timeout(Duration::from_millis(200))
+                .with_ctx(&ctx)
+                .unwrap()
+        };
+        let allowed = |server: &Server, username: &str, password: &str| {
+            let bound = server.last_endpoint().unwrap().unwrap();
+            let client = ClientBuilder::new()
+                .connect(bound)
+                .plain_credentials(username, password)
+                .with_ctx(&ctx)
+                .unwrap();
+            client.try_send("").unwrap();
+            server.recv_msg().is_ok()
+        };
+
+        // The unscoped credentials apply to the domains without a scope.
+        let server = bind(None);
+        assert!(allowed(&server, "alice", "secret"));
+        assert!(allowed(&server, "guest", ""));
+        assert!(!allowed(&server, "root", "root-secret"));
+
+        let server = bind(Some("admin"));
+        assert!(allowed(&server, "root", "root-secret"));
+        assert!(!allowed(&server, "alice", "secret"));
+        assert!(!allowed(&server, "guest", ""));
+
+        let server = bind(Some("ops"));
+        assert!(allowed(&server, "bob", "ops"));
+        assert!(!allowed(&server, "alice", "secret"));
+        assert!(!allowed(&server, "guest", ""));
+
+        auth.clear_domain_plain_handler("ops");
+        let server = bind(Some("ops"));
+        assert!(allowed(&server, "alice", "secret"));
+    }
+
+    #[test]
+    fn test_domain_policy() {
+        let ctx = Ctx::new();
+
+        let creds = PlainClientCreds::new("user", "pwd");
+        let auth = AuthBuilder::new()
+            .plain_registry(&creds)
+            .default_policy(AuthPolicy::AllowAll)
+            .domain_policy("internal", AuthPolicy::DenyAll)
+            .with_ctx(&ctx)
+            .unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let internal = ServerBuilder::new()
+            .bind(&addr)
+            .zap_domain("internal")
+            .plain_server(true)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+        let internal_bound = internal.last_endpoint().unwrap().unwrap();
+
+        let public = ServerBuilder::new()
+            .bind(&addr)
+            .plain_server(true)
+            .recv_timeout(Duration::from_millis(200))
+            .with_ctx(&ctx)
+            .unwrap();
+        let public_bound = public.last_endpoint().unwrap().unwrap();
+
+        let mut internal_events = handshakes(&internal);
+        let mut public_events = handshakes(&public);
+
+        // The registered user is denied by the domain policy. The denied
+        // client is dropped so that its reconnections do not report more
+        // handshakes.
+        let client = ClientBuilder::new()
+            .connect(&internal_bound)
+            .mechanism(&creds)
+            .with_ctx(&ctx)
+            .unwrap();
+
+        assert_eq!(next_handshake(&mut internal_events), DENIED);
+        drop(client);
+
+        // Any user is allowed by the default policy.
+        let client = ClientBuilder::new()
+            .connect(&public_bound)
+            .plain_credentials("unknown", "pwd")
+            .with_ctx(&ctx)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        public.recv_msg().unwrap();
+        let (event, _) = next_handshake(&mut public_events);
+        assert_eq!(event, sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED);
+
+        // Reverting to the registry.
+        auth.set_domain_policy("internal", None).unwrap();
+        auth.set_default_policy(AuthPolicy::Registry).unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(&internal_bound)
+            .mechanism(&creds)
+            .with_ctx(&ctx)
+            .unwrap();
+
+        client.try_send("").unwrap();
+        internal.recv_msg().unwrap();
+        let (event, _) = next_handshake(&mut internal_events);
+        assert_eq!(event, sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED);
+
+        let _client = ClientBuilder::new()
+            .connect(&public_bound)
+            .plain_credentials("unknown", "pwd")
+            .with_ctx(&ctx)
+            .unwrap();
+
+        assert_eq!(next_handshake(&mut public_events), DENIED);
+    }
 }
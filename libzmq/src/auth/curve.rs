@@ -1,12 +1,26 @@
 // The z85 codec logic is largely based on https://github.com/decafbad/z85
 
+use super::zpl::ZplCert;
+use crate::{
+    error::{Error, ErrorKind},
+    utils::{has, Capability},
+};
 use libzmq_sys as sys;
 
 use byteorder::{BigEndian, ByteOrder};
-use failure::Fail;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use std::{convert::TryFrom, ffi::CString, fmt, option, os::raw::c_char};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    ffi::CString,
+    fmt,
+    hash::{Hash, Hasher},
+    io, option,
+    os::raw::c_char,
+    path::Path,
+    str,
+};
 
 static LETTERS: [u8; 85] = [
     0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x61, 0x62,
@@ -32,17 +46,18 @@ static OCTETS: [u8; 96] = [
 
 // The size of a curve key in the z85 format.
 const CURVE_CURVE_KEY_SIZE: usize = 40;
+// The size of a curve key in the binary format.
+const CURVE_BIN_KEY_SIZE: usize = 32;
 
 /// A error when encoding or decoding a `CurveKey`.
-#[derive(Debug, Fail, Eq, PartialEq)]
+#[derive(Debug, thiserror::Error, Eq, PartialEq)]
 pub enum CurveError {
-    #[fail(display = "input string must have len of 40 char")]
+    #[error("input string must have len of 40 char")]
     InvalidSize,
-    #[fail(
-        display = "input string contains invalid byte 0x{:2X} at offset {}",
-        byte, pos
-    )]
+    #[error("input string contains invalid byte 0x{byte:2X} at offset {pos}")]
     InvalidByte { pos: usize, byte: u8 },
+    #[error("input bytes must have len of 32")]
+    InvalidBinSize,
 }
 
 fn z85_encode_chunk(input: &[u8]) -> [u8; 5] {
@@ -141,8 +156,35 @@ impl CurvePublicKey {
         Ok(Self { inner })
     }
 
+    /// Create a new `CurvePublicKey` from its 32 bytes binary representation.
+    ///
+    /// # Example
+    /// ```
+    /// use libzmq::auth::{CurveError, CurvePublicKey};
+    ///
+    /// let public = CurvePublicKey::from_bytes(&[0x42; 32]).unwrap();
+    /// assert_eq!(public.to_bytes(), vec![0x42; 32]);
+    ///
+    /// let err = CurvePublicKey::from_bytes(&[0x42; 8]).unwrap_err();
+    /// assert_eq!(err, CurveError::InvalidBinSize);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CurveError> {
+        let inner = CurveKey::from_bytes(bytes)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Returns the key in its 32 bytes binary representation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        BinCurveKey::from(self).bytes
+    }
+
     /// Derive a public key from a secret key.
     ///
+    /// # Panic
+    /// Panics if *libzmq* was built without `curve` support. See
+    /// [`curve_public`] for the fallible version.
+    ///
     /// # Example
     /// ```
     /// use libzmq::auth::{CurveCert, CurvePublicKey};
@@ -150,20 +192,36 @@ impl CurvePublicKey {
     ///
     /// assert_eq!(cert.public(), &CurvePublicKey::from_secret(cert.secret()));
     /// ```
+    ///
+    /// [`curve_public`]: fn.curve_public.html
     pub fn from_secret<K>(secret: K) -> Self
     where
         K: Into<CurveSecretKey>,
     {
-        let key: CurveKey = secret.into().into();
-        let inner = CurveKey::from_secret(key);
-
-        Self { inner }
+        curve_public(secret).expect("curve not supported")
     }
 
     /// Returns the key in `Z85` encoded string.
     pub fn as_str(&self) -> &str {
         self.inner.as_str()
     }
+
+    /// Reads the public key of a certificate file in the ZPL format of
+    /// *czmq*.
+    ///
+    /// This accepts both public and secret certificate files.
+    pub fn from_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let cert = ZplCert::read(path.as_ref())?;
+        let public = cert.public.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "missing public key")
+        })?;
+
+        Self::new(public)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
 }
 
 impl fmt::Display for CurvePublicKey {
@@ -192,6 +250,14 @@ impl<'a> From<&'a CurvePublicKey> for CurvePublicKey {
     }
 }
 
+impl From<[u8; CURVE_BIN_KEY_SIZE]> for CurvePublicKey {
+    fn from(bytes: [u8; CURVE_BIN_KEY_SIZE]) -> Self {
+        let inner = CurveKey::from_bytes(&bytes).unwrap();
+
+        Self { inner }
+    }
+}
+
 impl From<BinCurveKey> for CurvePublicKey {
     fn from(key: BinCurveKey) -> Self {
         let inner: CurveKey = key.into();
@@ -229,6 +295,13 @@ impl<'a> TryFrom<&'a String> for CurvePublicKey {
     }
 }
 
+impl str::FromStr for CurvePublicKey {
+    type Err = CurveError;
+    fn from_str(text: &str) -> Result<Self, CurveError> {
+        Self::new(text)
+    }
+}
+
 impl IntoIterator for CurvePublicKey {
     type Item = Self;
     type IntoIter = option::IntoIter<Self>;
@@ -247,7 +320,7 @@ impl<'a> IntoIterator for &'a CurvePublicKey {
     }
 }
 
-/// A public `CURVE` cryptographic key in the printable [`Z85`] representation.
+/// A secret `CURVE` cryptographic key in the printable [`Z85`] representation.
 ///
 /// Can be generated by [`CurveCert::new_unique()`].
 ///
@@ -255,9 +328,11 @@ impl<'a> IntoIterator for &'a CurvePublicKey {
 /// This type implements debug but does not leak the secret key.
 /// Use `as_str` to print the key.
 ///
+/// The keys are compared in constant time to prevent timing attacks.
+///
 /// [`Z85`]: https://rfc.zeromq.org/spec:32/Z85/
 /// [`CurveCert::new_unique()`]: struct.CurveCert.html#method.new_unique
-#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct CurveSecretKey {
     inner: CurveKey,
@@ -274,17 +349,97 @@ impl CurveSecretKey {
         Ok(Self { inner })
     }
 
+    /// Create a new `CurveSecretKey` from its 32 bytes binary representation.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CurveError> {
+        let inner = CurveKey::from_bytes(bytes)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Returns the key in its 32 bytes binary representation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        BinCurveKey::from(self).bytes
+    }
+
     /// Returns the key in `Z85` encoded string.
     pub fn as_str(&self) -> &str {
         self.inner.as_str()
     }
+
+    /// Derives the public key of the secret key.
+    ///
+    /// This is the same as [`curve_public`].
+    ///
+    /// # Returned Error Variants
+    /// * [`Unsupported`] (if *libzmq* was built without `curve` support)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::auth::{CurveKeyPair, CurveSecretKey};
+    ///
+    /// let pair = CurveKeyPair::new()?;
+    /// let secret = CurveSecretKey::from_bytes(&pair.secret().to_bytes())?;
+    ///
+    /// assert_eq!(&secret.public_key()?, pair.public());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`curve_public`]: fn.curve_public.html
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    pub fn public_key(&self) -> Result<CurvePublicKey, Error> {
+        curve_public(self)
+    }
+}
+
+/// Derives the public key of a secret key.
+///
+/// Unlike [`CurvePublicKey::from_secret`], this does not panic if *libzmq*
+/// was built without `curve` support. See [`zmq_curve_public`].
+///
+/// # Returned Error Variants
+/// * [`Unsupported`] (if *libzmq* was built without `curve` support)
+///
+/// [`CurvePublicKey::from_secret`]: struct.CurvePublicKey.html#method.from_secret
+/// [`zmq_curve_public`]: http://api.zeromq.org/master:zmq-curve-public
+/// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+pub fn curve_public<K>(secret: K) -> Result<CurvePublicKey, Error>
+where
+    K: Into<CurveSecretKey>,
+{
+    let key: CurveKey = secret.into().into();
+    let inner = CurveKey::from_secret(key)?;
+
+    Ok(CurvePublicKey { inner })
 }
 
 impl fmt::Debug for CurveSecretKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("CurveServerKey")
-            .field("key", &self.as_str())
-            .finish()
+        f.debug_struct("CurveSecretKey").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for CurveSecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        let lhs = self.as_str().as_bytes();
+        let rhs = other.as_str().as_bytes();
+
+        // Every byte is compared regardless of where the first difference
+        // is, so that the duration does not depend on the keys.
+        lhs.len() == rhs.len()
+            && lhs.iter().zip(rhs).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+    }
+}
+
+impl Eq for CurveSecretKey {}
+
+impl Hash for CurveSecretKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state);
     }
 }
 
@@ -300,6 +455,14 @@ impl<'a> From<&'a CurveSecretKey> for CurveSecretKey {
     }
 }
 
+impl From<[u8; CURVE_BIN_KEY_SIZE]> for CurveSecretKey {
+    fn from(bytes: [u8; CURVE_BIN_KEY_SIZE]) -> Self {
+        let inner = CurveKey::from_bytes(&bytes).unwrap();
+
+        Self { inner }
+    }
+}
+
 impl From<BinCurveKey> for CurveSecretKey {
     fn from(key: BinCurveKey) -> Self {
         let inner: CurveKey = key.into();
@@ -337,6 +500,13 @@ impl<'a> TryFrom<&'a String> for CurveSecretKey {
     }
 }
 
+impl str::FromStr for CurveSecretKey {
+    type Err = CurveError;
+    fn from_str(text: &str) -> Result<Self, CurveError> {
+        Self::new(text)
+    }
+}
+
 impl IntoIterator for CurveSecretKey {
     type Item = Self;
     type IntoIter = option::IntoIter<Self>;
@@ -380,10 +550,28 @@ impl CurveKey {
         Ok(Self { text })
     }
 
-    fn from_secret<K>(secret: K) -> Self
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CurveError> {
+        if bytes.len() != CURVE_BIN_KEY_SIZE {
+            return Err(CurveError::InvalidBinSize);
+        }
+
+        // No need to validate.
+        let text = z85_encode(bytes).unwrap();
+        Ok(Self { text })
+    }
+
+    fn from_secret<K>(secret: K) -> Result<Self, Error>
     where
         K: Into<CurveKey>,
     {
+        // `zmq_curve_public` does fail without curve support, but we check
+        // beforehand to give a proper error.
+        if !has(Capability::Curve) {
+            return Err(Error::new(ErrorKind::Unsupported {
+                msg: "curve not supported",
+            }));
+        }
+
         let secret = secret.into();
         let public = unsafe {
             CString::from_vec_unchecked(vec![0u8; CURVE_CURVE_KEY_SIZE])
@@ -397,11 +585,15 @@ impl CurveKey {
             )
         };
 
-        assert_eq!(rc, 0, "curve not supported");
+        if rc == -1 {
+            return Err(Error::new(ErrorKind::Unsupported {
+                msg: "curve not supported",
+            }));
+        }
 
-        Self {
+        Ok(Self {
             text: public.into_string().unwrap(),
-        }
+        })
     }
 
     fn as_str(&self) -> &str {
@@ -499,6 +691,11 @@ impl<'a> IntoIterator for &'a CurveKey {
 
 /// A `CURVE` certificate containing a public and secret `CurveKey`.
 ///
+/// The `Debug` output of the certificate does not leak its secret key.
+///
+/// A certificate can also carry arbitrary metadata, which is preserved
+/// when it is written to and read from a file.
+///
 /// # Example
 /// ```
 /// use libzmq::auth::CurveCert;
@@ -510,11 +707,51 @@ impl<'a> IntoIterator for &'a CurveKey {
 pub struct CurveCert {
     public: CurvePublicKey,
     secret: CurveSecretKey,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
 }
 
+/// A `CURVE` key pair, which is the same as a [`CurveCert`].
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::auth::{CurveKeyPair, CurveSecretKey};
+///
+/// let pair = CurveKeyPair::new()?;
+///
+/// // The secret key can be stored as `Z85` text.
+/// let text = pair.secret().as_str().to_owned();
+/// let secret: CurveSecretKey = text.parse()?;
+///
+/// // And the public key rederived from it.
+/// assert_eq!(CurveKeyPair::from_secret(secret), pair);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`CurveCert`]: struct.CurveCert.html
+pub type CurveKeyPair = CurveCert;
+
 impl CurveCert {
     /// Generate a new unique certificate.
-    pub fn new_unique() -> Self {
+    ///
+    /// # Returned Error Variants
+    /// * [`Unsupported`] (if *libzmq* was built without `curve` support)
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    pub fn new() -> Result<Self, Error> {
+        // `zmq_curve_keypair` does fail without curve support, but we check
+        // beforehand to give a proper error.
+        if !has(Capability::Curve) {
+            return Err(Error::new(ErrorKind::Unsupported {
+                msg: "curve not supported",
+            }));
+        }
+
         let public = unsafe {
             CString::from_vec_unchecked(vec![0u8; CURVE_CURVE_KEY_SIZE])
         };
@@ -543,7 +780,167 @@ impl CurveCert {
             CurveSecretKey { inner }
         };
 
-        Self { public, secret }
+        Ok(Self {
+            public,
+            secret,
+            metadata: BTreeMap::new(),
+        })
+    }
+
+    /// Generate a new unique certificate.
+    ///
+    /// # Panic
+    /// Panics if *libzmq* was built without `curve` support. See [`new`]
+    /// for the fallible version.
+    ///
+    /// [`new`]: #method.new
+    pub fn new_unique() -> Self {
+        Self::new().expect("curve not supported")
+    }
+
+    /// Create a certificate from a secret key by deriving its public key.
+    ///
+    /// # Panic
+    /// Panics if *libzmq* was built without `curve` support. See
+    /// [`try_from_secret`] for the fallible version.
+    ///
+    /// # Example
+    /// ```
+    /// use libzmq::auth::CurveCert;
+    ///
+    /// let cert = CurveCert::new_unique();
+    /// let copy = CurveCert::from_secret(cert.secret());
+    ///
+    /// assert_eq!(cert, copy);
+    /// ```
+    ///
+    /// [`try_from_secret`]: #method.try_from_secret
+    pub fn from_secret<K>(secret: K) -> Self
+    where
+        K: Into<CurveSecretKey>,
+    {
+        Self::try_from_secret(secret).expect("curve not supported")
+    }
+
+    /// Create a certificate from a secret key by deriving its public key.
+    ///
+    /// # Returned Error Variants
+    /// * [`Unsupported`] (if *libzmq* was built without `curve` support)
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    pub fn try_from_secret<K>(secret: K) -> Result<Self, Error>
+    where
+        K: Into<CurveSecretKey>,
+    {
+        let secret = secret.into();
+        let public = curve_public(&secret)?;
+
+        Ok(Self {
+            public,
+            secret,
+            metadata: BTreeMap::new(),
+        })
+    }
+
+    /// Reads a secret certificate file in the ZPL format of *czmq*.
+    ///
+    /// The file must contain both the `public-key` and `secret-key` of the
+    /// `curve` section, such as the ones written by [`to_file`] or
+    /// `zcert_save`. The entries of the `metadata` section are kept.
+    ///
+    /// # Errors
+    /// Returns an error of kind `InvalidData` if the file is malformed,
+    /// lacks a key or if the public key is not derived from the secret key.
+    ///
+    /// [`to_file`]: #method.to_file
+    pub fn from_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let cert = ZplCert::read(path.as_ref())?;
+        let secret = cert
+            .secret
+            .ok_or_else(|| invalid("missing secret key".to_owned()))?;
+        let public = cert
+            .public
+            .ok_or_else(|| invalid("missing public key".to_owned()))?;
+
+        let secret = CurveSecretKey::new(secret)
+            .map_err(|err| invalid(err.to_string()))?;
+        let public = CurvePublicKey::new(public)
+            .map_err(|err| invalid(err.to_string()))?;
+
+        let cert = Self {
+            public,
+            secret,
+            metadata: cert.metadata,
+        };
+        let valid = cert.is_valid().map_err(|err| {
+            io::Error::new(io::ErrorKind::Unsupported, err.to_string())
+        })?;
+        if !valid {
+            return Err(invalid("public key does not match secret key".into()));
+        }
+
+        Ok(cert)
+    }
+
+    /// Writes the certificate in the ZPL format of *czmq*.
+    ///
+    /// Like `zcert_save`, the public certificate is written to `path` and
+    /// the secret certificate to `path` followed by `_secret`. Only the
+    /// latter contains the secret key and on Unix it is only readable
+    /// by its owner.
+    ///
+    /// # Errors
+    /// Returns an error of kind `InvalidInput` if `path` ends with `_secret`
+    /// or if the metadata cannot be represented in the format.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::auth::{CurveCert, CurvePublicKey};
+    ///
+    /// let mut cert = CurveCert::new_unique();
+    /// cert.metadata_mut().insert("name".into(), "server".into());
+    ///
+    /// let path = std::env::temp_dir().join(cert.public().as_str());
+    /// cert.to_file(&path)?;
+    ///
+    /// // The public certificate can be shared with the clients.
+    /// assert_eq!(&CurvePublicKey::from_file(&path)?, cert.public());
+    ///
+    /// let mut secret_path = path.clone().into_os_string();
+    /// secret_path.push("_secret");
+    /// assert_eq!(CurveCert::from_file(&secret_path)?, cert);
+    /// #
+    /// #     std::fs::remove_file(&path)?;
+    /// #     std::fs::remove_file(&secret_path)?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn to_file<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let cert = ZplCert {
+            public: Some(self.public.as_str().to_owned()),
+            secret: Some(self.secret.as_str().to_owned()),
+            metadata: self.metadata.clone(),
+        };
+
+        cert.write(path.as_ref())
+    }
+
+    // Whether the public key is indeed derived from the secret key.
+    //
+    // This can be false for a deserialized certificate.
+    pub(crate) fn is_valid(&self) -> Result<bool, Error> {
+        Ok(self.public == curve_public(&self.secret)?)
     }
 
     /// Returns a reference to the certificate's public key.
@@ -555,6 +952,16 @@ impl CurveCert {
     pub fn secret(&self) -> &CurveSecretKey {
         &self.secret
     }
+
+    /// Returns a reference to the certificate's metadata.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    /// Returns a mutable reference to the certificate's metadata.
+    pub fn metadata_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.metadata
+    }
 }
 
 // Binary representation of the `CURVE` key. This is what is sent
@@ -625,6 +1032,7 @@ impl<'a> From<&'a CurveSecretKey> for BinCurveKey {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::zpl::secret_path;
     use quickcheck::quickcheck;
 
     const Z85_RFC: &str = "HelloWorld";
@@ -669,6 +1077,211 @@ mod tests {
         CurveCert::new_unique();
     }
 
+    #[test]
+    fn curve_key_from_bytes() {
+        let key = CurveKey::new(CURVE_KEY_SECRET).unwrap();
+        let bin: BinCurveKey = (&key).into();
+
+        let from_bin = CurveKey::from_bytes(bin.as_bytes()).unwrap();
+        assert_eq!(from_bin, key);
+
+        let err = CurveKey::from_bytes(&BIN_RFC).unwrap_err();
+        assert_eq!(err, CurveError::InvalidBinSize);
+    }
+
+    #[test]
+    fn curve_key_pair_new() {
+        let pair = match CurveKeyPair::new() {
+            Ok(pair) => pair,
+            Err(err) => match err.kind() {
+                ErrorKind::Unsupported { .. } => {
+                    assert!(!has(Capability::Curve));
+                    return;
+                }
+                _ => panic!("unexpected error"),
+            },
+        };
+        assert!(pair.is_valid().unwrap());
+        assert_ne!(CurveKeyPair::new().unwrap(), pair);
+
+        let copy = CurveKeyPair::from_secret(pair.secret());
+        assert_eq!(copy, pair);
+        let copy = CurveKeyPair::try_from_secret(pair.secret()).unwrap();
+        assert_eq!(copy, pair);
+    }
+
+    #[test]
+    fn curve_public_from_secret() {
+        let pair = match CurveKeyPair::new() {
+            Ok(pair) => pair,
+            Err(err) => match err.kind() {
+                ErrorKind::Unsupported { .. } => {
+                    let secret: CurveSecretKey =
+                        CURVE_KEY_SECRET.parse().unwrap();
+                    let err = secret.public_key().unwrap_err();
+                    match err.kind() {
+                        ErrorKind::Unsupported { .. } => (),
+                        _ => panic!("unexpected error"),
+                    }
+                    return;
+                }
+                _ => panic!("unexpected error"),
+            },
+        };
+
+        let z85: CurveSecretKey = pair.secret().as_str().parse().unwrap();
+        assert_eq!(&z85.public_key().unwrap(), pair.public());
+
+        let bin =
+            CurveSecretKey::from_bytes(&pair.secret().to_bytes()).unwrap();
+        assert_eq!(&bin.public_key().unwrap(), pair.public());
+
+        let public = curve_public(pair.secret()).unwrap();
+        assert_eq!(&public, pair.public());
+
+        let secret: CurveSecretKey = CURVE_KEY_SECRET.parse().unwrap();
+        let public: CurvePublicKey = CURVE_KEY_PUBLIC.parse().unwrap();
+        assert_eq!(secret.public_key().unwrap(), public);
+    }
+
+    #[test]
+    fn curve_key_z85_round_trip() {
+        let secret: CurveSecretKey = CURVE_KEY_SECRET.parse().unwrap();
+        assert_eq!(secret.as_str(), CURVE_KEY_SECRET);
+
+        let public: CurvePublicKey = CURVE_KEY_PUBLIC.parse().unwrap();
+        assert_eq!(public.to_string(), CURVE_KEY_PUBLIC);
+        assert_eq!(CurvePublicKey::from_secret(&secret), public);
+
+        let mut bytes = [0; CURVE_BIN_KEY_SIZE];
+        bytes.copy_from_slice(&secret.to_bytes());
+        assert_eq!(CurveSecretKey::from(bytes), secret);
+
+        bytes.copy_from_slice(&public.to_bytes());
+        assert_eq!(CurvePublicKey::from(bytes), public);
+
+        let err = CURVE_KEY_INVALID_BYTE
+            .parse::<CurveSecretKey>()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CurveError::InvalidByte {
+                pos: 20,
+                byte: 0x7E
+            }
+        );
+    }
+
+    #[test]
+    fn curve_secret_key_redacted() {
+        let secret: CurveSecretKey = CURVE_KEY_SECRET.parse().unwrap();
+        assert_eq!(format!("{:?}", secret), "CurveSecretKey { .. }");
+
+        let cert = CurveCert::from_secret(&secret);
+        assert!(!format!("{:?}", cert).contains(CURVE_KEY_SECRET));
+        assert!(format!("{:?}", cert).contains(CURVE_KEY_PUBLIC));
+    }
+
+    #[test]
+    fn curve_secret_key_eq() {
+        let secret: CurveSecretKey = CURVE_KEY_SECRET.parse().unwrap();
+        let other = CurveSecretKey::from([0x42; CURVE_BIN_KEY_SIZE]);
+
+        assert_eq!(secret, secret.clone());
+        assert_ne!(secret, other);
+    }
+
+    #[test]
+    fn curve_cert_is_valid() {
+        let cert = CurveCert::new_unique();
+        assert!(cert.is_valid().unwrap());
+
+        let other = CurveCert::new_unique();
+        let invalid = CurveCert {
+            public: other.public,
+            secret: cert.secret,
+            metadata: BTreeMap::new(),
+        };
+        assert!(!invalid.is_valid().unwrap());
+    }
+
+    const CERT_FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/curve.cert");
+
+    fn temp_cert_path(name: &str) -> std::path::PathBuf {
+        let cert = CurveCert::new_unique();
+        std::env::temp_dir().join(format!("{}-{}", name, cert.public()))
+    }
+
+    #[test]
+    fn curve_cert_from_czmq_file() {
+        let public = CurvePublicKey::from_file(CERT_FIXTURE).unwrap();
+        assert_eq!(public.as_str(), CURVE_KEY_PUBLIC);
+
+        let secret_path = secret_path(Path::new(CERT_FIXTURE));
+        let cert = CurveCert::from_file(&secret_path).unwrap();
+        assert_eq!(cert.public().as_str(), CURVE_KEY_PUBLIC);
+        assert_eq!(cert.secret().as_str(), CURVE_KEY_SECRET);
+        assert_eq!(cert.metadata()["name"], "server");
+        assert_eq!(cert.metadata()["email"], "admin@example.com");
+
+        // The public certificate has no secret key.
+        let err = CurveCert::from_file(CERT_FIXTURE).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn curve_cert_file_round_trip() {
+        let path = temp_cert_path("curve_cert_file_round_trip");
+        let secret_path = secret_path(&path);
+
+        let mut cert = CurveCert::new_unique();
+        let metadata = cert.metadata_mut();
+        metadata.insert("name".into(), "\"quoted\" # value".into());
+        metadata.insert("empty".into(), "".into());
+        cert.to_file(&path).unwrap();
+
+        let public = std::fs::read_to_string(&path).unwrap();
+        assert!(public.contains(cert.public().as_str()));
+        assert!(!public.contains(cert.secret().as_str()));
+        assert!(!public.contains("secret-key"));
+
+        assert_eq!(&CurvePublicKey::from_file(&path).unwrap(), cert.public());
+        assert_eq!(CurveCert::from_file(&secret_path).unwrap(), cert);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = std::fs::metadata(&secret_path)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&secret_path).unwrap();
+    }
+
+    #[test]
+    fn curve_cert_to_file_invalid() {
+        let cert = CurveCert::new_unique();
+
+        let path = temp_cert_path("curve_cert_to_file_invalid");
+        let path = secret_path(&path);
+        let err = cert.to_file(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+
+        let mut cert = cert;
+        cert.metadata_mut().insert("bad name".into(), "".into());
+        let path = temp_cert_path("curve_cert_to_file_invalid");
+        let err = cert.to_file(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
     #[test]
     fn z85_encode_chunk_rfc() {
         let curve_chunk_1 = z85_decode_chunk(&Z85_RFC.as_bytes()[..5]).unwrap();
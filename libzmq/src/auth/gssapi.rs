@@ -0,0 +1,326 @@
+use super::*;
+
+use libzmq_sys as sys;
+use serde::{Deserialize, Serialize};
+
+use std::os::raw::c_int;
+
+/// The name type of a `GSSAPI` principal.
+///
+/// # Default Value
+/// `HostBased`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GssapiNameType {
+    /// The name is in the host based service format, e.g. `service@host`.
+    HostBased,
+    /// The name is a local user name.
+    UserName,
+    /// The name is a Kerberos principal, e.g. `user@REALM`.
+    Krb5Principal,
+}
+
+impl Default for GssapiNameType {
+    fn default() -> Self {
+        GssapiNameType::HostBased
+    }
+}
+
+impl From<GssapiNameType> for c_int {
+    fn from(name_type: GssapiNameType) -> c_int {
+        match name_type {
+            GssapiNameType::HostBased => sys::ZMQ_GSSAPI_NT_HOSTBASED as c_int,
+            GssapiNameType::UserName => sys::ZMQ_GSSAPI_NT_USER_NAME as c_int,
+            GssapiNameType::Krb5Principal => {
+                sys::ZMQ_GSSAPI_NT_KRB5_PRINCIPAL as c_int
+            }
+        }
+    }
+}
+
+/// A principal name used by the `GSSAPI` mechanism.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GssapiPrincipal {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) name_type: GssapiNameType,
+}
+
+impl GssapiPrincipal {
+    /// Create a new `GssapiPrincipal` from a name using the default
+    /// `HostBased` name type.
+    pub fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            name_type: GssapiNameType::default(),
+        }
+    }
+
+    /// Specifies the name type of the principal.
+    pub fn add_name_type(mut self, name_type: GssapiNameType) -> Self {
+        self.name_type = name_type;
+        self
+    }
+
+    /// Returns the name of the principal.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name type of the principal.
+    pub fn name_type(&self) -> GssapiNameType {
+        self.name_type
+    }
+}
+
+impl<'a> From<&'a str> for GssapiPrincipal {
+    fn from(name: &'a str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for GssapiPrincipal {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl<'a> From<&'a GssapiPrincipal> for GssapiPrincipal {
+    fn from(principal: &'a GssapiPrincipal) -> Self {
+        principal.to_owned()
+    }
+}
+
+/// Credentials for a `GSSAPI` client.
+///
+/// # Example
+/// ```
+/// use libzmq::auth::*;
+///
+/// let creds = GssapiClientCreds::new("zmq@server.example.com")
+///     .add_principal("client@EXAMPLE.COM")
+///     .plaintext(true);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GssapiClientCreds {
+    pub(crate) principal: Option<GssapiPrincipal>,
+    pub(crate) service_principal: GssapiPrincipal,
+    #[serde(default)]
+    pub(crate) plaintext: bool,
+}
+
+impl GssapiClientCreds {
+    /// Create a new `GssapiClientCreds` from the server's service principal.
+    pub fn new<S>(service_principal: S) -> Self
+    where
+        S: Into<GssapiPrincipal>,
+    {
+        Self {
+            principal: None,
+            service_principal: service_principal.into(),
+            plaintext: false,
+        }
+    }
+
+    /// Associates a client principal with the credentials.
+    ///
+    /// If not specified, the default principal of the credential
+    /// cache is used.
+    pub fn add_principal<P>(mut self, principal: P) -> Self
+    where
+        P: Into<GssapiPrincipal>,
+    {
+        self.principal = Some(principal.into());
+        self
+    }
+
+    /// Disables the encryption of the messages if `cond` is `true`.
+    ///
+    /// Both peers must agree on this setting.
+    pub fn plaintext(mut self, cond: bool) -> Self {
+        self.plaintext = cond;
+        self
+    }
+
+    /// Returns a reference to the client principal.
+    pub fn principal(&self) -> Option<&GssapiPrincipal> {
+        self.principal.as_ref()
+    }
+
+    /// Returns a reference to the server's service principal.
+    pub fn service_principal(&self) -> &GssapiPrincipal {
+        &self.service_principal
+    }
+
+    /// Returns `true` if the messages are not encrypted.
+    pub fn is_plaintext(&self) -> bool {
+        self.plaintext
+    }
+}
+
+impl<'a> From<&'a GssapiClientCreds> for GssapiClientCreds {
+    fn from(creds: &'a GssapiClientCreds) -> Self {
+        creds.to_owned()
+    }
+}
+
+impl<'a> From<&'a GssapiClientCreds> for Mechanism {
+    fn from(creds: &'a GssapiClientCreds) -> Self {
+        Mechanism::GssapiClient(creds.to_owned())
+    }
+}
+
+impl From<GssapiClientCreds> for Mechanism {
+    fn from(creds: GssapiClientCreds) -> Self {
+        Mechanism::GssapiClient(creds)
+    }
+}
+
+/// Credentials for a `GSSAPI` server.
+///
+/// By default, the `AuthServer` accepts every client principal that
+/// completes the `GSSAPI` handshake. To only accept some principals, add
+/// them to the gssapi registry and enable it with
+/// [`AuthClient::set_gssapi_auth`].
+///
+/// # Example
+/// ```
+/// use libzmq::auth::*;
+///
+/// let creds = GssapiServerCreds::new()
+///     .add_principal("zmq@server.example.com");
+/// ```
+///
+/// [`AuthClient::set_gssapi_auth`]: struct.AuthClient.html#method.set_gssapi_auth
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GssapiServerCreds {
+    pub(crate) principal: Option<GssapiPrincipal>,
+    #[serde(default)]
+    pub(crate) plaintext: bool,
+}
+
+impl GssapiServerCreds {
+    /// Create a new `GssapiServerCreds` that uses the default principal
+    /// of the keytab.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Associates a server principal with the credentials.
+    pub fn add_principal<P>(mut self, principal: P) -> Self
+    where
+        P: Into<GssapiPrincipal>,
+    {
+        self.principal = Some(principal.into());
+        self
+    }
+
+    /// Disables the encryption of the messages if `cond` is `true`.
+    ///
+    /// Both peers must agree on this setting.
+    pub fn plaintext(mut self, cond: bool) -> Self {
+        self.plaintext = cond;
+        self
+    }
+
+    /// Returns a reference to the server principal.
+    pub fn principal(&self) -> Option<&GssapiPrincipal> {
+        self.principal.as_ref()
+    }
+
+    /// Returns `true` if the messages are not encrypted.
+    pub fn is_plaintext(&self) -> bool {
+        self.plaintext
+    }
+}
+
+impl<'a> From<&'a GssapiServerCreds> for GssapiServerCreds {
+    fn from(creds: &'a GssapiServerCreds) -> Self {
+        creds.to_owned()
+    }
+}
+
+impl<'a> From<&'a GssapiServerCreds> for Mechanism {
+    fn from(creds: &'a GssapiServerCreds) -> Self {
+        Mechanism::GssapiServer(creds.to_owned())
+    }
+}
+
+impl From<GssapiServerCreds> for Mechanism {
+    fn from(creds: GssapiServerCreds) -> Self {
+        Mechanism::GssapiServer(creds)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, socket::*, *};
+
+    use std::{convert::TryInto, time::Duration};
+
+    #[test]
+    fn test_gssapi_validation() {
+        let creds = GssapiClientCreds::new("");
+        let err = ClientBuilder::new()
+            .gssapi_client(creds)
+            .build()
+            .unwrap_err();
+
+        match err.kind() {
+            ErrorKind::Unsupported { .. } => assert!(!has(Capability::Gssapi)),
+            ErrorKind::InvalidInput { .. } => assert!(has(Capability::Gssapi)),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_gssapi_unsupported() {
+        if has(Capability::Gssapi) {
+            return;
+        }
+
+        let creds = GssapiServerCreds::new();
+        let err = ServerBuilder::new()
+            .gssapi_server(creds)
+            .build()
+            .unwrap_err();
+
+        match err.kind() {
+            ErrorKind::Unsupported { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    // Requires a libzmq built with gssapi support as well as a working KDC
+    // with a `zmq@localhost` service principal in the keytab.
+    #[test]
+    #[ignore]
+    fn test_gssapi() {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server_creds =
+            GssapiServerCreds::new().add_principal("zmq@localhost");
+        let server = ServerBuilder::new()
+            .gssapi_server(server_creds)
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let client_creds = GssapiClientCreds::new("zmq@localhost");
+        let client = ClientBuilder::new()
+            .gssapi_client(client_creds)
+            .connect(bound)
+            .build()
+            .unwrap();
+
+        client.try_send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+}
@@ -1,10 +1,9 @@
 use super::*;
 use crate::*;
 
-use failure::Fail;
 use serde::{Deserialize, Serialize};
 
-use std::{convert::TryFrom, option};
+use std::{convert::TryFrom, fmt, mem, option};
 
 /// Credentials for a `PLAIN` client.
 /// # Example
@@ -13,7 +12,7 @@ use std::{convert::TryFrom, option};
 ///
 /// let creds = PlainClientCreds::new("user", "pass");
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PlainClientCreds {
     pub(crate) username: String,
     pub(crate) password: String,
@@ -43,6 +42,15 @@ impl PlainClientCreds {
     }
 }
 
+// Does not leak the password.
+impl fmt::Debug for PlainClientCreds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PlainClientCreds")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<'a> From<&'a PlainClientCreds> for PlainClientCreds {
     fn from(creds: &'a PlainClientCreds) -> Self {
         creds.to_owned()
@@ -237,6 +245,18 @@ pub enum Mechanism {
     /// A socket using the `CurveServer` mechanism accepts connections from
     /// sockets using the `CurveClient` mechanism.
     CurveServer(CurveServerCreds),
+    /// Authentication and optional encryption using the `GSSAPI` mechanism,
+    /// typically backed by Kerberos.
+    ///
+    /// A socket using the `GssapiClient` mechanism connects to sockets using
+    /// the `GssapiServer` mechanism.
+    GssapiClient(GssapiClientCreds),
+    /// Authentication and optional encryption using the `GSSAPI` mechanism,
+    /// typically backed by Kerberos.
+    ///
+    /// A socket using the `GssapiServer` mechanism accepts connections from
+    /// sockets using the `GssapiClient` mechanism.
+    GssapiServer(GssapiServerCreds),
 }
 
 impl<'a> From<&'a Mechanism> for Mechanism {
@@ -245,22 +265,56 @@ impl<'a> From<&'a Mechanism> for Mechanism {
     }
 }
 
+impl Mechanism {
+    // Whether the mechanisms are of different kinds, such as `PlainServer`
+    // and `CurveServer`. The `Null` mechanism is compatible with any other
+    // since it is used to reset the mechanism.
+    pub(crate) fn conflicts_with(&self, other: &Mechanism) -> bool {
+        *self != Mechanism::Null
+            && *other != Mechanism::Null
+            && mem::discriminant(self) != mem::discriminant(other)
+    }
+
+    // Whether the mechanism holds a password or a secret key.
+    pub(crate) fn has_secrets(&self) -> bool {
+        match self {
+            Mechanism::PlainClient(_)
+            | Mechanism::CurveClient(_)
+            | Mechanism::CurveServer(_) => true,
+            _ => false,
+        }
+    }
+}
+
 impl Default for Mechanism {
     fn default() -> Self {
         Mechanism::Null
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub(crate) enum MechanismName {
+/// The kind of a [`Mechanism`], without its credentials.
+///
+/// This is the mechanism of the handshake with a peer, as returned by
+/// [`Msg::mechanism`].
+///
+/// [`Mechanism`]: enum.Mechanism.html
+/// [`Msg::mechanism`]: ../struct.Msg.html#method.mechanism
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MechanismName {
+    /// The `Null` mechanism.
     Null,
+    /// The `PlainClient` and `PlainServer` mechanisms.
     Plain,
+    /// The `CurveClient` and `CurveServer` mechanisms.
     Curve,
+    /// The `GssapiClient` and `GssapiServer` mechanisms.
+    Gssapi,
 }
 
-#[derive(Debug, Fail)]
-#[fail(display = "unsupported mechanism")]
-pub(crate) struct InvalidMechanismName;
+/// The error returned when parsing an unknown `MechanismName`.
+#[derive(Debug, thiserror::Error)]
+#[error("unsupported mechanism")]
+pub struct InvalidMechanismName;
 
 impl<'a> TryFrom<&'a str> for MechanismName {
     type Error = InvalidMechanismName;
@@ -270,6 +324,7 @@ impl<'a> TryFrom<&'a str> for MechanismName {
             "NULL" => Ok(MechanismName::Null),
             "PLAIN" => Ok(MechanismName::Plain),
             "CURVE" => Ok(MechanismName::Curve),
+            "GSSAPI" => Ok(MechanismName::Gssapi),
             _ => Err(InvalidMechanismName),
         }
     }
@@ -9,13 +9,30 @@
 //! performed depending on the configuration of the `AuthHandler`. This
 //! configuration can be modified by using a `AuthClient` which send commands
 //! to the handler.
+//!
+//! The handler thread is started along with its `Ctx` and lives as long as
+//! the context. It cannot be stopped on its own: it exits once the context
+//! is shut down via [`Ctx::shutdown`] or [`Ctx::terminate_timeout`], upon
+//! which its sockets fail with `CtxTerminated`. Since the thread holds a
+//! handle to the context, a `Ctx` that is never shut down is never
+//! terminated either, even once all the other handles were dropped.
+//!
+//! [`Ctx::shutdown`]: ../struct.Ctx.html#method.shutdown
+//! [`Ctx::terminate_timeout`]: ../struct.Ctx.html#method.terminate_timeout
 
 pub(crate) mod client;
 mod curve;
+mod gssapi;
 mod mechanism;
+mod net;
+mod plain;
 pub(crate) mod server;
+mod zpl;
 
 pub use client::{AuthBuilder, AuthClient};
 pub use curve::*;
+pub use gssapi::*;
 pub use mechanism::*;
-pub use server::{StatusCode, StatusCodeParseError};
+pub use net::IpNet;
+pub use plain::PlainRegistry;
+pub use server::{AuthPolicy, StatusCode, StatusCodeParseError};
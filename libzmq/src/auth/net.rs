@@ -0,0 +1,301 @@
+use crate::error::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str,
+};
+
+// The number of leading bits of a IPv4-mapped IPv6 address that are
+// not part of the IPv4 address.
+const MAPPED_PREFIX: u8 = 96;
+
+// Converts the address to IPv6, so that IPv4 addresses match their
+// IPv4-mapped IPv6 form.
+pub(crate) fn normalize(ip: IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(ipv4) => ipv4.to_ipv6_mapped(),
+        IpAddr::V6(ipv6) => ipv6,
+    }
+}
+
+/// A range of IP addresses in the CIDR notation, such as `10.0.0.0/8`
+/// or `fe80::/10`.
+///
+/// A single address is a range with a prefix spanning all of its bits.
+///
+/// IPv4 ranges are stored as IPv4-mapped IPv6 ranges, so that `10.0.0.0/8`
+/// and `::ffff:10.0.0.0/104` are the same range and match both the IPv4
+/// and the IPv4-mapped IPv6 form of an address.
+///
+/// It is serialized in the CIDR notation.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::auth::IpNet;
+///
+/// let net: IpNet = "192.168.0.0/16".parse()?;
+///
+/// assert!(net.contains("192.168.1.1".parse()?));
+/// assert!(net.contains("::ffff:192.168.1.1".parse()?));
+/// assert!(!net.contains("10.0.0.1".parse()?));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpNet {
+    // The address with the bits after the prefix cleared.
+    addr: Ipv6Addr,
+    prefix: u8,
+}
+
+impl IpNet {
+    /// Creates an `IpNet` from an address and the length of its prefix
+    /// in bits.
+    ///
+    /// The bits of the address after the prefix are ignored.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the prefix is longer than the address)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn new<I>(addr: I, prefix: u8) -> Result<Self, Error>
+    where
+        I: Into<IpAddr>,
+    {
+        let (addr, prefix) = match addr.into() {
+            IpAddr::V4(ipv4) if prefix <= 32 => {
+                (ipv4.to_ipv6_mapped(), prefix + MAPPED_PREFIX)
+            }
+            IpAddr::V6(ipv6) if prefix <= 128 => (ipv6, prefix),
+            _ => {
+                return Err(Error::new(ErrorKind::InvalidInput {
+                    msg: "prefix longer than address",
+                }))
+            }
+        };
+
+        let addr = Ipv6Addr::from(u128::from(addr) & mask(prefix));
+        Ok(Self { addr, prefix })
+    }
+
+    /// Returns the first address of the range.
+    ///
+    /// This is an IPv4 address for IPv4 ranges.
+    pub fn addr(&self) -> IpAddr {
+        match self.ipv4() {
+            Some(ipv4) => IpAddr::V4(ipv4),
+            None => IpAddr::V6(self.addr),
+        }
+    }
+
+    /// Returns the length of the prefix in bits.
+    ///
+    /// This is relative to a IPv4 address for IPv4 ranges.
+    pub fn prefix(&self) -> u8 {
+        match self.ipv4() {
+            Some(_) => self.prefix - MAPPED_PREFIX,
+            None => self.prefix,
+        }
+    }
+
+    /// Returns `true` if the address is part of the range.
+    ///
+    /// IPv4 and IPv4-mapped IPv6 addresses are considered the same.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        self.contains_ipv6(normalize(addr))
+    }
+
+    pub(crate) fn contains_ipv6(&self, addr: Ipv6Addr) -> bool {
+        u128::from(addr) & mask(self.prefix) == u128::from(self.addr)
+    }
+
+    fn ipv4(&self) -> Option<Ipv4Addr> {
+        if self.prefix < MAPPED_PREFIX {
+            return None;
+        }
+        match self.addr.segments() {
+            [0, 0, 0, 0, 0, 0xffff, ..] => {
+                let [.., a, b, c, d] = self.addr.octets();
+                Some(Ipv4Addr::new(a, b, c, d))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn mask(prefix: u8) -> u128 {
+    match prefix {
+        0 => 0,
+        _ => !0 << (128 - u32::from(prefix.min(128))),
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr(), self.prefix())
+    }
+}
+
+impl From<IpNet> for String {
+    fn from(net: IpNet) -> Self {
+        net.to_string()
+    }
+}
+
+impl From<IpAddr> for IpNet {
+    fn from(addr: IpAddr) -> Self {
+        let prefix = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        Self::new(addr, prefix).unwrap()
+    }
+}
+
+impl From<Ipv4Addr> for IpNet {
+    fn from(addr: Ipv4Addr) -> Self {
+        IpAddr::V4(addr).into()
+    }
+}
+
+impl From<Ipv6Addr> for IpNet {
+    fn from(addr: Ipv6Addr) -> Self {
+        IpAddr::V6(addr).into()
+    }
+}
+
+impl<'a> From<&'a IpNet> for IpNet {
+    fn from(net: &'a IpNet) -> Self {
+        *net
+    }
+}
+
+impl str::FromStr for IpNet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || {
+            Error::new(ErrorKind::InvalidInput {
+                msg: "invalid ip address or range",
+            })
+        };
+
+        match s.split_once('/') {
+            Some((addr, prefix)) => {
+                let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+                // Reject signs and leading whitespace.
+                if !prefix.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid());
+                }
+                let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+
+                Self::new(addr, prefix)
+            }
+            None => {
+                let addr: IpAddr = s.parse().map_err(|_| invalid())?;
+                Ok(addr.into())
+            }
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for IpNet {
+    type Error = Error;
+    fn try_from(s: &'a str) -> Result<Self, Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for IpNet {
+    type Error = Error;
+    fn try_from(s: String) -> Result<Self, Error> {
+        s.parse()
+    }
+}
+
+impl<'a> TryFrom<&'a String> for IpNet {
+    type Error = Error;
+    fn try_from(s: &'a String) -> Result<Self, Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ip_net_contains() {
+        let net: IpNet = "10.1.0.0/16".parse().unwrap();
+        assert!(net.contains(ip("10.1.2.3")));
+        assert!(net.contains(ip("::ffff:10.1.2.3")));
+        assert!(!net.contains(ip("10.2.0.0")));
+        assert!(!net.contains(ip("::a01:203")));
+
+        let net: IpNet = "fe80::/10".parse().unwrap();
+        assert!(net.contains(ip("fe80::1")));
+        assert!(net.contains(ip("febf::1")));
+        assert!(!net.contains(ip("fec0::1")));
+
+        let all: IpNet = "::/0".parse().unwrap();
+        assert!(all.contains(ip("127.0.0.1")));
+        assert!(all.contains(ip("::1")));
+
+        let single: IpNet = "127.0.0.1".parse().unwrap();
+        assert!(single.contains(ip("127.0.0.1")));
+        assert!(!single.contains(ip("127.0.0.2")));
+    }
+
+    #[test]
+    fn test_ip_net_normalized() {
+        let v4: IpNet = "10.1.2.3/8".parse().unwrap();
+        let mapped: IpNet = "::ffff:10.0.0.0/104".parse().unwrap();
+        assert_eq!(v4, mapped);
+        assert_eq!(mapped.to_string(), "10.0.0.0/8");
+        assert_eq!(mapped.prefix(), 8);
+
+        let v6: IpNet = "2001:db8::1/32".parse().unwrap();
+        assert_eq!(v6.to_string(), "2001:db8::/32");
+    }
+
+    #[test]
+    fn test_ip_net_invalid() {
+        for s in &["", "10.0.0.0/33", "::/129", "10.0.0.0/", "10.0.0/8", "a/8"]
+        {
+            let err = s.parse::<IpNet>().unwrap_err();
+            match err.kind() {
+                ErrorKind::InvalidInput { .. } => (),
+                _ => panic!("unexpected error"),
+            }
+        }
+        assert!("10.0.0.0/+8".parse::<IpNet>().is_err());
+    }
+
+    #[test]
+    fn test_ip_net_ser_de() {
+        let net: IpNet = "10.1.2.3/8".parse().unwrap();
+
+        let ron = ron::ser::to_string(&net).unwrap();
+        assert_eq!(ron, "\"10.0.0.0/8\"");
+        let de: IpNet = ron::de::from_str(&ron).unwrap();
+        assert_eq!(de, net);
+
+        // The ranges are validated and masked like when parsed.
+        let de: IpNet = ron::de::from_str("\"10.1.2.3/8\"").unwrap();
+        assert_eq!(de, net);
+        assert!(ron::de::from_str::<IpNet>("\"::/129\"").is_err());
+    }
+}
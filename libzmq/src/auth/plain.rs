@@ -0,0 +1,246 @@
+use hashbrown::HashMap;
+
+use std::fmt;
+
+type Verifier = Box<dyn Fn(&str, &str) -> bool + Send + Sync>;
+
+// Compares the bytes in a time that only depends on their length.
+pub(crate) fn constant_time_eq(lhs: &[u8], rhs: &[u8]) -> bool {
+    lhs.len() == rhs.len()
+        && lhs.iter().zip(rhs).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
+
+#[derive(Default)]
+struct Scope {
+    users: HashMap<String, String>,
+    verifier: Option<Verifier>,
+}
+
+impl Scope {
+    fn verify(&self, username: &str, password: &str) -> bool {
+        let known = self.users.get(username).is_some_and(|expected| {
+            constant_time_eq(expected.as_bytes(), password.as_bytes())
+        });
+
+        known
+            || self
+                .verifier
+                .as_ref()
+                .is_some_and(|verifier| verifier(username, password))
+    }
+}
+
+// Only prints the usernames.
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut users: Vec<&str> =
+            self.users.keys().map(String::as_str).collect();
+        users.sort_unstable();
+
+        f.debug_struct("Scope")
+            .field("users", &users)
+            .field("verifier", &self.verifier.is_some())
+            .finish()
+    }
+}
+
+/// The credentials accepted by the `AuthServer` for the `PLAIN` mechanism.
+///
+/// A registry holds static username and password pairs as well as an
+/// optional verifier callback, which is called for the credentials that
+/// no static pair accepts. This allows to delegate the authentication to
+/// an external system, such as a database or a LDAP directory.
+///
+/// The pairs and verifier can be scoped to a `ZAP` domain, in which case
+/// they replace the unscoped ones for the handshakes of this domain. The
+/// unscoped credentials of the `AuthServer`, namely its plain registry and
+/// the callback set by [`AuthClient::set_plain_handler`], do not apply to
+/// a domain with a scope either.
+///
+/// The passwords are compared in constant time and are never printed,
+/// not even by the `Debug` implementation.
+///
+/// The registry is installed with [`AuthClient::use_plain`]. The username
+/// of the authenticated credentials becomes the `ZAP` user id of the peer.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::auth::*;
+///
+/// let mut registry = PlainRegistry::new();
+/// registry
+///     .add_user("alice", "secret")
+///     .set_verifier(|username, password| {
+///         username.starts_with("guest") && password.is_empty()
+///     })
+///     .add_domain_user("admin", "root", "hunter2");
+///
+/// assert!(registry.verify("", "alice", "secret"));
+/// assert!(registry.verify("", "guest42", ""));
+/// // The `admin` domain has its own users.
+/// assert!(!registry.verify("admin", "alice", "secret"));
+/// assert!(registry.verify("admin", "root", "hunter2"));
+///
+/// assert!(!format!("{:?}", registry).contains("hunter2"));
+///
+/// let client = AuthClient::new()?;
+/// client.use_plain(registry);
+/// # client.clear_plain();
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`AuthClient::use_plain`]: struct.AuthClient.html#method.use_plain
+/// [`AuthClient::set_plain_handler`]: struct.AuthClient.html#method.set_plain_handler
+#[derive(Debug, Default)]
+pub struct PlainRegistry {
+    default: Scope,
+    domains: HashMap<String, Scope>,
+}
+
+impl PlainRegistry {
+    /// Creates an empty `PlainRegistry` that denies every credentials.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts the username and password pair, replacing the previous
+    /// password of the user.
+    pub fn add_user<U, P>(&mut self, username: U, password: P) -> &mut Self
+    where
+        U: Into<String>,
+        P: Into<String>,
+    {
+        self.default.users.insert(username.into(), password.into());
+        self
+    }
+
+    /// Removes the pair of the user, if it is present.
+    pub fn remove_user(&mut self, username: &str) -> &mut Self {
+        self.default.users.remove(username);
+        self
+    }
+
+    /// Sets the verifier called for the credentials that no static pair
+    /// accepts, replacing the previous one.
+    ///
+    /// The verifier returns `true` to accept the credentials. It is called
+    /// from the thread of the `AuthServer` and should thus not block for
+    /// long.
+    pub fn set_verifier<F>(&mut self, verifier: F) -> &mut Self
+    where
+        F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+    {
+        self.default.verifier = Some(Box::new(verifier));
+        self
+    }
+
+    /// Accepts the username and password pair for the handshakes of the
+    /// `ZAP` domain only.
+    ///
+    /// Once a domain has a pair or a verifier, the unscoped ones no longer
+    /// apply to its handshakes.
+    pub fn add_domain_user<D, U, P>(
+        &mut self,
+        domain: D,
+        username: U,
+        password: P,
+    ) -> &mut Self
+    where
+        D: Into<String>,
+        U: Into<String>,
+        P: Into<String>,
+    {
+        self.domains
+            .entry(domain.into())
+            .or_default()
+            .users
+            .insert(username.into(), password.into());
+        self
+    }
+
+    /// Sets the verifier of the `ZAP` domain, replacing the previous one.
+    ///
+    /// See [`set_verifier`] and [`add_domain_user`].
+    ///
+    /// [`set_verifier`]: #method.set_verifier
+    /// [`add_domain_user`]: #method.add_domain_user
+    pub fn set_domain_verifier<D, F>(
+        &mut self,
+        domain: D,
+        verifier: F,
+    ) -> &mut Self
+    where
+        D: Into<String>,
+        F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+    {
+        self.domains.entry(domain.into()).or_default().verifier =
+            Some(Box::new(verifier));
+        self
+    }
+
+    /// Returns `true` if the credentials are accepted for a handshake of
+    /// the `ZAP` domain.
+    ///
+    /// The handshakes with no `ZAP` domain have the empty domain `""`.
+    pub fn verify(&self, domain: &str, username: &str, password: &str) -> bool {
+        self.domains
+            .get(domain)
+            .unwrap_or(&self.default)
+            .verify(username, password)
+    }
+
+    // Returns `true` if the domain has a scope of its own.
+    pub(crate) fn has_scope(&self, domain: &str) -> bool {
+        self.domains.contains_key(domain)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+    }
+
+    #[test]
+    fn test_plain_registry_scopes() {
+        let mut registry = PlainRegistry::new();
+        assert!(!registry.verify("", "", ""));
+
+        registry
+            .add_user("user", "pass")
+            .set_domain_verifier("ldap", |user, pass| user == pass);
+
+        assert!(registry.verify("", "user", "pass"));
+        assert!(registry.verify("other", "user", "pass"));
+        assert!(!registry.verify("", "user", "other"));
+        assert!(!registry.verify("", "same", "same"));
+
+        assert!(!registry.verify("ldap", "user", "pass"));
+        assert!(registry.verify("ldap", "same", "same"));
+
+        registry.remove_user("user");
+        assert!(!registry.verify("", "user", "pass"));
+    }
+
+    #[test]
+    fn test_plain_registry_debug_redacted() {
+        let mut registry = PlainRegistry::new();
+        registry.add_user("user", "password1");
+        registry.add_domain_user("domain", "admin", "password2");
+
+        let debug = format!("{:?}", registry);
+        assert!(debug.contains("user") && debug.contains("admin"));
+        assert!(!debug.contains("password"));
+    }
+}
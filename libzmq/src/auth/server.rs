@@ -1,17 +1,18 @@
 use super::{client::*, *};
 use crate::{old::*, poll::*, prelude::*, socket::*, *};
 
-use failure::Fail;
 use hashbrown::{HashMap, HashSet};
 use lazy_static::lazy_static;
 use log::info;
 
 use libc::c_long;
+use serde::{Deserialize, Serialize};
 
 use std::{
     convert::{TryFrom, TryInto},
     fmt,
-    net::Ipv6Addr,
+    net::{IpAddr, Ipv6Addr},
+    sync::Arc,
     vec,
 };
 
@@ -47,8 +48,49 @@ impl fmt::Display for StatusCode {
     }
 }
 
-#[derive(Debug, Fail)]
-#[fail(display = "unable to parse status code")]
+/// How the `AuthServer` authenticates the handshakes of a `ZAP` domain.
+///
+/// The blacklist, the whitelist and the allowed and denied ranges are
+/// always applied beforehand.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+pub enum AuthPolicy {
+    /// Authenticates the credentials of the mechanism, such as with the
+    /// `PLAIN` and `CURVE` registries.
+    ///
+    /// This is the default.
+    #[default]
+    Registry,
+    /// Allows every handshake.
+    AllowAll,
+    /// Denies every handshake.
+    DenyAll,
+}
+
+// A user callback that authenticates `PLAIN` credentials.
+#[derive(Clone)]
+pub(crate) struct PlainHandler(
+    Arc<dyn Fn(&PlainClientCreds) -> bool + Send + Sync>,
+);
+
+impl PlainHandler {
+    pub(crate) fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&PlainClientCreds) -> bool + Send + Sync + 'static,
+    {
+        PlainHandler(Arc::new(handler))
+    }
+}
+
+impl fmt::Debug for PlainHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PlainHandler").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unable to parse status code")]
 #[doc(hidden)]
 pub struct StatusCodeParseError(());
 
@@ -97,7 +139,10 @@ impl ZapRequest {
 
         let request_id = parts.remove(0);
         let domain = parts.remove(0).to_str().unwrap().to_owned();
-        let addr: Ipv6Addr = parts.remove(0).to_str().unwrap().parse().unwrap();
+        // IPv4 peers are reported as such, unless the socket is in IPv6
+        // mode in which case they have the IPv4-mapped form.
+        let addr: IpAddr = parts.remove(0).to_str().unwrap().parse().unwrap();
+        let addr = super::net::normalize(addr);
 
         let identity = parts.remove(0);
 
@@ -147,18 +192,41 @@ struct AuthResult {
     metadata: Vec<u8>,
 }
 
+// The names of the ZAP metadata properties attached to the messages of the
+// authenticated peers.
+pub(crate) const MECHANISM_PROPERTY: &str = "X-Mechanism";
+pub(crate) const CURVE_KEY_PROPERTY: &str = "X-Curve-Public-Key";
+
+// Encodes a property as specified by ZMTP, with the name prefixed by its
+// length on one byte and the value by its length on four bytes.
+fn push_property(metadata: &mut Vec<u8>, name: &str, value: &str) {
+    metadata.push(name.len() as u8);
+    metadata.extend_from_slice(name.as_bytes());
+    metadata.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    metadata.extend_from_slice(value.as_bytes());
+}
+
 // A configurable ZAP handler.
 pub(crate) struct AuthServer {
+    ctx: Ctx,
     //  ZAP handler socket
     handler: OldSocket,
     request: Server,
     whitelist: HashSet<Ipv6Addr>,
     blacklist: HashSet<Ipv6Addr>,
+    allow: HashSet<IpNet>,
+    deny: HashSet<IpNet>,
     plain_registry: HashMap<String, String>,
     // Allowed public client keys.
     curve_registry: HashSet<CurvePublicKey>,
     // Whether curve auth is enabled.
     curve_auth: bool,
+    // Allowed GSSAPI principals.
+    gssapi_registry: HashSet<String>,
+    // Whether gssapi auth is enabled.
+    gssapi_auth: bool,
+    default_policy: AuthPolicy,
+    domain_policies: HashMap<String, AuthPolicy>,
 }
 
 impl AuthServer {
@@ -170,17 +238,24 @@ impl AuthServer {
         let mut handler = OldSocket::with_ctx(OldSocketType::Router, &ctx)?;
         handler.bind(&*ZAP_ENDPOINT)?;
 
-        let request = Server::with_ctx(ctx)?;
+        let request = Server::with_ctx(&ctx)?;
         request.bind(&*COMMAND_ENDPOINT).map_err(Error::cast)?;
 
         Ok(AuthServer {
+            ctx,
             handler,
             request,
             whitelist: HashSet::default(),
             blacklist: HashSet::default(),
+            allow: HashSet::default(),
+            deny: HashSet::default(),
             plain_registry: HashMap::default(),
             curve_registry: HashSet::default(),
             curve_auth: true,
+            gssapi_registry: HashSet::default(),
+            gssapi_auth: false,
+            default_policy: AuthPolicy::default(),
+            domain_policies: HashMap::default(),
         })
     }
 
@@ -272,6 +347,46 @@ impl AuthServer {
 
                 AuthReply::Success
             }
+            AuthRequest::AddAllow(net) => {
+                info!("added range: {} to allowed ranges", &net);
+                self.allow.insert(net);
+
+                AuthReply::Success
+            }
+            AuthRequest::RemoveAllow(net) => {
+                info!("removed range: {} from allowed ranges", &net);
+                self.allow.remove(&net);
+
+                AuthReply::Success
+            }
+            AuthRequest::SetAllow(nets) => {
+                info!("reset allowed ranges");
+                self.allow.clear();
+                info!("added ranges: {:#?} to allowed ranges", &nets);
+                self.allow.extend(nets);
+
+                AuthReply::Success
+            }
+            AuthRequest::AddDeny(net) => {
+                info!("added range: {} to denied ranges", &net);
+                self.deny.insert(net);
+
+                AuthReply::Success
+            }
+            AuthRequest::RemoveDeny(net) => {
+                info!("removed range: {} from denied ranges", &net);
+                self.deny.remove(&net);
+
+                AuthReply::Success
+            }
+            AuthRequest::SetDeny(nets) => {
+                info!("reset denied ranges");
+                self.deny.clear();
+                info!("added ranges: {:#?} to denied ranges", &nets);
+                self.deny.extend(nets);
+
+                AuthReply::Success
+            }
             AuthRequest::AddPlainRegistry(creds) => {
                 info!("added user : {} to plain registry", &creds.username);
                 self.plain_registry.insert(creds.username, creds.password);
@@ -324,6 +439,57 @@ impl AuthServer {
                 }
                 self.curve_auth = enabled;
 
+                AuthReply::Success
+            }
+            AuthRequest::AddGssapiRegistry(principal) => {
+                info!("added principal: {} to gssapi registry", &principal);
+                self.gssapi_registry.insert(principal);
+
+                AuthReply::Success
+            }
+            AuthRequest::RemoveGssapiRegistry(principal) => {
+                info!("removed principal: {} to gssapi registry", &principal);
+                self.gssapi_registry.remove(&principal);
+
+                AuthReply::Success
+            }
+            AuthRequest::SetGssapiRegistry(principals) => {
+                info!("reset gssapi registry");
+                self.gssapi_registry.clear();
+                info!(
+                    "added principals: {:#?} to gssapi registry",
+                    &principals
+                );
+                self.gssapi_registry.extend(principals);
+
+                AuthReply::Success
+            }
+            AuthRequest::SetGssapiAuth(enabled) => {
+                if enabled {
+                    info!("enabled gssapi auth");
+                } else {
+                    info!("disabled gssapi auth");
+                }
+                self.gssapi_auth = enabled;
+
+                AuthReply::Success
+            }
+            AuthRequest::SetDefaultPolicy(policy) => {
+                info!("set default policy to {:?}", policy);
+                self.default_policy = policy;
+
+                AuthReply::Success
+            }
+            AuthRequest::SetDomainPolicy(domain, Some(policy)) => {
+                info!("set policy of domain: {} to {:?}", &domain, policy);
+                self.domain_policies.insert(domain, policy);
+
+                AuthReply::Success
+            }
+            AuthRequest::SetDomainPolicy(domain, None) => {
+                info!("removed policy of domain: {}", &domain);
+                self.domain_policies.remove(&domain);
+
                 AuthReply::Success
             }
         }
@@ -342,60 +508,68 @@ impl AuthServer {
             {
                 info!("denied addr {}, blacklisted", &request.addr);
                 true
+            } else if self.deny.iter().any(|n| n.contains_ipv6(request.addr)) {
+                info!("denied addr {}, in a denied range", &request.addr);
+                true
+            } else if !self.allow.is_empty()
+                && !self.allow.iter().any(|n| n.contains_ipv6(request.addr))
+            {
+                info!("denied addr {}, not in an allowed range", &request.addr);
+                true
             } else {
                 false
             }
         };
 
-        let mut result = None;
-
-        if !denied {
-            if let Ok(mechanism) =
-                MechanismName::try_from(request.mechanism.as_str())
-            {
-                result = {
-                    match mechanism {
-                        MechanismName::Null => Some(AuthResult {
-                            user_id: String::new(),
-                            metadata: vec![],
-                        }),
-                        MechanismName::Plain => {
-                            let username = request
-                                .credentials
-                                .remove(0)
-                                .to_str()
-                                .unwrap()
-                                .to_owned();
-
-                            let password = request
-                                .credentials
-                                .remove(0)
-                                .to_str()
-                                .unwrap()
-                                .to_owned();
-
-                            let creds = PlainClientCreds { username, password };
-                            self.auth_plain(creds)
-                        }
-                        MechanismName::Curve => {
-                            let bin_public_key = BinCurveKey::new_unchecked(
-                                request
-                                    .credentials
-                                    .remove(0)
-                                    .as_bytes()
-                                    .to_owned(),
-                            );
-                            let public_key: CurvePublicKey =
-                                bin_public_key.into();
-
-                            self.auth_curve(public_key)
-                        }
-                    }
-                };
+        let policy = self
+            .domain_policies
+            .get(&request.domain)
+            .copied()
+            .unwrap_or(self.default_policy);
+
+        // Describes the handshake of the peer, since libzmq does not expose
+        // the mechanism nor the CURVE key of the peer on its messages.
+        let mut properties = vec![];
+        if request.mechanism != "NULL" {
+            push_property(
+                &mut properties,
+                MECHANISM_PROPERTY,
+                &request.mechanism,
+            );
+        }
+        if request.mechanism == "CURVE" {
+            if let Some(key) = request.credentials.first() {
+                let key = BinCurveKey::new_unchecked(key.as_bytes().to_owned());
+                let key: CurvePublicKey = key.into();
+                push_property(
+                    &mut properties,
+                    CURVE_KEY_PROPERTY,
+                    key.as_str(),
+                );
             }
         }
 
-        if let Some(result) = result {
+        let result = if denied {
+            None
+        } else {
+            match policy {
+                AuthPolicy::Registry => self.auth_mechanism(&mut request),
+                AuthPolicy::AllowAll => {
+                    info!("allowed domain {}, all allowed", &request.domain);
+                    Some(AuthResult {
+                        user_id: String::new(),
+                        metadata: vec![],
+                    })
+                }
+                AuthPolicy::DenyAll => {
+                    info!("denied domain {}, all denied", &request.domain);
+                    None
+                }
+            }
+        };
+
+        if let Some(mut result) = result {
+            result.metadata.extend(properties);
             Ok(ZapReply {
                 request_id: request.request_id,
                 user_id: result.user_id,
@@ -416,26 +590,95 @@ impl AuthServer {
         }
     }
 
-    fn auth_plain(&mut self, creds: PlainClientCreds) -> Option<AuthResult> {
-        match self.plain_registry.get(&creds.username) {
-            Some(password) => {
-                info!("allowed user: {}", &creds.username);
-                if password == &creds.password {
-                    Some(AuthResult {
-                        user_id: creds.username,
-                        metadata: vec![],
-                    })
-                } else {
-                    None
-                }
+    fn auth_mechanism(
+        &mut self,
+        request: &mut ZapRequest,
+    ) -> Option<AuthResult> {
+        let mechanism =
+            MechanismName::try_from(request.mechanism.as_str()).ok()?;
+
+        match mechanism {
+            MechanismName::Null => Some(AuthResult {
+                user_id: String::new(),
+                metadata: vec![],
+            }),
+            MechanismName::Plain => {
+                let username =
+                    request.credentials.remove(0).to_str().unwrap().to_owned();
+
+                let password =
+                    request.credentials.remove(0).to_str().unwrap().to_owned();
+
+                let creds = PlainClientCreds { username, password };
+                self.auth_plain(&request.domain, creds)
             }
-            None => {
-                info!("denied user: {}", &creds.username);
-                None
+            MechanismName::Curve => {
+                let bin_public_key = BinCurveKey::new_unchecked(
+                    request.credentials.remove(0).as_bytes().to_owned(),
+                );
+                let public_key: CurvePublicKey = bin_public_key.into();
+
+                self.auth_curve(public_key)
+            }
+            MechanismName::Gssapi => {
+                // The principal was already authenticated by
+                // the GSSAPI handshake.
+                let principal =
+                    request.credentials.remove(0).to_str().unwrap().to_owned();
+
+                self.auth_gssapi(principal)
             }
         }
     }
 
+    fn auth_plain(
+        &mut self,
+        domain: &str,
+        creds: PlainClientCreds,
+    ) -> Option<AuthResult> {
+        let registry = self.ctx.plain_registry();
+        let handler = self.ctx.domain_plain_handler(domain);
+        let scoped = handler.is_some()
+            || registry.as_ref().is_some_and(|r| r.has_scope(domain));
+
+        let verify = |registry: &PlainRegistry| {
+            registry.verify(domain, &creds.username, &creds.password)
+        };
+        let call = |PlainHandler(handler): PlainHandler| handler(&creds);
+
+        // A domain with scoped credentials ignores the unscoped ones. The
+        // callbacks are cloned out of the `Ctx`, so that they are called
+        // without holding its locks.
+        let allowed = if scoped {
+            handler.is_some_and(call)
+                || registry.is_some_and(|r| r.has_scope(domain) && verify(&r))
+        } else {
+            let known = match self.plain_registry.get(&creds.username) {
+                Some(password) => super::plain::constant_time_eq(
+                    password.as_bytes(),
+                    creds.password.as_bytes(),
+                ),
+                None => false,
+            };
+            // The handler and `PlainRegistry` are only consulted for the
+            // users the registry denies.
+            known
+                || self.ctx.plain_handler().is_some_and(call)
+                || registry.is_some_and(|r| verify(&r))
+        };
+
+        if allowed {
+            info!("allowed user: {}", &creds.username);
+            Some(AuthResult {
+                user_id: creds.username,
+                metadata: vec![],
+            })
+        } else {
+            info!("denied user: {}", &creds.username);
+            None
+        }
+    }
+
     fn auth_curve(&mut self, public_key: CurvePublicKey) -> Option<AuthResult> {
         if !self.curve_auth {
             info!("allowed curve public key {}", public_key);
@@ -454,4 +697,60 @@ impl AuthServer {
             None
         }
     }
+
+    fn auth_gssapi(&mut self, principal: String) -> Option<AuthResult> {
+        if !self.gssapi_auth || self.gssapi_registry.contains(&principal) {
+            info!("allowed gssapi principal {}", principal);
+            Some(AuthResult {
+                user_id: principal,
+                metadata: vec![],
+            })
+        } else {
+            info!("denied gssapi principal {}", principal);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A `ZAP` request of a peer authenticated by `GSSAPI`.
+    fn gssapi_request(principal: &str) -> ZapRequest {
+        ZapRequest {
+            version: ZAP_VERSION.to_owned(),
+            request_id: "1".into(),
+            domain: String::new(),
+            addr: Ipv6Addr::LOCALHOST,
+            identity: Msg::new(),
+            mechanism: "GSSAPI".to_owned(),
+            credentials: vec![principal.into()],
+        }
+    }
+
+    #[test]
+    fn test_gssapi_registry() {
+        let ctx = Ctx::without_auth();
+        let mut server = AuthServer::with_ctx(&ctx).unwrap();
+
+        // Every principal is accepted by default.
+        let reply = server.on_zap(gssapi_request("alice@REALM")).unwrap();
+        assert_eq!(reply.status_code, StatusCode::Allowed);
+        assert_eq!(reply.user_id, "alice@REALM");
+
+        let principals = vec!["alice@REALM".to_owned()];
+        server.on_request(AuthRequest::SetGssapiRegistry(principals));
+        server.on_request(AuthRequest::SetGssapiAuth(true));
+
+        let reply = server.on_zap(gssapi_request("alice@REALM")).unwrap();
+        assert_eq!(reply.status_code, StatusCode::Allowed);
+        let reply = server.on_zap(gssapi_request("mallory@REALM")).unwrap();
+        assert_eq!(reply.status_code, StatusCode::Denied);
+
+        server.on_request(AuthRequest::SetGssapiAuth(false));
+
+        let reply = server.on_zap(gssapi_request("mallory@REALM")).unwrap();
+        assert_eq!(reply.status_code, StatusCode::Allowed);
+    }
 }
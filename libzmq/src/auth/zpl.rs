@@ -0,0 +1,312 @@
+//! Reading and writing of certificates in the ZPL format used by *czmq*.
+//!
+//! See https://rfc.zeromq.org/spec:4/ZPL/ for the format itself.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(unix)]
+use std::{
+    fs::Permissions,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+};
+
+/// The suffix appended to the path of the secret certificate file.
+pub(crate) const SECRET_SUFFIX: &str = "_secret";
+
+// The content of a ZPL certificate file.
+#[derive(Debug, Default)]
+pub(crate) struct ZplCert {
+    pub(crate) public: Option<String>,
+    pub(crate) secret: Option<String>,
+    pub(crate) metadata: BTreeMap<String, String>,
+}
+
+impl ZplCert {
+    pub(crate) fn read(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+
+    pub(crate) fn parse(text: &str) -> io::Result<Self> {
+        let mut cert = Self::default();
+
+        for (path, value) in parse(text)? {
+            match path.as_str() {
+                "curve/public-key" => cert.public = Some(value),
+                "curve/secret-key" => cert.secret = Some(value),
+                _ => {
+                    if let Some(name) = path.strip_prefix("metadata/") {
+                        cert.metadata.insert(name.to_owned(), value);
+                    }
+                }
+            }
+        }
+
+        Ok(cert)
+    }
+
+    // Writes the public certificate to `path` and, if there is a secret key,
+    // the secret certificate to `path` followed by `_secret`, like
+    // `zcert_save` does.
+    pub(crate) fn write(&self, path: &Path) -> io::Result<()> {
+        if path.to_string_lossy().ends_with(SECRET_SUFFIX) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the public certificate path cannot end with `_secret`",
+            ));
+        }
+
+        let public = self.format(false)?;
+        let secret = match self.secret {
+            Some(_) => Some(self.format(true)?),
+            None => None,
+        };
+
+        create(path, false)?.write_all(public.as_bytes())?;
+        if let Some(secret) = secret {
+            create(&secret_path(path), true)?.write_all(secret.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn format(&self, secret: bool) -> io::Result<String> {
+        let mut out = String::new();
+
+        out.push_str("#   ****  Generated by libzmq-rs  ****\n");
+        if secret {
+            out.push_str("#   ZeroMQ CURVE **Secret** Certificate\n");
+            out.push_str(
+                "#   DO NOT PROVIDE THIS FILE TO OTHER USERS nor change its \
+                 permissions.\n",
+            );
+        } else {
+            out.push_str("#   ZeroMQ CURVE Public Certificate\n");
+            out.push_str(
+                "#   Exchange securely, or use a secure mechanism to verify \
+                 the contents\n",
+            );
+            out.push_str(
+                "#   of this file after exchange. Store public certificates \
+                 in your home\n",
+            );
+            out.push_str("#   directory, in the .curve subdirectory.\n");
+        }
+        out.push('\n');
+
+        out.push_str("metadata\n");
+        for (name, value) in &self.metadata {
+            push_entry(&mut out, name, value)?;
+        }
+
+        out.push_str("curve\n");
+        if let Some(public) = &self.public {
+            push_entry(&mut out, "public-key", public)?;
+        }
+        if secret {
+            if let Some(secret) = &self.secret {
+                push_entry(&mut out, "secret-key", secret)?;
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+pub(crate) fn secret_path(path: &Path) -> PathBuf {
+    let mut path: OsString = path.as_os_str().to_owned();
+    path.push(SECRET_SUFFIX);
+    path.into()
+}
+
+fn create(path: &Path, secret: bool) -> io::Result<fs::File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        if secret {
+            options.mode(0o600);
+        }
+    }
+
+    let file = options.open(path)?;
+
+    // The mode only applies to newly created files.
+    #[cfg(unix)]
+    {
+        if secret {
+            file.set_permissions(Permissions::from_mode(0o600))?;
+        }
+    }
+
+    Ok(file)
+}
+
+fn invalid_data(line: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line + 1, msg),
+    )
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "$-_@.&+/".contains(c))
+}
+
+// Strips a trailing comment.
+fn strip_comment(text: &str) -> &str {
+    match text.find('#') {
+        Some(pos) => text[..pos].trim_end(),
+        None => text.trim_end(),
+    }
+}
+
+fn parse_value(text: &str) -> Result<String, &'static str> {
+    let quote = match text.chars().next() {
+        Some(c @ '"') | Some(c @ '\'') => c,
+        _ => return Ok(strip_comment(text).to_owned()),
+    };
+
+    let end = text[1..].find(quote).ok_or("unterminated quoted value")? + 1;
+    let rest = text[end + 1..].trim_start();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err("unexpected text after quoted value");
+    }
+
+    Ok(text[1..end].to_owned())
+}
+
+// Flattens a ZPL document into `(path, value)` pairs in order of appearance,
+// where the path is made of the `/` separated names of the enclosing sections
+// followed by the name of the entry, such as `curve/public-key`.
+//
+// Blank lines and comments are ignored and the nesting is determined by the
+// indentation.
+fn parse(text: &str) -> io::Result<Vec<(String, String)>> {
+    let mut entries = vec![];
+    // The indentation and name of the enclosing sections.
+    let mut sections: Vec<(usize, &str)> = vec![];
+
+    for (index, line) in text.lines().enumerate() {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+
+        let indent = line.len() - content.len();
+        while sections.last().is_some_and(|(i, _)| *i >= indent) {
+            sections.pop();
+        }
+
+        // An `=` within a trailing comment does not start a value.
+        let assign = content
+            .find('=')
+            .filter(|pos| !content[..*pos].contains('#'));
+        let (name, value) = match assign {
+            Some(pos) => {
+                let value = parse_value(content[pos + 1..].trim_start())
+                    .map_err(|msg| invalid_data(index, msg))?;
+                (content[..pos].trim_end(), Some(value))
+            }
+            None => (strip_comment(content), None),
+        };
+
+        if !is_name(name) {
+            return Err(invalid_data(index, "invalid name"));
+        }
+
+        if let Some(value) = value {
+            let mut path = String::new();
+            for (_, section) in &sections {
+                path.push_str(section);
+                path.push('/');
+            }
+            path.push_str(name);
+            entries.push((path, value));
+        }
+
+        sections.push((indent, name));
+    }
+
+    Ok(entries)
+}
+
+fn push_entry(out: &mut String, name: &str, value: &str) -> io::Result<()> {
+    if !is_name(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid metadata name `{}`", name),
+        ));
+    }
+
+    let quote = if !value.contains('"') {
+        '"'
+    } else if !value.contains('\'') {
+        '\''
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "value cannot contain both single and double quotes",
+        ));
+    };
+
+    if value.contains(['\n', '\r']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "value cannot contain a line break",
+        ));
+    }
+
+    out.push_str(&format!("    {} = {}{}{}\n", name, quote, value, quote));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_comments_and_whitespace() {
+        let text = "\
+# A comment
+   # An indented comment
+
+metadata   # trailing = comment
+\tname = \"some # value\"   # comment
+    quote = 'say \"hi\"'
+    bare = unquoted   # comment
+curve
+    public-key = \"key\"
+";
+        let entries = parse(text).unwrap();
+        let expected = vec![
+            ("metadata/name", "some # value"),
+            ("metadata/quote", "say \"hi\""),
+            ("metadata/bare", "unquoted"),
+            ("curve/public-key", "key"),
+        ];
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|(p, v)| (p.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for text in &["curve\n    key = \"open", "bad name = 1", "x = 'a' b"] {
+            let err = parse(text).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,520 @@
+//! Bridges between sockets and Rust channels.
+//!
+//! A bridge runs a thread that pumps the messages from a socket into a
+//! channel, or from a channel into a socket, so that channel based code
+//! can interact with sockets without polling them.
+//!
+//! The channels are abstracted by the [`MsgSink`] and [`MsgSource`]
+//! traits, which are implemented for the `std::sync::mpsc` channels. Other
+//! channels, such as the `crossbeam` or `flume` ones, only require a small
+//! adapter.
+//!
+//! [`MsgSink`]: trait.MsgSink.html
+//! [`MsgSource`]: trait.MsgSource.html
+
+use crate::{error::*, poll::*, prelude::*, Msg};
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{
+            self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError,
+        },
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+// The interval at which a send bridge checks whether it was stopped while
+// waiting for a message from its source.
+const STOP_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The channel side of a receive bridge.
+///
+/// # Example
+/// An adapter for a hypothetical channel.
+/// ```
+/// use libzmq::{bridge::MsgSink, Msg};
+/// use std::sync::{mpsc::TrySendError, Arc, Mutex};
+///
+/// struct Collector(Arc<Mutex<Vec<Msg>>>);
+///
+/// impl MsgSink for Collector {
+///     fn send(&mut self, msg: Msg) -> Result<(), Msg> {
+///         self.0.lock().unwrap().push(msg);
+///         Ok(())
+///     }
+///
+///     fn try_send(&mut self, msg: Msg) -> Result<(), TrySendError<Msg>> {
+///         self.send(msg).map_err(TrySendError::Disconnected)
+///     }
+/// }
+/// ```
+pub trait MsgSink: Send + 'static {
+    /// Sends the message, blocking while the channel is full.
+    ///
+    /// Returns the message if the channel is disconnected.
+    fn send(&mut self, msg: Msg) -> Result<(), Msg>;
+
+    /// Sends the message without blocking.
+    fn try_send(&mut self, msg: Msg) -> Result<(), TrySendError<Msg>>;
+}
+
+impl MsgSink for Sender<Msg> {
+    fn send(&mut self, msg: Msg) -> Result<(), Msg> {
+        Sender::send(self, msg).map_err(|err| err.0)
+    }
+
+    fn try_send(&mut self, msg: Msg) -> Result<(), TrySendError<Msg>> {
+        // An unbounded channel is never full.
+        Sender::send(self, msg).map_err(|err| TrySendError::Disconnected(err.0))
+    }
+}
+
+impl MsgSink for SyncSender<Msg> {
+    fn send(&mut self, msg: Msg) -> Result<(), Msg> {
+        SyncSender::send(self, msg).map_err(|err| err.0)
+    }
+
+    fn try_send(&mut self, msg: Msg) -> Result<(), TrySendError<Msg>> {
+        SyncSender::try_send(self, msg)
+    }
+}
+
+/// The channel side of a send bridge.
+pub trait MsgSource: Send + 'static {
+    /// Waits for a message for at most the timeout.
+    fn recv_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Msg, RecvTimeoutError>;
+}
+
+impl MsgSource for Receiver<Msg> {
+    fn recv_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Msg, RecvTimeoutError> {
+        Receiver::recv_timeout(self, timeout)
+    }
+}
+
+/// What a bridge does with a message when its destination is full.
+///
+/// The destination is the channel of a receive bridge and the socket of a
+/// send bridge, which is full once it reaches its high water mark.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Backpressure {
+    /// Wait until the destination accepts the message.
+    ///
+    /// The messages then accumulate in the source, up to its own limits.
+    /// Note that a bridge blocked this way only stops once the message is
+    /// sent.
+    #[default]
+    Block,
+    /// Drop the message.
+    ///
+    /// The dropped messages are counted by [`BridgeHandle::dropped`].
+    ///
+    /// [`BridgeHandle::dropped`]: struct.BridgeHandle.html#method.dropped
+    Drop,
+}
+
+struct Shared {
+    stopped: AtomicBool,
+    dropped: AtomicU64,
+}
+
+/// A handle to a running bridge.
+///
+/// The bridge owns its socket and channel side, which are dropped once it
+/// stops. It stops when asked to, once its channel side is disconnected or
+/// on the first error.
+///
+/// Dropping the handle stops the bridge without waiting for its thread.
+pub struct BridgeHandle {
+    shared: Arc<Shared>,
+    waker: Option<Waker>,
+    thread: Option<JoinHandle<Result<(), Error>>>,
+}
+
+impl BridgeHandle {
+    /// Stops the bridge.
+    ///
+    /// The message being pumped, if any, is delivered before the bridge
+    /// stops. Use [`join`] to wait for the bridge to stop.
+    ///
+    /// [`join`]: #method.join
+    pub fn stop(&self) -> Result<(), Error> {
+        self.shared.stopped.store(true, Ordering::SeqCst);
+        match &self.waker {
+            Some(waker) => waker.wake(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the number of messages dropped because of the
+    /// [`Backpressure::Drop`] policy.
+    ///
+    /// [`Backpressure::Drop`]: enum.Backpressure.html#variant.Drop
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Waits for the bridge to stop and returns the error that stopped it,
+    /// if any.
+    pub fn join(mut self) -> Result<(), Error> {
+        let thread = self.thread.take().unwrap();
+
+        match thread.join() {
+            Ok(result) => result,
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    }
+}
+
+impl fmt::Debug for BridgeHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BridgeHandle")
+            .field("dropped", &self.dropped())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for BridgeHandle {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            let _ = self.stop();
+        }
+    }
+}
+
+/// Spawns a thread that pumps the messages received by the socket into
+/// the sink.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{bridge::*, prelude::*, *};
+/// use std::sync::mpsc;
+///
+/// let addr = InprocAddr::new_unique();
+/// let server = ServerBuilder::new().bind(&addr).build()?;
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+///
+/// let (sender, receiver) = mpsc::sync_channel::<Msg>(16);
+/// let handle = spawn_recv_bridge(server, sender, Backpressure::Block)?;
+///
+/// client.send("msg")?;
+/// let msg = receiver.recv()?;
+/// assert_eq!(msg.to_str()?, "msg");
+///
+/// handle.stop()?;
+/// handle.join()?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub fn spawn_recv_bridge<S, K>(
+    socket: S,
+    sink: K,
+    policy: Backpressure,
+) -> Result<BridgeHandle, Error>
+where
+    S: RecvMsg + Send + 'static,
+    K: MsgSink,
+{
+    let shared = new_shared();
+    let thread_shared = Arc::clone(&shared);
+    let (waker_sender, waker_receiver) = mpsc::channel();
+
+    // The poller cannot be sent to another thread, so its waker is sent
+    // back instead.
+    let thread = thread::spawn(move || {
+        let mut poller = Poller::new();
+        poller.set_retry_interrupted(true);
+        let setup = poller
+            .add(&socket, PollId(0), READABLE)
+            .and_then(|_| poller.add_waker(PollId(1)));
+
+        let waker = match setup {
+            Ok(waker) => waker,
+            Err(err) => {
+                let _ = waker_sender.send(None);
+                return Err(err);
+            }
+        };
+        let _ = waker_sender.send(Some(waker));
+
+        pump_recv(&mut poller, &socket, sink, policy, &thread_shared)
+    });
+
+    let waker = waker_receiver.recv().unwrap_or(None);
+
+    Ok(BridgeHandle {
+        shared,
+        waker,
+        thread: Some(thread),
+    })
+}
+
+fn new_shared() -> Arc<Shared> {
+    Arc::new(Shared {
+        stopped: AtomicBool::new(false),
+        dropped: AtomicU64::new(0),
+    })
+}
+
+fn pump_recv<S, K>(
+    poller: &mut Poller,
+    socket: &S,
+    mut sink: K,
+    policy: Backpressure,
+    shared: &Shared,
+) -> Result<(), Error>
+where
+    S: RecvMsg,
+    K: MsgSink,
+{
+    let mut events = Events::new();
+
+    while !shared.stopped.load(Ordering::SeqCst) {
+        poller.block(&mut events, None)?;
+
+        let msg = match socket.try_recv_msg() {
+            Ok(msg) => msg,
+            Err(err) => match err.kind() {
+                // Either a spurious or a waker event.
+                ErrorKind::WouldBlock | ErrorKind::Interrupted => continue,
+                _ => return Err(err),
+            },
+        };
+
+        let disconnected = match policy {
+            Backpressure::Block => sink.send(msg).is_err(),
+            Backpressure::Drop => match sink.try_send(msg) {
+                Ok(()) => false,
+                Err(TrySendError::Full(_)) => {
+                    shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => true,
+            },
+        };
+
+        if disconnected {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Spawns a thread that pumps the messages of the source into the socket.
+///
+/// Since the source cannot be polled along with the stop signal, the
+/// bridge checks whether it was stopped every few milliseconds.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{bridge::*, prelude::*, *};
+/// use std::sync::mpsc;
+///
+/// let addr = InprocAddr::new_unique();
+/// let server = ServerBuilder::new().bind(&addr).build()?;
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+///
+/// let (sender, receiver) = mpsc::channel::<Msg>();
+/// let handle = spawn_send_bridge(receiver, client, Backpressure::Block)?;
+///
+/// sender.send("msg".into())?;
+/// let msg = server.recv_msg()?;
+/// assert_eq!(msg.to_str()?, "msg");
+///
+/// handle.stop()?;
+/// handle.join()?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub fn spawn_send_bridge<R, S>(
+    source: R,
+    socket: S,
+    policy: Backpressure,
+) -> Result<BridgeHandle, Error>
+where
+    R: MsgSource,
+    S: SendMsg + Send + 'static,
+{
+    let shared = new_shared();
+    let thread_shared = Arc::clone(&shared);
+
+    let thread = thread::spawn(move || {
+        pump_send(source, &socket, policy, &thread_shared)
+    });
+
+    Ok(BridgeHandle {
+        shared,
+        waker: None,
+        thread: Some(thread),
+    })
+}
+
+fn pump_send<R, S>(
+    mut source: R,
+    socket: &S,
+    policy: Backpressure,
+    shared: &Shared,
+) -> Result<(), Error>
+where
+    R: MsgSource,
+    S: SendMsg,
+{
+    while !shared.stopped.load(Ordering::SeqCst) {
+        let msg = match source.recv_timeout(STOP_INTERVAL) {
+            Ok(msg) => msg,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let result = match policy {
+            Backpressure::Block => socket.send(msg),
+            Backpressure::Drop => socket.try_send(msg),
+        };
+
+        if let Err(err) = result {
+            match err.kind() {
+                ErrorKind::WouldBlock if policy == Backpressure::Drop => {
+                    shared.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                _ => return Err(err.cast()),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_recv_bridge() {
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let (sender, receiver) = mpsc::sync_channel::<Msg>(1);
+        let handle =
+            spawn_recv_bridge(server, sender, Backpressure::Block).unwrap();
+
+        for i in 0..100 {
+            client.send(i.to_string()).unwrap();
+        }
+        for i in 0..100 {
+            let msg = receiver.recv().unwrap();
+            assert_eq!(msg.to_str().unwrap(), i.to_string());
+        }
+
+        handle.stop().unwrap();
+        handle.join().unwrap();
+        // The sink was dropped along with the bridge.
+        assert!(receiver.recv().is_err());
+    }
+
+    #[test]
+    fn test_recv_bridge_drop() {
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let (sender, receiver) = mpsc::sync_channel::<Msg>(1);
+        let handle =
+            spawn_recv_bridge(server, sender, Backpressure::Drop).unwrap();
+
+        let count = 100;
+        for _ in 0..count {
+            client.send("").unwrap();
+        }
+        // Only the receiver side stops the bridge here.
+        while handle.dropped() + 1 < count {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(receiver.recv().is_ok());
+        drop(receiver);
+
+        // The bridge stops once it notices the disconnection.
+        client.send("").unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_send_bridge() {
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let (sender, receiver) = mpsc::channel::<Msg>();
+        let handle =
+            spawn_send_bridge(receiver, client, Backpressure::Block).unwrap();
+
+        for i in 0..100 {
+            sender.send(i.to_string().into()).unwrap();
+        }
+        for i in 0..100 {
+            let msg = server.recv_msg().unwrap();
+            assert_eq!(msg.to_str().unwrap(), i.to_string());
+        }
+
+        // The bridge stops once the source is disconnected.
+        drop(sender);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_bridge_stop_in_flight() {
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let (sender, receiver) = mpsc::channel::<Msg>();
+        let handle =
+            spawn_send_bridge(receiver, client, Backpressure::Block).unwrap();
+
+        let count = 1000;
+        for i in 0..count {
+            sender.send(i.to_string().into()).unwrap();
+        }
+        handle.stop().unwrap();
+        handle.join().unwrap();
+
+        // The messages are delivered in order, none being delivered
+        // partially nor twice.
+        let mut received = 0;
+        while let Ok(msg) = server.recv_msg() {
+            assert_eq!(msg.to_str().unwrap(), received.to_string());
+            received += 1;
+        }
+        assert!(received <= count);
+
+        // The source was dropped along with the bridge.
+        assert!(sender.send(Msg::new()).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{error::*, Msg};
+
+// The header bytes identifying the codec of a message. They can never
+// start a UTF-8 string, so that text payloads are never mistaken for
+// compressed ones.
+const NONE: u8 = 0xFD;
+const LZ4: u8 = 0xFE;
+const ZSTD: u8 = 0xFF;
+
+/// The maximum size of a payload decompressed by [`recv_decompressed`]
+/// when the socket has no `max_msg_size`.
+///
+/// This requires the `compression` feature.
+///
+/// [`recv_decompressed`]: prelude/trait.RecvMsg.html#method.recv_decompressed
+pub const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+/// The codec used to compress a message via [`send_compressed`].
+///
+/// This requires the `compression` feature.
+///
+/// A compressed message is prefixed by a header byte that identifies its
+/// codec, which [`recv_decompressed`] uses to decompress it. The header
+/// bytes are never valid at the start of UTF-8, so that the text messages
+/// of uncompressed peers, such as JSON, are received as is. However, a
+/// binary message starting with `0xFD`, `0xFE` or `0xFF` would be
+/// mistaken for a compressed one.
+///
+/// [`send_compressed`]: prelude/trait.SendMsg.html#method.send_compressed
+/// [`recv_decompressed`]: prelude/trait.RecvMsg.html#method.recv_decompressed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Codec {
+    /// The payload is sent raw, with the header.
+    None,
+    /// The payload is compressed with LZ4.
+    Lz4,
+    /// The payload is compressed with Zstandard at the specified level.
+    ///
+    /// The levels range from 1 to 22, where 0 means the default level.
+    Zstd(i32),
+}
+
+// Copies the properties used for routing.
+fn copy_routing(from: &Msg, to: &mut Msg) {
+    if let Some(id) = from.routing_id() {
+        to.set_routing_id(id);
+    }
+    if let Some(group) = from.group() {
+        to.set_group(group);
+    }
+}
+
+// Prefixes the payload with the header.
+fn with_header(header: u8, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(payload.len() + 1);
+    bytes.push(header);
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+pub(crate) fn compress(msg: Msg, codec: Codec) -> Msg {
+    if msg.is_empty() {
+        return msg;
+    }
+
+    let payload = msg.as_bytes();
+    let compressed = match codec {
+        Codec::None => None,
+        Codec::Lz4 => {
+            let compressed = lz4_flex::compress_prepend_size(payload);
+            Some(with_header(LZ4, &compressed))
+        }
+        Codec::Zstd(level) => zstd::bulk::compress(payload, level)
+            .ok()
+            .map(|compressed| with_header(ZSTD, &compressed)),
+    };
+
+    // The payloads that grew are sent raw.
+    let bytes = match compressed {
+        Some(bytes) if bytes.len() <= payload.len() => bytes,
+        _ => with_header(NONE, payload),
+    };
+
+    let mut compressed: Msg = bytes.into();
+    copy_routing(&msg, &mut compressed);
+    compressed
+}
+
+fn invalid(msg: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidMessage { msg })
+}
+
+// Decompresses a LZ4 payload prefixed by its size, which is checked before
+// allocating since it is chosen by the peer.
+fn decompress_lz4(payload: &[u8], max_size: usize) -> Result<Vec<u8>, Error> {
+    let size = match payload {
+        [a, b, c, d, ..] => u32::from_le_bytes([*a, *b, *c, *d]) as usize,
+        _ => return Err(invalid("invalid lz4 payload")),
+    };
+    if size > max_size {
+        return Err(invalid("decompressed payload is too large"));
+    }
+
+    lz4_flex::decompress_size_prepended(payload)
+        .map_err(|_| invalid("invalid lz4 payload"))
+}
+
+fn decompress_zstd(payload: &[u8], max_size: usize) -> Result<Vec<u8>, Error> {
+    // Fails if the output would exceed `max_size`, whatever content size
+    // the frame declares.
+    zstd::bulk::decompress(payload, max_size)
+        .map_err(|_| invalid("invalid or too large zstd payload"))
+}
+
+// Payloads decompressing to more than `max_size` bytes are rejected.
+pub(crate) fn decompress(msg: Msg, max_size: usize) -> Result<Msg, Error> {
+    let bytes = msg.as_bytes();
+    let payload = match bytes.first() {
+        Some(&NONE) => bytes[1..].to_vec(),
+        Some(&LZ4) => decompress_lz4(&bytes[1..], max_size)?,
+        Some(&ZSTD) => decompress_zstd(&bytes[1..], max_size)?,
+        // The message of an uncompressed peer.
+        _ => return Ok(msg),
+    };
+
+    let mut decompressed: Msg = payload.into();
+    copy_routing(&msg, &mut decompressed);
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, test_util::*, *};
+
+    const JSON: &str = concat!(
+        r#"{"values": [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, "#,
+        r#"1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, "#,
+        r#"1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]}"#,
+    );
+
+    #[test]
+    fn test_codecs_round_trip() {
+        let (client, server) = client_server_pair();
+
+        for &codec in &[Codec::None, Codec::Lz4, Codec::Zstd(0)] {
+            client.send_compressed(JSON, codec).unwrap();
+
+            let raw = server.recv_msg().unwrap();
+            let header = raw.as_bytes()[0];
+            match codec {
+                Codec::None => assert_eq!(header, NONE),
+                Codec::Lz4 => assert_eq!(header, LZ4),
+                Codec::Zstd(_) => assert_eq!(header, ZSTD),
+            }
+
+            // The routing id is preserved, so that the reply is routed.
+            let request = decompress(raw, MAX_DECOMPRESSED_SIZE).unwrap();
+            assert_eq!(request.to_str().unwrap(), JSON);
+            server.send_compressed(request, codec).unwrap();
+
+            let msg = client.recv_decompressed().unwrap();
+            assert_eq!(msg.to_str().unwrap(), JSON);
+        }
+    }
+
+    #[test]
+    fn test_mixed_conversation() {
+        let (client, server) = client_server_pair();
+
+        client.send_compressed(JSON, Codec::Zstd(3)).unwrap();
+        client.send(JSON).unwrap();
+        client.send_compressed("", Codec::Lz4).unwrap();
+
+        let msg = server.recv_decompressed().unwrap();
+        assert_eq!(msg.to_str().unwrap(), JSON);
+        let id = msg.routing_id().unwrap();
+        let msg = server.recv_decompressed().unwrap();
+        assert_eq!(msg.to_str().unwrap(), JSON);
+        // Empty messages are sent as is.
+        let msg = server.recv_msg().unwrap();
+        assert!(msg.is_empty());
+
+        let mut reply: Msg = "ok".into();
+        reply.set_routing_id(id);
+        server.send(reply).unwrap();
+        let msg = client.recv_decompressed().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ok");
+    }
+
+    #[test]
+    fn test_incompressible_payload_sent_raw() {
+        let (client, server) = client_server_pair();
+
+        client.send_compressed("a", Codec::Lz4).unwrap();
+        let raw = server.recv_msg().unwrap();
+        assert_eq!(raw.as_bytes(), &[NONE, b'a']);
+
+        let msg = decompress(raw, MAX_DECOMPRESSED_SIZE).unwrap();
+        assert_eq!(msg.to_str().unwrap(), "a");
+    }
+
+    #[test]
+    fn test_group_preserved() {
+        let (radio, dish) = radio_dish_pair(&["group"]);
+
+        let mut msg: Msg = JSON.into();
+        msg.set_group(group!("group"));
+        radio.send_compressed(msg, Codec::Lz4).unwrap();
+
+        let msg = dish.recv_decompressed().unwrap();
+        assert_eq!(msg.group().unwrap(), "group");
+        assert_eq!(msg.to_str().unwrap(), JSON);
+    }
+
+    #[test]
+    fn test_forged_lz4_size() {
+        // Claims to decompress to 4 GiB.
+        let msg: Msg = vec![LZ4, 0xFF, 0xFF, 0xFF, 0xFF, 0].into();
+        let err = decompress(msg, MAX_DECOMPRESSED_SIZE).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::InvalidMessage {
+                msg: "decompressed payload is too large"
+            }
+        );
+    }
+
+    #[test]
+    fn test_max_decompressed_size() {
+        let payload = vec![0; 1024];
+
+        for &codec in &[Codec::Lz4, Codec::Zstd(0)] {
+            let msg = compress(payload.clone().into(), codec);
+            let err = decompress(msg.clone(), 1023).unwrap_err();
+            match err.kind() {
+                ErrorKind::InvalidMessage { .. } => (),
+                _ => panic!("unexpected error"),
+            }
+
+            let msg = decompress(msg, 1024).unwrap();
+            assert_eq!(msg.as_bytes(), payload.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_max_msg_size_limit() {
+        let (client, server) = client_server_pair();
+        server.set_max_msg_size(Some(1024)).unwrap();
+
+        // The compressed message fits, but not its payload.
+        client
+            .send_compressed(vec![0; 2048], Codec::Zstd(0))
+            .unwrap();
+        let err = server.recv_decompressed().unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidMessage { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_payload() {
+        let msg: Msg = vec![ZSTD, 1, 2, 3].into();
+        let err = decompress(msg, MAX_DECOMPRESSED_SIZE).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidMessage { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+}
@@ -1,14 +1,27 @@
 //! The set of core ØMQ socket traits.
 
+// Logs a socket lifecycle event under the `libzmq::socket` target if the
+// `verbose-log` feature is enabled, or else compiles to nothing.
+macro_rules! socket_log {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "verbose-log")]
+        log::$level!(target: "libzmq::socket", $($arg)+);
+    }};
+}
+
 mod raw;
 mod recv;
 mod send;
 pub(crate) mod sockopt;
+#[cfg(feature = "stats")]
+mod stats;
 
 pub(crate) use raw::*;
 
 pub use recv::*;
 pub use send::*;
+#[cfg(feature = "stats")]
+pub use stats::SocketStats;
 
 /// Prevent users from implementing the AsRawSocket trait.
 mod private {
@@ -22,6 +35,8 @@ mod private {
     impl Sealed for Client {}
     impl Sealed for ClientConfig {}
     impl Sealed for ClientBuilder {}
+    impl Sealed for SendHalf {}
+    impl Sealed for RecvHalf {}
     impl Sealed for Server {}
     impl Sealed for ServerConfig {}
     impl Sealed for ServerBuilder {}
@@ -42,14 +57,27 @@ mod private {
     // Pub crate
     use crate::old::OldSocket;
     impl Sealed for OldSocket {}
+    impl Sealed for std::sync::Arc<RawSocket> {}
 }
 
-use crate::{addr::Endpoint, auth::*, error::Error};
+use crate::{
+    addr::Endpoint,
+    auth::*,
+    error::*,
+    has,
+    poll::{self, Flags},
+    Capability,
+};
 
+use bitflags::bitflags;
 use humantime_serde::Serde;
 use serde::{Deserialize, Serialize};
 
-use std::{sync::MutexGuard, time::Duration};
+use std::{
+    os::raw::c_int,
+    sync::MutexGuard,
+    time::{Duration, Instant},
+};
 
 /// Represents a period of time.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -151,6 +179,108 @@ impl From<Option<i32>> for Quantity {
     }
 }
 
+// The time from now of the deadline of a `Duration` too large to be added
+// to an `Instant`, which is about a century.
+const FAR_FUTURE: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// A point in time by which an operation must complete.
+///
+/// Unlike a timeout, a `Deadline` can be shared by a sequence of operations
+/// that must complete within the same time budget, since each operation
+/// only waits for the time that remains. It can be built from either an
+/// `Instant` or a `Duration` from now.
+///
+/// Operations given a deadline that already passed still make a single
+/// non-blocking attempt, then fail with [`WouldBlock`].
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, *};
+/// use std::time::Duration;
+///
+/// let addr = InprocAddr::new_unique();
+/// let server = ServerBuilder::new().bind(&addr).build()?;
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+///
+/// // The whole exchange must complete within a second.
+/// let deadline = Deadline::after(Duration::from_secs(1));
+///
+/// client.send_deadline("ping", deadline)?;
+/// let (id, _) = server.recv_from()?;
+/// let mut reply: Msg = "pong".into();
+/// reply.set_routing_id(id);
+/// server.send(reply)?;
+/// let msg = client.recv_deadline(deadline)?;
+/// assert_eq!(msg.to_str()?, "pong");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Deadline {
+    instant: Instant,
+}
+
+impl Deadline {
+    /// Create a `Deadline` at the given instant.
+    pub fn new(instant: Instant) -> Self {
+        Self { instant }
+    }
+
+    /// Create a `Deadline` that passes once `duration` has elapsed.
+    ///
+    /// If the resulting instant cannot be represented, such as for
+    /// `Duration::MAX`, the deadline saturates to about a century from now,
+    /// which in practice never passes.
+    pub fn after(duration: Duration) -> Self {
+        let now = Instant::now();
+        let instant = now
+            .checked_add(duration)
+            .unwrap_or_else(|| now + FAR_FUTURE);
+
+        Self::new(instant)
+    }
+
+    /// Returns the instant of the deadline.
+    pub fn instant(&self) -> Instant {
+        self.instant
+    }
+
+    /// Returns the time left until the deadline, which is zero if it
+    /// already passed.
+    pub fn remaining(&self) -> Duration {
+        self.instant
+            .checked_duration_since(Instant::now())
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+
+    /// Returns `true` if the deadline passed.
+    pub fn has_passed(&self) -> bool {
+        Instant::now() >= self.instant
+    }
+}
+
+impl From<Instant> for Deadline {
+    fn from(instant: Instant) -> Self {
+        Self::new(instant)
+    }
+}
+
+/// Same as [`Deadline::after`], which saturates the durations that are too
+/// large.
+///
+/// [`Deadline::after`]: struct.Deadline.html#method.after
+impl From<Duration> for Deadline {
+    fn from(duration: Duration) -> Self {
+        Self::after(duration)
+    }
+}
+
 /// Socket heartbeating configuration.
 ///
 /// # Example
@@ -164,6 +294,7 @@ impl From<Option<i32>> for Quantity {
 ///     .add_timeout(2 * duration);
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Heartbeat {
     #[serde(with = "humantime_serde")]
     pub(crate) interval: Duration,
@@ -232,7 +363,72 @@ impl<'a> From<&'a Heartbeat> for Heartbeat {
     }
 }
 
+/// A snapshot of the readiness of a socket.
+///
+/// This `struct` is created by the [`readiness`] method on [`Socket`].
+///
+/// [`readiness`]: trait.Socket.html#method.readiness
+/// [`Socket`]: trait.Socket.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Readiness {
+    flags: Flags,
+}
+
+impl Readiness {
+    /// Returns `true` if at least one message can be received from the
+    /// socket without blocking.
+    pub fn is_readable(self) -> bool {
+        self.flags.contains(Flags::READABLE)
+    }
+
+    /// Returns `true` if at least one message can be sent to the socket
+    /// without blocking.
+    pub fn is_writable(self) -> bool {
+        self.flags.contains(Flags::WRITABLE)
+    }
+
+    /// Returns the readiness as poll flags.
+    pub fn flags(self) -> Flags {
+        self.flags
+    }
+}
+
+bitflags! {
+    /// The conditions under which a socket stops reconnecting to an
+    /// endpoint.
+    ///
+    /// Once the reconnection stopped, the socket never connects to the
+    /// endpoint again, so it should be rebuilt by the application. Note
+    /// that this crate exposes no monitor to be notified of it, but
+    /// [`Client::wait_connected`] times out in this case.
+    ///
+    /// This requires *libzmq* 4.3.3, while the vendored version is 4.3.2.
+    /// With older versions, setting the option fails with [`Unsupported`],
+    /// and so does building a socket whose builder or config specifies it,
+    /// at the `reconnect_stop` [`BuildStep`].
+    ///
+    /// [`Client::wait_connected`]: struct.Client.html#method.wait_connected
+    /// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+    /// [`BuildStep`]: enum.BuildStep.html
+    #[derive(Default, Serialize, Deserialize)]
+    pub struct ReconnectStop: c_int {
+        /// Stops when the connection is refused by the peer.
+        const CONN_REFUSED = 0b001;
+        /// Stops when the handshake with the peer fails.
+        const HANDSHAKE_FAILED = 0b010;
+        /// Stops once the socket was disconnected from the endpoint,
+        /// instead of reconnecting to it.
+        const AFTER_DISCONNECT = 0b100;
+    }
+}
+
 /// Methods shared by all thread-safe sockets.
+///
+/// All thread-safe sockets can be cloned, which returns another handle
+/// to the same underlying ØMQ socket. This allows to share a socket between
+/// threads without wrapping it in an `Arc`. The ØMQ socket is closed only
+/// once the last handle is dropped. Since the handles share the same socket,
+/// options set through one handle apply to all of them.
 pub trait Socket: GetRawSocket {
     /// Schedules a connection to one or more [`Endpoints`] and then accepts
     /// incoming connections.
@@ -253,14 +449,18 @@ pub trait Socket: GetRawSocket {
     ///
     /// # Returned Errors
     /// * [`InvalidInput`] (invalid endpoint)
-    /// * [`IncompatTransport`] (transport not supported)
+    /// * [`Unsupported`] (transport not supported)
     /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`NoIoThread`]
     ///
     /// [`Endpoints`]: ../endpoint/enum.Endpoint.html
     /// [`zmq_connect`]: http://api.zeromq.org/master:zmq-connect
     /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
-    /// [`IncompatTransport`]: ../enum.ErrorKind.html#variant.IncompatTransport
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
     /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`NoIoThread`]: ../enum.ErrorKind.html#variant.NoIoThread
     fn connect<I, E>(&self, endpoints: I) -> Result<(), Error<usize>>
     where
         I: IntoIterator<Item = E>,
@@ -272,7 +472,7 @@ pub trait Socket: GetRawSocket {
         for endpoint in endpoints.into_iter().map(E::into) {
             raw_socket
                 .connect(&endpoint)
-                .map_err(|err| Error::with_content(err.kind(), count))?;
+                .map_err(|err| err.cast_with(count))?;
 
             count += 1;
         }
@@ -318,7 +518,7 @@ pub trait Socket: GetRawSocket {
         for endpoint in endpoints.into_iter().map(E::into) {
             raw_socket
                 .disconnect(&endpoint)
-                .map_err(|err| Error::with_content(err.kind(), count))?;
+                .map_err(|err| err.cast_with(count))?;
 
             count += 1;
         }
@@ -343,21 +543,27 @@ pub trait Socket: GetRawSocket {
     /// * The transport must be supported by socket type.
     /// * The endpoint must not be in use.
     /// * The endpoint must be local.
+    /// * A `TcpAddr` must not have a source address.
+    /// * A `TcpAddr` with a hostname must name a network interface.
     ///
     /// # Returned Errors
     /// * [`InvalidInput`] (invalid endpoint)
-    /// * [`IncompatTransport`] (transport is not supported)
+    /// * [`Unsupported`] (transport is not supported)
     /// * [`AddrInUse`] (addr already in use)
     /// * [`AddrNotAvailable`] (not local)
     /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`NoIoThread`]
     ///
     /// [`Endpoints`]: ../endpoint/enum.Endpoint.html
     /// [`zmq_bind`]: http://api.zeromq.org/master:zmq-bind
     /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
-    /// [`IncompatTransport`]: ../enum.ErrorKind.html#variant.IncompatTransport
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
     /// [`AddrInUse`]: ../enum.ErrorKind.html#variant.AddrInUse
     /// [`AddrNotAvailable`]: ../enum.ErrorKind.html#variant.AddrNotAvailable
     /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`NoIoThread`]: ../enum.ErrorKind.html#variant.NoIoThread
     fn bind<I, E>(&self, endpoints: I) -> Result<(), Error<usize>>
     where
         I: IntoIterator<Item = E>,
@@ -369,7 +575,7 @@ pub trait Socket: GetRawSocket {
         for endpoint in endpoints.into_iter().map(E::into) {
             raw_socket
                 .bind(&endpoint)
-                .map_err(|err| Error::with_content(err.kind(), count))?;
+                .map_err(|err| err.cast_with(count))?;
 
             count += 1;
         }
@@ -418,7 +624,7 @@ pub trait Socket: GetRawSocket {
         for endpoint in endpoints.into_iter().map(E::into) {
             raw_socket
                 .unbind(&endpoint)
-                .map_err(|err| Error::with_content(err.kind(), count))?;
+                .map_err(|err| err.cast_with(count))?;
 
             count += 1;
         }
@@ -432,9 +638,9 @@ pub trait Socket: GetRawSocket {
     ///
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{prelude::*, Server, TcpAddr, addr::Endpoint};
     /// use std::convert::TryInto;
     ///
@@ -462,6 +668,88 @@ pub trait Socket: GetRawSocket {
         self.raw_socket().last_endpoint()
     }
 
+    /// Returns the current readiness of the socket.
+    ///
+    /// This allows to check whether a message can be sent or received
+    /// without blocking, without actually doing it or constructing
+    /// a `Poller`.
+    ///
+    /// The result is only a snapshot. Since the socket is thread-safe,
+    /// another thread could send or receive a message right after the
+    /// call, which makes the readiness purely advisory.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, Server};
+    ///
+    /// let server = Server::new()?;
+    /// let readiness = server.readiness()?;
+    /// assert!(!readiness.is_readable());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    fn readiness(&self) -> Result<Readiness, Error> {
+        let flags = self.raw_socket().events()?;
+        Ok(Readiness { flags })
+    }
+
+    /// Blocks until a message can be received from the socket or the
+    /// `timeout` expires, in which case `false` is returned.
+    ///
+    /// A `timeout` of `None` blocks indefinitely. This is a shorthand for
+    /// waiting on a [`Poller`] with a single socket registered. The poller
+    /// is reused across calls made by the same thread.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`InvalidInput`] (if the timeout is too large)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::time::Duration;
+    ///
+    /// let addr = InprocAddr::new_unique();
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// let timeout = Some(Duration::from_millis(10));
+    /// assert!(!server.wait_readable(timeout)?);
+    ///
+    /// client.send("")?;
+    /// assert!(server.wait_readable(None)?);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Poller`]: ../poll/struct.Poller.html
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn wait_readable(&self, timeout: Option<Duration>) -> Result<bool, Error> {
+        poll::wait_single(self.raw_socket(), poll::READABLE, timeout)
+    }
+
+    /// Blocks until a message can be sent to the socket or the `timeout`
+    /// expires, in which case `false` is returned.
+    ///
+    /// This is the counterpart of [`wait_readable`] and has the same
+    /// error variants.
+    ///
+    /// [`wait_readable`]: #method.wait_readable
+    fn wait_writable(&self, timeout: Option<Duration>) -> Result<bool, Error> {
+        poll::wait_single(self.raw_socket(), poll::WRITABLE, timeout)
+    }
+
     /// Returns the linger period for the socket shutdown.
     fn linger(&self) -> Result<Period, Error> {
         self.raw_socket().linger()
@@ -489,9 +777,9 @@ pub trait Socket: GetRawSocket {
     ///
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{prelude::*, Server, auth::Mechanism};
     ///
     /// let server = Server::new()?;
@@ -509,9 +797,9 @@ pub trait Socket: GetRawSocket {
     /// Set the socket's [`Mechanism`].
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{prelude::*, Client, auth::*};
     ///
     /// let client = Client::new()?;
@@ -547,152 +835,810 @@ pub trait Socket: GetRawSocket {
         set_mechanism(raw_socket, mechanism, mutex)
     }
 
-    /// Returns a the socket's heartbeat configuration.
-    fn heartbeat(&self) -> Option<Heartbeat> {
-        self.raw_socket().heartbeat().lock().unwrap().to_owned()
-    }
-
-    /// Set the socket's heartbeat configuration.
-    ///
-    /// Only applies to connection based transports such as `TCP`.
-    /// A value of `None` means no heartbeating.
-    ///
-    /// # Contract
-    /// * timeout and interval duration in ms cannot exceed i32::MAX
-    /// * ttl duration in ms cannot exceed 6553599
+    /// Returns the socket's ZAP domain.
     ///
-    /// # Default value
-    /// `None`
-    ///
-    /// # Return Errors
-    /// * [InvalidInput`]: (if contract no respected)
+    /// # Default Value
+    /// `"global"`
     ///
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
-    /// use libzmq::{prelude::*, Client, Heartbeat, auth::*};
-    /// use std::time::Duration;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, Server};
     ///
-    /// let client = Client::new()?;
-    /// assert_eq!(client.heartbeat(), None);
-    ///
-    /// let duration = Duration::from_millis(300);
-    /// let hb = Heartbeat::new(duration)
-    ///     .add_timeout(2 * duration);
-    /// let expected = hb.clone();
+    /// let server = Server::new()?;
+    /// assert_eq!(server.zap_domain()?, "global");
     ///
-    /// client.set_heartbeat(Some(hb))?;
-    /// assert_eq!(client.heartbeat(), Some(expected));
+    /// server.set_zap_domain("internal")?;
+    /// assert_eq!(server.zap_domain()?, "internal");
     /// #
     /// #     Ok(())
     /// # }
     /// ```
-    ///
-    /// [`Mechanism`]: ../auth/enum.Mechanism.html
-    fn set_heartbeat(&self, maybe: Option<Heartbeat>) -> Result<(), Error> {
-        let raw_socket = self.raw_socket();
-        let mutex = raw_socket.heartbeat().lock().unwrap();
-
-        set_heartbeat(raw_socket, maybe, mutex)
+    fn zap_domain(&self) -> Result<String, Error> {
+        self.raw_socket().zap_domain()
     }
-}
 
-fn set_mechanism(
-    raw_socket: &RawSocket,
-    mut mechanism: Mechanism,
-    mut mutex: MutexGuard<Mechanism>,
-) -> Result<(), Error> {
-    if *mutex == mechanism {
-        return Ok(());
+    /// Sets the socket's ZAP domain.
+    ///
+    /// The domain is sent to the authentication handler of the socket's
+    /// context with each ZAP request, so that distinct security policies
+    /// can be applied to distinct sets of sockets. It only affects
+    /// subsequent connections.
+    ///
+    /// # Usage Contract
+    /// * The domain cannot be empty.
+    /// * The domain cannot exceed 254 bytes.
+    /// * The domain cannot contain a nul byte.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if contract is not followed)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn set_zap_domain(&self, domain: &str) -> Result<(), Error> {
+        self.raw_socket().set_zap_domain(domain)
     }
 
-    // Undo the previous mechanism.
-    match &*mutex {
-        Mechanism::Null => (),
-        Mechanism::PlainClient(_) => {
-            raw_socket.set_username(None)?;
-            raw_socket.set_password(None)?;
-        }
-        Mechanism::PlainServer => {
-            raw_socket.set_plain_server(false)?;
-        }
-        Mechanism::CurveClient(_) => {
-            raw_socket.set_curve_server_key(None)?;
-            raw_socket.set_curve_public_key(None)?;
-            raw_socket.set_curve_secret_key(None)?;
-        }
-        Mechanism::CurveServer(_) => {
-            raw_socket.set_curve_secret_key(None)?;
-            raw_socket.set_curve_server(false)?;
-        }
+    /// Returns `true` if the ZAP domain is enforced.
+    fn enforce_zap_domain(&self) -> Result<bool, Error> {
+        self.raw_socket().enforce_zap_domain()
     }
 
-    // Check if we need to generate a client cert.
-    let mut missing_client_cert = false;
-    if let Mechanism::CurveClient(creds) = &mechanism {
-        if creds.client.is_none() {
-            missing_client_cert = true;
-        }
+    /// Sets whether the ZAP domain is strictly enforced, as per the RFC 27.
+    ///
+    /// When enabled, connections using the `Null` mechanism are also
+    /// authenticated by the authentication handler.
+    ///
+    /// # Default Value
+    /// `true`
+    fn set_enforce_zap_domain(&self, enabled: bool) -> Result<(), Error> {
+        self.raw_socket().set_enforce_zap_domain(enabled)
     }
 
-    // Generate a client certificate if it was not supplied.
-    if missing_client_cert {
-        let cert = CurveCert::new_unique();
-        let server_key = if let Mechanism::CurveClient(creds) = mechanism {
-            creds.server
-        } else {
-            unreachable!()
-        };
+    /// Returns the maximum length of the queue of outstanding peer
+    /// connections.
+    fn backlog(&self) -> Result<i32, Error> {
+        self.raw_socket().backlog()
+    }
 
-        let creds = CurveClientCreds {
-            client: Some(cert),
-            server: server_key,
-        };
-        mechanism = Mechanism::CurveClient(creds);
+    /// Sets the maximum length of the queue of outstanding peer connections.
+    ///
+    /// This only matters for connection-oriented transports, such as `TCP`,
+    /// when a large number of peers attempt to connect simultaneously.
+    /// Connections exceeding the backlog might be refused by the OS, which
+    /// can also silently cap the value (e.g. `net.core.somaxconn` on Linux).
+    ///
+    /// Only affects subsequent calls to [`bind`].
+    ///
+    /// # Usage Contract
+    /// * The backlog must be positive.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if contract is not followed)
+    ///
+    /// # Default Value
+    /// 100
+    ///
+    /// [`bind`]: #method.bind
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn set_backlog(&self, value: i32) -> Result<(), Error> {
+        self.raw_socket().set_backlog(value)
     }
 
-    // Apply the new mechanism.
-    match &mechanism {
-        Mechanism::Null => (),
-        Mechanism::PlainClient(creds) => {
-            raw_socket.set_username(Some(&creds.username))?;
-            raw_socket.set_password(Some(&creds.password))?;
-        }
-        Mechanism::PlainServer => {
-            raw_socket.set_plain_server(true)?;
-        }
-        Mechanism::CurveClient(creds) => {
-            let server_key: BinCurveKey = (&creds.server).into();
-            raw_socket.set_curve_server_key(Some(&server_key))?;
+    /// Returns the size of the kernel transmit buffer of the socket.
+    ///
+    /// A value of `0` means that the OS default is used.
+    fn send_buffer(&self) -> Result<i32, Error> {
+        self.raw_socket().send_buffer()
+    }
 
-            // Cannot fail since we would have generated a cert.
-            let cert = creds.client.as_ref().unwrap();
-            let public_key: BinCurveKey = cert.public().into();
-            raw_socket.set_curve_public_key(Some(&public_key))?;
-            let secret_key: BinCurveKey = cert.secret().into();
-            raw_socket.set_curve_secret_key(Some(&secret_key))?;
-        }
-        Mechanism::CurveServer(creds) => {
-            let secret_key: BinCurveKey = (&creds.secret).into();
-            raw_socket.set_curve_secret_key(Some(&secret_key))?;
-            raw_socket.set_curve_server(true)?;
-        }
+    /// Sets the size in bytes of the kernel transmit buffer (`SO_SNDBUF`)
+    /// of the socket.
+    ///
+    /// A value of `0` means that the OS default is used. Note that the
+    /// kernel clamps the value to its own limits (e.g. `net.core.wmem_max`
+    /// on Linux), so the effective buffer size might differ.
+    ///
+    /// Only affects subsequent connections.
+    ///
+    /// # Usage Contract
+    /// * The size cannot be negative.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if contract is not followed)
+    ///
+    /// # Default Value
+    /// 0
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn set_send_buffer(&self, size: i32) -> Result<(), Error> {
+        self.raw_socket().set_send_buffer(size)
     }
 
-    // Update mechanism
-    *mutex = mechanism;
-    Ok(())
-}
+    /// Returns the size of the kernel receive buffer of the socket.
+    ///
+    /// A value of `0` means that the OS default is used.
+    fn recv_buffer(&self) -> Result<i32, Error> {
+        self.raw_socket().recv_buffer()
+    }
 
-fn set_heartbeat(
-    raw_socket: &RawSocket,
-    maybe: Option<Heartbeat>,
-    mut mutex: MutexGuard<Option<Heartbeat>>,
-) -> Result<(), Error> {
-    if *mutex == maybe {
-        return Ok(());
+    /// Sets the size in bytes of the kernel receive buffer (`SO_RCVBUF`)
+    /// of the socket.
+    ///
+    /// A value of `0` means that the OS default is used. Note that the
+    /// kernel clamps the value to its own limits (e.g. `net.core.rmem_max`
+    /// on Linux), so the effective buffer size might differ.
+    ///
+    /// Only affects subsequent connections.
+    ///
+    /// # Usage Contract
+    /// * The size cannot be negative.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if contract is not followed)
+    ///
+    /// # Default Value
+    /// 0
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn set_recv_buffer(&self, size: i32) -> Result<(), Error> {
+        self.raw_socket().set_recv_buffer(size)
+    }
+
+    /// Returns the maximum size of the inbound messages.
+    ///
+    /// A value of `None` means that there is no limit.
+    fn max_msg_size(&self) -> Result<Option<i64>, Error> {
+        self.raw_socket().max_msg_size()
+    }
+
+    /// Sets the maximum size of the inbound messages.
+    ///
+    /// Peers sending a message that exceeds the limit will be disconnected
+    /// by ØMQ, which never hands the message to the socket. This is only
+    /// observable via the monitor API. Note that messages coming from an
+    /// `inproc` peer are never checked.
+    ///
+    /// Only affects subsequent connections.
+    ///
+    /// A value of `None` means that there is no limit.
+    ///
+    /// # Usage Contract
+    /// * The size cannot be negative.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if contract is not followed)
+    ///
+    /// # Default Value
+    /// `None`
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn set_max_msg_size(&self, maybe: Option<i64>) -> Result<(), Error> {
+        self.raw_socket().set_max_msg_size(maybe)
+    }
+
+    /// Returns `true` if IPv6 is enabled on the socket.
+    fn ipv6(&self) -> Result<bool, Error> {
+        self.raw_socket().ipv6()
+    }
+
+    /// Enables IPv6 on the socket.
+    ///
+    /// When enabled, the socket will connect to, or accept connections
+    /// from, both IPv4 and IPv6 hosts. Otherwise, only IPv4 is used and
+    /// IPv6 endpoints will fail to resolve.
+    ///
+    /// Only affects subsequent calls to [`connect`] and [`bind`].
+    ///
+    /// # Default Value
+    /// `false`
+    ///
+    /// [`connect`]: #method.connect
+    /// [`bind`]: #method.bind
+    fn set_ipv6(&self, enabled: bool) -> Result<(), Error> {
+        self.raw_socket().set_ipv6(enabled)
+    }
+
+    /// Returns the size of the `VMCI` buffer of the socket.
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* was built without `vmci` support)
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn vmci_buffer_size(&self) -> Result<u64, Error> {
+        self.raw_socket().vmci_buffer_size()
+    }
+
+    /// Sets the size in bytes of the underlying buffer of the `VMCI`
+    /// connections of the socket.
+    ///
+    /// Only affects subsequent connections.
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* was built without `vmci` support)
+    ///
+    /// # Default Value
+    /// 262144
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn set_vmci_buffer_size(&self, size: u64) -> Result<(), Error> {
+        self.raw_socket().set_vmci_buffer_size(size)
+    }
+
+    /// Returns the minimum size of the `VMCI` buffer of the socket.
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* was built without `vmci` support)
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn vmci_buffer_min_size(&self) -> Result<u64, Error> {
+        self.raw_socket().vmci_buffer_min_size()
+    }
+
+    /// Sets the minimum size in bytes of the underlying buffer of the `VMCI`
+    /// connections of the socket.
+    ///
+    /// Only affects subsequent connections.
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* was built without `vmci` support)
+    ///
+    /// # Default Value
+    /// 128
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn set_vmci_buffer_min_size(&self, size: u64) -> Result<(), Error> {
+        self.raw_socket().set_vmci_buffer_min_size(size)
+    }
+
+    /// Returns the maximum size of the `VMCI` buffer of the socket.
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* was built without `vmci` support)
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn vmci_buffer_max_size(&self) -> Result<u64, Error> {
+        self.raw_socket().vmci_buffer_max_size()
+    }
+
+    /// Sets the maximum size in bytes of the underlying buffer of the `VMCI`
+    /// connections of the socket.
+    ///
+    /// Only affects subsequent connections.
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* was built without `vmci` support)
+    ///
+    /// # Default Value
+    /// 262144
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn set_vmci_buffer_max_size(&self, size: u64) -> Result<(), Error> {
+        self.raw_socket().set_vmci_buffer_max_size(size)
+    }
+
+    /// Returns the connect timeout of the `VMCI` connections of the socket.
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* was built without `vmci` support)
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn vmci_connect_timeout(&self) -> Result<Period, Error> {
+        self.raw_socket().vmci_connect_timeout()
+    }
+
+    /// Sets the connect timeout of the `VMCI` connections of the socket.
+    ///
+    /// A value of `Infinite` means that the connection attempts never
+    /// time out.
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* was built without `vmci` support)
+    ///
+    /// # Default Value
+    /// `Infinite`
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn set_vmci_connect_timeout(&self, period: Period) -> Result<(), Error> {
+        self.raw_socket().set_vmci_connect_timeout(period)
+    }
+
+    /// Returns the conditions under which the socket stops reconnecting.
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* is older than 4.3.3)
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn reconnect_stop(&self) -> Result<ReconnectStop, Error> {
+        self.raw_socket().reconnect_stop()
+    }
+
+    /// Sets the conditions under which the socket stops reconnecting.
+    ///
+    /// Only affects subsequent connections. See [`ReconnectStop`].
+    ///
+    /// # Returned Errors
+    /// * [`Unsupported`] (if *libzmq* is older than 4.3.3)
+    ///
+    /// # Default Value
+    /// `ReconnectStop::empty()`, namely the socket reconnects forever.
+    ///
+    /// [`ReconnectStop`]: ../struct.ReconnectStop.html
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn set_reconnect_stop(&self, stop: ReconnectStop) -> Result<(), Error> {
+        self.raw_socket().set_reconnect_stop(stop)
+    }
+
+    /// Returns a the socket's heartbeat configuration.
+    fn heartbeat(&self) -> Option<Heartbeat> {
+        self.raw_socket().heartbeat().lock().unwrap().to_owned()
+    }
+
+    /// Set the socket's heartbeat configuration.
+    ///
+    /// Only applies to connection based transports such as `TCP`.
+    /// A value of `None` means no heartbeating.
+    ///
+    /// # Contract
+    /// * timeout and interval duration in ms cannot exceed i32::MAX
+    /// * ttl duration in ms cannot exceed 6553599
+    ///
+    /// # Default value
+    /// `None`
+    ///
+    /// # Return Errors
+    /// * [InvalidInput`]: (if contract no respected)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, Client, Heartbeat, auth::*};
+    /// use std::time::Duration;
+    ///
+    /// let client = Client::new()?;
+    /// assert_eq!(client.heartbeat(), None);
+    ///
+    /// let duration = Duration::from_millis(300);
+    /// let hb = Heartbeat::new(duration)
+    ///     .add_timeout(2 * duration);
+    /// let expected = hb.clone();
+    ///
+    /// client.set_heartbeat(Some(hb))?;
+    /// assert_eq!(client.heartbeat(), Some(expected));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Mechanism`]: ../auth/enum.Mechanism.html
+    fn set_heartbeat(&self, maybe: Option<Heartbeat>) -> Result<(), Error> {
+        let raw_socket = self.raw_socket();
+        let mutex = raw_socket.heartbeat().lock().unwrap();
+
+        set_heartbeat(raw_socket, maybe, mutex)
+    }
+
+    /// Returns `true` if the blocking operations of the socket transparently
+    /// retry on [`Interrupted`] errors.
+    ///
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    fn retry_interrupted(&self) -> bool {
+        self.raw_socket().retry_interrupted()
+    }
+
+    /// Specifies whether the blocking operations of the socket, namely
+    /// [`send`] and [`recv`], transparently retry on [`Interrupted`] errors.
+    ///
+    /// This is useful for processes that receive signals for other purposes
+    /// than shutdown, such as `SIGCHLD`. A terminated context still breaks
+    /// the loop with a [`CtxTerminated`] error. The non-blocking operations
+    /// are never retried.
+    ///
+    /// # Default Value
+    /// `false`
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let server = ServerBuilder::new().retry_interrupted(true).build()?;
+    /// assert!(server.retry_interrupted());
+    ///
+    /// server.set_retry_interrupted(false);
+    /// assert!(!server.retry_interrupted());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`send`]: prelude/trait.SendMsg.html#method.send
+    /// [`recv`]: prelude/trait.RecvMsg.html#method.recv
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    fn set_retry_interrupted(&self, enabled: bool) {
+        self.raw_socket().set_retry_interrupted(enabled)
+    }
+
+    /// Returns the value of an integer socket option that is not wrapped by
+    /// the crate.
+    ///
+    /// The `option` is one of the `ZMQ_*` constants from `libzmq-sys`.
+    ///
+    /// # Safety
+    /// The option is passed as is to `zmq_getsockopt` and must have
+    /// a value of type `int`. Otherwise, the value will be truncated or
+    /// garbage.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if the option is unknown)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, Client};
+    /// use libzmq_sys as sys;
+    /// use std::os::raw::c_int;
+    ///
+    /// let client = Client::new()?;
+    /// let linger = unsafe {
+    ///     client.get_raw_option_i32(sys::ZMQ_LINGER as c_int)?
+    /// };
+    /// assert_eq!(linger, 30_000);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    unsafe fn get_raw_option_i32(&self, option: c_int) -> Result<i32, Error> {
+        sockopt::getsockopt_raw_i32(self.raw_socket().as_mut_ptr(), option)
+    }
+
+    /// Sets an integer socket option that is not wrapped by the crate.
+    ///
+    /// The `option` is one of the `ZMQ_*` constants from `libzmq-sys`.
+    ///
+    /// # Safety
+    /// The option is passed as is to `zmq_setsockopt` and must expect
+    /// a value of type `int`. Since the value is not checked by the crate,
+    /// it could also break the invariants of the typed methods (e.g. a
+    /// `Mechanism` option that differs from what [`mechanism`] returns).
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if the option or the value is invalid)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// [`mechanism`]: #method.mechanism
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    unsafe fn set_raw_option_i32(
+        &self,
+        option: c_int,
+        value: i32,
+    ) -> Result<(), Error> {
+        sockopt::setsockopt_raw_i32(
+            self.raw_socket().as_mut_ptr(),
+            option,
+            value,
+        )
+    }
+
+    /// Returns the value of a binary socket option that is not wrapped by
+    /// the crate.
+    ///
+    /// The value is read into a buffer of 255 bytes. Since *libzmq* rejects
+    /// a buffer that is too small instead of truncating the value, larger
+    /// values fail with [`InvalidInput`].
+    ///
+    /// # Safety
+    /// The option is passed as is to `zmq_getsockopt` and must have
+    /// a binary or string value.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if the option is unknown or its value is larger
+    ///     than 255 bytes)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    unsafe fn get_raw_option_bytes(
+        &self,
+        option: c_int,
+    ) -> Result<Vec<u8>, Error> {
+        sockopt::getsockopt_raw_bytes(self.raw_socket().as_mut_ptr(), option)
+    }
+
+    /// Sets a binary socket option that is not wrapped by the crate.
+    ///
+    /// # Safety
+    /// The option is passed as is to `zmq_setsockopt` and must expect
+    /// a binary or string value. Since the value is not checked by the
+    /// crate, it could also break the invariants of the typed methods.
+    ///
+    /// # Returned Errors
+    /// * [`InvalidInput`] (if the option or the value is invalid)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    unsafe fn set_raw_option_bytes(
+        &self,
+        option: c_int,
+        value: &[u8],
+    ) -> Result<(), Error> {
+        sockopt::setsockopt_raw_bytes(
+            self.raw_socket().as_mut_ptr(),
+            option,
+            value,
+        )
+    }
+
+    /// Returns a snapshot of the message counters of the socket.
+    ///
+    /// The counters are shared by all the handles to the socket. This
+    /// requires the `stats` feature.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let addr = InprocAddr::new_unique();
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// client.send("ping")?;
+    /// server.recv_msg()?;
+    ///
+    /// let stats = client.stats();
+    /// assert_eq!(stats.msgs_sent(), 1);
+    /// assert_eq!(stats.bytes_sent(), 4);
+    /// assert_eq!(server.stats().msgs_received(), 1);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> SocketStats {
+        self.raw_socket().stats().snapshot()
+    }
+
+    /// Resets the message counters of the socket to zero.
+    ///
+    /// This requires the `stats` feature.
+    #[cfg(feature = "stats")]
+    fn reset_stats(&self) {
+        self.raw_socket().stats().reset();
+    }
+}
+
+fn set_mechanism(
+    raw_socket: &RawSocket,
+    mut mechanism: Mechanism,
+    mut mutex: MutexGuard<Mechanism>,
+) -> Result<(), Error> {
+    if *mutex == mechanism {
+        return Ok(());
+    }
+
+    // Check that the mechanism is valid before modifying the socket.
+    match &mechanism {
+        Mechanism::CurveClient(_) | Mechanism::CurveServer(_) => {
+            if !has(Capability::Curve) {
+                return Err(Error::new(ErrorKind::Unsupported {
+                    msg: "curve mechanism not supported",
+                }));
+            }
+        }
+        Mechanism::GssapiClient(creds) => {
+            check_gssapi_support()?;
+            if let Some(principal) = &creds.principal {
+                check_gssapi_principal(principal)?;
+            }
+            check_gssapi_principal(&creds.service_principal)?;
+        }
+        Mechanism::GssapiServer(creds) => {
+            check_gssapi_support()?;
+            if let Some(principal) = &creds.principal {
+                check_gssapi_principal(principal)?;
+            }
+        }
+        _ => (),
+    }
+    if let Mechanism::PlainClient(creds) = &mechanism {
+        check_plain_field(&creds.username)?;
+        check_plain_field(&creds.password)?;
+    }
+    if let Mechanism::CurveClient(creds) = &mechanism {
+        if let Some(cert) = &creds.client {
+            if !cert.is_valid()? {
+                return Err(Error::new(ErrorKind::InvalidInput {
+                    msg: "client public key does not match secret key",
+                }));
+            }
+        }
+    }
+
+    // Undo the previous mechanism.
+    match &*mutex {
+        Mechanism::Null => (),
+        Mechanism::PlainClient(_) => {
+            raw_socket.set_username(None)?;
+            raw_socket.set_password(None)?;
+        }
+        Mechanism::PlainServer => {
+            raw_socket.set_plain_server(false)?;
+        }
+        Mechanism::CurveClient(_) => {
+            raw_socket.set_curve_server_key(None)?;
+            raw_socket.set_curve_public_key(None)?;
+            raw_socket.set_curve_secret_key(None)?;
+        }
+        Mechanism::CurveServer(_) => {
+            raw_socket.set_curve_secret_key(None)?;
+            raw_socket.set_curve_server(false)?;
+        }
+        Mechanism::GssapiClient(_) | Mechanism::GssapiServer(_) => {
+            raw_socket.set_gssapi_server(false)?;
+            raw_socket.set_gssapi_plaintext(false)?;
+            // There is no way to unset the gssapi options, so we rely on
+            // the fact that unsetting the plain username resets the
+            // mechanism to `NULL`.
+            raw_socket.set_username(None)?;
+        }
+    }
+
+    // Check if we need to generate a client cert.
+    let mut missing_client_cert = false;
+    if let Mechanism::CurveClient(creds) = &mechanism {
+        if creds.client.is_none() {
+            missing_client_cert = true;
+        }
+    }
+
+    // Generate a client certificate if it was not supplied.
+    if missing_client_cert {
+        let cert = CurveCert::new_unique();
+        let server_key = if let Mechanism::CurveClient(creds) = mechanism {
+            creds.server
+        } else {
+            unreachable!()
+        };
+
+        let creds = CurveClientCreds {
+            client: Some(cert),
+            server: server_key,
+        };
+        mechanism = Mechanism::CurveClient(creds);
+    }
+
+    // Apply the new mechanism.
+    match &mechanism {
+        Mechanism::Null => (),
+        Mechanism::PlainClient(creds) => {
+            raw_socket.set_username(Some(&creds.username))?;
+            raw_socket.set_password(Some(&creds.password))?;
+        }
+        Mechanism::PlainServer => {
+            raw_socket.set_plain_server(true)?;
+        }
+        Mechanism::CurveClient(creds) => {
+            let server_key: BinCurveKey = (&creds.server).into();
+            raw_socket.set_curve_server_key(Some(&server_key))?;
+
+            // Cannot fail since we would have generated a cert.
+            let cert = creds.client.as_ref().unwrap();
+            let public_key: BinCurveKey = cert.public().into();
+            raw_socket.set_curve_public_key(Some(&public_key))?;
+            let secret_key: BinCurveKey = cert.secret().into();
+            raw_socket.set_curve_secret_key(Some(&secret_key))?;
+        }
+        Mechanism::CurveServer(creds) => {
+            let secret_key: BinCurveKey = (&creds.secret).into();
+            raw_socket.set_curve_secret_key(Some(&secret_key))?;
+            raw_socket.set_curve_server(true)?;
+        }
+        Mechanism::GssapiClient(creds) => {
+            if let Some(principal) = &creds.principal {
+                raw_socket.set_gssapi_principal(principal)?;
+            }
+            raw_socket
+                .set_gssapi_service_principal(&creds.service_principal)?;
+            raw_socket.set_gssapi_plaintext(creds.plaintext)?;
+            raw_socket.set_gssapi_server(false)?;
+        }
+        Mechanism::GssapiServer(creds) => {
+            if let Some(principal) = &creds.principal {
+                raw_socket.set_gssapi_principal(principal)?;
+            }
+            raw_socket.set_gssapi_plaintext(creds.plaintext)?;
+            raw_socket.set_gssapi_server(true)?;
+        }
+    }
+
+    // Update mechanism
+    *mutex = mechanism;
+    Ok(())
+}
+
+// The `PLAIN` mechanism encodes the username and password length
+// in a single byte.
+const MAX_PLAIN_FIELD_SIZE: usize = 255;
+
+fn check_plain_field(field: &str) -> Result<(), Error> {
+    if field.len() > MAX_PLAIN_FIELD_SIZE {
+        Err(Error::new(ErrorKind::InvalidInput {
+            msg: "plain username and password cannot exceed 255 bytes",
+        }))
+    } else if field.as_bytes().contains(&0) {
+        Err(Error::new(ErrorKind::InvalidInput {
+            msg: "plain username and password cannot contain a nul byte",
+        }))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_gssapi_support() -> Result<(), Error> {
+    if has(Capability::Gssapi) {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::Unsupported {
+            msg: "gssapi mechanism not supported",
+        }))
+    }
+}
+
+// The string option getter is limited to 255 bytes including
+// the terminating null byte.
+const MAX_GSSAPI_PRINCIPAL_SIZE: usize = 254;
+
+fn check_gssapi_principal(principal: &GssapiPrincipal) -> Result<(), Error> {
+    let name = principal.name();
+    if name.is_empty() {
+        Err(Error::new(ErrorKind::InvalidInput {
+            msg: "gssapi principal cannot be empty",
+        }))
+    } else if name.len() > MAX_GSSAPI_PRINCIPAL_SIZE {
+        Err(Error::new(ErrorKind::InvalidInput {
+            msg: "gssapi principal cannot exceed 254 bytes",
+        }))
+    } else if name.as_bytes().contains(&0) {
+        Err(Error::new(ErrorKind::InvalidInput {
+            msg: "gssapi principal cannot contain a nul byte",
+        }))
+    } else {
+        Ok(())
+    }
+}
+
+fn set_heartbeat(
+    raw_socket: &RawSocket,
+    maybe: Option<Heartbeat>,
+    mut mutex: MutexGuard<Option<Heartbeat>>,
+) -> Result<(), Error> {
+    if *mutex == maybe {
+        return Ok(());
     }
 
     if let Some(heartbeat) = &maybe {
@@ -721,27 +1667,189 @@ pub struct SocketConfig {
     pub(crate) heartbeat: Option<Heartbeat>,
     pub(crate) linger: Period,
     pub(crate) mechanism: Option<Mechanism>,
+    // Whether conflicting mechanisms were set by the builder.
+    pub(crate) mechanism_conflict: bool,
+    // Whether the mechanism was left out by `to_builder` because of its
+    // secrets, in which case it must be supplied again.
+    pub(crate) mechanism_redacted: bool,
+    pub(crate) zap_domain: Option<String>,
+    pub(crate) enforce_zap_domain: Option<bool>,
+    pub(crate) ipv6: Option<bool>,
+    pub(crate) max_msg_size: Option<i64>,
+    pub(crate) backlog: Option<i32>,
+    pub(crate) send_buffer: Option<i32>,
+    pub(crate) recv_buffer: Option<i32>,
+    pub(crate) vmci_buffer_size: Option<u64>,
+    pub(crate) vmci_buffer_min_size: Option<u64>,
+    pub(crate) vmci_buffer_max_size: Option<u64>,
+    pub(crate) vmci_connect_timeout: Option<Period>,
+    pub(crate) reconnect_stop: Option<ReconnectStop>,
+    pub(crate) label: Option<String>,
+    pub(crate) retry_interrupted: Option<bool>,
 }
 
-impl SocketConfig {
+impl SocketConfig {
+    // The endpoints and the label are not tracked by the socket so they
+    // are left empty. The `vmci` options are also left empty since they
+    // cannot be read without `vmci` support, and so is the reconnect stop
+    // with an older libzmq.
+    pub(crate) fn from_socket<S: Socket>(socket: &S) -> Result<Self, Error> {
+        let zap_domain = socket.zap_domain()?;
+        let zap_domain = if zap_domain.is_empty() {
+            None
+        } else {
+            Some(zap_domain)
+        };
+
+        let reconnect_stop = match socket.reconnect_stop() {
+            Ok(stop) => Some(stop),
+            Err(err) => match err.kind() {
+                ErrorKind::Unsupported { .. } => None,
+                _ => return Err(err),
+            },
+        };
+
+        Ok(Self {
+            connect: None,
+            bind: None,
+            heartbeat: socket.heartbeat(),
+            linger: socket.linger()?,
+            mechanism: Some(socket.mechanism()),
+            mechanism_conflict: false,
+            mechanism_redacted: false,
+            zap_domain,
+            enforce_zap_domain: Some(socket.enforce_zap_domain()?),
+            ipv6: Some(socket.ipv6()?),
+            max_msg_size: socket.max_msg_size()?,
+            backlog: Some(socket.backlog()?),
+            send_buffer: Some(socket.send_buffer()?),
+            recv_buffer: Some(socket.recv_buffer()?),
+            vmci_buffer_size: None,
+            vmci_buffer_min_size: None,
+            vmci_buffer_max_size: None,
+            vmci_connect_timeout: None,
+            reconnect_stop,
+            label: None,
+            retry_interrupted: Some(socket.retry_interrupted()),
+        })
+    }
+
+    // Leaves out the mechanism if it holds secrets.
+    pub(crate) fn redact_secrets(&mut self) {
+        if let Some(mechanism) = &self.mechanism {
+            if mechanism.has_secrets() {
+                self.mechanism = None;
+                self.mechanism_redacted = true;
+            }
+        }
+    }
+
     pub(crate) fn apply<S: Socket>(
         &self,
         socket: &S,
     ) -> Result<(), Error<usize>> {
-        socket
-            .set_heartbeat(self.heartbeat.clone())
-            .map_err(Error::cast)?;
-        socket.set_linger(self.linger).map_err(Error::cast)?;
-        if let Some(ref mechanism) = self.mechanism {
-            socket.set_mechanism(mechanism).map_err(Error::cast)?;
-        }
+        self.apply_options(socket)?;
         // We connect as the last step because some socket options
         // only affect subsequent connections.
         if let Some(ref endpoints) = self.connect {
-            socket.connect(endpoints)?;
+            socket
+                .connect(endpoints)
+                .map_err(|err| err.with_step(BuildStep::Connect))?;
         }
         if let Some(ref endpoints) = self.bind {
-            socket.bind(endpoints)?;
+            socket
+                .bind(endpoints)
+                .map_err(|err| err.with_step(BuildStep::Bind))?;
+        }
+        Ok(())
+    }
+
+    fn apply_options<S: Socket>(&self, socket: &S) -> Result<(), Error<usize>> {
+        socket
+            .set_heartbeat(self.heartbeat.clone())
+            .map_err(option_step("heartbeat"))?;
+        socket
+            .set_linger(self.linger)
+            .map_err(option_step("linger"))?;
+        if self.mechanism_conflict {
+            let err = Error::<()>::new(ErrorKind::InvalidInput {
+                msg: "conflicting security mechanisms",
+            });
+            return Err(option_step("mechanism")(err));
+        }
+        if self.mechanism_redacted {
+            let err = Error::<()>::new(ErrorKind::InvalidInput {
+                msg: "mechanism secrets must be supplied",
+            });
+            return Err(option_step("mechanism")(err));
+        }
+        if let Some(ref mechanism) = self.mechanism {
+            socket
+                .set_mechanism(mechanism)
+                .map_err(option_step("mechanism"))?;
+        }
+        if let Some(ref domain) = self.zap_domain {
+            socket
+                .set_zap_domain(domain)
+                .map_err(option_step("zap_domain"))?;
+        }
+        if let Some(enabled) = self.enforce_zap_domain {
+            socket
+                .set_enforce_zap_domain(enabled)
+                .map_err(option_step("enforce_zap_domain"))?;
+        }
+        if let Some(enabled) = self.ipv6 {
+            socket.set_ipv6(enabled).map_err(option_step("ipv6"))?;
+        }
+        socket
+            .set_max_msg_size(self.max_msg_size)
+            .map_err(option_step("max_msg_size"))?;
+        if let Some(value) = self.backlog {
+            socket.set_backlog(value).map_err(option_step("backlog"))?;
+        }
+        if let Some(size) = self.send_buffer {
+            socket
+                .set_send_buffer(size)
+                .map_err(option_step("send_buffer"))?;
+        }
+        if let Some(size) = self.recv_buffer {
+            socket
+                .set_recv_buffer(size)
+                .map_err(option_step("recv_buffer"))?;
+        }
+        if let Some(size) = self.vmci_buffer_size {
+            socket
+                .set_vmci_buffer_size(size)
+                .map_err(option_step("vmci_buffer_size"))?;
+        }
+        if let Some(size) = self.vmci_buffer_min_size {
+            socket
+                .set_vmci_buffer_min_size(size)
+                .map_err(option_step("vmci_buffer_min_size"))?;
+        }
+        if let Some(size) = self.vmci_buffer_max_size {
+            socket
+                .set_vmci_buffer_max_size(size)
+                .map_err(option_step("vmci_buffer_max_size"))?;
+        }
+        if let Some(period) = self.vmci_connect_timeout {
+            socket
+                .set_vmci_connect_timeout(period)
+                .map_err(option_step("vmci_connect_timeout"))?;
+        }
+        if let Some(stop) = self.reconnect_stop {
+            socket
+                .set_reconnect_stop(stop)
+                .map_err(option_step("reconnect_stop"))?;
+        }
+        if let Some(enabled) = self.retry_interrupted {
+            socket.set_retry_interrupted(enabled);
+        }
+        #[cfg(any(feature = "diagnostics", feature = "verbose-log"))]
+        {
+            if let Some(ref label) = self.label {
+                socket.raw_socket().set_label(label);
+            }
         }
         Ok(())
     }
@@ -807,7 +1915,10 @@ pub trait ConfigureSocket: GetSocketConfig {
     }
 
     fn set_mechanism(&mut self, maybe: Option<Mechanism>) {
-        self.socket_config_mut().mechanism = maybe;
+        let config = self.socket_config_mut();
+        config.mechanism = maybe;
+        config.mechanism_conflict = false;
+        config.mechanism_redacted = false;
     }
 
     fn heartbeat(&self) -> Option<&Heartbeat> {
@@ -817,6 +1928,118 @@ pub trait ConfigureSocket: GetSocketConfig {
     fn set_heartbeat(&mut self, maybe: Option<Heartbeat>) {
         self.socket_config_mut().heartbeat = maybe;
     }
+
+    fn zap_domain(&self) -> Option<&str> {
+        self.socket_config().zap_domain.as_ref().map(String::as_str)
+    }
+
+    fn set_zap_domain(&mut self, maybe: Option<String>) {
+        self.socket_config_mut().zap_domain = maybe;
+    }
+
+    fn enforce_zap_domain(&self) -> Option<bool> {
+        self.socket_config().enforce_zap_domain
+    }
+
+    fn set_enforce_zap_domain(&mut self, maybe: Option<bool>) {
+        self.socket_config_mut().enforce_zap_domain = maybe;
+    }
+
+    fn ipv6(&self) -> Option<bool> {
+        self.socket_config().ipv6
+    }
+
+    fn set_ipv6(&mut self, maybe: Option<bool>) {
+        self.socket_config_mut().ipv6 = maybe;
+    }
+
+    fn max_msg_size(&self) -> Option<i64> {
+        self.socket_config().max_msg_size
+    }
+
+    fn set_max_msg_size(&mut self, maybe: Option<i64>) {
+        self.socket_config_mut().max_msg_size = maybe;
+    }
+
+    fn backlog(&self) -> Option<i32> {
+        self.socket_config().backlog
+    }
+
+    fn set_backlog(&mut self, maybe: Option<i32>) {
+        self.socket_config_mut().backlog = maybe;
+    }
+
+    fn send_buffer(&self) -> Option<i32> {
+        self.socket_config().send_buffer
+    }
+
+    fn set_send_buffer(&mut self, maybe: Option<i32>) {
+        self.socket_config_mut().send_buffer = maybe;
+    }
+
+    fn recv_buffer(&self) -> Option<i32> {
+        self.socket_config().recv_buffer
+    }
+
+    fn set_recv_buffer(&mut self, maybe: Option<i32>) {
+        self.socket_config_mut().recv_buffer = maybe;
+    }
+
+    fn vmci_buffer_size(&self) -> Option<u64> {
+        self.socket_config().vmci_buffer_size
+    }
+
+    fn set_vmci_buffer_size(&mut self, maybe: Option<u64>) {
+        self.socket_config_mut().vmci_buffer_size = maybe;
+    }
+
+    fn vmci_buffer_min_size(&self) -> Option<u64> {
+        self.socket_config().vmci_buffer_min_size
+    }
+
+    fn set_vmci_buffer_min_size(&mut self, maybe: Option<u64>) {
+        self.socket_config_mut().vmci_buffer_min_size = maybe;
+    }
+
+    fn vmci_buffer_max_size(&self) -> Option<u64> {
+        self.socket_config().vmci_buffer_max_size
+    }
+
+    fn set_vmci_buffer_max_size(&mut self, maybe: Option<u64>) {
+        self.socket_config_mut().vmci_buffer_max_size = maybe;
+    }
+
+    fn vmci_connect_timeout(&self) -> Option<Period> {
+        self.socket_config().vmci_connect_timeout
+    }
+
+    fn set_vmci_connect_timeout(&mut self, maybe: Option<Period>) {
+        self.socket_config_mut().vmci_connect_timeout = maybe;
+    }
+
+    fn reconnect_stop(&self) -> Option<ReconnectStop> {
+        self.socket_config().reconnect_stop
+    }
+
+    fn set_reconnect_stop(&mut self, maybe: Option<ReconnectStop>) {
+        self.socket_config_mut().reconnect_stop = maybe;
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.socket_config().label.as_deref()
+    }
+
+    fn set_label(&mut self, maybe: Option<String>) {
+        self.socket_config_mut().label = maybe;
+    }
+
+    fn retry_interrupted(&self) -> Option<bool> {
+        self.socket_config().retry_interrupted
+    }
+
+    fn set_retry_interrupted(&mut self, maybe: Option<bool>) {
+        self.socket_config_mut().retry_interrupted = maybe;
+    }
 }
 
 impl ConfigureSocket for SocketConfig {}
@@ -846,15 +2069,93 @@ pub trait BuildSocket: GetSocketConfig + Sized {
         self
     }
 
-    fn mechanism<M>(&mut self, mechanism: M) -> &mut Self
+    /// Use the given security `Mechanism`.
+    ///
+    /// This sets all the underlying socket options at once, so that the
+    /// socket is never left with a mix of say `PLAIN` and `CURVE` options.
+    /// All the shorthands such as [`plain_server`] or [`curve_client`]
+    /// delegate to this method.
+    ///
+    /// Setting a mechanism of a different kind than a previous one, such as
+    /// `plain_server(true)` followed by `curve_server(..)`, is rejected since
+    /// it is unclear which one was intended. The `Null` mechanism resets
+    /// the mechanism and never conflicts.
+    ///
+    /// The credentials of each mechanism are complete by construction. For
+    /// instance a `CURVE` client always has the server public key and its
+    /// own key pair, which is generated if not supplied.
+    ///
+    /// # Returned Errors
+    /// The `build` call will fail with:
+    /// * [`InvalidInput`] (if conflicting mechanisms were set, or if the
+    ///     credentials are invalid)
+    /// * [`Unsupported`] (if *libzmq* was built without support for
+    ///     the mechanism)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, auth::*, *};
+    ///
+    /// let cert = CurveCert::new_unique();
+    ///
+    /// let err = ServerBuilder::new()
+    ///     .plain_server(true)
+    ///     .security(CurveServerCreds::new(cert.secret()))
+    ///     .build()
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "failed to set mechanism: invalid input: conflicting security mechanisms",
+    /// );
+    ///
+    /// // Explicitly reset the previous mechanism.
+    /// let server = ServerBuilder::new()
+    ///     .plain_server(true)
+    ///     .security(Mechanism::Null)
+    ///     .security(CurveServerCreds::new(cert.secret()))
+    ///     .build()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`plain_server`]: #method.plain_server
+    /// [`curve_client`]: #method.curve_client
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    fn security<M>(&mut self, mechanism: M) -> &mut Self
     where
         M: Into<Mechanism>,
     {
-        self.socket_config_mut()
-            .set_mechanism(Some(mechanism.into()));
+        let mechanism = mechanism.into();
+        let config = self.socket_config_mut();
+
+        if let Some(previous) = &config.mechanism {
+            if previous.conflicts_with(&mechanism) {
+                config.mechanism_conflict = true;
+            }
+        }
+        config.mechanism = Some(mechanism);
+        config.mechanism_redacted = false;
         self
     }
 
+    /// Use the given security `Mechanism`.
+    ///
+    /// This is the same as [`security`].
+    ///
+    /// [`security`]: #method.security
+    fn mechanism<M>(&mut self, mechanism: M) -> &mut Self
+    where
+        M: Into<Mechanism>,
+    {
+        self.security(mechanism)
+    }
+
     fn heartbeat<H>(&mut self, heartbeat: H) -> &mut Self
     where
         H: Into<Heartbeat>,
@@ -863,4 +2164,282 @@ pub trait BuildSocket: GetSocketConfig + Sized {
             .set_heartbeat(Some(heartbeat.into()));
         self
     }
+
+    fn zap_domain(&mut self, domain: &str) -> &mut Self {
+        self.socket_config_mut()
+            .set_zap_domain(Some(domain.to_owned()));
+        self
+    }
+
+    fn enforce_zap_domain(&mut self, enabled: bool) -> &mut Self {
+        self.socket_config_mut()
+            .set_enforce_zap_domain(Some(enabled));
+        self
+    }
+
+    fn ipv6(&mut self, enabled: bool) -> &mut Self {
+        self.socket_config_mut().set_ipv6(Some(enabled));
+        self
+    }
+
+    fn max_msg_size(&mut self, maybe: Option<i64>) -> &mut Self {
+        self.socket_config_mut().set_max_msg_size(maybe);
+        self
+    }
+
+    fn backlog(&mut self, value: i32) -> &mut Self {
+        self.socket_config_mut().set_backlog(Some(value));
+        self
+    }
+
+    fn send_buffer(&mut self, size: i32) -> &mut Self {
+        self.socket_config_mut().set_send_buffer(Some(size));
+        self
+    }
+
+    fn recv_buffer(&mut self, size: i32) -> &mut Self {
+        self.socket_config_mut().set_recv_buffer(Some(size));
+        self
+    }
+
+    fn vmci_buffer_size(&mut self, size: u64) -> &mut Self {
+        self.socket_config_mut().set_vmci_buffer_size(Some(size));
+        self
+    }
+
+    fn vmci_buffer_min_size(&mut self, size: u64) -> &mut Self {
+        self.socket_config_mut()
+            .set_vmci_buffer_min_size(Some(size));
+        self
+    }
+
+    fn vmci_buffer_max_size(&mut self, size: u64) -> &mut Self {
+        self.socket_config_mut()
+            .set_vmci_buffer_max_size(Some(size));
+        self
+    }
+
+    fn vmci_connect_timeout(&mut self, period: Period) -> &mut Self {
+        self.socket_config_mut()
+            .set_vmci_connect_timeout(Some(period));
+        self
+    }
+
+    /// Sets the conditions under which the socket stops reconnecting.
+    ///
+    /// The `build` of the socket fails with [`Unsupported`] if *libzmq*
+    /// is older than 4.3.3. See [`ReconnectStop`].
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    /// [`ReconnectStop`]: ../struct.ReconnectStop.html
+    fn reconnect_stop(&mut self, stop: ReconnectStop) -> &mut Self {
+        self.socket_config_mut().set_reconnect_stop(Some(stop));
+        self
+    }
+
+    /// Attaches a label to the socket for diagnostic purposes.
+    ///
+    /// The label is listed by [`Ctx::open_sockets`] along with the other
+    /// open sockets of the context. It is only recorded if the
+    /// `diagnostics` feature is enabled.
+    ///
+    /// [`Ctx::open_sockets`]: ../struct.Ctx.html#method.open_sockets
+    fn label(&mut self, label: &str) -> &mut Self {
+        self.socket_config_mut().set_label(Some(label.to_owned()));
+        self
+    }
+
+    /// See [`Socket::set_retry_interrupted`].
+    ///
+    /// [`Socket::set_retry_interrupted`]: trait.Socket.html#method.set_retry_interrupted
+    fn retry_interrupted(&mut self, enabled: bool) -> &mut Self {
+        self.socket_config_mut()
+            .set_retry_interrupted(Some(enabled));
+        self
+    }
+
+    /// Use the `PlainServer` mechanism if `enabled`, otherwise the `Null`
+    /// mechanism.
+    ///
+    /// Since the `PLAIN` mechanism is only useful when the credentials
+    /// are actually checked, the handler of the socket's context must have a
+    /// registry of allowed credentials. See [`AuthBuilder::plain_registry`].
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *, auth::*};
+    /// use std::{convert::TryInto, time::Duration};
+    ///
+    /// let ctx = Ctx::new();
+    ///
+    /// // Allow the "user" identified by "pass" in the context's handler.
+    /// let creds = PlainClientCreds::new("user", "pass");
+    /// let _ = AuthBuilder::new()
+    ///     .plain_registry(&creds)
+    ///     .with_ctx(&ctx)?;
+    ///
+    /// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+    /// let server = ServerBuilder::new()
+    ///     .plain_server(true)
+    ///     .bind(addr)
+    ///     .recv_timeout(Duration::from_millis(200))
+    ///     .with_ctx(&ctx)?;
+    ///
+    /// let bound = server.last_endpoint()?.unwrap();
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .plain_credentials("user", "pass")
+    ///     .connect(bound)
+    ///     .with_ctx(&ctx)?;
+    ///
+    /// client.try_send("")?;
+    /// server.recv_msg()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`AuthBuilder::plain_registry`]: ../auth/struct.AuthBuilder.html#method.plain_registry
+    fn plain_server(&mut self, enabled: bool) -> &mut Self {
+        if enabled {
+            self.security(Mechanism::PlainServer)
+        } else {
+            self.security(Mechanism::Null)
+        }
+    }
+
+    /// Use the `PlainClient` mechanism with the given username and password.
+    ///
+    /// This is a shorthand for `security(PlainClientCreds::new(..))`.
+    ///
+    /// # Usage Contract
+    /// * The username and password cannot exceed 255 bytes.
+    /// * The username and password cannot contain a nul byte.
+    ///
+    /// If the contract is not respected, the `build` call will fail with
+    /// [`InvalidInput`].
+    ///
+    /// # Example
+    /// ```
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let result = ClientBuilder::new()
+    ///     .plain_credentials("user\0", "pass")
+    ///     .build();
+    ///
+    /// match result.unwrap_err().kind() {
+    ///     ErrorKind::InvalidInput { .. } => (),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn plain_credentials<U, P>(&mut self, username: U, password: P) -> &mut Self
+    where
+        U: Into<String>,
+        P: Into<String>,
+    {
+        self.security(PlainClientCreds::new(username, password))
+    }
+
+    /// Use the `GssapiServer` mechanism with the given credentials.
+    ///
+    /// This is a shorthand for `security(creds)`.
+    ///
+    /// # Returned Errors
+    /// The `build` call will fail with:
+    /// * [`Unsupported`] (if *libzmq* was built without `GSSAPI` support)
+    /// * [`InvalidInput`] (if a principal is empty, exceeds 254 bytes or
+    /// contains a nul byte)
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn gssapi_server<C>(&mut self, creds: C) -> &mut Self
+    where
+        C: Into<GssapiServerCreds>,
+    {
+        self.security(creds.into())
+    }
+
+    /// Use the `GssapiClient` mechanism with the given credentials.
+    ///
+    /// This is a shorthand for `security(creds)`.
+    ///
+    /// # Returned Errors
+    /// The `build` call will fail with:
+    /// * [`Unsupported`] (if *libzmq* was built without `GSSAPI` support)
+    /// * [`InvalidInput`] (if a principal is empty, exceeds 254 bytes or
+    /// contains a nul byte)
+    ///
+    /// [`Unsupported`]: ../enum.ErrorKind.html#variant.Unsupported
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn gssapi_client<C>(&mut self, creds: C) -> &mut Self
+    where
+        C: Into<GssapiClientCreds>,
+    {
+        self.security(creds.into())
+    }
+
+    /// Use the `CurveServer` mechanism with the given server secret key.
+    ///
+    /// This is a shorthand for `security(CurveServerCreds::new(secret))`.
+    /// The mechanism is applied before any bind or connect.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *, auth::*};
+    /// use std::{convert::TryInto, time::Duration};
+    ///
+    /// let server_cert = CurveCert::new_unique();
+    /// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+    ///
+    /// let server = ServerBuilder::new()
+    ///     .curve_server(server_cert.secret())
+    ///     .bind(addr)
+    ///     .recv_timeout(Duration::from_millis(200))
+    ///     .build()?;
+    ///
+    /// let bound = server.last_endpoint()?.unwrap();
+    ///
+    /// // The client must know the server's public key.
+    /// let creds = CurveClientCreds::new(server_cert.public());
+    /// let client = ClientBuilder::new()
+    ///     .curve_client(creds)
+    ///     .connect(bound)
+    ///     .build()?;
+    ///
+    /// // Since no client public key was registered and curve
+    /// // authentication is enabled by default, the client is denied.
+    /// client.try_send("")?;
+    /// assert!(server.recv_msg().is_err());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    fn curve_server<S>(&mut self, secret: S) -> &mut Self
+    where
+        S: Into<CurveSecretKey>,
+    {
+        self.security(CurveServerCreds::new(secret))
+    }
+
+    /// Use the `CurveClient` mechanism with the given credentials.
+    ///
+    /// This is a shorthand for `security(creds)`. If the credentials
+    /// contain a client certificate whose public key does not match its
+    /// secret key, the `build` call will fail with [`InvalidInput`].
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    fn curve_client<C>(&mut self, creds: C) -> &mut Self
+    where
+        C: Into<CurveClientCreds>,
+    {
+        self.security(creds.into())
+    }
 }
@@ -2,8 +2,12 @@ use crate::{
     addr::Endpoint,
     auth::*,
     core::sockopt::*,
-    core::{Heartbeat, Period, Quantity},
+    #[cfg(feature = "stats")]
+    core::stats::Counters,
+    core::{Heartbeat, Period, Quantity, ReconnectStop},
     error::*,
+    poll::Flags,
+    utils::{has, version, Capability},
     Ctx,
 };
 
@@ -14,18 +18,31 @@ use log::error;
 
 use std::{
     ffi::CString,
-    os::raw::{c_int, c_void},
-    sync::Mutex,
+    os::raw::{c_int, c_short, c_void},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
 const MAX_HB_TTL: i64 = 6_553_599;
+// The string option getter is limited to 255 bytes including
+// the terminating null byte.
+const MAX_ZAP_DOMAIN_SIZE: usize = 254;
 
 #[doc(hidden)]
 pub trait GetRawSocket: super::private::Sealed {
-    fn raw_socket(&self) -> &RawSocket;
+    fn raw_socket(&self) -> &Arc<RawSocket>;
 }
 
+impl GetRawSocket for Arc<RawSocket> {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
 pub(crate) enum RawSocketType {
     Client = sys::ZMQ_CLIENT as isize,
     Server = sys::ZMQ_SERVER as isize,
@@ -56,6 +73,53 @@ impl From<RawSocketType> for c_int {
     }
 }
 
+// Checks that the linked libzmq provides the endpoint's transport, for the
+// transports that are optional.
+fn check_transport(endpoint: &Endpoint) -> Result<(), Error> {
+    if (endpoint.is_pgm() || endpoint.is_edpgm()) && !has(Capability::Pgm) {
+        Err(Error::new(ErrorKind::Unsupported {
+            msg: "libzmq was built without pgm support",
+        }))
+    } else if endpoint.is_vmci() {
+        check_vmci()
+    } else if endpoint.is_tipc() && !has(Capability::Tipc) {
+        Err(Error::new(ErrorKind::Unsupported {
+            msg: "libzmq was built without tipc support",
+        }))
+    } else if endpoint.is_ipc() && !has(Capability::Ipc) {
+        Err(Error::new(ErrorKind::Unsupported {
+            msg: "libzmq was built without ipc support",
+        }))
+    } else {
+        Ok(())
+    }
+}
+
+// The `ZMQ_VMCI_*` options are unknown to `libzmq` when built without `vmci`
+// support, which would make `getsockopt` and `setsockopt` panic.
+fn check_vmci() -> Result<(), Error> {
+    if has(Capability::Vmci) {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::Unsupported {
+            msg: "libzmq was built without vmci support",
+        }))
+    }
+}
+
+// `ZMQ_RECONNECT_STOP` is unknown to the versions of libzmq older than
+// 4.3.3, which would make `getsockopt` and `setsockopt` fail with `EINVAL`
+// and be mistaken for an invalid value.
+fn check_reconnect_stop() -> Result<(), Error> {
+    if version() >= (4, 3, 3) {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::Unsupported {
+            msg: "reconnect stop requires libzmq 4.3.3",
+        }))
+    }
+}
+
 fn connect(socket_ptr: *mut c_void, c_string: CString) -> Result<(), Error> {
     let rc = unsafe { sys::zmq_connect(socket_ptr, c_string.as_ptr()) };
 
@@ -66,20 +130,14 @@ fn connect(socket_ptr: *mut c_void, c_string: CString) -> Result<(), Error> {
                 errno::EINVAL => Error::new(ErrorKind::InvalidInput {
                     msg: "invalid endpoint",
                 }),
-                errno::EPROTONOSUPPORT => Error::new(ErrorKind::InvalidInput {
-                    msg: "endpoint protocol not supported",
-                }),
                 errno::ENOCOMPATPROTO => Error::new(ErrorKind::InvalidInput {
                     msg: "endpoint protocol incompatible",
                 }),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::ENOTSOCK => panic!("invalid socket"),
-                errno::EMTHREAD => panic!("no i/o thread available"),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             }
         };
 
-        Err(err)
+        Err(err.with_errno(errno))
     } else {
         Ok(())
     }
@@ -95,23 +153,14 @@ fn bind(socket_ptr: *mut c_void, c_string: CString) -> Result<(), Error> {
                 errno::EINVAL => Error::new(ErrorKind::InvalidInput {
                     msg: "invalid endpoint",
                 }),
-                errno::EPROTONOSUPPORT => Error::new(ErrorKind::InvalidInput {
-                    msg: "endpoint protocol not supported",
-                }),
                 errno::ENOCOMPATPROTO => Error::new(ErrorKind::InvalidInput {
                     msg: "endpoint protocol incompatible",
                 }),
-                errno::EADDRINUSE => Error::new(ErrorKind::AddrInUse),
-                errno::EADDRNOTAVAIL => Error::new(ErrorKind::AddrNotAvailable),
-                errno::ENODEV => Error::new(ErrorKind::AddrNotAvailable),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::ENOTSOCK => panic!("invalid socket"),
-                errno::EMTHREAD => panic!("no i/o thread available"),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             }
         };
 
-        Err(err)
+        Err(err.with_errno(errno))
     } else {
         Ok(())
     }
@@ -127,16 +176,14 @@ fn disconnect(socket_ptr: *mut c_void, c_string: CString) -> Result<(), Error> {
                 errno::EINVAL => Error::new(ErrorKind::InvalidInput {
                     msg: "invalid endpoint",
                 }),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::ENOTSOCK => panic!("invalid socket"),
                 errno::ENOENT => Error::new(ErrorKind::NotFound {
                     msg: "endpoint was not connected to",
                 }),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             }
         };
 
-        Err(err)
+        Err(err.with_errno(errno))
     } else {
         Ok(())
     }
@@ -152,21 +199,42 @@ fn unbind(socket_ptr: *mut c_void, c_str: CString) -> Result<(), Error> {
                 errno::EINVAL => Error::new(ErrorKind::InvalidInput {
                     msg: "invalid endpoint",
                 }),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::ENOTSOCK => panic!("invalid socket"),
                 errno::ENOENT => Error::new(ErrorKind::NotFound {
                     msg: "endpoint was not bound to",
                 }),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             }
         };
 
-        Err(err)
+        Err(err.with_errno(errno))
     } else {
         Ok(())
     }
 }
 
+// ØMQ uses `-1` to leave the OS default untouched, but we expose it as `0`.
+fn get_buffer_size(
+    socket_ptr: *mut c_void,
+    option: SocketOption,
+) -> Result<i32, Error> {
+    let size: i32 = getsockopt_scalar(socket_ptr, option)?;
+    Ok(size.max(0))
+}
+
+fn set_buffer_size(
+    socket_ptr: *mut c_void,
+    option: SocketOption,
+    size: i32,
+) -> Result<(), Error> {
+    if size < 0 {
+        return Err(Error::new(ErrorKind::InvalidInput {
+            msg: "buffer size cannot be negative",
+        }));
+    }
+    let size = if size == 0 { -1 } else { size };
+    setsockopt_scalar(socket_ptr, option, size)
+}
+
 /// This socket may or may not be thread safe depending on the `RawSocketType`.
 /// We prevent that it is always thread-safe and let the wrapping types decide.
 #[derive(Debug)]
@@ -176,6 +244,13 @@ pub struct RawSocket {
     ctx: Ctx,
     mechanism: Mutex<Mechanism>,
     heartbeat: Mutex<Option<Heartbeat>>,
+    retry_interrupted: AtomicBool,
+    #[cfg(feature = "verbose-log")]
+    sock_type: RawSocketType,
+    #[cfg(feature = "verbose-log")]
+    label: Mutex<Option<String>>,
+    #[cfg(feature = "stats")]
+    stats: Counters,
 }
 
 impl RawSocket {
@@ -200,57 +275,143 @@ impl RawSocket {
             let err = match errno {
                 errno::EINVAL => panic!("invalid socket type"),
                 errno::EFAULT => panic!("invalid ctx"),
-                errno::EMFILE => Error::new(ErrorKind::SocketLimit),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             };
 
             Err(err)
         } else {
+            socket_log!(
+                debug,
+                "socket {:p}: created {:?} socket",
+                socket_mut_ptr,
+                sock_type
+            );
+
             // Set ZAP domain handling to strictly adhere the RFC.
             // This will eventually be enabled by default by ØMQ.
             setsockopt_bool(socket_mut_ptr, SocketOption::EnforceDomain, true)?;
-            // We hardset the same domain name for each sockets because I don't
-            // see any use cases for them.
+            // By default, we use the same domain name for each sockets.
             setsockopt_str(
                 socket_mut_ptr,
                 SocketOption::ZapDomain,
                 Some("global"),
             )?;
 
+            ctx.register_socket(socket_mut_ptr);
+
             Ok(Self {
                 ctx,
                 socket_mut_ptr,
                 mechanism: Mutex::default(),
                 heartbeat: Mutex::default(),
+                retry_interrupted: AtomicBool::new(false),
+                #[cfg(feature = "verbose-log")]
+                sock_type,
+                #[cfg(feature = "verbose-log")]
+                label: Mutex::default(),
+                #[cfg(feature = "stats")]
+                stats: Counters::new(),
             })
         }
     }
 
     pub(crate) fn connect(&self, endpoint: &Endpoint) -> Result<(), Error> {
+        check_transport(endpoint).map_err(|err| err.with_endpoint(endpoint))?;
         let c_string = CString::new(endpoint.to_zmq()).unwrap();
-        connect(self.as_mut_ptr(), c_string)
+        let result = connect(self.as_mut_ptr(), c_string);
+        self.log_endpoint("connect", endpoint, &result);
+        result.map_err(|err| err.with_endpoint(endpoint))
     }
 
     pub(crate) fn disconnect(&self, endpoint: &Endpoint) -> Result<(), Error> {
         let c_string = CString::new(endpoint.to_zmq()).unwrap();
-        disconnect(self.as_mut_ptr(), c_string)
+        let result = disconnect(self.as_mut_ptr(), c_string);
+        self.log_endpoint("disconnect", endpoint, &result);
+        result.map_err(|err| err.with_endpoint(endpoint))
     }
 
     pub(crate) fn bind(&self, endpoint: &Endpoint) -> Result<(), Error> {
+        check_transport(endpoint).map_err(|err| err.with_endpoint(endpoint))?;
+        if let Endpoint::Tcp(addr) = endpoint {
+            if addr.src().is_some() {
+                let err = Error::new(ErrorKind::InvalidInput {
+                    msg: "cannot bind to a tcp addr with a source addr",
+                });
+                return Err(err.with_endpoint(endpoint));
+            }
+        }
         let c_string = CString::new(endpoint.to_zmq()).unwrap();
-        bind(self.as_mut_ptr(), c_string)
+        let result = bind(self.as_mut_ptr(), c_string);
+        self.log_endpoint("bind", endpoint, &result);
+        result.map_err(|err| err.with_endpoint(endpoint))
     }
 
     pub(crate) fn unbind(&self, endpoint: &Endpoint) -> Result<(), Error> {
         let c_string = CString::new(endpoint.to_zmq()).unwrap();
-        unbind(self.as_mut_ptr(), c_string)
+        let result = unbind(self.as_mut_ptr(), c_string);
+        self.log_endpoint("unbind", endpoint, &result);
+        result.map_err(|err| err.with_endpoint(endpoint))
+    }
+
+    #[cfg_attr(not(feature = "verbose-log"), allow(unused_variables))]
+    fn log_endpoint(
+        &self,
+        action: &str,
+        endpoint: &Endpoint,
+        result: &Result<(), Error>,
+    ) {
+        match result {
+            Ok(()) => socket_log!(
+                debug,
+                "socket {:p}: {} `{}`",
+                self.socket_mut_ptr,
+                action,
+                endpoint
+            ),
+            Err(err) => socket_log!(
+                debug,
+                "socket {:p}: failed to {} `{}`: {}",
+                self.socket_mut_ptr,
+                action,
+                endpoint,
+                err
+            ),
+        }
+    }
+
+    pub(crate) fn retry_interrupted(&self) -> bool {
+        self.retry_interrupted.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_retry_interrupted(&self, enabled: bool) {
+        self.retry_interrupted.store(enabled, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "stats")]
+    pub(crate) fn stats(&self) -> &Counters {
+        &self.stats
     }
 
     pub(crate) fn ctx(&self) -> &Ctx {
         &self.ctx
     }
 
+    #[cfg(any(feature = "diagnostics", feature = "verbose-log"))]
+    pub(crate) fn set_label(&self, label: &str) {
+        #[cfg(feature = "diagnostics")]
+        self.ctx.label_socket(self.socket_mut_ptr, label);
+        #[cfg(feature = "verbose-log")]
+        {
+            socket_log!(
+                debug,
+                "socket {:p}: labeled `{}`",
+                self.socket_mut_ptr,
+                label
+            );
+            *self.label.lock().unwrap() = Some(label.to_owned());
+        }
+    }
+
     /// This is safe since the pointed socket is thread safe.
     pub(crate) fn as_mut_ptr(&self) -> *mut c_void {
         self.socket_mut_ptr
@@ -324,6 +485,68 @@ impl RawSocket {
         )
     }
 
+    pub(crate) fn events(&self) -> Result<Flags, Error> {
+        let events: c_int =
+            getsockopt_scalar(self.as_mut_ptr(), SocketOption::Events)?;
+        Ok(Flags::from_bits_truncate(events as c_short))
+    }
+
+    pub(crate) fn backlog(&self) -> Result<i32, Error> {
+        getsockopt_scalar(self.as_mut_ptr(), SocketOption::Backlog)
+    }
+
+    pub(crate) fn set_backlog(&self, value: i32) -> Result<(), Error> {
+        if value <= 0 {
+            return Err(Error::new(ErrorKind::InvalidInput {
+                msg: "backlog must be positive",
+            }));
+        }
+        setsockopt_scalar(self.as_mut_ptr(), SocketOption::Backlog, value)
+    }
+
+    pub(crate) fn send_buffer(&self) -> Result<i32, Error> {
+        get_buffer_size(self.as_mut_ptr(), SocketOption::SendBuffer)
+    }
+
+    pub(crate) fn set_send_buffer(&self, size: i32) -> Result<(), Error> {
+        set_buffer_size(self.as_mut_ptr(), SocketOption::SendBuffer, size)
+    }
+
+    pub(crate) fn recv_buffer(&self) -> Result<i32, Error> {
+        get_buffer_size(self.as_mut_ptr(), SocketOption::RecvBuffer)
+    }
+
+    pub(crate) fn set_recv_buffer(&self, size: i32) -> Result<(), Error> {
+        set_buffer_size(self.as_mut_ptr(), SocketOption::RecvBuffer, size)
+    }
+
+    pub(crate) fn max_msg_size(&self) -> Result<Option<i64>, Error> {
+        getsockopt_option_scalar(
+            self.as_mut_ptr(),
+            SocketOption::MaxMsgSize,
+            -1,
+        )
+    }
+
+    pub(crate) fn set_max_msg_size(
+        &self,
+        maybe: Option<i64>,
+    ) -> Result<(), Error> {
+        if let Some(size) = maybe {
+            if size < 0 {
+                return Err(Error::new(ErrorKind::InvalidInput {
+                    msg: "max msg size cannot be negative",
+                }));
+            }
+        }
+        setsockopt_option_scalar(
+            self.as_mut_ptr(),
+            SocketOption::MaxMsgSize,
+            maybe,
+            -1,
+        )
+    }
+
     pub(crate) fn set_username(
         &self,
         maybe: Option<&str>,
@@ -342,6 +565,49 @@ impl RawSocket {
         setsockopt_bool(self.as_mut_ptr(), SocketOption::PlainServer, cond)
     }
 
+    pub(crate) fn zap_domain(&self) -> Result<String, Error> {
+        getsockopt_string(self.as_mut_ptr(), SocketOption::ZapDomain)
+            .map(Option::unwrap_or_default)
+    }
+
+    pub(crate) fn set_zap_domain(&self, domain: &str) -> Result<(), Error> {
+        if domain.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput {
+                msg: "zap domain cannot be empty",
+            }));
+        }
+        if domain.len() > MAX_ZAP_DOMAIN_SIZE {
+            return Err(Error::new(ErrorKind::InvalidInput {
+                msg: "zap domain cannot exceed 254 bytes",
+            }));
+        }
+        if domain.as_bytes().contains(&0) {
+            return Err(Error::new(ErrorKind::InvalidInput {
+                msg: "zap domain cannot contain a nul byte",
+            }));
+        }
+        setsockopt_str(self.as_mut_ptr(), SocketOption::ZapDomain, Some(domain))
+    }
+
+    pub(crate) fn ipv6(&self) -> Result<bool, Error> {
+        getsockopt_bool(self.as_mut_ptr(), SocketOption::Ipv6)
+    }
+
+    pub(crate) fn set_ipv6(&self, enabled: bool) -> Result<(), Error> {
+        setsockopt_bool(self.as_mut_ptr(), SocketOption::Ipv6, enabled)
+    }
+
+    pub(crate) fn enforce_zap_domain(&self) -> Result<bool, Error> {
+        getsockopt_bool(self.as_mut_ptr(), SocketOption::EnforceDomain)
+    }
+
+    pub(crate) fn set_enforce_zap_domain(
+        &self,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        setsockopt_bool(self.as_mut_ptr(), SocketOption::EnforceDomain, enabled)
+    }
+
     pub(crate) fn recv_high_water_mark(&self) -> Result<Quantity, Error> {
         getsockopt_option_scalar(
             self.as_mut_ptr(),
@@ -436,6 +702,17 @@ impl RawSocket {
         setsockopt_bool(self.as_mut_ptr(), SocketOption::NoDrop, enabled)
     }
 
+    pub(crate) fn multicast_loop(&self) -> Result<bool, Error> {
+        getsockopt_bool(self.as_mut_ptr(), SocketOption::MulticastLoop)
+    }
+
+    pub(crate) fn set_multicast_loop(
+        &self,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        setsockopt_bool(self.as_mut_ptr(), SocketOption::MulticastLoop, enabled)
+    }
+
     pub(crate) fn set_curve_public_key(
         &self,
         key: Option<&BinCurveKey>,
@@ -463,6 +740,139 @@ impl RawSocket {
         let key = key.map(BinCurveKey::as_bytes);
         setsockopt_bytes(self.as_mut_ptr(), SocketOption::CurveServerKey, key)
     }
+
+    pub(crate) fn set_gssapi_server(&self, enabled: bool) -> Result<(), Error> {
+        setsockopt_bool(self.as_mut_ptr(), SocketOption::GssapiServer, enabled)
+    }
+
+    pub(crate) fn set_gssapi_plaintext(
+        &self,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        setsockopt_bool(
+            self.as_mut_ptr(),
+            SocketOption::GssapiPlaintext,
+            enabled,
+        )
+    }
+
+    pub(crate) fn set_gssapi_principal(
+        &self,
+        principal: &GssapiPrincipal,
+    ) -> Result<(), Error> {
+        setsockopt_scalar(
+            self.as_mut_ptr(),
+            SocketOption::GssapiPrincipalNameType,
+            c_int::from(principal.name_type),
+        )?;
+        setsockopt_str(
+            self.as_mut_ptr(),
+            SocketOption::GssapiPrincipal,
+            Some(&principal.name),
+        )
+    }
+
+    pub(crate) fn set_gssapi_service_principal(
+        &self,
+        principal: &GssapiPrincipal,
+    ) -> Result<(), Error> {
+        setsockopt_scalar(
+            self.as_mut_ptr(),
+            SocketOption::GssapiServicePrincipalNameType,
+            c_int::from(principal.name_type),
+        )?;
+        setsockopt_str(
+            self.as_mut_ptr(),
+            SocketOption::GssapiServicePrincipal,
+            Some(&principal.name),
+        )
+    }
+
+    pub(crate) fn vmci_buffer_size(&self) -> Result<u64, Error> {
+        check_vmci()?;
+        getsockopt_scalar(self.as_mut_ptr(), SocketOption::VmciBufferSize)
+    }
+
+    pub(crate) fn set_vmci_buffer_size(&self, size: u64) -> Result<(), Error> {
+        check_vmci()?;
+        setsockopt_scalar(self.as_mut_ptr(), SocketOption::VmciBufferSize, size)
+    }
+
+    pub(crate) fn vmci_buffer_min_size(&self) -> Result<u64, Error> {
+        check_vmci()?;
+        getsockopt_scalar(self.as_mut_ptr(), SocketOption::VmciBufferMinSize)
+    }
+
+    pub(crate) fn set_vmci_buffer_min_size(
+        &self,
+        size: u64,
+    ) -> Result<(), Error> {
+        check_vmci()?;
+        setsockopt_scalar(
+            self.as_mut_ptr(),
+            SocketOption::VmciBufferMinSize,
+            size,
+        )
+    }
+
+    pub(crate) fn vmci_buffer_max_size(&self) -> Result<u64, Error> {
+        check_vmci()?;
+        getsockopt_scalar(self.as_mut_ptr(), SocketOption::VmciBufferMaxSize)
+    }
+
+    pub(crate) fn set_vmci_buffer_max_size(
+        &self,
+        size: u64,
+    ) -> Result<(), Error> {
+        check_vmci()?;
+        setsockopt_scalar(
+            self.as_mut_ptr(),
+            SocketOption::VmciBufferMaxSize,
+            size,
+        )
+    }
+
+    pub(crate) fn vmci_connect_timeout(&self) -> Result<Period, Error> {
+        check_vmci()?;
+        getsockopt_option_duration(
+            self.as_mut_ptr(),
+            SocketOption::VmciConnectTimeout,
+            -1,
+        )
+        .map(Into::into)
+    }
+
+    pub(crate) fn set_vmci_connect_timeout(
+        &self,
+        period: Period,
+    ) -> Result<(), Error> {
+        check_vmci()?;
+        setsockopt_option_duration(
+            self.as_mut_ptr(),
+            SocketOption::VmciConnectTimeout,
+            period.into(),
+            -1,
+        )
+    }
+
+    pub(crate) fn reconnect_stop(&self) -> Result<ReconnectStop, Error> {
+        check_reconnect_stop()?;
+        let bits: c_int =
+            getsockopt_scalar(self.as_mut_ptr(), SocketOption::ReconnectStop)?;
+        Ok(ReconnectStop::from_bits_truncate(bits))
+    }
+
+    pub(crate) fn set_reconnect_stop(
+        &self,
+        stop: ReconnectStop,
+    ) -> Result<(), Error> {
+        check_reconnect_stop()?;
+        setsockopt_scalar(
+            self.as_mut_ptr(),
+            SocketOption::ReconnectStop,
+            stop.bits(),
+        )
+    }
 }
 
 impl PartialEq for RawSocket {
@@ -486,5 +896,15 @@ impl Drop for RawSocket {
             let errno = unsafe { sys::zmq_errno() };
             error!("error while dropping socket: {}", msg_from_errno(errno));
         }
+
+        socket_log!(
+            debug,
+            "socket {:p}: closed {:?} socket (label {:?})",
+            self.socket_mut_ptr,
+            self.sock_type,
+            self.label.lock().unwrap().as_deref()
+        );
+
+        self.ctx.unregister_socket(self.socket_mut_ptr);
     }
 }
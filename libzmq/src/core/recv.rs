@@ -1,21 +1,22 @@
+#[cfg(feature = "compression")]
+use crate::compression;
 use crate::{
     core::{raw::GetRawSocket, *},
-    error::{msg_from_errno, Error, ErrorKind},
+    error::{from_errno, option_step, retry_interrupted, Error, ErrorKind},
     msg::Msg,
+    poll,
 };
 use libzmq_sys as sys;
 use sys::errno;
 
-use std::{
-    os::raw::{c_int, c_void},
-    time::Duration,
-};
+use std::{os::raw::c_int, time::Duration};
 
-fn recv(
-    socket_ptr: *mut c_void,
+pub(crate) fn recv(
+    raw_socket: &RawSocket,
     msg: &mut Msg,
     no_block: bool,
 ) -> Result<(), Error> {
+    let socket_ptr = raw_socket.as_mut_ptr();
     let rc = unsafe {
         sys::zmq_msg_recv(msg.as_mut_ptr(), socket_ptr, no_block as c_int)
     };
@@ -24,21 +25,19 @@ fn recv(
         let errno = unsafe { sys::zmq_errno() };
         let err = {
             match errno {
-                errno::EAGAIN => Error::new(ErrorKind::WouldBlock),
                 errno::ENOTSUP => panic!("recv not supported by socket type"),
                 errno::EFSM => panic!(
                     "operation cannot be completed in current socket state"
                 ),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::ENOTSOCK => panic!("invalid socket"),
-                errno::EINTR => Error::new(ErrorKind::Interrupted),
                 errno::EFAULT => panic!("invalid message"),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             }
         };
 
         Err(err)
     } else {
+        #[cfg(feature = "stats")]
+        raw_socket.stats().record_recv(msg.len());
         Ok(())
     }
 }
@@ -64,7 +63,12 @@ pub trait RecvMsg: GetRawSocket {
     /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
     /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
     fn recv(&self, msg: &mut Msg) -> Result<(), Error> {
-        recv(self.raw_socket().as_mut_ptr(), msg, false)
+        let raw_socket = self.raw_socket();
+        if raw_socket.retry_interrupted() {
+            retry_interrupted(|| recv(raw_socket, msg, false))
+        } else {
+            recv(raw_socket, msg, false)
+        }
     }
 
     /// Try to retrieve a message from the inbound socket queue without blocking.
@@ -85,7 +89,7 @@ pub trait RecvMsg: GetRawSocket {
     /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
     /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
     fn try_recv(&self, msg: &mut Msg) -> Result<(), Error> {
-        recv(self.raw_socket().as_mut_ptr(), msg, true)
+        recv(self.raw_socket(), msg, true)
     }
 
     /// A convenience function that allocates a [`Msg`] with the same properties
@@ -112,6 +116,168 @@ pub trait RecvMsg: GetRawSocket {
         Ok(msg)
     }
 
+    /// Retrieves a message like [`recv_msg`], but gives up with
+    /// [`WouldBlock`] once the deadline passes.
+    ///
+    /// The `recv_timeout` of the socket is ignored. If the deadline already
+    /// passed, this makes a single non-blocking attempt. See [`Deadline`].
+    ///
+    /// ## Possible Error Variants
+    /// * [`WouldBlock`] (if the deadline passes)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// [`recv_msg`]: #method.recv_msg
+    /// [`Deadline`]: ../struct.Deadline.html
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    fn recv_deadline<D>(&self, deadline: D) -> Result<Msg, Error>
+    where
+        D: Into<Deadline>,
+    {
+        let deadline = deadline.into();
+
+        loop {
+            match self.try_recv_msg() {
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => (),
+                result => return result,
+            }
+
+            // Another thread might receive the message before us once the
+            // socket is readable, in which case we keep waiting.
+            if deadline.has_passed()
+                || !poll::wait_single(
+                    self.raw_socket(),
+                    poll::READABLE,
+                    Some(deadline.remaining()),
+                )?
+            {
+                return Err(Error::new(ErrorKind::WouldBlock));
+            }
+        }
+    }
+
+    /// Retrieves a message like [`recv_msg`] and decompresses its payload
+    /// if it was sent via [`send_compressed`].
+    ///
+    /// The messages of uncompressed peers are returned as is. The routing
+    /// id and the group of the message are preserved, but not its other
+    /// metadata properties. This requires the `compression` feature.
+    ///
+    /// The codec of a message is told by its first byte, which is never
+    /// valid at the start of UTF-8. Hence, an uncompressed binary message
+    /// starting with `0xFD` loses its first byte, while one starting with
+    /// `0xFE` or `0xFF` most likely fails with [`InvalidMessage`]. Only use
+    /// this method with peers that send either text or compressed messages.
+    ///
+    /// Since the compression ratio is chosen by the peer, the decompressed
+    /// payload is limited to the `max_msg_size` of the socket, or to
+    /// [`MAX_DECOMPRESSED_SIZE`] if it is unlimited.
+    ///
+    /// # Error
+    /// The message is lost if it cannot be decompressed.
+    ///
+    /// ## Possible Error Variants
+    /// * [`WouldBlock`] (if `recv_timeout` expires)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`InvalidMessage`] (if the compressed payload is invalid or
+    ///   exceeds the limit once decompressed)
+    ///
+    /// [`recv_msg`]: #method.recv_msg
+    /// [`send_compressed`]: trait.SendMsg.html#method.send_compressed
+    /// [`MAX_DECOMPRESSED_SIZE`]: ../constant.MAX_DECOMPRESSED_SIZE.html
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`InvalidMessage`]: ../enum.ErrorKind.html#variant.InvalidMessage
+    #[cfg(feature = "compression")]
+    fn recv_decompressed(&self) -> Result<Msg, Error> {
+        let max_size = match self.raw_socket().max_msg_size()? {
+            Some(size) => size as usize,
+            None => compression::MAX_DECOMPRESSED_SIZE,
+        };
+        compression::decompress(self.recv_msg()?, max_size)
+    }
+
+    /// Retrieve up to `max` messages from the inbound socket queue in one
+    /// call and append them to `buf`.
+    ///
+    /// The first message is received like [`recv`] would, which means that
+    /// it might block until `recv_timeout` expires. The remaining messages
+    /// are received without blocking, so only those that are immediately
+    /// available are drained.
+    ///
+    /// Returns the number of messages appended to `buf`.
+    ///
+    /// # Error
+    /// An error is only returned if the first message could not be received,
+    /// in which case `buf` is left untouched. Errors past the first message
+    /// simply end the batch and will be reported by the next call.
+    ///
+    /// ## Possible Error Variants
+    /// * [`WouldBlock`] (if `recv_timeout` expires)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let addr = InprocAddr::new_unique();
+    ///
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// for _ in 0..3 {
+    ///     client.send("msg")?;
+    /// }
+    ///
+    /// let mut batch = Vec::new();
+    /// let mut count = 0;
+    /// while count < 3 {
+    ///     count += server.recv_batch(&mut batch, 16)?;
+    /// }
+    /// assert_eq!(batch.len(), 3);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`recv`]: #method.recv
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    fn recv_batch(
+        &self,
+        buf: &mut Vec<Msg>,
+        max: usize,
+    ) -> Result<usize, Error> {
+        if max == 0 {
+            return Ok(0);
+        }
+
+        let mut msg = Msg::new();
+        self.recv(&mut msg)?;
+        buf.push(msg);
+
+        let mut count = 1;
+        while count < max {
+            let mut msg = Msg::new();
+            if self.try_recv(&mut msg).is_err() {
+                break;
+            }
+            buf.push(msg);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
     /// The high water mark for incoming messages on the specified socket.
     ///
     /// The high water mark is a hard limit on the maximum number of
@@ -161,6 +327,138 @@ pub trait RecvMsg: GetRawSocket {
     {
         self.raw_socket().set_recv_timeout(period.into())
     }
+
+    /// Returns an iterator over the incoming messages of the socket.
+    ///
+    /// Each iteration blocks like [`recv_msg`] would. [`Interrupted`]
+    /// errors are transparently retried unless specified otherwise
+    /// via [`retry_interrupted`]. The iterator ends once the context of
+    /// the socket is terminated.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let addr = InprocAddr::new_unique();
+    ///
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// for _ in 0..3 {
+    ///     client.send("msg")?;
+    /// }
+    ///
+    /// for msg in server.incoming().take(3) {
+    ///     assert_eq!(msg?.to_str()?, "msg");
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`recv_msg`]: #method.recv_msg
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`retry_interrupted`]: struct.Incoming.html#method.retry_interrupted
+    fn incoming(&self) -> Incoming<Self>
+    where
+        Self: Sized,
+    {
+        Incoming::new(self, false)
+    }
+
+    /// Returns an iterator over the messages that are already queued in
+    /// the socket.
+    ///
+    /// Unlike [`incoming`], the iterator never blocks and ends at the
+    /// first [`WouldBlock`] error.
+    ///
+    /// [`incoming`]: #method.incoming
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    fn try_incoming(&self) -> Incoming<Self>
+    where
+        Self: Sized,
+    {
+        Incoming::new(self, true)
+    }
+}
+
+/// An iterator over the incoming messages of a socket.
+///
+/// This `struct` is created by the [`incoming`] and [`try_incoming`]
+/// methods on [`RecvMsg`].
+///
+/// [`incoming`]: trait.RecvMsg.html#method.incoming
+/// [`try_incoming`]: trait.RecvMsg.html#method.try_incoming
+/// [`RecvMsg`]: trait.RecvMsg.html
+pub struct Incoming<'a, S> {
+    socket: &'a S,
+    no_block: bool,
+    retry_interrupted: bool,
+    done: bool,
+}
+
+impl<'a, S> Incoming<'a, S>
+where
+    S: RecvMsg,
+{
+    fn new(socket: &'a S, no_block: bool) -> Self {
+        Self {
+            socket,
+            no_block,
+            retry_interrupted: true,
+            done: false,
+        }
+    }
+
+    /// Specifies whether [`Interrupted`] errors are retried instead of
+    /// being yielded by the iterator.
+    ///
+    /// # Default Value
+    /// `true`
+    ///
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    pub fn retry_interrupted(mut self, cond: bool) -> Self {
+        self.retry_interrupted = cond;
+        self
+    }
+}
+
+impl<'a, S> Iterator for Incoming<'a, S>
+where
+    S: RecvMsg,
+{
+    type Item = Result<Msg, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            let mut msg = Msg::new();
+            let result = if self.no_block {
+                self.socket.try_recv(&mut msg)
+            } else {
+                self.socket.recv(&mut msg)
+            };
+
+            match result {
+                Ok(()) => return Some(Ok(msg)),
+                Err(err) => match err.kind() {
+                    ErrorKind::Interrupted if self.retry_interrupted => (),
+                    ErrorKind::WouldBlock if self.no_block => break,
+                    ErrorKind::CtxTerminated => break,
+                    _ => return Some(Err(err)),
+                },
+            }
+        }
+
+        self.done = true;
+        None
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
@@ -171,9 +469,20 @@ pub struct RecvConfig {
 }
 
 impl RecvConfig {
+    pub(crate) fn from_socket<S: RecvMsg>(socket: &S) -> Result<Self, Error> {
+        Ok(Self {
+            recv_high_water_mark: socket.recv_high_water_mark()?,
+            recv_timeout: socket.recv_timeout()?,
+        })
+    }
+
     pub(crate) fn apply<S: RecvMsg>(&self, socket: &S) -> Result<(), Error> {
-        socket.set_recv_high_water_mark(self.recv_high_water_mark)?;
-        socket.set_recv_timeout(self.recv_timeout)?;
+        socket
+            .set_recv_high_water_mark(self.recv_high_water_mark)
+            .map_err(option_step("recv_high_water_mark"))?;
+        socket
+            .set_recv_timeout(self.recv_timeout)
+            .map_err(option_step("recv_timeout"))?;
 
         Ok(())
     }
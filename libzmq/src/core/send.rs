@@ -1,21 +1,24 @@
+#[cfg(feature = "compression")]
+use crate::compression::{self, Codec};
 use crate::{
     core::*,
-    error::{msg_from_errno, Error, ErrorKind},
+    error::{from_errno, option_step, Error, ErrorKind},
     msg::Msg,
+    poll,
 };
 use libzmq_sys as sys;
 use sys::errno;
 
-use std::{
-    os::raw::{c_int, c_void},
-    time::Duration,
-};
+use std::{os::raw::c_int, time::Duration};
 
-fn send(
-    socket_ptr: *mut c_void,
+pub(crate) fn send(
+    raw_socket: &RawSocket,
     mut msg: Msg,
     no_block: bool,
 ) -> Result<(), Error<Msg>> {
+    #[cfg(feature = "stats")]
+    let len = msg.len();
+    let socket_ptr = raw_socket.as_mut_ptr();
     let rc = unsafe {
         sys::zmq_msg_send(msg.as_mut_ptr(), socket_ptr, no_block as c_int)
     };
@@ -24,9 +27,6 @@ fn send(
         let errno = unsafe { sys::zmq_errno() };
         let err = {
             match errno {
-                errno::EAGAIN => {
-                    Error::with_content(ErrorKind::WouldBlock, msg)
-                }
                 errno::ENOTSUP => {
                     panic!("send is not supported by socket type")
                 }
@@ -36,27 +36,40 @@ fn send(
                 errno::EFSM => panic!(
                     "operation cannot be completed in current socket state"
                 ),
-                errno::ETERM => {
-                    Error::with_content(ErrorKind::CtxTerminated, msg)
-                }
-                errno::ENOTSOCK => panic!("invalid socket"),
-                errno::EINTR => {
-                    Error::with_content(ErrorKind::Interrupted, msg)
-                }
                 errno::EFAULT => panic!("invalid message"),
-                errno::EHOSTUNREACH => {
-                    Error::with_content(ErrorKind::HostUnreachable, msg)
-                }
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno).cast_with(msg),
             }
         };
+        #[cfg(feature = "stats")]
+        raw_socket.stats().record_send_failure(err.kind());
 
         Err(err)
     } else {
+        #[cfg(feature = "stats")]
+        raw_socket.stats().record_send(len);
         Ok(())
     }
 }
 
+// Sends the message via `f`, retrying on `Interrupted` errors if `retry`.
+pub(crate) fn send_retrying<F>(
+    retry: bool,
+    mut msg: Msg,
+    mut f: F,
+) -> Result<(), Error<Msg>>
+where
+    F: FnMut(Msg) -> Result<(), Error<Msg>>,
+{
+    loop {
+        match f(msg) {
+            Err(mut err) if retry && err.kind() == ErrorKind::Interrupted => {
+                msg = err.take_content().unwrap();
+            }
+            result => return result,
+        }
+    }
+}
+
 /// Send messages in a thread-safe fashion.
 ///
 /// Does not support multipart messages.
@@ -73,7 +86,12 @@ pub trait SendMsg: GetRawSocket {
     ///
     /// # Error
     /// In case of an error, the message is not queued and
-    /// the ownership is returned.
+    /// the ownership is returned. It can be reclaimed using
+    /// [`take_content`].
+    ///
+    /// All the possible error variants are reported by ØMQ before it takes
+    /// ownership of the message, which means that the message is always
+    /// returned intact.
     ///
     /// ## Possible Error Variants
     /// * [`WouldBlock`] (if `send_timeout` expires)
@@ -87,11 +105,39 @@ pub trait SendMsg: GetRawSocket {
     /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
     /// [`HostUnreachable`]: ../enum.ErrorKind.html#variant.HostUnreachable
     /// [`Server`]: struct.Server.html
+    /// [`take_content`]: ../struct.Error.html#method.take_content
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::time::Duration;
+    ///
+    /// // The client has no peer so it is in mute state.
+    /// let client = ClientBuilder::new()
+    ///     .send_timeout(Duration::from_millis(1))
+    ///     .build()?;
+    ///
+    /// let mut err = client.send("msg").unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    ///
+    /// // The message can be reclaimed to be sent again later.
+    /// let msg = err.take_content().unwrap();
+    /// assert_eq!(msg.to_str()?, "msg");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
     fn send<M>(&self, msg: M) -> Result<(), Error<Msg>>
     where
         M: Into<Msg>,
     {
-        send(self.raw_socket().as_mut_ptr(), msg.into(), false)
+        let raw_socket = self.raw_socket();
+        send_retrying(raw_socket.retry_interrupted(), msg.into(), |msg| {
+            send(raw_socket, msg, false)
+        })
     }
 
     /// Try to push a message into the outgoing socket queue without blocking.
@@ -106,7 +152,12 @@ pub trait SendMsg: GetRawSocket {
     ///
     /// # Error
     /// In case of an error, the message is not queued and
-    /// the ownership is returned.
+    /// the ownership is returned. It can be reclaimed using
+    /// [`take_content`].
+    ///
+    /// All the possible error variants are reported by ØMQ before it takes
+    /// ownership of the message, which means that the message is always
+    /// returned intact.
     ///
     /// ## Possible Error Variants
     /// * [`WouldBlock`]
@@ -120,11 +171,183 @@ pub trait SendMsg: GetRawSocket {
     /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
     /// [`HostUnreachable`]: ../enum.ErrorKind.html#variant.HostUnreachable
     /// [`Server`]: struct.Server.html
+    /// [`take_content`]: ../struct.Error.html#method.take_content
     fn try_send<M>(&self, msg: M) -> Result<(), Error<Msg>>
     where
         M: Into<Msg>,
     {
-        send(self.raw_socket().as_mut_ptr(), msg.into(), true)
+        send(self.raw_socket(), msg.into(), true)
+    }
+
+    /// Pushes a message into the outgoing socket queue like [`send`], but
+    /// gives up with [`WouldBlock`] once the deadline passes.
+    ///
+    /// The `send_timeout` of the socket is ignored. If the deadline already
+    /// passed, this makes a single non-blocking attempt. See [`Deadline`].
+    ///
+    /// If the message could not be sent, it can be retrieved from the error
+    /// via [`take_content`].
+    ///
+    /// ## Possible Error Variants
+    /// * [`WouldBlock`] (if the deadline passes)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`HostUnreachable`] (only for [`Server`] socket)
+    ///
+    /// [`send`]: #method.send
+    /// [`Deadline`]: ../struct.Deadline.html
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`HostUnreachable`]: ../enum.ErrorKind.html#variant.HostUnreachable
+    /// [`Server`]: struct.Server.html
+    /// [`take_content`]: ../struct.Error.html#method.take_content
+    fn send_deadline<M, D>(&self, msg: M, deadline: D) -> Result<(), Error<Msg>>
+    where
+        M: Into<Msg>,
+        D: Into<Deadline>,
+    {
+        let deadline = deadline.into();
+        let mut msg = msg.into();
+
+        loop {
+            match self.try_send(msg) {
+                Err(mut err) => {
+                    if err.kind() != ErrorKind::WouldBlock {
+                        return Err(err);
+                    }
+                    msg = err.take_content().unwrap();
+                }
+                Ok(()) => return Ok(()),
+            }
+
+            if deadline.has_passed() {
+                return Err(Error::with_content(ErrorKind::WouldBlock, msg));
+            }
+
+            let writable = poll::wait_single(
+                self.raw_socket(),
+                poll::WRITABLE,
+                Some(deadline.remaining()),
+            );
+            match writable {
+                Ok(true) => (),
+                Ok(false) => {
+                    return Err(Error::with_content(ErrorKind::WouldBlock, msg))
+                }
+                Err(err) => return Err(err.cast_with(msg)),
+            }
+        }
+    }
+
+    /// Compresses the payload of the message with the codec and pushes it
+    /// into the outgoing socket queue, like [`send`].
+    ///
+    /// The routing id and the group of the message are preserved. Empty
+    /// messages are sent as is, and the payloads that would grow are sent
+    /// raw. This requires the `compression` feature.
+    ///
+    /// See [`Codec`] for the format of the compressed messages.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let addr = InprocAddr::new_unique();
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// let json = r#"{"a": "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}"#;
+    /// client.send_compressed(json, Codec::Zstd(3))?;
+    ///
+    /// let msg = server.recv_decompressed()?;
+    /// assert_eq!(msg.to_str()?, json);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`send`]: #method.send
+    /// [`Codec`]: ../enum.Codec.html
+    #[cfg(feature = "compression")]
+    fn send_compressed<M>(&self, msg: M, codec: Codec) -> Result<(), Error<Msg>>
+    where
+        M: Into<Msg>,
+    {
+        self.send(compression::compress(msg.into(), codec))
+    }
+
+    /// Try to push a batch of messages into the outgoing socket queue
+    /// without blocking.
+    ///
+    /// Messages are sent in order, like [`try_send`] would, until either
+    /// the batch is exhausted or an error occurs.
+    ///
+    /// # Success
+    /// Every message was queued. The number of messages sent is returned.
+    ///
+    /// # Error
+    /// The content of the error is a tuple of the number of messages
+    /// that were queued and the remainder of the batch that was not,
+    /// starting with the message that failed. It can be reclaimed using
+    /// [`take_content`].
+    ///
+    /// ## Possible Error Variants
+    /// * [`WouldBlock`]
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`HostUnreachable`] (only for [`Server`] socket)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// // The client has no peer so it is in mute state.
+    /// let client = Client::new()?;
+    ///
+    /// let batch: Vec<Msg> = vec!["a".into(), "b".into()];
+    /// let mut err = client.send_batch(batch).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    ///
+    /// let (sent, unsent) = err.take_content().unwrap();
+    /// assert_eq!(sent, 0);
+    /// assert_eq!(unsent.len(), 2);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`try_send`]: #method.try_send
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`HostUnreachable`]: ../enum.ErrorKind.html#variant.HostUnreachable
+    /// [`Server`]: struct.Server.html
+    /// [`take_content`]: ../struct.Error.html#method.take_content
+    fn send_batch<I>(&self, msgs: I) -> Result<usize, Error<(usize, Vec<Msg>)>>
+    where
+        I: IntoIterator<Item = Msg>,
+    {
+        let mut msgs = msgs.into_iter();
+        let mut count = 0;
+
+        for msg in msgs.by_ref() {
+            if let Err(mut err) = self.try_send(msg) {
+                let mut unsent = vec![err.take_content().unwrap()];
+                unsent.extend(msgs);
+
+                return Err(Error::with_content(err.kind(), (count, unsent)));
+            }
+            count += 1;
+        }
+
+        Ok(count)
     }
 
     /// The high water mark for outbound messages on the specified socket.
@@ -174,9 +397,9 @@ pub trait SendMsg: GetRawSocket {
     ///
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{prelude::*, Client, ErrorKind};
     /// use std::time::Duration;
     ///
@@ -207,9 +430,20 @@ pub struct SendConfig {
 }
 
 impl SendConfig {
+    pub(crate) fn from_socket<S: SendMsg>(socket: &S) -> Result<Self, Error> {
+        Ok(Self {
+            send_high_water_mark: socket.send_high_water_mark()?,
+            send_timeout: socket.send_timeout()?,
+        })
+    }
+
     pub(crate) fn apply<S: SendMsg>(&self, socket: &S) -> Result<(), Error> {
-        socket.set_send_high_water_mark(self.send_high_water_mark)?;
-        socket.set_send_timeout(self.send_timeout)?;
+        socket
+            .set_send_high_water_mark(self.send_high_water_mark)
+            .map_err(option_step("send_high_water_mark"))?;
+        socket
+            .set_send_timeout(self.send_timeout)
+            .map_err(option_step("send_timeout"))?;
 
         Ok(())
     }
@@ -253,3 +487,47 @@ pub trait BuildSend: GetSendConfig {
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_send_retrying() {
+        let interrupted =
+            |msg| Err(Error::with_content(ErrorKind::Interrupted, msg));
+
+        let mut calls = 0;
+        send_retrying(true, "msg".into(), |msg| {
+            calls += 1;
+            if calls < 3 {
+                interrupted(msg)
+            } else {
+                assert_eq!(msg.to_str().unwrap(), "msg");
+                Ok(())
+            }
+        })
+        .unwrap();
+        assert_eq!(calls, 3);
+
+        // A terminated context breaks the loop.
+        let mut calls = 0;
+        let err = send_retrying(true, "msg".into(), |msg| {
+            calls += 1;
+            if calls < 2 {
+                interrupted(msg)
+            } else {
+                Err(Error::with_content(ErrorKind::CtxTerminated, msg))
+            }
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CtxTerminated);
+        assert_eq!(calls, 2);
+
+        // Without retry, the error is surfaced with the message.
+        let mut err =
+            send_retrying(false, "msg".into(), interrupted).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+        assert_eq!(err.take_content().unwrap().to_str().unwrap(), "msg");
+    }
+}
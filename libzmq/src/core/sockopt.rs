@@ -1,4 +1,4 @@
-use crate::error::{msg_from_errno, Error, ErrorKind};
+use crate::error::{from_errno, Error, ErrorKind};
 use libzmq_sys as sys;
 use sys::errno;
 
@@ -6,6 +6,7 @@ use libc::{c_int, size_t};
 
 use std::{
     ffi::CString,
+    fmt,
     os::raw::c_void,
     time::Duration,
     {mem, ptr, str},
@@ -23,17 +24,23 @@ pub(crate) enum SocketOption {
     HeartbeatInterval = sys::ZMQ_HEARTBEAT_IVL as isize,
     HeartbeatTimeout = sys::ZMQ_HEARTBEAT_TIMEOUT as isize,
     HeartbeatTtl = sys::ZMQ_HEARTBEAT_TTL as isize,
+    Ipv6 = sys::ZMQ_IPV6 as isize,
     SendHighWaterMark = sys::ZMQ_SNDHWM as isize,
     SendTimeout = sys::ZMQ_SNDTIMEO as isize,
     RecvHighWaterMark = sys::ZMQ_RCVHWM as isize,
     RecvTimeout = sys::ZMQ_RCVTIMEO as isize,
+    SendBuffer = sys::ZMQ_SNDBUF as isize,
+    RecvBuffer = sys::ZMQ_RCVBUF as isize,
     NoDrop = sys::ZMQ_XPUB_NODROP as isize,
+    MulticastLoop = sys::ZMQ_MULTICAST_LOOP as isize,
     Linger = sys::ZMQ_LINGER as isize,
+    MaxMsgSize = sys::ZMQ_MAXMSGSIZE as isize,
     LastEndpoint = sys::ZMQ_LAST_ENDPOINT as isize,
     PlainPassword = sys::ZMQ_PLAIN_PASSWORD as isize,
     PlainUsername = sys::ZMQ_PLAIN_USERNAME as isize,
     PlainServer = sys::ZMQ_PLAIN_SERVER as isize,
     EnforceDomain = sys::ZMQ_ZAP_ENFORCE_DOMAIN as isize,
+    Events = sys::ZMQ_EVENTS as isize,
     ZapDomain = sys::ZMQ_ZAP_DOMAIN as isize,
     Subscribe = sys::ZMQ_SUBSCRIBE as isize,
     Unsubscribe = sys::ZMQ_UNSUBSCRIBE as isize,
@@ -41,6 +48,18 @@ pub(crate) enum SocketOption {
     CurveSecretKey = sys::ZMQ_CURVE_SECRETKEY as isize,
     CurveServer = sys::ZMQ_CURVE_SERVER as isize,
     CurveServerKey = sys::ZMQ_CURVE_SERVERKEY as isize,
+    GssapiServer = sys::ZMQ_GSSAPI_SERVER as isize,
+    GssapiPrincipal = sys::ZMQ_GSSAPI_PRINCIPAL as isize,
+    GssapiServicePrincipal = sys::ZMQ_GSSAPI_SERVICE_PRINCIPAL as isize,
+    GssapiPlaintext = sys::ZMQ_GSSAPI_PLAINTEXT as isize,
+    GssapiPrincipalNameType = sys::ZMQ_GSSAPI_PRINCIPAL_NAMETYPE as isize,
+    GssapiServicePrincipalNameType =
+        sys::ZMQ_GSSAPI_SERVICE_PRINCIPAL_NAMETYPE as isize,
+    VmciBufferSize = sys::ZMQ_VMCI_BUFFER_SIZE as isize,
+    VmciBufferMinSize = sys::ZMQ_VMCI_BUFFER_MIN_SIZE as isize,
+    VmciBufferMaxSize = sys::ZMQ_VMCI_BUFFER_MAX_SIZE as isize,
+    VmciConnectTimeout = sys::ZMQ_VMCI_CONNECT_TIMEOUT as isize,
+    ReconnectStop = sys::ZMQ_RECONNECT_STOP as isize,
 }
 
 impl From<SocketOption> for c_int {
@@ -60,6 +79,7 @@ impl From<SocketOption> for c_int {
                 SocketOption::HeartbeatTimeout as c_int
             }
             SocketOption::HeartbeatTtl => SocketOption::HeartbeatTtl as c_int,
+            SocketOption::Ipv6 => SocketOption::Ipv6 as c_int,
             SocketOption::SendHighWaterMark => {
                 SocketOption::SendHighWaterMark as c_int
             }
@@ -68,13 +88,18 @@ impl From<SocketOption> for c_int {
                 SocketOption::RecvHighWaterMark as c_int
             }
             SocketOption::RecvTimeout => SocketOption::RecvTimeout as c_int,
+            SocketOption::SendBuffer => SocketOption::SendBuffer as c_int,
+            SocketOption::RecvBuffer => SocketOption::RecvBuffer as c_int,
             SocketOption::NoDrop => SocketOption::NoDrop as c_int,
+            SocketOption::MulticastLoop => SocketOption::MulticastLoop as c_int,
             SocketOption::Linger => SocketOption::Linger as c_int,
+            SocketOption::MaxMsgSize => SocketOption::MaxMsgSize as c_int,
             SocketOption::LastEndpoint => SocketOption::LastEndpoint as c_int,
             SocketOption::PlainPassword => SocketOption::PlainPassword as c_int,
             SocketOption::PlainUsername => SocketOption::PlainUsername as c_int,
             SocketOption::PlainServer => SocketOption::PlainServer as c_int,
             SocketOption::EnforceDomain => SocketOption::EnforceDomain as c_int,
+            SocketOption::Events => SocketOption::Events as c_int,
             SocketOption::ZapDomain => SocketOption::ZapDomain as c_int,
             SocketOption::Subscribe => SocketOption::Subscribe as c_int,
             SocketOption::Unsubscribe => SocketOption::Unsubscribe as c_int,
@@ -88,6 +113,35 @@ impl From<SocketOption> for c_int {
             SocketOption::CurveServerKey => {
                 SocketOption::CurveServerKey as c_int
             }
+            SocketOption::GssapiServer => SocketOption::GssapiServer as c_int,
+            SocketOption::GssapiPrincipal => {
+                SocketOption::GssapiPrincipal as c_int
+            }
+            SocketOption::GssapiServicePrincipal => {
+                SocketOption::GssapiServicePrincipal as c_int
+            }
+            SocketOption::GssapiPlaintext => {
+                SocketOption::GssapiPlaintext as c_int
+            }
+            SocketOption::GssapiPrincipalNameType => {
+                SocketOption::GssapiPrincipalNameType as c_int
+            }
+            SocketOption::GssapiServicePrincipalNameType => {
+                SocketOption::GssapiServicePrincipalNameType as c_int
+            }
+            SocketOption::VmciBufferSize => {
+                SocketOption::VmciBufferSize as c_int
+            }
+            SocketOption::VmciBufferMinSize => {
+                SocketOption::VmciBufferMinSize as c_int
+            }
+            SocketOption::VmciBufferMaxSize => {
+                SocketOption::VmciBufferMaxSize as c_int
+            }
+            SocketOption::VmciConnectTimeout => {
+                SocketOption::VmciConnectTimeout as c_int
+            }
+            SocketOption::ReconnectStop => SocketOption::ReconnectStop as c_int,
         }
     }
 }
@@ -107,10 +161,7 @@ fn getsockopt(
         let err = {
             match errno {
                 errno::EINVAL => panic!("invalid option"),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::ENOTSOCK => panic!("invalid socket"),
-                errno::EINTR => Error::new(ErrorKind::Interrupted),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             }
         };
 
@@ -221,6 +272,43 @@ pub(crate) fn getsockopt_option_duration(
     }
 }
 
+impl SocketOption {
+    // Whether the value of the option must not be logged.
+    #[cfg(feature = "verbose-log")]
+    fn is_secret(self) -> bool {
+        matches!(
+            self,
+            SocketOption::PlainPassword | SocketOption::CurveSecretKey
+        )
+    }
+}
+
+#[cfg_attr(not(feature = "verbose-log"), allow(unused_variables))]
+fn log_set<T>(mut_sock_ptr: *mut c_void, option: SocketOption, value: T)
+where
+    T: fmt::Debug,
+{
+    #[cfg(feature = "verbose-log")]
+    {
+        if option.is_secret() {
+            socket_log!(
+                trace,
+                "socket {:p}: set {:?} to <redacted>",
+                mut_sock_ptr,
+                option
+            );
+        } else {
+            socket_log!(
+                trace,
+                "socket {:p}: set {:?} to {:?}",
+                mut_sock_ptr,
+                option,
+                value
+            );
+        }
+    }
+}
+
 fn setsockopt(
     mut_sock_ptr: *mut c_void,
     option: SocketOption,
@@ -236,10 +324,7 @@ fn setsockopt(
         let err = {
             match errno {
                 errno::EINVAL => panic!("invalid option"),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::ENOTSOCK => panic!("invalid socket"),
-                errno::EINTR => Error::new(ErrorKind::Interrupted),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             }
         };
 
@@ -254,6 +339,7 @@ pub(crate) fn setsockopt_bool(
     option: SocketOption,
     value: bool,
 ) -> Result<(), Error> {
+    log_set(mut_sock_ptr, option, value);
     let value = value as c_int;
     let size = mem::size_of::<c_int>() as size_t;
     let value_ptr = &value as *const c_int as *const c_void;
@@ -265,7 +351,11 @@ pub(crate) fn setsockopt_scalar<T>(
     mut_sock_ptr: *mut c_void,
     option: SocketOption,
     value: T,
-) -> Result<(), Error> {
+) -> Result<(), Error>
+where
+    T: fmt::Debug,
+{
+    log_set(mut_sock_ptr, option, &value);
     let size = mem::size_of::<T>() as size_t;
     let value_ptr = &value as *const T as *const c_void;
 
@@ -279,8 +369,9 @@ pub(crate) fn setsockopt_option_scalar<T>(
     none_value: T,
 ) -> Result<(), Error>
 where
-    T: Eq,
+    T: Eq + fmt::Debug,
 {
+    log_set(mut_sock_ptr, option, &maybe);
     let size = mem::size_of::<T>() as size_t;
 
     let value_ptr = match maybe {
@@ -294,6 +385,15 @@ pub(crate) fn setsockopt_bytes(
     mut_sock_ptr: *mut c_void,
     option: SocketOption,
     maybe: Option<&[u8]>,
+) -> Result<(), Error> {
+    log_set(mut_sock_ptr, option, maybe);
+    set_bytes(mut_sock_ptr, option, maybe)
+}
+
+fn set_bytes(
+    mut_sock_ptr: *mut c_void,
+    option: SocketOption,
+    maybe: Option<&[u8]>,
 ) -> Result<(), Error> {
     match maybe {
         Some(bytes) => {
@@ -310,9 +410,10 @@ pub(crate) fn setsockopt_str(
     option: SocketOption,
     maybe: Option<&str>,
 ) -> Result<(), Error> {
+    log_set(mut_sock_ptr, option, maybe);
     // No need to add a terminating zero byte.
     // http://api.zeromq.org/master:zmq-setsockopt
-    setsockopt_bytes(mut_sock_ptr, option, maybe.map(str::as_bytes))
+    set_bytes(mut_sock_ptr, option, maybe.map(str::as_bytes))
 }
 
 pub(crate) fn setsockopt_null(
@@ -358,3 +459,79 @@ fn check_duration(duration: Duration) -> Result<(), Error> {
         Ok(())
     }
 }
+
+fn raw_sockopt_error() -> Error {
+    let errno = unsafe { sys::zmq_errno() };
+    match errno {
+        // Since the option is unchecked, this is not a bug of the crate.
+        errno::EINVAL => Error::new(ErrorKind::InvalidInput {
+            msg: "invalid option or value",
+        })
+        .with_errno(errno),
+        _ => from_errno(errno),
+    }
+}
+
+pub(crate) unsafe fn getsockopt_raw_i32(
+    mut_sock_ptr: *mut c_void,
+    option: c_int,
+) -> Result<i32, Error> {
+    let mut value: i32 = 0;
+    let mut size = mem::size_of::<i32>();
+    let value_ptr = &mut value as *mut i32 as *mut c_void;
+
+    let rc = sys::zmq_getsockopt(mut_sock_ptr, option, value_ptr, &mut size);
+    if rc == -1 {
+        Err(raw_sockopt_error())
+    } else {
+        Ok(value)
+    }
+}
+
+pub(crate) unsafe fn setsockopt_raw_i32(
+    mut_sock_ptr: *mut c_void,
+    option: c_int,
+    value: i32,
+) -> Result<(), Error> {
+    let size = mem::size_of::<i32>() as size_t;
+    let value_ptr = &value as *const i32 as *const c_void;
+
+    let rc = sys::zmq_setsockopt(mut_sock_ptr, option, value_ptr, size);
+    if rc == -1 {
+        Err(raw_sockopt_error())
+    } else {
+        Ok(())
+    }
+}
+
+pub(crate) unsafe fn getsockopt_raw_bytes(
+    mut_sock_ptr: *mut c_void,
+    option: c_int,
+) -> Result<Vec<u8>, Error> {
+    let mut size = MAX_OPTION_SIZE;
+    let mut value = vec![0u8; size];
+    let value_ptr = value.as_mut_ptr() as *mut c_void;
+
+    let rc = sys::zmq_getsockopt(mut_sock_ptr, option, value_ptr, &mut size);
+    if rc == -1 {
+        Err(raw_sockopt_error())
+    } else {
+        value.truncate(size);
+        Ok(value)
+    }
+}
+
+pub(crate) unsafe fn setsockopt_raw_bytes(
+    mut_sock_ptr: *mut c_void,
+    option: c_int,
+    bytes: &[u8],
+) -> Result<(), Error> {
+    let value_ptr = bytes.as_ptr() as *const c_void;
+
+    let rc = sys::zmq_setsockopt(mut_sock_ptr, option, value_ptr, bytes.len());
+    if rc == -1 {
+        Err(raw_sockopt_error())
+    } else {
+        Ok(())
+    }
+}
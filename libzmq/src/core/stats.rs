@@ -0,0 +1,190 @@
+use crate::error::ErrorKind;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "stats-activity")]
+use std::time::{Duration, Instant};
+
+// The send error kinds that are counted separately. The others are
+// counted in the last slot.
+const FAILURE_KINDS: [ErrorKind; 4] = [
+    ErrorKind::WouldBlock,
+    ErrorKind::HostUnreachable,
+    ErrorKind::Interrupted,
+    ErrorKind::CtxTerminated,
+];
+
+fn failure_slot(kind: ErrorKind) -> usize {
+    FAILURE_KINDS
+        .iter()
+        .position(|&k| k == kind)
+        .unwrap_or(FAILURE_KINDS.len())
+}
+
+// The counters of a socket, which are only updated with relaxed atomic
+// operations since they are only meant to be read as a whole.
+//
+// The last activity reads the clock on every message, so it is only
+// tracked with the `stats-activity` feature.
+#[derive(Debug)]
+pub(crate) struct Counters {
+    msgs_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    msgs_received: AtomicU64,
+    bytes_received: AtomicU64,
+    send_failures: [AtomicU64; FAILURE_KINDS.len() + 1],
+    #[cfg(feature = "stats-activity")]
+    created: Instant,
+    // The nanoseconds since the creation plus one, or zero if there was
+    // no activity yet.
+    #[cfg(feature = "stats-activity")]
+    last_activity: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn new() -> Self {
+        Self {
+            msgs_sent: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            msgs_received: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            send_failures: Default::default(),
+            #[cfg(feature = "stats-activity")]
+            created: Instant::now(),
+            #[cfg(feature = "stats-activity")]
+            last_activity: AtomicU64::new(0),
+        }
+    }
+
+    #[cfg(feature = "stats-activity")]
+    fn touch(&self) {
+        let nanos = self.created.elapsed().as_nanos() as u64;
+        self.last_activity.store(nanos + 1, Ordering::Relaxed);
+    }
+
+    #[cfg(not(feature = "stats-activity"))]
+    fn touch(&self) {}
+
+    pub(crate) fn record_send(&self, len: usize) {
+        self.msgs_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(len as u64, Ordering::Relaxed);
+        self.touch();
+    }
+
+    pub(crate) fn record_send_failure(&self, kind: ErrorKind) {
+        self.send_failures[failure_slot(kind)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_recv(&self, len: usize) {
+        self.msgs_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received.fetch_add(len as u64, Ordering::Relaxed);
+        self.touch();
+    }
+
+    pub(crate) fn snapshot(&self) -> SocketStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        #[cfg(feature = "stats-activity")]
+        let last_activity = match load(&self.last_activity) {
+            0 => None,
+            nanos => Some(self.created + Duration::from_nanos(nanos - 1)),
+        };
+
+        let mut send_failures = [0; FAILURE_KINDS.len() + 1];
+        for (count, counter) in
+            send_failures.iter_mut().zip(&self.send_failures)
+        {
+            *count = load(counter);
+        }
+
+        SocketStats {
+            msgs_sent: load(&self.msgs_sent),
+            bytes_sent: load(&self.bytes_sent),
+            msgs_received: load(&self.msgs_received),
+            bytes_received: load(&self.bytes_received),
+            send_failures,
+            #[cfg(feature = "stats-activity")]
+            last_activity,
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        let counters = [
+            &self.msgs_sent,
+            &self.bytes_sent,
+            &self.msgs_received,
+            &self.bytes_received,
+        ];
+        for counter in counters.iter().copied().chain(&self.send_failures) {
+            counter.store(0, Ordering::Relaxed);
+        }
+        #[cfg(feature = "stats-activity")]
+        self.last_activity.store(0, Ordering::Relaxed);
+    }
+}
+
+/// A snapshot of the counters of a socket.
+///
+/// This `struct` is created by the [`stats`] method on [`Socket`] and
+/// requires the `stats` feature. Only the messages sent and received by
+/// the application are counted, not the messages used internally by ØMQ
+/// such as the heartbeats.
+///
+/// Since the counters are updated independently, a snapshot taken while
+/// messages are in flight might be slightly inconsistent.
+///
+/// [`stats`]: prelude/trait.Socket.html#method.stats
+/// [`Socket`]: prelude/trait.Socket.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SocketStats {
+    msgs_sent: u64,
+    bytes_sent: u64,
+    msgs_received: u64,
+    bytes_received: u64,
+    send_failures: [u64; FAILURE_KINDS.len() + 1],
+    #[cfg(feature = "stats-activity")]
+    last_activity: Option<Instant>,
+}
+
+impl SocketStats {
+    /// Returns the number of messages sent.
+    pub fn msgs_sent(&self) -> u64 {
+        self.msgs_sent
+    }
+
+    /// Returns the number of bytes sent.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// Returns the number of messages received.
+    pub fn msgs_received(&self) -> u64 {
+        self.msgs_received
+    }
+
+    /// Returns the number of bytes received.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    /// Returns the number of sends that failed with an error of that kind.
+    ///
+    /// The kinds that sends do not usually report, namely all but
+    /// `WouldBlock`, `HostUnreachable`, `Interrupted` and `CtxTerminated`,
+    /// are counted together.
+    pub fn send_failures(&self, kind: ErrorKind) -> u64 {
+        self.send_failures[failure_slot(kind)]
+    }
+
+    /// Returns the total number of sends that failed.
+    pub fn total_send_failures(&self) -> u64 {
+        self.send_failures.iter().sum()
+    }
+
+    /// Returns when the last message was sent or received, if any.
+    ///
+    /// This requires the `stats-activity` feature, since tracking it reads
+    /// the clock on every message.
+    #[cfg(feature = "stats-activity")]
+    pub fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+}
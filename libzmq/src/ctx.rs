@@ -1,6 +1,13 @@
 //! The ØMQ context type.
 
-use crate::{auth::server::AuthServer, error::*};
+use crate::{
+    auth::{
+        server::{AuthServer, PlainHandler},
+        PlainRegistry,
+    },
+    error::*,
+    version,
+};
 use libzmq_sys as sys;
 use sys::errno;
 
@@ -8,16 +15,32 @@ use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 
 use std::{
+    collections::HashMap,
     os::raw::{c_int, c_void},
     ptr, str,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
 lazy_static! {
-    static ref GLOBAL_CONTEXT: Ctx = Ctx::new();
+    // The context specified via `set_global` before the first use, if any.
+    static ref PENDING_GLOBAL: Mutex<Option<Ctx>> = Mutex::new(None);
+    static ref GLOBAL_CONTEXT: Ctx = {
+        let pending = {
+            let mut pending = PENDING_GLOBAL.lock().unwrap();
+            GLOBAL_INITIALIZED.store(true, Ordering::SeqCst);
+            pending.take()
+        };
+        pending.unwrap_or_else(Ctx::new)
+    };
 }
 
+static GLOBAL_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
 #[derive(Copy, Clone, Debug)]
 enum RawCtxOption {
     IOThreads,
@@ -26,6 +49,10 @@ enum RawCtxOption {
     SocketLimit,
     IPV6,
     Blocky,
+    ThreadSchedPolicy,
+    ThreadPriority,
+    ThreadAffinityCpuAdd,
+    ZeroCopyRecv,
 }
 
 impl From<RawCtxOption> for c_int {
@@ -37,13 +64,51 @@ impl From<RawCtxOption> for c_int {
             RawCtxOption::SocketLimit => sys::ZMQ_SOCKET_LIMIT as c_int,
             RawCtxOption::IPV6 => sys::ZMQ_IPV6 as c_int,
             RawCtxOption::Blocky => sys::ZMQ_BLOCKY as c_int,
+            RawCtxOption::ThreadSchedPolicy => {
+                sys::ZMQ_THREAD_SCHED_POLICY as c_int
+            }
+            RawCtxOption::ThreadPriority => sys::ZMQ_THREAD_PRIORITY as c_int,
+            RawCtxOption::ThreadAffinityCpuAdd => {
+                sys::ZMQ_THREAD_AFFINITY_CPU_ADD as c_int
+            }
+            RawCtxOption::ZeroCopyRecv => sys::ZMQ_ZERO_COPY_RECV as c_int,
         }
     }
 }
 
+// Older versions of libzmq reject `ZMQ_ZERO_COPY_RECV` with `EINVAL`, which
+// would be mistaken for an invalid value.
+fn check_zero_copy_recv() -> Result<(), Error> {
+    if version() >= (4, 3, 3) {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::Unsupported {
+            msg: "zero copy receive requires libzmq 4.3.3",
+        }))
+    }
+}
+
+// The state of a context that can outlive it, so that its termination
+// can be awaited without holding a handle to it.
+#[derive(Debug, Default)]
+struct CtxState {
+    sockets: AtomicUsize,
+    terminated: Mutex<bool>,
+    cond: Condvar,
+}
+
 #[derive(Debug)]
 struct RawCtx {
     ctx: *mut c_void,
+    state: Arc<CtxState>,
+    // Shared with the `AuthServer` since closures cannot be sent to it.
+    // They are cloned out of their lock before being called, so that a
+    // slow callback does not block the other handshakes.
+    plain_handler: Mutex<Option<PlainHandler>>,
+    domain_plain_handlers: Mutex<HashMap<String, PlainHandler>>,
+    plain_registry: Mutex<Option<Arc<PlainRegistry>>>,
+    #[cfg(feature = "diagnostics")]
+    registry: Mutex<HashMap<usize, SocketInfo>>,
 }
 
 impl RawCtx {
@@ -59,7 +124,8 @@ impl RawCtx {
             match errno {
                 errno::EINVAL => Err(Error::new(ErrorKind::InvalidInput {
                     msg: "invalid value",
-                })),
+                })
+                .with_errno(errno)),
                 _ => panic!(msg_from_errno(errno)),
             }
         } else {
@@ -97,6 +163,58 @@ impl RawCtx {
         // Should never fail.
         assert_eq!(rc, 0);
     }
+
+    #[cfg(unix)]
+    fn set_thread_options(
+        &self,
+        policy: Option<SchedPolicy>,
+        priority: Option<i32>,
+        affinity: Option<&[usize]>,
+    ) -> Result<(), Error> {
+        if let Some(policy) = policy {
+            self.set(RawCtxOption::ThreadSchedPolicy, policy.into())
+                .map_err(option_step("thread_sched_policy"))?;
+        }
+        if let Some(priority) = priority {
+            self.set(RawCtxOption::ThreadPriority, priority)
+                .map_err(option_step("thread_priority"))?;
+        }
+        for &cpu in affinity.unwrap_or(&[]) {
+            if cpu > i32::max_value() as usize {
+                let err = Error::new(ErrorKind::InvalidInput {
+                    msg: "cpu index must be less than i32::MAX",
+                });
+                return Err(option_step("thread_affinity")(err));
+            }
+            self.set(RawCtxOption::ThreadAffinityCpuAdd, cpu as i32)
+                .map_err(option_step("thread_affinity"))?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn set_thread_options(
+        &self,
+        policy: Option<SchedPolicy>,
+        priority: Option<i32>,
+        affinity: Option<&[usize]>,
+    ) -> Result<(), Error> {
+        let name = if policy.is_some() {
+            "thread_sched_policy"
+        } else if priority.is_some() {
+            "thread_priority"
+        } else if affinity.is_some() {
+            "thread_affinity"
+        } else {
+            return Ok(());
+        };
+
+        let err = Error::new(ErrorKind::Unsupported {
+            msg: "thread scheduling options require a unix platform",
+        });
+        Err(option_step(name)(err))
+    }
 }
 
 // The `zmq_ctx` is internally threadsafe.
@@ -105,7 +223,10 @@ unsafe impl Sync for RawCtx {}
 
 impl Drop for RawCtx {
     fn drop(&mut self) {
-        self.terminate()
+        self.terminate();
+
+        *self.state.terminated.lock().unwrap() = true;
+        self.state.cond.notify_all();
     }
 }
 
@@ -126,21 +247,117 @@ impl Default for RawCtx {
             panic!(msg_from_errno(unsafe { sys::zmq_errno() }));
         }
 
-        Self { ctx }
+        let raw = Self {
+            ctx,
+            state: Arc::default(),
+            plain_handler: Mutex::default(),
+            domain_plain_handlers: Mutex::default(),
+            plain_registry: Mutex::default(),
+            #[cfg(feature = "diagnostics")]
+            registry: Mutex::default(),
+        };
+        // Enable ipv6 by default.
+        raw.set_bool(RawCtxOption::IPV6, true).unwrap();
+
+        raw
+    }
+}
+
+/// Information on an open socket of a [`Ctx`].
+///
+/// This requires the `diagnostics` feature.
+///
+/// [`Ctx`]: struct.Ctx.html
+#[cfg(feature = "diagnostics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketInfo {
+    label: Option<String>,
+    backtrace: String,
+}
+
+#[cfg(feature = "diagnostics")]
+impl SocketInfo {
+    /// Returns the label specified when building the socket, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns the backtrace captured when the socket was created.
+    ///
+    /// The backtrace is empty unless the `RUST_BACKTRACE` environment
+    /// variable is set.
+    pub fn backtrace(&self) -> &str {
+        &self.backtrace
+    }
+}
+
+/// The scheduling policy of the ØMQ I/O threads.
+///
+/// See `sched(7)` for the meaning of each policy.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchedPolicy {
+    /// The default time-sharing policy (`SCHED_OTHER`).
+    Other,
+    /// A first-in, first-out real-time policy (`SCHED_FIFO`).
+    Fifo,
+    /// A round-robin real-time policy (`SCHED_RR`).
+    RoundRobin,
+}
+
+#[cfg(unix)]
+impl From<SchedPolicy> for c_int {
+    fn from(policy: SchedPolicy) -> c_int {
+        match policy {
+            SchedPolicy::Other => libc::SCHED_OTHER,
+            SchedPolicy::Fifo => libc::SCHED_FIFO,
+            SchedPolicy::RoundRobin => libc::SCHED_RR,
+        }
     }
 }
 
 /// A config for a [`Ctx`].
 ///
-/// Usefull in configuration files.
+/// Usefull in configuration files. All the fields are optional and default
+/// to the *libzmq* defaults, so an empty config is valid.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::config::CtxConfig;
+///
+/// let config: CtxConfig = toml::from_str(
+///     r#"
+///     io_threads = 2
+///     max_sockets = 64
+///     no_linger = true
+///     "#,
+/// )?;
+/// let ctx = config.build()?;
+///
+/// assert_eq!(ctx.io_threads(), 2);
+/// assert_eq!(ctx.max_sockets(), 64);
+/// assert_eq!(ctx.no_linger(), true);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
 ///
 /// [`Ctx`]: struct.Ctx.html
-#[derive(Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CtxConfig {
     io_threads: Option<i32>,
     max_msg_size: Option<i32>,
     max_sockets: Option<i32>,
     no_linger: Option<bool>,
+    ipv6: Option<bool>,
+    thread_sched_policy: Option<SchedPolicy>,
+    thread_priority: Option<i32>,
+    thread_affinity: Option<Vec<usize>>,
+    zero_copy_recv: Option<bool>,
 }
 
 impl CtxConfig {
@@ -148,26 +365,59 @@ impl CtxConfig {
         Self::default()
     }
 
+    /// Builds a `Ctx` from a `CtxConfig`.
+    ///
+    /// The options are set before the context creates any socket, which
+    /// is required for `io_threads` and `max_sockets` to take effect.
     pub fn build(&self) -> Result<Ctx, Error> {
-        let ctx = Ctx::new();
-        self.apply(&ctx)?;
+        let raw = RawCtx::default();
+        self.apply_raw(&raw)?;
 
-        Ok(ctx)
+        Ok(Ctx::from_raw(raw))
     }
 
+    /// Applies a `CtxConfig` to an existing `Ctx`.
+    ///
+    /// Note that some options have no effect on a context that already
+    /// created sockets, see [`set_io_threads`] and [`set_max_sockets`].
+    ///
+    /// [`set_io_threads`]: struct.Ctx.html#method.set_io_threads
+    /// [`set_max_sockets`]: struct.Ctx.html#method.set_max_sockets
     pub fn apply(&self, ctx: &Ctx) -> Result<(), Error> {
+        self.apply_raw(&ctx.raw)
+    }
+
+    fn apply_raw(&self, raw: &RawCtx) -> Result<(), Error> {
         if let Some(value) = self.io_threads {
-            ctx.set_io_threads(value)?;
+            raw.set(RawCtxOption::IOThreads, value)
+                .map_err(option_step("io_threads"))?;
         }
         if let Some(value) = self.max_sockets {
-            ctx.set_max_sockets(value)?;
+            raw.set(RawCtxOption::MaxSockets, value)
+                .map_err(option_step("max_sockets"))?;
         }
         if let Some(value) = self.max_msg_size {
-            ctx.set_max_msg_size(value)?;
+            raw.set(RawCtxOption::MaxMsgSize, value)
+                .map_err(option_step("max_msg_size"))?;
         }
         if let Some(value) = self.no_linger {
-            ctx.set_no_linger(value)?;
+            raw.set_bool(RawCtxOption::Blocky, !value)
+                .map_err(option_step("no_linger"))?;
         }
+        if let Some(value) = self.ipv6 {
+            raw.set_bool(RawCtxOption::IPV6, value)
+                .map_err(option_step("ipv6"))?;
+        }
+        if let Some(value) = self.zero_copy_recv {
+            check_zero_copy_recv()
+                .and_then(|_| raw.set_bool(RawCtxOption::ZeroCopyRecv, value))
+                .map_err(option_step("zero_copy_recv"))?;
+        }
+        raw.set_thread_options(
+            self.thread_sched_policy,
+            self.thread_priority,
+            self.thread_affinity.as_deref(),
+        )?;
 
         Ok(())
     }
@@ -188,7 +438,7 @@ impl CtxConfig {
         self.max_msg_size = value;
     }
 
-    pub fn max_sockets(&mut self) -> Option<i32> {
+    pub fn max_sockets(&self) -> Option<i32> {
         self.max_sockets
     }
 
@@ -203,6 +453,46 @@ impl CtxConfig {
     pub fn set_no_linger(&mut self, value: Option<bool>) {
         self.no_linger = value;
     }
+
+    pub fn ipv6(&self) -> Option<bool> {
+        self.ipv6
+    }
+
+    pub fn set_ipv6(&mut self, value: Option<bool>) {
+        self.ipv6 = value;
+    }
+
+    pub fn thread_sched_policy(&self) -> Option<SchedPolicy> {
+        self.thread_sched_policy
+    }
+
+    pub fn set_thread_sched_policy(&mut self, value: Option<SchedPolicy>) {
+        self.thread_sched_policy = value;
+    }
+
+    pub fn thread_priority(&self) -> Option<i32> {
+        self.thread_priority
+    }
+
+    pub fn set_thread_priority(&mut self, value: Option<i32>) {
+        self.thread_priority = value;
+    }
+
+    pub fn thread_affinity(&self) -> Option<&[usize]> {
+        self.thread_affinity.as_deref()
+    }
+
+    pub fn set_thread_affinity(&mut self, value: Option<Vec<usize>>) {
+        self.thread_affinity = value;
+    }
+
+    pub fn zero_copy_recv(&self) -> Option<bool> {
+        self.zero_copy_recv
+    }
+
+    pub fn set_zero_copy_recv(&mut self, value: Option<bool>) {
+        self.zero_copy_recv = value;
+    }
 }
 
 /// A convenience builder for a [`Ctx`].
@@ -210,7 +500,7 @@ impl CtxConfig {
 /// Makes complex context configuration more convenient.
 ///
 /// [`Ctx`]: struct.Ctx.html
-#[derive(Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CtxBuilder {
     inner: CtxConfig,
 }
@@ -222,11 +512,14 @@ impl CtxBuilder {
 
     /// Builds a `Ctx` from a `CtxBuilder`.
     ///
+    /// This is the recommended way to configure a context, since all the
+    /// options are set before the context creates its first socket.
+    ///
     /// # Usage Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::*;
     ///
     /// let ctx = CtxBuilder::new()
@@ -249,11 +542,14 @@ impl CtxBuilder {
 
     /// Applies a `CtxBuilder` to an existing `Ctx`.
     ///
+    /// Note that some options have no effect on a context that already
+    /// created sockets, see [`set_io_threads`] and [`set_max_sockets`].
+    ///
     /// # Usage Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::*;
     ///
     /// let global = Ctx::global();
@@ -273,6 +569,9 @@ impl CtxBuilder {
     /// #     Ok(())
     /// # }
     /// ```
+    ///
+    /// [`set_io_threads`]: struct.Ctx.html#method.set_io_threads
+    /// [`set_max_sockets`]: struct.Ctx.html#method.set_max_sockets
     pub fn apply(&self, ctx: &Ctx) -> Result<(), Error> {
         self.inner.apply(ctx)
     }
@@ -308,13 +607,96 @@ impl CtxBuilder {
         self.inner.set_no_linger(Some(true));
         self
     }
+
+    /// See [`set_ipv6`].
+    ///
+    /// [`set_ipv6`]: struct.Ctx.html#method.set_ipv6
+    pub fn ipv6(&mut self, enabled: bool) -> &mut Self {
+        self.inner.set_ipv6(Some(enabled));
+        self
+    }
+
+    /// See [`set_zero_copy_recv`].
+    ///
+    /// [`set_zero_copy_recv`]: struct.Ctx.html#method.set_zero_copy_recv
+    pub fn zero_copy_recv(&mut self, enabled: bool) -> &mut Self {
+        self.inner.set_zero_copy_recv(Some(enabled));
+        self
+    }
+
+    /// Sets the scheduling policy of the I/O threads.
+    ///
+    /// Like all the thread options, it is applied when the I/O threads are
+    /// spawned, which is why it is only available via the builder.
+    ///
+    /// # Returned Error Variants
+    /// * [`Unsupported`] (on non-unix platforms)
+    ///
+    /// # Default Value
+    /// The policy of the calling thread.
+    ///
+    /// # Usage Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::*;
+    ///
+    /// let ctx = CtxBuilder::new()
+    ///   .thread_sched_policy(SchedPolicy::Other)
+    ///   .build()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+    pub fn thread_sched_policy(&mut self, policy: SchedPolicy) -> &mut Self {
+        self.inner.set_thread_sched_policy(Some(policy));
+        self
+    }
+
+    /// Sets the scheduling priority of the I/O threads.
+    ///
+    /// The valid range of values depends on the scheduling policy, see
+    /// `sched(7)`. Raising the priority usually requires privileges.
+    ///
+    /// # Returned Error Variants
+    /// * [`Unsupported`] (on non-unix platforms)
+    ///
+    /// # Default Value
+    /// The priority of the calling thread.
+    ///
+    /// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+    pub fn thread_priority(&mut self, priority: i32) -> &mut Self {
+        self.inner.set_thread_priority(Some(priority));
+        self
+    }
+
+    /// Pins the I/O threads to the given CPUs.
+    ///
+    /// # Returned Error Variants
+    /// * [`Unsupported`] (on non-unix platforms)
+    /// * [`InvalidInput`] (if a CPU index exceeds `i32::MAX`)
+    ///
+    /// # Default Value
+    /// No affinity.
+    ///
+    /// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn thread_affinity(&mut self, cpus: &[usize]) -> &mut Self {
+        self.inner.set_thread_affinity(Some(cpus.to_vec()));
+        self
+    }
 }
 
 /// Keeps the list of sockets and manages the async I/O thread and
 /// internal queries.
 ///
 /// Each context also has an associated `AuthServer` which handles socket
-/// authentification.
+/// authentification. It runs on its own thread, which lives as long as
+/// the context and exits once the context is shut down, see the [`auth`]
+/// module.
 ///
 /// # Drop
 /// The context will call terminate when dropped which will cause all
@@ -324,11 +706,19 @@ impl CtxBuilder {
 /// * All messages sent by the application with have either been physically
 ///     transferred to a network peer, or the socket's linger period has expired.
 ///
+/// Since the `AuthServer` thread holds a handle to the context, dropping
+/// the other handles does not drop the context. Call [`shutdown`] or
+/// [`terminate_timeout`] to terminate it.
+///
 /// # Thread safety
 /// A ØMQ context is internally thread safe.
 ///
 /// # Multiple Contexts
 /// Multiple contexts are allowed but are considered exotic.
+///
+/// [`auth`]: auth/index.html
+/// [`shutdown`]: #method.shutdown
+/// [`terminate_timeout`]: #method.terminate_timeout
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Ctx {
     raw: Arc<RawCtx>,
@@ -357,34 +747,51 @@ impl Ctx {
     ///
     /// [`global`]: #method.global
     pub fn new() -> Self {
-        let raw = Arc::new(RawCtx::default());
-        // Enable ipv6 by default.
-        raw.set_bool(RawCtxOption::IPV6, true).unwrap();
+        Self::from_raw(RawCtx::default())
+    }
 
-        let ctx = Self { raw };
+    // Wraps a raw context. Its options must be set beforehand since this
+    // creates the socket of the `ZAP` handler.
+    fn from_raw(raw: RawCtx) -> Self {
+        let ctx = Self { raw: Arc::new(raw) };
 
         // Start a `ZAP` handler for the context.
         let mut auth = AuthServer::with_ctx(&ctx).unwrap();
 
-        // This thread is guaranteed to terminate before the ctx
-        // since it holds a `Arc` to it. No need to store & join the
-        // thread handle.
+        // The thread holds a handle to the ctx, so it runs until the ctx is
+        // shut down, upon which `run` fails with `CtxTerminated` and the
+        // thread exits, dropping its sockets and its handle. Thus the
+        // termination of the ctx waits for the thread, and there is no need
+        // to store and join its handle.
         thread::spawn(move || auth.run());
 
         ctx
     }
 
+    // Creates a context without a `ZAP` handler, so that tests can bind
+    // their own.
+    #[cfg(test)]
+    pub(crate) fn without_auth() -> Self {
+        Self {
+            raw: Arc::new(RawCtx::default()),
+        }
+    }
+
     /// Returns a reference to the global context.
     ///
     /// This is a singleton used by sockets created via their respective
-    /// `::new()` method. It merely exists for convenience and is no different
-    /// from a context obtained via `Ctx::new()`.
+    /// `::new()` method as well as by the builders, unless a context is
+    /// explicitly specified via `with_ctx`. It merely exists for convenience
+    /// and is no different from a context obtained via `Ctx::new()`.
+    ///
+    /// The global context is lazily created on first use, unless one was
+    /// specified beforehand via [`set_global`].
     ///
     /// # Usage Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{Ctx, Client};
     ///
     /// // A socket created via `new` will use the global `Ctx`.
@@ -394,10 +801,58 @@ impl Ctx {
     /// #     Ok(())
     /// # }
     /// ```
+    ///
+    /// [`set_global`]: #method.set_global
     pub fn global() -> &'static Ctx {
         &GLOBAL_CONTEXT
     }
 
+    /// Specifies the context returned by [`global`].
+    ///
+    /// This allows options such as `io_threads`, which must be set before
+    /// the context creates any sockets, to be configured for the global
+    /// context. This can only be done once and before the global context
+    /// is first used.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the global context was already used or set)
+    ///
+    /// # Usage Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{Ctx, CtxBuilder, Client};
+    ///
+    /// let ctx = CtxBuilder::new().io_threads(4).build()?;
+    /// Ctx::set_global(ctx.clone())?;
+    ///
+    /// let client = Client::new()?;
+    /// assert_eq!(client.ctx(), &ctx);
+    /// assert_eq!(Ctx::global().io_threads(), 4);
+    ///
+    /// // It can only be set once.
+    /// assert!(Ctx::set_global(Ctx::new()).is_err());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`global`]: #method.global
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn set_global(ctx: Ctx) -> Result<(), Error> {
+        let mut pending = PENDING_GLOBAL.lock().unwrap();
+
+        if GLOBAL_INITIALIZED.load(Ordering::SeqCst) || pending.is_some() {
+            Err(Error::new(ErrorKind::InvalidInput {
+                msg: "global context already initialized",
+            }))
+        } else {
+            *pending = Some(ctx);
+            Ok(())
+        }
+    }
+
     /// Returns the size of the ØMQ thread pool for this context.
     pub fn io_threads(&self) -> i32 {
         self.raw.as_ref().get(RawCtxOption::IOThreads)
@@ -408,16 +863,26 @@ impl Ctx {
     /// "The general rule of thumb is to allow one I/O thread per gigabyte of
     /// data in or out per second." - [`Pieter Hintjens`]
     ///
+    /// A value of `0` is valid for contexts that exclusively use the
+    /// `inproc` transport, which requires no I/O thread.
+    ///
+    /// # Ordering
+    /// The thread pool is started when the context creates its
+    /// first socket, after which this option has no effect. Since every
+    /// context creates a socket for its `ZAP` handler on construction, use
+    /// the [`CtxBuilder`] to set this option.
+    ///
     /// [`Pieter Hintjens`]: http://zguide.zeromq.org/page:all#I-O-Threads
+    /// [`CtxBuilder`]: struct.CtxBuilder.html
     ///
     /// # Default
     /// The default value is `1`.
     ///
     /// # Usage Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::Ctx;
     ///
     /// let ctx = Ctx::new();
@@ -442,14 +907,21 @@ impl Ctx {
 
     /// Sets the maximum number of sockets allowed on the context.
     ///
+    /// # Ordering
+    /// Like [`set_io_threads`], this option has no effect once the context
+    /// created its first socket, so the [`CtxBuilder`] should be used.
+    ///
+    /// [`set_io_threads`]: #method.set_io_threads
+    /// [`CtxBuilder`]: struct.CtxBuilder.html
+    ///
     /// # Default
     /// The default value is `1023`.
     ///
     /// # Usage Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::Ctx;
     ///
     /// let ctx = Ctx::new();
@@ -477,9 +949,9 @@ impl Ctx {
     ///
     /// # Usage Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::Ctx;
     ///
     /// let ctx = Ctx::new();
@@ -510,14 +982,24 @@ impl Ctx {
     /// When set to `true`, all new sockets are given a linger timeout
     /// of zero.
     ///
+    /// This maps to the inverse of the `ZMQ_BLOCKY` option. It makes the
+    /// context termination non-blocking for the sockets that did not specify
+    /// a linger period, which prevents a leaked socket from hanging the
+    /// termination indefinitely.
+    ///
+    /// # Message Loss
+    /// The messages that were queued but not yet transferred to a network
+    /// peer are silently discarded when such a socket is closed. Sockets that
+    /// must deliver their messages should specify an explicit linger period.
+    ///
     /// # Default
     /// The default value is `false`.
     ///
     /// # Usage Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::Ctx;
     ///
     /// let ctx = Ctx::new();
@@ -533,6 +1015,111 @@ impl Ctx {
         self.raw.as_ref().set_bool(RawCtxOption::Blocky, !enabled)
     }
 
+    /// Returns `true` if the new sockets are IPv6 enabled.
+    pub fn ipv6(&self) -> bool {
+        self.raw.as_ref().get_bool(RawCtxOption::IPV6)
+    }
+
+    /// When set to `true`, all new sockets are IPv6 enabled.
+    ///
+    /// This is only a default, the sockets created afterwards inherit the
+    /// value on creation. Specifying the `ipv6` option of a socket, either
+    /// via its builder or its setter, takes precedence over this value.
+    /// Sockets that were already created are not affected.
+    ///
+    /// The same applies to [`set_no_linger`], since the linger period is
+    /// also a socket option.
+    ///
+    /// # Default
+    /// The default value is `true`.
+    ///
+    /// # Usage Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, Ctx, Server, ServerBuilder};
+    ///
+    /// let ctx = Ctx::new();
+    /// assert_eq!(ctx.ipv6(), true);
+    ///
+    /// ctx.set_ipv6(false)?;
+    /// let server = Server::with_ctx(&ctx)?;
+    /// assert_eq!(server.ipv6()?, false);
+    ///
+    /// // The socket option takes precedence.
+    /// let server = ServerBuilder::new().ipv6(true).with_ctx(&ctx)?;
+    /// assert_eq!(server.ipv6()?, true);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_no_linger`]: #method.set_no_linger
+    pub fn set_ipv6(&self, enabled: bool) -> Result<(), Error> {
+        self.raw.as_ref().set_bool(RawCtxOption::IPV6, enabled)
+    }
+
+    /// Returns `true` if the zero-copy receive strategy is enabled.
+    ///
+    /// # Returned Error Variants
+    /// * [`Unsupported`] (if *libzmq* is older than 4.3.3)
+    ///
+    /// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+    pub fn zero_copy_recv(&self) -> Result<bool, Error> {
+        check_zero_copy_recv()?;
+        Ok(self.raw.as_ref().get_bool(RawCtxOption::ZeroCopyRecv))
+    }
+
+    /// When set to `false`, the sockets no longer use the zero-copy
+    /// receive strategy.
+    ///
+    /// With zero-copy, the large messages reference the buffer in which
+    /// they were decoded instead of being copied out of it. Since a buffer
+    /// is only released once all of its messages are dropped, a few
+    /// messages kept alive can hold on to many large buffers, which inflates
+    /// the memory usage when there are many connections. Disabling it copies
+    /// every message out of the decoding buffer, which trades some
+    /// throughput for a lower and more predictable memory usage.
+    ///
+    /// This maps to the `ZMQ_ZERO_COPY_RECV` option, which is a draft option
+    /// of libzmq 4.3.3. It only affects the sockets created afterwards.
+    ///
+    /// # Returned Error Variants
+    /// * [`Unsupported`] (if *libzmq* is older than 4.3.3)
+    ///
+    /// # Default
+    /// The default value is `true`.
+    ///
+    /// # Usage Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::*;
+    ///
+    /// let ctx = Ctx::new();
+    ///
+    /// match ctx.set_zero_copy_recv(false) {
+    ///     Ok(()) => assert_eq!(ctx.zero_copy_recv()?, false),
+    ///     Err(err) => match err.kind() {
+    ///         ErrorKind::Unsupported { .. } => (),
+    ///         _ => return Err(err.into()),
+    ///     },
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+    pub fn set_zero_copy_recv(&self, enabled: bool) -> Result<(), Error> {
+        check_zero_copy_recv()?;
+        self.raw
+            .as_ref()
+            .set_bool(RawCtxOption::ZeroCopyRecv, enabled)
+    }
+
     /// Shutdown the ØMQ context context.
     ///
     /// Context shutdown will cause any blocking operations currently in
@@ -542,11 +1129,255 @@ impl Ctx {
     /// Any further operations on sockets open within context shall fail with
     /// with [`CtxTerminated`].
     ///
-    /// [`CtxTerminated`]: ../error/enum.ErrorKind.html#variant.CtxTerminated
+    /// Unlike the termination, the shutdown does not block and can be called
+    /// from any thread while sockets are still open. This allows the threads
+    /// using the sockets to unwind cleanly.
+    ///
+    /// # Termination
+    /// The context is terminated once the last handle to it is dropped. Since
+    /// each socket holds a handle to its context, this happens after all the
+    /// sockets were dropped and their pending messages were either sent or
+    /// discarded according to their linger period (see [`set_no_linger`]).
+    ///
+    /// # Usage Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::thread;
+    ///
+    /// let ctx = Ctx::new();
+    /// let server = Server::with_ctx(&ctx)?;
+    ///
+    /// let handle = thread::spawn(move || server.recv_msg());
+    /// ctx.shutdown();
+    ///
+    /// let err = handle.join().unwrap().unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::CtxTerminated);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`set_no_linger`]: #method.set_no_linger
     pub fn shutdown(&self) {
         self.raw.shutdown()
     }
 
+    /// Shutdown the ØMQ context and wait for its termination for at most
+    /// `timeout`.
+    ///
+    /// This consumes the handle, so the context is terminated once the
+    /// handles held by the other clones and by the sockets were dropped.
+    /// The blocking termination is performed on a helper thread, which
+    /// allows giving up the wait when the sockets linger for too long.
+    ///
+    /// If the timeout expires, the termination is not cancelled: the context
+    /// will still be terminated once its sockets are dropped and their
+    /// linger period expires. To bound that period as well, set a linger on
+    /// the sockets or use [`set_no_linger`].
+    ///
+    /// # Returned Error Variants
+    /// * [`WouldBlock`] (if the timeout expired)
+    ///
+    /// In this case, the error contains the number of sockets of the context
+    /// that were still open. If it is zero, the termination was waiting on
+    /// the messages of closed sockets.
+    ///
+    /// # Usage Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::time::Duration;
+    ///
+    /// let ctx = Ctx::new();
+    /// let server = Server::with_ctx(&ctx)?;
+    ///
+    /// // The server is still open.
+    /// let err = ctx.clone()
+    ///     .terminate_timeout(Duration::from_millis(10))
+    ///     .unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    ///
+    /// drop(server);
+    /// ctx.terminate_timeout(Duration::from_secs(1))?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    /// [`set_no_linger`]: #method.set_no_linger
+    pub fn terminate_timeout(
+        self,
+        timeout: Duration,
+    ) -> Result<(), Error<usize>> {
+        let deadline = Instant::now() + timeout;
+        let state = Arc::clone(&self.raw.state);
+
+        self.shutdown();
+        // If this was the last handle, dropping it blocks until the
+        // termination completes.
+        thread::spawn(move || drop(self));
+
+        let mut terminated = state.terminated.lock().unwrap();
+        while !*terminated {
+            let now = Instant::now();
+            if now >= deadline {
+                let open = state.sockets.load(Ordering::SeqCst);
+                return Err(Error::with_content(ErrorKind::WouldBlock, open));
+            }
+
+            terminated = state
+                .cond
+                .wait_timeout(terminated, deadline - now)
+                .unwrap()
+                .0;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of open sockets in the context.
+    ///
+    /// This includes the sockets used internally by the `ZAP` handler of the
+    /// context, which count towards the [`max_sockets`] as well.
+    ///
+    /// # Usage Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{Ctx, Client};
+    ///
+    /// let ctx = Ctx::new();
+    /// let count = ctx.socket_count();
+    ///
+    /// let client = Client::with_ctx(&ctx)?;
+    /// assert_eq!(ctx.socket_count(), count + 1);
+    ///
+    /// drop(client);
+    /// assert_eq!(ctx.socket_count(), count);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`max_sockets`]: #method.max_sockets
+    pub fn socket_count(&self) -> usize {
+        self.raw.state.sockets.load(Ordering::SeqCst)
+    }
+
+    /// Returns the information on each open socket in the context.
+    ///
+    /// This requires the `diagnostics` feature. The sockets are listed in
+    /// no particular order.
+    ///
+    /// # Usage Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, Ctx, DishBuilder};
+    ///
+    /// let ctx = Ctx::new();
+    /// let dish = DishBuilder::new().label("metrics-dish").with_ctx(&ctx)?;
+    ///
+    /// let sockets = ctx.open_sockets();
+    /// assert!(sockets.iter().any(|s| s.label() == Some("metrics-dish")));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "diagnostics")]
+    pub fn open_sockets(&self) -> Vec<SocketInfo> {
+        let registry = self.raw.registry.lock().unwrap();
+        registry.values().cloned().collect()
+    }
+
+    #[cfg_attr(not(feature = "diagnostics"), allow(unused_variables))]
+    pub(crate) fn register_socket(&self, socket: *mut c_void) {
+        self.raw.state.sockets.fetch_add(1, Ordering::SeqCst);
+
+        #[cfg(feature = "diagnostics")]
+        {
+            use std::backtrace::{Backtrace, BacktraceStatus};
+
+            let backtrace = Backtrace::capture();
+            let backtrace = match backtrace.status() {
+                BacktraceStatus::Captured => backtrace.to_string(),
+                _ => String::new(),
+            };
+            let info = SocketInfo {
+                label: None,
+                backtrace,
+            };
+            self.raw
+                .registry
+                .lock()
+                .unwrap()
+                .insert(socket as usize, info);
+        }
+    }
+
+    #[cfg_attr(not(feature = "diagnostics"), allow(unused_variables))]
+    pub(crate) fn unregister_socket(&self, socket: *mut c_void) {
+        self.raw.state.sockets.fetch_sub(1, Ordering::SeqCst);
+
+        #[cfg(feature = "diagnostics")]
+        {
+            self.raw.registry.lock().unwrap().remove(&(socket as usize));
+        }
+    }
+
+    #[cfg(feature = "diagnostics")]
+    pub(crate) fn label_socket(&self, socket: *mut c_void, label: &str) {
+        let mut registry = self.raw.registry.lock().unwrap();
+        if let Some(info) = registry.get_mut(&(socket as usize)) {
+            info.label = Some(label.to_owned());
+        }
+    }
+
+    pub(crate) fn plain_handler(&self) -> Option<PlainHandler> {
+        self.raw.plain_handler.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_plain_handler(&self, handler: Option<PlainHandler>) {
+        *self.raw.plain_handler.lock().unwrap() = handler;
+    }
+
+    pub(crate) fn domain_plain_handler(
+        &self,
+        domain: &str,
+    ) -> Option<PlainHandler> {
+        let handlers = self.raw.domain_plain_handlers.lock().unwrap();
+        handlers.get(domain).cloned()
+    }
+
+    pub(crate) fn set_domain_plain_handler(
+        &self,
+        domain: String,
+        handler: Option<PlainHandler>,
+    ) {
+        let mut handlers = self.raw.domain_plain_handlers.lock().unwrap();
+        match handler {
+            Some(handler) => handlers.insert(domain, handler),
+            None => handlers.remove(&domain),
+        };
+    }
+
+    pub(crate) fn plain_registry(&self) -> Option<Arc<PlainRegistry>> {
+        self.raw.plain_registry.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_plain_registry(&self, registry: Option<PlainRegistry>) {
+        *self.raw.plain_registry.lock().unwrap() = registry.map(Arc::new);
+    }
+
     pub(crate) fn as_ptr(&self) -> *mut c_void {
         self.raw.ctx
     }
@@ -563,3 +1394,436 @@ impl<'a> From<&'a Ctx> for Ctx {
         c.to_owned()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, *};
+
+    use std::{convert::TryInto, sync::mpsc, time::Duration};
+
+    #[test]
+    fn test_builder_options() {
+        let ctx = CtxBuilder::new()
+            .io_threads(2)
+            .max_sockets(64)
+            .max_msg_size(1024)
+            .build()
+            .unwrap();
+
+        assert_eq!(ctx.raw.get(RawCtxOption::IOThreads), 2);
+        assert_eq!(ctx.raw.get(RawCtxOption::MaxSockets), 64);
+        assert_eq!(ctx.raw.get(RawCtxOption::MaxMsgSize), 1024);
+    }
+
+    #[test]
+    fn test_builder_max_sockets_enforced() {
+        let ctx = CtxBuilder::new().max_sockets(4).build().unwrap();
+
+        // One socket is used by the `ZAP` handler.
+        let mut sockets = Vec::new();
+        let err = loop {
+            match Client::with_ctx(&ctx) {
+                Ok(client) => sockets.push(client),
+                Err(err) => break err,
+            }
+            assert!(sockets.len() < 4);
+        };
+
+        match err.kind() {
+            ErrorKind::SocketLimit => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_global_shared_by_builders() {
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        assert_eq!(server.ctx(), Ctx::global());
+        assert_eq!(client.ctx(), Ctx::global());
+
+        // Inproc only works within the same context.
+        client.send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_set_global_after_use() {
+        Ctx::global();
+
+        let err = Ctx::set_global(Ctx::new()).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_ipv6_inherited() {
+        let ctx = CtxBuilder::new().ipv6(false).build().unwrap();
+        assert!(!ctx.ipv6());
+        let server = Server::with_ctx(&ctx).unwrap();
+        assert!(!server.ipv6().unwrap());
+
+        ctx.set_ipv6(true).unwrap();
+        assert!(ctx.ipv6());
+        let client = Client::with_ctx(&ctx).unwrap();
+        assert!(client.ipv6().unwrap());
+
+        // Already created sockets keep their value.
+        assert!(!server.ipv6().unwrap());
+    }
+
+    #[test]
+    fn test_zero_copy_recv() {
+        let ctx = Ctx::new();
+        if version() < (4, 3, 3) {
+            let unsupported = ErrorKind::Unsupported {
+                msg: "zero copy receive requires libzmq 4.3.3",
+            };
+            let err = ctx.zero_copy_recv().unwrap_err();
+            assert_eq!(err.kind(), unsupported);
+            let err = ctx.set_zero_copy_recv(false).unwrap_err();
+            assert_eq!(err.kind(), unsupported);
+            let err = CtxBuilder::new().zero_copy_recv(false).build();
+            assert_eq!(err.unwrap_err().kind(), unsupported);
+
+            // The default still applies.
+            zero_copy_round_trip(&ctx);
+        } else {
+            assert!(ctx.zero_copy_recv().unwrap());
+            ctx.set_zero_copy_recv(false).unwrap();
+            assert!(!ctx.zero_copy_recv().unwrap());
+            ctx.set_zero_copy_recv(true).unwrap();
+            assert!(ctx.zero_copy_recv().unwrap());
+            zero_copy_round_trip(&ctx);
+
+            let ctx = CtxBuilder::new().zero_copy_recv(false).build().unwrap();
+            assert!(!ctx.zero_copy_recv().unwrap());
+            zero_copy_round_trip(&ctx);
+        }
+    }
+
+    // Messages of any size go through, with or without zero-copy.
+    fn zero_copy_round_trip(ctx: &Ctx) {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new().bind(&addr).with_ctx(ctx).unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+        let client = ClientBuilder::new().connect(bound).with_ctx(ctx).unwrap();
+
+        for &size in &[0, 1, 64, 8 * 1024, 1024 * 1024] {
+            client.send(vec![size as u8; size]).unwrap();
+            let msg = server.recv_msg().unwrap();
+            assert_eq!(msg.as_bytes(), vec![size as u8; size].as_slice());
+        }
+    }
+
+    #[test]
+    fn test_no_linger_inherited() {
+        let ctx = CtxBuilder::new().no_linger().build().unwrap();
+        let client = Client::with_ctx(&ctx).unwrap();
+        let linger = Period::Finite(Duration::from_millis(0));
+        assert_eq!(client.linger().unwrap(), linger);
+    }
+
+    #[test]
+    fn test_no_linger_termination() {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            // Without a `ZAP` handler, the context is terminated as soon
+            // as the last handle is dropped.
+            let raw = RawCtx::default();
+            raw.set_bool(RawCtxOption::Blocky, false).unwrap();
+            let ctx = Ctx { raw: Arc::new(raw) };
+
+            {
+                // Nobody listens on that endpoint so the messages stay
+                // queued.
+                let addr: TcpAddr = "127.0.0.1:9".try_into().unwrap();
+                let client =
+                    ClientBuilder::new().connect(addr).with_ctx(&ctx).unwrap();
+                for _ in 0..10 {
+                    client.try_send("").unwrap();
+                }
+            }
+
+            drop(ctx);
+            sender.send(()).unwrap();
+        });
+
+        receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+    }
+
+    // A context without a `ZAP` handler, that has no internal sockets.
+    fn bare_ctx() -> Ctx {
+        Ctx {
+            raw: Arc::new(RawCtx::default()),
+        }
+    }
+
+    #[test]
+    fn test_terminate_timeout() {
+        let ctx = Ctx::new();
+        let client = Client::with_ctx(&ctx).unwrap();
+        drop(client);
+
+        ctx.terminate_timeout(Duration::from_secs(1)).unwrap();
+    }
+
+    #[test]
+    fn test_terminate_timeout_lingering() {
+        let ctx = bare_ctx();
+        {
+            // Nobody listens on that endpoint so the messages stay queued
+            // for the whole linger period.
+            let addr: TcpAddr = "127.0.0.1:9".try_into().unwrap();
+            let client = ClientBuilder::new()
+                .connect(addr)
+                .linger(Period::Finite(Duration::from_secs(10)))
+                .with_ctx(&ctx)
+                .unwrap();
+            for _ in 0..10 {
+                client.try_send("").unwrap();
+            }
+        }
+
+        let start = Instant::now();
+        let err = ctx
+            .terminate_timeout(Duration::from_millis(100))
+            .unwrap_err();
+        let elapsed = start.elapsed();
+
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(err.content(), Some(&0));
+        assert!(elapsed >= Duration::from_millis(100));
+        assert!(elapsed < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_terminate_timeout_open_socket() {
+        let ctx = bare_ctx();
+        let server = Server::with_ctx(&ctx).unwrap();
+
+        let err = ctx
+            .clone()
+            .terminate_timeout(Duration::from_millis(10))
+            .unwrap_err();
+        assert_eq!(err.content(), Some(&1));
+
+        // The context was shutdown.
+        let err = server.recv_msg().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CtxTerminated);
+
+        drop(server);
+        ctx.terminate_timeout(Duration::from_secs(1)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_thread_sched_policy() {
+        let mut builder = CtxBuilder::new();
+        builder
+            .thread_sched_policy(SchedPolicy::Other)
+            .thread_priority(0);
+        assert_eq!(
+            builder.inner.thread_sched_policy(),
+            Some(SchedPolicy::Other)
+        );
+        assert_eq!(builder.inner.thread_priority(), Some(0));
+
+        let ctx = builder.build().unwrap();
+        assert_eq!(
+            ctx.raw.get(RawCtxOption::ThreadSchedPolicy),
+            libc::SCHED_OTHER
+        );
+
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new().bind(&addr).with_ctx(&ctx).unwrap();
+        let client =
+            ClientBuilder::new().connect(&addr).with_ctx(&ctx).unwrap();
+
+        client.send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
+    // Pins the I/O thread to the first CPU, which might not be allowed.
+    #[cfg(unix)]
+    #[test]
+    #[ignore]
+    fn test_thread_affinity() {
+        let ctx = CtxBuilder::new().thread_affinity(&[0]).build().unwrap();
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new().bind(addr).with_ctx(&ctx).unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+        let client =
+            ClientBuilder::new().connect(bound).with_ctx(&ctx).unwrap();
+
+        client.send("").unwrap();
+        server.recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_config_ser_de() {
+        let mut config = CtxConfig::new();
+        config.set_io_threads(Some(2));
+        config.set_max_sockets(Some(64));
+        config.set_max_msg_size(Some(1024));
+        config.set_no_linger(Some(true));
+        config.set_ipv6(Some(false));
+        config.set_thread_sched_policy(Some(SchedPolicy::Other));
+        config.set_thread_priority(Some(0));
+        config.set_thread_affinity(Some(vec![0, 1]));
+        config.set_zero_copy_recv(Some(false));
+
+        let toml = toml::to_string(&config).unwrap();
+        let de: CtxConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config, de);
+    }
+
+    #[test]
+    fn test_config_empty() {
+        let config: CtxConfig = toml::from_str("").unwrap();
+        assert_eq!(config, CtxConfig::new());
+
+        let ctx = config.build().unwrap();
+        assert_eq!(ctx.io_threads(), 1);
+        assert_eq!(ctx.max_sockets(), 1023);
+        assert_eq!(ctx.max_msg_size(), i32::max_value());
+        assert!(!ctx.no_linger());
+        assert!(ctx.ipv6());
+    }
+
+    #[test]
+    fn test_config_unknown_field() {
+        let toml = "io_thread = 2";
+        assert!(toml::from_str::<CtxConfig>(toml).is_err());
+    }
+
+    #[test]
+    fn test_build_all_from_config() {
+        #[derive(Deserialize)]
+        struct Config {
+            ctx: CtxConfig,
+            server: config::ServerConfig,
+            client: config::ClientConfig,
+        }
+
+        let config: Config = toml::from_str(
+            r#"
+            [ctx]
+            io_threads = 0
+            no_linger = true
+
+            [server]
+            bind = [{ inproc = "test_build_all_from_config" }]
+            recv_timeout = "1s"
+
+            [client]
+            connect = [{ inproc = "test_build_all_from_config" }]
+            recv_timeout = "1s"
+            "#,
+        )
+        .unwrap();
+
+        let ctx = config.ctx.build().unwrap();
+        let server = config.server.with_ctx(&ctx).unwrap();
+        let client = config.client.with_ctx(&ctx).unwrap();
+
+        client.send("ping").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+    }
+
+    #[test]
+    fn test_socket_count() {
+        let ctx = Ctx::new();
+        // The sockets of the `ZAP` handler.
+        assert_eq!(ctx.socket_count(), 2);
+
+        let server = Server::with_ctx(&ctx).unwrap();
+        let clients: Vec<Client> =
+            (0..3).map(|_| Client::with_ctx(&ctx).unwrap()).collect();
+        assert_eq!(ctx.socket_count(), 6);
+
+        // Clones share the same socket.
+        let cloned = server.clone();
+        drop(server);
+        assert_eq!(ctx.socket_count(), 6);
+
+        drop(cloned);
+        drop(clients);
+        assert_eq!(ctx.socket_count(), 2);
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    fn test_open_sockets_labels() {
+        let ctx = Ctx::new();
+        let server = ServerBuilder::new()
+            .label("test-server")
+            .with_ctx(&ctx)
+            .unwrap();
+        let client = ClientBuilder::new().with_ctx(&ctx).unwrap();
+
+        let sockets = ctx.open_sockets();
+        assert_eq!(sockets.len(), ctx.socket_count());
+        let labels: Vec<_> =
+            sockets.iter().filter_map(SocketInfo::label).collect();
+        assert_eq!(labels, vec!["test-server"]);
+
+        drop(server);
+        drop(client);
+        assert!(ctx.open_sockets().iter().all(|s| s.label().is_none()));
+    }
+
+    #[test]
+    fn test_shutdown_unblocks_recv() {
+        let ctx = Ctx::new();
+        let server = Server::with_ctx(&ctx).unwrap();
+        let client = Client::with_ctx(&ctx).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let result = server.recv_msg();
+            sender.send(()).unwrap();
+            result
+        });
+
+        // Give the thread time to block.
+        thread::sleep(Duration::from_millis(50));
+        ctx.shutdown();
+
+        receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        let err = handle.join().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CtxTerminated);
+
+        // Further operations also fail.
+        let err = client.try_send("").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CtxTerminated);
+    }
+
+    #[test]
+    fn test_inproc_without_io_threads() {
+        let ctx = CtxBuilder::new().io_threads(0).build().unwrap();
+        assert_eq!(ctx.io_threads(), 0);
+
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new().bind(&addr).with_ctx(&ctx).unwrap();
+        let client =
+            ClientBuilder::new().connect(&addr).with_ctx(&ctx).unwrap();
+
+        for _ in 0..10 {
+            client.send("ping").unwrap();
+            let mut msg = server.recv_msg().unwrap();
+            assert_eq!(msg.to_str().unwrap(), "ping");
+            server.send(msg).unwrap();
+            msg = client.recv_msg().unwrap();
+            assert_eq!(msg.to_str().unwrap(), "ping");
+        }
+    }
+}
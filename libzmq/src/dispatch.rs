@@ -0,0 +1,354 @@
+//! Dispatching of `Dish` messages based on their group.
+
+use crate::{
+    error::{Error, ErrorKind},
+    group::GroupOwned,
+    prelude::RecvMsg,
+    Dish, Msg,
+};
+
+use std::{collections::HashMap, convert::TryInto};
+
+type Handler<'a, E> = Box<dyn FnMut(Msg) -> Result<(), E> + 'a>;
+
+/// Dispatches messages to handlers based on their group.
+///
+/// Handlers are registered either for an exact group or for a prefix.
+/// A prefix is matched on whole dot-separated segments, so that the
+/// prefix `"metrics"` matches `"metrics.cpu"` and `"metrics.cpu.load"`,
+/// but neither `"metrics"` itself nor `"metricsx.cpu"`. This is akin to
+/// a `"metrics.*"` pattern.
+///
+/// A message is routed to, in order of priority:
+/// * the handler of its exact group,
+/// * the handler of its longest matching prefix,
+/// * the fallback handler.
+///
+/// A message that matches no handler is dropped.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{group, Dispatcher, Msg};
+///
+/// let mut status = 0;
+/// let mut metrics = 0;
+/// let mut others = 0;
+///
+/// let mut dispatcher = Dispatcher::new();
+/// dispatcher
+///     .exact(group!("status"), |_msg| {
+///         status += 1;
+///         Ok::<_, libzmq::Error>(())
+///     })?
+///     .prefix("metrics", |_msg| {
+///         metrics += 1;
+///         Ok(())
+///     })?
+///     .fallback(|_msg| {
+///         others += 1;
+///         Ok(())
+///     });
+///
+/// for group in &[group!("status"), group!("metrics.cpu"), group!("other")] {
+///     let mut msg = Msg::new();
+///     msg.set_group(*group);
+///     dispatcher.route(msg)?;
+/// }
+///
+/// drop(dispatcher);
+/// assert_eq!((status, metrics, others), (1, 1, 1));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub struct Dispatcher<'a, E> {
+    exact: HashMap<GroupOwned, Handler<'a, E>>,
+    // Sorted by decreasing length so that the longest prefix matches first.
+    prefixes: Vec<(String, Handler<'a, E>)>,
+    fallback: Option<Handler<'a, E>>,
+}
+
+impl<'a, E> Dispatcher<'a, E> {
+    /// Creates a `Dispatcher` without any handler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a handler for the messages of the specified group.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the group is invalid or already has a handler)
+    ///
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn exact<G, F>(
+        &mut self,
+        group: G,
+        handler: F,
+    ) -> Result<&mut Self, Error>
+    where
+        G: TryInto<GroupOwned>,
+        G::Error: Into<Error>,
+        F: FnMut(Msg) -> Result<(), E> + 'a,
+    {
+        let group = group.try_into().map_err(Into::into)?;
+        if self.exact.contains_key(&group) {
+            return Err(Error::new(ErrorKind::InvalidInput {
+                msg: "group already has a handler",
+            }));
+        }
+
+        self.exact.insert(group, Box::new(handler));
+        Ok(self)
+    }
+
+    /// Registers a handler for the messages whose group starts with the
+    /// specified dot-separated segments.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the prefix is empty, contains an empty segment,
+    ///     is not a valid group or already has a handler)
+    ///
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn prefix<S, F>(
+        &mut self,
+        prefix: S,
+        handler: F,
+    ) -> Result<&mut Self, Error>
+    where
+        S: Into<String>,
+        F: FnMut(Msg) -> Result<(), E> + 'a,
+    {
+        let prefix = prefix.into();
+        if prefix.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput {
+                msg: "prefix cannot be empty",
+            }));
+        }
+        if prefix.split('.').any(str::is_empty) {
+            return Err(Error::new(ErrorKind::InvalidInput {
+                msg: "prefix cannot contain an empty segment",
+            }));
+        }
+        prefix.parse::<GroupOwned>()?;
+
+        if self.prefixes.iter().any(|(p, _)| *p == prefix) {
+            return Err(Error::new(ErrorKind::InvalidInput {
+                msg: "prefix already has a handler",
+            }));
+        }
+
+        let index = self
+            .prefixes
+            .iter()
+            .position(|(p, _)| p.len() < prefix.len())
+            .unwrap_or(self.prefixes.len());
+        self.prefixes.insert(index, (prefix, Box::new(handler)));
+        Ok(self)
+    }
+
+    /// Registers the handler for the messages that match neither an exact
+    /// group nor a prefix, replacing the previous one.
+    pub fn fallback<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: FnMut(Msg) -> Result<(), E> + 'a,
+    {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    /// Invokes the handler matching the group of the message.
+    ///
+    /// The message is dropped if no handler matches. Returns the result of
+    /// the handler.
+    pub fn route(&mut self, msg: Msg) -> Result<(), E> {
+        let group = msg.group().map(ToOwned::to_owned);
+
+        let handler = match group {
+            Some(group) => self.find(&group),
+            None => self.fallback.as_mut(),
+        };
+
+        match handler {
+            Some(handler) => handler(msg),
+            None => Ok(()),
+        }
+    }
+
+    /// Routes the messages received by the `Dish` until the context of the
+    /// socket is terminated.
+    ///
+    /// This uses the blocking [`incoming`] iterator. The loop stops early
+    /// at the first error, either returned by a handler or by the socket.
+    ///
+    /// [`incoming`]: prelude/trait.RecvMsg.html#method.incoming
+    pub fn run(&mut self, dish: &Dish) -> Result<(), E>
+    where
+        E: From<Error>,
+    {
+        for msg in dish.incoming() {
+            self.route(msg?)?;
+        }
+        Ok(())
+    }
+
+    fn find(&mut self, group: &GroupOwned) -> Option<&mut Handler<'a, E>> {
+        if self.exact.contains_key(group) {
+            return self.exact.get_mut(group);
+        }
+
+        let group = group.as_str();
+        let prefix = self.prefixes.iter_mut().find(|(prefix, _)| {
+            group.starts_with(prefix.as_str())
+                && group.as_bytes().get(prefix.len()) == Some(&b'.')
+        });
+
+        match prefix {
+            Some((_, handler)) => Some(handler),
+            None => self.fallback.as_mut(),
+        }
+    }
+}
+
+impl<'a, E> Default for Dispatcher<'a, E> {
+    fn default() -> Self {
+        Self {
+            exact: HashMap::new(),
+            prefixes: Vec::new(),
+            fallback: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{group, *};
+
+    use std::{cell::RefCell, time::Duration};
+
+    fn msg(group: &str) -> Msg {
+        let mut msg = Msg::new();
+        let group: &Group = group.try_into().unwrap();
+        msg.set_group(group);
+        msg
+    }
+
+    #[test]
+    fn test_dispatcher_prefix_segments() {
+        let routed = RefCell::new(vec![]);
+
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .exact("metrics.cpu", |_| {
+                routed.borrow_mut().push("exact");
+                Ok::<_, Error>(())
+            })
+            .unwrap()
+            .prefix("metrics", |_| {
+                routed.borrow_mut().push("metrics");
+                Ok(())
+            })
+            .unwrap()
+            .prefix("metrics.mem", |_| {
+                routed.borrow_mut().push("metrics.mem");
+                Ok(())
+            })
+            .unwrap()
+            .fallback(|_| {
+                routed.borrow_mut().push("fallback");
+                Ok(())
+            });
+
+        let cases = vec![
+            ("metrics.cpu", "exact"),
+            ("metrics.disk", "metrics"),
+            ("metrics.cpu.load", "metrics"),
+            ("metrics.mem.free", "metrics.mem"),
+            ("metrics.memory", "metrics"),
+            ("metrics", "fallback"),
+            ("metricsx.cpu", "fallback"),
+            ("metrics.", "metrics"),
+            ("other", "fallback"),
+        ];
+
+        for (group, expected) in cases {
+            dispatcher.route(msg(group)).unwrap();
+            assert_eq!(routed.borrow_mut().pop(), Some(expected), "{}", group);
+        }
+
+        // Messages without a group go to the fallback.
+        dispatcher.route(Msg::new()).unwrap();
+        assert_eq!(routed.borrow_mut().pop(), Some("fallback"));
+    }
+
+    #[test]
+    fn test_dispatcher_registration_conflicts() {
+        let mut dispatcher = Dispatcher::<Error>::new();
+        dispatcher.exact("status", |_| Ok(())).unwrap();
+        dispatcher.prefix("status", |_| Ok(())).unwrap();
+
+        let err = dispatcher.exact("status", |_| Ok(())).err().unwrap();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+        assert!(dispatcher.prefix("status", |_| Ok(())).is_err());
+
+        for prefix in &["", ".status", "status.", "a..b", "nul\0byte"] {
+            assert!(dispatcher.prefix(*prefix, |_| Ok(())).is_err());
+        }
+    }
+
+    #[test]
+    fn test_dispatcher_without_fallback() {
+        let mut dispatcher = Dispatcher::<Error>::new();
+        dispatcher
+            .exact("status", |_| panic!("unexpected msg"))
+            .unwrap();
+
+        dispatcher.route(msg("other")).unwrap();
+    }
+
+    #[test]
+    fn test_dispatcher_run() {
+        let addr = InprocAddr::new_unique();
+        let radio = RadioBuilder::new().bind(&addr).build().unwrap();
+        let dish = DishBuilder::new()
+            .connect(&addr)
+            .join(group!("status"))
+            .build()
+            .unwrap();
+
+        let mut count = 0;
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .exact(group!("status"), |msg| {
+                assert_eq!(msg.to_str().unwrap(), "msg");
+                count += 1;
+                if count == 3 {
+                    Err(Error::new(ErrorKind::Interrupted))
+                } else {
+                    Ok(())
+                }
+            })
+            .unwrap();
+
+        let transmit = std::thread::spawn(move || {
+            for _ in 0..100 {
+                radio.transmit(group!("status"), "msg").unwrap();
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        });
+
+        // The handler error stops the loop.
+        let err = dispatcher.run(&dish).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+        drop(dispatcher);
+        assert_eq!(count, 3);
+
+        transmit.join().unwrap();
+    }
+}
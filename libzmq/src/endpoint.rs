@@ -1,10 +1,11 @@
-use failure::Fail;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use uuid::Uuid;
 
+#[cfg(unix)]
+use std::path::{Component, Path, PathBuf};
 use std::{
     convert::TryFrom,
-    fmt,
+    error, fmt,
     net::{self, IpAddr, Ipv4Addr, Ipv6Addr},
     option,
     str::{self, FromStr},
@@ -14,6 +15,18 @@ use std::{
 /// The maximum number of characters in a `inproc` address.
 pub const INPROC_MAX_SIZE: usize = 256;
 
+/// The maximum number of bytes in a `ipc` address.
+///
+/// This is the size of `sun_path` minus the terminating null byte.
+#[cfg(all(unix, any(target_os = "linux", target_os = "android")))]
+pub const IPC_MAX_SIZE: usize = 107;
+
+/// The maximum number of bytes in a `ipc` address.
+///
+/// This is the size of `sun_path` minus the terminating null byte.
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+pub const IPC_MAX_SIZE: usize = 103;
+
 /// A trait equivalent to `IntoIter<Item=Into<IpAddr>>` for `std::net::*` types.
 pub trait IntoIpAddrs {
     /// Returned iterator over ip addresses which this type may correspond
@@ -78,22 +91,49 @@ where
 /// An error that occurs when an address cannot be parsed.
 ///
 /// The error contains a message detailling the source of the error.
-#[derive(Debug, Fail)]
-#[fail(display = "cannot parse address : {}", msg)]
+///
+/// When parsing an [`Endpoint`], the error also contains the offending input.
+///
+/// [`Endpoint`]: enum.Endpoint.html
+#[derive(Debug)]
 pub struct AddrParseError {
     msg: &'static str,
+    input: Option<String>,
 }
 
 impl AddrParseError {
     fn new(msg: &'static str) -> Self {
-        Self { msg }
+        Self { msg, input: None }
+    }
+
+    fn with_input(mut self, input: &str) -> Self {
+        self.input = Some(input.to_owned());
+        self
     }
 
     pub fn msg(&self) -> &'static str {
         self.msg
     }
+
+    /// Returns the input that could not be parsed, if known.
+    pub fn input(&self) -> Option<&str> {
+        self.input.as_deref()
+    }
+}
+
+impl fmt::Display for AddrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.input {
+            Some(ref input) => {
+                write!(f, "cannot parse address `{}` : {}", input, self.msg)
+            }
+            None => write!(f, "cannot parse address : {}", self.msg),
+        }
+    }
 }
 
+impl error::Error for AddrParseError {}
+
 macro_rules! serde_display_tryfrom {
     ($name:ident) => {
         impl Serialize for $name {
@@ -147,14 +187,16 @@ macro_rules! tryfrom_fromstr {
 /// It can represente a network interface, a DNS address or
 /// a IP hostname depending on the context.
 ///
-/// The hostname must be strictly alpha-numeric except for the `-` character
-/// that is allowed.
+/// The hostname must be made of dot separated labels as described in
+/// [`RFC 1123`]. Each label is between 1 and 63 bytes long, is strictly
+/// alpha-numeric except for the `-` character, which cannot start or end
+/// a label. The whole hostname is at most 253 bytes long.
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::addr::Hostname;
 /// use std::convert::TryInto;
 ///
@@ -164,10 +206,17 @@ macro_rules! tryfrom_fromstr {
 /// let dns: Hostname = "server-name".try_into()?;
 /// // This is a IPv4 hostname
 /// let localhost: Hostname = "localhost".try_into()?;
+/// // This is a fully qualified domain name.
+/// let fqdn: Hostname = "broker.example.com".try_into()?;
+///
+/// assert!(Hostname::new("-broker.example.com").is_err());
+/// assert!(Hostname::new("broker..example.com").is_err());
 /// #
 /// #     Ok(())
 /// # }
 /// ```
+///
+/// [`RFC 1123`]: https://tools.ietf.org/html/rfc1123#page-13
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Hostname {
     name: String,
@@ -180,24 +229,47 @@ impl Hostname {
     {
         let name = name.into();
 
-        if !name.is_empty() {
-            for c in name.as_str().chars() {
+        if name.is_empty() {
+            return Err(AddrParseError::new("empty hostname"));
+        }
+        if name.len() > 253 {
+            return Err(AddrParseError::new("hostname is too long"));
+        }
+
+        for label in name.split('.') {
+            if label.is_empty() {
+                return Err(AddrParseError::new("empty hostname label"));
+            }
+            if label.len() > 63 {
+                return Err(AddrParseError::new("hostname label is too long"));
+            }
+            if label.starts_with('-') || label.ends_with('-') {
+                return Err(AddrParseError::new(
+                    "invalid `-` in hostname label",
+                ));
+            }
+            for c in label.chars() {
                 if !c.is_ascii_alphanumeric() && c != '-' {
                     return Err(AddrParseError::new(
                         "hostname contains illegal char",
                     ));
                 }
             }
-
-            Ok(Self { name })
-        } else {
-            Err(AddrParseError::new("empty hostname"))
         }
+
+        Ok(Self { name })
     }
 
     pub fn as_str(&self) -> &str {
         self.name.as_str()
     }
+
+    // Since hostnames are restricted to ASCII, this is still valid.
+    fn to_lowercase(&self) -> Self {
+        Self {
+            name: self.name.to_ascii_lowercase(),
+        }
+    }
 }
 
 impl FromStr for Hostname {
@@ -240,9 +312,9 @@ impl<'a> TryFrom<&'a str> for Hostname {
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::addr::Port;
 /// use std::convert::TryInto;
 ///
@@ -252,6 +324,16 @@ impl<'a> TryFrom<&'a str> for Hostname {
 /// #     Ok(())
 /// # }
 /// ```
+///
+/// # Ephemeral Ports
+/// When bound, an `Unspecified` port is assigned by the system. The assigned
+/// port can then be retrieved via the typed `last_endpoint` of the socket,
+/// see [`Endpoint::port`].
+///
+/// Unlike *czmq*, *libzmq* does not support the `!` random port nor the
+/// `[min-max]` port range syntaxes.
+///
+/// [`Endpoint::port`]: enum.Endpoint.html#method.port
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Port {
     /// An specified port number.
@@ -276,6 +358,13 @@ impl Port {
             false
         }
     }
+    /// Returns the port number, if specified.
+    pub fn number(self) -> Option<u16> {
+        match self {
+            Port::Specified(num) => Some(num),
+            Port::Unspecified => None,
+        }
+    }
 }
 
 impl FromStr for Port {
@@ -312,9 +401,9 @@ serde_display_tryfrom!(Port);
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::addr::Interface;
 /// use std::convert::TryInto;
 ///
@@ -362,15 +451,22 @@ serde_display_tryfrom!(Interface);
 
 /// A socket address with an [`Interface`] and a [`Port`].
 ///
+/// IPv6 addresses must be enclosed in brackets and can specify a
+/// scope id, which is either a network interface or its numeric index.
+///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::addr::SocketAddr;
 /// use std::convert::TryInto;
 ///
 /// let host: SocketAddr = "127.0.0.1:3000".try_into()?;
+///
+/// let host: SocketAddr = "[fe80::1%eth0]:3000".try_into()?;
+/// assert_eq!(host.scope_id(), Some("eth0"));
+/// assert_eq!(host.to_string(), "[fe80::1%eth0]:3000");
 /// #
 /// #     Ok(())
 /// # }
@@ -382,11 +478,25 @@ serde_display_tryfrom!(Interface);
 pub struct SocketAddr {
     interface: Interface,
     port: Port,
+    scope_id: Option<Hostname>,
 }
 
 impl SocketAddr {
     pub fn new(interface: Interface, port: Port) -> Self {
-        Self { interface, port }
+        Self {
+            interface,
+            port,
+            scope_id: None,
+        }
+    }
+
+    /// Create a new IPv6 `SocketAddr` with the specified scope id.
+    pub fn with_scope_id(ip: Ipv6Addr, scope_id: Hostname, port: Port) -> Self {
+        Self {
+            interface: Interface::Ip(IpAddr::V6(ip)),
+            port,
+            scope_id: Some(scope_id),
+        }
     }
 
     pub fn interface(&self) -> &Interface {
@@ -396,6 +506,36 @@ impl SocketAddr {
     pub fn port(&self) -> Port {
         self.port
     }
+
+    /// Returns the scope id of the IPv6 address, if any.
+    pub fn scope_id(&self) -> Option<&str> {
+        self.scope_id.as_ref().map(Hostname::as_str)
+    }
+
+    fn is_ipv6(&self) -> bool {
+        if let Interface::Ip(IpAddr::V6(_)) = self.interface {
+            true
+        } else {
+            false
+        }
+    }
+
+    // The scope id is a network interface name, which is case sensitive,
+    // so it is left as is.
+    fn normalize(&self) -> Self {
+        let interface = match &self.interface {
+            Interface::Hostname(name) => {
+                Interface::Hostname(name.to_lowercase())
+            }
+            Interface::Ip(ip) => Interface::Ip(*ip),
+        };
+
+        Self {
+            interface,
+            port: self.port,
+            scope_id: self.scope_id.clone(),
+        }
+    }
 }
 
 impl FromStr for SocketAddr {
@@ -403,26 +543,36 @@ impl FromStr for SocketAddr {
     fn from_str(s: &str) -> Result<Self, AddrParseError> {
         // We reverse search so that we don't have to deal will IPv6 syntax.
         if let Some(mid) = s.rfind(':') {
-            let addr = {
-                // Check for IPv6.
-                if s.chars().nth(0).unwrap() == '['
-                    && s.chars().nth(mid - 1).unwrap() == ']'
-                {
-                    let interface = Interface::from_str(&s[1..mid - 1])?;
-                    let port = Port::from_str(&s[mid + 1..])?;
-
-                    Self { interface, port }
-                } else {
-                    let interface = Interface::from_str(&s[0..mid])?;
-                    let port = Port::from_str(&s[mid + 1..])?;
-
-                    Self { interface, port }
-                }
-            };
+            let port = Port::from_str(&s[mid + 1..])?;
+
+            // Check for IPv6.
+            if s.starts_with('[') && s[..mid].ends_with(']') {
+                let inner = &s[1..mid - 1];
+                let (ip, scope_id) = match inner.find('%') {
+                    Some(index) => {
+                        let scope_id = Hostname::from_str(&inner[index + 1..])
+                            .map_err(|_| {
+                                AddrParseError::new("invalid scope id")
+                            })?;
+                        (&inner[..index], Some(scope_id))
+                    }
+                    None => (inner, None),
+                };
+                let ip = Ipv6Addr::from_str(ip)
+                    .map_err(|_| AddrParseError::new("invalid IPv6 addr"))?;
+
+                Ok(Self {
+                    interface: Interface::Ip(IpAddr::V6(ip)),
+                    port,
+                    scope_id,
+                })
+            } else {
+                let interface = Interface::from_str(&s[0..mid])?;
 
-            Ok(addr)
+                Ok(Self::new(interface, port))
+            }
         } else {
-            Err(AddrParseError::new("invalid addr format"))
+            Err(AddrParseError::new("missing port"))
         }
     }
 }
@@ -431,7 +581,16 @@ tryfrom_fromstr!(SocketAddr);
 
 impl fmt::Display for SocketAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}:{}", self.interface, self.port)
+        if self.is_ipv6() {
+            match self.scope_id {
+                Some(ref scope_id) => {
+                    write!(f, "[{}%{}]:{}", self.interface, scope_id, self.port)
+                }
+                None => write!(f, "[{}]:{}", self.interface, self.port),
+            }
+        } else {
+            write!(f, "{}:{}", self.interface, self.port)
+        }
     }
 }
 
@@ -443,6 +602,21 @@ impl From<net::SocketAddr> for SocketAddr {
     }
 }
 
+impl<I> From<(I, u16)> for SocketAddr
+where
+    I: Into<IpAddr>,
+{
+    fn from((ip, port): (I, u16)) -> Self {
+        Self::new(Interface::Ip(ip.into()), Port::Specified(port))
+    }
+}
+
+impl From<(Hostname, u16)> for SocketAddr {
+    fn from((hostname, port): (Hostname, u16)) -> Self {
+        Self::new(Interface::Hostname(hostname), Port::Specified(port))
+    }
+}
+
 impl<'a> From<&'a SocketAddr> for SocketAddr {
     fn from(addr: &'a SocketAddr) -> Self {
         addr.to_owned()
@@ -460,9 +634,9 @@ impl<'a> From<&'a SocketAddr> for SocketAddr {
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::addr::SrcAddr;
 /// use std::convert::TryInto;
 ///
@@ -514,6 +688,12 @@ impl From<SocketAddr> for SrcAddr {
     }
 }
 
+impl From<Interface> for SrcAddr {
+    fn from(interface: Interface) -> Self {
+        SrcAddr::Interface(interface)
+    }
+}
+
 impl<'a> From<&'a SocketAddr> for SrcAddr {
     fn from(addr: &'a SocketAddr) -> Self {
         SrcAddr::Socket(addr.to_owned())
@@ -529,13 +709,13 @@ impl<'a> From<&'a SrcAddr> for SrcAddr {
 /// A socket address with the `TCP` transport.
 ///
 /// # Supported Sockets
-/// [`Dish`], [`Radio`], [`Client`] and [`Server]
+/// [`Dish`], [`Radio`], [`Client`] and [`Server`]
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::TcpAddr;
 /// use std::convert::TryInto;
 ///
@@ -548,6 +728,53 @@ impl<'a> From<&'a SrcAddr> for SrcAddr {
 /// #     Ok(())
 /// # }
 /// ```
+///
+/// # Source Address
+/// On multi-homed hosts, the local interface or address used by an outgoing
+/// connection can be specified via a source address, using the
+/// `source;host` syntax. The source can be a network interface name, an IP
+/// address, or a socket address to also specify the local port.
+///
+/// A source address only applies to `connect`, binding to such a `TcpAddr`
+/// fails with [`InvalidInput`].
+///
+/// # Hostnames
+/// The host can also be a DNS hostname, which is stored as is and resolved
+/// by *libzmq* on connection, and again on each reconnection. Whether the
+/// host is literal or a hostname is given by the [`Interface`] of the
+/// [`host`].
+///
+/// When binding, the hostname is instead the name of a network interface,
+/// such as `eth0`. Binding to a DNS hostname fails with
+/// [`AddrNotAvailable`].
+///
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{TcpAddr, addr::Hostname};
+/// use std::{convert::TryInto, net::{IpAddr, Ipv4Addr}};
+///
+/// let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+/// let addr: TcpAddr = (ip, 5555).into();
+/// assert_eq!(addr.to_string(), "127.0.0.1:5555");
+///
+/// let hostname: Hostname = "broker.example.com".try_into()?;
+/// let addr: TcpAddr = (hostname, 5555).into();
+/// assert!(addr.is_hostname());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Interface`]: addr/enum.Interface.html
+/// [`host`]: #method.host
+/// [`AddrNotAvailable`]: enum.ErrorKind.html#variant.AddrNotAvailable
+/// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+/// [`Dish`]: struct.Dish.html
+/// [`Radio`]: struct.Radio.html
+/// [`Client`]: struct.Client.html
+/// [`Server`]: struct.Server.html
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct TcpAddr {
     src: Option<SrcAddr>,
@@ -579,6 +806,44 @@ impl TcpAddr {
     pub fn src(&self) -> Option<&SrcAddr> {
         self.src.as_ref()
     }
+
+    /// Create a new `TcpAddr` with a system assigned ephemeral port.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::net::Ipv4Addr;
+    ///
+    /// let server = ServerBuilder::new()
+    ///     .bind(TcpAddr::ephemeral(Ipv4Addr::LOCALHOST))
+    ///     .build()?;
+    ///
+    /// let bound = server.last_endpoint()?.unwrap();
+    /// assert!(bound.port().is_some());
+    ///
+    /// let client = ClientBuilder::new().connect(bound).build()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn ephemeral<I>(ip: I) -> Self
+    where
+        I: Into<IpAddr>,
+    {
+        Self::new(SocketAddr::new(Interface::Ip(ip.into()), Port::Unspecified))
+    }
+
+    /// Returns `true` if the host is a hostname instead of an IP address.
+    pub fn is_hostname(&self) -> bool {
+        if let Interface::Hostname(_) = self.host.interface() {
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl FromStr for TcpAddr {
@@ -602,7 +867,7 @@ tryfrom_fromstr!(TcpAddr);
 impl fmt::Display for TcpAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.src.is_some() {
-            write!(f, "{};{}", self.host, self.src.as_ref().unwrap())
+            write!(f, "{};{}", self.src.as_ref().unwrap(), self.host)
         } else {
             write!(f, "{}", self.host)
         }
@@ -617,6 +882,27 @@ impl From<SocketAddr> for TcpAddr {
     }
 }
 
+impl From<net::SocketAddr> for TcpAddr {
+    fn from(addr: net::SocketAddr) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl<I> From<(I, u16)> for TcpAddr
+where
+    I: Into<IpAddr>,
+{
+    fn from(addr: (I, u16)) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl From<(Hostname, u16)> for TcpAddr {
+    fn from(addr: (Hostname, u16)) -> Self {
+        Self::new(addr)
+    }
+}
+
 impl IntoIterator for TcpAddr {
     type Item = Self;
     type IntoIter = option::IntoIter<Self>;
@@ -654,9 +940,9 @@ impl<'a> From<&'a TcpAddr> for Endpoint {
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::UdpAddr;
 /// use std::convert::TryInto;
 ///
@@ -678,9 +964,9 @@ pub struct UdpAddr {
 impl UdpAddr {
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{UdpAddr, addr::SocketAddr};
     /// use std::convert::TryInto;
     ///
@@ -703,9 +989,9 @@ impl UdpAddr {
 
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{UdpAddr, addr::{SrcAddr, SocketAddr}};
     /// use std::convert::TryInto;
     ///
@@ -762,7 +1048,7 @@ tryfrom_fromstr!(UdpAddr);
 impl fmt::Display for UdpAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.src.is_some() {
-            write!(f, "{};{}", self.host, self.src.as_ref().unwrap())
+            write!(f, "{};{}", self.src.as_ref().unwrap(), self.host)
         } else {
             write!(f, "{}", self.host)
         }
@@ -807,13 +1093,46 @@ impl<'a> From<&'a UdpAddr> for Endpoint {
     }
 }
 
+// Parses the `interface;multicast-group:port` syntax of the `pgm` and `epgm`
+// transports.
+fn parse_multicast(
+    s: &str,
+) -> Result<(Option<SrcAddr>, SocketAddr), AddrParseError> {
+    let (src, host) = match s.find(';') {
+        Some(mid) => {
+            let interface = Interface::from_str(&s[..mid])
+                .map_err(|_| AddrParseError::new("invalid interface"))?;
+            (Some(SrcAddr::Interface(interface)), &s[mid + 1..])
+        }
+        None => (None, s),
+    };
+
+    let host = SocketAddr::from_str(host)?;
+    if host.port().is_unspecified() {
+        return Err(AddrParseError::new("multicast port must be specified"));
+    }
+
+    Ok((src, host))
+}
+
 /// A socket address with the `PGM` transport.
 ///
+/// The address has the form `interface;multicast-group:port`, where the
+/// interface is optional and the port must be specified.
+///
+/// # Supported Sockets
+/// *libzmq* only supports the `pgm` and `epgm` transports for the `PUB` and
+/// `SUB` family of sockets, which this crate does not provide. As a result,
+/// the [`Radio`] and [`Dish`] sockets fail with [`InvalidInput`].
+///
+/// If *libzmq* was built without `pgm` support, binding or connecting fails
+/// with [`Unsupported`] instead, see [`has`].
+///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::PgmAddr;
 /// use std::convert::TryInto;
 ///
@@ -821,11 +1140,17 @@ impl<'a> From<&'a UdpAddr> for Endpoint {
 /// // using the network interface with the address 192.168.1.1
 /// // and the PGM protocol
 /// let addr: PgmAddr = "192.168.1.1;239.192.1.1:5555".try_into()?;
+/// assert_eq!(addr.to_string(), "192.168.1.1;239.192.1.1:5555");
 /// #
 /// #     Ok(())
 /// # }
 /// ```
 ///
+/// [`Radio`]: struct.Radio.html
+/// [`Dish`]: struct.Dish.html
+/// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+/// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+/// [`has`]: fn.has.html
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct PgmAddr {
     src: Option<SrcAddr>,
@@ -841,6 +1166,13 @@ impl PgmAddr {
         Self { host, src: None }
     }
 
+    /// Specifies the network interface used to join the multicast group,
+    /// either by name or by IP.
+    pub fn add_interface(mut self, interface: Interface) -> Self {
+        self.src = Some(SrcAddr::Interface(interface));
+        self
+    }
+
     /// A source address can be specified when a client communicate with a public
     /// server from behind a private network. This allows the server's replies to
     /// be routed properly.
@@ -853,10 +1185,12 @@ impl PgmAddr {
         self
     }
 
+    /// Returns the multicast group and port.
     pub fn host(&self) -> &SocketAddr {
         &self.host
     }
 
+    /// Returns the network interface, if any.
     pub fn src(&self) -> Option<&SrcAddr> {
         self.src.as_ref()
     }
@@ -865,16 +1199,9 @@ impl PgmAddr {
 impl FromStr for PgmAddr {
     type Err = AddrParseError;
     fn from_str(s: &str) -> Result<Self, AddrParseError> {
-        if let Some(mid) = s.find(';') {
-            let src = Some(SrcAddr::from_str(&s[..mid])?);
-            let host = SocketAddr::from_str(&s[mid + 1..])?;
-
-            Ok(Self { src, host })
-        } else {
-            let host = SocketAddr::from_str(s)?;
+        let (src, host) = parse_multicast(s)?;
 
-            Ok(Self { src: None, host })
-        }
+        Ok(Self { src, host })
     }
 }
 
@@ -883,7 +1210,7 @@ tryfrom_fromstr!(PgmAddr);
 impl fmt::Display for PgmAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.src.is_some() {
-            write!(f, "{};{}", self.host, self.src.as_ref().unwrap())
+            write!(f, "{};{}", self.src.as_ref().unwrap(), self.host)
         } else {
             write!(f, "{}", self.host)
         }
@@ -930,11 +1257,22 @@ impl<'a> From<&'a PgmAddr> for Endpoint {
 
 /// A socket address with the Encapsulated `PGM` transport.
 ///
+/// This is the same as a [`PgmAddr`], except that the `PGM` datagrams are
+/// encapsulated in `UDP` datagrams.
+///
+/// # Supported Sockets
+/// *libzmq* only supports the `pgm` and `epgm` transports for the `PUB` and
+/// `SUB` family of sockets, which this crate does not provide. As a result,
+/// the [`Radio`] and [`Dish`] sockets fail with [`InvalidInput`].
+///
+/// If *libzmq* was built without `pgm` support, binding or connecting fails
+/// with [`Unsupported`] instead, see [`has`].
+///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::EpgmAddr;
 /// use std::convert::TryInto;
 ///
@@ -946,6 +1284,13 @@ impl<'a> From<&'a PgmAddr> for Endpoint {
 /// #     Ok(())
 /// # }
 /// ```
+///
+/// [`PgmAddr`]: struct.PgmAddr.html
+/// [`Radio`]: struct.Radio.html
+/// [`Dish`]: struct.Dish.html
+/// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+/// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+/// [`has`]: fn.has.html
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct EpgmAddr {
     src: Option<SrcAddr>,
@@ -961,6 +1306,13 @@ impl EpgmAddr {
         Self { host, src: None }
     }
 
+    /// Specifies the network interface used to join the multicast group,
+    /// either by name or by IP.
+    pub fn add_interface(mut self, interface: Interface) -> Self {
+        self.src = Some(SrcAddr::Interface(interface));
+        self
+    }
+
     /// A source address can be specified when a client communicate with a public
     /// server from behind a private network. This allows the server's replies to
     /// be routed properly.
@@ -973,10 +1325,12 @@ impl EpgmAddr {
         self
     }
 
+    /// Returns the multicast group and port.
     pub fn host(&self) -> &SocketAddr {
         &self.host
     }
 
+    /// Returns the network interface, if any.
     pub fn src(&self) -> Option<&SrcAddr> {
         self.src.as_ref()
     }
@@ -985,16 +1339,9 @@ impl EpgmAddr {
 impl FromStr for EpgmAddr {
     type Err = AddrParseError;
     fn from_str(s: &str) -> Result<Self, AddrParseError> {
-        if let Some(mid) = s.find(';') {
-            let src = Some(SrcAddr::from_str(&s[..mid])?);
-            let host = SocketAddr::from_str(&s[mid + 1..])?;
-
-            Ok(Self { src, host })
-        } else {
-            let host = SocketAddr::from_str(s)?;
+        let (src, host) = parse_multicast(s)?;
 
-            Ok(Self { src: None, host })
-        }
+        Ok(Self { src, host })
     }
 }
 
@@ -1003,7 +1350,7 @@ tryfrom_fromstr!(EpgmAddr);
 impl fmt::Display for EpgmAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.src.is_some() {
-            write!(f, "{};{}", self.host, self.src.as_ref().unwrap())
+            write!(f, "{};{}", self.src.as_ref().unwrap(), self.host)
         } else {
             write!(f, "{}", self.host)
         }
@@ -1055,14 +1402,19 @@ impl<'a> From<&'a EpgmAddr> for Endpoint {
 ///
 /// The `inproc` transport can only be used by sockets that share the same `Ctx`.
 ///
+/// # Bind vs. Connect
+/// Since *libzmq* 4.0, the `inproc` transport does not require the address
+/// to be bound before connecting to it. Like with the other transports,
+/// the connection is established once the address is bound.
+///
 /// # Supported Sockets
-/// [`Dish`], [`Radio`], [`Client`] and [`Server]
+/// [`Dish`], [`Radio`], [`Client`] and [`Server`]
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::InprocAddr;
 /// use std::convert::TryInto;
 ///
@@ -1076,6 +1428,10 @@ impl<'a> From<&'a EpgmAddr> for Endpoint {
 /// ```
 ///
 /// [`INPROC_MAX_SIZE`]: constant.INPROC_MAX_SIZE.html
+/// [`Dish`]: struct.Dish.html
+/// [`Radio`]: struct.Radio.html
+/// [`Client`]: struct.Client.html
+/// [`Server`]: struct.Server.html
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct InprocAddr {
     host: String,
@@ -1108,9 +1464,9 @@ impl InprocAddr {
     ///
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{prelude::*, InprocAddr, ServerBuilder};
     ///
     /// let addr = InprocAddr::new_unique();
@@ -1200,95 +1556,675 @@ impl<'a> From<&'a InprocAddr> for Endpoint {
     }
 }
 
-/// A transport and a transport-specific address supported by ØMQ.
+/// A `ipc` address used for local inter-process communication.
 ///
-/// The transport specifies the underlying protocol to use. The address
-/// specifies the transport-specific address to connect to.
+/// The `ipc` address is a path to a unix domain socket file, which is
+/// created when bound. Since *libzmq* takes endpoints as C strings, the path
+/// must be valid `UTF-8` and contain no null byte. Its length cannot exceed
+/// [`IPC_MAX_SIZE`] bytes.
 ///
-/// # Bind vs. Connect
-/// For most transports and socket types the connection is not performed
-/// immediately but as needed by ØMQ. Thus a successful call to `connect`
-/// does not mean that the connection was or could actually be established.
-/// Because of this, for most transports and socket types the order in
-/// which a server socket is bound and a client socket is connected to it
-/// does not matter.
+/// Two special forms are also supported:
+/// * A path starting with `@` is a name in the abstract namespace, which
+///     has no corresponding file. This is only supported on Linux.
+/// * The `*` wildcard, which binds to a unique temporary path. The actual
+///     path can then be retrieved via `last_endpoint`.
 ///
-/// # Summary of Transports
-/// | Transport `str` | Description                                 | Reference      |
-/// | ----------------|:-------------------------------------------:|:--------------:|
-/// | "tcp"           | unicast transport using TCP                 | [`zmq_tcp`]    |
-/// | "udp"           | UDP multicast and unicast transport         | [`zmq_udp`]    |
-/// | "ipc"           | local inter-process communication transport | [`zmq_ipc`]    |
-/// | "inproc"        | local in-process communication transport    | [`zmq_inproc`] |
-/// | "pgm", "epgm"   | reliable multicast transport using PGM      | [`zmq_pgm`]    |
-/// | "vmci"          | virtual machine communications interface    | [`zmq_vmci`]   |
+/// The `ipc` transport is only available on unix platforms.
+///
+/// # Supported Sockets
+/// [`Dish`], [`Radio`], [`Client`] and [`Server`]
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
-/// use libzmq::{TcpAddr, addr::Endpoint};
-/// use std::convert::TryInto;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::IpcAddr;
+/// use std::{convert::TryInto, path::Path};
 ///
-/// // IPv4 addr with TCP transport.
-/// let addr: TcpAddr = "127.0.0.1:9090".try_into()?;
-/// let endpoint: Endpoint = addr.into();
-/// assert!(endpoint.is_tcp());
+/// let addr: IpcAddr = "/tmp/service.sock".try_into()?;
+/// assert_eq!(addr.as_path(), Path::new("/tmp/service.sock"));
+///
+/// // A name in the abstract namespace.
+/// let addr: IpcAddr = "@service".try_into()?;
+/// assert!(addr.is_abstract());
+///
+/// let addr = IpcAddr::wildcard();
+/// assert!(addr.is_wildcard());
 /// #
 /// #     Ok(())
 /// # }
 /// ```
 ///
-/// This enum type is non-exhaustive and could have additional variants
-/// added in future. Therefore, when matching against variants of
-/// non-exhaustive enums, an extra wildcard arm must be added to account
-/// for any future variants.
-///
-/// [`zmq_tcp`]: http://api.zeromq.org/master:zmq_tcp
-/// [`zmq_udp`]: http://api.zeromq.org/master:zmq-udp
-/// [`zmq_ipc`]: http://api.zeromq.org/master:zmq_ipc
-/// [`zmq_inproc`]: http://api.zeromq.org/master:zmq_inproc
-/// [`zmq_pgm`]: http://api.zeromq.org/master:zmq_pgm
-/// [`zmq_vmci`]: http://api.zeromq.org/master:zmq_vmci
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum Endpoint {
-    /// Unicast transport using TCP, see [`zmq_tcp`].
+/// [`IPC_MAX_SIZE`]: constant.IPC_MAX_SIZE.html
+/// [`Dish`]: struct.Dish.html
+/// [`Radio`]: struct.Radio.html
+/// [`Client`]: struct.Client.html
+/// [`Server`]: struct.Server.html
+#[cfg(unix)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct IpcAddr {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl IpcAddr {
+    /// Create a new `IpcAddr` from a path.
     ///
-    /// [`zmq_tcp`]: http://api.zeromq.org/master:zmq-tcp
-    Tcp(TcpAddr),
-    /// ØMQ UDP multicast and unicast transport
+    /// The path cannot be empty or longuer than `IPC_MAX_SIZE` bytes and
+    /// must be valid `UTF-8` without any null byte.
+    pub fn new<P>(path: P) -> Result<Self, AddrParseError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let s = path
+            .to_str()
+            .ok_or_else(|| AddrParseError::new("path must be valid UTF-8"))?;
+
+        if s.is_empty() {
+            Err(AddrParseError::new("empty path"))
+        } else if s.len() > IPC_MAX_SIZE {
+            Err(AddrParseError::new(
+                "path cannot exceed `IPC_MAX_SIZE` bytes",
+            ))
+        } else if s.contains('\0') {
+            Err(AddrParseError::new("path cannot contain a null byte"))
+        } else if s == "@" {
+            Err(AddrParseError::new("empty abstract name"))
+        } else {
+            Ok(Self {
+                path: path.to_owned(),
+            })
+        }
+    }
+
+    /// Creates the `*` wildcard, which binds to a unique temporary path.
     ///
-    /// [`zmq_udp`]: http://api.zeromq.org/master:zmq-udp
-    Udp(UdpAddr),
-    /// Local in-process (inter-thread) communication transport
+    /// This is the `ipc` equivalent of a system assigned port.
     ///
-    /// [`zmq_inproc`]: http://api.zeromq.org/master:zmq-inproc
-    Inproc(InprocAddr),
-    /// Reliable multicast transport using PGM, see [`zmq_pgm`].
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, IpcAddr, ServerBuilder};
     ///
-    /// [`zmq_pgm`]: http://api.zeromq.org/master:zmq-pgm
-    Pgm(PgmAddr),
-    /// Reliable multicast transport using EPGM, see [`zmq_pgm`].
+    /// let server = ServerBuilder::new()
+    ///     .bind(IpcAddr::wildcard())
+    ///     .build()?;
     ///
-    /// [`zmq_pgm`]: http://api.zeromq.org/master:zmq-pgm
-    Epgm(EpgmAddr),
-}
-
-impl Endpoint {
-    /// Returns `true` if the endpoint uses the `Tcp` transport.
-    pub fn is_tcp(&self) -> bool {
-        if let Endpoint::Tcp(_) = self {
-            true
-        } else {
-            false
+    /// let bound = server.last_endpoint()?.unwrap();
+    /// # assert!(bound.is_ipc());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn wildcard() -> Self {
+        Self {
+            path: PathBuf::from("*"),
         }
     }
 
-    /// Returns `true` if the endpoint uses the `Ucp` transport.
-    pub fn is_udp(&self) -> bool {
-        if let Endpoint::Udp(_) = self {
+    /// Returns the path of the `IpcAddr`.
+    ///
+    /// For an abstract name, this includes the leading `@`.
+    pub fn as_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns `true` if the address is a name in the abstract namespace.
+    pub fn is_abstract(&self) -> bool {
+        self.as_str().starts_with('@')
+    }
+
+    /// Returns `true` if the address is the `*` wildcard.
+    pub fn is_wildcard(&self) -> bool {
+        self.as_str() == "*"
+    }
+
+    fn as_str(&self) -> &str {
+        // The path is checked to be valid `UTF-8` on creation.
+        self.path.to_str().unwrap()
+    }
+
+    // Abstract names and the wildcard are not paths, so they are left as is.
+    fn normalize(&self) -> Self {
+        if self.is_abstract() || self.is_wildcard() {
+            return self.clone();
+        }
+
+        let mut path: PathBuf = self
+            .path
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .collect();
+        if path.as_os_str().is_empty() {
+            path.push(".");
+        }
+
+        Self { path }
+    }
+}
+
+#[cfg(unix)]
+impl FromStr for IpcAddr {
+    type Err = AddrParseError;
+    fn from_str(s: &str) -> Result<Self, AddrParseError> {
+        Self::new(s)
+    }
+}
+
+#[cfg(unix)]
+impl fmt::Display for IpcAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+#[cfg(unix)]
+tryfrom_fromstr!(IpcAddr);
+
+#[cfg(unix)]
+serde_display_tryfrom!(IpcAddr);
+
+#[cfg(unix)]
+impl<'a> TryFrom<&'a Path> for IpcAddr {
+    type Error = AddrParseError;
+    fn try_from(path: &'a Path) -> Result<Self, AddrParseError> {
+        Self::new(path)
+    }
+}
+
+#[cfg(unix)]
+impl TryFrom<PathBuf> for IpcAddr {
+    type Error = AddrParseError;
+    fn try_from(path: PathBuf) -> Result<Self, AddrParseError> {
+        Self::new(path)
+    }
+}
+
+#[cfg(unix)]
+impl IntoIterator for IpcAddr {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+#[cfg(unix)]
+impl<'a> IntoIterator for &'a IpcAddr {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+#[cfg(unix)]
+impl From<IpcAddr> for Endpoint {
+    fn from(addr: IpcAddr) -> Endpoint {
+        Endpoint::Ipc(addr)
+    }
+}
+
+#[cfg(unix)]
+impl<'a> From<&'a IpcAddr> for Endpoint {
+    fn from(addr: &'a IpcAddr) -> Endpoint {
+        Endpoint::Ipc(addr.to_owned())
+    }
+}
+
+/// The context identifier (`CID`) of a `VMCI` address.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum VmciCid {
+    /// The `CID` of the local machine, written `@`.
+    Local,
+    /// Any `CID`, written `*`. Only valid for binding.
+    Any,
+    /// A specified `CID`.
+    Specified(u32),
+}
+
+impl FromStr for VmciCid {
+    type Err = AddrParseError;
+    fn from_str(s: &str) -> Result<Self, AddrParseError> {
+        match s {
+            "" => Err(AddrParseError::new("empty cid")),
+            "@" => Ok(VmciCid::Local),
+            "*" => Ok(VmciCid::Any),
+            _ => u32::from_str(s)
+                .map(VmciCid::Specified)
+                .map_err(|_| AddrParseError::new("invalid cid")),
+        }
+    }
+}
+
+impl fmt::Display for VmciCid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VmciCid::Local => write!(f, "@"),
+            VmciCid::Any => write!(f, "*"),
+            VmciCid::Specified(cid) => write!(f, "{}", cid),
+        }
+    }
+}
+
+/// A socket address with the `VMCI` transport.
+///
+/// The `VMCI` transport allows VMware virtual machines to communicate with
+/// their host and with each other. The address has the form `cid:port`,
+/// where the `cid` is the context identifier of a machine.
+///
+/// If *libzmq* was built without `vmci` support, binding or connecting
+/// fails with [`Unsupported`], see [`has`].
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{VmciAddr, addr::{Port, VmciCid}};
+/// use std::convert::TryInto;
+///
+/// // Connect to the port 5555 of the machine with the cid 2.
+/// let addr: VmciAddr = "2:5555".try_into()?;
+/// assert_eq!(addr.cid(), VmciCid::Specified(2));
+///
+/// // Bind to a system assigned port of the local machine.
+/// let addr = VmciAddr::new(VmciCid::Local, Port::Unspecified);
+/// assert_eq!(addr.to_string(), "@:*");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+/// [`has`]: fn.has.html
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct VmciAddr {
+    cid: VmciCid,
+    port: Port,
+}
+
+impl VmciAddr {
+    /// Create a new `VmciAddr` from a `CID` and a port.
+    pub fn new(cid: VmciCid, port: Port) -> Self {
+        Self { cid, port }
+    }
+
+    /// Returns the context identifier.
+    pub fn cid(&self) -> VmciCid {
+        self.cid
+    }
+
+    /// Returns the port.
+    pub fn port(&self) -> Port {
+        self.port
+    }
+}
+
+impl FromStr for VmciAddr {
+    type Err = AddrParseError;
+    fn from_str(s: &str) -> Result<Self, AddrParseError> {
+        if let Some(mid) = s.rfind(':') {
+            let cid = VmciCid::from_str(&s[..mid])?;
+            let port = Port::from_str(&s[mid + 1..])?;
+
+            Ok(Self { cid, port })
+        } else {
+            Err(AddrParseError::new("missing port"))
+        }
+    }
+}
+
+tryfrom_fromstr!(VmciAddr);
+
+impl fmt::Display for VmciAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.cid, self.port)
+    }
+}
+
+serde_display_tryfrom!(VmciAddr);
+
+impl IntoIterator for VmciAddr {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a VmciAddr {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl From<VmciAddr> for Endpoint {
+    fn from(addr: VmciAddr) -> Endpoint {
+        Endpoint::Vmci(addr)
+    }
+}
+
+impl<'a> From<&'a VmciAddr> for Endpoint {
+    fn from(addr: &'a VmciAddr) -> Endpoint {
+        Endpoint::Vmci(addr.to_owned())
+    }
+}
+
+/// A `TIPC` address.
+///
+/// The `TIPC` transport is a cluster communication protocol which is only
+/// available on Linux. A socket binds to a port name sequence and connects
+/// to a port name within that sequence, where both are identified by a
+/// service type. The service types `0` to `63` are reserved by `TIPC` and
+/// are rejected by *libzmq* with [`InvalidInput`].
+///
+/// When bound, a socket is also assigned a port identifier, which is
+/// returned by its `last_endpoint`. Binding to the `<*>` wildcard only
+/// assigns a random port identifier for the socket.
+///
+/// If *libzmq* was built without `tipc` support, which is always the case
+/// on other platforms than Linux, binding or connecting fails with
+/// [`Unsupported`], see [`has`].
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::TipcAddr;
+/// use std::convert::TryInto;
+///
+/// // Bind to the instances 1 to 10 of the service type 5560.
+/// let bind: TipcAddr = "{5560,1,10}".try_into()?;
+/// assert_eq!(
+///     bind,
+///     TipcAddr::NameSeq { service: 5560, lower: 1, upper: 10 }
+/// );
+///
+/// // Connect to the instance 5 of the same service type.
+/// let connect = TipcAddr::Name { service: 5560, instance: 5 };
+/// assert_eq!(connect.to_string(), "{5560,5}");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+/// [`Unsupported`]: enum.ErrorKind.html#variant.Unsupported
+/// [`has`]: fn.has.html
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TipcAddr {
+    /// A port name `{type,instance}`, used to connect.
+    Name { service: u32, instance: u32 },
+    /// A port name sequence `{type,lower,upper}`, used to bind.
+    NameSeq {
+        service: u32,
+        lower: u32,
+        upper: u32,
+    },
+    /// A port identifier `<zone.cluster.node:reference>`, used to connect.
+    PortId {
+        zone: u32,
+        cluster: u32,
+        node: u32,
+        reference: u32,
+    },
+    /// The `<*>` wildcard, used to bind to a random port identifier.
+    Wildcard,
+}
+
+fn parse_u32_list(s: &str, sep: char) -> Result<Vec<u32>, AddrParseError> {
+    s.split(sep)
+        .map(|n| {
+            u32::from_str(n.trim())
+                .map_err(|_| AddrParseError::new("invalid integer"))
+        })
+        .collect()
+}
+
+impl FromStr for TipcAddr {
+    type Err = AddrParseError;
+    fn from_str(s: &str) -> Result<Self, AddrParseError> {
+        if s == "<*>" {
+            return Ok(TipcAddr::Wildcard);
+        }
+
+        if let Some(inner) =
+            s.strip_prefix('{').and_then(|s| s.strip_suffix('}'))
+        {
+            match parse_u32_list(inner, ',')?.as_slice() {
+                [service, instance] => Ok(TipcAddr::Name {
+                    service: *service,
+                    instance: *instance,
+                }),
+                [service, lower, upper] if lower <= upper => {
+                    Ok(TipcAddr::NameSeq {
+                        service: *service,
+                        lower: *lower,
+                        upper: *upper,
+                    })
+                }
+                [_, _, _] => Err(AddrParseError::new(
+                    "lower bound cannot exceed upper bound",
+                )),
+                _ => Err(AddrParseError::new("invalid port name")),
+            }
+        } else if let Some(inner) =
+            s.strip_prefix('<').and_then(|s| s.strip_suffix('>'))
+        {
+            let mid = inner
+                .rfind(':')
+                .ok_or_else(|| AddrParseError::new("missing reference"))?;
+            let reference = u32::from_str(&inner[mid + 1..])
+                .map_err(|_| AddrParseError::new("invalid reference"))?;
+
+            match parse_u32_list(&inner[..mid], '.')?.as_slice() {
+                [zone, cluster, node] => Ok(TipcAddr::PortId {
+                    zone: *zone,
+                    cluster: *cluster,
+                    node: *node,
+                    reference,
+                }),
+                _ => Err(AddrParseError::new("invalid port id")),
+            }
+        } else {
+            Err(AddrParseError::new("invalid tipc addr"))
+        }
+    }
+}
+
+tryfrom_fromstr!(TipcAddr);
+
+impl fmt::Display for TipcAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TipcAddr::Name { service, instance } => {
+                write!(f, "{{{},{}}}", service, instance)
+            }
+            TipcAddr::NameSeq {
+                service,
+                lower,
+                upper,
+            } => write!(f, "{{{},{},{}}}", service, lower, upper),
+            TipcAddr::PortId {
+                zone,
+                cluster,
+                node,
+                reference,
+            } => write!(f, "<{}.{}.{}:{}>", zone, cluster, node, reference),
+            TipcAddr::Wildcard => write!(f, "<*>"),
+        }
+    }
+}
+
+serde_display_tryfrom!(TipcAddr);
+
+impl IntoIterator for TipcAddr {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a TipcAddr {
+    type Item = Self;
+    type IntoIter = option::IntoIter<Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Some(self).into_iter()
+    }
+}
+
+impl From<TipcAddr> for Endpoint {
+    fn from(addr: TipcAddr) -> Endpoint {
+        Endpoint::Tipc(addr)
+    }
+}
+
+impl<'a> From<&'a TipcAddr> for Endpoint {
+    fn from(addr: &'a TipcAddr) -> Endpoint {
+        Endpoint::Tipc(addr.to_owned())
+    }
+}
+
+/// A transport and a transport-specific address supported by ØMQ.
+///
+/// The transport specifies the underlying protocol to use. The address
+/// specifies the transport-specific address to connect to.
+///
+/// # Bind vs. Connect
+/// For most transports and socket types the connection is not performed
+/// immediately but as needed by ØMQ. Thus a successful call to `connect`
+/// does not mean that the connection was or could actually be established.
+/// Because of this, for most transports and socket types the order in
+/// which a server socket is bound and a client socket is connected to it
+/// does not matter.
+///
+/// # Summary of Transports
+/// | Transport `str` | Description                                 | Reference      |
+/// | ----------------|:-------------------------------------------:|:--------------:|
+/// | "tcp"           | unicast transport using TCP                 | [`zmq_tcp`]    |
+/// | "udp"           | UDP multicast and unicast transport         | [`zmq_udp`]    |
+/// | "ipc"           | local inter-process communication transport | [`zmq_ipc`]    |
+/// | "inproc"        | local in-process communication transport    | [`zmq_inproc`] |
+/// | "pgm", "epgm"   | reliable multicast transport using PGM      | [`zmq_pgm`]    |
+/// | "vmci"          | virtual machine communications interface    | [`zmq_vmci`]   |
+/// | "tipc"          | cluster communication transport using TIPC  | [`zmq_tipc`]   |
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{TcpAddr, addr::Endpoint};
+/// use std::convert::TryInto;
+///
+/// // IPv4 addr with TCP transport.
+/// let addr: TcpAddr = "127.0.0.1:9090".try_into()?;
+/// let endpoint: Endpoint = addr.into();
+/// assert!(endpoint.is_tcp());
+///
+/// // The same endpoint parsed from a full URI.
+/// let parsed: Endpoint = "tcp://127.0.0.1:9090".parse()?;
+/// assert_eq!(parsed, endpoint);
+/// assert_eq!(parsed.to_string(), "tcp://127.0.0.1:9090");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Serialization
+/// An endpoint is serialized as its URI, e.g. `"tcp://127.0.0.1:9090"`. The
+/// URI is validated on deserialization. The tagged form of the previous
+/// versions, e.g. `tcp: "127.0.0.1:9090"` in YAML, is also accepted.
+///
+/// # Equality
+/// Two endpoints are equal, and have the same hash, if their addresses are
+/// structurally equal, so they can be used as keys in a `HashMap`. Since
+/// this does not account for hostname case or `ipc` path spelling, use
+/// [`normalize`] to deduplicate endpoints that refer to the same place.
+///
+/// This enum type is non-exhaustive and could have additional variants
+/// added in future. Therefore, when matching against variants of
+/// non-exhaustive enums, an extra wildcard arm must be added to account
+/// for any future variants.
+///
+/// [`zmq_tcp`]: http://api.zeromq.org/master:zmq_tcp
+/// [`zmq_udp`]: http://api.zeromq.org/master:zmq-udp
+/// [`zmq_ipc`]: http://api.zeromq.org/master:zmq_ipc
+/// [`zmq_inproc`]: http://api.zeromq.org/master:zmq_inproc
+/// [`zmq_pgm`]: http://api.zeromq.org/master:zmq_pgm
+/// [`zmq_vmci`]: http://api.zeromq.org/master:zmq_vmci
+/// [`zmq_tipc`]: http://api.zeromq.org/master:zmq_tipc
+/// [`normalize`]: #method.normalize
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Endpoint {
+    /// Unicast transport using TCP, see [`zmq_tcp`].
+    ///
+    /// [`zmq_tcp`]: http://api.zeromq.org/master:zmq-tcp
+    Tcp(TcpAddr),
+    /// ØMQ UDP multicast and unicast transport
+    ///
+    /// [`zmq_udp`]: http://api.zeromq.org/master:zmq-udp
+    Udp(UdpAddr),
+    /// Local in-process (inter-thread) communication transport
+    ///
+    /// [`zmq_inproc`]: http://api.zeromq.org/master:zmq-inproc
+    Inproc(InprocAddr),
+    /// Local inter-process communication transport, see [`zmq_ipc`].
+    ///
+    /// This transport is only available on unix platforms.
+    ///
+    /// [`zmq_ipc`]: http://api.zeromq.org/master:zmq-ipc
+    #[cfg(unix)]
+    Ipc(IpcAddr),
+    /// Reliable multicast transport using PGM, see [`zmq_pgm`].
+    ///
+    /// [`zmq_pgm`]: http://api.zeromq.org/master:zmq-pgm
+    Pgm(PgmAddr),
+    /// Reliable multicast transport using EPGM, see [`zmq_pgm`].
+    ///
+    /// [`zmq_pgm`]: http://api.zeromq.org/master:zmq-pgm
+    Epgm(EpgmAddr),
+    /// Virtual machine communications interface transport, see
+    /// [`zmq_vmci`].
+    ///
+    /// [`zmq_vmci`]: http://api.zeromq.org/master:zmq-vmci
+    Vmci(VmciAddr),
+    /// Cluster communication transport using TIPC, see [`zmq_tipc`].
+    ///
+    /// This transport is only available on Linux.
+    ///
+    /// [`zmq_tipc`]: http://api.zeromq.org/master:zmq-tipc
+    Tipc(TipcAddr),
+}
+
+impl Endpoint {
+    /// Returns `true` if the endpoint uses the `Tcp` transport.
+    pub fn is_tcp(&self) -> bool {
+        if let Endpoint::Tcp(_) = self {
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns `true` if the endpoint uses the `Ucp` transport.
+    pub fn is_udp(&self) -> bool {
+        if let Endpoint::Udp(_) = self {
             true
         } else {
             false
@@ -1303,6 +2239,15 @@ impl Endpoint {
             false
         }
     }
+    /// Returns `true` if the endpoint uses the `Ipc` transport.
+    #[cfg(unix)]
+    pub fn is_ipc(&self) -> bool {
+        if let Endpoint::Ipc(_) = self {
+            true
+        } else {
+            false
+        }
+    }
     /// Returns `true` if the endpoint uses the `Pgm` transport.
     pub fn is_pgm(&self) -> bool {
         if let Endpoint::Pgm(_) = self {
@@ -1319,42 +2264,231 @@ impl Endpoint {
             false
         }
     }
+    /// Returns `true` if the endpoint uses the `Vmci` transport.
+    pub fn is_vmci(&self) -> bool {
+        if let Endpoint::Vmci(_) = self {
+            true
+        } else {
+            false
+        }
+    }
+    /// Returns `true` if the endpoint uses the `Tipc` transport.
+    pub fn is_tipc(&self) -> bool {
+        if let Endpoint::Tipc(_) = self {
+            true
+        } else {
+            false
+        }
+    }
 
-    pub(crate) fn from_zmq(s: &str) -> Self {
-        let index = s.find("://").unwrap();
+    /// Returns the specified port of the endpoint, if any.
+    ///
+    /// This is mostly useful to retrieve the port that was assigned by the
+    /// system to a bound ephemeral port, via `last_endpoint`. Only the
+    /// `Tcp`, `Udp`, `Pgm`, `Epgm` and `Vmci` transports have a port.
+    pub fn port(&self) -> Option<u16> {
+        if let Endpoint::Vmci(addr) = self {
+            return addr.port().number();
+        }
 
-        match &s[0..index] {
-            "tcp" => {
-                let addr = TcpAddr::from_str(&s[index + 3..]).unwrap();
-                Endpoint::Tcp(addr)
-            }
-            "inproc" => {
-                let addr = InprocAddr::from_str(&s[index + 3..]).unwrap();
-                Endpoint::Inproc(addr)
-            }
-            "udp" => {
-                let addr = UdpAddr::from_str(&s[index + 3..]).unwrap();
-                Endpoint::Udp(addr)
-            }
-            "pgm" => {
-                let addr = PgmAddr::from_str(&s[index + 3..]).unwrap();
-                Endpoint::Pgm(addr)
-            }
-            "epgm" => {
-                let addr = EpgmAddr::from_str(&s[index + 3..]).unwrap();
-                Endpoint::Epgm(addr)
+        let host = match self {
+            Endpoint::Tcp(addr) => addr.host(),
+            Endpoint::Udp(addr) => addr.host(),
+            Endpoint::Pgm(addr) => addr.host(),
+            Endpoint::Epgm(addr) => addr.host(),
+            _ => return None,
+        };
+
+        host.port().number()
+    }
+
+    /// Returns the normalized form of the endpoint.
+    ///
+    /// Endpoints are compared structurally, so two endpoints that refer to
+    /// the same place might not be equal. Comparing their normalized forms
+    /// instead takes the following into account:
+    /// * Hostnames are lowercased, since DNS names are case insensitive.
+    ///     IPv6 scope ids and source addresses are left as is.
+    /// * IP addresses are compared by value, so that `[::0001]` is the
+    ///     same as `[::1]`. This holds even without normalization.
+    /// * The `.` components, repeated separators and trailing slashes of
+    ///     `ipc` paths are removed. The `..` components are kept since
+    ///     they cannot be resolved without accessing the filesystem.
+    ///
+    /// Normalization never resolves anything, so a hostname is never equal
+    /// to the IP address it resolves to, and an IPv4 address is not equal to
+    /// its IPv4-mapped IPv6 address. Likewise, `inproc` names, abstract
+    /// `ipc` names and network interface names are case sensitive and
+    /// are left as is.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::addr::Endpoint;
+    ///
+    /// let a: Endpoint = "tcp://Broker-A:5555".parse()?;
+    /// let b: Endpoint = "tcp://broker-a:5555".parse()?;
+    /// assert_ne!(a, b);
+    /// assert_eq!(a.normalize(), b.normalize());
+    /// assert_eq!(a.normalize().to_string(), "tcp://broker-a:5555");
+    ///
+    /// let ip: Endpoint = "tcp://127.0.0.1:5555".parse()?;
+    /// let hostname: Endpoint = "tcp://localhost:5555".parse()?;
+    /// assert_ne!(ip.normalize(), hostname.normalize());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn normalize(&self) -> Self {
+        match self {
+            Endpoint::Tcp(addr) => Endpoint::Tcp(TcpAddr {
+                src: addr.src.clone(),
+                host: addr.host.normalize(),
+            }),
+            Endpoint::Udp(addr) => Endpoint::Udp(UdpAddr {
+                src: addr.src.clone(),
+                host: addr.host.normalize(),
+            }),
+            Endpoint::Pgm(addr) => Endpoint::Pgm(PgmAddr {
+                src: addr.src.clone(),
+                host: addr.host.normalize(),
+            }),
+            Endpoint::Epgm(addr) => Endpoint::Epgm(EpgmAddr {
+                src: addr.src.clone(),
+                host: addr.host.normalize(),
+            }),
+            #[cfg(unix)]
+            Endpoint::Ipc(addr) => Endpoint::Ipc(addr.normalize()),
+            Endpoint::Inproc(_) | Endpoint::Vmci(_) | Endpoint::Tipc(_) => {
+                self.clone()
             }
-            _ => unreachable!(),
         }
     }
 
+    pub(crate) fn from_zmq(s: &str) -> Self {
+        Self::from_str(s).unwrap()
+    }
+
     pub(crate) fn to_zmq(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = AddrParseError;
+    fn from_str(s: &str) -> Result<Self, AddrParseError> {
+        let index = s.find("://").ok_or_else(|| {
+            AddrParseError::new("missing `://` separator").with_input(s)
+        })?;
+        let addr = &s[index + 3..];
+
+        let result = match &s[0..index] {
+            "tcp" => TcpAddr::from_str(addr).map(Endpoint::Tcp),
+            "inproc" => InprocAddr::from_str(addr).map(Endpoint::Inproc),
+            #[cfg(unix)]
+            "ipc" => IpcAddr::from_str(addr).map(Endpoint::Ipc),
+            "udp" => UdpAddr::from_str(addr).map(Endpoint::Udp),
+            "pgm" => PgmAddr::from_str(addr).map(Endpoint::Pgm),
+            "epgm" => EpgmAddr::from_str(addr).map(Endpoint::Epgm),
+            "vmci" => VmciAddr::from_str(addr).map(Endpoint::Vmci),
+            "tipc" => TipcAddr::from_str(addr).map(Endpoint::Tipc),
+            _ => Err(AddrParseError::new("unknown transport")),
+        };
+
+        result.map_err(|err| err.with_input(s))
+    }
+}
+
+tryfrom_fromstr!(Endpoint);
+
+impl Serialize for Endpoint {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+// The tagged representation of an `Endpoint` used by the previous versions.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TaggedEndpoint {
+    Tcp(TcpAddr),
+    Udp(UdpAddr),
+    Inproc(InprocAddr),
+    #[cfg(unix)]
+    Ipc(IpcAddr),
+    Pgm(PgmAddr),
+    Epgm(EpgmAddr),
+    Vmci(VmciAddr),
+    Tipc(TipcAddr),
+}
+
+impl From<TaggedEndpoint> for Endpoint {
+    fn from(tagged: TaggedEndpoint) -> Self {
+        match tagged {
+            TaggedEndpoint::Tcp(addr) => Endpoint::Tcp(addr),
+            TaggedEndpoint::Udp(addr) => Endpoint::Udp(addr),
+            TaggedEndpoint::Inproc(addr) => Endpoint::Inproc(addr),
+            #[cfg(unix)]
+            TaggedEndpoint::Ipc(addr) => Endpoint::Ipc(addr),
+            TaggedEndpoint::Pgm(addr) => Endpoint::Pgm(addr),
+            TaggedEndpoint::Epgm(addr) => Endpoint::Epgm(addr),
+            TaggedEndpoint::Vmci(addr) => Endpoint::Vmci(addr),
+            TaggedEndpoint::Tipc(addr) => Endpoint::Tipc(addr),
+        }
+    }
+}
+
+struct EndpointVisitor;
+
+impl<'de> de::Visitor<'de> for EndpointVisitor {
+    type Value = Endpoint;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an endpoint URI")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Endpoint, E>
+    where
+        E: de::Error,
+    {
+        Endpoint::from_str(s).map_err(de::Error::custom)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Endpoint, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let deserializer = de::value::MapAccessDeserializer::new(map);
+        TaggedEndpoint::deserialize(deserializer).map(Endpoint::from)
+    }
+}
+
+impl<'de> Deserialize<'de> for Endpoint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(EndpointVisitor)
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Endpoint::Tcp(addr) => format!("tcp://{}", addr),
-            Endpoint::Inproc(addr) => format!("inproc://{}", addr),
-            Endpoint::Udp(addr) => format!("udp://{}", addr),
-            Endpoint::Epgm(addr) => format!("pgm://{}", addr),
-            Endpoint::Pgm(addr) => format!("epgm://{}", addr),
+            Endpoint::Tcp(addr) => write!(f, "tcp://{}", addr),
+            Endpoint::Inproc(addr) => write!(f, "inproc://{}", addr),
+            #[cfg(unix)]
+            Endpoint::Ipc(addr) => write!(f, "ipc://{}", addr),
+            Endpoint::Udp(addr) => write!(f, "udp://{}", addr),
+            Endpoint::Pgm(addr) => write!(f, "pgm://{}", addr),
+            Endpoint::Epgm(addr) => write!(f, "epgm://{}", addr),
+            Endpoint::Vmci(addr) => write!(f, "vmci://{}", addr),
+            Endpoint::Tipc(addr) => write!(f, "tipc://{}", addr),
         }
     }
 }
@@ -1415,4 +2549,645 @@ mod test {
     test_addr_ser_de!(pgm, PgmAddr, "0.0.0.0:3000");
     test_addr_ser_de!(epgm, EpgmAddr, "0.0.0.0:3000");
     test_addr_ser_de!(inproc, InprocAddr, "test");
+    test_addr_ser_de!(vmci, VmciAddr, "2:5555");
+    test_addr_ser_de!(tipc, TipcAddr, "{5560,1,10}");
+    #[cfg(unix)]
+    test_addr_ser_de!(ipc, IpcAddr, "/tmp/test.sock");
+    test_addr_ser_de!(tcp_ipv6, TcpAddr, "[fe80::1%eth0]:3000");
+
+    use super::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_ipv6_parse() {
+        let addr: SocketAddr = "[::1]:3000".try_into().unwrap();
+        assert_eq!(
+            addr.interface(),
+            &Interface::Ip(IpAddr::V6(Ipv6Addr::LOCALHOST))
+        );
+        assert_eq!(addr.port(), Port::Specified(3000));
+        assert_eq!(addr.scope_id(), None);
+
+        let addr: SocketAddr = "[fe80::1%eth0]:*".try_into().unwrap();
+        assert_eq!(addr.scope_id(), Some("eth0"));
+        assert!(addr.port().is_unspecified());
+
+        let addr: SocketAddr = "[fe80::1%2]:3000".try_into().unwrap();
+        assert_eq!(addr.scope_id(), Some("2"));
+    }
+
+    #[test]
+    fn test_ipv6_parse_invalid() {
+        assert!(SocketAddr::from_str("[::1]").is_err());
+        assert!(SocketAddr::from_str("[]:3000").is_err());
+        assert!(SocketAddr::from_str("[localhost]:3000").is_err());
+        assert!(SocketAddr::from_str("[fe80::1%]:3000").is_err());
+        assert!(SocketAddr::from_str("[fe80::1%eth.0]:3000").is_err());
+    }
+
+    #[test]
+    fn test_ipv6_display() {
+        for s in &["[::1]:3000", "[fe80::1%eth0]:*", "[2001:db8::1]:8080"] {
+            let addr: SocketAddr = s.parse().unwrap();
+            assert_eq!(addr.to_string(), *s);
+        }
+
+        let addr = SocketAddr::with_scope_id(
+            Ipv6Addr::LOCALHOST,
+            "lo".try_into().unwrap(),
+            Port::Specified(3000),
+        );
+        assert_eq!(addr.to_string(), "[::1%lo]:3000");
+
+        let addr: TcpAddr = "[::1]:3000".try_into().unwrap();
+        assert_eq!(addr.to_string(), "[::1]:3000");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ipc_parse() {
+        let addr: IpcAddr = "/tmp/test.sock".try_into().unwrap();
+        assert_eq!(addr.as_path(), Path::new("/tmp/test.sock"));
+        assert!(!addr.is_abstract());
+        assert!(!addr.is_wildcard());
+
+        let addr: IpcAddr = Path::new("@test").try_into().unwrap();
+        assert!(addr.is_abstract());
+        assert_eq!(addr.to_string(), "@test");
+
+        let addr: IpcAddr = "*".try_into().unwrap();
+        assert_eq!(addr, IpcAddr::wildcard());
+
+        let max = "a".repeat(IPC_MAX_SIZE);
+        assert!(IpcAddr::new(&max).is_ok());
+        assert!(IpcAddr::new(max + "a").is_err());
+
+        assert!(IpcAddr::new("").is_err());
+        assert!(IpcAddr::new("@").is_err());
+        assert!(IpcAddr::new("/tmp/te\0st").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ipc_round_trip() {
+        use crate::{prelude::*, *};
+
+        let server = ServerBuilder::new()
+            .bind(IpcAddr::wildcard())
+            .build()
+            .unwrap();
+
+        let bound = match server.last_endpoint().unwrap().unwrap() {
+            Endpoint::Ipc(addr) => addr,
+            _ => panic!("expected an ipc endpoint"),
+        };
+        assert!(!bound.is_wildcard());
+        assert!(bound.as_path().is_absolute());
+
+        let client = ClientBuilder::new().connect(&bound).build().unwrap();
+        client.send("ping").unwrap();
+
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+    }
+
+    #[test]
+    fn test_inproc_connect_before_bind() {
+        use crate::{prelude::*, *};
+
+        let addr = InprocAddr::new_unique();
+
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+
+        client.send("ping").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+    }
+
+    #[test]
+    fn test_inproc_new_unique() {
+        let addr = InprocAddr::new_unique();
+        assert_ne!(addr, InprocAddr::new_unique());
+
+        assert!(InprocAddr::new("a".repeat(INPROC_MAX_SIZE)).is_ok());
+        assert!(InprocAddr::new("a".repeat(INPROC_MAX_SIZE + 1)).is_err());
+        assert!(InprocAddr::new("").is_err());
+    }
+
+    #[test]
+    fn test_pgm_parse_display() {
+        let addr: PgmAddr = "eth0;239.192.1.1:5555".try_into().unwrap();
+        assert_eq!(
+            addr.src(),
+            Some(&SrcAddr::Interface(Interface::from_str("eth0").unwrap()))
+        );
+        assert_eq!(addr.host().port(), Port::Specified(5555));
+        assert_eq!(addr.to_string(), "eth0;239.192.1.1:5555");
+
+        let addr: EpgmAddr = "192.168.1.1;239.192.1.1:5555".try_into().unwrap();
+        assert_eq!(addr.to_string(), "192.168.1.1;239.192.1.1:5555");
+
+        let addr: EpgmAddr = "239.192.1.1:5555".try_into().unwrap();
+        assert!(addr.src().is_none());
+        assert_eq!(addr.to_string(), "239.192.1.1:5555");
+
+        let addr = PgmAddr::new(addr.host().to_owned())
+            .add_interface(Interface::from_str("eth1").unwrap());
+        assert_eq!(addr.to_string(), "eth1;239.192.1.1:5555");
+
+        let endpoint: Endpoint = addr.into();
+        assert_eq!(endpoint.to_zmq(), "pgm://eth1;239.192.1.1:5555");
+        let endpoint: Endpoint =
+            EpgmAddr::from_str("239.192.1.1:5555").unwrap().into();
+        assert_eq!(endpoint.to_zmq(), "epgm://239.192.1.1:5555");
+    }
+
+    #[test]
+    fn test_pgm_parse_invalid() {
+        assert!(PgmAddr::from_str("eth0;239.192.1.1:*").is_err());
+        assert!(PgmAddr::from_str("eth0;239.192.1.1").is_err());
+        assert!(PgmAddr::from_str("eth0:1;239.192.1.1:5555").is_err());
+        assert!(EpgmAddr::from_str(";239.192.1.1:5555").is_err());
+    }
+
+    #[test]
+    fn test_pgm_radio() {
+        use crate::{prelude::*, *};
+
+        let addr: PgmAddr = "239.192.1.1:5555".try_into().unwrap();
+        let err = Radio::new().unwrap().connect(addr).unwrap_err();
+
+        if has(Capability::Pgm) {
+            assert_eq!(
+                err.kind(),
+                ErrorKind::InvalidInput {
+                    msg: "endpoint protocol incompatible"
+                }
+            );
+        } else {
+            match err.kind() {
+                ErrorKind::Unsupported { .. } => (),
+                _ => panic!("unexpected error"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_endpoint_round_trip() {
+        let mut uris = vec![
+            "tcp://127.0.0.1:5555",
+            "tcp://0.0.0.0:*",
+            "tcp://eth0;192.168.1.1:5555",
+            "tcp://[fe80::1%eth0]:5555",
+            "tcp://localhost:5555",
+            "udp://239.0.0.1:5555",
+            "udp://eth0;239.0.0.1:5555",
+            "inproc://test",
+            "pgm://eth0;239.192.1.1:5555",
+            "epgm://192.168.1.1;239.192.1.1:5555",
+            "vmci://2:5555",
+            "vmci://@:*",
+            "vmci://*:5555",
+            "tipc://{5560,5}",
+            "tipc://{5560,1,10}",
+            "tipc://<1.1.1:3594108509>",
+            "tipc://<*>",
+        ];
+        if cfg!(unix) {
+            uris.extend(&["ipc:///tmp/test.sock", "ipc://@test", "ipc://*"]);
+        }
+
+        for uri in uris {
+            let endpoint: Endpoint = uri.parse().unwrap();
+            assert_eq!(endpoint.to_string(), uri);
+            assert_eq!(
+                endpoint.to_string().parse::<Endpoint>().unwrap(),
+                endpoint
+            );
+        }
+
+        let endpoint: Endpoint = "tcp://127.0.0.1:5555".try_into().unwrap();
+        assert!(endpoint.is_tcp());
+        let endpoint: Endpoint = "inproc://test".try_into().unwrap();
+        assert!(endpoint.is_inproc());
+    }
+
+    #[test]
+    fn test_endpoint_parse_invalid() {
+        let cases = &[
+            ("127.0.0.1:5555", "missing `://` separator"),
+            ("tcp:/127.0.0.1:5555", "missing `://` separator"),
+            ("foo://127.0.0.1:5555", "unknown transport"),
+            ("tcp://:5555", "empty interface"),
+            ("tcp://host", "missing port"),
+            ("tcp://host:", "empty port"),
+            ("tcp://host:70000", "invalid port number"),
+            ("inproc://", "empty host"),
+        ];
+
+        for (input, msg) in cases {
+            let err = Endpoint::from_str(input).unwrap_err();
+            assert_eq!(err.msg(), *msg);
+            assert_eq!(err.input(), Some(*input));
+            assert!(err.to_string().contains(input));
+        }
+    }
+
+    #[test]
+    fn test_endpoint_json() {
+        let mut uris = vec![
+            "tcp://127.0.0.1:5555",
+            "tcp://0.0.0.0:*",
+            "tcp://[::1]:*",
+            "tcp://eth0;[fe80::1%eth0]:5555",
+            "udp://239.0.0.1:5555",
+            "inproc://test",
+            "pgm://eth0;239.192.1.1:5555",
+            "epgm://239.192.1.1:5555",
+        ];
+        if cfg!(unix) {
+            uris.extend(&["ipc:///tmp/test.sock", "ipc://@test", "ipc://*"]);
+        }
+
+        for uri in uris {
+            let endpoint: Endpoint = uri.parse().unwrap();
+
+            let json = serde_json::to_string(&endpoint).unwrap();
+            assert_eq!(json, format!("\"{}\"", uri));
+
+            let de: Endpoint = serde_json::from_str(&json).unwrap();
+            assert_eq!(de, endpoint);
+        }
+    }
+
+    #[test]
+    fn test_endpoint_de_invalid() {
+        let err =
+            serde_json::from_str::<Endpoint>("\"tcp://host\"").unwrap_err();
+        assert!(err.to_string().contains("tcp://host"));
+
+        assert!(serde_json::from_str::<Endpoint>("\"foo://bar\"").is_err());
+        assert!(serde_json::from_str::<Endpoint>("3000").is_err());
+    }
+
+    #[test]
+    fn test_endpoint_de_tagged() {
+        let de: Endpoint =
+            serde_json::from_str("{\"tcp\": \"127.0.0.1:5555\"}").unwrap();
+        assert_eq!(de, Endpoint::from_str("tcp://127.0.0.1:5555").unwrap());
+
+        let de: Vec<Endpoint> =
+            serde_yaml::from_str("- inproc: test\n- \"inproc://test\"")
+                .unwrap();
+        assert_eq!(de[0], de[1]);
+    }
+
+    #[test]
+    fn test_endpoint_hash_map() {
+        use std::collections::HashMap;
+
+        let mut map = HashMap::new();
+        map.insert(Endpoint::from_str("tcp://[::1]:5555").unwrap(), 0);
+
+        let same = Endpoint::from_str("tcp://[0:0::1]:5555").unwrap();
+        assert_eq!(same.to_string(), "tcp://[::1]:5555");
+        assert_eq!(map.get(&same), Some(&0));
+    }
+
+    #[test]
+    fn test_tcp_from_std() {
+        let addr: TcpAddr =
+            net::SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), 5555)).into();
+        assert_eq!(addr.to_string(), "10.0.0.1:5555");
+        assert!(!addr.is_hostname());
+
+        let addr: TcpAddr = (Ipv6Addr::LOCALHOST, 5555).into();
+        assert_eq!(addr.to_string(), "[::1]:5555");
+
+        let addr: TcpAddr = (IpAddr::V4(Ipv4Addr::LOCALHOST), 5555).into();
+        assert_eq!(addr, TcpAddr::from_str("127.0.0.1:5555").unwrap());
+    }
+
+    #[test]
+    fn test_tcp_hostname() {
+        let hostname: Hostname = "broker.example.com".try_into().unwrap();
+        let addr: TcpAddr = (hostname, 5555).into();
+        assert!(addr.is_hostname());
+        assert_eq!(addr.to_string(), "broker.example.com:5555");
+
+        let endpoint: Endpoint = addr.into();
+        assert_eq!(endpoint.to_zmq(), "tcp://broker.example.com:5555");
+        assert_eq!(Endpoint::from_zmq(&endpoint.to_zmq()), endpoint);
+    }
+
+    #[test]
+    fn test_hostname_labels() {
+        let long_label = "a".repeat(63);
+        let long_name = vec!["a".repeat(49); 5].join(".");
+        assert_eq!(long_name.len(), 249);
+
+        for name in &[
+            "localhost",
+            "eth0",
+            "broker.example.com",
+            "a-b.c-d.e",
+            "1.example.com",
+            long_label.as_str(),
+            long_name.as_str(),
+        ] {
+            let hostname = Hostname::new(*name).unwrap();
+            assert_eq!(hostname.as_str(), *name);
+        }
+
+        let too_long_label = "a".repeat(64);
+        let too_long_name = vec!["a".repeat(50); 5].join(".");
+        let cases = &[
+            ("", "empty hostname"),
+            (".example.com", "empty hostname label"),
+            ("broker..example.com", "empty hostname label"),
+            ("broker.example.com.", "empty hostname label"),
+            (too_long_label.as_str(), "hostname label is too long"),
+            (too_long_name.as_str(), "hostname is too long"),
+            ("-broker.example.com", "invalid `-` in hostname label"),
+            ("broker-.example.com", "invalid `-` in hostname label"),
+            ("broker_a.example.com", "hostname contains illegal char"),
+        ];
+
+        for (input, msg) in cases {
+            let err = Hostname::new(*input).unwrap_err();
+            assert_eq!(err.msg(), *msg, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_tcp_bind_hostname() {
+        use crate::{prelude::*, *};
+
+        let addr: TcpAddr = "broker.example.com:*".try_into().unwrap();
+        assert!(addr.is_hostname());
+
+        let err = Server::new().unwrap().bind(addr).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AddrNotAvailable);
+
+        // The loopback interface is named `lo` on Linux.
+        if cfg!(target_os = "linux") {
+            let addr: TcpAddr = "lo:*".try_into().unwrap();
+            Server::new().unwrap().bind(addr).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_tcp_connect_hostname() {
+        use crate::{prelude::*, *};
+
+        let server = ServerBuilder::new()
+            .bind(TcpAddr::from_str("127.0.0.1:*").unwrap())
+            .recv_timeout(std::time::Duration::from_secs(1))
+            .build()
+            .unwrap();
+        let port = match server.last_endpoint().unwrap().unwrap() {
+            Endpoint::Tcp(addr) => match addr.host().port() {
+                Port::Specified(port) => port,
+                Port::Unspecified => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        // Only resolve to IPv4 addresses, since the server is bound to one.
+        let hostname: Hostname = "localhost".try_into().unwrap();
+        let client = ClientBuilder::new()
+            .ipv6(false)
+            .connect(TcpAddr::from((hostname, port)))
+            .build()
+            .unwrap();
+
+        client.send("ping").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+    }
+
+    #[test]
+    fn test_ephemeral_port_round_trip() {
+        use crate::{prelude::*, *};
+
+        let addr = TcpAddr::ephemeral(Ipv4Addr::LOCALHOST);
+        assert!(addr.host().port().is_unspecified());
+        assert_eq!(Endpoint::from(&addr).port(), None);
+
+        let server = ServerBuilder::new().bind(addr).build().unwrap();
+
+        let bound = server.last_endpoint().unwrap().unwrap();
+        let port = bound.port().unwrap();
+        assert_ne!(port, 0);
+
+        let client = ClientBuilder::new().connect(&bound).build().unwrap();
+        client.send("ping").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+
+        let addr = TcpAddr::from((Ipv4Addr::LOCALHOST, port));
+        assert_eq!(Endpoint::from(addr), bound);
+    }
+
+    #[test]
+    fn test_port_number() {
+        assert_eq!(Port::Specified(3000).number(), Some(3000));
+        assert_eq!(Port::Unspecified.number(), None);
+        assert_eq!(Endpoint::from(InprocAddr::new_unique()).port(), None);
+    }
+
+    #[test]
+    fn test_tcp_src_parse_display() {
+        for s in &[
+            "eth0;192.168.1.1:5555",
+            "192.168.1.17;192.168.1.1:5555",
+            "192.168.1.17:*;192.168.1.1:5555",
+            "::1;[2001:db8::1]:5555",
+        ] {
+            let addr: TcpAddr = s.parse().unwrap();
+            assert!(addr.src().is_some());
+            assert_eq!(addr.to_string(), *s);
+        }
+
+        let addr: TcpAddr = "eth0;192.168.1.1:5555".parse().unwrap();
+        assert_eq!(
+            addr.src(),
+            Some(&SrcAddr::Interface(Interface::from_str("eth0").unwrap()))
+        );
+
+        let addr = TcpAddr::from((Ipv4Addr::LOCALHOST, 5555))
+            .add_src(Interface::Ip(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        assert_eq!(addr.to_string(), "127.0.0.1;127.0.0.1:5555");
+    }
+
+    #[test]
+    fn test_tcp_src_connect() {
+        use crate::{prelude::*, *};
+
+        let server = ServerBuilder::new()
+            .bind(TcpAddr::ephemeral(Ipv4Addr::LOCALHOST))
+            .build()
+            .unwrap();
+        let port = server.last_endpoint().unwrap().unwrap().port().unwrap();
+
+        let addr = TcpAddr::from((Ipv4Addr::LOCALHOST, port))
+            .add_src(Interface::Ip(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        client.send("ping").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+
+        let err = Server::new().unwrap().bind(addr).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_vmci_parse() {
+        let addr: VmciAddr = "2:5555".try_into().unwrap();
+        assert_eq!(addr.cid(), VmciCid::Specified(2));
+        assert_eq!(addr.port(), Port::Specified(5555));
+
+        let addr: VmciAddr = "@:*".try_into().unwrap();
+        assert_eq!(addr.cid(), VmciCid::Local);
+        assert!(addr.port().is_unspecified());
+
+        let addr: VmciAddr = "*:5555".try_into().unwrap();
+        assert_eq!(addr.cid(), VmciCid::Any);
+        assert_eq!(Endpoint::from(addr).port(), Some(5555));
+
+        assert!(VmciAddr::from_str("2").is_err());
+        assert!(VmciAddr::from_str(":5555").is_err());
+        assert!(VmciAddr::from_str("host:5555").is_err());
+        assert!(VmciAddr::from_str("-1:5555").is_err());
+    }
+
+    #[test]
+    fn test_endpoint_normalize() {
+        let mut cases = vec![
+            ("tcp://LocalHost:1", "tcp://localhost:1", true),
+            ("tcp://[::0001]:1", "tcp://[::1]:1", true),
+            ("tcp://[2001:DB8:0:0::1]:1", "tcp://[2001:db8::1]:1", true),
+            ("udp://Server-A:1", "udp://server-a:1", true),
+            ("tcp://eth0;Server-A:1", "tcp://eth0;server-a:1", true),
+            ("tcp://localhost:1", "tcp://127.0.0.1:1", false),
+            ("tcp://127.0.0.1:1", "tcp://[::ffff:127.0.0.1]:1", false),
+            ("tcp://127.0.0.1:1", "tcp://127.0.0.1:2", false),
+            ("tcp://127.0.0.1:1", "udp://127.0.0.1:1", false),
+            ("tcp://[fe80::1%Eth0]:1", "tcp://[fe80::1%eth0]:1", false),
+            ("tcp://Eth0;127.0.0.1:1", "tcp://eth0;127.0.0.1:1", false),
+            ("inproc://Test", "inproc://test", false),
+        ];
+        #[cfg(unix)]
+        cases.extend(vec![
+            ("ipc:///tmp/a/", "ipc:///tmp/a", true),
+            ("ipc:///tmp/./a", "ipc:///tmp/a", true),
+            ("ipc:///tmp//a", "ipc:///tmp/a", true),
+            ("ipc://./a.sock", "ipc://a.sock", true),
+            ("ipc:///tmp/a/../b", "ipc:///tmp/b", false),
+            ("ipc://@Test", "ipc://@test", false),
+        ]);
+
+        for (a, b, equal) in cases {
+            let a: Endpoint = a.parse().unwrap();
+            let b: Endpoint = b.parse().unwrap();
+            let (a, b) = (a.normalize(), b.normalize());
+            assert_eq!(a == b, equal, "{} vs {}", a, b);
+            if equal {
+                assert_eq!(a.to_string(), b.to_string());
+            }
+            assert_eq!(a.normalize(), a);
+        }
+
+        let std: net::SocketAddr = "127.0.0.1:5555".parse().unwrap();
+        let parsed: Endpoint = "tcp://127.0.0.1:5555".parse().unwrap();
+        assert_eq!(Endpoint::from(TcpAddr::from(std)), parsed);
+    }
+
+    #[test]
+    fn test_tipc_parse() {
+        let addr: TipcAddr = "{5560, 1, 10}".try_into().unwrap();
+        assert_eq!(
+            addr,
+            TipcAddr::NameSeq {
+                service: 5560,
+                lower: 1,
+                upper: 10
+            }
+        );
+        assert_eq!(addr.to_string(), "{5560,1,10}");
+
+        let addr: TipcAddr = "<1.2.3:42>".try_into().unwrap();
+        assert_eq!(
+            addr,
+            TipcAddr::PortId {
+                zone: 1,
+                cluster: 2,
+                node: 3,
+                reference: 42
+            }
+        );
+
+        assert!(TipcAddr::from_str("{5560}").is_err());
+        assert!(TipcAddr::from_str("{5560,10,1}").is_err());
+        assert!(TipcAddr::from_str("{5560,1,2,3}").is_err());
+        assert!(TipcAddr::from_str("{5560,-1}").is_err());
+        assert!(TipcAddr::from_str("5560,1").is_err());
+        assert!(TipcAddr::from_str("<1.2:42>").is_err());
+        assert!(TipcAddr::from_str("<1.2.3>").is_err());
+        assert!(TipcAddr::from_str("{").is_err());
+        assert!(TipcAddr::from_str("<").is_err());
+        // Multibyte characters are not split.
+        assert!(TipcAddr::from_str("é").is_err());
+        assert!(TipcAddr::from_str("{1,2é").is_err());
+        assert!(TipcAddr::from_str("é1.2.3:4>").is_err());
+    }
+
+    #[test]
+    fn test_tipc_unsupported() {
+        use crate::{prelude::*, *};
+
+        if has(Capability::Tipc) {
+            return;
+        }
+
+        let addr = TipcAddr::Name {
+            service: 5560,
+            instance: 1,
+        };
+        let err = Client::new().unwrap().connect(addr).unwrap_err();
+        match err.kind() {
+            ErrorKind::Unsupported { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    // Requires the `tipc` kernel module to be loaded.
+    #[test]
+    #[ignore]
+    fn test_tipc_loopback() {
+        use crate::{prelude::*, *};
+
+        let bind = TipcAddr::NameSeq {
+            service: 5560,
+            lower: 0,
+            upper: 10,
+        };
+        let server = ServerBuilder::new().bind(bind).build().unwrap();
+        assert!(server.last_endpoint().unwrap().unwrap().is_tipc());
+
+        let connect = TipcAddr::Name {
+            service: 5560,
+            instance: 5,
+        };
+        let client = ClientBuilder::new().connect(connect).build().unwrap();
+
+        client.send("ping").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+    }
 }
@@ -1,14 +1,16 @@
-use crate::{addr::AddrParseError, group::GroupParseError};
+use crate::{
+    addr::{AddrParseError, Endpoint},
+    group::GroupParseError,
+};
 use libzmq_sys as sys;
-
-use failure::{Backtrace, Context, Fail};
+use sys::errno;
 
 use std::{
-    convert::Infallible,
-    ffi,
+    convert::{Infallible, TryFrom},
+    error, ffi,
     fmt::Debug,
     fmt::{self, Display},
-    str,
+    io, str,
 };
 
 /// An error with a kind and a msg.
@@ -18,11 +20,15 @@ use std::{
 /// in case of a failed `send` function call. When no `T` is specified, it
 /// defaults to `()`.
 ///
+/// `Error` implements `std::error::Error` and thus can be propagated with `?`
+/// into a `Box<dyn std::error::Error>` or any similar error type. It also
+/// converts into a `std::io::Error`.
+///
 /// # Usage example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, *, ErrorKind::*};
 ///
 /// // This client has no peer and is therefore in mute state.
@@ -51,8 +57,12 @@ use std::{
 /// [`ErrorKind`]: enum.ErrorKind.html
 #[derive(Debug)]
 pub struct Error<T = ()> {
-    inner: Context<ErrorKind>,
+    kind: ErrorKind,
     content: Option<T>,
+    // Boxed to keep the `Result` of the send and recv methods small.
+    endpoint: Option<Box<Endpoint>>,
+    step: Option<BuildStep>,
+    errno: Option<i32>,
 }
 
 impl<T> Error<T> {
@@ -61,22 +71,28 @@ impl<T> Error<T> {
     /// The `content` field will be `None`.
     pub(crate) fn new(kind: ErrorKind) -> Self {
         Self {
-            inner: Context::new(kind),
+            kind,
             content: None,
+            endpoint: None,
+            step: None,
+            errno: None,
         }
     }
 
     /// Creates a new `Error` from an `ErrorKind` and some content.
     pub(crate) fn with_content(kind: ErrorKind, content: T) -> Self {
         Self {
-            inner: Context::new(kind),
+            kind,
             content: Some(content),
+            endpoint: None,
+            step: None,
+            errno: None,
         }
     }
 
     /// Returns the kind of error.
     pub fn kind(&self) -> ErrorKind {
-        *self.inner.get_context()
+        self.kind
     }
 
     /// Returns a reference to the content held by the error.
@@ -89,6 +105,42 @@ impl<T> Error<T> {
         self.content.take()
     }
 
+    /// Returns the endpoint at fault, if the error was raised by a `bind`,
+    /// `connect`, `unbind` or `disconnect` operation.
+    ///
+    /// The endpoint is also displayed as part of the error.
+    pub fn endpoint(&self) -> Option<&Endpoint> {
+        self.endpoint.as_ref().map(|endpoint| &**endpoint)
+    }
+
+    /// Returns the step that failed, if the error was raised while building
+    /// a socket or applying a `CtxConfig` or `AuthConfig`.
+    pub fn build_step(&self) -> Option<BuildStep> {
+        self.step
+    }
+
+    /// Returns the errno returned by *libzmq*, if the error originates
+    /// from it.
+    ///
+    /// This allows matching on errnos that are not modeled by the
+    /// [`ErrorKind`]. Note that some errnos, such as `ETERM`, are specific
+    /// to *libzmq* and thus have no meaning for the OS.
+    ///
+    /// [`ErrorKind`]: enum.ErrorKind.html
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.errno
+    }
+
+    /// Returns `true` if the operation that returned this error can be
+    /// retried as is, namely if the error is either [`WouldBlock`] or
+    /// [`Interrupted`].
+    ///
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.kind, ErrorKind::WouldBlock | ErrorKind::Interrupted)
+    }
+
     /// This allows casting to any `Error<I>` by replacing the content
     /// of the error with `None`.
     ///
@@ -96,36 +148,110 @@ impl<T> Error<T> {
     /// information is lost in the conversion.
     pub fn cast<I>(self) -> Error<I> {
         Error {
-            inner: self.inner,
+            kind: self.kind,
             content: None,
+            endpoint: self.endpoint,
+            step: self.step,
+            errno: self.errno,
         }
     }
-}
 
-impl<T> Fail for Error<T>
-where
-    T: 'static + Debug + Sync + Send,
-{
-    fn cause(&self) -> Option<&Fail> {
-        self.inner.cause()
+    /// Casts to an `Error<I>` holding the specified content.
+    pub(crate) fn cast_with<I>(self, content: I) -> Error<I> {
+        let mut err = self.cast();
+        err.content = Some(content);
+        err
     }
 
-    fn backtrace(&self) -> Option<&Backtrace> {
-        self.inner.backtrace()
+    pub(crate) fn with_endpoint(mut self, endpoint: &Endpoint) -> Self {
+        self.endpoint = Some(Box::new(endpoint.clone()));
+        self
+    }
+
+    pub(crate) fn with_step(mut self, step: BuildStep) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub(crate) fn with_errno(mut self, errno: i32) -> Self {
+        self.errno = Some(errno);
+        self
     }
 }
 
+// The `ErrorKind` is not returned as the `source` since it is already
+// displayed by the `Error` itself.
+impl<T> error::Error for Error<T> where T: Debug {}
+
 impl<T> Display for Error<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        Display::fmt(&self.inner, f)
+        if let Some(step) = self.step {
+            write!(f, "failed to {}: ", step)?;
+        }
+        Display::fmt(&self.kind, f)?;
+        if let Some(ref endpoint) = self.endpoint {
+            write!(f, " (endpoint `{}`)", endpoint)?;
+        }
+        Ok(())
+    }
+}
+
+/// The step of a builder or config that failed.
+///
+/// This is returned by [`Error::build_step`]. For the `Connect` and `Bind`
+/// steps, the endpoint at fault is returned by [`Error::endpoint`].
+///
+/// # Example
+/// ```
+/// use libzmq::{prelude::*, *};
+///
+/// let err = ServerBuilder::new().zap_domain("").build().unwrap_err();
+/// assert_eq!(
+///     err.build_step(),
+///     Some(BuildStep::Option { name: "zap_domain" }),
+/// );
+/// assert_eq!(
+///     err.to_string(),
+///     "failed to set zap_domain: invalid input: zap domain cannot be empty",
+/// );
+/// ```
+///
+/// [`Error::build_step`]: struct.Error.html#method.build_step
+/// [`Error::endpoint`]: struct.Error.html#method.endpoint
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BuildStep {
+    /// Setting one of the options.
+    Option {
+        /// The name of the builder method or config field of the option.
+        name: &'static str,
+    },
+    /// Connecting to one of the endpoints.
+    Connect,
+    /// Binding to one of the endpoints.
+    Bind,
+}
+
+impl Display for BuildStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildStep::Option { name } => write!(f, "set {}", name),
+            BuildStep::Connect => write!(f, "connect"),
+            BuildStep::Bind => write!(f, "bind"),
+        }
     }
 }
 
+/// Returns a closure that tags the error with the socket option that
+/// failed to be set.
+pub(crate) fn option_step<T, I>(
+    name: &'static str,
+) -> impl FnOnce(Error<T>) -> Error<I> {
+    move |err| err.cast().with_step(BuildStep::Option { name })
+}
+
 impl<T> From<GroupParseError> for Error<T> {
-    fn from(_error: GroupParseError) -> Self {
-        Error::new(ErrorKind::InvalidInput {
-            msg: "unable to parse group",
-        })
+    fn from(error: GroupParseError) -> Self {
+        Error::new(ErrorKind::InvalidInput { msg: error.msg() })
     }
 }
 
@@ -141,6 +267,62 @@ impl<T> From<Infallible> for Error<T> {
     }
 }
 
+/// Maps the `ErrorKind` to the closest `io::ErrorKind`, the content of the
+/// error being dropped.
+///
+/// The `Error` itself is kept as the inner error of the `io::Error`, so
+/// that the conversion can be reverted losslessly via `TryFrom`.
+impl<T> From<Error<T>> for io::Error {
+    fn from(error: Error<T>) -> Self {
+        let kind = match error.kind() {
+            ErrorKind::WouldBlock => io::ErrorKind::WouldBlock,
+            ErrorKind::HostUnreachable => io::ErrorKind::HostUnreachable,
+            ErrorKind::CtxTerminated => io::ErrorKind::ConnectionAborted,
+            ErrorKind::Interrupted => io::ErrorKind::Interrupted,
+            ErrorKind::AddrInUse => io::ErrorKind::AddrInUse,
+            ErrorKind::AddrNotAvailable => io::ErrorKind::AddrNotAvailable,
+            ErrorKind::NotFound { .. } => io::ErrorKind::NotFound,
+            ErrorKind::InvalidInput { .. } => io::ErrorKind::InvalidInput,
+            ErrorKind::Unsupported { .. } => io::ErrorKind::Unsupported,
+            ErrorKind::RetriesExhausted { .. } => io::ErrorKind::TimedOut,
+            ErrorKind::InvalidMessage { .. } => io::ErrorKind::InvalidData,
+            ErrorKind::SocketLimit | ErrorKind::NoIoThread => {
+                io::ErrorKind::Other
+            }
+        };
+
+        io::Error::new(kind, error.cast::<()>())
+    }
+}
+
+/// Recovers the `Error` of an `io::Error` converted via `From`, or maps
+/// the `io::ErrorKind` for the kinds that have an equivalent.
+///
+/// The `io::Error` is returned as is if it cannot be converted.
+impl TryFrom<io::Error> for Error {
+    type Error = io::Error;
+
+    fn try_from(error: io::Error) -> Result<Self, io::Error> {
+        if error.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = error.into_inner().unwrap();
+            return Ok(*inner.downcast::<Error>().unwrap());
+        }
+
+        let kind = match error.kind() {
+            io::ErrorKind::WouldBlock => ErrorKind::WouldBlock,
+            io::ErrorKind::Interrupted => ErrorKind::Interrupted,
+            io::ErrorKind::HostUnreachable => ErrorKind::HostUnreachable,
+            io::ErrorKind::AddrInUse => ErrorKind::AddrInUse,
+            io::ErrorKind::AddrNotAvailable => ErrorKind::AddrNotAvailable,
+            _ => return Err(error),
+        };
+
+        let mut err = Error::new(kind);
+        err.errno = error.raw_os_error();
+        Ok(err)
+    }
+}
+
 /// Used to give context to an `Error`.
 ///
 /// # Note
@@ -150,17 +332,24 @@ impl<T> From<Infallible> for Error<T> {
 /// for any future variants.
 ///
 /// [`Error`]: enum.Error.html
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Fail, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error, Hash)]
+#[non_exhaustive]
 pub enum ErrorKind {
-    /// Non-blocking mode was requested and the message cannot be sent
-    /// without blocking
-    #[fail(display = "operation would block")]
+    /// Non-blocking mode was requested and the operation cannot complete
+    /// without blocking, or a timeout expired (`EAGAIN`).
+    ///
+    /// This is returned by the `try_*` send and receive methods, by the
+    /// blocking ones if a send or receive timeout is set, and by
+    /// [`Poller::poll`] if no event occurred within its timeout.
+    ///
+    /// [`Poller::poll`]: poll/struct.Poller.html#method.poll
+    #[error("operation would block")]
     WouldBlock,
     /// Occurs when a [`Server`] socket cannot route a message
     /// to a host.
     ///
     /// [`Server`]: socket/struct.Server.html
-    #[fail(display = "host unreachable")]
+    #[error("host unreachable")]
     HostUnreachable,
     /// The context was terminated while the operation was ongoing. Any
     /// further operations on sockets that share this context will result
@@ -170,29 +359,45 @@ pub enum ErrorKind {
     ///
     /// [`Ctx`]: ../ctx/struct.Ctx.html
     /// [`terminated`]: ../ctx/struct.Ctx.html#method.terminate
-    #[fail(display = "context terminated")]
+    #[error("context terminated")]
     CtxTerminated,
-    /// The operation was interrupted by a OS signal delivery.
-    #[fail(display = "interrupted by signal")]
+    /// The operation was interrupted by a OS signal delivery (`EINTR`).
+    ///
+    /// Any operation that can block may return this, namely sending,
+    /// receiving and polling, as well as the socket operations that process
+    /// pending commands such as `bind`, `connect`, `join` or setting an
+    /// option. The operation can safely be retried, see
+    /// [`retry_interrupted`].
+    ///
+    /// [`retry_interrupted`]: fn.retry_interrupted.html
+    #[error("interrupted by signal")]
     Interrupted,
     /// The addr cannot be bound because it is already in use.
-    #[fail(display = "addr in use")]
+    #[error("addr in use")]
     AddrInUse,
     /// A nonexistent interface was requested or the requested address was
     /// not local.
-    #[fail(display = "addr not available")]
+    #[error("addr not available")]
     AddrNotAvailable,
     /// An entity was not found.
     ///
     /// The inner `msg` contains information on the specific entity.
-    #[fail(display = "not found: {}", msg)]
+    #[error("not found: {msg}")]
     NotFound {
         /// Additionnal information on the error.
         msg: &'static str,
     },
     /// The open socket limit was reached.
-    #[fail(display = "open socket limit was reached")]
+    #[error("open socket limit was reached")]
     SocketLimit,
+    /// No I/O thread is available to accomplish the task.
+    ///
+    /// This occurs when connecting or binding with a [`Ctx`] that was
+    /// configured with zero I/O threads.
+    ///
+    /// [`Ctx`]: ../ctx/struct.Ctx.html
+    #[error("no i/o thread available")]
+    NoIoThread,
     /// A fn call did not follow its usage contract and provided invalid inputs.
     ///
     /// An `InvalidInput` error is guaranteed to be related to some API misuse
@@ -200,11 +405,102 @@ pub enum ErrorKind {
     /// those types of error.
     ///
     /// The inner `msg` contains information on the specific contract breach.
-    #[fail(display = "invalid input: {}", msg)]
+    #[error("invalid input: {msg}")]
     InvalidInput {
         /// Additionnal information on the error.
         msg: &'static str,
     },
+    /// The requested feature is not supported by the linked *libzmq*.
+    ///
+    /// The inner `msg` contains information on the missing capability.
+    #[error("unsupported: {msg}")]
+    Unsupported {
+        /// Additionnal information on the error.
+        msg: &'static str,
+    },
+    /// A request was not answered after all the attempts allowed by its
+    /// [`RetryPolicy`].
+    ///
+    /// [`RetryPolicy`]: struct.RetryPolicy.html
+    #[error("no reply after {attempts} attempts")]
+    RetriesExhausted {
+        /// The number of times the request was sent.
+        attempts: usize,
+    },
+    /// A received message could not be decoded.
+    ///
+    /// The inner `msg` contains information on the decoding failure.
+    #[error("invalid message: {msg}")]
+    InvalidMessage {
+        /// Additionnal information on the error.
+        msg: &'static str,
+    },
+}
+
+/// Calls the closure until it returns anything but an [`Interrupted`] error.
+///
+/// Unlike [`is_retryable`], this does not retry on [`WouldBlock`] since
+/// the operation would then loop until it succeeds.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, *};
+///
+/// let addr = InprocAddr::new_unique();
+/// let server = ServerBuilder::new().bind(&addr).build()?;
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+///
+/// client.send("msg")?;
+/// let msg = retry_interrupted(|| server.recv_msg())?;
+/// assert_eq!(msg.to_str()?, "msg");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+/// [`is_retryable`]: struct.Error.html#method.is_retryable
+/// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+pub fn retry_interrupted<F, R, T>(mut f: F) -> Result<R, Error<T>>
+where
+    F: FnMut() -> Result<R, Error<T>>,
+{
+    loop {
+        match f() {
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => (),
+            result => return result,
+        }
+    }
+}
+
+/// Maps the errnos that have the same meaning for every operation.
+///
+/// The errnos whose meaning depends on the operation, such as `EINVAL`,
+/// must be handled beforehand by the caller.
+///
+/// # Panic
+/// Panics on the errnos that cannot occur unless the crate has a bug.
+pub(crate) fn from_errno(errno: i32) -> Error {
+    let kind = match errno {
+        errno::EAGAIN => ErrorKind::WouldBlock,
+        errno::EINTR => ErrorKind::Interrupted,
+        errno::ETERM => ErrorKind::CtxTerminated,
+        errno::EHOSTUNREACH => ErrorKind::HostUnreachable,
+        errno::EMFILE => ErrorKind::SocketLimit,
+        errno::EMTHREAD => ErrorKind::NoIoThread,
+        errno::EADDRINUSE => ErrorKind::AddrInUse,
+        errno::EADDRNOTAVAIL | errno::ENODEV => ErrorKind::AddrNotAvailable,
+        errno::EPROTONOSUPPORT => ErrorKind::Unsupported {
+            msg: "transport not supported",
+        },
+        errno::ENOTSOCK => panic!("invalid socket"),
+        _ => panic!(msg_from_errno(errno)),
+    };
+
+    Error::new(kind).with_errno(errno)
 }
 
 pub(crate) fn msg_from_errno(x: i32) -> String {
@@ -217,3 +513,282 @@ pub(crate) fn msg_from_errno(x: i32) -> String {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, *};
+
+    use std::{convert::TryInto, time::Duration};
+
+    use std::error::Error as StdError;
+
+    #[test]
+    fn test_from_errno() {
+        let cases = vec![
+            (errno::EAGAIN, ErrorKind::WouldBlock),
+            (errno::EINTR, ErrorKind::Interrupted),
+            (errno::ETERM, ErrorKind::CtxTerminated),
+            (errno::EHOSTUNREACH, ErrorKind::HostUnreachable),
+            (errno::EMFILE, ErrorKind::SocketLimit),
+            (errno::EMTHREAD, ErrorKind::NoIoThread),
+            (errno::EADDRINUSE, ErrorKind::AddrInUse),
+            (errno::EADDRNOTAVAIL, ErrorKind::AddrNotAvailable),
+            (errno::ENODEV, ErrorKind::AddrNotAvailable),
+            (
+                errno::EPROTONOSUPPORT,
+                ErrorKind::Unsupported {
+                    msg: "transport not supported",
+                },
+            ),
+        ];
+
+        for (errno, kind) in cases {
+            let err = from_errno(errno);
+            assert_eq!(err.kind(), kind);
+            assert_eq!(err.raw_os_error(), Some(errno));
+        }
+    }
+
+    #[test]
+    fn test_into_io_error() {
+        let cases = vec![
+            (ErrorKind::WouldBlock, io::ErrorKind::WouldBlock),
+            (ErrorKind::Interrupted, io::ErrorKind::Interrupted),
+            (ErrorKind::HostUnreachable, io::ErrorKind::HostUnreachable),
+            (ErrorKind::CtxTerminated, io::ErrorKind::ConnectionAborted),
+            (ErrorKind::AddrInUse, io::ErrorKind::AddrInUse),
+            (ErrorKind::AddrNotAvailable, io::ErrorKind::AddrNotAvailable),
+            (ErrorKind::NotFound { msg: "" }, io::ErrorKind::NotFound),
+            (
+                ErrorKind::InvalidInput { msg: "" },
+                io::ErrorKind::InvalidInput,
+            ),
+            (
+                ErrorKind::Unsupported { msg: "" },
+                io::ErrorKind::Unsupported,
+            ),
+            (
+                ErrorKind::RetriesExhausted { attempts: 3 },
+                io::ErrorKind::TimedOut,
+            ),
+            (
+                ErrorKind::InvalidMessage { msg: "" },
+                io::ErrorKind::InvalidData,
+            ),
+            (ErrorKind::SocketLimit, io::ErrorKind::Other),
+            (ErrorKind::NoIoThread, io::ErrorKind::Other),
+        ];
+
+        for (kind, io_kind) in cases {
+            let err = Error::with_content(kind, "msg");
+            let io_err: io::Error = err.into();
+            assert_eq!(io_err.kind(), io_kind);
+            assert_eq!(io_err.to_string(), kind.to_string());
+        }
+    }
+
+    #[test]
+    fn test_try_from_io_error() {
+        let cases = vec![
+            (io::ErrorKind::WouldBlock, ErrorKind::WouldBlock),
+            (io::ErrorKind::Interrupted, ErrorKind::Interrupted),
+            (io::ErrorKind::HostUnreachable, ErrorKind::HostUnreachable),
+            (io::ErrorKind::AddrInUse, ErrorKind::AddrInUse),
+            (io::ErrorKind::AddrNotAvailable, ErrorKind::AddrNotAvailable),
+        ];
+
+        for (io_kind, kind) in cases {
+            let err = Error::try_from(io::Error::from(io_kind)).unwrap();
+            assert_eq!(err.kind(), kind);
+        }
+
+        let io_err = io::Error::from_raw_os_error(libc::EINTR);
+        let err = Error::try_from(io_err).unwrap();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+        assert_eq!(err.raw_os_error(), Some(libc::EINTR));
+
+        // Kinds without an equivalent are returned as is.
+        let io_err = io::Error::new(io::ErrorKind::BrokenPipe, "pipe");
+        let io_err = Error::try_from(io_err).unwrap_err();
+        assert_eq!(io_err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn test_io_error_round_trip() {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let err = ServerBuilder::new().bind(&bound).build().unwrap_err();
+        let errno = err.raw_os_error();
+        assert!(errno.is_some());
+
+        let io_err: io::Error = err.into();
+        assert_eq!(io_err.kind(), io::ErrorKind::AddrInUse);
+
+        let err = Error::try_from(io_err).unwrap();
+        assert_eq!(err.kind(), ErrorKind::AddrInUse);
+        assert_eq!(err.build_step(), Some(BuildStep::Bind));
+        assert_eq!(err.endpoint(), Some(&bound));
+        assert_eq!(err.raw_os_error(), errno);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid socket")]
+    fn test_from_errno_bug() {
+        from_errno(errno::ENOTSOCK);
+    }
+
+    #[test]
+    fn test_no_io_thread() {
+        let ctx = CtxBuilder::new().io_threads(0).build().unwrap();
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let err = ServerBuilder::new().bind(addr).with_ctx(&ctx).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NoIoThread);
+    }
+
+    #[test]
+    fn test_ctx_terminated() {
+        let ctx = Ctx::new();
+        let server = Server::with_ctx(&ctx).unwrap();
+        ctx.shutdown();
+
+        let err = server.try_recv_msg().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CtxTerminated);
+        let err = Server::with_ctx(&ctx).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CtxTerminated);
+    }
+
+    #[test]
+    fn test_would_block() {
+        let server = Server::new().unwrap();
+
+        let err = server.try_recv_msg().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert!(err.is_retryable());
+
+        // Would block errors are not retried.
+        let mut calls = 0;
+        let err = retry_interrupted(|| {
+            calls += 1;
+            server.try_recv_msg()
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_retry_interrupted() {
+        let mut calls = 0;
+        let result: Result<_, Error> = retry_interrupted(|| {
+            calls += 1;
+            if calls < 3 {
+                Err(Error::new(ErrorKind::Interrupted))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let err: Error = Error::new(ErrorKind::Interrupted);
+        assert!(err.is_retryable());
+        let err: Error = Error::new(ErrorKind::CtxTerminated);
+        assert!(!err.is_retryable());
+        let result: Result<(), Error> =
+            retry_interrupted(|| Err(Error::new(ErrorKind::CtxTerminated)));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::CtxTerminated);
+    }
+
+    #[test]
+    fn test_bind_error_endpoint() {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new().bind(addr).build().unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let err = ServerBuilder::new().bind(&bound).build().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AddrInUse);
+        assert_eq!(err.endpoint(), Some(&bound));
+        assert_eq!(err.build_step(), Some(BuildStep::Bind));
+        assert_eq!(
+            err.to_string(),
+            format!("failed to bind: addr in use (endpoint `{}`)", bound)
+        );
+
+        let err = Server::new().unwrap().bind(&bound).unwrap_err();
+        assert_eq!(err.endpoint(), Some(&bound));
+        assert_eq!(err.build_step(), None);
+    }
+
+    #[test]
+    fn test_connect_error_endpoint() {
+        // The `udp` transport is incompatible with the `Client` socket.
+        let udp: UdpAddr = "127.0.0.1:5555".try_into().unwrap();
+        let endpoints: Vec<Endpoint> = vec![
+            InprocAddr::new_unique().into(),
+            InprocAddr::new_unique().into(),
+            udp.into(),
+        ];
+
+        let err = ClientBuilder::new()
+            .connect(endpoints.clone())
+            .build()
+            .unwrap_err();
+        assert_eq!(err.content(), Some(&2));
+        assert_eq!(err.endpoint(), Some(&endpoints[2]));
+        assert_eq!(err.build_step(), Some(BuildStep::Connect));
+        assert!(err.to_string().starts_with("failed to connect: "));
+        assert!(err.to_string().contains("udp://127.0.0.1:5555"));
+    }
+
+    #[test]
+    fn test_option_error_step() {
+        let err = ServerBuilder::new().zap_domain("").build().unwrap_err();
+        assert_eq!(
+            err.build_step(),
+            Some(BuildStep::Option { name: "zap_domain" })
+        );
+        assert_eq!(err.endpoint(), None);
+        assert_eq!(
+            err.to_string(),
+            "failed to set zap_domain: invalid input: zap domain cannot be empty"
+        );
+
+        // The options specific to a socket type are also reported.
+        let err = ClientBuilder::new()
+            .send_timeout(Duration::from_secs(u64::max_value()))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.build_step(),
+            Some(BuildStep::Option {
+                name: "send_timeout"
+            })
+        );
+
+        // The options are set before connecting.
+        let err = ClientBuilder::new()
+            .connect(InprocAddr::new_unique())
+            .zap_domain("")
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.build_step(),
+            Some(BuildStep::Option { name: "zap_domain" })
+        );
+    }
+
+    #[test]
+    fn test_std_error() {
+        let err: Error<usize> =
+            Error::with_content(ErrorKind::InvalidInput { msg: "some msg" }, 1);
+        assert_eq!(err.to_string(), "invalid input: some msg");
+        assert!(err.source().is_none());
+
+        // Can be used with `?` in functions returning a boxed error.
+        let boxed: Box<dyn StdError + Send + Sync> = err.into();
+        assert_eq!(boxed.to_string(), "invalid input: some msg");
+    }
+}
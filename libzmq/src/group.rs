@@ -1,41 +1,145 @@
 //! Message groups used by the `Radio` and `Dish` sockets.
 
-use failure::Fail;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use libzmq_sys as sys;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use std::{
     borrow::{Borrow, ToOwned},
     convert::TryFrom,
-    fmt, ops, option, str,
+    ffi::CStr,
+    fmt,
+    hash::{Hash, Hasher},
+    ops, option, str,
 };
 
 /// The maximum allowed number of characters in a group.
-pub const MAX_GROUP_SIZE: usize = 15;
+///
+/// Starting with `libzmq` 4.3.3, groups can hold up to 255 char instead of
+/// 15. Since `libzmq-sys` statically links its vendored `libzmq`, the limit
+/// is known at compile time.
+pub const MAX_GROUP_SIZE: usize = max_group_size(
+    sys::ZMQ_VERSION_MAJOR,
+    sys::ZMQ_VERSION_MINOR,
+    sys::ZMQ_VERSION_PATCH,
+);
+
+const fn max_group_size(major: u32, minor: u32, patch: u32) -> usize {
+    if major > 4 || (major == 4 && (minor > 3 || (minor == 3 && patch >= 3))) {
+        255
+    } else {
+        15
+    }
+}
+
+// A `&'static str` cannot be formatted in a `const fn`, so the message
+// for each known limit is spelled out.
+const fn too_long_msg(max: usize) -> &'static str {
+    match max {
+        15 => "group cannot exceed 15 char",
+        255 => "group cannot exceed 255 char",
+        _ => "group cannot exceed MAX_GROUP_SIZE char",
+    }
+}
 
 /// An error returned when trying to parse a `Group` or `GroupOwned`.
 ///
-/// This error occurs from a string that exceeds [`MAX_GROUP_SIZE`] char.
+/// This error occurs from a string that exceeds [`MAX_GROUP_SIZE`] char
+/// or that contains a null byte.
 ///
 /// [`MAX_GROUP_SIZE`]: constant.MAX_GROUP_SIZE.html
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Fail, Hash)]
-#[fail(display = "group cannot exceed MAX_GROUP_SIZE char")]
-pub struct GroupParseError(());
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error, Hash)]
+#[error("{msg}")]
+pub struct GroupParseError {
+    msg: &'static str,
+}
+
+impl GroupParseError {
+    pub fn msg(&self) -> &'static str {
+        self.msg
+    }
+}
+
+// This is a `const fn` so that it can be used by the `group!` macro.
+const fn check_group(s: &str) -> Result<(), GroupParseError> {
+    check_group_with(s, MAX_GROUP_SIZE)
+}
+
+// Checks the group against the given limit, so that both limits can be
+// tested whatever the version of the vendored `libzmq`.
+const fn check_group_with(s: &str, max: usize) -> Result<(), GroupParseError> {
+    let bytes = s.as_bytes();
+    if bytes.len() > max {
+        return Err(GroupParseError {
+            msg: too_long_msg(max),
+        });
+    }
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == 0 {
+            return Err(GroupParseError {
+                msg: "group cannot contain a null byte",
+            });
+        }
+        i += 1;
+    }
+
+    Ok(())
+}
+
+/// Creates a `&'static Group` from a string literal, which is validated at
+/// compile time.
+///
+/// An invalid literal, namely one that exceeds [`MAX_GROUP_SIZE`] char or
+/// that contains a null byte, fails to compile with the reason of the
+/// failure.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, *};
+///
+/// let dish = DishBuilder::new().join(group!("status")).build()?;
+/// assert_eq!(dish.joined(), vec![group!("status")]);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// This fails to compile:
+/// ```compile_fail
+/// let group = libzmq::group!("nul\0byte");
+/// ```
+///
+/// [`MAX_GROUP_SIZE`]: constant.MAX_GROUP_SIZE.html
+#[macro_export]
+macro_rules! group {
+    ($name:literal) => {{
+        const GROUP: &'static $crate::Group =
+            $crate::Group::__from_literal($name);
+        GROUP
+    }};
+}
 
 /// A `str` slice that is a valid ØMQ group identifier.
 ///
-/// Namely, the length this group identifier must not exceed [`MAX_GROUP_SIZE`].
+/// Namely, the length this group identifier must not exceed [`MAX_GROUP_SIZE`]
+/// and it cannot contain a null byte. The empty group is valid.
 ///
 /// # Example
 /// ```
 /// #
-/// # use failure::Error;
-/// # fn main() -> Result<(), Error> {
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::Group;
 /// use std::convert::TryInto;
 ///
 /// let group: &Group = "some group".try_into()?;
 ///
-/// let result: Result<&Group, _> = "group that exceed the char limit".try_into();
+/// let too_long = "a".repeat(Group::max_length() + 1);
+/// let result: Result<&Group, _> = too_long.as_str().try_into();
 /// assert!(result.is_err());
 /// #
 /// #     Ok(())
@@ -49,10 +153,28 @@ pub struct Group {
 }
 
 impl Group {
-    pub(crate) fn from_str_unchecked(s: &str) -> &Group {
+    /// Returns the maximum number of char a group can hold, which is
+    /// [`MAX_GROUP_SIZE`].
+    ///
+    /// [`MAX_GROUP_SIZE`]: constant.MAX_GROUP_SIZE.html
+    pub const fn max_length() -> usize {
+        MAX_GROUP_SIZE
+    }
+
+    pub(crate) const fn from_str_unchecked(s: &str) -> &Group {
         unsafe { &*(s as *const str as *const Group) }
     }
 
+    // Used by the `group!` macro, which forces this to be evaluated at
+    // compile time, so that the panic becomes a compilation error.
+    #[doc(hidden)]
+    pub const fn __from_literal(s: &'static str) -> &'static Group {
+        match check_group(s) {
+            Ok(()) => Group::from_str_unchecked(s),
+            Err(err) => panic!("{}", err.msg),
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         &self.inner
     }
@@ -74,20 +196,15 @@ impl ToOwned for Group {
     type Owned = GroupOwned;
 
     fn to_owned(&self) -> Self::Owned {
-        GroupOwned {
-            inner: self.inner.to_owned(),
-        }
+        GroupOwned::from_str_unchecked(&self.inner)
     }
 }
 
 impl<'a> TryFrom<&'a str> for &'a Group {
     type Error = GroupParseError;
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        if value.len() > MAX_GROUP_SIZE {
-            Err(GroupParseError(()))
-        } else {
-            Ok(Group::from_str_unchecked(value))
-        }
+        check_group(value)?;
+        Ok(Group::from_str_unchecked(value))
     }
 }
 
@@ -110,12 +227,24 @@ impl PartialEq<Group> for str {
     }
 }
 
+impl<'a> PartialEq<&'a str> for Group {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
 impl<'a> PartialEq<GroupOwned> for Group {
     fn eq(&self, other: &GroupOwned) -> bool {
         self.as_str() == other.as_str()
     }
 }
 
+impl<'a> PartialEq<GroupOwned> for &'a Group {
+    fn eq(&self, other: &GroupOwned) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
 impl AsRef<str> for Group {
     fn as_ref(&self) -> &str {
         self.borrow()
@@ -131,12 +260,31 @@ impl ops::Deref for Group {
     }
 }
 
-impl<'a> fmt::Display for &'a Group {
+impl fmt::Display for Group {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", &self.inner)
     }
 }
 
+impl Serialize for Group {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for &'a Group {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <&'de str>::deserialize(deserializer)?;
+        TryFrom::try_from(s).map_err(de::Error::custom)
+    }
+}
+
 impl<'a> IntoIterator for &'a Group {
     type Item = &'a Group;
     type IntoIter = option::IntoIter<&'a Group>;
@@ -146,15 +294,25 @@ impl<'a> IntoIterator for &'a Group {
     }
 }
 
-/// An owned `String` that is a valid ØMQ group identifier.
+/// An owned, inline string that is a valid ØMQ group identifier.
+///
+/// Namely, the length this group identifier must not exceed [`MAX_GROUP_SIZE`]
+/// and it cannot contain a null byte. The empty group is valid.
 ///
-/// Namely, the length this group identifier must not exceed [`MAX_GROUP_SIZE`].
+/// A `GroupOwned` can be parsed via `str::parse` and is (de)serialized as
+/// a string, which is validated on deserialization. Since it borrows as
+/// a [`Group`], a `HashMap<GroupOwned, _>` can be looked up by `&Group`
+/// without allocating.
+///
+/// Since a group is at most [`MAX_GROUP_SIZE`] bytes, it is stored inline
+/// as a null terminated buffer. Thus a `GroupOwned` never allocates and is
+/// `Copy`.
 ///
 /// # Example
 /// ```
 /// #
-/// # use failure::Error;
-/// # fn main() -> Result<(), Error> {
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::GroupOwned;
 /// use std::convert::TryInto;
 ///
@@ -167,9 +325,59 @@ impl<'a> IntoIterator for &'a Group {
 /// # }
 /// ```
 /// [`MAX_GROUP_SIZE`]: constant.MAX_GROUP_SIZE.html
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// [`Group`]: struct.Group.html
+#[derive(Copy, Clone)]
 pub struct GroupOwned {
-    inner: String,
+    // The buffer is null terminated so that it can be passed as is to
+    // `libzmq`.
+    buf: [u8; MAX_GROUP_SIZE + 1],
+    len: u8,
+}
+
+impl GroupOwned {
+    // The string must already be a valid group.
+    fn from_str_unchecked(s: &str) -> Self {
+        let mut buf = [0; MAX_GROUP_SIZE + 1];
+        buf[..s.len()].copy_from_slice(s.as_bytes());
+
+        Self {
+            buf,
+            len: s.len() as u8,
+        }
+    }
+
+    fn as_inner_str(&self) -> &str {
+        // The buffer is copied from a `str` on creation.
+        unsafe { str::from_utf8_unchecked(&self.buf[..self.len as usize]) }
+    }
+
+    pub(crate) fn as_c_str(&self) -> &CStr {
+        // The group is checked to contain no null byte on creation.
+        unsafe {
+            CStr::from_bytes_with_nul_unchecked(&self.buf[..=self.len as usize])
+        }
+    }
+}
+
+impl fmt::Debug for GroupOwned {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_inner_str(), formatter)
+    }
+}
+
+impl PartialEq for GroupOwned {
+    fn eq(&self, other: &GroupOwned) -> bool {
+        self.as_inner_str() == other.as_inner_str()
+    }
+}
+
+impl Eq for GroupOwned {}
+
+// This must hash like a `Group` to respect the `Borrow` contract.
+impl Hash for GroupOwned {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_inner_str().hash(state)
+    }
 }
 
 impl<'a> From<&'a Group> for GroupOwned {
@@ -180,50 +388,37 @@ impl<'a> From<&'a Group> for GroupOwned {
 
 impl From<GroupOwned> for String {
     fn from(g: GroupOwned) -> String {
-        g.inner
+        g.as_inner_str().to_owned()
     }
 }
 
 impl<'a> From<&'a GroupOwned> for GroupOwned {
     fn from(g: &'a GroupOwned) -> GroupOwned {
-        g.to_owned()
+        *g
     }
 }
 
 impl TryFrom<String> for GroupOwned {
     type Error = GroupParseError;
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        if value.len() > MAX_GROUP_SIZE {
-            Err(GroupParseError(()))
-        } else {
-            Ok(Self { inner: value })
-        }
+        check_group(&value)?;
+        Ok(Self::from_str_unchecked(&value))
     }
 }
 
 impl<'a> TryFrom<&'a String> for GroupOwned {
     type Error = GroupParseError;
     fn try_from(value: &'a String) -> Result<Self, Self::Error> {
-        if value.len() > MAX_GROUP_SIZE {
-            Err(GroupParseError(()))
-        } else {
-            Ok(Self {
-                inner: value.to_owned(),
-            })
-        }
+        check_group(value)?;
+        Ok(Self::from_str_unchecked(value))
     }
 }
 
 impl<'a> TryFrom<&'a str> for GroupOwned {
     type Error = GroupParseError;
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        if value.len() > MAX_GROUP_SIZE {
-            Err(GroupParseError(()))
-        } else {
-            Ok(Self {
-                inner: value.to_owned(),
-            })
-        }
+        check_group(value)?;
+        Ok(Self::from_str_unchecked(value))
     }
 }
 
@@ -231,19 +426,19 @@ impl str::FromStr for GroupOwned {
     type Err = GroupParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Self::try_from(s.to_owned())
+        Self::try_from(s)
     }
 }
 
 impl fmt::Display for GroupOwned {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.inner)
+        write!(f, "{}", self.as_inner_str())
     }
 }
 
 impl Borrow<Group> for GroupOwned {
     fn borrow(&self) -> &Group {
-        Group::from_str_unchecked(self.inner.as_str())
+        Group::from_str_unchecked(self.as_inner_str())
     }
 }
 
@@ -268,6 +463,12 @@ impl<'a> PartialEq<Group> for GroupOwned {
     }
 }
 
+impl<'a> PartialEq<&'a Group> for GroupOwned {
+    fn eq(&self, other: &&'a Group) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
 impl PartialEq<str> for GroupOwned {
     fn eq(&self, other: &str) -> bool {
         &**self == other
@@ -327,3 +528,114 @@ impl<'de> Deserialize<'de> for GroupOwned {
         serde_with::rust::display_fromstr::deserialize(deserializer)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::{collections::HashMap, convert::TryInto};
+
+    #[test]
+    fn test_group_validation() {
+        let max = "a".repeat(MAX_GROUP_SIZE);
+        let over = "a".repeat(MAX_GROUP_SIZE + 1);
+
+        let valid = vec!["", "group", max.as_str()];
+        for s in valid {
+            let group: &Group = s.try_into().unwrap();
+            assert_eq!(group, s);
+            assert_eq!(s.parse::<GroupOwned>().unwrap(), s);
+            assert_eq!(GroupOwned::try_from(s.to_owned()).unwrap(), s);
+            assert_eq!(group.to_string(), s);
+        }
+
+        let invalid =
+            vec![("nul\0byte", "null byte"), (over.as_str(), "exceed")];
+        for (s, msg) in invalid {
+            let result: Result<&Group, _> = s.try_into();
+            assert!(result.unwrap_err().msg().contains(msg));
+            assert!(s.parse::<GroupOwned>().is_err());
+            assert!(GroupOwned::try_from(s.to_owned()).is_err());
+            assert!(GroupOwned::try_from(&s.to_owned()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_group_max_length() {
+        assert_eq!(Group::max_length(), MAX_GROUP_SIZE);
+        assert!(MAX_GROUP_SIZE == 15 || MAX_GROUP_SIZE == 255);
+        assert_eq!(max_group_size(4, 3, 2), 15);
+        assert_eq!(max_group_size(4, 3, 3), 255);
+        assert_eq!(max_group_size(5, 0, 0), 255);
+
+        let over = "a".repeat(MAX_GROUP_SIZE + 1);
+        let err = over.parse::<GroupOwned>().unwrap_err();
+        assert!(err.msg().contains(&MAX_GROUP_SIZE.to_string()));
+
+        // Structured names only fit within the extended limit.
+        let name = "tenant-1234/orders/created";
+        let result: Result<&Group, _> = name.try_into();
+        assert_eq!(result.is_ok(), Group::max_length() >= name.len());
+    }
+
+    #[test]
+    fn test_check_group_limits() {
+        assert!(check_group_with(&"a".repeat(15), 15).is_ok());
+        assert!(check_group_with(&"a".repeat(255), 255).is_ok());
+
+        let err = check_group_with(&"a".repeat(16), 15).unwrap_err();
+        assert_eq!(err.msg(), "group cannot exceed 15 char");
+        let err = check_group_with(&"a".repeat(256), 255).unwrap_err();
+        assert_eq!(err.msg(), "group cannot exceed 255 char");
+        assert!(check_group_with("a\0", 255).is_err());
+    }
+
+    #[test]
+    fn test_group_ser_de() {
+        let group: GroupOwned = "group".parse().unwrap();
+
+        let json = serde_json::to_string(&group).unwrap();
+        assert_eq!(json, "\"group\"");
+        let de: GroupOwned = serde_json::from_str(&json).unwrap();
+        assert_eq!(de, group);
+
+        let borrowed: &Group = serde_json::from_str(&json).unwrap();
+        assert_eq!(borrowed, group);
+        assert_eq!(serde_json::to_string(borrowed).unwrap(), json);
+
+        let over = format!("\"{}\"", "a".repeat(MAX_GROUP_SIZE + 1));
+        assert!(serde_json::from_str::<GroupOwned>(&over).is_err());
+        assert!(serde_json::from_str::<&Group>(&over).is_err());
+        assert!(serde_json::from_str::<GroupOwned>("\"a\\u0000\"").is_err());
+    }
+
+    #[test]
+    fn test_group_lookup() {
+        let mut map = HashMap::new();
+        map.insert(GroupOwned::try_from("status").unwrap(), 1);
+
+        let group: &Group = "status".try_into().unwrap();
+        assert_eq!(map.get(group), Some(&1));
+        assert_eq!(group, map.keys().next().unwrap().to_owned());
+        assert_eq!(*group, "status");
+    }
+
+    #[test]
+    fn test_group_owned_inline() {
+        let max = "a".repeat(MAX_GROUP_SIZE);
+        let group: GroupOwned = max.parse().unwrap();
+        assert_eq!(group.as_c_str().to_bytes(), max.as_bytes());
+        assert_eq!(String::from(group), max);
+
+        let empty: GroupOwned = "".parse().unwrap();
+        assert!(empty.as_c_str().to_bytes().is_empty());
+        assert_ne!(empty, group);
+    }
+
+    #[test]
+    fn test_group_macro() {
+        let group: &Group = crate::group!("fifteen chars!!");
+        assert_eq!(group.len(), 15);
+        assert_eq!(crate::group!(""), "");
+    }
+}
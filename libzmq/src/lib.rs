@@ -2,32 +2,60 @@
 
 //! *libzmq* - A strict subset of ØMQ with a high level API.
 
+#[cfg(feature = "failure")]
+#[doc(hidden)]
 pub use failure;
 
 #[macro_use]
 mod core;
+#[cfg(feature = "async")]
+pub mod aio;
 pub mod auth;
+pub mod bridge;
+#[cfg(feature = "compression")]
+mod compression;
 mod ctx;
+mod dispatch;
 mod endpoint;
 mod error;
 mod group;
 mod msg;
 mod old;
 pub mod poll;
+pub mod proxy;
+mod select;
+pub mod shutdown;
 mod socket;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod topology;
 mod utils;
 
-pub use crate::core::{Heartbeat, Period, Quantity};
-pub use ctx::{Ctx, CtxBuilder};
+#[cfg(feature = "compression")]
+pub use crate::compression::{Codec, MAX_DECOMPRESSED_SIZE};
+#[cfg(feature = "stats")]
+pub use crate::core::SocketStats;
+pub use crate::core::{
+    Deadline, Heartbeat, Incoming, Period, Quantity, Readiness, ReconnectStop,
+};
+#[cfg(feature = "diagnostics")]
+pub use ctx::SocketInfo;
+pub use ctx::{Ctx, CtxBuilder, SchedPolicy};
+pub use dispatch::Dispatcher;
 pub use endpoint::{
-    EpgmAddr, InprocAddr, PgmAddr, TcpAddr, UdpAddr, INPROC_MAX_SIZE,
+    EpgmAddr, InprocAddr, PgmAddr, TcpAddr, TipcAddr, UdpAddr, VmciAddr,
+    INPROC_MAX_SIZE,
 };
-pub use error::{Error, ErrorKind};
+#[cfg(unix)]
+pub use endpoint::{IpcAddr, IPC_MAX_SIZE};
+pub use error::{retry_interrupted, BuildStep, Error, ErrorKind};
 pub use group::*;
 pub use msg::*;
 pub use socket::{
-    Client, ClientBuilder, Dish, DishBuilder, Gather, GatherBuilder, Radio,
-    RadioBuilder, Scatter, ScatterBuilder, Server, ServerBuilder, SocketType,
+    BroadcastReport, Client, ClientBuilder, Dish, DishBuilder, Gather,
+    GatherBuilder, HandlerError, Radio, RadioBuilder, RecvHalf, Request,
+    RetryPolicy, Scatter, ScatterBuilder, SendHalf, Server, ServerBuilder,
+    ServerSessions, Session, SocketType,
 };
 pub use utils::*;
 
@@ -45,7 +73,7 @@ pub mod config {
 pub mod addr {
     pub use crate::endpoint::{
         AddrParseError, Endpoint, Hostname, Interface, IntoIpAddrs, Port,
-        SocketAddr, SrcAddr,
+        SocketAddr, SrcAddr, VmciCid,
     };
 }
 
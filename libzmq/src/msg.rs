@@ -1,4 +1,11 @@
-use crate::{error::msg_from_errno, Group, GroupOwned};
+use crate::{
+    auth::{
+        server::{CURVE_KEY_PROPERTY, MECHANISM_PROPERTY},
+        CurvePublicKey, MechanismName,
+    },
+    error::msg_from_errno,
+    Group, GroupOwned,
+};
 use libzmq_sys as sys;
 use sys::errno;
 
@@ -7,6 +14,7 @@ use log::error;
 use serde::{Deserialize, Serialize};
 
 use std::{
+    convert::TryFrom,
     ffi::{CStr, CString},
     fmt,
     os::raw::c_void,
@@ -21,30 +29,26 @@ use std::{
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, *};
-/// use std::convert::TryInto;
 ///
-/// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+/// let addr = InprocAddr::new_unique();
 ///
 /// let server = ServerBuilder::new()
-///     .bind(addr)
+///     .bind(&addr)
 ///     .build()?;
 ///
-/// let bound = server.last_endpoint()?;
-///
 /// let client = ClientBuilder::new()
-///     .connect(bound)
+///     .connect(addr)
 ///     .build()?;
 ///
 /// // The client initiates the conversation.
 /// client.send("")?;
 /// // ØMQ generates a `RoutingId` for the client upon reception of the
 /// // first message.
-/// let msg = server.recv_msg()?;
-/// let routing_id = msg.routing_id().unwrap();
+/// let (routing_id, _) = server.recv_from()?;
 ///
 /// // This `RoutingId` is used to route messages back to the `Client`.
 /// let mut msg: Msg = "".into();
@@ -107,6 +111,63 @@ impl Msg {
         }
     }
 
+    /// Create a `Msg` from the concatenation of `slices`.
+    ///
+    /// The message is allocated once at the total size of the slices, which
+    /// are then copied in sequence, so no intermediate buffer is needed.
+    ///
+    /// ```
+    /// use libzmq::Msg;
+    ///
+    /// let msg = Msg::from_slices(&[b"header:", b"", b"body"]);
+    ///
+    /// assert_eq!(msg.as_bytes(), b"header:body");
+    /// ```
+    pub fn from_slices(slices: &[&[u8]]) -> Self {
+        Self::from_slices_with_capacity(slices, 0)
+    }
+
+    /// Create a `Msg` of at least `capacity` bytes that starts with the
+    /// concatenation of `slices`.
+    ///
+    /// Since the size of a ØMQ message cannot change once allocated, the
+    /// trailing headroom is part of the message and is zeroed. It can be
+    /// filled in place later on using [`as_bytes_mut`]. If the slices are
+    /// larger than `capacity`, this is the same as [`from_slices`].
+    ///
+    /// [`as_bytes_mut`]: #method.as_bytes_mut
+    /// [`from_slices`]: #method.from_slices
+    ///
+    /// ```
+    /// use libzmq::Msg;
+    ///
+    /// let mut msg = Msg::from_slices_with_capacity(&[b"len:"], 5);
+    /// assert_eq!(msg.as_bytes(), b"len:\0");
+    ///
+    /// msg.as_bytes_mut()[4] = b'4';
+    /// assert_eq!(msg.as_bytes(), b"len:4");
+    /// ```
+    pub fn from_slices_with_capacity(
+        slices: &[&[u8]],
+        capacity: usize,
+    ) -> Self {
+        let len: usize = slices.iter().map(|slice| slice.len()).sum();
+        let mut msg = Msg::with_size(len.max(capacity));
+        let bytes = msg.as_bytes_mut();
+
+        let mut offset = 0;
+        for slice in slices {
+            bytes[offset..offset + slice.len()].copy_from_slice(slice);
+            offset += slice.len();
+        }
+        // `zmq_msg_init_size` does not initialize the content.
+        for byte in &mut bytes[len..] {
+            *byte = 0;
+        }
+
+        msg
+    }
+
     /// Returns the message content size in bytes.
     ///
     /// See [`zmq_msg_size`].
@@ -124,9 +185,9 @@ impl Msg {
     /// Return the message content as a `str` slice if it is valid UTF-8.
     ///
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::Msg;
     ///
     /// let text = "blzit";
@@ -232,9 +293,9 @@ impl Msg {
     /// Set the group property on the message.
     ///
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{Msg, Group};
     /// use std::convert::TryInto;
     ///
@@ -249,21 +310,20 @@ impl Msg {
     /// ```
     ///
     /// # Usage Contract
-    /// * Cannot hold more than 15 characters.
+    /// * Cannot hold more than [`MAX_GROUP_SIZE`] characters.
     ///
     /// # Returned Error Variants
     /// * [`InvalidInput`] (if contract is not followed)
     ///
+    /// [`MAX_GROUP_SIZE`]: constant.MAX_GROUP_SIZE.html
     /// [`InvalidInput`]: ../enum.Error.html#variant.InvalidInput
     pub fn set_group<G>(&mut self, group: G)
     where
         G: Into<GroupOwned>,
     {
         let group = group.into();
-        let string: String = group.into();
-        let c_string = CString::new(string).unwrap();
         let rc = unsafe {
-            sys::zmq_msg_set_group(self.as_mut_ptr(), c_string.as_ptr())
+            sys::zmq_msg_set_group(self.as_mut_ptr(), group.as_c_str().as_ptr())
         };
 
         // Should never occur.
@@ -273,6 +333,60 @@ impl Msg {
         }
     }
 
+    /// Get a metadata property of the message, such as the `User-Id`
+    /// attributed to the peer by the `AuthServer`.
+    ///
+    /// Returns `None` if the property is not set, which is always the case
+    /// for messages that were not received from a socket.
+    ///
+    /// See [`zmq_msg_gets`].
+    ///
+    /// [`zmq_msg_gets`]: http://api.zeromq.org/master:zmq-msg-gets
+    pub fn property(&self, name: &str) -> Option<&str> {
+        let name = CString::new(name).ok()?;
+        let char_ptr =
+            unsafe { sys::zmq_msg_gets(self.as_ptr(), name.as_ptr()) };
+
+        if char_ptr.is_null() {
+            None
+        } else {
+            // The property lives as long as the msg.
+            unsafe { CStr::from_ptr(char_ptr).to_str().ok() }
+        }
+    }
+
+    /// Returns the user id attributed to the peer by the `AuthServer`.
+    ///
+    /// This is the username for the `PLAIN` mechanism and the public key
+    /// for the `CURVE` mechanism when authentication is enabled.
+    ///
+    /// Returns `None` if the peer was not authenticated or if its user id
+    /// is empty.
+    pub fn user_id(&self) -> Option<&str> {
+        self.property("User-Id").filter(|id| !id.is_empty())
+    }
+
+    /// Returns the mechanism of the handshake with the peer.
+    ///
+    /// Returns `None` if the peer was not authenticated by the `AuthServer`,
+    /// which is always the case for the `Null` mechanism.
+    pub fn mechanism(&self) -> Option<MechanismName> {
+        self.property(MECHANISM_PROPERTY)
+            .and_then(|name| MechanismName::try_from(name).ok())
+    }
+
+    /// Returns the public key of the peer if it was authenticated using
+    /// the `CURVE` mechanism.
+    ///
+    /// Unlike the [`user_id`], this is set even if `CURVE` authentication
+    /// is disabled.
+    ///
+    /// [`user_id`]: #method.user_id
+    pub fn peer_curve_key(&self) -> Option<CurvePublicKey> {
+        self.property(CURVE_KEY_PROPERTY)
+            .and_then(|key| CurvePublicKey::new(key).ok())
+    }
+
     // Defers the allocation of a zmq_msg_t to the closure.
     //
     // TODO Consider allocating without zeroing.
@@ -500,4 +614,38 @@ mod tests {
             assert_eq!(i, j.0);
         }
     }
+
+    #[test]
+    fn test_from_slices_empty() {
+        let msg = Msg::from_slices(&[]);
+        assert!(msg.is_empty());
+
+        let msg = Msg::from_slices(&[b"", b""]);
+        assert!(msg.is_empty());
+    }
+
+    #[test]
+    fn test_from_slices_empty_elements() {
+        let msg = Msg::from_slices(&[b"", b"first", b"", b"second", b""]);
+        assert_eq!(msg.as_bytes(), b"firstsecond");
+    }
+
+    #[test]
+    fn test_from_slices_concat() {
+        let slices: Vec<Vec<u8>> =
+            (0..10u8).map(|i| (0..i).map(|j| i ^ j).collect()).collect();
+        let refs: Vec<&[u8]> = slices.iter().map(Vec::as_slice).collect();
+
+        let msg = Msg::from_slices(&refs);
+        assert_eq!(msg.as_bytes(), slices.concat().as_slice());
+    }
+
+    #[test]
+    fn test_from_slices_with_capacity() {
+        let msg = Msg::from_slices_with_capacity(&[b"ab", b"c"], 6);
+        assert_eq!(msg.as_bytes(), b"abc\0\0\0");
+
+        let msg = Msg::from_slices_with_capacity(&[b"ab", b"c"], 1);
+        assert_eq!(msg.as_bytes(), b"abc");
+    }
 }
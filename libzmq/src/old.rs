@@ -5,11 +5,10 @@ use crate::{
     Ctx, Msg,
 };
 use libzmq_sys as sys;
-use sys::errno;
 
 use libc::c_int;
 
-use std::os::raw::c_void;
+use std::{os::raw::c_void, sync::Arc};
 
 fn send(
     mut_sock_ptr: *mut c_void,
@@ -29,14 +28,7 @@ fn send(
 
     if rc == -1 {
         let errno = unsafe { sys::zmq_errno() };
-        let err = {
-            match errno {
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::EINTR => Error::new(ErrorKind::Interrupted),
-                errno::EAGAIN => Error::new(ErrorKind::WouldBlock),
-                _ => panic!(msg_from_errno(errno)),
-            }
-        };
+        let err = from_errno(errno);
 
         Err(err)
     } else {
@@ -44,19 +36,19 @@ fn send(
     }
 }
 
-fn recv(mut_sock_ptr: *mut c_void, msg: &mut Msg) -> Result<(), Error> {
-    let rc = unsafe { sys::zmq_msg_recv(msg.as_mut_ptr(), mut_sock_ptr, 0) };
+fn recv(
+    mut_sock_ptr: *mut c_void,
+    msg: &mut Msg,
+    no_block: bool,
+) -> Result<(), Error> {
+    let flags = if no_block { sys::ZMQ_DONTWAIT } else { 0 };
+    let rc = unsafe {
+        sys::zmq_msg_recv(msg.as_mut_ptr(), mut_sock_ptr, flags as c_int)
+    };
 
     if rc == -1 {
         let errno = unsafe { sys::zmq_errno() };
-        let err = {
-            match errno {
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::EINTR => Error::new(ErrorKind::Interrupted),
-                errno::EAGAIN => Error::new(ErrorKind::WouldBlock),
-                _ => panic!(msg_from_errno(errno)),
-            }
-        };
+        let err = from_errno(errno);
 
         Err(err)
     } else {
@@ -84,7 +76,7 @@ impl From<OldSocketType> for RawSocketType {
 
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) struct OldSocket {
-    inner: RawSocket,
+    inner: Arc<RawSocket>,
 }
 
 impl OldSocket {
@@ -95,7 +87,7 @@ impl OldSocket {
     where
         C: Into<Ctx>,
     {
-        let inner = RawSocket::with_ctx(socket.into(), ctx.into())?;
+        let inner = Arc::new(RawSocket::with_ctx(socket.into(), ctx.into())?);
 
         Ok(Self { inner })
     }
@@ -108,6 +100,14 @@ impl OldSocket {
         self.inner.bind(&endpoint)
     }
 
+    pub(crate) fn connect<E>(&mut self, endpoint: E) -> Result<(), Error>
+    where
+        E: Into<Endpoint>,
+    {
+        let endpoint = endpoint.into();
+        self.inner.connect(&endpoint)
+    }
+
     pub(crate) fn send<M>(&mut self, msg: M, more: bool) -> Result<(), Error>
     where
         M: Into<Msg>,
@@ -137,10 +137,19 @@ impl OldSocket {
     }
 
     pub(crate) fn recv_msg_multipart(&mut self) -> Result<Vec<Msg>, Error> {
+        self.recv_frames(false)
+    }
+
+    // Returns `WouldBlock` if no message is pending.
+    pub(crate) fn try_recv_msg_multipart(&mut self) -> Result<Vec<Msg>, Error> {
+        self.recv_frames(true)
+    }
+
+    fn recv_frames(&mut self, no_block: bool) -> Result<Vec<Msg>, Error> {
         let mut vec = Vec::new();
         loop {
             let mut msg = Msg::new();
-            recv(self.inner.as_mut_ptr(), &mut msg)?;
+            recv(self.inner.as_mut_ptr(), &mut msg, no_block)?;
             let has_more = msg.has_more();
             vec.push(msg);
             if !has_more {
@@ -154,7 +163,7 @@ impl OldSocket {
 unsafe impl Send for OldSocket {}
 
 impl GetRawSocket for OldSocket {
-    fn raw_socket(&self) -> &RawSocket {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
         &self.inner
     }
 }
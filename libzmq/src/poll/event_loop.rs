@@ -0,0 +1,596 @@
+use super::*;
+use crate::core::recv;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+type SocketHandler<'a> = Box<dyn FnMut(&mut Context<'a>, Msg) + 'a>;
+type TimerHandler<'a> = Box<dyn FnMut(&mut Context<'a>) + 'a>;
+
+/// The identifier of a handler registered in an [`EventLoop`].
+///
+/// [`EventLoop`]: struct.EventLoop.html
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HandlerId(usize);
+
+// The token of the sources registered in the poller of the loop.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Socket(HandlerId),
+    Timer(HandlerId),
+    Waker,
+}
+
+// A registration change requested by a callback, which is applied at the
+// end of the dispatch cycle.
+enum Change<'a> {
+    AddSocket(HandlerId, Arc<RawSocket>, SocketHandler<'a>),
+    AddTimer {
+        id: HandlerId,
+        interval: Duration,
+        once: bool,
+        handler: TimerHandler<'a>,
+    },
+    Remove(HandlerId),
+}
+
+struct TimerEntry<'a> {
+    timer: TimerId,
+    once: bool,
+    handler: TimerHandler<'a>,
+}
+
+/// The handle passed to the callbacks of an [`EventLoop`].
+///
+/// It allows the callbacks to change the registrations of the loop and
+/// to stop it. The changes take effect at the end of the dispatch cycle,
+/// once all the events of the current wait were dispatched. Thus a
+/// handler that is removed by a callback can still be called within the
+/// same cycle.
+///
+/// [`EventLoop`]: struct.EventLoop.html
+pub struct Context<'a> {
+    next_id: usize,
+    changes: Vec<Change<'a>>,
+    stopped: bool,
+}
+
+impl<'a> Context<'a> {
+    fn new() -> Self {
+        Self {
+            next_id: 0,
+            changes: Vec::new(),
+            stopped: false,
+        }
+    }
+
+    fn next_id(&mut self) -> HandlerId {
+        let id = HandlerId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Registers a callback that is called with each message received by
+    /// the socket.
+    ///
+    /// An error in registering the socket, such as a socket that is
+    /// already registered, is returned by [`run`] once the other changes
+    /// were applied.
+    ///
+    /// [`run`]: struct.EventLoop.html#method.run
+    pub fn add<S, F>(&mut self, socket: &S, handler: F) -> HandlerId
+    where
+        S: RecvMsg,
+        F: FnMut(&mut Context<'a>, Msg) + 'a,
+    {
+        let id = self.next_id();
+        let socket = Arc::clone(socket.raw_socket());
+        self.changes
+            .push(Change::AddSocket(id, socket, Box::new(handler)));
+        id
+    }
+
+    /// Registers a callback that is called every `interval`.
+    ///
+    /// The interval is rounded up to the millisecond.
+    pub fn add_timer<F>(&mut self, interval: Duration, handler: F) -> HandlerId
+    where
+        F: FnMut(&mut Context<'a>) + 'a,
+    {
+        self.add_timer_inner(interval, false, Box::new(handler))
+    }
+
+    /// Registers a callback that is called once after `delay`.
+    ///
+    /// The handler is removed once it was called.
+    pub fn add_timer_once<F>(
+        &mut self,
+        delay: Duration,
+        handler: F,
+    ) -> HandlerId
+    where
+        F: FnMut(&mut Context<'a>) + 'a,
+    {
+        self.add_timer_inner(delay, true, Box::new(handler))
+    }
+
+    fn add_timer_inner(
+        &mut self,
+        interval: Duration,
+        once: bool,
+        handler: TimerHandler<'a>,
+    ) -> HandlerId {
+        let id = self.next_id();
+        self.changes.push(Change::AddTimer {
+            id,
+            interval,
+            once,
+            handler,
+        });
+        id
+    }
+
+    /// Removes a handler, which does nothing if it was already removed.
+    ///
+    /// The socket of a removed socket handler is deregistered from the
+    /// loop.
+    pub fn remove(&mut self, id: HandlerId) {
+        self.changes.push(Change::Remove(id));
+    }
+
+    /// Stops the loop at the end of the dispatch cycle.
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+}
+
+/// A handle used to stop an [`EventLoop`] from another thread.
+///
+/// This `struct` is created by the [`stopper`] method on [`EventLoop`].
+///
+/// [`EventLoop`]: struct.EventLoop.html
+/// [`stopper`]: struct.EventLoop.html#method.stopper
+#[derive(Debug, Clone)]
+pub struct Stopper {
+    stopped: Arc<AtomicBool>,
+    waker: Waker,
+}
+
+impl Stopper {
+    /// Stops the associated [`EventLoop`].
+    ///
+    /// If the loop is not running, the next [`run`] returns immediately.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    ///
+    /// [`EventLoop`]: struct.EventLoop.html
+    /// [`run`]: struct.EventLoop.html#method.run
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    pub fn stop(&self) -> Result<(), Error> {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.waker.wake()
+    }
+}
+
+/// A callback-driven event loop on top of a [`Poller`].
+///
+/// Callbacks are registered for the messages received by sockets and for
+/// [`Timers`]. Then [`run`] waits for the events and dispatches them to
+/// the callbacks until the loop is stopped, either by a callback through
+/// its [`Context`] or from another thread through a [`Stopper`].
+///
+/// Each dispatch cycle receives at most one message per readable socket,
+/// so that a busy socket cannot starve the others. The timers that
+/// expired are then dispatched in the order of their deadlines.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{poll::*, prelude::*, *};
+/// use std::time::Duration;
+///
+/// let addr = InprocAddr::new_unique();
+/// let server = ServerBuilder::new().bind(&addr).build()?;
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+/// client.send("ping")?;
+///
+/// let mut event_loop = EventLoop::new()?;
+/// event_loop.add(&server, |ctx, msg| {
+///     assert_eq!(msg.to_str().unwrap(), "ping");
+///     ctx.stop();
+/// })?;
+/// event_loop.add_timer_once(Duration::from_secs(10), |_| {
+///     panic!("timed out");
+/// })?;
+///
+/// event_loop.run()?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Poller`]: struct.Poller.html
+/// [`Timers`]: struct.Timers.html
+/// [`run`]: #method.run
+/// [`Context`]: struct.Context.html
+/// [`Stopper`]: struct.Stopper.html
+pub struct EventLoop<'a> {
+    ctx: Context<'a>,
+    poller: Poller<Token>,
+    timers: Timers<Token>,
+    events: Events<Token>,
+    sockets: HashMap<HandlerId, (Arc<RawSocket>, SocketHandler<'a>)>,
+    timer_entries: HashMap<HandlerId, TimerEntry<'a>>,
+    stopper: Stopper,
+}
+
+impl<'a> EventLoop<'a> {
+    /// Creates an `EventLoop` without any handler.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    /// * [`SocketLimit`]
+    ///
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`SocketLimit`]: ../enum.ErrorKind.html#variant.SocketLimit
+    pub fn new() -> Result<Self, Error> {
+        let mut poller = Poller::new();
+        poller.set_retry_interrupted(true);
+        let waker = poller.add_waker(Token::Waker)?;
+
+        Ok(Self {
+            ctx: Context::new(),
+            poller,
+            timers: Timers::new(),
+            events: Events::new(),
+            sockets: HashMap::new(),
+            timer_entries: HashMap::new(),
+            stopper: Stopper {
+                stopped: Arc::default(),
+                waker,
+            },
+        })
+    }
+
+    /// Registers a callback that is called with each message received by
+    /// the socket.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the socket was already added)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add<S, F>(
+        &mut self,
+        socket: &S,
+        handler: F,
+    ) -> Result<HandlerId, Error>
+    where
+        S: RecvMsg,
+        F: FnMut(&mut Context<'a>, Msg) + 'a,
+    {
+        let id = self.ctx.add(socket, handler);
+        self.apply().map(|()| id)
+    }
+
+    /// Registers a callback that is called every `interval`.
+    ///
+    /// The interval is rounded up to the millisecond.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the interval overflows)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add_timer<F>(
+        &mut self,
+        interval: Duration,
+        handler: F,
+    ) -> Result<HandlerId, Error>
+    where
+        F: FnMut(&mut Context<'a>) + 'a,
+    {
+        let id = self.ctx.add_timer(interval, handler);
+        self.apply().map(|()| id)
+    }
+
+    /// Registers a callback that is called once after `delay`.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the delay overflows)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add_timer_once<F>(
+        &mut self,
+        delay: Duration,
+        handler: F,
+    ) -> Result<HandlerId, Error>
+    where
+        F: FnMut(&mut Context<'a>) + 'a,
+    {
+        let id = self.ctx.add_timer_once(delay, handler);
+        self.apply().map(|()| id)
+    }
+
+    /// Removes a handler.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the handler does not exist)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn remove(&mut self, id: HandlerId) -> Result<(), Error> {
+        if !self.sockets.contains_key(&id)
+            && !self.timer_entries.contains_key(&id)
+        {
+            return Err(Error::new(ErrorKind::InvalidInput {
+                msg: "handler does not exist",
+            }));
+        }
+
+        self.ctx.remove(id);
+        self.apply()
+    }
+
+    /// Returns a [`Stopper`] that stops the loop from another thread.
+    ///
+    /// [`Stopper`]: struct.Stopper.html
+    pub fn stopper(&self) -> Stopper {
+        self.stopper.clone()
+    }
+
+    /// Runs the loop until it is stopped.
+    ///
+    /// The interrupted waits are retried. The loop returns once the
+    /// dispatch cycle during which it was stopped completes, after the
+    /// registration changes of the cycle were applied. It can then be run
+    /// again.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if a callback added a socket twice or a timer
+    ///     that overflows)
+    /// * [`CtxTerminated`]
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    pub fn run(&mut self) -> Result<(), Error> {
+        loop {
+            let stopped = self.stopper.stopped.swap(false, Ordering::SeqCst);
+            if stopped || self.ctx.stopped {
+                self.ctx.stopped = false;
+                return Ok(());
+            }
+
+            self.poller.wait_with_timers(
+                &mut self.events,
+                &mut self.timers,
+                None,
+            )?;
+            self.dispatch()?;
+            self.apply()?;
+        }
+    }
+
+    fn dispatch(&mut self) -> Result<(), Error> {
+        for event in &self.events {
+            match event.id() {
+                Token::Socket(id) => {
+                    let (socket, handler) = match self.sockets.get_mut(&id) {
+                        Some(entry) => entry,
+                        None => continue,
+                    };
+
+                    let mut msg = Msg::new();
+                    match recv(socket, &mut msg, true) {
+                        Ok(()) => handler(&mut self.ctx, msg),
+                        // The message was received by another thread or the
+                        // wait was interrupted.
+                        Err(err) => match err.kind() {
+                            ErrorKind::WouldBlock => (),
+                            ErrorKind::Interrupted => (),
+                            _ => return Err(err),
+                        },
+                    }
+                }
+                Token::Timer(id) => {
+                    if let Some(entry) = self.timer_entries.get_mut(&id) {
+                        (entry.handler)(&mut self.ctx);
+                        // The timer was cancelled by the `Timers`.
+                        if entry.once {
+                            self.timer_entries.remove(&id);
+                        }
+                    }
+                }
+                // The wakeups are drained by the poller.
+                Token::Waker => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Applies the registration changes requested so far. A failed change
+    // does not prevent the following ones, and the first error is returned.
+    fn apply(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+
+        for change in mem::take(&mut self.ctx.changes) {
+            if let Err(err) = self.apply_change(change) {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn apply_change(&mut self, change: Change<'a>) -> Result<(), Error> {
+        match change {
+            Change::AddSocket(id, socket, handler) => {
+                self.poller.add(&socket, Token::Socket(id), READABLE)?;
+                self.sockets.insert(id, (socket, handler));
+            }
+            Change::AddTimer {
+                id,
+                interval,
+                once,
+                handler,
+            } => {
+                let timer = if once {
+                    self.timers.add_once(interval, Token::Timer(id))?
+                } else {
+                    self.timers.add(interval, Token::Timer(id))?
+                };
+                let entry = TimerEntry {
+                    timer,
+                    once,
+                    handler,
+                };
+                self.timer_entries.insert(id, entry);
+            }
+            Change::Remove(id) => {
+                if let Some((socket, _)) = self.sockets.remove(&id) {
+                    self.poller.remove(&socket)?;
+                } else if let Some(entry) = self.timer_entries.remove(&id) {
+                    self.timers.cancel(entry.timer)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, test_util::*};
+
+    use std::{cell::Cell, thread};
+
+    #[test]
+    fn test_sockets_and_timer() {
+        let (client_a, server_a) = client_server_pair();
+        let (client_b, server_b) = client_server_pair();
+        let received_a = Cell::new(0);
+        let received_b = Cell::new(0);
+        let ticks = Cell::new(0);
+
+        let mut event_loop = EventLoop::new().unwrap();
+        event_loop
+            .add(&server_a, |_, _| received_a.set(received_a.get() + 1))
+            .unwrap();
+        event_loop
+            .add(&server_b, |_, _| received_b.set(received_b.get() + 1))
+            .unwrap();
+        event_loop
+            .add_timer(Duration::from_millis(10), |ctx| {
+                ticks.set(ticks.get() + 1);
+                if ticks.get() >= 3
+                    && received_a.get() == 2
+                    && received_b.get() == 1
+                {
+                    ctx.stop();
+                }
+            })
+            .unwrap();
+
+        client_a.send("a").unwrap();
+        client_a.send("a").unwrap();
+        client_b.send("b").unwrap();
+
+        event_loop.run().unwrap();
+        drop(event_loop);
+
+        assert_eq!(received_a.get(), 2);
+        assert_eq!(received_b.get(), 1);
+        assert!(ticks.get() >= 3);
+    }
+
+    #[test]
+    fn test_registration_changes_from_callback() {
+        let (client_a, server_a) = client_server_pair();
+        let (client_b, server_b) = client_server_pair();
+        let received_a = Cell::new(0);
+        let handler_a = Cell::new(None);
+
+        let mut event_loop = EventLoop::new().unwrap();
+        let id = event_loop
+            .add(&server_a, |ctx, _| {
+                received_a.set(received_a.get() + 1);
+                ctx.remove(handler_a.get().unwrap());
+                ctx.add(&server_b, |ctx, msg| {
+                    assert_eq!(msg.to_str().unwrap(), "b");
+                    ctx.stop();
+                });
+            })
+            .unwrap();
+        handler_a.set(Some(id));
+
+        client_a.send("a").unwrap();
+        client_a.send("a").unwrap();
+        client_b.send("b").unwrap();
+
+        event_loop.run().unwrap();
+
+        // The handler was removed once it received the first message.
+        let err = event_loop.remove(id).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+        drop(event_loop);
+
+        assert_eq!(received_a.get(), 1);
+        server_a.try_recv_msg().unwrap();
+    }
+
+    #[test]
+    fn test_failed_change_from_callback() {
+        let (client, server) = client_server_pair();
+        let fired = Cell::new(false);
+
+        let mut event_loop = EventLoop::new().unwrap();
+        event_loop
+            .add(&server, |ctx, _| {
+                // The socket is already registered.
+                ctx.add(&server, |_, _| ());
+                ctx.add_timer_once(Duration::from_millis(1), |ctx| {
+                    fired.set(true);
+                    ctx.stop();
+                });
+            })
+            .unwrap();
+
+        client.send("").unwrap();
+        let err = event_loop.run().unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+
+        // The timer was still registered.
+        event_loop.run().unwrap();
+        drop(event_loop);
+        assert!(fired.get());
+    }
+
+    #[test]
+    fn test_cross_thread_stop() {
+        let (_client, server) = client_server_pair();
+
+        let mut event_loop = EventLoop::new().unwrap();
+        event_loop
+            .add(&server, |_, _| panic!("unexpected message"))
+            .unwrap();
+
+        let stopper = event_loop.stopper();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            stopper.stop()
+        });
+
+        event_loop.run().unwrap();
+        handle.join().unwrap().unwrap();
+    }
+}
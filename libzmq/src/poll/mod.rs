@@ -0,0 +1,3028 @@
+//! Asynchronous polling mechanim.
+
+use crate::{
+    addr::InprocAddr,
+    core::{Deadline, GetRawSocket, RawSocket, RecvMsg, SendMsg, Socket},
+    error::{from_errno, msg_from_errno, Error, ErrorKind},
+    socket::{Client, Server},
+    Msg,
+};
+
+use libzmq_sys as sys;
+use sys::errno;
+
+use bitflags::bitflags;
+#[cfg(all(feature = "mio", unix))]
+use mio::{event::Source, unix::SourceFd, Interest, Registry, Token};
+
+#[cfg(unix)]
+use std::os::unix::io::{AsFd, AsRawFd};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    mem,
+    os::raw::{c_int, c_short, c_void},
+    slice,
+    sync::Arc,
+    time::{Duration, Instant},
+    vec,
+};
+#[cfg(all(feature = "mio", unix))]
+use std::{
+    io,
+    ops::{Deref, DerefMut},
+};
+
+mod event_loop;
+
+pub use event_loop::*;
+
+bitflags! {
+    /// The event flags that can be specified to the poller.
+    pub struct Flags: c_short {
+        /// Specifies no wakeup condition at all.
+        const NO_WAKEUP = 0b00_000_000;
+        /// Specifies wakeup on read readiness event.
+        const READABLE = 0b00_000_001;
+        /// Specifies wakeup on write readiness event.
+        const WRITABLE = 0b00_000_010;
+        /// Reported when an error condition occurred on a raw file
+        /// descriptor, such as the write end of a pipe being closed.
+        ///
+        /// It is always reported and need not be specified.
+        const ERROR = 0b00_000_100;
+    }
+}
+
+/// Specifies no wakeup condition at all.
+pub const NO_WAKEUP: Flags = Flags::NO_WAKEUP;
+/// Specifies wakeup on read readiness.
+pub const READABLE: Flags = Flags::READABLE;
+/// Specifies wakeup on write readiness.
+pub const WRITABLE: Flags = Flags::WRITABLE;
+/// Reported when an error condition occurred on a raw file descriptor.
+pub const ERROR: Flags = Flags::ERROR;
+
+/// A raw file descriptor that can be polled via [`add_fd`].
+///
+/// This is a `RawFd` on unix and a `RawSocket` on windows.
+///
+/// [`add_fd`]: struct.Poller.html#method.add_fd
+#[cfg(unix)]
+pub type RawFd = std::os::unix::io::RawFd;
+/// A raw file descriptor that can be polled via [`add_fd`].
+///
+/// This is a `RawFd` on unix and a `RawSocket` on windows.
+///
+/// [`add_fd`]: struct.Poller.html#method.add_fd
+#[cfg(windows)]
+pub type RawFd = std::os::windows::io::RawSocket;
+
+/// The type used to alias a socket or a `RawFd` when polling.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PollId(pub usize);
+
+impl From<usize> for PollId {
+    fn from(val: usize) -> PollId {
+        PollId(val)
+    }
+}
+
+impl From<PollId> for usize {
+    fn from(val: PollId) -> usize {
+        val.0
+    }
+}
+
+/// An `Iterator` over references to [`Event`].
+///
+/// [`Event`]: struct.Event.html
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T> {
+    inner: slice::Iter<'a, Event<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T>
+where
+    T: Copy,
+{
+    type Item = Event<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().cloned()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// An `Iterator` over a set of [`Event`].
+///
+/// [`Event`]: struct.Event.html
+#[derive(Debug)]
+pub struct IntoIter<T> {
+    inner: vec::IntoIter<Event<T>>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = Event<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// An event detected by a poller.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Event<T = PollId> {
+    flags: Flags,
+    id: T,
+    fd: Option<RawFd>,
+    timer: Option<TimerId>,
+}
+
+impl<T> Event<T>
+where
+    T: Copy,
+{
+    /// Specifies the kind of event that was triggered.
+    ///
+    /// It will never be equal to [`NO_WAKEUP`], unless the event was
+    /// triggered by a timer.
+    ///
+    /// [`NO_WAKEUP`]: constant.NO_WAKEUP.html
+    pub fn flags(&self) -> Flags {
+        self.flags
+    }
+
+    /// The token that was associated with the socket when it was
+    /// registered in the poller.
+    pub fn id(&self) -> T {
+        self.id
+    }
+
+    /// Returns `true` if the event source is ready to be read from.
+    pub fn is_readable(&self) -> bool {
+        self.flags.contains(READABLE)
+    }
+
+    /// Returns `true` if the event source is ready to be written to.
+    pub fn is_writable(&self) -> bool {
+        self.flags.contains(WRITABLE)
+    }
+
+    /// Returns `true` if an error condition occurred on the raw file
+    /// descriptor, which should then be removed from the poller.
+    ///
+    /// This includes the hang up of the peer, such as the write end of a
+    /// pipe being closed. Sockets never report it.
+    pub fn is_error(&self) -> bool {
+        self.flags.contains(ERROR)
+    }
+
+    /// Returns the file descriptor that triggered the event if it was
+    /// registered via [`add_fd`], or `None` if it came from a socket.
+    ///
+    /// [`add_fd`]: struct.Poller.html#method.add_fd
+    pub fn fd(&self) -> Option<RawFd> {
+        self.fd
+    }
+
+    /// Returns the timer that triggered the event if it came from
+    /// [`Timers`], or `None` otherwise.
+    ///
+    /// [`Timers`]: struct.Timers.html
+    pub fn timer(&self) -> Option<TimerId> {
+        self.timer
+    }
+}
+
+/// Used to store [`Event`]s for polling.
+///
+/// The buffer is meant to be reused across waits to avoid allocations. Only
+/// the events that fired are stored.
+///
+/// [`Event`]: struct.Event.html
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Events<T = PollId> {
+    inner: Vec<Event<T>>,
+    limit: Option<usize>,
+    truncated: bool,
+}
+
+impl<T> Events<T>
+where
+    T: Copy,
+{
+    /// Create an unbounded `Events` buffer that can store all the events
+    /// that fired.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an unbounded `Events` buffer that can store `capacity` events
+    /// without reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+            limit: None,
+            truncated: false,
+        }
+    }
+
+    /// Create an `Events` buffer that stores at most `limit` events.
+    ///
+    /// If more events fired than the buffer can store, the extra events
+    /// are dropped and [`is_truncated`] returns `true`. Since polling is
+    /// level-triggered, the dropped events will fire again on the next wait.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{poll::*, Server};
+    ///
+    /// // A server is always writable.
+    /// let first = Server::new()?;
+    /// let second = Server::new()?;
+    ///
+    /// let mut poller = Poller::new();
+    /// poller.add(&first, PollId(0), WRITABLE)?;
+    /// poller.add(&second, PollId(1), WRITABLE)?;
+    ///
+    /// let mut events = Events::with_limit(1);
+    /// let fired = poller.wait_all(&mut events, None)?;
+    ///
+    /// assert_eq!(fired, 2);
+    /// assert_eq!(events.len(), 1);
+    /// assert!(events.is_truncated());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`is_truncated`]: #method.is_truncated
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(limit),
+            limit: Some(limit),
+            truncated: false,
+        }
+    }
+
+    /// Returns the number of events the buffer can store without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Returns the maximum number of events stored by the buffer, or `None`
+    /// if it is unbounded.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Returns `true` if more events fired during the last wait than the
+    /// buffer could store.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn iter(&self) -> Iter<T> {
+        Iter {
+            inner: self.inner.iter(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        self.truncated = false;
+    }
+
+    // Stores the event unless the buffer is full.
+    fn push(&mut self, event: Event<T>) {
+        match self.limit {
+            Some(limit) if self.inner.len() == limit => {
+                self.truncated = true;
+            }
+            _ => self.inner.push(event),
+        }
+    }
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            inner: Vec::new(),
+            limit: None,
+            truncated: false,
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Events<T>
+where
+    T: Copy,
+{
+    type Item = Event<T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for Events<T> {
+    type Item = Event<T>;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.inner.into_iter(),
+        }
+    }
+}
+
+// What was registered in a poller.
+//
+// A socket is held by a strong reference so that it remains open
+// as long as it is registered.
+#[derive(Eq, PartialEq, Debug)]
+enum Source {
+    Socket(Arc<RawSocket>),
+    Fd(RawFd),
+    // The receiving end of a `Waker`.
+    Waker(Server),
+}
+
+// A source registered in a poller along with its token.
+#[derive(Eq, PartialEq, Debug)]
+struct Entry<T> {
+    source: Source,
+    id: T,
+    // The state of a one-shot registration, if it is one.
+    oneshot: Option<Oneshot>,
+}
+
+// A registration that is disarmed once its first event is delivered.
+#[derive(Eq, PartialEq, Debug)]
+struct Oneshot {
+    // The flags the registration is armed with.
+    flags: Flags,
+    armed: bool,
+}
+
+/// A handle used to wake up a [`Poller`] from another thread.
+///
+/// This `struct` is created by the [`add_waker`] method on [`Poller`].
+///
+/// Calling [`wake`] causes the wait of the poller to return with an
+/// [`Event`] that has the token that was specified in [`add_waker`]. If the
+/// poller is not waiting, the wakeup is not lost and the next wait returns
+/// immediately. Consecutive wakeups are coalesced into a single [`Event`].
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::poll::*;
+/// use std::thread;
+///
+/// let mut poller = Poller::new();
+/// let waker = poller.add_waker(PollId(0))?;
+///
+/// let handle = thread::spawn(move || waker.wake());
+///
+/// let mut events = Events::new();
+/// poller.wait(&mut events, None)?;
+///
+/// let event = events.iter().next().unwrap();
+/// assert_eq!(event.id(), PollId(0));
+/// # handle.join().unwrap()?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Poller`]: struct.Poller.html
+/// [`add_waker`]: struct.Poller.html#method.add_waker
+/// [`wake`]: #method.wake
+/// [`Event`]: struct.Event.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Waker {
+    client: Client,
+}
+
+impl Waker {
+    /// Wake up the associated [`Poller`].
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    ///
+    /// [`Poller`]: struct.Poller.html
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    pub fn wake(&self) -> Result<(), Error> {
+        loop {
+            match self.client.try_send(Msg::new()) {
+                Ok(()) => return Ok(()),
+                Err(err) => match err.kind() {
+                    // The queue is full so a wakeup is already pending.
+                    ErrorKind::WouldBlock => return Ok(()),
+                    ErrorKind::Interrupted => (),
+                    _ => return Err(err.cast()),
+                },
+            }
+        }
+    }
+}
+
+/// A mechanism for input/output events multiplexing in a level-triggered fashion.
+///
+/// Each socket is registered along with a token of type `T` which is
+/// yielded back by the [`Event`]s it triggers. Any `Copy` type can be used
+/// as a token, the default being [`PollId`].
+///
+/// Sockets can be added, modified and removed between calls to [`block`] or
+/// [`poll`]. The poller keeps a registered socket alive even if all of its
+/// handles are dropped, until it is either removed or the poller itself is
+/// dropped. Note that this means the context of the socket cannot
+/// be terminated until then.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, Client, Server, InprocAddr, poll::*};
+///
+/// // We initialize our sockets and connect them to each other.
+/// let addr = InprocAddr::new_unique();
+///
+/// let server = Server::new()?;
+/// server.bind(&addr)?;
+///
+/// let client = Client::new()?;
+/// client.connect(addr)?;
+///
+/// // We create our poller instance.
+/// let mut poller = Poller::new();
+/// poller.add(&server, PollId(0), READABLE)?;
+/// poller.add(&client, PollId(1), READABLE)?;
+///
+/// // Initialize the client.
+/// client.send("ping")?;
+///
+/// let mut events = Events::new();
+///
+/// // Now the client and each server will send messages back and forth.
+/// for _ in 0..100 {
+///     // This waits indefinitely until at least one event is detected.
+///     poller.block(&mut events, None)?;
+///     // Iterate over the detected events.
+///     for event in &events {
+///         // Guard against spurious wakeups.
+///         if event.flags() != NO_WAKEUP {
+///             match event.id() {
+///                 // The server is ready to receive an incoming message.
+///                 PollId(0) => {
+///                     let msg = server.recv_msg()?;
+///                     assert_eq!("ping", msg.to_str()?);
+///                     server.send(msg)?;
+///                 }
+///                 // One of the clients is ready to receive an incoming message.
+///                 PollId(1) => {
+///                     let msg = client.recv_msg()?;
+///                     assert_eq!("ping", msg.to_str()?);
+///                     client.send(msg)?;
+///                 }
+///                 _ => unimplemented!(),
+///             }
+///         }
+///     }
+/// }
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// With a custom token type.
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, *, poll::*};
+///
+/// #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// enum Token {
+///     Frontend,
+///     Backend,
+/// }
+///
+/// let addr = InprocAddr::new_unique();
+///
+/// let frontend = ServerBuilder::new().bind(&addr).build()?;
+/// let backend = Client::new()?;
+///
+/// let mut poller = Poller::new();
+/// poller.add(&frontend, Token::Frontend, READABLE)?;
+/// poller.add(&backend, Token::Backend, READABLE)?;
+///
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+/// client.send("")?;
+///
+/// let mut events = Events::new();
+/// poller.block(&mut events, None)?;
+///
+/// for event in &events {
+///     match event.id() {
+///         Token::Frontend => {
+///             frontend.recv_msg()?;
+///         }
+///         Token::Backend => unreachable!(),
+///     }
+/// }
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Event`]: struct.Event.html
+/// [`PollId`]: struct.PollId.html
+/// [`block`]: #method.block
+/// [`poll`]: #method.poll
+#[derive(Eq, PartialEq, Debug)]
+pub struct Poller<T = PollId> {
+    poller: *mut c_void,
+    entries: Vec<Option<Entry<T>>>,
+    raw_events: Vec<sys::zmq_poller_event_t>,
+    events: Events<T>,
+    default_timeout: Option<Duration>,
+    retry_interrupted: bool,
+}
+
+impl<T> Poller<T>
+where
+    T: Copy,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a socket in the poller along with its token and the
+    /// events to poll for.
+    ///
+    /// A socket can only be registered once. Use [`modify`] to change the
+    /// polled events of a registered socket.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the socket was already added)
+    ///
+    /// [`modify`]: #method.modify
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{Server, poll::*, ErrorKind};
+    ///
+    /// let server = Server::new()?;
+    ///
+    /// let mut poller = Poller::new();
+    ///
+    /// poller.add(&server, PollId(0), NO_WAKEUP)?;
+    /// let err = poller.add(&server, PollId(1), NO_WAKEUP).unwrap_err();
+    ///
+    /// match err.kind() {
+    ///     ErrorKind::InvalidInput { .. } => (),
+    ///     _ => panic!("unexpected error"),
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn add(
+        &mut self,
+        socket: &GetRawSocket,
+        id: T,
+        flags: Flags,
+    ) -> Result<(), Error> {
+        let socket = socket.raw_socket();
+        let socket_mut_ptr = socket.as_mut_ptr();
+
+        let slot = self.next_slot();
+        let user_data = slot as *mut usize as *mut c_void;
+
+        let rc = unsafe {
+            sys::zmq_poller_add(
+                self.poller,
+                socket_mut_ptr,
+                user_data,
+                flags.bits(),
+            )
+        };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+            let err = {
+                match errno {
+                    errno::EINVAL => Error::new(ErrorKind::InvalidInput {
+                        msg: "cannot add socket twice",
+                    }),
+                    _ => from_errno(errno),
+                }
+            };
+
+            Err(err.with_errno(errno))
+        } else {
+            let source = Source::Socket(Arc::clone(socket));
+            let entry = Entry {
+                source,
+                id,
+                oneshot: None,
+            };
+            self.insert(slot, entry);
+            Ok(())
+        }
+    }
+
+    /// Deregister a socket from the poller.
+    ///
+    /// This releases the reference that the poller held on the socket.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the socket was not added)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{Server, poll::*, ErrorKind};
+    ///
+    /// let server = Server::new()?;
+    /// let mut poller = Poller::new();
+    ///
+    /// poller.add(&server, PollId(0), NO_WAKEUP)?;
+    /// poller.remove(&server)?;
+    ///
+    /// let err = poller.remove(&server).unwrap_err();
+    /// match err.kind() {
+    ///     ErrorKind::InvalidInput { .. } => (), // cannot remove socket twice.
+    ///     _ => panic!("unexpected error"),
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn remove(&mut self, socket: &GetRawSocket) -> Result<(), Error> {
+        let socket_mut_ptr = socket.raw_socket().as_mut_ptr();
+
+        let rc = unsafe { sys::zmq_poller_remove(self.poller, socket_mut_ptr) };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+
+            let err = {
+                match errno {
+                    errno::EINVAL => Error::new(ErrorKind::InvalidInput {
+                        msg: "cannot remove absent socket",
+                    }),
+                    _ => from_errno(errno),
+                }
+            };
+
+            Err(err.with_errno(errno))
+        } else {
+            // This releases the reference held on the socket.
+            self.release(|source| match source {
+                Source::Socket(socket) => socket.as_mut_ptr() == socket_mut_ptr,
+                _ => false,
+            });
+            Ok(())
+        }
+    }
+
+    /// Change the events polled for a registered socket.
+    ///
+    /// The token associated with the socket is left unchanged.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the socket was not added)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{Server, poll::*, ErrorKind};
+    ///
+    /// let server = Server::new()?;
+    /// let mut poller = Poller::new();
+    ///
+    /// let err = poller.modify(&server, READABLE).unwrap_err();
+    /// match err.kind() {
+    ///     ErrorKind::InvalidInput { .. } => (), // cannot modify absent socket.
+    ///     _ => panic!("unexpected error"),
+    /// }
+    ///
+    /// poller.add(&server, PollId(0), READABLE)?;
+    /// poller.modify(&server, READABLE | WRITABLE)?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn modify(
+        &mut self,
+        socket: &GetRawSocket,
+        flags: Flags,
+    ) -> Result<(), Error> {
+        let socket_mut_ptr = socket.raw_socket().as_mut_ptr();
+
+        let rc = unsafe {
+            sys::zmq_poller_modify(self.poller, socket_mut_ptr, flags.bits())
+        };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+
+            let err = {
+                match errno {
+                    errno::EINVAL => Error::new(ErrorKind::InvalidInput {
+                        msg: "cannot modify absent socket",
+                    }),
+                    _ => from_errno(errno),
+                }
+            };
+
+            Err(err.with_errno(errno))
+        } else {
+            // This also rearms a one-shot registration.
+            if let Some(oneshot) = self.oneshot_mut(socket_mut_ptr) {
+                oneshot.flags = flags;
+                oneshot.armed = true;
+            }
+            Ok(())
+        }
+    }
+
+    /// Register a socket in the poller as a one-shot registration.
+    ///
+    /// Once an event of the socket is delivered by a wait, the registration
+    /// is disarmed and the socket is no longer polled until [`rearm`] is
+    /// called with its token or its events are changed via [`modify`]. This
+    /// allows the thread that handles the event to take ownership of the
+    /// socket, similar to `EPOLLONESHOT`. Since the readiness of a socket
+    /// is level-triggered, rearming a socket that is still ready delivers
+    /// its event right away.
+    ///
+    /// The socket is otherwise registered like with [`add`] and is
+    /// deregistered via [`remove`] whether it is armed or not.
+    ///
+    /// # Races
+    /// ØMQ has no native one-shot registrations, so the poller emulates
+    /// them by disabling the events of the socket as soon as the wait that
+    /// reported them returns. This is free of races within the poller, since
+    /// it cannot be waited on concurrently. However, the registration only
+    /// affects this poller: another poller or a thread calling a blocking
+    /// `recv` on the same socket still sees its readiness.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the socket was already added)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, poll::*, *};
+    /// use std::time::Duration;
+    ///
+    /// let addr = InprocAddr::new_unique();
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// let mut poller = Poller::new();
+    /// poller.add_oneshot(&server, PollId(0), READABLE)?;
+    ///
+    /// client.send("")?;
+    /// let mut events = Events::new();
+    /// poller.block(&mut events, None)?;
+    ///
+    /// // The message is still pending but the registration is disarmed.
+    /// let timeout = Some(Duration::from_millis(10));
+    /// assert_eq!(poller.wait(&mut events, timeout)?, 0);
+    ///
+    /// poller.rearm(PollId(0))?;
+    /// assert_eq!(poller.wait(&mut events, None)?, 1);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`rearm`]: #method.rearm
+    /// [`modify`]: #method.modify
+    /// [`add`]: #method.add
+    /// [`remove`]: #method.remove
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add_oneshot(
+        &mut self,
+        socket: &GetRawSocket,
+        id: T,
+        flags: Flags,
+    ) -> Result<(), Error> {
+        let slot = self.next_slot();
+        self.add(socket, id, flags)?;
+
+        let entry = self.entries[slot].as_mut().unwrap();
+        entry.oneshot = Some(Oneshot { flags, armed: true });
+        Ok(())
+    }
+
+    /// Register a raw file descriptor in the poller along with its token
+    /// and the events to poll for.
+    ///
+    /// This allows to poll non-ØMQ file descriptors, such as pipes or
+    /// `signalfd`, alongside sockets. The [`Event`]s triggered by the file
+    /// descriptor report it via [`Event::fd`].
+    ///
+    /// If an error condition occurs on the file descriptor, the event
+    /// reports it via [`Event::is_error`], whatever the flags.
+    ///
+    /// # Usage Contract
+    /// * The file descriptor must remain open while it is registered.
+    ///   Otherwise it might be reused by the OS, in which case the poller
+    ///   would report the events of an unrelated file descriptor. Use
+    ///   [`remove_fd`] before closing it. [`add_as_fd`] ensures that the
+    ///   file descriptor is at least open when it is added.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the file descriptor was already added)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::poll::*;
+    /// use std::{net::UdpSocket, os::unix::io::AsRawFd};
+    ///
+    /// let udp = UdpSocket::bind("127.0.0.1:0")?;
+    ///
+    /// let mut poller = Poller::new();
+    /// poller.add_fd(udp.as_raw_fd(), PollId(0), WRITABLE)?;
+    ///
+    /// let mut events = Events::new();
+    /// poller.poll(&mut events)?;
+    ///
+    /// let event = events.iter().next().unwrap();
+    /// assert_eq!(event.fd(), Some(udp.as_raw_fd()));
+    ///
+    /// poller.remove_fd(udp.as_raw_fd())?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Event`]: struct.Event.html
+    /// [`Event::fd`]: struct.Event.html#method.fd
+    /// [`Event::is_error`]: struct.Event.html#method.is_error
+    /// [`remove_fd`]: #method.remove_fd
+    /// [`add_as_fd`]: #method.add_as_fd
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add_fd(
+        &mut self,
+        fd: RawFd,
+        id: T,
+        flags: Flags,
+    ) -> Result<(), Error> {
+        let slot = self.next_slot();
+        let user_data = slot as *mut usize as *mut c_void;
+
+        let rc = unsafe {
+            sys::zmq_poller_add_fd(self.poller, fd, user_data, flags.bits())
+        };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+            let err = {
+                match errno {
+                    errno::EINVAL => Error::new(ErrorKind::InvalidInput {
+                        msg: "cannot add fd twice",
+                    }),
+                    _ => from_errno(errno),
+                }
+            };
+
+            Err(err.with_errno(errno))
+        } else {
+            let source = Source::Fd(fd);
+            let entry = Entry {
+                source,
+                id,
+                oneshot: None,
+            };
+            self.insert(slot, entry);
+            Ok(())
+        }
+    }
+
+    /// Same as [`add_fd`] but takes a borrowed file descriptor, so that it
+    /// is known to be open when it is registered.
+    ///
+    /// The usage contract of [`add_fd`] still applies, namely the file
+    /// descriptor must be removed via [`remove_as_fd`] before it is closed.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::poll::*;
+    /// use std::net::UdpSocket;
+    ///
+    /// let udp = UdpSocket::bind("127.0.0.1:0")?;
+    ///
+    /// let mut poller = Poller::new();
+    /// poller.add_as_fd(&udp, PollId(0), WRITABLE)?;
+    ///
+    /// let mut events = Events::new();
+    /// poller.poll(&mut events)?;
+    /// assert!(events.iter().next().unwrap().is_writable());
+    ///
+    /// poller.remove_as_fd(&udp)?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`add_fd`]: #method.add_fd
+    /// [`remove_as_fd`]: #method.remove_as_fd
+    #[cfg(unix)]
+    pub fn add_as_fd<F>(
+        &mut self,
+        source: &F,
+        id: T,
+        flags: Flags,
+    ) -> Result<(), Error>
+    where
+        F: AsFd,
+    {
+        self.add_fd(source.as_fd().as_raw_fd(), id, flags)
+    }
+
+    /// Same as [`remove_fd`] but takes a borrowed file descriptor.
+    ///
+    /// [`remove_fd`]: #method.remove_fd
+    #[cfg(unix)]
+    pub fn remove_as_fd<F>(&mut self, source: &F) -> Result<(), Error>
+    where
+        F: AsFd,
+    {
+        self.remove_fd(source.as_fd().as_raw_fd())
+    }
+
+    /// Create a [`Waker`] that can wake up the poller from another thread.
+    ///
+    /// The wakeups are reported as [`Event`]s with the specified token.
+    /// The waker remains registered for the lifetime of the poller.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    /// * [`SocketLimit`]
+    ///
+    /// [`Waker`]: struct.Waker.html
+    /// [`Event`]: struct.Event.html
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`SocketLimit`]: ../enum.ErrorKind.html#variant.SocketLimit
+    pub fn add_waker(&mut self, id: T) -> Result<Waker, Error> {
+        let addr = InprocAddr::new_unique();
+
+        let server = Server::new()?;
+        server.bind(&addr)?;
+        let client = Client::new()?;
+        client.connect(&addr)?;
+
+        let slot = self.next_slot();
+        let user_data = slot as *mut usize as *mut c_void;
+
+        let rc = unsafe {
+            sys::zmq_poller_add(
+                self.poller,
+                server.raw_socket().as_mut_ptr(),
+                user_data,
+                READABLE.bits(),
+            )
+        };
+
+        if rc == -1 {
+            panic!(msg_from_errno(unsafe { sys::zmq_errno() }));
+        }
+
+        let source = Source::Waker(server);
+        let entry = Entry {
+            source,
+            id,
+            oneshot: None,
+        };
+        self.insert(slot, entry);
+
+        Ok(Waker { client })
+    }
+
+    /// Deregister a raw file descriptor from the poller.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the file descriptor was not added)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn remove_fd(&mut self, fd: RawFd) -> Result<(), Error> {
+        let rc = unsafe { sys::zmq_poller_remove_fd(self.poller, fd) };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+            let err = {
+                match errno {
+                    errno::EINVAL => Error::new(ErrorKind::InvalidInput {
+                        msg: "cannot remove absent fd",
+                    }),
+                    _ => from_errno(errno),
+                }
+            };
+
+            Err(err.with_errno(errno))
+        } else {
+            self.release(|source| *source == Source::Fd(fd));
+            Ok(())
+        }
+    }
+
+    /// Change the events polled for a registered raw file descriptor.
+    ///
+    /// The token associated with the file descriptor is left unchanged.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the file descriptor was not added)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn modify_fd(&mut self, fd: RawFd, flags: Flags) -> Result<(), Error> {
+        let rc =
+            unsafe { sys::zmq_poller_modify_fd(self.poller, fd, flags.bits()) };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+            let err = {
+                match errno {
+                    errno::EINVAL => Error::new(ErrorKind::InvalidInput {
+                        msg: "cannot modify absent fd",
+                    }),
+                    _ => from_errno(errno),
+                }
+            };
+
+            Err(err.with_errno(errno))
+        } else {
+            Ok(())
+        }
+    }
+
+    // The user data of a registration is the index of the entry that
+    // stores its token.
+    fn next_slot(&self) -> usize {
+        self.entries
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or_else(|| self.entries.len())
+    }
+
+    fn insert(&mut self, slot: usize, entry: Entry<T>) {
+        if slot == self.entries.len() {
+            self.entries.push(Some(entry));
+        } else {
+            self.entries[slot] = Some(entry);
+        }
+    }
+
+    fn release<F>(&mut self, predicate: F)
+    where
+        F: Fn(&Source) -> bool,
+    {
+        let slot = self.entries.iter().position(|e| match e {
+            Some(entry) => predicate(&entry.source),
+            None => false,
+        });
+        if let Some(slot) = slot {
+            self.entries[slot] = None;
+        }
+    }
+
+    fn oneshot_mut(
+        &mut self,
+        socket_mut_ptr: *mut c_void,
+    ) -> Option<&mut Oneshot> {
+        self.entries.iter_mut().find_map(|e| match e {
+            Some(Entry {
+                source: Source::Socket(socket),
+                oneshot,
+                ..
+            }) if socket.as_mut_ptr() == socket_mut_ptr => oneshot.as_mut(),
+            _ => None,
+        })
+    }
+
+    // Returns the number of events that fired, retrying on `Interrupted`
+    // errors if enabled.
+    fn wait_ms(
+        &mut self,
+        events: &mut Events<T>,
+        mut timeout: i64,
+    ) -> Result<usize, Error> {
+        if !self.retry_interrupted {
+            return self.wait_ms_once(events, timeout);
+        }
+
+        // The timeout is recomputed so that the retries do not extend it.
+        let deadline = if timeout > 0 {
+            Some(Instant::now() + Duration::from_millis(timeout as u64))
+        } else {
+            None
+        };
+
+        loop {
+            match self.wait_ms_once(events, timeout) {
+                Err(ref err) if err.kind() == ErrorKind::Interrupted => {
+                    if let Some(deadline) = deadline {
+                        let now = Instant::now();
+                        timeout = timeout_ms(Some(
+                            deadline.saturating_duration_since(now),
+                        ))?;
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Returns the number of events that fired.
+    fn wait_ms_once(
+        &mut self,
+        events: &mut Events<T>,
+        timeout: i64,
+    ) -> Result<usize, Error> {
+        events.clear();
+        let count = self.entries.iter().filter(|e| e.is_some()).count();
+        self.raw_events.clear();
+        self.raw_events
+            .resize_with(count, sys::zmq_poller_event_t::default);
+
+        let rc = unsafe {
+            sys::zmq_poller_wait_all(
+                self.poller,
+                self.raw_events.as_mut_ptr(),
+                self.raw_events.len() as i32,
+                timeout,
+            )
+        };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+            let err = {
+                match errno {
+                    errno::EINVAL => panic!("invalid poller"),
+                    _ => from_errno(errno),
+                }
+            };
+
+            Err(err)
+        } else {
+            let mut fired = 0;
+            for raw in &self.raw_events[..rc as usize] {
+                // Skip empty events.
+                if raw.events == 0 {
+                    continue;
+                }
+                fired += 1;
+                let slot = raw.user_data as *mut usize as usize;
+                let entry = self.entries[slot].as_mut().unwrap();
+                if let Some(ref mut oneshot) = entry.oneshot {
+                    disarm(self.poller, &entry.source, oneshot);
+                }
+                let fd = match entry.source {
+                    Source::Fd(fd) => Some(fd),
+                    Source::Waker(ref server) => {
+                        // Coalesce all the pending wakeups.
+                        let mut msg = Msg::new();
+                        while server.try_recv(&mut msg).is_ok() {}
+                        None
+                    }
+                    Source::Socket(_) => None,
+                };
+                events.push(Event {
+                    id: entry.id,
+                    // The events we have no flag for, namely `ZMQ_POLLPRI`,
+                    // are ignored.
+                    flags: Flags::from_bits_truncate(raw.events),
+                    fd,
+                    timer: None,
+                });
+            }
+            Ok(fired)
+        }
+    }
+
+    /// The poller will poll for events, returning instantly.
+    ///
+    /// If there are none, returns [`WouldBlock`].
+    ///
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    pub fn poll(&mut self, events: &mut Events<T>) -> Result<(), Error> {
+        self.wait_ms(events, 0).map(|_| ())
+    }
+
+    /// The poller will block until at least an event occurs.
+    ///
+    /// If a duration is specified, the poller will wait for at most the
+    /// duration for an event before it returns [`WouldBlock`]. The duration
+    /// is rounded up to the millisecond.
+    ///
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    pub fn block(
+        &mut self,
+        events: &mut Events<T>,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        self.wait_ms(events, timeout_ms(timeout)?).map(|_| ())
+    }
+
+    /// The poller will wait for events for at most the specified timeout
+    /// and returns the number of detected events.
+    ///
+    /// * `None` blocks until at least an event occurs.
+    /// * `Some(Duration::from_secs(0))` checks for events without blocking.
+    /// * Any other duration is rounded up to the millisecond, so that a
+    ///   sub-millisecond timeout does not turn into a busy loop.
+    ///
+    /// Unlike [`block`] and [`poll`], an expired timeout is not an error
+    /// and returns `Ok(0)`.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`InvalidInput`] (if the timeout overflows)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{Server, poll::*};
+    /// use std::time::Duration;
+    ///
+    /// let server = Server::new()?;
+    ///
+    /// let mut poller = Poller::new();
+    /// poller.add(&server, PollId(0), READABLE)?;
+    ///
+    /// let mut events = Events::new();
+    /// let count = poller.wait(&mut events, Some(Duration::from_millis(1)))?;
+    /// assert_eq!(count, 0);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`block`]: #method.block
+    /// [`poll`]: #method.poll
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn wait(
+        &mut self,
+        events: &mut Events<T>,
+        timeout: Option<Duration>,
+    ) -> Result<usize, Error> {
+        self.wait_all(events, timeout).map(|_| events.len())
+    }
+
+    /// Same as [`wait`] except that it waits until the deadline passes.
+    ///
+    /// If the deadline already passed, this checks for events without
+    /// blocking. See [`Deadline`].
+    ///
+    /// [`wait`]: #method.wait
+    /// [`Deadline`]: ../struct.Deadline.html
+    pub fn wait_deadline<D>(
+        &mut self,
+        events: &mut Events<T>,
+        deadline: D,
+    ) -> Result<usize, Error>
+    where
+        D: Into<Deadline>,
+    {
+        self.wait(events, Some(deadline.into().remaining()))
+    }
+
+    /// Same as [`wait`] except that the events are stored in the buffer
+    /// owned by the poller and the default timeout is used.
+    ///
+    /// Both are configured via the [`PollerBuilder`]. By default, the
+    /// buffer is unbounded and the timeout is infinite.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{poll::*, Server};
+    /// use std::time::Duration;
+    ///
+    /// let server = Server::new()?;
+    /// let mut poller = PollerBuilder::new()
+    ///     .add(&server, PollId(0), READABLE)
+    ///     .default_timeout(Some(Duration::from_millis(10)))
+    ///     .build()?;
+    ///
+    /// let events = poller.wait_default()?;
+    /// assert!(events.is_empty());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`wait`]: #method.wait
+    /// [`PollerBuilder`]: struct.PollerBuilder.html
+    pub fn wait_default(&mut self) -> Result<&Events<T>, Error> {
+        let mut events = mem::take(&mut self.events);
+        let result = self.wait(&mut events, self.default_timeout);
+        self.events = events;
+
+        result?;
+        Ok(&self.events)
+    }
+
+    /// Returns the timeout used by [`wait_default`].
+    ///
+    /// [`wait_default`]: #method.wait_default
+    pub fn default_timeout(&self) -> Option<Duration> {
+        self.default_timeout
+    }
+
+    /// Returns `true` if the waits transparently retry on [`Interrupted`]
+    /// errors.
+    ///
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    pub fn retry_interrupted(&self) -> bool {
+        self.retry_interrupted
+    }
+
+    /// Specifies whether the waits transparently retry on [`Interrupted`]
+    /// errors, without extending their timeout. A terminated context
+    /// still returns [`CtxTerminated`].
+    ///
+    /// # Default Value
+    /// `false`
+    ///
+    /// [`Interrupted`]: ../enum.ErrorKind.html#variant.Interrupted
+    /// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+    pub fn set_retry_interrupted(&mut self, enabled: bool) {
+        self.retry_interrupted = enabled;
+    }
+
+    /// Same as [`wait`] except that it returns the number of events that
+    /// fired, which exceeds the length of `events` if it was truncated.
+    ///
+    /// [`wait`]: #method.wait
+    pub fn wait_all(
+        &mut self,
+        events: &mut Events<T>,
+        timeout: Option<Duration>,
+    ) -> Result<usize, Error> {
+        match self.wait_ms(events, timeout_ms(timeout)?) {
+            Ok(fired) => Ok(fired),
+            Err(err) => match err.kind() {
+                ErrorKind::WouldBlock => Ok(0),
+                _ => Err(err),
+            },
+        }
+    }
+
+    /// Same as [`wait`] except that the [`Timers`] are also driven by the
+    /// poller.
+    ///
+    /// The wait ends no later than the next timer deadline, after which
+    /// the timers that expired are reported as [`Event`]s alongside the
+    /// socket events.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::poll::*;
+    /// use std::time::Duration;
+    ///
+    /// let mut poller = Poller::new();
+    /// let mut timers = Timers::new();
+    /// let timer = timers.add_once(Duration::from_millis(1), PollId(0))?;
+    ///
+    /// let mut events = Events::new();
+    /// while events.is_empty() {
+    ///     poller.wait_with_timers(&mut events, &mut timers, None)?;
+    /// }
+    ///
+    /// let event = events.iter().next().unwrap();
+    /// assert_eq!(event.timer(), Some(timer));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`wait`]: #method.wait
+    /// [`Timers`]: struct.Timers.html
+    /// [`Event`]: struct.Event.html
+    pub fn wait_with_timers(
+        &mut self,
+        events: &mut Events<T>,
+        timers: &mut Timers<T>,
+        timeout: Option<Duration>,
+    ) -> Result<usize, Error> {
+        let timeout = match (timeout, timers.timeout()) {
+            (Some(timeout), Some(next)) => Some(timeout.min(next)),
+            (None, next) => next,
+            (timeout, None) => timeout,
+        };
+
+        self.wait_all(events, timeout)?;
+        timers.execute(events);
+
+        Ok(events.len())
+    }
+
+    /// Returns the file descriptor that signals the readiness of the
+    /// thread-safe sockets registered to the poller.
+    ///
+    /// This allows the poller to be embedded in a foreign event loop. Once
+    /// the descriptor is readable, the events must be harvested with a
+    /// non-blocking [`wait`] until no more events are returned, since the
+    /// descriptor is edge-triggered.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if no thread-safe socket was added)
+    ///
+    /// [`wait`]: #method.wait
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn fd(&self) -> Result<RawFd, Error> {
+        let mut fd = 0;
+        let rc = unsafe { sys::zmq_poller_fd(self.poller, &mut fd) };
+
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+            let err = match errno {
+                errno::EINVAL => Error::new(ErrorKind::InvalidInput {
+                    msg: "poller has no thread-safe socket",
+                }),
+                errno::EFAULT => panic!("invalid poller"),
+                _ => from_errno(errno),
+            };
+
+            Err(err.with_errno(errno))
+        } else {
+            Ok(fd)
+        }
+    }
+}
+
+impl<T> Poller<T>
+where
+    T: Copy + PartialEq,
+{
+    /// Rearms the one-shot registrations with this token, so that their
+    /// socket is polled again with the events it was registered with.
+    ///
+    /// Rearming an armed registration has no effect.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if no one-shot registration has this token)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn rearm(&mut self, id: T) -> Result<(), Error> {
+        let mut found = false;
+        for entry in self.entries.iter_mut().filter_map(Option::as_mut) {
+            if entry.id != id {
+                continue;
+            }
+            if let (Source::Socket(socket), Some(oneshot)) =
+                (&entry.source, &mut entry.oneshot)
+            {
+                found = true;
+                if !oneshot.armed {
+                    let rc = unsafe {
+                        sys::zmq_poller_modify(
+                            self.poller,
+                            socket.as_mut_ptr(),
+                            oneshot.flags.bits(),
+                        )
+                    };
+                    if rc == -1 {
+                        panic!(msg_from_errno(unsafe { sys::zmq_errno() }));
+                    }
+                    oneshot.armed = true;
+                }
+            }
+        }
+
+        if found {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::InvalidInput {
+                msg: "no oneshot registration with this id",
+            }))
+        }
+    }
+}
+
+// Stops polling the events of a one-shot registration.
+fn disarm(poller: *mut c_void, source: &Source, oneshot: &mut Oneshot) {
+    if let Source::Socket(socket) = source {
+        let rc = unsafe {
+            sys::zmq_poller_modify(
+                poller,
+                socket.as_mut_ptr(),
+                NO_WAKEUP.bits(),
+            )
+        };
+        if rc == -1 {
+            panic!(msg_from_errno(unsafe { sys::zmq_errno() }));
+        }
+        oneshot.armed = false;
+    }
+}
+
+// Converts the timeout into milliseconds, rounding sub-millisecond
+// durations up. `-1` means infinite.
+fn timeout_ms(timeout: Option<Duration>) -> Result<i64, Error> {
+    match timeout {
+        Some(duration) => {
+            let mut ms = duration.as_millis();
+            if duration.subsec_nanos() % 1_000_000 != 0 {
+                ms += 1;
+            }
+            if ms > i64::max_value() as u128 {
+                return Err(Error::new(ErrorKind::InvalidInput {
+                    msg: "ms in timeout must be less than i64::MAX",
+                }));
+            }
+            Ok(ms as i64)
+        }
+        None => Ok(-1),
+    }
+}
+
+thread_local! {
+    // Reused by `wait_single` to avoid creating a poller on each call.
+    static SINGLE: RefCell<(Poller, Events)> =
+        RefCell::new((Poller::new(), Events::with_capacity(1)));
+}
+
+// Waits for a single socket to become ready.
+pub(crate) fn wait_single(
+    socket: &Arc<RawSocket>,
+    flags: Flags,
+    timeout: Option<Duration>,
+) -> Result<bool, Error> {
+    SINGLE.with(|single| {
+        let (poller, events) = &mut *single.borrow_mut();
+        poller.add(socket, PollId(0), flags)?;
+
+        // The socket must be removed whatever the outcome, otherwise the
+        // poller would keep it alive.
+        let result = poller.wait(events, timeout);
+        poller.remove(socket)?;
+
+        result.map(|count| count > 0)
+    })
+}
+
+/// The identifier of a timer returned by [`Timers`].
+///
+/// [`Timers`]: struct.Timers.html
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimerId(c_int);
+
+// The timers that expired during an execution.
+#[derive(Debug, Default)]
+struct Fired {
+    timers: Vec<c_int>,
+}
+
+extern "C" fn on_timer(timer_id: c_int, arg: *mut c_void) {
+    let fired = unsafe { &mut *(arg as *mut Fired) };
+    fired.timers.push(timer_id);
+}
+
+/// A set of timers that are driven by a [`Poller`].
+///
+/// Each timer is associated with a token that is reported by the
+/// [`Event`]s it triggers once it expires. The timers are executed by
+/// [`wait_with_timers`].
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, poll::*, *};
+/// use std::time::Duration;
+///
+/// #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// enum Token {
+///     Heartbeat,
+///     Incoming,
+/// }
+///
+/// let server = Server::new()?;
+///
+/// let mut poller = Poller::new();
+/// poller.add(&server, Token::Incoming, READABLE)?;
+///
+/// let mut timers = Timers::new();
+/// timers.add(Duration::from_millis(10), Token::Heartbeat)?;
+///
+/// let mut events = Events::new();
+/// let mut heartbeats = 0;
+/// while heartbeats < 3 {
+///     poller.wait_with_timers(&mut events, &mut timers, None)?;
+///     for event in &events {
+///         match event.id() {
+///             Token::Heartbeat => heartbeats += 1,
+///             Token::Incoming => {
+///                 server.recv_msg()?;
+///             }
+///         }
+///     }
+/// }
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Poller`]: struct.Poller.html
+/// [`Event`]: struct.Event.html
+/// [`wait_with_timers`]: struct.Poller.html#method.wait_with_timers
+#[derive(Debug)]
+pub struct Timers<T = PollId> {
+    timers: *mut c_void,
+    // The tokens and whether the timers are one-shot.
+    entries: HashMap<c_int, (T, bool)>,
+    // Boxed so that its address can be handed to the timer callback.
+    fired: Box<Fired>,
+}
+
+impl<T> Timers<T>
+where
+    T: Copy,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a periodic timer that expires every `interval`.
+    ///
+    /// The interval is rounded up to the millisecond.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the interval overflows)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add(&mut self, interval: Duration, id: T) -> Result<TimerId, Error> {
+        self.add_timer(interval, id, false)
+    }
+
+    /// Add a one-shot timer that expires once after `delay`.
+    ///
+    /// The timer is automatically cancelled once it expired.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the delay overflows)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn add_once(
+        &mut self,
+        delay: Duration,
+        id: T,
+    ) -> Result<TimerId, Error> {
+        self.add_timer(delay, id, true)
+    }
+
+    fn add_timer(
+        &mut self,
+        interval: Duration,
+        id: T,
+        once: bool,
+    ) -> Result<TimerId, Error> {
+        let interval = timeout_ms(Some(interval))? as usize;
+        let arg = &mut *self.fired as *mut Fired as *mut c_void;
+
+        let rc = unsafe {
+            sys::zmq_timers_add(self.timers, interval, Some(on_timer), arg)
+        };
+
+        if rc == -1 {
+            panic!(msg_from_errno(unsafe { sys::zmq_errno() }));
+        }
+
+        self.entries.insert(rc, (id, once));
+        Ok(TimerId(rc))
+    }
+
+    /// Change the interval of a timer.
+    ///
+    /// The timer is also reset.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the timer does not exist)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn set_interval(
+        &mut self,
+        timer: TimerId,
+        interval: Duration,
+    ) -> Result<(), Error> {
+        let interval = timeout_ms(Some(interval))? as usize;
+        let rc = unsafe {
+            sys::zmq_timers_set_interval(self.timers, timer.0, interval)
+        };
+
+        self.check(rc)
+    }
+
+    /// Reset a timer so that it expires after a whole interval.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the timer does not exist)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn reset(&mut self, timer: TimerId) -> Result<(), Error> {
+        let rc = unsafe { sys::zmq_timers_reset(self.timers, timer.0) };
+
+        self.check(rc)
+    }
+
+    /// Cancel a timer so that it never expires again.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the timer does not exist)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn cancel(&mut self, timer: TimerId) -> Result<(), Error> {
+        let rc = unsafe { sys::zmq_timers_cancel(self.timers, timer.0) };
+
+        self.check(rc)?;
+        self.entries.remove(&timer.0);
+        Ok(())
+    }
+
+    /// Returns the duration until the next timer expires, or `None` if
+    /// there are no timers.
+    pub fn timeout(&self) -> Option<Duration> {
+        let rc = unsafe { sys::zmq_timers_timeout(self.timers) };
+
+        if rc < 0 {
+            None
+        } else {
+            Some(Duration::from_millis(rc as u64))
+        }
+    }
+
+    fn check(&self, rc: c_int) -> Result<(), Error> {
+        if rc == -1 {
+            let errno = unsafe { sys::zmq_errno() };
+            let err = {
+                match errno {
+                    errno::EINVAL => Error::new(ErrorKind::InvalidInput {
+                        msg: "timer does not exist",
+                    }),
+                    _ => from_errno(errno),
+                }
+            };
+
+            Err(err.with_errno(errno))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Executes the expired timers and stores their events.
+    fn execute(&mut self, events: &mut Events<T>) {
+        let rc = unsafe { sys::zmq_timers_execute(self.timers) };
+        if rc == -1 {
+            panic!(msg_from_errno(unsafe { sys::zmq_errno() }));
+        }
+
+        let mut fired = mem::take(&mut self.fired.timers);
+        for timer in fired.drain(..) {
+            let (id, once) = match self.entries.get(&timer) {
+                Some(entry) => *entry,
+                // The timer was cancelled.
+                None => continue,
+            };
+
+            events.push(Event {
+                id,
+                flags: NO_WAKEUP,
+                fd: None,
+                timer: Some(TimerId(timer)),
+            });
+
+            if once {
+                self.cancel(TimerId(timer)).unwrap();
+            }
+        }
+        // Reuse the allocation.
+        self.fired.timers = fired;
+    }
+}
+
+impl<T> Default for Timers<T> {
+    fn default() -> Self {
+        let timers = unsafe { sys::zmq_timers_new() };
+
+        if timers.is_null() {
+            panic!(msg_from_errno(unsafe { sys::zmq_errno() }));
+        }
+
+        Self {
+            timers,
+            entries: HashMap::new(),
+            fired: Box::default(),
+        }
+    }
+}
+
+impl<T> Drop for Timers<T> {
+    fn drop(&mut self) {
+        let rc = unsafe { sys::zmq_timers_destroy(&mut self.timers) };
+
+        if rc != 0 {
+            panic!(msg_from_errno(unsafe { sys::zmq_errno() }));
+        }
+    }
+}
+
+impl<T> Default for Poller<T> {
+    fn default() -> Self {
+        let poller = unsafe { sys::zmq_poller_new() };
+
+        if poller.is_null() {
+            panic!(msg_from_errno(unsafe { sys::zmq_errno() }));
+        }
+
+        Self {
+            poller,
+            entries: Vec::new(),
+            raw_events: Vec::new(),
+            events: Events::default(),
+            default_timeout: None,
+            retry_interrupted: false,
+        }
+    }
+}
+
+impl<T> Drop for Poller<T> {
+    fn drop(&mut self) {
+        let rc = unsafe { sys::zmq_poller_destroy(&mut self.poller) };
+
+        if rc != 0 {
+            let errno = unsafe { sys::zmq_errno() };
+
+            match errno {
+                errno::EFAULT => panic!("invalid poller"),
+                _ => panic!(msg_from_errno(errno)),
+            }
+        }
+    }
+}
+
+/// A builder for a [`Poller`].
+///
+/// Accumulates the sockets to register so that they are all added at once
+/// when the poller is built.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{poll::*, prelude::*, *};
+///
+/// let addr = InprocAddr::new_unique();
+/// let server = ServerBuilder::new().bind(&addr).build()?;
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+///
+/// let mut poller = PollerBuilder::new()
+///     .add(&server, PollId(0), READABLE)
+///     .add(&client, PollId(1), READABLE)
+///     .event_limit(2)
+///     .build()?;
+///
+/// client.send("")?;
+/// let events = poller.wait_default()?;
+/// assert_eq!(events.iter().next().unwrap().id(), PollId(0));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Poller`]: struct.Poller.html
+#[derive(Debug, Clone)]
+pub struct PollerBuilder<T = PollId> {
+    entries: Vec<(Arc<RawSocket>, T, Flags)>,
+    event_limit: Option<usize>,
+    default_timeout: Option<Duration>,
+    retry_interrupted: bool,
+}
+
+impl<T> PollerBuilder<T>
+where
+    T: Copy,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the socket with the given id and flags.
+    ///
+    /// See [`Poller::add`].
+    ///
+    /// [`Poller::add`]: struct.Poller.html#method.add
+    pub fn add<S>(&mut self, socket: &S, id: T, flags: Flags) -> &mut Self
+    where
+        S: GetRawSocket,
+    {
+        self.entries
+            .push((Arc::clone(socket.raw_socket()), id, flags));
+        self
+    }
+
+    /// Bounds the events buffer used by [`wait_default`].
+    ///
+    /// See [`Events::with_limit`].
+    ///
+    /// # Default Value
+    /// Unbounded.
+    ///
+    /// [`wait_default`]: struct.Poller.html#method.wait_default
+    /// [`Events::with_limit`]: struct.Events.html#method.with_limit
+    pub fn event_limit(&mut self, limit: usize) -> &mut Self {
+        self.event_limit = Some(limit);
+        self
+    }
+
+    /// Sets the timeout used by [`wait_default`].
+    ///
+    /// # Default Value
+    /// `None` (infinite).
+    ///
+    /// [`wait_default`]: struct.Poller.html#method.wait_default
+    pub fn default_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    /// See [`Poller::set_retry_interrupted`].
+    ///
+    /// [`Poller::set_retry_interrupted`]: struct.Poller.html#method.set_retry_interrupted
+    pub fn retry_interrupted(&mut self, enabled: bool) -> &mut Self {
+        self.retry_interrupted = enabled;
+        self
+    }
+
+    /// Builds the poller, registering all the sockets.
+    ///
+    /// If any socket cannot be added, no poller is returned and the
+    /// sockets that were already added are released.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if a socket was added twice)
+    ///
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn build(&self) -> Result<Poller<T>, Error> {
+        let mut poller = Poller::new();
+
+        for (socket, id, flags) in &self.entries {
+            poller.add(socket, *id, *flags)?;
+        }
+
+        if let Some(limit) = self.event_limit {
+            poller.events = Events::with_limit(limit);
+        }
+        poller.default_timeout = self.default_timeout;
+        poller.retry_interrupted = self.retry_interrupted;
+
+        Ok(poller)
+    }
+}
+
+impl<T> Default for PollerBuilder<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            event_limit: None,
+            default_timeout: None,
+            retry_interrupted: false,
+        }
+    }
+}
+
+/// A [`Poller`] that can be registered to a `mio::Poll`.
+///
+/// This requires the `mio` feature.
+///
+/// The source is readable whenever the poller might have pending events,
+/// which are then harvested with a non-blocking [`wait`]. At least one
+/// thread-safe socket must be added to the poller before it is registered.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{poll::*, *};
+/// use mio::{Events as MioEvents, Interest, Poll, Token};
+/// use std::time::Duration;
+///
+/// let addr = InprocAddr::new_unique();
+/// let server = ServerBuilder::new().bind(&addr).build()?;
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+///
+/// let mut poller = Poller::new();
+/// poller.add(&server, PollId(0), READABLE)?;
+/// let mut source = MioPoller::new(poller);
+///
+/// let mut poll = Poll::new()?;
+/// poll.registry()
+///     .register(&mut source, Token(0), Interest::READABLE)?;
+///
+/// client.send("")?;
+///
+/// let mut mio_events = MioEvents::with_capacity(8);
+/// let mut events = Events::new();
+/// while events.is_empty() {
+///     poll.poll(&mut mio_events, None)?;
+///     source.wait(&mut events, Some(Duration::from_millis(0)))?;
+/// }
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Poller`]: struct.Poller.html
+/// [`wait`]: struct.Poller.html#method.wait
+#[cfg(all(feature = "mio", unix))]
+pub struct MioPoller<T = PollId> {
+    poller: Poller<T>,
+}
+
+#[cfg(all(feature = "mio", unix))]
+impl<T> MioPoller<T> {
+    /// Wraps the poller into a `mio::event::Source`.
+    pub fn new(poller: Poller<T>) -> Self {
+        Self { poller }
+    }
+
+    /// Returns a reference to the underlying poller.
+    pub fn get_ref(&self) -> &Poller<T> {
+        &self.poller
+    }
+
+    /// Returns a mutable reference to the underlying poller.
+    pub fn get_mut(&mut self) -> &mut Poller<T> {
+        &mut self.poller
+    }
+
+    /// Consumes the wrapper, returning the underlying poller.
+    pub fn into_inner(self) -> Poller<T> {
+        self.poller
+    }
+
+    // Maps our errors to the `io::Error` expected by mio.
+    fn fd(&self) -> io::Result<RawFd> {
+        self.poller.fd().map_err(io::Error::from)
+    }
+}
+
+#[cfg(all(feature = "mio", unix))]
+impl<T> Deref for MioPoller<T> {
+    type Target = Poller<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.poller
+    }
+}
+
+#[cfg(all(feature = "mio", unix))]
+impl<T> DerefMut for MioPoller<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.poller
+    }
+}
+
+#[cfg(all(feature = "mio", unix))]
+impl<T> Source for MioPoller<T> {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.fd()?).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.fd()?).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.fd()?).deregister(registry)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, test_util::*, *};
+
+    use std::time::Instant;
+
+    #[test]
+    fn test_flags() {
+        assert_eq!(READABLE.bits(), sys::ZMQ_POLLIN as c_short);
+        assert_eq!(WRITABLE.bits(), sys::ZMQ_POLLOUT as c_short);
+        assert_eq!(ERROR.bits(), sys::ZMQ_POLLERR as c_short);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pipe_hang_up() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (read_fd, write_fd) = (fds[0], fds[1]);
+
+        let mut poller = Poller::new();
+        poller.add_fd(read_fd, PollId(0), READABLE).unwrap();
+
+        let mut events = Events::new();
+        let timeout = Some(Duration::from_millis(10));
+        assert_eq!(poller.wait(&mut events, timeout).unwrap(), 0);
+
+        // Closing the write end hangs up the read end.
+        unsafe { libc::close(write_fd) };
+        assert_eq!(poller.wait(&mut events, timeout).unwrap(), 1);
+        let event = events.iter().next().unwrap();
+        assert_eq!(event.fd(), Some(read_fd));
+        assert!(event.is_error());
+
+        poller.remove_fd(read_fd).unwrap();
+        unsafe { libc::close(read_fd) };
+    }
+
+    #[test]
+    fn test_wait_deadline() {
+        let (client, server) = client_server_pair();
+        let mut poller = Poller::new();
+        poller.add(&server, PollId(0), READABLE).unwrap();
+
+        let mut events = Events::new();
+        let deadline = Deadline::after(Duration::from_millis(50));
+        let start = Instant::now();
+        assert_eq!(poller.wait_deadline(&mut events, deadline).unwrap(), 0);
+        assert!(start.elapsed() >= Duration::from_millis(40));
+
+        // A passed deadline only checks for events.
+        client.send("").unwrap();
+        assert!(server.wait_readable(None).unwrap());
+        assert_eq!(poller.wait_deadline(&mut events, deadline).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_oneshot() {
+        let (client, server) = client_server_pair();
+        let mut poller = Poller::new();
+        poller.add_oneshot(&server, PollId(0), READABLE).unwrap();
+        client.send("").unwrap();
+
+        let mut events = Events::new();
+        let timeout = Some(Duration::from_millis(10));
+        assert_eq!(poller.wait(&mut events, None).unwrap(), 1);
+        // The message is still pending.
+        assert_eq!(poller.wait(&mut events, timeout).unwrap(), 0);
+
+        poller.rearm(PollId(0)).unwrap();
+        assert_eq!(poller.wait(&mut events, None).unwrap(), 1);
+        assert_eq!(poller.wait(&mut events, timeout).unwrap(), 0);
+
+        // Changing the events also rearms the registration.
+        poller.modify(&server, READABLE).unwrap();
+        assert_eq!(poller.wait(&mut events, None).unwrap(), 1);
+
+        let err = poller.rearm(PollId(1)).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+
+        poller.remove(&server).unwrap();
+        let err = poller.rearm(PollId(0)).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+        let err = poller.remove(&server).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_timeout_ms() {
+        assert_eq!(timeout_ms(None).unwrap(), -1);
+        assert_eq!(timeout_ms(Some(Duration::from_secs(0))).unwrap(), 0);
+        assert_eq!(timeout_ms(Some(Duration::from_micros(100))).unwrap(), 1);
+        assert_eq!(timeout_ms(Some(Duration::from_millis(2))).unwrap(), 2);
+        assert_eq!(timeout_ms(Some(Duration::from_micros(2001))).unwrap(), 3);
+        assert_eq!(timeout_ms(Some(Duration::from_nanos(1))).unwrap(), 1);
+
+        let err = timeout_ms(Some(Duration::from_secs(u64::max_value())))
+            .unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_wait_timeout_modes() {
+        let (client, server) = client_server_pair();
+
+        let mut poller = Poller::new();
+        poller.add(&server, PollId(0), READABLE).unwrap();
+        let mut events = Events::new();
+
+        // Non-blocking check.
+        let count = poller
+            .wait(&mut events, Some(Duration::from_secs(0)))
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(events.is_empty());
+
+        // A sub-millisecond timeout still waits for a whole millisecond.
+        let start = Instant::now();
+        let count = poller
+            .wait(&mut events, Some(Duration::from_micros(100)))
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(start.elapsed() >= Duration::from_millis(1));
+
+        // Blocks until an event occurs.
+        client.send("").unwrap();
+        let count = poller.wait(&mut events, None).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(events.len(), 1);
+
+        // The event is level-triggered.
+        let count = poller
+            .wait(&mut events, Some(Duration::from_secs(0)))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pipe_fd() {
+        use std::thread;
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (read_fd, write_fd) = (fds[0], fds[1]);
+
+        let server = Server::new().unwrap();
+
+        let mut poller = Poller::new();
+        poller.add(&server, PollId(0), READABLE).unwrap();
+        poller.add_fd(read_fd, PollId(1), READABLE).unwrap();
+
+        let err = poller.add_fd(read_fd, PollId(2), READABLE).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            let byte = [1u8];
+            let rc = unsafe {
+                libc::write(write_fd, byte.as_ptr() as *const libc::c_void, 1)
+            };
+            assert_eq!(rc, 1);
+        });
+
+        let mut events = Events::new();
+        poller.block(&mut events, None).unwrap();
+        handle.join().unwrap();
+
+        let fired: Vec<Event> = events.iter().collect();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].id(), PollId(1));
+        assert_eq!(fired[0].fd(), Some(read_fd));
+        assert!(fired[0].flags().contains(READABLE));
+
+        poller.modify_fd(read_fd, NO_WAKEUP).unwrap();
+        let err = poller.poll(&mut events).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        poller.remove_fd(read_fd).unwrap();
+        let err = poller.remove_fd(read_fd).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+
+        unsafe {
+            libc::close(read_fd);
+            libc::close(write_fd);
+        }
+    }
+
+    #[test]
+    fn test_independent_flags() {
+        let (client, server) = client_server_pair();
+        // Has no peer so it is not writable.
+        let idle = Client::new().unwrap();
+        // Is always writable.
+        let writer = Server::new().unwrap();
+
+        let mut poller = Poller::new();
+        poller.add(&server, PollId(0), READABLE).unwrap();
+        poller.add(&idle, PollId(1), READABLE | WRITABLE).unwrap();
+        poller.add(&writer, PollId(2), WRITABLE).unwrap();
+
+        client.send("").unwrap();
+
+        // The writer fires immediately, so wait until the message
+        // is also received.
+        let mut events = Events::new();
+        while poller.wait_all(&mut events, None).unwrap() < 2 {}
+
+        assert_eq!(events.len(), 2);
+        assert!(!events.is_truncated());
+        for event in &events {
+            match event.id() {
+                PollId(0) => {
+                    assert!(event.is_readable());
+                    assert!(!event.is_writable());
+                }
+                PollId(2) => {
+                    assert!(!event.is_readable());
+                    assert!(event.is_writable());
+                }
+                _ => panic!("unexpected event"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_events_truncation() {
+        let sockets: Vec<Server> =
+            (0..3).map(|_| Server::new().unwrap()).collect();
+
+        let mut poller = Poller::new();
+        for (i, socket) in sockets.iter().enumerate() {
+            poller.add(socket, PollId(i), WRITABLE).unwrap();
+        }
+
+        let mut events = Events::with_limit(2);
+        assert_eq!(events.limit(), Some(2));
+
+        let fired = poller.wait_all(&mut events, None).unwrap();
+        assert_eq!(fired, 3);
+        assert_eq!(events.len(), 2);
+        assert!(events.is_truncated());
+
+        // The buffer is reused and the flag reset.
+        poller.remove(&sockets[0]).unwrap();
+        let fired = poller.wait_all(&mut events, None).unwrap();
+        assert_eq!(fired, 2);
+        assert_eq!(events.len(), 2);
+        assert!(!events.is_truncated());
+
+        // A capacity only preallocates the buffer.
+        let mut events = Events::with_capacity(1);
+        assert_eq!(events.limit(), None);
+        assert_eq!(poller.wait(&mut events, None).unwrap(), 2);
+        assert_eq!(events.len(), 2);
+        assert!(!events.is_truncated());
+    }
+
+    #[test]
+    fn test_waker_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync + Clone>() {}
+        assert_send_sync::<Waker>();
+    }
+
+    #[test]
+    fn test_waker_not_lost() {
+        let mut poller = Poller::new();
+        let waker = poller.add_waker(PollId(0)).unwrap();
+
+        let mut events = Events::new();
+        let count = poller
+            .wait(&mut events, Some(Duration::from_secs(0)))
+            .unwrap();
+        assert_eq!(count, 0);
+
+        // Nobody is waiting, the next wait must return immediately.
+        waker.wake().unwrap();
+        let count = poller
+            .wait(&mut events, Some(Duration::from_secs(10)))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(events.iter().next().unwrap().id(), PollId(0));
+
+        // The wakeup was consumed.
+        let count = poller
+            .wait(&mut events, Some(Duration::from_secs(0)))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_waker_coalesce() {
+        let mut poller = Poller::new();
+        let waker = poller.add_waker(PollId(0)).unwrap();
+        let clone = waker.clone();
+
+        for _ in 0..5 {
+            waker.wake().unwrap();
+            clone.wake().unwrap();
+        }
+
+        let mut events = Events::new();
+        assert_eq!(poller.wait(&mut events, None).unwrap(), 1);
+        let count = poller
+            .wait(&mut events, Some(Duration::from_secs(0)))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_waker_blocked_wait() {
+        use std::thread;
+
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+
+        let mut poller = Poller::new();
+        poller.add(&server, PollId(0), READABLE).unwrap();
+        let waker = poller.add_waker(PollId(1)).unwrap();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            waker.wake().unwrap();
+        });
+
+        let mut events = Events::new();
+        poller.wait(&mut events, None).unwrap();
+        handle.join().unwrap();
+
+        let ids: Vec<PollId> = events.iter().map(|e| e.id()).collect();
+        assert_eq!(ids, vec![PollId(1)]);
+    }
+
+    #[test]
+    fn test_periodic_timer() {
+        use std::{
+            sync::{
+                atomic::{AtomicBool, Ordering},
+                Arc,
+            },
+            thread,
+        };
+
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        enum Kind {
+            Tick,
+            Incoming,
+        }
+
+        let (client, server) = client_server_pair();
+
+        let mut poller = Poller::new();
+        poller.add(&server, Kind::Incoming, READABLE).unwrap();
+
+        let mut timers = Timers::new();
+        let period = Duration::from_millis(50);
+        timers.add(period, Kind::Tick).unwrap();
+
+        // Generate socket traffic during the whole test.
+        let done = Arc::new(AtomicBool::new(false));
+        let sender = {
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                while !done.load(Ordering::SeqCst) {
+                    client.send("").unwrap();
+                    thread::sleep(Duration::from_millis(5));
+                }
+            })
+        };
+
+        let n = 10;
+        let start = Instant::now();
+        let mut ticks = 0;
+        let mut received = 0;
+        let mut events = Events::new();
+
+        while start.elapsed() < period * n {
+            let remaining = (period * n)
+                .checked_sub(start.elapsed())
+                .unwrap_or_default();
+            poller
+                .wait_with_timers(&mut events, &mut timers, Some(remaining))
+                .unwrap();
+
+            for event in &events {
+                match event.id() {
+                    Kind::Tick => {
+                        assert!(event.timer().is_some());
+                        ticks += 1;
+                    }
+                    Kind::Incoming => {
+                        assert!(event.timer().is_none());
+                        server.recv_msg().unwrap();
+                        received += 1;
+                    }
+                }
+            }
+        }
+
+        done.store(true, Ordering::SeqCst);
+        sender.join().unwrap();
+
+        assert!(ticks >= n - 2 && ticks <= n, "ticks: {}", ticks);
+        assert!(received > 0);
+    }
+
+    #[test]
+    fn test_cancel_timer() {
+        let mut poller: Poller = Poller::new();
+        let mut timers = Timers::new();
+        let timer = timers.add(Duration::from_millis(5), PollId(0)).unwrap();
+
+        let mut events = Events::new();
+        while events.is_empty() {
+            poller
+                .wait_with_timers(&mut events, &mut timers, None)
+                .unwrap();
+        }
+        assert_eq!(events.iter().next().unwrap().timer(), Some(timer));
+
+        timers.cancel(timer).unwrap();
+        assert_eq!(timers.timeout(), None);
+
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(50) {
+            poller
+                .wait_with_timers(
+                    &mut events,
+                    &mut timers,
+                    Some(Duration::from_millis(10)),
+                )
+                .unwrap();
+            assert!(events.is_empty());
+        }
+
+        let err = timers.cancel(timer).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_one_shot_timer() {
+        let mut poller: Poller = Poller::new();
+        let mut timers = Timers::new();
+        let timer = timers
+            .add_once(Duration::from_millis(5), PollId(0))
+            .unwrap();
+
+        let mut events = Events::new();
+        let mut fired = 0;
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(50) {
+            poller
+                .wait_with_timers(
+                    &mut events,
+                    &mut timers,
+                    Some(Duration::from_millis(10)),
+                )
+                .unwrap();
+            fired += events.len();
+        }
+        assert_eq!(fired, 1);
+
+        // The timer was automatically cancelled.
+        let err = timers.reset(timer).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    enum Token {
+        Alpha,
+        Beta,
+        Gamma,
+    }
+
+    #[test]
+    fn test_enum_tokens() {
+        let tokens = [Token::Alpha, Token::Beta, Token::Gamma];
+        let mut poller = Poller::new();
+        let mut servers = vec![];
+        let mut clients = vec![];
+
+        for token in tokens.iter() {
+            let addr = InprocAddr::new_unique();
+            let server = ServerBuilder::new().bind(&addr).build().unwrap();
+            let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+            poller.add(&server, *token, READABLE).unwrap();
+            client.send(format!("{:?}", token)).unwrap();
+
+            servers.push(server);
+            clients.push(client);
+        }
+
+        let mut events = Events::new();
+        let mut received = vec![];
+        while received.len() < tokens.len() {
+            poller.block(&mut events, None).unwrap();
+            for event in &events {
+                let server = match event.id() {
+                    Token::Alpha => &servers[0],
+                    Token::Beta => &servers[1],
+                    Token::Gamma => &servers[2],
+                };
+                let msg = server.recv_msg().unwrap();
+                assert_eq!(msg.to_str().unwrap(), format!("{:?}", event.id()));
+                received.push(event.id());
+            }
+        }
+    }
+
+    #[test]
+    fn test_token_slot_reuse() {
+        let first = Server::new().unwrap();
+        let second = Server::new().unwrap();
+        let third = Server::new().unwrap();
+
+        let mut poller = Poller::new();
+        poller.add(&first, Token::Alpha, WRITABLE).unwrap();
+        poller.add(&second, Token::Beta, NO_WAKEUP).unwrap();
+        poller.remove(&first).unwrap();
+        // The freed slot is reused without affecting the other tokens.
+        poller.add(&third, Token::Gamma, NO_WAKEUP).unwrap();
+        poller.modify(&second, WRITABLE).unwrap();
+
+        let mut events = Events::new();
+        poller.poll(&mut events).unwrap();
+
+        let ids: Vec<Token> = events.iter().map(|e| e.id()).collect();
+        assert_eq!(ids, vec![Token::Beta]);
+    }
+
+    #[test]
+    fn test_add_server_mid_loop() {
+        let first_addr = InprocAddr::new_unique();
+        let second_addr = InprocAddr::new_unique();
+
+        let first = ServerBuilder::new().bind(&first_addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&first_addr).build().unwrap();
+
+        let mut poller = Poller::new();
+        poller.add(&first, PollId(0), READABLE).unwrap();
+
+        client.send("first").unwrap();
+
+        let mut events = Events::new();
+        poller.block(&mut events, None).unwrap();
+        let ids: Vec<PollId> = events.iter().map(|e| e.id()).collect();
+        assert_eq!(ids, vec![PollId(0)]);
+        first.recv_msg().unwrap();
+
+        // A supervisor gains a new socket between two waits.
+        let second = ServerBuilder::new().bind(&second_addr).build().unwrap();
+        poller.add(&second, PollId(1), READABLE).unwrap();
+
+        let client =
+            ClientBuilder::new().connect(&second_addr).build().unwrap();
+        client.send("second").unwrap();
+
+        poller.block(&mut events, None).unwrap();
+        let ids: Vec<PollId> = events.iter().map(|e| e.id()).collect();
+        assert_eq!(ids, vec![PollId(1)]);
+        assert_eq!(second.recv_msg().unwrap().to_str().unwrap(), "second");
+    }
+
+    #[test]
+    fn test_modify_interest() {
+        let (client, server) = client_server_pair();
+
+        let mut poller = Poller::new();
+        poller.add(&server, PollId(0), READABLE).unwrap();
+
+        let mut events = Events::new();
+        let err = poller.poll(&mut events).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        // A server is always writable.
+        poller.modify(&server, READABLE | WRITABLE).unwrap();
+        poller.poll(&mut events).unwrap();
+        let flags: Vec<Flags> = events.iter().map(|e| e.flags()).collect();
+        assert_eq!(flags, vec![WRITABLE]);
+
+        client.send("").unwrap();
+        poller.block(&mut events, None).unwrap();
+        let flags = events.iter().next().unwrap().flags();
+        assert!(flags.contains(READABLE));
+    }
+
+    #[test]
+    fn test_remove_absent() {
+        let server = Server::new().unwrap();
+        let mut poller: Poller = Poller::new();
+
+        let err = poller.remove(&server).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+        let err = poller.modify(&server, READABLE).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_drop_registered_socket() {
+        let (client, server) = client_server_pair();
+
+        let mut poller = Poller::new();
+        poller.add(&server, PollId(0), READABLE).unwrap();
+
+        let weak = Arc::downgrade(server.raw_socket());
+        drop(server);
+        // The poller keeps the socket alive.
+        assert!(weak.upgrade().is_some());
+
+        client.send("").unwrap();
+        let mut events = Events::new();
+        poller.block(&mut events, None).unwrap();
+        assert_eq!(events.len(), 1);
+
+        drop(poller);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_builder_req_rep() {
+        let (client, server) = client_server_pair();
+
+        let mut poller = PollerBuilder::new()
+            .add(&server, PollId(0), READABLE)
+            .add(&client, PollId(1), READABLE)
+            .event_limit(2)
+            .default_timeout(Some(Duration::from_secs(1)))
+            .build()
+            .unwrap();
+
+        client.send("ping").unwrap();
+
+        let mut count = 0;
+        while count < 10 {
+            let events = poller.wait_default().unwrap();
+            assert!(!events.is_empty());
+            assert_eq!(events.limit(), Some(2));
+
+            for event in events {
+                match event.id() {
+                    PollId(0) => {
+                        let msg = server.recv_msg().unwrap();
+                        server.send(msg).unwrap();
+                    }
+                    PollId(1) => {
+                        let msg = client.recv_msg().unwrap();
+                        client.send(msg).unwrap();
+                        count += 1;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_builder_default_timeout() {
+        let server = Server::new().unwrap();
+        let mut poller = PollerBuilder::new()
+            .add(&server, PollId(0), READABLE)
+            .default_timeout(Some(Duration::from_millis(10)))
+            .build()
+            .unwrap();
+
+        assert_eq!(poller.default_timeout(), Some(Duration::from_millis(10)));
+        assert!(poller.wait_default().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_builder_retry_interrupted() {
+        let server = Server::new().unwrap();
+        let mut poller = PollerBuilder::new()
+            .add(&server, PollId(0), READABLE)
+            .retry_interrupted(true)
+            .build()
+            .unwrap();
+        assert!(poller.retry_interrupted());
+
+        // The timeout is still honored.
+        let mut events = Events::new();
+        let timeout = Some(Duration::from_millis(10));
+        assert_eq!(poller.wait(&mut events, timeout).unwrap(), 0);
+        let err = poller.poll(&mut events).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        poller.set_retry_interrupted(false);
+        assert!(!poller.retry_interrupted());
+    }
+
+    #[test]
+    fn test_builder_atomic() {
+        let server = Server::new().unwrap();
+        let weak = Arc::downgrade(server.raw_socket());
+
+        let err = PollerBuilder::new()
+            .add(&server, PollId(0), READABLE)
+            .add(&server, PollId(1), READABLE)
+            .build()
+            .unwrap_err();
+
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+
+        // The partially built poller released the socket.
+        drop(server);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_wait_readable() {
+        let (client, server) = client_server_pair();
+
+        let timeout = Some(Duration::from_millis(10));
+        for _ in 0..100 {
+            assert!(!server.wait_readable(timeout).unwrap());
+        }
+
+        client.send("").unwrap();
+        assert!(server.wait_readable(timeout).unwrap());
+        // Waiting does not consume the message.
+        assert!(server.wait_readable(None).unwrap());
+        server.try_recv_msg().unwrap();
+        assert!(client.wait_writable(timeout).unwrap());
+    }
+
+    #[test]
+    fn test_wait_readable_with_poller() {
+        let (client, server) = client_server_pair();
+
+        let mut poller = Poller::new();
+        poller.add(&server, PollId(0), READABLE).unwrap();
+
+        client.send("").unwrap();
+        assert!(server.wait_readable(None).unwrap());
+
+        // The caller's poller still holds the socket.
+        let mut events = Events::new();
+        poller.wait(&mut events, None).unwrap();
+        assert_eq!(events.iter().next().unwrap().id(), PollId(0));
+
+        let err = poller.add(&server, PollId(1), READABLE).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[cfg(all(feature = "mio", unix))]
+    #[test]
+    fn test_mio_event_loop() {
+        use mio::net::TcpListener;
+        use std::{io::Write, net::TcpStream};
+
+        const ZMQ: Token = Token(0);
+        const LISTENER: Token = Token(1);
+
+        let (client, server) = client_server_pair();
+
+        let mut poller = Poller::new();
+        poller.add(&server, PollId(0), READABLE).unwrap();
+        let mut source = MioPoller::new(poller);
+
+        let mut listener =
+            TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let tcp_addr = listener.local_addr().unwrap();
+
+        let mut poll = mio::Poll::new().unwrap();
+        poll.registry()
+            .register(&mut source, ZMQ, Interest::READABLE)
+            .unwrap();
+        poll.registry()
+            .register(&mut listener, LISTENER, Interest::READABLE)
+            .unwrap();
+
+        client.send("zmq").unwrap();
+        let mut stream = TcpStream::connect(tcp_addr).unwrap();
+        stream.write_all(b"tcp").unwrap();
+
+        let mut mio_events = mio::Events::with_capacity(8);
+        let mut events = Events::new();
+        let (mut got_msg, mut got_conn) = (false, false);
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        while !(got_msg && got_conn) {
+            assert!(Instant::now() < deadline);
+            poll.poll(&mut mio_events, Some(Duration::from_millis(100)))
+                .unwrap();
+
+            for mio_event in &mio_events {
+                match mio_event.token() {
+                    ZMQ => loop {
+                        source
+                            .wait(&mut events, Some(Duration::from_millis(0)))
+                            .unwrap();
+                        if events.is_empty() {
+                            break;
+                        }
+                        for event in &events {
+                            assert_eq!(event.id(), PollId(0));
+                            let msg = server.try_recv_msg().unwrap();
+                            assert_eq!(msg.to_str().unwrap(), "zmq");
+                            got_msg = true;
+                        }
+                    },
+                    LISTENER => {
+                        listener.accept().unwrap();
+                        got_conn = true;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+
+        poll.registry().deregister(&mut source).unwrap();
+    }
+}
@@ -0,0 +1,856 @@
+//! Forwarding of messages between sockets.
+//!
+//! `zmq_proxy` only supports the legacy sockets, so this module provides a
+//! pump built on top of the thread-safe sockets instead.
+
+use crate::{
+    error::*, poll::*, prelude::*, Client, ClientBuilder, InprocAddr, Msg,
+    Server,
+};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fmt, panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+// The maximum number of messages forwarded before the control channel
+// is checked again.
+const BATCH_SIZE: usize = 256;
+
+// The interval at which a `Control` checks whether the forwarder stopped
+// while waiting for a reply.
+const REPLY_INTERVAL: Duration = Duration::from_millis(10);
+
+type MapFn = Box<dyn FnMut(Msg) -> Option<Msg> + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Command {
+    Pause,
+    Resume,
+    Stop,
+    Stats,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Reply {
+    Done,
+    Stats(ProxyStats),
+    Stopped,
+}
+
+/// The counters of a [`Forwarder`], as returned by [`Control::stats`].
+///
+/// [`Forwarder`]: struct.Forwarder.html
+/// [`Control::stats`]: struct.Control.html#method.stats
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+pub struct ProxyStats {
+    fwd_msgs: u64,
+    fwd_bytes: u64,
+    dropped_msgs: u64,
+}
+
+impl ProxyStats {
+    /// Returns the number of messages sent to the backend.
+    pub fn fwd_msgs(&self) -> u64 {
+        self.fwd_msgs
+    }
+
+    /// Returns the number of bytes sent to the backend.
+    pub fn fwd_bytes(&self) -> u64 {
+        self.fwd_bytes
+    }
+
+    /// Returns the number of messages dropped by the `map` callback or
+    /// because the backend was in mute state when the forwarder stopped.
+    pub fn dropped_msgs(&self) -> u64 {
+        self.dropped_msgs
+    }
+}
+
+/// Forwards the messages received by a frontend socket to a backend socket.
+///
+/// The messages are forwarded as is, which means that their group is
+/// preserved, so that a `Dish` to `Radio` forwarder relays each message to
+/// the group it was received from. A [`map`] callback can be used to modify
+/// or drop the messages, for instance to assign a routing id before sending
+/// to a `Server`.
+///
+/// A forwarder is unidirectional. To forward in both directions, spawn a
+/// second forwarder with clones of the sockets swapped.
+///
+/// The forwarder never blocks on the backend. While the backend is in mute
+/// state, the forwarder stops reading from the frontend but still handles
+/// its commands.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, proxy::Forwarder, *};
+///
+/// let front_addr = InprocAddr::new_unique();
+/// let back_addr = InprocAddr::new_unique();
+///
+/// let producer = ScatterBuilder::new().bind(&front_addr).build()?;
+/// let frontend = GatherBuilder::new().connect(&front_addr).build()?;
+///
+/// let backend = ScatterBuilder::new().bind(&back_addr).build()?;
+/// let consumer = GatherBuilder::new().connect(&back_addr).build()?;
+///
+/// let handle = Forwarder::new(frontend, backend).spawn()?;
+///
+/// producer.send("job")?;
+/// let msg = consumer.recv_msg()?;
+/// assert_eq!(msg.to_str()?, "job");
+///
+/// handle.stop()?;
+/// handle.join()?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`map`]: #method.map
+pub struct Forwarder<F, B> {
+    frontend: F,
+    backend: B,
+    map: Option<MapFn>,
+}
+
+impl<F, B> Forwarder<F, B>
+where
+    F: RecvMsg + Send + 'static,
+    B: SendMsg + Send + 'static,
+{
+    /// Creates a `Forwarder` from the `frontend` socket to the `backend`
+    /// socket.
+    pub fn new(frontend: F, backend: B) -> Self {
+        Self {
+            frontend,
+            backend,
+            map: None,
+        }
+    }
+
+    /// Sets a callback applied to every message before it is sent to the
+    /// backend.
+    ///
+    /// The message is dropped if the callback returns `None`. The callback
+    /// is called from the thread of the forwarder.
+    pub fn map<M>(&mut self, map: M) -> &mut Self
+    where
+        M: FnMut(Msg) -> Option<Msg> + Send + 'static,
+    {
+        self.map = Some(Box::new(map));
+        self
+    }
+
+    /// Spawns a thread that forwards the messages until it is stopped via
+    /// the returned [`ForwarderHandle`] or an error occurs.
+    ///
+    /// The control channel of the forwarder uses the context of the
+    /// frontend socket.
+    ///
+    /// [`ForwarderHandle`]: struct.ForwarderHandle.html
+    pub fn spawn(self) -> Result<ForwarderHandle, Error> {
+        self.spawn_steerable().map(|(handle, _)| handle)
+    }
+
+    /// Same as [`spawn`] except that a [`Control`] is also returned to
+    /// pause, resume and stop the forwarder and to read its counters.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, proxy::Forwarder, *};
+    ///
+    /// let front_addr = InprocAddr::new_unique();
+    /// let back_addr = InprocAddr::new_unique();
+    ///
+    /// let producer = ScatterBuilder::new().bind(&front_addr).build()?;
+    /// let frontend = GatherBuilder::new().connect(&front_addr).build()?;
+    ///
+    /// let backend = ScatterBuilder::new().bind(&back_addr).build()?;
+    /// let consumer = GatherBuilder::new().connect(&back_addr).build()?;
+    ///
+    /// let (handle, control) = Forwarder::new(frontend, backend)
+    ///     .spawn_steerable()?;
+    ///
+    /// producer.send("job")?;
+    /// consumer.recv_msg()?;
+    ///
+    /// let stats = control.stats()?;
+    /// assert_eq!(stats.fwd_msgs(), 1);
+    /// assert_eq!(stats.fwd_bytes(), 3);
+    ///
+    /// control.stop()?;
+    /// handle.join()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`spawn`]: #method.spawn
+    /// [`Control`]: struct.Control.html
+    pub fn spawn_steerable(self) -> Result<(ForwarderHandle, Control), Error> {
+        let ctx = self.frontend.raw_socket().ctx().clone();
+        let addr = InprocAddr::new_unique();
+
+        let control = Server::with_ctx(&ctx)?;
+        control.bind(&addr).map_err(Error::cast)?;
+
+        let handle_client = ClientBuilder::new()
+            .connect(&addr)
+            .with_ctx(&ctx)
+            .map_err(Error::cast)?;
+
+        let control_client = ClientBuilder::new()
+            .connect(&addr)
+            .recv_timeout(REPLY_INTERVAL)
+            .with_ctx(&ctx)
+            .map_err(Error::cast)?;
+
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let pump = Pump {
+            forwarder: self,
+            control,
+            finished: Arc::clone(&finished),
+            stats: ProxyStats::default(),
+            pending: None,
+            paused: false,
+        };
+        let thread = thread::spawn(move || pump.run());
+
+        let handle = ForwarderHandle {
+            client: handle_client,
+            thread: Some(thread),
+        };
+        let control = Control {
+            client: control_client,
+            finished,
+            next_id: Mutex::new(0),
+        };
+
+        Ok((handle, control))
+    }
+}
+
+impl<F, B> fmt::Debug for Forwarder<F, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Forwarder")
+            .field("map", &self.map.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+struct Pump<F, B> {
+    forwarder: Forwarder<F, B>,
+    control: Server,
+    // Set once the pump no longer handles commands.
+    finished: Arc<AtomicBool>,
+    stats: ProxyStats,
+    // The message that the backend could not accept yet.
+    pending: Option<Msg>,
+    paused: bool,
+}
+
+impl<F, B> Pump<F, B>
+where
+    F: RecvMsg,
+    B: SendMsg,
+{
+    fn run(mut self) -> Result<(), Error> {
+        let result = self.pump();
+        self.finished.store(true, Ordering::SeqCst);
+
+        // Answer the pending commands so that no `Control` waits for them.
+        while let Ok(msg) = self.control.try_recv_msg() {
+            let (id, _): (u64, Command) =
+                bincode::deserialize(msg.as_bytes()).unwrap();
+            let _ = self.reply(&msg, id, Reply::Stopped);
+        }
+
+        result
+    }
+
+    fn pump(&mut self) -> Result<(), Error> {
+        let mut poller = Poller::new();
+        poller.set_retry_interrupted(true);
+        poller.add(&self.forwarder.frontend, PollId(0), READABLE)?;
+        poller.add(&self.control, PollId(1), READABLE)?;
+        poller.add(&self.forwarder.backend, PollId(2), NO_WAKEUP)?;
+
+        let mut events = Events::new();
+        let mut frontend_flags = READABLE;
+        let mut backend_flags = NO_WAKEUP;
+
+        loop {
+            poller.block(&mut events, None)?;
+
+            for event in &events {
+                match event.id() {
+                    PollId(0) | PollId(2) => self.forward()?,
+                    PollId(1) => {
+                        let msg = self.control.recv_msg()?;
+                        let (id, command): (u64, Command) =
+                            bincode::deserialize(msg.as_bytes()).unwrap();
+
+                        let reply = match command {
+                            // The messages remain queued in the frontend
+                            // while it is not polled.
+                            Command::Pause => {
+                                self.paused = true;
+                                Reply::Done
+                            }
+                            Command::Resume => {
+                                self.paused = false;
+                                Reply::Done
+                            }
+                            Command::Stop => {
+                                self.flush()?;
+                                self.reply(&msg, id, Reply::Done)?;
+                                return Ok(());
+                            }
+                            Command::Stats => Reply::Stats(self.stats),
+                        };
+
+                        self.reply(&msg, id, reply)?;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+
+            // Stop reading from the frontend until the backend accepts
+            // the pending message.
+            let flags = if self.paused || self.pending.is_some() {
+                NO_WAKEUP
+            } else {
+                READABLE
+            };
+            if flags != frontend_flags {
+                poller.modify(&self.forwarder.frontend, flags)?;
+                frontend_flags = flags;
+            }
+
+            let flags = if self.pending.is_some() {
+                WRITABLE
+            } else {
+                NO_WAKEUP
+            };
+            if flags != backend_flags {
+                poller.modify(&self.forwarder.backend, flags)?;
+                backend_flags = flags;
+            }
+        }
+    }
+
+    fn reply(&self, request: &Msg, id: u64, reply: Reply) -> Result<(), Error> {
+        let routing_id = request.routing_id().unwrap();
+        let ser = bincode::serialize(&(id, reply)).unwrap();
+
+        let mut msg: Msg = ser.into();
+        msg.set_routing_id(routing_id);
+        // The `ForwarderHandle` never reads its replies, so they are
+        // dropped once its queue is full.
+        match self.control.try_send(msg) {
+            Ok(()) => Ok(()),
+            Err(err) => match err.kind() {
+                ErrorKind::WouldBlock | ErrorKind::HostUnreachable => Ok(()),
+                _ => Err(err.cast()),
+            },
+        }
+    }
+
+    // Forwards the pending message then the queued messages, up to a batch.
+    fn forward(&mut self) -> Result<(), Error> {
+        if let Some(msg) = self.pending.take() {
+            if !self.send(msg)? {
+                return Ok(());
+            }
+        }
+
+        if self.paused {
+            return Ok(());
+        }
+
+        for _ in 0..BATCH_SIZE {
+            let forwarder = &mut self.forwarder;
+            let msg = match forwarder.frontend.try_recv_msg() {
+                Ok(msg) => msg,
+                Err(err) => match err.kind() {
+                    ErrorKind::WouldBlock | ErrorKind::Interrupted => {
+                        return Ok(())
+                    }
+                    _ => return Err(err),
+                },
+            };
+
+            let msg = match &mut forwarder.map {
+                Some(map) => map(msg),
+                None => Some(msg),
+            };
+
+            match msg {
+                Some(msg) => {
+                    if !self.send(msg)? {
+                        return Ok(());
+                    }
+                }
+                None => self.stats.dropped_msgs += 1,
+            }
+        }
+
+        Ok(())
+    }
+
+    // Sends the message to the backend without blocking and returns whether
+    // it was sent. Otherwise, the message is kept as pending.
+    fn send(&mut self, msg: Msg) -> Result<bool, Error> {
+        let len = msg.len() as u64;
+
+        match self.forwarder.backend.try_send(msg) {
+            Ok(()) => {
+                self.stats.fwd_msgs += 1;
+                self.stats.fwd_bytes += len;
+                Ok(true)
+            }
+            Err(mut err) => match err.kind() {
+                ErrorKind::WouldBlock | ErrorKind::Interrupted => {
+                    self.pending = err.take_content();
+                    Ok(false)
+                }
+                _ => Err(err.cast()),
+            },
+        }
+    }
+
+    // Makes a last attempt to send the pending message before stopping.
+    fn flush(&mut self) -> Result<(), Error> {
+        if let Some(msg) = self.pending.take() {
+            if !self.send(msg)? {
+                // Drop the message put back by `send`.
+                self.pending = None;
+                self.stats.dropped_msgs += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A handle to a running [`Forwarder`].
+///
+/// Dropping the handle stops the forwarder without waiting for its thread.
+///
+/// [`Forwarder`]: struct.Forwarder.html
+pub struct ForwarderHandle {
+    client: Client,
+    thread: Option<JoinHandle<Result<(), Error>>>,
+}
+
+impl ForwarderHandle {
+    /// Stops the forwarder.
+    ///
+    /// The message being forwarded, if any, is sent to the backend before
+    /// the forwarder stops while the queued messages remain in the
+    /// frontend socket. If the backend is in mute state, that message is
+    /// dropped instead. Use [`join`] to wait for the forwarder to stop.
+    ///
+    /// Does nothing if the forwarder is already stopped.
+    ///
+    /// [`join`]: #method.join
+    pub fn stop(&self) -> Result<(), Error> {
+        // The replies are never read so the request id does not matter.
+        let ser = bincode::serialize(&(0u64, Command::Stop)).unwrap();
+
+        match self.client.try_send(ser) {
+            Ok(()) => Ok(()),
+            Err(err) => match err.kind() {
+                // Either the forwarder already stopped or a command is
+                // already pending.
+                ErrorKind::WouldBlock => Ok(()),
+                _ => Err(err.cast()),
+            },
+        }
+    }
+
+    /// Waits for the forwarder to stop and returns the error that stopped
+    /// it, if any.
+    ///
+    /// If the `map` callback panicked, the panic is propagated.
+    pub fn join(mut self) -> Result<(), Error> {
+        let thread = self.thread.take().unwrap();
+
+        match thread.join() {
+            Ok(result) => result,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+impl fmt::Debug for ForwarderHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ForwarderHandle").finish_non_exhaustive()
+    }
+}
+
+/// A handle to steer a running [`Forwarder`], returned by
+/// [`spawn_steerable`].
+///
+/// The commands are sent to the thread of the forwarder over an `inproc`
+/// channel and each method returns once its command was handled. The
+/// commands are handled in between the forwarded messages, so a `map`
+/// callback that blocks also delays them.
+///
+/// A `Control` can be shared between threads, in which case their commands
+/// are sent one at a time.
+///
+/// # Returned Error Variants
+/// Every method returns [`NotFound`] once the forwarder is stopped.
+///
+/// [`Forwarder`]: struct.Forwarder.html
+/// [`spawn_steerable`]: struct.Forwarder.html#method.spawn_steerable
+/// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+pub struct Control {
+    client: Client,
+    finished: Arc<AtomicBool>,
+    // Held for the whole request so that replies are not mixed up.
+    next_id: Mutex<u64>,
+}
+
+impl Control {
+    /// Pauses the forwarder.
+    ///
+    /// While paused, the incoming messages remain queued in the frontend
+    /// socket, up to its high water mark.
+    pub fn pause(&self) -> Result<(), Error> {
+        self.request(Command::Pause).map(|_| ())
+    }
+
+    /// Resumes a paused forwarder.
+    pub fn resume(&self) -> Result<(), Error> {
+        self.request(Command::Resume).map(|_| ())
+    }
+
+    /// Stops the forwarder, like [`ForwarderHandle::stop`].
+    ///
+    /// [`ForwarderHandle::stop`]: struct.ForwarderHandle.html#method.stop
+    pub fn stop(&self) -> Result<(), Error> {
+        self.request(Command::Stop).map(|_| ())
+    }
+
+    /// Returns the current counters of the forwarder.
+    pub fn stats(&self) -> Result<ProxyStats, Error> {
+        match self.request(Command::Stats)? {
+            Reply::Stats(stats) => Ok(stats),
+            _ => Err(Error::new(ErrorKind::InvalidMessage {
+                msg: "unexpected reply to stats command",
+            })),
+        }
+    }
+
+    fn request(&self, command: Command) -> Result<Reply, Error> {
+        let stopped = || {
+            Error::new(ErrorKind::NotFound {
+                msg: "forwarder is stopped",
+            })
+        };
+
+        let mut next_id = self.next_id.lock().unwrap();
+        let id = *next_id;
+        *next_id = next_id.wrapping_add(1);
+
+        let ser = bincode::serialize(&(id, command)).unwrap();
+        if let Err(err) = self.client.try_send(ser) {
+            return match err.kind() {
+                ErrorKind::WouldBlock => Err(stopped()),
+                _ => Err(err.cast()),
+            };
+        }
+
+        let reply = loop {
+            let msg = match self.client.recv_msg() {
+                Ok(msg) => msg,
+                Err(err) => match err.kind() {
+                    // The command might have been received after the pump
+                    // answered its last commands.
+                    ErrorKind::WouldBlock => {
+                        if !self.finished.load(Ordering::SeqCst) {
+                            continue;
+                        }
+                        self.client.try_recv_msg().map_err(|_| stopped())?
+                    }
+                    ErrorKind::Interrupted => continue,
+                    _ => return Err(err),
+                },
+            };
+
+            // Skip the late replies to the requests that failed.
+            let (reply_id, reply): (u64, Reply) =
+                bincode::deserialize(msg.as_bytes()).unwrap();
+            if reply_id == id {
+                break reply;
+            }
+        };
+
+        match reply {
+            Reply::Stopped => Err(stopped()),
+            reply => Ok(reply),
+        }
+    }
+}
+
+impl fmt::Debug for Control {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Control").finish_non_exhaustive()
+    }
+}
+
+impl Drop for ForwarderHandle {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            let _ = self.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    use std::{convert::TryInto, thread, time::Duration};
+
+    fn pipeline() -> (Scatter, Gather, Scatter, Gather) {
+        let front_addr = InprocAddr::new_unique();
+        let back_addr = InprocAddr::new_unique();
+
+        let producer = ScatterBuilder::new().bind(&front_addr).build().unwrap();
+        let frontend =
+            GatherBuilder::new().connect(&front_addr).build().unwrap();
+
+        let backend = ScatterBuilder::new().bind(&back_addr).build().unwrap();
+        let consumer = GatherBuilder::new()
+            .connect(&back_addr)
+            .recv_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+
+        (producer, frontend, backend, consumer)
+    }
+
+    #[test]
+    fn test_forwarder_delivery() {
+        let (producer, frontend, backend, consumer) = pipeline();
+
+        let mut forwarder = Forwarder::new(frontend, backend);
+        forwarder.map(|msg| match msg.to_str() {
+            Ok("skip") => None,
+            _ => Some(msg),
+        });
+        let handle = forwarder.spawn().unwrap();
+
+        for i in 0..100 {
+            producer.send(i.to_string()).unwrap();
+            producer.send("skip").unwrap();
+        }
+
+        for i in 0..100 {
+            let msg = consumer.recv_msg().unwrap();
+            assert_eq!(msg.to_str().unwrap(), i.to_string());
+        }
+        assert!(consumer.recv_msg().is_err());
+
+        handle.stop().unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_forwarder_stop_is_lossless() {
+        let (producer, frontend, backend, consumer) = pipeline();
+        let queue = frontend.clone();
+
+        let handle = Forwarder::new(frontend, backend).spawn().unwrap();
+
+        // Stay below the high water mark of the backend since the consumer
+        // only receives once the forwarder is stopped.
+        let count = 1000;
+        for _ in 0..count {
+            producer.send("").unwrap();
+        }
+        handle.stop().unwrap();
+        // Stopping twice is harmless.
+        handle.stop().unwrap();
+        handle.join().unwrap();
+
+        // Every message is either forwarded or still queued.
+        let mut received = 0;
+        while consumer.try_recv_msg().is_ok() {
+            received += 1;
+        }
+        while queue.try_recv_msg().is_ok() {
+            received += 1;
+        }
+
+        assert_eq!(received, count);
+    }
+
+    #[test]
+    fn test_forwarder_pause_resume() {
+        let (producer, frontend, backend, consumer) = pipeline();
+
+        let mut forwarder = Forwarder::new(frontend, backend);
+        forwarder.map(|msg| match msg.to_str() {
+            Ok("skip") => None,
+            _ => Some(msg),
+        });
+        let (handle, control) = forwarder.spawn_steerable().unwrap();
+
+        control.pause().unwrap();
+
+        let count = 100;
+        for _ in 0..count {
+            producer.send("data").unwrap();
+        }
+        producer.send("skip").unwrap();
+        assert!(consumer.recv_msg().is_err());
+        assert_eq!(control.stats().unwrap(), ProxyStats::default());
+
+        control.resume().unwrap();
+        for _ in 0..count {
+            let msg = consumer.recv_msg().unwrap();
+            assert_eq!(msg.to_str().unwrap(), "data");
+        }
+
+        // The message dropped by the callback follows the others.
+        let stats = loop {
+            let stats = control.stats().unwrap();
+            if stats.dropped_msgs() == 1 {
+                break stats;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(stats.fwd_msgs(), count);
+        assert_eq!(stats.fwd_bytes(), 4 * count);
+
+        control.stop().unwrap();
+        handle.join().unwrap();
+
+        let err = control.stats().unwrap_err();
+        match err.kind() {
+            ErrorKind::NotFound { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_control_shared() {
+        let (_producer, frontend, backend, _consumer) = pipeline();
+
+        let (handle, control) =
+            Forwarder::new(frontend, backend).spawn_steerable().unwrap();
+        let control = Arc::new(control);
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let control = Arc::clone(&control);
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        control.pause().unwrap();
+                        control.stats().unwrap();
+                        control.resume().unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        control.stop().unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_forwarder_preserves_groups() {
+        let front_addr = InprocAddr::new_unique();
+        let back_addr = InprocAddr::new_unique();
+        let group: &Group = "group".try_into().unwrap();
+
+        let producer = RadioBuilder::new().bind(&front_addr).build().unwrap();
+        let frontend = DishBuilder::new()
+            .connect(&front_addr)
+            .join(group)
+            .build()
+            .unwrap();
+
+        let backend = RadioBuilder::new().bind(&back_addr).build().unwrap();
+        let consumer = DishBuilder::new()
+            .connect(&back_addr)
+            .join(group)
+            .build()
+            .unwrap();
+
+        let handle = Forwarder::new(frontend, backend).spawn().unwrap();
+
+        // The group subscriptions propagate asynchronously.
+        let done = Arc::new(AtomicBool::new(false));
+        let producer = {
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let group: &Group = "group".try_into().unwrap();
+                while !done.load(Ordering::SeqCst) {
+                    let mut msg = Msg::new();
+                    msg.set_group(group);
+                    producer.send(msg).unwrap();
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+
+        let msg = consumer.recv_msg().unwrap();
+        assert_eq!(msg.group().unwrap(), group);
+
+        done.store(true, Ordering::SeqCst);
+        producer.join().unwrap();
+
+        handle.stop().unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_forwarder_mute_backend() {
+        let addr = InprocAddr::new_unique();
+
+        let producer = ScatterBuilder::new().bind(&addr).build().unwrap();
+        let frontend = GatherBuilder::new().connect(&addr).build().unwrap();
+        // The backend has no peer so it is in mute state.
+        let backend = Scatter::new().unwrap();
+
+        let (handle, control) =
+            Forwarder::new(frontend, backend).spawn_steerable().unwrap();
+
+        producer.send("job").unwrap();
+
+        // The forwarder still handles its commands.
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(control.stats().unwrap(), ProxyStats::default());
+
+        control.stop().unwrap();
+        handle.join().unwrap();
+    }
+}
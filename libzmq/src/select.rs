@@ -0,0 +1,274 @@
+/// Waits until a message can be received from one of multiple sockets.
+///
+/// Each socket arm has the form `msg = socket.recv() => { ... }`, where
+/// `socket` is any expression of one of the receiving socket types and `msg`
+/// is bound to the `Result<Msg, Error>` of the non-blocking receive. Each
+/// `socket` expression is evaluated once. If multiple sockets are ready, the
+/// first arm in order fires.
+///
+/// The socket arms can be followed by either:
+/// * a `timeout(duration) => { ... }` arm, that fires if no socket was
+///     ready before the `duration` elapsed.
+/// * a `default => { ... }` arm, that fires if no socket was ready
+///     immediately, making the whole select non-blocking.
+///
+/// Without any of these arms the select blocks until a socket is ready. The
+/// wait is done via a [`Poller`] so that no busy loop occurs.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, *};
+/// use std::{convert::TryInto, time::Duration};
+///
+/// let addr = InprocAddr::new_unique();
+/// let server = ServerBuilder::new().bind(&addr).build()?;
+/// let client = ClientBuilder::new().connect(&addr).build()?;
+///
+/// let group: &Group = "group".try_into()?;
+/// let dish = DishBuilder::new().join(group).build()?;
+///
+/// client.send("ping")?;
+///
+/// let received = libzmq::select! {
+///     msg = server.recv() => { msg?.to_str()?.to_owned() },
+///     _msg = dish.recv() => { panic!("unexpected message") },
+///     timeout(Duration::from_secs(1)) => { panic!("timed out") },
+/// };
+/// assert_eq!(received, "ping");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// A `timeout` arm alone blocks for the duration:
+/// ```
+/// use std::time::Duration;
+///
+/// let fired = libzmq::select! {
+///     timeout(Duration::from_millis(10)) => { true },
+/// };
+/// assert!(fired);
+/// ```
+///
+/// A `default` arm alone fires immediately:
+/// ```
+/// let fired = libzmq::select! {
+///     default => { true },
+/// };
+/// assert!(fired);
+/// ```
+///
+/// [`Poller`]: poll/struct.Poller.html
+#[macro_export]
+macro_rules! select {
+    (@munch $arms:tt $count:tt , $($rest:tt)*) => {
+        $crate::select!(@munch $arms $count $($rest)*)
+    };
+    (@munch [] $count:tt) => {
+        compile_error!("select! requires at least one arm")
+    };
+    (@munch [$($arms:tt)*] $count:tt) => {
+        $crate::select!(@build [$($arms)*] (None) { unreachable!() })
+    };
+    (@munch [$($arms:tt)*] $count:tt
+        timeout($timeout:expr) => $body:block $(,)?
+    ) => {
+        $crate::select!(@build [$($arms)*] (Some($timeout)) $body)
+    };
+    (@munch [$($arms:tt)*] $count:tt default => $body:block $(,)?) => {
+        $crate::select!(
+            @build [$($arms)*]
+            (Some(::std::time::Duration::from_millis(0)))
+            $body
+        )
+    };
+    (@munch $arms:tt $count:tt $msg:pat = $($rest:tt)*) => {
+        $crate::select!(@socket $arms $count ($msg) [] $($rest)*)
+    };
+    // Accumulate the socket expression up to the `.recv()` call, since an
+    // `expr` fragment cannot be followed by a method call.
+    (@socket [$($arms:tt)*] [$($count:tt)*] ($msg:pat) [$($socket:tt)+]
+        .recv() => $body:block $($rest:tt)*
+    ) => {
+        // Each expansion gets its own hygienic `socket` binding.
+        $crate::select!(
+            @munch [$($arms)* ($msg, socket, $($socket)+, $body, [$($count)*])]
+            [$($count)* ()]
+            $($rest)*
+        )
+    };
+    (@socket $arms:tt $count:tt $msg:tt [$($socket:tt)*]) => {
+        compile_error!("expected a `socket.recv() => { ... }` arm")
+    };
+    (@socket $arms:tt $count:tt $msg:tt [$($socket:tt)*]
+        $next:tt $($rest:tt)*
+    ) => {
+        $crate::select!(@socket $arms $count $msg [$($socket)* $next] $($rest)*)
+    };
+    (@build [$((
+            $msg:pat,
+            $name:ident,
+            $socket:expr,
+            $body:block,
+            [$($count:tt)*]
+        ))*]
+        ($timeout:expr) $fallback:block
+    ) => {{
+        $(let $name = &$socket;)*
+
+        let timeout: ::std::option::Option<::std::time::Duration> = $timeout;
+        let deadline = timeout.map(|t| ::std::time::Instant::now() + t);
+
+        let mut poller = $crate::poll::Poller::new();
+        $(
+            // Only fails if the same socket is selected twice.
+            let _ = poller.add(
+                $name,
+                $crate::poll::PollId(0),
+                $crate::poll::READABLE,
+            );
+        )*
+        let mut events = $crate::poll::Events::with_limit(1);
+
+        let fired: ::std::option::Option<(
+            usize,
+            ::std::result::Result<$crate::Msg, $crate::Error>,
+        )> = loop {
+            $(
+                match $crate::prelude::RecvMsg::try_recv_msg($name) {
+                    Err(ref err)
+                        if err.kind() == $crate::ErrorKind::WouldBlock => {}
+                    result => {
+                        break Some((<[()]>::len(&[$($count),*]), result))
+                    }
+                }
+            )*
+
+            let remaining = match deadline {
+                Some(deadline) => {
+                    let now = ::std::time::Instant::now();
+                    if now >= deadline {
+                        break None;
+                    }
+                    Some(deadline - now)
+                }
+                None => None,
+            };
+
+            // The errors are reported by the sockets themselves.
+            let _ = poller.wait(&mut events, remaining);
+        };
+
+        match fired {
+            #[allow(unused_variables)]
+            Some((index, result)) => {
+                $(
+                    if index == <[()]>::len(&[$($count),*]) {
+                        let $msg = result;
+                        $body
+                    } else
+                )*
+                { unreachable!() }
+            }
+            None => $fallback,
+        }
+    }};
+    ($($tokens:tt)*) => {
+        $crate::select!(@munch [] [] $($tokens)*)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{prelude::*, *};
+
+    use std::{convert::TryInto, time::Duration};
+
+    #[test]
+    fn test_select_fires_ready_arm() {
+        let addr = InprocAddr::new_unique();
+        let first = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        // Make sure user variables do not collide with the macro's.
+        let addr = InprocAddr::new_unique();
+        let poller = ServerBuilder::new().bind(&addr).build().unwrap();
+        let other = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        for _ in 0..10 {
+            other.send("second").unwrap();
+            let fired = crate::select! {
+                msg = first.recv() => { (0, msg.unwrap()) },
+                msg = poller.recv() => { (1, msg.unwrap()) },
+                timeout(Duration::from_secs(1)) => { panic!("timed out") },
+            };
+            assert_eq!(fired.0, 1);
+            assert_eq!(fired.1.to_str().unwrap(), "second");
+
+            client.send("first").unwrap();
+            let fired = crate::select! {
+                msg = first.recv() => { (0, msg.unwrap()) },
+                msg = poller.recv() => { (1, msg.unwrap()) },
+            };
+            assert_eq!(fired.0, 0);
+            assert_eq!(fired.1.to_str().unwrap(), "first");
+        }
+    }
+
+    #[test]
+    fn test_select_socket_expr() {
+        struct Pair {
+            server: Server,
+            client: Client,
+        }
+
+        let pairs: Vec<Pair> = (0..2)
+            .map(|_| {
+                let addr = InprocAddr::new_unique();
+                let server = ServerBuilder::new().bind(&addr).build().unwrap();
+                let client =
+                    ClientBuilder::new().connect(&addr).build().unwrap();
+                Pair { server, client }
+            })
+            .collect();
+
+        let mut evaluated = 0;
+        let mut first = || {
+            evaluated += 1;
+            &pairs[0]
+        };
+
+        pairs[1].client.send("second").unwrap();
+        let fired = crate::select! {
+            msg = first().server.recv() => { (0, msg.unwrap()) },
+            msg = pairs[1].server.recv() => { (1, msg.unwrap()) },
+            timeout(Duration::from_secs(1)) => { panic!("timed out") },
+        };
+        assert_eq!(fired.0, 1);
+        assert_eq!(fired.1.to_str().unwrap(), "second");
+        assert_eq!(evaluated, 1);
+    }
+
+    #[test]
+    fn test_select_timeout_and_default() {
+        let server = Server::new().unwrap();
+        let group: &Group = "group".try_into().unwrap();
+        let dish = DishBuilder::new().join(group).build().unwrap();
+
+        let fired = crate::select! {
+            _msg = server.recv() => { false },
+            _msg = dish.recv() => { false },
+            timeout(Duration::from_millis(10)) => { true },
+        };
+        assert!(fired);
+
+        let fired = crate::select! {
+            _msg = server.recv() => { false },
+            default => { true },
+        };
+        assert!(fired);
+    }
+}
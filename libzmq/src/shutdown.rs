@@ -0,0 +1,382 @@
+//! Coordinated shutdown of a context and of the threads using it.
+
+use crate::{core::*, error::*, poll::Waker, Ctx, Period};
+
+use std::{
+    collections::BTreeMap,
+    mem,
+    sync::{Arc, Condvar, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Default)]
+struct State {
+    initiated: bool,
+    next_id: u64,
+    workers: BTreeMap<u64, String>,
+    sockets: Vec<Weak<RawSocket>>,
+    wakers: Vec<Waker>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    ctx: Mutex<Option<Ctx>>,
+    state: Mutex<State>,
+    cond: Condvar,
+}
+
+impl Inner {
+    fn add_waker(&self, waker: Waker) {
+        let mut state = self.state.lock().unwrap();
+        if state.initiated {
+            let _ = waker.wake();
+        } else {
+            state.wakers.push(waker);
+        }
+    }
+}
+
+/// What did not finish within the budget of a [`Shutdown`].
+///
+/// [`Shutdown`]: struct.Shutdown.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShutdownReport {
+    workers: Vec<String>,
+    open_sockets: usize,
+}
+
+impl ShutdownReport {
+    /// Returns the names of the workers that still held their token.
+    pub fn workers(&self) -> &[String] {
+        &self.workers
+    }
+
+    /// Returns the number of sockets of the context that were still open.
+    ///
+    /// If it is zero while the workers all finished, the termination of the
+    /// context was waiting on the messages of closed sockets.
+    pub fn open_sockets(&self) -> usize {
+        self.open_sockets
+    }
+}
+
+/// Coordinates the shutdown of a context along with the threads and
+/// sockets using it.
+///
+/// The threads using the context take a [`ShutdownToken`] which they hold
+/// until they exit, and the sockets whose pending messages should not delay
+/// the shutdown for too long are registered. Once [`initiate`] is called,
+/// the coordinator:
+/// 1. bounds the linger period of the registered sockets by the budget,
+/// 2. wakes up the registered [`Waker`], so that event loops can exit,
+/// 3. shuts the context down, so that the blocked calls on its sockets
+///    return [`CtxTerminated`],
+/// 4. waits for the threads to drop their token,
+/// 5. terminates the context.
+///
+/// Since a context is terminated once its last handle is dropped, the
+/// coordinator should own the last handle that is not held by a socket.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, shutdown::Shutdown, *};
+/// use std::{thread, time::Duration};
+///
+/// let ctx = Ctx::new();
+/// let server = Server::with_ctx(&ctx)?;
+/// let shutdown = Shutdown::new(ctx);
+/// shutdown.register(&server);
+///
+/// let token = shutdown.token("worker");
+/// thread::spawn(move || {
+///     let _token = token;
+///     while server.recv_msg().is_ok() {}
+/// });
+///
+/// shutdown.initiate(Duration::from_secs(1))?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`ShutdownToken`]: struct.ShutdownToken.html
+/// [`initiate`]: #method.initiate
+/// [`Waker`]: ../poll/struct.Waker.html
+/// [`CtxTerminated`]: ../enum.ErrorKind.html#variant.CtxTerminated
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+impl Shutdown {
+    /// Creates a coordinator for the context.
+    pub fn new<C>(ctx: C) -> Self
+    where
+        C: Into<Ctx>,
+    {
+        let inner = Inner {
+            ctx: Mutex::new(Some(ctx.into())),
+            state: Mutex::default(),
+            cond: Condvar::new(),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Returns `true` if the shutdown was initiated.
+    pub fn is_initiated(&self) -> bool {
+        self.inner.state.lock().unwrap().initiated
+    }
+
+    /// Creates a token that the shutdown waits for until it is dropped.
+    ///
+    /// The name identifies the token in the [`ShutdownReport`].
+    ///
+    /// [`ShutdownReport`]: struct.ShutdownReport.html
+    pub fn token<N>(&self, name: N) -> ShutdownToken
+    where
+        N: Into<String>,
+    {
+        let mut state = self.inner.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.workers.insert(id, name.into());
+
+        ShutdownToken {
+            inner: Arc::clone(&self.inner),
+            id,
+        }
+    }
+
+    /// Registers the socket, whose linger period is bounded by the budget of
+    /// the shutdown.
+    ///
+    /// The coordinator does not keep the socket open.
+    pub fn register<S>(&self, socket: &S)
+    where
+        S: Socket,
+    {
+        let mut state = self.inner.state.lock().unwrap();
+        if !state.initiated {
+            state.sockets.push(Arc::downgrade(socket.raw_socket()));
+        }
+    }
+
+    /// Registers a waker that is woken up once the shutdown is initiated.
+    ///
+    /// This allows an event loop that also polls non-socket sources to exit
+    /// promptly. A waker registered after the initiation is woken up
+    /// right away.
+    pub fn add_waker(&self, waker: Waker) {
+        self.inner.add_waker(waker);
+    }
+
+    /// Initiates the shutdown and waits for it to complete for at most
+    /// `budget`.
+    ///
+    /// # Returned Error Variants
+    /// * [`WouldBlock`] (if the shutdown did not complete in time)
+    /// * [`InvalidInput`] (if the shutdown was already initiated)
+    ///
+    /// In the first case, the error contains the [`ShutdownReport`]. The
+    /// context will still be terminated once its sockets are dropped and
+    /// their linger period expires.
+    ///
+    /// [`WouldBlock`]: ../enum.ErrorKind.html#variant.WouldBlock
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    /// [`ShutdownReport`]: struct.ShutdownReport.html
+    pub fn initiate(
+        &self,
+        budget: Duration,
+    ) -> Result<(), Error<ShutdownReport>> {
+        let deadline = Instant::now() + budget;
+        let ctx = self.inner.ctx.lock().unwrap().take().ok_or_else(|| {
+            Error::new(ErrorKind::InvalidInput {
+                msg: "shutdown already initiated",
+            })
+        })?;
+
+        let wakers = {
+            let mut state = self.inner.state.lock().unwrap();
+            state.initiated = true;
+
+            // The options can no longer be set once the context is shut
+            // down.
+            for socket in state.sockets.drain(..).filter_map(|s| s.upgrade()) {
+                let _ = socket.set_linger(Period::Finite(budget));
+            }
+            mem::take(&mut state.wakers)
+        };
+
+        for waker in &wakers {
+            let _ = waker.wake();
+        }
+        ctx.shutdown();
+
+        let mut state = self.inner.state.lock().unwrap();
+        while !state.workers.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            state = self
+                .inner
+                .cond
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+        let workers: Vec<String> = state.workers.values().cloned().collect();
+        drop(state);
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let terminated = ctx.terminate_timeout(remaining);
+
+        if workers.is_empty() && terminated.is_ok() {
+            Ok(())
+        } else {
+            let open_sockets = match terminated {
+                Ok(()) => 0,
+                Err(err) => err.content().copied().unwrap_or_default(),
+            };
+            let report = ShutdownReport {
+                workers,
+                open_sockets,
+            };
+
+            Err(Error::with_content(ErrorKind::WouldBlock, report))
+        }
+    }
+}
+
+/// A token held by a thread until it finished using the context of a
+/// [`Shutdown`].
+///
+/// [`Shutdown`]: struct.Shutdown.html
+#[derive(Debug)]
+pub struct ShutdownToken {
+    inner: Arc<Inner>,
+    id: u64,
+}
+
+impl ShutdownToken {
+    /// Returns `true` if the shutdown was initiated, in which case the
+    /// thread should exit.
+    pub fn is_initiated(&self) -> bool {
+        self.inner.state.lock().unwrap().initiated
+    }
+
+    // Same as `Shutdown::add_waker`.
+    pub(crate) fn add_waker(&self, waker: Waker) {
+        self.inner.add_waker(waker);
+    }
+}
+
+impl Drop for ShutdownToken {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.workers.remove(&self.id);
+        self.inner.cond.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{poll::*, prelude::*, *};
+
+    use std::thread;
+
+    #[test]
+    fn test_shutdown_blocked_workers() {
+        let ctx = Ctx::new();
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new().bind(&addr).with_ctx(&ctx).unwrap();
+        let client =
+            ClientBuilder::new().connect(&addr).with_ctx(&ctx).unwrap();
+
+        let shutdown = Shutdown::new(ctx);
+        shutdown.register(&server);
+        shutdown.register(&client);
+
+        let handles: Vec<_> = vec![server.clone(), server]
+            .into_iter()
+            .enumerate()
+            .map(|(i, server)| {
+                let token = shutdown.token(format!("worker-{}", i));
+                thread::spawn(move || {
+                    let _token = token;
+                    loop {
+                        if let Err(err) = server.recv_msg() {
+                            return err.kind();
+                        }
+                    }
+                })
+            })
+            .collect();
+        // Some messages are pending.
+        client.send("").unwrap();
+        drop(client);
+
+        let start = Instant::now();
+        shutdown.initiate(Duration::from_secs(5)).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        for handle in handles {
+            let kind = handle.join().unwrap();
+            assert_eq!(kind, ErrorKind::CtxTerminated);
+        }
+        assert!(shutdown.is_initiated());
+    }
+
+    #[test]
+    fn test_shutdown_report() {
+        let ctx = Ctx::new();
+        let server = Server::with_ctx(&ctx).unwrap();
+        let shutdown = Shutdown::new(ctx);
+        let token = shutdown.token("stuck");
+
+        let mut err = shutdown.initiate(Duration::from_millis(50)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        let report = err.take_content().unwrap();
+        assert_eq!(report.workers(), ["stuck".to_owned()]);
+        assert!(report.open_sockets() >= 1);
+        assert!(token.is_initiated());
+
+        let err = shutdown.initiate(Duration::from_millis(50)).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+        drop(server);
+    }
+
+    #[test]
+    fn test_shutdown_wakes_poller() {
+        let shutdown = Shutdown::new(Ctx::new());
+        let token = shutdown.token("event-loop");
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let clone = shutdown.clone();
+        let handle = thread::spawn(move || {
+            let mut poller = Poller::new();
+            clone.add_waker(poller.add_waker(PollId(0)).unwrap());
+            sender.send(()).unwrap();
+
+            let mut events = Events::new();
+            while !token.is_initiated() {
+                poller.block(&mut events, None).unwrap();
+            }
+        });
+
+        receiver.recv().unwrap();
+        shutdown.initiate(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+    }
+}
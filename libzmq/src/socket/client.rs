@@ -1,8 +1,170 @@
-use crate::{addr::Endpoint, auth::*, core::*, error::*, Ctx};
+use crate::{
+    addr::Endpoint, auth::*, core::*, error::*, old::*, poll::*, Ctx,
+    InprocAddr, Msg,
+};
+use libzmq_sys as sys;
 
+use hashbrown::HashSet;
 use serde::{Deserialize, Serialize};
 
-use std::sync::Arc;
+use std::{
+    ffi::CString,
+    net::ToSocketAddrs,
+    os::raw::c_int,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+// The interval at which `wait_connected` checks the connection state.
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+// Returns a socket receiving the events of the socket that are in the mask.
+pub(crate) fn monitor(
+    socket: &RawSocket,
+    events: u32,
+) -> Result<OldSocket, Error> {
+    let endpoint: Endpoint = InprocAddr::new_unique().into();
+    let c_string = CString::new(endpoint.to_string()).unwrap();
+
+    let rc = unsafe {
+        sys::zmq_socket_monitor(
+            socket.as_mut_ptr(),
+            c_string.as_ptr(),
+            events as c_int,
+        )
+    };
+
+    if rc == -1 {
+        let errno = unsafe { sys::zmq_errno() };
+        return Err(from_errno(errno));
+    }
+
+    let mut monitor = OldSocket::with_ctx(OldSocketType::Pair, socket.ctx())?;
+    monitor.connect(endpoint)?;
+
+    Ok(monitor)
+}
+
+// Returns the next event of the monitor along with its value, or `None` if
+// no event is reported within the timeout.
+#[cfg(test)]
+pub(crate) fn recv_event(
+    monitor: &mut OldSocket,
+    timeout: Duration,
+) -> Option<(u32, u32)> {
+    let deadline = Deadline::after(timeout);
+
+    loop {
+        match monitor.try_recv_msg_multipart() {
+            Ok(frames) => {
+                // The first frame holds the event as a native `u16` followed
+                // by its value as a native `u32`.
+                if let [a, b, c, d, e, f] = frames[0].as_bytes() {
+                    let event = u16::from_ne_bytes([*a, *b]);
+                    let value = u32::from_ne_bytes([*c, *d, *e, *f]);
+                    return Some((u32::from(event), value));
+                }
+            }
+            Err(err) => assert_eq!(err.kind(), ErrorKind::WouldBlock),
+        }
+
+        if deadline.has_passed() {
+            return None;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+// The connection state of a `Client`.
+//
+// It is tracked from the events of a socket monitor, which is started
+// before the first connection to an endpoint that is not `inproc` so that
+// no handshake is missed. Since `inproc` connections emit no event, they
+// are deemed established as soon as they are made.
+#[derive(Debug, Default)]
+struct Liveness {
+    monitor: Option<OldSocket>,
+    inproc: HashSet<String>,
+    peers: HashSet<String>,
+}
+
+impl Liveness {
+    fn start_monitor(&mut self, socket: &RawSocket) -> Result<(), Error> {
+        if self.monitor.is_none() {
+            let events = sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED
+                | sys::ZMQ_EVENT_DISCONNECTED;
+            self.monitor = Some(monitor(socket, events)?);
+        }
+
+        Ok(())
+    }
+
+    // Applies the pending events of the monitor, if any.
+    fn update(&mut self) -> Result<(), Error> {
+        let monitor = match &mut self.monitor {
+            Some(monitor) => monitor,
+            None => return Ok(()),
+        };
+
+        loop {
+            let frames = match monitor.try_recv_msg_multipart() {
+                Ok(frames) => frames,
+                Err(err) => match err.kind() {
+                    ErrorKind::WouldBlock => return Ok(()),
+                    _ => return Err(err),
+                },
+            };
+
+            // The first frame starts with the event as a native `u16`, the
+            // second holds the endpoint of the connection.
+            if let [event, endpoint] = frames.as_slice() {
+                let event = match event.as_bytes() {
+                    [a, b, ..] => u32::from(u16::from_ne_bytes([*a, *b])),
+                    _ => continue,
+                };
+                let endpoint =
+                    String::from_utf8_lossy(endpoint.as_bytes()).into_owned();
+
+                match event {
+                    sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED => {
+                        self.peers.insert(endpoint);
+                    }
+                    sys::ZMQ_EVENT_DISCONNECTED => {
+                        self.peers.remove(&endpoint);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        !self.inproc.is_empty() || !self.peers.is_empty()
+    }
+}
+
+// Returns the addresses that the monitor reports for the connections to
+// the endpoint, which are resolved if the endpoint has a hostname.
+fn event_addrs(endpoint: &Endpoint) -> Vec<String> {
+    let addr = match endpoint {
+        Endpoint::Tcp(addr) => addr,
+        _ => return vec![endpoint.to_string()],
+    };
+
+    let host = addr.host();
+    match (host.interface(), host.port().number()) {
+        (crate::addr::Interface::Hostname(name), Some(port)) => {
+            let addrs = match (name.as_str(), port).to_socket_addrs() {
+                Ok(addrs) => addrs,
+                Err(_) => return vec![],
+            };
+            addrs.map(|addr| format!("tcp://{}", addr)).collect()
+        }
+        // The source address is not part of the reported address.
+        _ => vec![format!("tcp://{}", host)],
+    }
+}
 
 /// A `Client` socket is used for advanced request-reply messaging.
 ///
@@ -33,32 +195,27 @@ use std::sync::Arc;
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, *};
-/// use std::convert::TryInto;
 ///
-/// // Use a system assigned port.
-/// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+/// // Use a unique in-process address.
+/// let addr = InprocAddr::new_unique();
 ///
 /// let server = ServerBuilder::new()
-///     .bind(addr)
+///     .bind(&addr)
 ///     .build()?;
 ///
-/// // Retrieve the addr that was assigned.
-/// let bound = server.last_endpoint()?;
-///
 /// let client = ClientBuilder::new()
-///     .connect(bound)
+///     .connect(addr)
 ///     .build()?;
 ///
 /// // Send a string request.
 /// client.send("tell me something")?;
 ///
 /// // Receive the client request.
-/// let msg = server.recv_msg()?;
-/// let id = msg.routing_id().unwrap();
+/// let (id, _) = server.recv_from()?;
 ///
 /// // Reply to the client.
 /// let mut reply: Msg = "it takes 224 bits to store a i32 in java".into();
@@ -80,9 +237,10 @@ use std::sync::Arc;
 /// ```
 ///
 /// [`Server`]: struct.Server.html
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct Client {
     inner: Arc<RawSocket>,
+    liveness: Arc<Mutex<Liveness>>,
 }
 
 impl Client {
@@ -98,7 +256,10 @@ impl Client {
     pub fn new() -> Result<Self, Error> {
         let inner = Arc::new(RawSocket::new(RawSocketType::Client)?);
 
-        Ok(Self { inner })
+        Ok(Self {
+            inner,
+            liveness: Arc::default(),
+        })
     }
 
     /// Create a `Client` socket from a specific context.
@@ -115,22 +276,606 @@ impl Client {
     {
         let inner = Arc::new(RawSocket::with_ctx(RawSocketType::Client, ctx)?);
 
-        Ok(Self { inner })
+        Ok(Self {
+            inner,
+            liveness: Arc::default(),
+        })
     }
 
     /// Returns a reference to the context of the socket.
     pub fn ctx(&self) -> &crate::Ctx {
         self.inner.ctx()
     }
+
+    /// Returns a snapshot of the socket's current configuration.
+    ///
+    /// The option values are read back from the socket, which is useful
+    /// to log its effective configuration. Since the socket does not
+    /// keep track of its endpoints, `connect` and `bind` are always `None`.
+    pub fn config(&self) -> Result<ClientConfig, Error> {
+        Ok(ClientConfig {
+            socket_config: SocketConfig::from_socket(self)?,
+            send_config: SendConfig::from_socket(self)?,
+            recv_config: RecvConfig::from_socket(self)?,
+        })
+    }
+
+    /// Returns a `ClientBuilder` configured like the socket, but with no
+    /// endpoints.
+    ///
+    /// This is useful to create another socket configured exactly like this
+    /// one, for instance to rebuild it after a fatal error. The options are
+    /// read back from the socket as with [`config`].
+    ///
+    /// The secrets of the [`Mechanism`], that is the password of a
+    /// `PlainClient` and the secret keys of a `CurveClient` or a
+    /// `CurveServer`, are left out. In that case the builder fails with
+    /// [`InvalidInput`] until a mechanism is supplied again, so that the new
+    /// socket is never silently downgraded to the `Null` mechanism. Use
+    /// [`to_builder_with_secrets`] to carry them over instead.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, auth::*, *};
+    /// use std::time::Duration;
+    ///
+    /// let creds = PlainClientCreds::new("user", "pass");
+    /// let client = ClientBuilder::new()
+    ///     .send_timeout(Duration::from_millis(100))
+    ///     .mechanism(&creds)
+    ///     .build()?;
+    ///
+    /// let other = client
+    ///     .to_builder()?
+    ///     .mechanism(&creds)
+    ///     .connect(InprocAddr::new_unique())
+    ///     .build()?;
+    /// assert_eq!(other.send_timeout()?, client.send_timeout()?);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`config`]: #method.config
+    /// [`Mechanism`]: auth/enum.Mechanism.html
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    /// [`to_builder_with_secrets`]: #method.to_builder_with_secrets
+    pub fn to_builder(&self) -> Result<ClientBuilder, Error> {
+        let mut inner = self.config()?;
+        inner.socket_config_mut().redact_secrets();
+        Ok(ClientBuilder { inner })
+    }
+
+    /// Same as [`to_builder`] except that the secrets of the [`Mechanism`]
+    /// are carried over to the builder.
+    ///
+    /// [`to_builder`]: #method.to_builder
+    /// [`Mechanism`]: auth/enum.Mechanism.html
+    pub fn to_builder_with_secrets(&self) -> Result<ClientBuilder, Error> {
+        Ok(ClientBuilder {
+            inner: self.config()?,
+        })
+    }
+
+    /// Returns `true` if at least one connection of the socket completed
+    /// its handshake and was not lost since.
+    ///
+    /// The state is computed from the events that ØMQ reports for the
+    /// connections made through [`connect`]. Since `inproc` connections
+    /// report no event, they count as established as soon as they are
+    /// made, even if no socket is bound to their endpoint yet.
+    ///
+    /// The events are read from a monitor that the `Client` starts on its
+    /// first connection to an endpoint that is not `inproc`. Since ØMQ
+    /// only supports one monitor per socket, it replaces any monitor
+    /// started before, and a monitor started after it stops the tracking.
+    ///
+    /// [`connect`]: prelude/trait.Socket.html#method.connect
+    pub fn is_connected(&self) -> bool {
+        let mut liveness = self.liveness.lock().unwrap();
+        // On error, the last known state is the best we have.
+        let _ = liveness.update();
+
+        liveness.is_connected()
+    }
+
+    /// Waits until [`is_connected`] returns `true` or the timeout expires.
+    ///
+    /// A timeout of `None` waits forever.
+    ///
+    /// # Returned Error Variants
+    /// * [`WouldBlock`] (if the timeout expires)
+    /// * [`CtxTerminated`]
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::{convert::TryInto, time::Duration};
+    ///
+    /// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let endpoint = server.last_endpoint()?.unwrap();
+    ///
+    /// let client = ClientBuilder::new().connect(endpoint).build()?;
+    /// client.wait_connected(Some(Duration::from_secs(1)))?;
+    /// assert!(client.is_connected());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`is_connected`]: #method.is_connected
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    pub fn wait_connected(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        self.wait_connected_until(timeout.map(Deadline::after))
+    }
+
+    /// Same as [`wait_connected`] except that it waits until the deadline
+    /// passes. See [`Deadline`].
+    ///
+    /// [`wait_connected`]: #method.wait_connected
+    /// [`Deadline`]: struct.Deadline.html
+    pub fn wait_connected_deadline<D>(&self, deadline: D) -> Result<(), Error>
+    where
+        D: Into<Deadline>,
+    {
+        self.wait_connected_until(Some(deadline.into()))
+    }
+
+    fn wait_connected_until(
+        &self,
+        deadline: Option<Deadline>,
+    ) -> Result<(), Error> {
+        loop {
+            {
+                let mut liveness = self.liveness.lock().unwrap();
+                liveness.update()?;
+                if liveness.is_connected() {
+                    return Ok(());
+                }
+            }
+
+            // The lock is released while waiting so that other threads can
+            // check the state in the meantime.
+            let interval = match deadline {
+                Some(deadline) => {
+                    if deadline.has_passed() {
+                        return Err(Error::new(ErrorKind::WouldBlock));
+                    }
+                    WAIT_INTERVAL.min(deadline.remaining())
+                }
+                None => WAIT_INTERVAL,
+            };
+            thread::sleep(interval);
+        }
+    }
+
+    /// Sends a request and waits for its reply, resending the request
+    /// according to the `RetryPolicy` if no reply is received in time.
+    ///
+    /// This is the "lazy pirate" pattern of the zguide. Since the socket is
+    /// not recreated between the attempts, a late reply to a previous
+    /// attempt can be returned. If the requests are not idempotent,
+    /// the replies should be correlated with the requests by the caller.
+    ///
+    /// # Returned Error Variants
+    /// * [`RetriesExhausted`] (if no reply was received)
+    /// * [`WouldBlock`] (if `send_timeout` expires)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::time::Duration;
+    ///
+    /// let addr = InprocAddr::new_unique();
+    ///
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// let policy = RetryPolicy::new(Duration::from_millis(100))
+    ///     .add_max_retries(2);
+    ///
+    /// // Nobody replies to the request.
+    /// let err = client.request("ping", policy).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::RetriesExhausted { attempts: 3 });
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`RetriesExhausted`]: enum.ErrorKind.html#variant.RetriesExhausted
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    pub fn request<M>(&self, msg: M, policy: RetryPolicy) -> Result<Msg, Error>
+    where
+        M: Into<Msg>,
+    {
+        self.request_until(msg.into(), policy, None)
+    }
+
+    /// Same as [`request`] except that the whole exchange, including the
+    /// retries and their backoff, must complete before the deadline.
+    ///
+    /// Each attempt waits for at most the time that remains. See
+    /// [`Deadline`].
+    ///
+    /// # Returned Error Variants
+    /// * [`WouldBlock`] (if the deadline passes)
+    /// * [`RetriesExhausted`] (if no reply was received before the deadline)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::time::Duration;
+    ///
+    /// let addr = InprocAddr::new_unique();
+    ///
+    /// let server = ServerBuilder::new().bind(&addr).build()?;
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// // The policy would allow for 5 secs, but the deadline cuts it short.
+    /// let policy = RetryPolicy::new(Duration::from_secs(1))
+    ///     .add_max_retries(4);
+    /// let deadline = Duration::from_millis(100);
+    ///
+    /// let err = client
+    ///     .request_deadline("ping", policy, deadline)
+    ///     .unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`request`]: #method.request
+    /// [`Deadline`]: struct.Deadline.html
+    /// [`RetriesExhausted`]: enum.ErrorKind.html#variant.RetriesExhausted
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    pub fn request_deadline<M, D>(
+        &self,
+        msg: M,
+        policy: RetryPolicy,
+        deadline: D,
+    ) -> Result<Msg, Error>
+    where
+        M: Into<Msg>,
+        D: Into<Deadline>,
+    {
+        self.request_until(msg.into(), policy, Some(deadline.into()))
+    }
+
+    fn request_until(
+        &self,
+        msg: Msg,
+        policy: RetryPolicy,
+        deadline: Option<Deadline>,
+    ) -> Result<Msg, Error> {
+        let mut poller = Poller::new();
+        poller.add(self, PollId(0), READABLE)?;
+
+        let mut events = Events::new();
+        let mut backoff = policy.backoff;
+
+        // Caps a wait to the time left before the deadline.
+        let capped = |duration: Duration| match deadline {
+            Some(deadline) => duration.min(deadline.remaining()),
+            None => duration,
+        };
+
+        for attempt in 0..=policy.max_retries {
+            if attempt > 0 {
+                thread::sleep(capped(backoff));
+                backoff = backoff.checked_mul(2).unwrap_or(Duration::MAX);
+            }
+
+            match deadline {
+                Some(deadline) => self.send_deadline(msg.clone(), deadline),
+                None => self.send(msg.clone()),
+            }
+            .map_err(Error::cast)?;
+
+            match poller.block(&mut events, Some(capped(policy.timeout))) {
+                Ok(()) => {
+                    return match deadline {
+                        Some(deadline) => self.recv_deadline(deadline),
+                        None => self.recv_msg(),
+                    }
+                }
+                Err(err) => {
+                    if err.kind() != ErrorKind::WouldBlock {
+                        return Err(err);
+                    }
+                }
+            }
+
+            if matches!(deadline, Some(deadline) if deadline.has_passed()) {
+                return Err(Error::new(ErrorKind::WouldBlock));
+            }
+        }
+
+        Err(Error::new(ErrorKind::RetriesExhausted {
+            attempts: policy.max_retries + 1,
+        }))
+    }
+
+    /// Splits the `Client` into a half that can only send and a half that
+    /// can only receive.
+    ///
+    /// The halves share the same underlying socket and can be moved to
+    /// different threads. They can be reunited into the `Client` via
+    /// [`SendHalf::reunite`].
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # #[cfg(feature = "test-util")]
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, test_util::client_server_pair, *};
+    /// use std::thread;
+    ///
+    /// let (client, server) = client_server_pair();
+    ///
+    /// let (sender, receiver) = client.split();
+    /// let handle = thread::spawn(move || sender.send("ping"));
+    ///
+    /// let (id, _) = server.recv_from()?;
+    /// let mut reply: Msg = "pong".into();
+    /// reply.set_routing_id(id);
+    /// server.send(reply)?;
+    ///
+    /// assert_eq!(receiver.recv_msg()?.to_str()?, "pong");
+    /// handle.join().unwrap()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`SendHalf::reunite`]: struct.SendHalf.html#method.reunite
+    pub fn split(self) -> (SendHalf, RecvHalf) {
+        let sender = SendHalf {
+            client: self.clone(),
+        };
+        let receiver = RecvHalf { client: self };
+
+        (sender, receiver)
+    }
+}
+
+// Returns the `Client` if both halves come from it.
+fn reunite(
+    sender: SendHalf,
+    receiver: RecvHalf,
+) -> Result<Client, Error<(SendHalf, RecvHalf)>> {
+    if sender.client == receiver.client {
+        Ok(sender.client)
+    } else {
+        Err(Error::with_content(
+            ErrorKind::InvalidInput {
+                msg: "halves come from different clients",
+            },
+            (sender, receiver),
+        ))
+    }
+}
+
+/// The sending half of a [`Client`], created by [`split`].
+///
+/// [`Client`]: struct.Client.html
+/// [`split`]: struct.Client.html#method.split
+#[derive(Debug)]
+pub struct SendHalf {
+    client: Client,
+}
+
+impl SendHalf {
+    /// Reunites the halves into the `Client` they were split from.
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the halves come from different clients)
+    ///
+    /// In that case, the error contains the halves.
+    ///
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn reunite(
+        self,
+        receiver: RecvHalf,
+    ) -> Result<Client, Error<(SendHalf, RecvHalf)>> {
+        reunite(self, receiver)
+    }
+}
+
+impl GetRawSocket for SendHalf {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
+        self.client.raw_socket()
+    }
+}
+
+impl SendMsg for SendHalf {}
+
+/// The receiving half of a [`Client`], created by [`split`].
+///
+/// [`Client`]: struct.Client.html
+/// [`split`]: struct.Client.html#method.split
+#[derive(Debug)]
+pub struct RecvHalf {
+    client: Client,
+}
+
+impl RecvHalf {
+    /// Reunites the halves into the `Client` they were split from.
+    ///
+    /// See [`SendHalf::reunite`].
+    ///
+    /// [`SendHalf::reunite`]: struct.SendHalf.html#method.reunite
+    pub fn reunite(
+        self,
+        sender: SendHalf,
+    ) -> Result<Client, Error<(SendHalf, RecvHalf)>> {
+        reunite(sender, self)
+    }
+}
+
+impl GetRawSocket for RecvHalf {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
+        self.client.raw_socket()
+    }
+}
+
+impl RecvMsg for RecvHalf {}
+
+/// The retry policy of a [`request`].
+///
+/// # Default Value
+/// A timeout of 2.5 seconds with 3 retries and no backoff.
+///
+/// [`request`]: struct.Client.html#method.request
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    timeout: Duration,
+    max_retries: usize,
+    backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Create a new `RetryPolicy` that waits at most `timeout` for the
+    /// reply and never retries.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            max_retries: 0,
+            backoff: Duration::from_millis(0),
+        }
+    }
+
+    /// Specifies the maximum number of times the request is resent.
+    pub fn add_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Specifies the delay before the first resend.
+    ///
+    /// The delay is doubled after each resend, up to `Duration::MAX`.
+    pub fn add_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Returns the duration to wait for a reply on each attempt.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns the maximum number of times the request is resent.
+    pub fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    /// Returns the delay before the first resend.
+    pub fn backoff(&self) -> Duration {
+        self.backoff
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(2500)).add_max_retries(3)
+    }
 }
 
 impl GetRawSocket for Client {
-    fn raw_socket(&self) -> &RawSocket {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
         &self.inner
     }
 }
 
-impl Socket for Client {}
+impl PartialEq for Client {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for Client {}
+
+// Tracks the connections for `is_connected`.
+impl Socket for Client {
+    fn connect<I, E>(&self, endpoints: I) -> Result<(), Error<usize>>
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<Endpoint>,
+    {
+        let mut count = 0;
+        let mut liveness = self.liveness.lock().unwrap();
+
+        for endpoint in endpoints.into_iter().map(E::into) {
+            if !endpoint.is_inproc() {
+                liveness
+                    .start_monitor(&self.inner)
+                    .map_err(|err| err.cast_with(count))?;
+            }
+
+            self.inner
+                .connect(&endpoint)
+                .map_err(|err| err.cast_with(count))?;
+
+            if endpoint.is_inproc() {
+                liveness.inproc.insert(endpoint.to_string());
+            }
+            count += 1;
+        }
+
+        Ok(())
+    }
+
+    fn disconnect<I, E>(&self, endpoints: I) -> Result<(), Error<usize>>
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<Endpoint>,
+    {
+        let mut count = 0;
+        let mut liveness = self.liveness.lock().unwrap();
+
+        for endpoint in endpoints.into_iter().map(E::into) {
+            self.inner
+                .disconnect(&endpoint)
+                .map_err(|err| err.cast_with(count))?;
+
+            liveness.inproc.remove(&endpoint.to_string());
+            for addr in event_addrs(&endpoint) {
+                liveness.peers.remove(&addr);
+            }
+            count += 1;
+        }
+
+        Ok(())
+    }
+}
 impl SendMsg for Client {}
 impl RecvMsg for Client {}
 
@@ -180,6 +925,7 @@ impl ClientConfig {
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct FlatClientConfig {
     connect: Option<Vec<Endpoint>>,
     bind: Option<Vec<Endpoint>>,
@@ -190,6 +936,20 @@ struct FlatClientConfig {
     recv_high_water_mark: Quantity,
     recv_timeout: Period,
     mechanism: Option<Mechanism>,
+    zap_domain: Option<String>,
+    enforce_zap_domain: Option<bool>,
+    ipv6: Option<bool>,
+    max_msg_size: Option<i64>,
+    backlog: Option<i32>,
+    send_buffer: Option<i32>,
+    recv_buffer: Option<i32>,
+    vmci_buffer_size: Option<u64>,
+    vmci_buffer_min_size: Option<u64>,
+    vmci_buffer_max_size: Option<u64>,
+    vmci_connect_timeout: Option<Period>,
+    reconnect_stop: Option<ReconnectStop>,
+    label: Option<String>,
+    retry_interrupted: Option<bool>,
 }
 
 impl From<ClientConfig> for FlatClientConfig {
@@ -203,6 +963,20 @@ impl From<ClientConfig> for FlatClientConfig {
             heartbeat: socket_config.heartbeat,
             linger: socket_config.linger,
             mechanism: socket_config.mechanism,
+            zap_domain: socket_config.zap_domain,
+            enforce_zap_domain: socket_config.enforce_zap_domain,
+            ipv6: socket_config.ipv6,
+            max_msg_size: socket_config.max_msg_size,
+            backlog: socket_config.backlog,
+            send_buffer: socket_config.send_buffer,
+            recv_buffer: socket_config.recv_buffer,
+            vmci_buffer_size: socket_config.vmci_buffer_size,
+            vmci_buffer_min_size: socket_config.vmci_buffer_min_size,
+            vmci_buffer_max_size: socket_config.vmci_buffer_max_size,
+            vmci_connect_timeout: socket_config.vmci_connect_timeout,
+            reconnect_stop: socket_config.reconnect_stop,
+            label: socket_config.label,
+            retry_interrupted: socket_config.retry_interrupted,
             send_high_water_mark: send_config.send_high_water_mark,
             send_timeout: send_config.send_timeout,
             recv_high_water_mark: recv_config.recv_high_water_mark,
@@ -219,6 +993,22 @@ impl From<FlatClientConfig> for ClientConfig {
             heartbeat: flat.heartbeat,
             linger: flat.linger,
             mechanism: flat.mechanism,
+            mechanism_conflict: false,
+            mechanism_redacted: false,
+            zap_domain: flat.zap_domain,
+            enforce_zap_domain: flat.enforce_zap_domain,
+            ipv6: flat.ipv6,
+            max_msg_size: flat.max_msg_size,
+            backlog: flat.backlog,
+            send_buffer: flat.send_buffer,
+            recv_buffer: flat.recv_buffer,
+            vmci_buffer_size: flat.vmci_buffer_size,
+            vmci_buffer_min_size: flat.vmci_buffer_min_size,
+            vmci_buffer_max_size: flat.vmci_buffer_max_size,
+            vmci_connect_timeout: flat.vmci_connect_timeout,
+            reconnect_stop: flat.reconnect_stop,
+            label: flat.label,
+            retry_interrupted: flat.retry_interrupted,
         };
         let send_config = SendConfig {
             send_high_water_mark: flat.send_high_water_mark,
@@ -336,8 +1126,11 @@ impl BuildRecv for ClientBuilder {}
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::InprocAddr;
-    use std::convert::TryInto;
+    use crate::{
+        has, version, BuildStep, Capability, InprocAddr, ServerBuilder,
+        TcpAddr, VmciAddr,
+    };
+    use std::{convert::TryInto, time::Instant};
 
     #[test]
     fn test_ser_de() {
@@ -350,4 +1143,790 @@ mod test {
         let de: ClientConfig = ron::de::from_str(&ron).unwrap();
         assert_eq!(config, de);
     }
+
+    #[test]
+    fn test_ser_de_toml() {
+        let addr: InprocAddr = "test".try_into().unwrap();
+
+        let mut config = ClientConfig::new();
+        config.set_connect(Some(&addr));
+        config.set_heartbeat(Some(Heartbeat::new(Duration::from_secs(1))));
+        config.set_send_high_water_mark(Quantity::Limited(10));
+        config.set_recv_timeout(Period::Finite(Duration::from_millis(300)));
+
+        // A `toml::Value` emits the tables after the values, as TOML requires.
+        let toml = toml::Value::try_from(&config).unwrap().to_string();
+        let de: ClientConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config, de);
+    }
+
+    #[test]
+    fn test_getters() {
+        let hb = Heartbeat::new(Duration::from_millis(300))
+            .add_timeout(Duration::from_millis(600));
+
+        let client = ClientBuilder::new()
+            .heartbeat(&hb)
+            .linger(Period::Finite(Duration::from_millis(100)))
+            .zap_domain("test_getters")
+            .enforce_zap_domain(false)
+            .ipv6(true)
+            .max_msg_size(Some(1024))
+            .backlog(200)
+            .send_buffer(64 * 1024)
+            .recv_buffer(128 * 1024)
+            .send_high_water_mark(10)
+            .send_timeout(Duration::from_millis(200))
+            .recv_high_water_mark(20)
+            .recv_timeout(Duration::from_millis(300))
+            .build()
+            .unwrap();
+
+        assert_eq!(client.heartbeat(), Some(hb));
+        assert_eq!(
+            client.linger().unwrap(),
+            Period::Finite(Duration::from_millis(100))
+        );
+        assert_eq!(client.mechanism(), Mechanism::Null);
+        assert_eq!(client.zap_domain().unwrap(), "test_getters");
+        assert_eq!(client.enforce_zap_domain().unwrap(), false);
+        assert_eq!(client.ipv6().unwrap(), true);
+        assert_eq!(client.max_msg_size().unwrap(), Some(1024));
+        assert_eq!(client.backlog().unwrap(), 200);
+        assert_eq!(client.send_buffer().unwrap(), 64 * 1024);
+        assert_eq!(client.recv_buffer().unwrap(), 128 * 1024);
+        assert_eq!(
+            client.send_high_water_mark().unwrap(),
+            Quantity::Limited(10)
+        );
+        assert_eq!(
+            client.send_timeout().unwrap(),
+            Period::Finite(Duration::from_millis(200))
+        );
+        assert_eq!(
+            client.recv_high_water_mark().unwrap(),
+            Quantity::Limited(20)
+        );
+        assert_eq!(
+            client.recv_timeout().unwrap(),
+            Period::Finite(Duration::from_millis(300))
+        );
+    }
+
+    #[test]
+    fn test_config_snapshot() {
+        let mut config = ClientConfig::new();
+        config.set_heartbeat(Some(Heartbeat::new(Duration::from_secs(1))));
+        config.set_linger(Period::Finite(Duration::from_millis(100)));
+        config.set_mechanism(Some(Mechanism::Null));
+        config.set_zap_domain(Some("test_config_snapshot".to_owned()));
+        config.set_enforce_zap_domain(Some(false));
+        config.set_ipv6(Some(true));
+        config.set_max_msg_size(Some(1024));
+        config.set_backlog(Some(200));
+        config.set_send_buffer(Some(64 * 1024));
+        config.set_recv_buffer(Some(128 * 1024));
+        config.set_send_high_water_mark(Quantity::Limited(10));
+        config.set_send_timeout(Period::Finite(Duration::from_millis(200)));
+        config.set_recv_high_water_mark(Quantity::Limited(20));
+        config.set_recv_timeout(Period::Finite(Duration::from_millis(300)));
+
+        let client = config.build().unwrap();
+        assert_eq!(client.config().unwrap(), config);
+    }
+
+    #[test]
+    fn test_to_builder() {
+        let hb = Heartbeat::new(Duration::from_millis(300))
+            .add_timeout(Duration::from_millis(600));
+        let creds = PlainClientCreds::new("user", "pass");
+
+        let client = ClientBuilder::new()
+            .heartbeat(&hb)
+            .linger(Period::Finite(Duration::from_millis(100)))
+            .mechanism(&creds)
+            .send_high_water_mark(10)
+            .send_timeout(Duration::from_millis(200))
+            .recv_high_water_mark(20)
+            .recv_timeout(Duration::from_millis(300))
+            .build()
+            .unwrap();
+
+        let copy = client.to_builder_with_secrets().unwrap().build().unwrap();
+        assert_eq!(copy.heartbeat(), Some(hb));
+        assert_eq!(copy.linger().unwrap(), client.linger().unwrap());
+        assert_eq!(copy.mechanism(), Mechanism::PlainClient(creds.clone()));
+        assert_eq!(copy.send_high_water_mark().unwrap(), Quantity::Limited(10));
+        assert_eq!(
+            copy.recv_timeout().unwrap(),
+            Period::Finite(Duration::from_millis(300))
+        );
+        assert_eq!(copy.config().unwrap(), client.config().unwrap());
+
+        // The secrets must be supplied again.
+        let err = client.to_builder().unwrap().build().unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+
+        let copy = client
+            .to_builder()
+            .unwrap()
+            .mechanism(&creds)
+            .build()
+            .unwrap();
+        assert_eq!(copy.config().unwrap(), client.config().unwrap());
+
+        // The reconnect stop conditions are copied where supported.
+        if version() >= (4, 3, 3) {
+            let stop = ReconnectStop::CONN_REFUSED;
+            let client =
+                ClientBuilder::new().reconnect_stop(stop).build().unwrap();
+            let copy = client.to_builder().unwrap().build().unwrap();
+            assert_eq!(copy.reconnect_stop().unwrap(), stop);
+        }
+    }
+
+    #[test]
+    fn test_vmci_options() {
+        let mut config = ClientConfig::new();
+        config.set_vmci_buffer_size(Some(512 * 1024));
+        config.set_vmci_buffer_min_size(Some(1024));
+        config.set_vmci_buffer_max_size(Some(1024 * 1024));
+        config.set_vmci_connect_timeout(Some(Period::Finite(
+            Duration::from_millis(100),
+        )));
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        let de: ClientConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(config, de);
+
+        let result = config.build();
+        if has(Capability::Vmci) {
+            let client = result.unwrap();
+            assert_eq!(client.vmci_buffer_size().unwrap(), 512 * 1024);
+            assert_eq!(client.vmci_buffer_min_size().unwrap(), 1024);
+            assert_eq!(client.vmci_buffer_max_size().unwrap(), 1024 * 1024);
+            assert_eq!(
+                client.vmci_connect_timeout().unwrap(),
+                Period::Finite(Duration::from_millis(100))
+            );
+        } else {
+            match result.unwrap_err().kind() {
+                ErrorKind::Unsupported { .. } => (),
+                _ => panic!("unexpected error"),
+            }
+
+            let client = Client::new().unwrap();
+            match client.vmci_buffer_size().unwrap_err().kind() {
+                ErrorKind::Unsupported { .. } => (),
+                _ => panic!("unexpected error"),
+            }
+        }
+    }
+
+    // Requires a VMware guest or host with the VMCI driver loaded.
+    #[test]
+    #[ignore]
+    fn test_vmci_connect() {
+        let addr: VmciAddr = "@:*".try_into().unwrap();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let client = ClientBuilder::new()
+            .connect(bound)
+            .vmci_connect_timeout(Period::Finite(Duration::from_secs(1)))
+            .build()
+            .unwrap();
+
+        client.send("ping").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+    }
+
+    #[test]
+    fn test_send_reclaim() {
+        let addr = InprocAddr::new_unique();
+
+        let _server = ServerBuilder::new()
+            .bind(&addr)
+            .recv_high_water_mark(1)
+            .build()
+            .unwrap();
+        let client = ClientBuilder::new()
+            .connect(&addr)
+            .send_high_water_mark(1)
+            .build()
+            .unwrap();
+
+        // Fill the queues until the client enters the mute state.
+        let mut err = loop {
+            if let Err(err) = client.try_send("filler") {
+                break err;
+            }
+        };
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(err.take_content().unwrap().as_bytes(), b"filler");
+
+        let bytes = vec![1, 2, 3, 4];
+        let mut err = client.try_send(bytes.clone()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(err.take_content().unwrap().as_bytes(), bytes.as_slice());
+    }
+
+    #[test]
+    fn test_send_batch_partial() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .recv_high_water_mark(1)
+            .build()
+            .unwrap();
+        let client = ClientBuilder::new()
+            .connect(&addr)
+            .send_high_water_mark(1)
+            .build()
+            .unwrap();
+
+        let batch: Vec<Msg> = (0..10u8).map(|i| vec![i].into()).collect();
+        let mut err = client.send_batch(batch).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        let (sent, unsent) = err.take_content().unwrap();
+        assert!(sent > 0);
+        assert_eq!(sent + unsent.len(), 10);
+        // The remainder starts with the message that failed.
+        assert_eq!(unsent[0].as_bytes(), &[sent as u8]);
+
+        let mut received = Vec::new();
+        while received.len() < sent {
+            server.recv_batch(&mut received, 10).unwrap();
+        }
+        assert_eq!(received.len(), sent);
+        for (i, msg) in received.iter().enumerate() {
+            assert_eq!(msg.as_bytes(), &[i as u8]);
+        }
+    }
+
+    #[test]
+    fn test_recv_batch() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new()
+            .connect(&addr)
+            .recv_timeout(Duration::from_millis(10))
+            .build()
+            .unwrap();
+
+        let mut buf = Vec::new();
+        let err = client.recv_batch(&mut buf, 10).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert!(buf.is_empty());
+        assert_eq!(client.recv_batch(&mut buf, 0).unwrap(), 0);
+
+        client.send("").unwrap();
+        let id = server.recv_msg().unwrap().routing_id().unwrap();
+
+        let batch: Vec<Msg> = (0..5)
+            .map(|_| {
+                let mut msg = Msg::new();
+                msg.set_routing_id(id);
+                msg
+            })
+            .collect();
+        assert_eq!(server.send_batch(batch).unwrap(), 5);
+
+        // A batch never exceeds `max`, and the rest stays queued.
+        let mut count = 0;
+        while count < 3 {
+            count += client.recv_batch(&mut buf, 3 - count).unwrap();
+        }
+        assert_eq!(buf.len(), 3);
+        while count < 5 {
+            count += client.recv_batch(&mut buf, 10).unwrap();
+        }
+        assert_eq!(buf.len(), 5);
+    }
+
+    #[test]
+    fn test_buffer_size() {
+        let client = Client::new().unwrap();
+        assert_eq!(client.send_buffer().unwrap(), 0);
+        assert_eq!(client.recv_buffer().unwrap(), 0);
+
+        client.set_send_buffer(4096).unwrap();
+        assert_eq!(client.send_buffer().unwrap(), 4096);
+        client.set_send_buffer(0).unwrap();
+        assert_eq!(client.send_buffer().unwrap(), 0);
+
+        let err = client.set_recv_buffer(-1).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_raw_option() {
+        use libzmq_sys as sys;
+        use std::os::raw::c_int;
+
+        let client = Client::new().unwrap();
+
+        unsafe {
+            client
+                .set_raw_option_i32(sys::ZMQ_RCVTIMEO as c_int, 200)
+                .unwrap();
+            assert_eq!(
+                client
+                    .get_raw_option_i32(sys::ZMQ_RCVTIMEO as c_int)
+                    .unwrap(),
+                200
+            );
+        }
+        assert_eq!(
+            client.recv_timeout().unwrap(),
+            Period::Finite(Duration::from_millis(200))
+        );
+
+        unsafe {
+            client
+                .set_raw_option_bytes(sys::ZMQ_ZAP_DOMAIN as c_int, b"raw")
+                .unwrap();
+        }
+        assert_eq!(client.zap_domain().unwrap(), "raw");
+
+        let err = unsafe { client.set_raw_option_i32(-1, 0) }.unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_request_retry() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        // The server ignores the first two requests.
+        let handle = thread::spawn(move || {
+            for _ in 0..2 {
+                server.recv_msg().unwrap();
+            }
+            let msg = server.recv_msg().unwrap();
+            let mut reply: Msg = "pong".into();
+            reply.set_routing_id(msg.routing_id().unwrap());
+            server.send(reply).unwrap();
+        });
+
+        let policy = RetryPolicy::new(Duration::from_millis(100))
+            .add_max_retries(3)
+            .add_backoff(Duration::from_millis(10));
+        let reply = client.request("ping", policy).unwrap();
+        assert_eq!(reply.to_str().unwrap(), "pong");
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_request_retries_exhausted() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let policy =
+            RetryPolicy::new(Duration::from_millis(50)).add_max_retries(1);
+        let err = client.request("ping", policy).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::RetriesExhausted { attempts: 2 });
+
+        // Every attempt was sent.
+        assert_eq!(server.try_incoming().count(), 2);
+    }
+
+    #[test]
+    fn test_deadline_chain() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let budget = Duration::from_millis(200);
+        let start = Instant::now();
+        let deadline = Deadline::after(budget);
+
+        // Send, receive and send under the same deadline.
+        client.send_deadline("ping", deadline).unwrap();
+        let (id, _) = server.recv_from().unwrap();
+        let mut reply: Msg = "pong".into();
+        reply.set_routing_id(id);
+        server.send_deadline(reply, deadline).unwrap();
+        let reply = client.recv_deadline(deadline).unwrap();
+        assert_eq!(reply.to_str().unwrap(), "pong");
+
+        // No reply will come, so the remaining budget is spent waiting.
+        let err = client.recv_deadline(deadline).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        let elapsed = start.elapsed();
+        assert!(elapsed >= budget);
+        assert!(elapsed < budget + Duration::from_millis(100));
+        assert!(deadline.has_passed());
+        assert_eq!(deadline.remaining(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn test_deadline_saturates() {
+        let deadline = Deadline::after(Duration::MAX);
+        assert!(!deadline.has_passed());
+        assert!(deadline.remaining() > Duration::from_secs(60 * 60));
+
+        let (client, server) = crate::test_util::client_server_pair();
+        client.send("ping").unwrap();
+        let msg = server.recv_deadline(Duration::MAX).unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+    }
+
+    #[test]
+    fn test_deadline_passed() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let deadline = Deadline::new(Instant::now());
+        thread::sleep(Duration::from_millis(1));
+        assert!(deadline.has_passed());
+
+        // A passed deadline still allows a non-blocking attempt.
+        client.send_deadline("ping", deadline).unwrap();
+        assert!(server.wait_readable(None).unwrap());
+        let msg = server.recv_deadline(deadline).unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+
+        let err = server.recv_deadline(deadline).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        let policy = RetryPolicy::new(Duration::from_secs(1));
+        let err = client
+            .request_deadline("ping", policy, deadline)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        // The `inproc` connection is established, which is checked before
+        // the deadline.
+        client.wait_connected_deadline(deadline).unwrap();
+    }
+
+    #[test]
+    fn test_request_deadline() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let policy = RetryPolicy::new(Duration::from_millis(100))
+            .add_max_retries(10)
+            .add_backoff(Duration::from_millis(10));
+        let start = Instant::now();
+        let err = client
+            .request_deadline("ping", policy, Duration::from_millis(250))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert!(start.elapsed() < Duration::from_millis(350));
+
+        // Only the attempts that fit within the deadline were sent.
+        let count = server.try_incoming().count();
+        assert!((2..=4).contains(&count));
+    }
+
+    #[test]
+    fn test_request_backoff_overflow() {
+        let addr = InprocAddr::new_unique();
+
+        let _server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        // Doubling the backoff would overflow after the first resend.
+        let policy = RetryPolicy::new(Duration::from_millis(10))
+            .add_max_retries(3)
+            .add_backoff(Duration::from_secs(u64::MAX));
+        let err = client
+            .request_deadline("ping", policy, Duration::from_millis(50))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    }
+
+    #[cfg(feature = "verbose-log")]
+    #[test]
+    fn test_verbose_log() {
+        use log::{LevelFilter, Log, Metadata, Record};
+        use std::sync::Mutex;
+
+        static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        struct Capture;
+
+        impl Log for Capture {
+            fn enabled(&self, metadata: &Metadata) -> bool {
+                metadata.target() == "libzmq::socket"
+            }
+
+            fn log(&self, record: &Record) {
+                if self.enabled(record.metadata()) {
+                    RECORDS.lock().unwrap().push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: Capture = Capture;
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+
+        let addr: Endpoint = InprocAddr::new_unique().into();
+        let client = ClientBuilder::new()
+            .connect(&addr)
+            .label("test-client")
+            .plain_credentials("user", "hunter2")
+            .build()
+            .unwrap();
+        let prefix = format!("socket {:p}: ", client.raw_socket().as_mut_ptr());
+        drop(client);
+
+        // Only keep the records of this client, from its creation to its
+        // close, since other tests create sockets concurrently.
+        let records: Vec<String> = RECORDS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|r| r.strip_prefix(&prefix))
+            .skip_while(|r| !r.starts_with("created"))
+            .map(ToOwned::to_owned)
+            .collect();
+        let end = records.iter().position(|r| r.starts_with("closed"));
+        let records = &records[..=end.unwrap()];
+
+        assert_eq!(records[0], "created Client socket");
+        assert!(records.contains(&"set PlainUsername to Some(\"user\")".into()));
+        assert!(records.contains(&"set PlainPassword to <redacted>".into()));
+        assert!(records.contains(&"labeled `test-client`".into()));
+        assert!(records.contains(&format!("connect `{}`", addr)));
+        assert!(records.iter().all(|r| !r.contains("hunter2")));
+        assert_eq!(
+            records.last().unwrap(),
+            "closed Client socket (label Some(\"test-client\"))"
+        );
+    }
+
+    #[test]
+    fn test_wait_connected() {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        // Find a free port, which is released once the server is dropped.
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let endpoint = server.last_endpoint().unwrap().unwrap();
+        drop(server);
+
+        let client = ClientBuilder::new().connect(&endpoint).build().unwrap();
+        assert!(!client.is_connected());
+        let err = client
+            .wait_connected(Some(Duration::from_millis(200)))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        let _server = ServerBuilder::new().bind(&endpoint).build().unwrap();
+        client.wait_connected(Some(Duration::from_secs(5))).unwrap();
+        assert!(client.is_connected());
+
+        client.disconnect(&endpoint).unwrap();
+        assert!(!client.is_connected());
+    }
+
+    #[test]
+    fn test_disconnect_hostname() {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let port = server.last_endpoint().unwrap().unwrap().port().unwrap();
+
+        // The monitor reports the resolved address of the connection.
+        let endpoint: TcpAddr =
+            format!("localhost:{}", port).try_into().unwrap();
+        let client = ClientBuilder::new().connect(&endpoint).build().unwrap();
+        client.wait_connected(Some(Duration::from_secs(5))).unwrap();
+
+        client.disconnect(&endpoint).unwrap();
+        assert!(!client.is_connected());
+    }
+
+    #[test]
+    fn test_reconnect_stop() {
+        let stop = ReconnectStop::CONN_REFUSED;
+        if version() < (4, 3, 3) {
+            let unsupported = ErrorKind::Unsupported {
+                msg: "reconnect stop requires libzmq 4.3.3",
+            };
+            let client = Client::new().unwrap();
+            let err = client.set_reconnect_stop(stop).unwrap_err();
+            assert_eq!(err.kind(), unsupported);
+            let err = client.reconnect_stop().unwrap_err();
+            assert_eq!(err.kind(), unsupported);
+
+            // Which makes the build fail as well.
+            let err = ClientBuilder::new().reconnect_stop(stop).build();
+            let err = err.unwrap_err();
+            assert_eq!(err.kind(), unsupported);
+            assert_eq!(
+                err.build_step(),
+                Some(BuildStep::Option {
+                    name: "reconnect_stop"
+                })
+            );
+            return;
+        }
+
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let endpoint = server.last_endpoint().unwrap().unwrap();
+        drop(server);
+
+        let retrying = Client::new().unwrap();
+        let client = ClientBuilder::new().reconnect_stop(stop).build().unwrap();
+        assert_eq!(client.reconnect_stop().unwrap(), stop);
+
+        let retried = sys::ZMQ_EVENT_CONNECT_RETRIED;
+        let closed = sys::ZMQ_EVENT_CLOSED;
+        let mut retrying_events =
+            monitor(retrying.raw_socket(), retried).unwrap();
+        let mut events =
+            monitor(client.raw_socket(), retried | closed).unwrap();
+        // `Client::connect` would replace these monitors with the one
+        // tracking the liveness of the connections.
+        retrying.raw_socket().connect(&endpoint).unwrap();
+        client.raw_socket().connect(&endpoint).unwrap();
+
+        // The refused connection is closed for good instead of retried.
+        let timeout = Duration::from_secs(1);
+        let (event, _) = recv_event(&mut events, timeout).unwrap();
+        assert_eq!(event, closed);
+
+        // The connection is retried every 100ms by default.
+        for _ in 0..3 {
+            let (event, _) = recv_event(&mut retrying_events, timeout).unwrap();
+            assert_eq!(event, retried);
+        }
+        assert_eq!(recv_event(&mut events, Duration::from_millis(300)), None);
+    }
+
+    #[test]
+    fn test_is_connected_inproc() {
+        let addr = InprocAddr::new_unique();
+        let client = Client::new().unwrap();
+        assert!(!client.is_connected());
+
+        client.connect(&addr).unwrap();
+        assert!(client.is_connected());
+        client.wait_connected(None).unwrap();
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_stats() {
+        let (client, server) = crate::test_util::client_server_pair();
+        #[cfg(feature = "stats-activity")]
+        assert_eq!(client.stats().last_activity(), None);
+
+        client.send("ping").unwrap();
+        client.send("ping!").unwrap();
+        let msg = server.recv_msg().unwrap();
+        server.recv_msg().unwrap();
+        let mut reply: Msg = "pong".into();
+        reply.set_routing_id(msg.routing_id().unwrap());
+        server.send(reply).unwrap();
+        client.recv_msg().unwrap();
+
+        let stats = client.stats();
+        assert_eq!(stats.msgs_sent(), 2);
+        assert_eq!(stats.bytes_sent(), 9);
+        assert_eq!(stats.msgs_received(), 1);
+        assert_eq!(stats.bytes_received(), 4);
+        assert_eq!(stats.total_send_failures(), 0);
+        #[cfg(feature = "stats-activity")]
+        assert!(stats.last_activity().is_some());
+
+        let stats = server.stats();
+        assert_eq!(stats.msgs_sent(), 1);
+        assert_eq!(stats.msgs_received(), 2);
+        assert_eq!(stats.bytes_received(), 9);
+
+        // A client without peer cannot queue any message.
+        let lonely = Client::new().unwrap();
+        let err = lonely.try_send("").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        let stats = lonely.stats();
+        assert_eq!(stats.send_failures(ErrorKind::WouldBlock), 1);
+        assert_eq!(stats.total_send_failures(), 1);
+        assert_eq!(stats.msgs_sent(), 0);
+
+        client.reset_stats();
+        let stats = client.stats();
+        assert_eq!(stats.msgs_sent(), 0);
+        assert_eq!(stats.bytes_received(), 0);
+        #[cfg(feature = "stats-activity")]
+        assert_eq!(stats.last_activity(), None);
+    }
+
+    #[test]
+    fn test_split_producer_consumer() {
+        let (client, server) = crate::test_util::client_server_pair();
+        let original = client.clone();
+        let (sender, receiver) = client.split();
+
+        let echo = thread::spawn(move || {
+            for _ in 0..16 {
+                let msg = server.recv_msg().unwrap();
+                server.send(msg).unwrap();
+            }
+        });
+        let producer = thread::spawn(move || {
+            for i in 0..16 {
+                sender.send(format!("{}", i)).unwrap();
+            }
+            sender
+        });
+        let consumer = thread::spawn(move || {
+            for i in 0..16 {
+                let msg = receiver.recv_msg().unwrap();
+                assert_eq!(msg.to_str().unwrap(), format!("{}", i));
+            }
+            receiver
+        });
+
+        let sender = producer.join().unwrap();
+        let receiver = consumer.join().unwrap();
+        echo.join().unwrap();
+
+        assert_eq!(sender.reunite(receiver).unwrap(), original);
+    }
+
+    #[test]
+    fn test_reunite_mismatched_halves() {
+        let (sender, _) = Client::new().unwrap().split();
+        let (_, receiver) = Client::new().unwrap().split();
+
+        let mut err = sender.reunite(receiver).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+
+        // The halves are given back.
+        let (sender, receiver) = err.take_content().unwrap();
+        assert!(receiver.reunite(sender).is_err());
+    }
 }
@@ -1,18 +1,21 @@
-use crate::{addr::Endpoint, auth::*, core::*, error::*, Ctx, GroupOwned};
+use crate::{
+    addr::Endpoint, auth::*, core::*, error::*, Ctx, Group, GroupOwned, Msg,
+};
 use libzmq_sys as sys;
 use sys::errno;
 
+use log::error;
 use serde::{Deserialize, Serialize};
 
 use std::{
-    ffi::{c_void, CString},
-    str,
+    ffi::c_void,
+    mem, str,
     sync::{Arc, Mutex},
 };
 
 fn join(socket_mut_ptr: *mut c_void, group: &GroupOwned) -> Result<(), Error> {
-    let c_str = CString::new(group.as_str()).unwrap();
-    let rc = unsafe { sys::zmq_join(socket_mut_ptr, c_str.as_ptr()) };
+    let rc =
+        unsafe { sys::zmq_join(socket_mut_ptr, group.as_c_str().as_ptr()) };
 
     if rc == -1 {
         let errno = unsafe { sys::zmq_errno() };
@@ -21,23 +24,19 @@ fn join(socket_mut_ptr: *mut c_void, group: &GroupOwned) -> Result<(), Error> {
                 errno::EINVAL => Error::new(ErrorKind::InvalidInput {
                     msg: "cannot join group twice",
                 }),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::EINTR => Error::new(ErrorKind::Interrupted),
-                errno::ENOTSOCK => panic!("invalid socket"),
-                errno::EMTHREAD => panic!("no i/o thread available"),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             }
         };
 
-        Err(err)
+        Err(err.with_errno(errno))
     } else {
         Ok(())
     }
 }
 
 fn leave(socket_mut_ptr: *mut c_void, group: &GroupOwned) -> Result<(), Error> {
-    let c_str = CString::new(group.as_str()).unwrap();
-    let rc = unsafe { sys::zmq_leave(socket_mut_ptr, c_str.as_ptr()) };
+    let rc =
+        unsafe { sys::zmq_leave(socket_mut_ptr, group.as_c_str().as_ptr()) };
 
     if rc == -1 {
         let errno = unsafe { sys::zmq_errno() };
@@ -46,15 +45,11 @@ fn leave(socket_mut_ptr: *mut c_void, group: &GroupOwned) -> Result<(), Error> {
                 errno::EINVAL => Error::new(ErrorKind::InvalidInput {
                     msg: "cannot leave a group that wasn't joined",
                 }),
-                errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-                errno::EINTR => Error::new(ErrorKind::Interrupted),
-                errno::ENOTSOCK => panic!("invalid socket"),
-                errno::EMTHREAD => panic!("no i/o thread available"),
-                _ => panic!(msg_from_errno(errno)),
+                _ => from_errno(errno),
             }
         };
 
-        Err(err)
+        Err(err.with_errno(errno))
     } else {
         Ok(())
     }
@@ -77,9 +72,9 @@ fn leave(socket_mut_ptr: *mut c_void, group: &GroupOwned) -> Result<(), Error> {
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, *};
 /// use std::{convert::TryInto, thread, time::Duration};
 ///
@@ -184,6 +179,69 @@ impl Dish {
     pub fn ctx(&self) -> &crate::Ctx {
         self.inner.ctx()
     }
+
+    /// Returns a snapshot of the socket's current configuration.
+    ///
+    /// The option values are read back from the socket, which is useful
+    /// to log its effective configuration. Since the socket does not
+    /// keep track of its endpoints, `connect` and `bind` are always `None`.
+    pub fn config(&self) -> Result<DishConfig, Error> {
+        let groups = self.joined();
+        let groups = if groups.is_empty() {
+            None
+        } else {
+            Some(groups)
+        };
+
+        Ok(DishConfig {
+            socket_config: SocketConfig::from_socket(self)?,
+            recv_config: RecvConfig::from_socket(self)?,
+            groups,
+            multicast_loop: Some(self.multicast_loop()?),
+        })
+    }
+
+    /// Returns a `DishBuilder` configured like the socket, but with no
+    /// endpoints.
+    ///
+    /// The secrets of the mechanism are left out. See
+    /// [`Client::to_builder`].
+    ///
+    /// [`Client::to_builder`]: struct.Client.html#method.to_builder
+    pub fn to_builder(&self) -> Result<DishBuilder, Error> {
+        let mut inner = self.config()?;
+        inner.socket_config_mut().redact_secrets();
+        Ok(DishBuilder { inner })
+    }
+
+    /// Same as [`to_builder`] except that the secrets of the mechanism are
+    /// carried over to the builder.
+    ///
+    /// [`to_builder`]: #method.to_builder
+    pub fn to_builder_with_secrets(&self) -> Result<DishBuilder, Error> {
+        Ok(DishBuilder {
+            inner: self.config()?,
+        })
+    }
+
+    /// Returns `true` if the `multicast_loop` option is set.
+    pub fn multicast_loop(&self) -> Result<bool, Error> {
+        self.inner.multicast_loop()
+    }
+
+    /// Sets whether the datagrams sent to a `udp` multicast group by the
+    /// sockets of the same host are received.
+    ///
+    /// This only affects the `udp` multicast endpoints and is ignored for
+    /// the others. It only applies to the endpoints that are bound or
+    /// connected afterwards.
+    ///
+    /// # Default value
+    /// `true`
+    pub fn set_multicast_loop(&self, enabled: bool) -> Result<(), Error> {
+        self.inner.set_multicast_loop(enabled)
+    }
+
     /// Joins the specified group(s).
     ///
     /// When any of the connection attempt fail, the `Error` will contain the position
@@ -201,15 +259,18 @@ impl Dish {
     ///
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
-    /// use libzmq::{prelude::*, Dish, Group};
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, group, Dish, Group};
     /// use std::convert::TryInto;
     ///
     /// let group: &Group = "some group".try_into()?;
     /// let dish = Dish::new()?;
     /// dish.join(group)?;
+    ///
+    /// // A literal group can also be validated at compile time.
+    /// dish.join(group!("other group"))?;
     /// #
     /// #     Ok(())
     /// # }
@@ -236,15 +297,71 @@ impl Dish {
         Ok(())
     }
 
+    /// Joins the specified groups, skipping those that are already joined.
+    ///
+    /// Unlike [`join`], this is idempotent which makes it suitable to
+    /// restore a list of groups previously saved via [`joined`].
+    ///
+    /// When any of the join attempt fail, the `Error` will contain the
+    /// number of groups that were newly joined before the failure.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, Dish, GroupOwned};
+    /// use std::convert::TryInto;
+    ///
+    /// let first: GroupOwned = "first group".try_into()?;
+    /// let second: GroupOwned = "second group".try_into()?;
+    ///
+    /// let dish = Dish::new()?;
+    /// dish.join(&first)?;
+    ///
+    /// // `first` is already joined so it is skipped.
+    /// dish.rejoin(&[first, second])?;
+    /// assert_eq!(dish.joined().len(), 2);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`join`]: #method.join
+    /// [`joined`]: #method.joined
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    pub fn rejoin(&self, groups: &[GroupOwned]) -> Result<(), Error<usize>> {
+        let mut count = 0;
+        let mut guard = self.groups.lock().unwrap();
+
+        for group in groups {
+            if guard.contains(group) {
+                continue;
+            }
+
+            join(self.raw_socket().as_mut_ptr(), group)
+                .map_err(|err| Error::with_content(err.kind(), count))?;
+
+            guard.push(group.to_owned());
+            count += 1;
+        }
+        Ok(())
+    }
+
     /// Returns a snapshot of the list of joined `Group`.
     ///
     /// The list might be modified by another thread after it is returned.
     ///
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{prelude::*, Dish, Group};
     /// use std::convert::TryInto;
     ///
@@ -280,9 +397,9 @@ impl Dish {
     ///
     /// # Example
     /// ```
-    /// # use failure::Error;
+    /// # use std::error::Error;
     /// #
-    /// # fn main() -> Result<(), Error> {
+    /// # fn main() -> Result<(), Box<dyn Error>> {
     /// use libzmq::{prelude::*, Dish, GroupOwned};
     /// use std::convert::TryInto;
     ///
@@ -321,6 +438,141 @@ impl Dish {
         }
         Ok(())
     }
+
+    /// Leave all the joined groups.
+    ///
+    /// When any of the leave attempt fail, the `Error` will contain the
+    /// number of groups that were left before the failure.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    pub fn leave_all(&self) -> Result<(), Error<usize>> {
+        let mut count = 0;
+        let mut guard = self.groups.lock().unwrap();
+
+        while let Some(group) = guard.last() {
+            leave(self.raw_socket().as_mut_ptr(), group)
+                .map_err(|err| Error::with_content(err.kind(), count))?;
+
+            guard.pop();
+            count += 1;
+        }
+        Ok(())
+    }
+
+    /// Joins the specified group until the returned [`Subscription`] is
+    /// dropped.
+    ///
+    /// This is the same as [`join`] except that the group is left when
+    /// the `Subscription` goes out of scope, including on early returns.
+    /// If the group was already left, for instance via [`leave_all`], the
+    /// drop does nothing.
+    ///
+    /// # Usage Contract
+    /// * The group cannot be already joined.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`InvalidInput`] (if group was already joined)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, group, Dish};
+    ///
+    /// let dish = Dish::new()?;
+    /// {
+    ///     let subscription = dish.join_scoped(group!("status"))?;
+    ///     assert_eq!(subscription.group(), "status");
+    ///     assert_eq!(dish.joined().len(), 1);
+    /// }
+    /// // The group was left when the subscription was dropped.
+    /// assert!(dish.joined().is_empty());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Subscription`]: struct.Subscription.html
+    /// [`join`]: #method.join
+    /// [`leave_all`]: #method.leave_all
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn join_scoped<G>(&self, group: G) -> Result<Subscription<'_>, Error>
+    where
+        G: Into<GroupOwned>,
+    {
+        let group = group.into();
+        self.join(group).map_err(Error::cast)?;
+
+        Ok(Subscription { dish: self, group })
+    }
+
+    /// Retreive a message from the inbound socket queue along with
+    /// the group it was sent to.
+    ///
+    /// This is a shorthand for calling [`recv_msg`] and then extracting
+    /// the message's [`group`].
+    ///
+    /// # Returned Error Variants
+    /// * [`WouldBlock`] (if `recv_timeout` expires)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::{convert::TryInto, thread, time::Duration};
+    ///
+    /// let addr: InprocAddr = InprocAddr::new_unique();
+    ///
+    /// let radio = RadioBuilder::new()
+    ///     .bind(&addr)
+    ///     .build()?;
+    ///
+    /// let a: &Group = "A".try_into()?;
+    ///
+    /// let dish = DishBuilder::new()
+    ///     .connect(addr)
+    ///     .join(a)
+    ///     .build()?;
+    ///
+    /// thread::spawn(move || loop {
+    ///     radio.transmit("A", "some msg").unwrap();
+    ///     thread::sleep(Duration::from_millis(1));
+    /// });
+    ///
+    /// let (group, msg) = dish.recv_from()?;
+    /// assert_eq!(group, a);
+    /// assert_eq!(msg.to_str()?, "some msg");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`recv_msg`]: prelude/trait.RecvMsg.html#method.recv_msg
+    /// [`group`]: struct.Msg.html#method.group
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    pub fn recv_from(&self) -> Result<(GroupOwned, Msg), Error> {
+        let msg = self.recv_msg()?;
+        // A message received by a `Dish` always has a group.
+        let group = msg.group().unwrap().to_owned();
+
+        Ok((group, msg))
+    }
 }
 
 impl PartialEq for Dish {
@@ -332,7 +584,7 @@ impl PartialEq for Dish {
 impl Eq for Dish {}
 
 impl GetRawSocket for Dish {
-    fn raw_socket(&self) -> &RawSocket {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
         &self.inner
     }
 }
@@ -343,6 +595,50 @@ impl RecvMsg for Dish {}
 unsafe impl Send for Dish {}
 unsafe impl Sync for Dish {}
 
+/// A group joined by a `Dish` that is left when dropped.
+///
+/// Returned by [`join_scoped`].
+///
+/// [`join_scoped`]: struct.Dish.html#method.join_scoped
+#[derive(Debug)]
+#[must_use = "the group is left as soon as the subscription is dropped"]
+pub struct Subscription<'a> {
+    dish: &'a Dish,
+    group: GroupOwned,
+}
+
+impl<'a> Subscription<'a> {
+    /// Returns the group of the subscription.
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    /// Consumes the subscription without leaving the group.
+    ///
+    /// The group then stays joined until it is explicitly left.
+    pub fn forget(self) {
+        mem::forget(self)
+    }
+}
+
+impl<'a> Drop for Subscription<'a> {
+    fn drop(&mut self) {
+        let mut guard = self.dish.groups.lock().unwrap();
+
+        // The group might have been left manually in the meantime.
+        if let Some(position) = guard.iter().position(|g| g == &self.group) {
+            match leave(self.dish.raw_socket().as_mut_ptr(), &self.group) {
+                Ok(()) => {
+                    guard.remove(position);
+                }
+                Err(err) => {
+                    error!("unable to leave group {}: {}", self.group, err)
+                }
+            }
+        }
+    }
+}
+
 /// A configuration for a `Dish`.
 ///
 /// Especially helpfull in config files.
@@ -355,6 +651,7 @@ pub struct DishConfig {
     socket_config: SocketConfig,
     recv_config: RecvConfig,
     groups: Option<Vec<GroupOwned>>,
+    multicast_loop: Option<bool>,
 }
 
 impl DishConfig {
@@ -388,9 +685,23 @@ impl DishConfig {
         self.groups = groups;
     }
 
+    /// Returns `true` if the `multicast_loop` option is set.
+    pub fn multicast_loop(&self) -> bool {
+        self.multicast_loop.unwrap_or(true)
+    }
+
+    /// Sets the `multicast_loop` option.
+    pub fn set_multicast_loop(&mut self, cond: bool) {
+        self.multicast_loop = Some(cond);
+    }
+
     pub fn apply(&self, dish: &Dish) -> Result<(), Error<usize>> {
         if let Some(ref groups) = self.groups {
-            dish.join(groups)?;
+            dish.rejoin(groups)?;
+        }
+        if let Some(enabled) = self.multicast_loop {
+            dish.set_multicast_loop(enabled)
+                .map_err(option_step("multicast_loop"))?;
         }
         self.recv_config.apply(dish).map_err(Error::cast)?;
         self.socket_config.apply(dish)?;
@@ -400,6 +711,7 @@ impl DishConfig {
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct FlatDishConfig {
     connect: Option<Vec<Endpoint>>,
     bind: Option<Vec<Endpoint>>,
@@ -408,7 +720,22 @@ struct FlatDishConfig {
     recv_high_water_mark: Quantity,
     recv_timeout: Period,
     groups: Option<Vec<GroupOwned>>,
+    multicast_loop: Option<bool>,
     mechanism: Option<Mechanism>,
+    zap_domain: Option<String>,
+    enforce_zap_domain: Option<bool>,
+    ipv6: Option<bool>,
+    max_msg_size: Option<i64>,
+    backlog: Option<i32>,
+    send_buffer: Option<i32>,
+    recv_buffer: Option<i32>,
+    vmci_buffer_size: Option<u64>,
+    vmci_buffer_min_size: Option<u64>,
+    vmci_buffer_max_size: Option<u64>,
+    vmci_connect_timeout: Option<Period>,
+    reconnect_stop: Option<ReconnectStop>,
+    label: Option<String>,
+    retry_interrupted: Option<bool>,
 }
 
 impl From<DishConfig> for FlatDishConfig {
@@ -421,9 +748,24 @@ impl From<DishConfig> for FlatDishConfig {
             heartbeat: socket_config.heartbeat,
             linger: socket_config.linger,
             mechanism: socket_config.mechanism,
+            zap_domain: socket_config.zap_domain,
+            enforce_zap_domain: socket_config.enforce_zap_domain,
+            ipv6: socket_config.ipv6,
+            max_msg_size: socket_config.max_msg_size,
+            backlog: socket_config.backlog,
+            send_buffer: socket_config.send_buffer,
+            recv_buffer: socket_config.recv_buffer,
+            vmci_buffer_size: socket_config.vmci_buffer_size,
+            vmci_buffer_min_size: socket_config.vmci_buffer_min_size,
+            vmci_buffer_max_size: socket_config.vmci_buffer_max_size,
+            vmci_connect_timeout: socket_config.vmci_connect_timeout,
+            reconnect_stop: socket_config.reconnect_stop,
+            label: socket_config.label,
+            retry_interrupted: socket_config.retry_interrupted,
             recv_high_water_mark: recv_config.recv_high_water_mark,
             recv_timeout: recv_config.recv_timeout,
             groups: config.groups,
+            multicast_loop: config.multicast_loop,
         }
     }
 }
@@ -436,6 +778,22 @@ impl From<FlatDishConfig> for DishConfig {
             heartbeat: flat.heartbeat,
             linger: flat.linger,
             mechanism: flat.mechanism,
+            mechanism_conflict: false,
+            mechanism_redacted: false,
+            zap_domain: flat.zap_domain,
+            enforce_zap_domain: flat.enforce_zap_domain,
+            ipv6: flat.ipv6,
+            max_msg_size: flat.max_msg_size,
+            backlog: flat.backlog,
+            send_buffer: flat.send_buffer,
+            recv_buffer: flat.recv_buffer,
+            vmci_buffer_size: flat.vmci_buffer_size,
+            vmci_buffer_min_size: flat.vmci_buffer_min_size,
+            vmci_buffer_max_size: flat.vmci_buffer_max_size,
+            vmci_connect_timeout: flat.vmci_connect_timeout,
+            reconnect_stop: flat.reconnect_stop,
+            label: flat.label,
+            retry_interrupted: flat.retry_interrupted,
         };
         let recv_config = RecvConfig {
             recv_high_water_mark: flat.recv_high_water_mark,
@@ -445,6 +803,7 @@ impl From<FlatDishConfig> for DishConfig {
             socket_config,
             recv_config,
             groups: flat.groups,
+            multicast_loop: flat.multicast_loop,
         }
     }
 }
@@ -496,13 +855,64 @@ impl DishBuilder {
         self.inner.with_ctx(ctx)
     }
 
+    /// Sets the `multicast_loop` option.
+    ///
+    /// See [`Dish::set_multicast_loop`].
+    ///
+    /// [`Dish::set_multicast_loop`]: struct.Dish.html#method.set_multicast_loop
+    pub fn multicast_loop(&mut self, enabled: bool) -> &mut Self {
+        self.inner.set_multicast_loop(enabled);
+        self
+    }
+
     pub fn join<I, G>(&mut self, groups: I) -> &mut Self
     where
         I: IntoIterator<Item = G>,
         G: Into<GroupOwned>,
     {
-        let groups: Vec<GroupOwned> = groups.into_iter().map(G::into).collect();
-        self.inner.set_groups(Some(groups));
+        self.groups(groups)
+    }
+
+    /// Specifies the groups that the `Dish` joins as part of `build`.
+    ///
+    /// Any group specified multiple times is only joined once, so that a
+    /// list of groups saved via [`joined`] can be used to restore the
+    /// subscriptions of a new `Dish`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    /// use std::convert::TryInto;
+    ///
+    /// let a: &Group = "group a".try_into()?;
+    /// let b: &Group = "group b".try_into()?;
+    ///
+    /// let dish = DishBuilder::new().groups(vec![a, b]).build()?;
+    /// let saved = dish.joined();
+    ///
+    /// let restored = DishBuilder::new().groups(&saved).build()?;
+    /// assert_eq!(restored.joined(), saved);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`joined`]: struct.Dish.html#method.joined
+    pub fn groups<I, G>(&mut self, groups: I) -> &mut Self
+    where
+        I: IntoIterator<Item = G>,
+        G: Into<GroupOwned>,
+    {
+        let mut unique: Vec<GroupOwned> = vec![];
+        for group in groups.into_iter().map(G::into) {
+            if !unique.contains(&group) {
+                unique.push(group);
+            }
+        }
+        self.inner.set_groups(Some(unique));
         self
     }
 }
@@ -544,6 +954,35 @@ mod test {
         assert_eq!(config, de);
     }
 
+    #[test]
+    fn test_ser_de_toml() {
+        use crate::InprocAddr;
+        use std::{convert::TryInto, time::Duration};
+
+        let addr: InprocAddr = "test".try_into().unwrap();
+        let group: GroupOwned = "group".try_into().unwrap();
+
+        let mut config = DishConfig::new();
+        config.set_connect(Some(&addr));
+        config.set_groups(Some(vec![group]));
+        config.set_recv_timeout(Period::Finite(Duration::from_millis(300)));
+        config.set_multicast_loop(false);
+
+        let toml = toml::to_string(&config).unwrap();
+        let de: DishConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config, de);
+    }
+
+    #[test]
+    fn test_multicast_loop() {
+        let dish = Dish::new().unwrap();
+        assert!(dish.multicast_loop().unwrap());
+
+        let dish = DishBuilder::new().multicast_loop(false).build().unwrap();
+        assert!(!dish.multicast_loop().unwrap());
+        assert!(!dish.config().unwrap().multicast_loop());
+    }
+
     #[test]
     fn test_dish() {
         use crate::{prelude::*, TcpAddr, *};
@@ -590,4 +1029,176 @@ mod test {
         let msg = dish.recv_msg().unwrap();
         assert_eq!(msg.group().unwrap(), a);
     }
+
+    #[test]
+    fn test_rejoin() {
+        use std::convert::TryInto;
+
+        let a: GroupOwned = "group a".try_into().unwrap();
+        let b: GroupOwned = "group b".try_into().unwrap();
+
+        let dish = Dish::new().unwrap();
+        dish.join(&a).unwrap();
+        let err = dish.join(&a).unwrap_err();
+        assert_eq!(err.content(), Some(&0));
+
+        dish.rejoin(&[a, b, a]).unwrap();
+        dish.rejoin(&[b]).unwrap();
+        assert_eq!(dish.joined(), vec![a, b]);
+    }
+
+    #[test]
+    fn test_restore_groups() {
+        use crate::{prelude::*, *};
+        use std::{
+            convert::TryInto,
+            sync::atomic::{AtomicBool, Ordering},
+            thread,
+            time::Duration,
+        };
+
+        let addr = InprocAddr::new_unique();
+        let radio = RadioBuilder::new().bind(&addr).build().unwrap();
+
+        let a: &Group = "group a".try_into().unwrap();
+        let b: &Group = "group b".try_into().unwrap();
+
+        // Checkpoint the subscriptions of a dish, then recreate it.
+        let saved = {
+            let dish = DishBuilder::new().join(vec![a, b]).build().unwrap();
+            dish.joined()
+        };
+
+        let dish = DishBuilder::new()
+            .connect(&addr)
+            .groups(&saved)
+            .build()
+            .unwrap();
+        assert_eq!(dish.joined(), saved);
+
+        // Restoring twice is harmless.
+        dish.rejoin(&saved).unwrap();
+        assert_eq!(dish.joined(), saved);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                let groups = ["group a", "group b", "group c"];
+                let mut count = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    let group = groups[count % groups.len()];
+                    radio.transmit(group, "").unwrap();
+                    count += 1;
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+
+        let mut received = vec![];
+        while received.len() < saved.len() {
+            let (group, _) = dish.recv_from().unwrap();
+            assert_ne!(group, "group c");
+            if !received.contains(&group) {
+                received.push(group);
+            }
+        }
+
+        stop.store(true, Ordering::Relaxed);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_join_scoped() {
+        use crate::{group, prelude::*};
+        use std::time::Duration;
+
+        let dish = Dish::new().unwrap();
+        let sub = dish.join_scoped(group!("a")).unwrap();
+
+        // Joining the same group twice is refused.
+        let err = dish.join_scoped(group!("a")).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::InvalidInput {
+                msg: "cannot join group twice"
+            }
+        );
+        assert_eq!(sub.group(), "a");
+        drop(sub);
+        assert!(dish.joined().is_empty());
+
+        // The drop is a no-op if the group was already left.
+        let _b = dish.join_scoped(group!("b")).unwrap();
+        dish.join(group!("c")).unwrap();
+        dish.leave_all().unwrap();
+        assert!(dish.joined().is_empty());
+        dish.join(group!("b")).unwrap();
+        dish.leave(group!("b")).unwrap();
+
+        // A forgotten subscription leaves the group joined.
+        dish.join_scoped(group!("d")).unwrap().forget();
+        assert_eq!(dish.joined(), vec![group!("d")]);
+
+        // The drop does not panic once the context is gone.
+        let ctx = Ctx::new();
+        let dish = DishBuilder::new()
+            .with_ctx(&ctx)
+            .recv_timeout(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let sub = dish.join_scoped(group!("e")).unwrap();
+        ctx.shutdown();
+        drop(sub);
+    }
+
+    #[test]
+    fn test_join_scoped_stops_messages() {
+        use crate::{group, prelude::*, *};
+        use std::time::Duration;
+
+        let addr = InprocAddr::new_unique();
+        let radio = RadioBuilder::new().bind(&addr).build().unwrap();
+        let dish = DishBuilder::new()
+            .connect(&addr)
+            .join(group!("sync"))
+            .recv_timeout(Duration::from_secs(1))
+            .build()
+            .unwrap();
+
+        let sub = dish.join_scoped(group!("scoped")).unwrap();
+        // Wait until the radio knows about the subscription.
+        loop {
+            radio.transmit(group!("scoped"), "").unwrap();
+            if let Ok(msg) = dish.try_recv_msg() {
+                if msg.group().unwrap() == group!("scoped") {
+                    break;
+                }
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        drop(sub);
+
+        // Messages sent before the radio processed the leave might still
+        // arrive, so we wait for a round without any.
+        for _ in 0..100 {
+            for _ in 0..3 {
+                radio.transmit(group!("scoped"), "").unwrap();
+            }
+            radio.transmit(group!("sync"), "").unwrap();
+
+            let mut scoped = 0;
+            loop {
+                let (group, _) = dish.recv_from().unwrap();
+                if group == group!("sync") {
+                    break;
+                }
+                scoped += 1;
+            }
+            if scoped == 0 {
+                return;
+            }
+        }
+        panic!("messages kept arriving after the subscription was dropped");
+    }
 }
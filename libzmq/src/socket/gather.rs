@@ -20,9 +20,9 @@ use std::{str, sync::Arc};
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, *};
 ///
 /// let addr_a = InprocAddr::new_unique();
@@ -108,6 +108,41 @@ impl Gather {
     pub fn ctx(&self) -> &crate::Ctx {
         self.inner.ctx()
     }
+
+    /// Returns a snapshot of the socket's current configuration.
+    ///
+    /// The option values are read back from the socket, which is useful
+    /// to log its effective configuration. Since the socket does not
+    /// keep track of its endpoints, `connect` and `bind` are always `None`.
+    pub fn config(&self) -> Result<GatherConfig, Error> {
+        Ok(GatherConfig {
+            socket_config: SocketConfig::from_socket(self)?,
+            recv_config: RecvConfig::from_socket(self)?,
+        })
+    }
+
+    /// Returns a `GatherBuilder` configured like the socket, but with no
+    /// endpoints.
+    ///
+    /// The secrets of the mechanism are left out. See
+    /// [`Client::to_builder`].
+    ///
+    /// [`Client::to_builder`]: struct.Client.html#method.to_builder
+    pub fn to_builder(&self) -> Result<GatherBuilder, Error> {
+        let mut inner = self.config()?;
+        inner.socket_config_mut().redact_secrets();
+        Ok(GatherBuilder { inner })
+    }
+
+    /// Same as [`to_builder`] except that the secrets of the mechanism are
+    /// carried over to the builder.
+    ///
+    /// [`to_builder`]: #method.to_builder
+    pub fn to_builder_with_secrets(&self) -> Result<GatherBuilder, Error> {
+        Ok(GatherBuilder {
+            inner: self.config()?,
+        })
+    }
 }
 
 impl PartialEq for Gather {
@@ -119,7 +154,7 @@ impl PartialEq for Gather {
 impl Eq for Gather {}
 
 impl GetRawSocket for Gather {
-    fn raw_socket(&self) -> &RawSocket {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
         &self.inner
     }
 }
@@ -171,6 +206,7 @@ impl GatherConfig {
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct FlatGatherConfig {
     connect: Option<Vec<Endpoint>>,
     bind: Option<Vec<Endpoint>>,
@@ -179,6 +215,20 @@ struct FlatGatherConfig {
     recv_high_water_mark: Quantity,
     recv_timeout: Period,
     mechanism: Option<Mechanism>,
+    zap_domain: Option<String>,
+    enforce_zap_domain: Option<bool>,
+    ipv6: Option<bool>,
+    max_msg_size: Option<i64>,
+    backlog: Option<i32>,
+    send_buffer: Option<i32>,
+    recv_buffer: Option<i32>,
+    vmci_buffer_size: Option<u64>,
+    vmci_buffer_min_size: Option<u64>,
+    vmci_buffer_max_size: Option<u64>,
+    vmci_connect_timeout: Option<Period>,
+    reconnect_stop: Option<ReconnectStop>,
+    label: Option<String>,
+    retry_interrupted: Option<bool>,
 }
 
 impl From<GatherConfig> for FlatGatherConfig {
@@ -191,6 +241,20 @@ impl From<GatherConfig> for FlatGatherConfig {
             heartbeat: socket_config.heartbeat,
             linger: socket_config.linger,
             mechanism: socket_config.mechanism,
+            zap_domain: socket_config.zap_domain,
+            enforce_zap_domain: socket_config.enforce_zap_domain,
+            ipv6: socket_config.ipv6,
+            max_msg_size: socket_config.max_msg_size,
+            backlog: socket_config.backlog,
+            send_buffer: socket_config.send_buffer,
+            recv_buffer: socket_config.recv_buffer,
+            vmci_buffer_size: socket_config.vmci_buffer_size,
+            vmci_buffer_min_size: socket_config.vmci_buffer_min_size,
+            vmci_buffer_max_size: socket_config.vmci_buffer_max_size,
+            vmci_connect_timeout: socket_config.vmci_connect_timeout,
+            reconnect_stop: socket_config.reconnect_stop,
+            label: socket_config.label,
+            retry_interrupted: socket_config.retry_interrupted,
             recv_high_water_mark: recv_config.recv_high_water_mark,
             recv_timeout: recv_config.recv_timeout,
         }
@@ -205,6 +269,22 @@ impl From<FlatGatherConfig> for GatherConfig {
             heartbeat: flat.heartbeat,
             linger: flat.linger,
             mechanism: flat.mechanism,
+            mechanism_conflict: false,
+            mechanism_redacted: false,
+            zap_domain: flat.zap_domain,
+            enforce_zap_domain: flat.enforce_zap_domain,
+            ipv6: flat.ipv6,
+            max_msg_size: flat.max_msg_size,
+            backlog: flat.backlog,
+            send_buffer: flat.send_buffer,
+            recv_buffer: flat.recv_buffer,
+            vmci_buffer_size: flat.vmci_buffer_size,
+            vmci_buffer_min_size: flat.vmci_buffer_min_size,
+            vmci_buffer_max_size: flat.vmci_buffer_max_size,
+            vmci_connect_timeout: flat.vmci_connect_timeout,
+            reconnect_stop: flat.reconnect_stop,
+            label: flat.label,
+            retry_interrupted: flat.retry_interrupted,
         };
         let recv_config = RecvConfig {
             recv_high_water_mark: flat.recv_high_water_mark,
@@ -5,42 +5,227 @@ mod dish;
 mod gather;
 mod radio;
 mod scatter;
+mod serve;
 mod server;
+mod session;
 
 pub use client::*;
 pub use dish::*;
 pub use gather::*;
 pub use radio::*;
 pub use scatter::*;
+pub use serve::*;
 pub use server::*;
+pub use session::*;
+
+pub(crate) use client::monitor;
+#[cfg(test)]
+pub(crate) use client::recv_event;
 
 use crate::{
-    core::{GetRawSocket, RawSocket},
-    Error,
+    core::{GetRawSocket, RawSocket, RecvMsg, SendMsg, Socket},
+    error::{Error, ErrorKind},
+    Ctx, Msg,
 };
 
 use serde::{Deserialize, Serialize};
 
+use std::{convert::TryFrom, sync::Arc};
+
 /// An enum containing all the socket types.
+///
+/// This allows sockets of distinct types to be stored in the same
+/// collection, polled together and dropped uniformly.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, *};
+///
+/// let sockets: Vec<SocketType> = vec![
+///     Server::new()?.into(),
+///     Dish::new()?.into(),
+/// ];
+///
+/// for socket in &sockets {
+///     // Methods shared by all sockets are available.
+///     assert!(socket.last_endpoint()?.is_none());
+/// }
+///
+/// // A `Dish` cannot send messages.
+/// let err = sockets[1].try_send("msg").unwrap_err();
+/// match err.kind() {
+///     ErrorKind::InvalidInput { .. } => (),
+///     _ => unreachable!(),
+/// }
+/// #
+/// #     Ok(())
+/// # }
+/// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SocketType {
     Client(Client),
     Server(Server),
     Radio(Radio),
     Dish(Dish),
+    Scatter(Scatter),
+    Gather(Gather),
+}
+
+impl SocketType {
+    /// Push a message into the outgoing socket queue.
+    ///
+    /// See [`SendMsg::send`].
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the socket type cannot send)
+    ///
+    /// As well as the errors returned by the underlying socket type.
+    ///
+    /// [`SendMsg::send`]: prelude/trait.SendMsg.html#method.send
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn send<M>(&self, msg: M) -> Result<(), Error<Msg>>
+    where
+        M: Into<Msg>,
+    {
+        match self {
+            SocketType::Client(client) => client.send(msg),
+            SocketType::Server(server) => server.send(msg),
+            SocketType::Radio(radio) => radio.send(msg),
+            SocketType::Scatter(scatter) => scatter.send(msg),
+            SocketType::Dish(_) | SocketType::Gather(_) => {
+                Err(cannot_send(msg.into()))
+            }
+        }
+    }
+
+    /// Try to push a message into the outgoing socket queue without blocking.
+    ///
+    /// See [`SendMsg::try_send`].
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the socket type cannot send)
+    ///
+    /// As well as the errors returned by the underlying socket type.
+    ///
+    /// [`SendMsg::try_send`]: prelude/trait.SendMsg.html#method.try_send
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn try_send<M>(&self, msg: M) -> Result<(), Error<Msg>>
+    where
+        M: Into<Msg>,
+    {
+        match self {
+            SocketType::Client(client) => client.try_send(msg),
+            SocketType::Server(server) => server.try_send(msg),
+            SocketType::Radio(radio) => radio.try_send(msg),
+            SocketType::Scatter(scatter) => scatter.try_send(msg),
+            SocketType::Dish(_) | SocketType::Gather(_) => {
+                Err(cannot_send(msg.into()))
+            }
+        }
+    }
+
+    /// Retreive a message from the inbound socket queue.
+    ///
+    /// See [`RecvMsg::recv_msg`].
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the socket type cannot receive)
+    ///
+    /// As well as the errors returned by the underlying socket type.
+    ///
+    /// [`RecvMsg::recv_msg`]: prelude/trait.RecvMsg.html#method.recv_msg
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn recv_msg(&self) -> Result<Msg, Error> {
+        match self {
+            SocketType::Client(client) => client.recv_msg(),
+            SocketType::Server(server) => server.recv_msg(),
+            SocketType::Dish(dish) => dish.recv_msg(),
+            SocketType::Gather(gather) => gather.recv_msg(),
+            SocketType::Radio(_) | SocketType::Scatter(_) => Err(cannot_recv()),
+        }
+    }
+
+    /// Try to retreive a message from the inbound socket queue without
+    /// blocking.
+    ///
+    /// See [`RecvMsg::try_recv_msg`].
+    ///
+    /// # Returned Error Variants
+    /// * [`InvalidInput`] (if the socket type cannot receive)
+    ///
+    /// As well as the errors returned by the underlying socket type.
+    ///
+    /// [`RecvMsg::try_recv_msg`]: prelude/trait.RecvMsg.html#method.try_recv_msg
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn try_recv_msg(&self) -> Result<Msg, Error> {
+        match self {
+            SocketType::Client(client) => client.try_recv_msg(),
+            SocketType::Server(server) => server.try_recv_msg(),
+            SocketType::Dish(dish) => dish.try_recv_msg(),
+            SocketType::Gather(gather) => gather.try_recv_msg(),
+            SocketType::Radio(_) | SocketType::Scatter(_) => Err(cannot_recv()),
+        }
+    }
+}
+
+fn cannot_send(msg: Msg) -> Error<Msg> {
+    Error::with_content(
+        ErrorKind::InvalidInput {
+            msg: "socket type cannot send",
+        },
+        msg,
+    )
+}
+
+fn cannot_recv() -> Error {
+    Error::new(ErrorKind::InvalidInput {
+        msg: "socket type cannot receive",
+    })
 }
 
 impl GetRawSocket for SocketType {
-    fn raw_socket(&self) -> &RawSocket {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
         match self {
             SocketType::Client(client) => client.raw_socket(),
             SocketType::Server(server) => server.raw_socket(),
             SocketType::Radio(radio) => radio.raw_socket(),
             SocketType::Dish(dish) => dish.raw_socket(),
+            SocketType::Scatter(scatter) => scatter.raw_socket(),
+            SocketType::Gather(gather) => gather.raw_socket(),
         }
     }
 }
 
+impl Socket for SocketType {}
+
+macro_rules! socket_type_from {
+    ($($socket:ident),*) => {
+        $(
+            impl From<$socket> for SocketType {
+                fn from(socket: $socket) -> Self {
+                    SocketType::$socket(socket)
+                }
+            }
+
+            impl TryFrom<SocketType> for $socket {
+                type Error = SocketType;
+
+                fn try_from(socket: SocketType) -> Result<Self, SocketType> {
+                    match socket {
+                        SocketType::$socket(socket) => Ok(socket),
+                        socket => Err(socket),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+socket_type_from!(Client, Server, Radio, Dish, Scatter, Gather);
+
 /// An enum containing all the socket config types.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -49,27 +234,127 @@ pub enum ConfigType {
     Server(ServerConfig),
     Radio(RadioConfig),
     Dish(DishConfig),
+    Scatter(ScatterConfig),
+    Gather(GatherConfig),
 }
 
 impl ConfigType {
     pub fn build(&self) -> Result<SocketType, Error<usize>> {
+        self.with_ctx(Ctx::global())
+    }
+
+    pub fn with_ctx<C>(&self, ctx: C) -> Result<SocketType, Error<usize>>
+    where
+        C: Into<Ctx>,
+    {
         match self {
             ConfigType::Client(config) => {
-                let client = config.build()?;
+                let client = config.with_ctx(ctx)?;
                 Ok(SocketType::Client(client))
             }
             ConfigType::Server(config) => {
-                let server = config.build()?;
+                let server = config.with_ctx(ctx)?;
                 Ok(SocketType::Server(server))
             }
             ConfigType::Radio(config) => {
-                let radio = config.build()?;
+                let radio = config.with_ctx(ctx)?;
                 Ok(SocketType::Radio(radio))
             }
             ConfigType::Dish(config) => {
-                let dish = config.build()?;
+                let dish = config.with_ctx(ctx)?;
                 Ok(SocketType::Dish(dish))
             }
+            ConfigType::Scatter(config) => {
+                let scatter = config.with_ctx(ctx)?;
+                Ok(SocketType::Scatter(scatter))
+            }
+            ConfigType::Gather(config) => {
+                let gather = config.with_ctx(ctx)?;
+                Ok(SocketType::Gather(gather))
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{poll::*, prelude::*, *};
+
+    use std::{
+        convert::TryInto,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn test_poll_socket_types() {
+        let server_addr = InprocAddr::new_unique();
+        let radio_addr = InprocAddr::new_unique();
+        let group: &Group = "group".try_into().unwrap();
+
+        let sockets: Vec<SocketType> = vec![
+            ServerBuilder::new()
+                .bind(&server_addr)
+                .build()
+                .unwrap()
+                .into(),
+            DishBuilder::new()
+                .connect(&radio_addr)
+                .join(group)
+                .build()
+                .unwrap()
+                .into(),
+        ];
+
+        let client = ClientBuilder::new().connect(server_addr).build().unwrap();
+        let radio = RadioBuilder::new().bind(radio_addr).build().unwrap();
+
+        let mut poller = Poller::new();
+        for (i, socket) in sockets.iter().enumerate() {
+            poller.add(socket, PollId(i), READABLE).unwrap();
+        }
+
+        client.send("client").unwrap();
+        // The group subscription propagates asynchronously.
+        let done = Arc::new(AtomicBool::new(false));
+        let sender = {
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                while !done.load(Ordering::SeqCst) {
+                    let mut msg: Msg = "radio".into();
+                    msg.set_group(group);
+                    radio.send(msg).unwrap();
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+
+        let mut events = Events::new();
+        let mut received = [false; 2];
+        while !received.iter().all(|r| *r) {
+            poller.block(&mut events, None).unwrap();
+            for event in &events {
+                let PollId(i) = event.id();
+                let msg = sockets[i].try_recv_msg().unwrap();
+                match sockets[i] {
+                    SocketType::Server(_) => {
+                        assert_eq!(msg.to_str().unwrap(), "client")
+                    }
+                    SocketType::Dish(_) => {
+                        assert_eq!(msg.to_str().unwrap(), "radio")
+                    }
+                    _ => unreachable!(),
+                }
+                received[i] = true;
+            }
+        }
+
+        done.store(true, Ordering::SeqCst);
+        sender.join().unwrap();
+    }
+}
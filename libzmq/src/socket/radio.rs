@@ -1,8 +1,8 @@
-use crate::{addr::Endpoint, auth::*, core::*, error::*, Ctx};
+use crate::{addr::Endpoint, auth::*, core::*, error::*, Ctx, GroupOwned, Msg};
 
 use serde::{Deserialize, Serialize};
 
-use std::sync::Arc;
+use std::{convert::TryInto, sync::Arc};
 
 /// A `Radio` socket is used by a publisher to distribute data to [`Dish`]
 /// sockets.
@@ -27,9 +27,9 @@ use std::sync::Arc;
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, *};
 /// use std::{convert::TryInto, thread, time::Duration};
 ///
@@ -132,6 +132,43 @@ impl Radio {
         self.inner.ctx()
     }
 
+    /// Returns a snapshot of the socket's current configuration.
+    ///
+    /// The option values are read back from the socket, which is useful
+    /// to log its effective configuration. Since the socket does not
+    /// keep track of its endpoints, `connect` and `bind` are always `None`.
+    pub fn config(&self) -> Result<RadioConfig, Error> {
+        Ok(RadioConfig {
+            socket_config: SocketConfig::from_socket(self)?,
+            send_config: SendConfig::from_socket(self)?,
+            no_drop: Some(self.no_drop()?),
+            multicast_loop: Some(self.multicast_loop()?),
+        })
+    }
+
+    /// Returns a `RadioBuilder` configured like the socket, but with no
+    /// endpoints.
+    ///
+    /// The secrets of the mechanism are left out. See
+    /// [`Client::to_builder`].
+    ///
+    /// [`Client::to_builder`]: struct.Client.html#method.to_builder
+    pub fn to_builder(&self) -> Result<RadioBuilder, Error> {
+        let mut inner = self.config()?;
+        inner.socket_config_mut().redact_secrets();
+        Ok(RadioBuilder { inner })
+    }
+
+    /// Same as [`to_builder`] except that the secrets of the mechanism are
+    /// carried over to the builder.
+    ///
+    /// [`to_builder`]: #method.to_builder
+    pub fn to_builder_with_secrets(&self) -> Result<RadioBuilder, Error> {
+        Ok(RadioBuilder {
+            inner: self.config()?,
+        })
+    }
+
     /// Returns `true` if the `no_drop` option is set.
     pub fn no_drop(&self) -> Result<bool, Error> {
         self.inner.no_drop()
@@ -148,10 +185,93 @@ impl Radio {
     pub fn set_no_drop(&self, enabled: bool) -> Result<(), Error> {
         self.inner.set_no_drop(enabled)
     }
+
+    /// Returns `true` if the `multicast_loop` option is set.
+    pub fn multicast_loop(&self) -> Result<bool, Error> {
+        self.inner.multicast_loop()
+    }
+
+    /// Sets whether the datagrams sent to a `udp` multicast group are
+    /// looped back to the sockets of the same host.
+    ///
+    /// This only affects the `udp` multicast endpoints and is ignored for
+    /// the others. It only applies to the endpoints that are bound or
+    /// connected afterwards.
+    ///
+    /// # Default value
+    /// `true`
+    pub fn set_multicast_loop(&self, enabled: bool) -> Result<(), Error> {
+        self.inner.set_multicast_loop(enabled)
+    }
+
+    /// Sets the group of the message and then pushes it to the socket.
+    ///
+    /// This is a shorthand for calling [`set_group`] followed by [`send`].
+    /// The group is validated before anything is sent, so a group that
+    /// exceeds [`MAX_GROUP_SIZE`] char is rejected with [`InvalidInput`].
+    ///
+    /// If the message could not be sent, it is returned as the content
+    /// of the `Error`.
+    ///
+    /// # Returned Error Variants
+    /// * [`WouldBlock`] (if `no_drop` is set and in mute state)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`InvalidInput`] (if the group is invalid)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let radio = Radio::new()?;
+    ///
+    /// // Without subscribers, the message is simply dropped.
+    /// radio.transmit("some group", "some msg")?;
+    ///
+    /// // But a group that exceeds `MAX_GROUP_SIZE` char is rejected.
+    /// let too_long = "a".repeat(MAX_GROUP_SIZE + 1);
+    /// let err = radio.transmit(too_long.as_str(), "msg").unwrap_err();
+    /// match err.kind() {
+    ///     ErrorKind::InvalidInput { .. } => (),
+    ///     _ => panic!("unexpected error"),
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_group`]: struct.Msg.html#method.set_group
+    /// [`send`]: prelude/trait.SendMsg.html#method.send
+    /// [`MAX_GROUP_SIZE`]: constant.MAX_GROUP_SIZE.html
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    /// [`InvalidInput`]: enum.ErrorKind.html#variant.InvalidInput
+    pub fn transmit<G, M>(&self, group: G, msg: M) -> Result<(), Error<Msg>>
+    where
+        G: TryInto<GroupOwned>,
+        M: Into<Msg>,
+        Error<Msg>: From<G::Error>,
+    {
+        let mut msg = msg.into();
+        let group = match group.try_into() {
+            Ok(group) => group,
+            Err(err) => {
+                let err: Error<Msg> = err.into();
+                return Err(Error::with_content(err.kind(), msg));
+            }
+        };
+
+        msg.set_group(group);
+        self.send(msg)
+    }
 }
 
 impl GetRawSocket for Radio {
-    fn raw_socket(&self) -> &RawSocket {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
         &self.inner
     }
 }
@@ -172,6 +292,7 @@ pub struct RadioConfig {
     socket_config: SocketConfig,
     send_config: SendConfig,
     no_drop: Option<bool>,
+    multicast_loop: Option<bool>,
 }
 
 impl RadioConfig {
@@ -203,9 +324,24 @@ impl RadioConfig {
         self.no_drop = Some(cond);
     }
 
+    /// Returns `true` if the `multicast_loop` option is set.
+    pub fn multicast_loop(&self) -> bool {
+        self.multicast_loop.unwrap_or(true)
+    }
+
+    /// Sets the `multicast_loop` option.
+    pub fn set_multicast_loop(&mut self, cond: bool) {
+        self.multicast_loop = Some(cond);
+    }
+
     pub fn apply(&self, radio: &Radio) -> Result<(), Error<usize>> {
         if let Some(enabled) = self.no_drop {
-            radio.set_no_drop(enabled).map_err(Error::cast)?;
+            radio.set_no_drop(enabled).map_err(option_step("no_drop"))?;
+        }
+        if let Some(enabled) = self.multicast_loop {
+            radio
+                .set_multicast_loop(enabled)
+                .map_err(option_step("multicast_loop"))?;
         }
         self.send_config.apply(radio).map_err(Error::cast)?;
         self.socket_config.apply(radio)?;
@@ -218,6 +354,7 @@ impl RadioConfig {
 // https://github.com/serde-rs/serde/issues/1346
 // Wish there was a better way.
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct FlatRadioConfig {
     connect: Option<Vec<Endpoint>>,
     bind: Option<Vec<Endpoint>>,
@@ -226,7 +363,22 @@ struct FlatRadioConfig {
     send_high_water_mark: Quantity,
     send_timeout: Period,
     no_drop: Option<bool>,
+    multicast_loop: Option<bool>,
     mechanism: Option<Mechanism>,
+    zap_domain: Option<String>,
+    enforce_zap_domain: Option<bool>,
+    ipv6: Option<bool>,
+    max_msg_size: Option<i64>,
+    backlog: Option<i32>,
+    send_buffer: Option<i32>,
+    recv_buffer: Option<i32>,
+    vmci_buffer_size: Option<u64>,
+    vmci_buffer_min_size: Option<u64>,
+    vmci_buffer_max_size: Option<u64>,
+    vmci_connect_timeout: Option<Period>,
+    reconnect_stop: Option<ReconnectStop>,
+    label: Option<String>,
+    retry_interrupted: Option<bool>,
 }
 
 impl From<RadioConfig> for FlatRadioConfig {
@@ -241,7 +393,22 @@ impl From<RadioConfig> for FlatRadioConfig {
             send_high_water_mark: send_config.send_high_water_mark,
             send_timeout: send_config.send_timeout,
             no_drop: config.no_drop,
+            multicast_loop: config.multicast_loop,
             mechanism: socket_config.mechanism,
+            zap_domain: socket_config.zap_domain,
+            enforce_zap_domain: socket_config.enforce_zap_domain,
+            ipv6: socket_config.ipv6,
+            max_msg_size: socket_config.max_msg_size,
+            backlog: socket_config.backlog,
+            send_buffer: socket_config.send_buffer,
+            recv_buffer: socket_config.recv_buffer,
+            vmci_buffer_size: socket_config.vmci_buffer_size,
+            vmci_buffer_min_size: socket_config.vmci_buffer_min_size,
+            vmci_buffer_max_size: socket_config.vmci_buffer_max_size,
+            vmci_connect_timeout: socket_config.vmci_connect_timeout,
+            reconnect_stop: socket_config.reconnect_stop,
+            label: socket_config.label,
+            retry_interrupted: socket_config.retry_interrupted,
         }
     }
 }
@@ -254,6 +421,22 @@ impl From<FlatRadioConfig> for RadioConfig {
             heartbeat: flat.heartbeat,
             linger: flat.linger,
             mechanism: flat.mechanism,
+            mechanism_conflict: false,
+            mechanism_redacted: false,
+            zap_domain: flat.zap_domain,
+            enforce_zap_domain: flat.enforce_zap_domain,
+            ipv6: flat.ipv6,
+            max_msg_size: flat.max_msg_size,
+            backlog: flat.backlog,
+            send_buffer: flat.send_buffer,
+            recv_buffer: flat.recv_buffer,
+            vmci_buffer_size: flat.vmci_buffer_size,
+            vmci_buffer_min_size: flat.vmci_buffer_min_size,
+            vmci_buffer_max_size: flat.vmci_buffer_max_size,
+            vmci_connect_timeout: flat.vmci_connect_timeout,
+            reconnect_stop: flat.reconnect_stop,
+            label: flat.label,
+            retry_interrupted: flat.retry_interrupted,
         };
         let send_config = SendConfig {
             send_high_water_mark: flat.send_high_water_mark,
@@ -263,6 +446,7 @@ impl From<FlatRadioConfig> for RadioConfig {
             socket_config,
             send_config,
             no_drop: flat.no_drop,
+            multicast_loop: flat.multicast_loop,
         }
     }
 }
@@ -309,6 +493,16 @@ impl RadioBuilder {
         self
     }
 
+    /// Sets the `multicast_loop` option.
+    ///
+    /// See [`Radio::set_multicast_loop`].
+    ///
+    /// [`Radio::set_multicast_loop`]: struct.Radio.html#method.set_multicast_loop
+    pub fn multicast_loop(&mut self, enabled: bool) -> &mut Self {
+        self.inner.set_multicast_loop(enabled);
+        self
+    }
+
     pub fn build(&self) -> Result<Radio, Error<usize>> {
         self.inner.build()
     }
@@ -357,4 +551,43 @@ mod test {
         let de: RadioConfig = ron::de::from_str(&ron).unwrap();
         assert_eq!(config, de);
     }
+
+    #[test]
+    fn test_ser_de_toml() {
+        use crate::InprocAddr;
+        use std::time::Duration;
+
+        let addr: InprocAddr = "test".try_into().unwrap();
+
+        let mut config = RadioConfig::new();
+        config.set_bind(Some(&addr));
+        config.set_heartbeat(Some(Heartbeat::new(Duration::from_secs(1))));
+        config.set_send_timeout(Period::Finite(Duration::from_millis(300)));
+        config.set_no_drop(true);
+
+        // A `toml::Value` emits the tables after the values, as TOML requires.
+        let toml = toml::Value::try_from(&config).unwrap().to_string();
+        let de: RadioConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config, de);
+    }
+
+    #[test]
+    fn test_readiness() {
+        // A radio drops messages when it has no subscribers so it
+        // never blocks.
+        let radio = Radio::new().unwrap();
+        let readiness = radio.readiness().unwrap();
+        assert!(readiness.is_writable());
+        assert!(!readiness.is_readable());
+    }
+
+    #[test]
+    fn test_multicast_loop() {
+        let radio = Radio::new().unwrap();
+        assert!(radio.multicast_loop().unwrap());
+
+        let radio = RadioBuilder::new().multicast_loop(false).build().unwrap();
+        assert!(!radio.multicast_loop().unwrap());
+        assert!(!radio.config().unwrap().multicast_loop());
+    }
 }
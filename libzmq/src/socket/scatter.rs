@@ -20,9 +20,9 @@ use std::{str, sync::Arc};
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, *};
 /// use std::time::Duration;
 ///
@@ -99,6 +99,41 @@ impl Scatter {
     pub fn ctx(&self) -> &crate::Ctx {
         self.inner.ctx()
     }
+
+    /// Returns a snapshot of the socket's current configuration.
+    ///
+    /// The option values are read back from the socket, which is useful
+    /// to log its effective configuration. Since the socket does not
+    /// keep track of its endpoints, `connect` and `bind` are always `None`.
+    pub fn config(&self) -> Result<ScatterConfig, Error> {
+        Ok(ScatterConfig {
+            socket_config: SocketConfig::from_socket(self)?,
+            send_config: SendConfig::from_socket(self)?,
+        })
+    }
+
+    /// Returns a `ScatterBuilder` configured like the socket, but with no
+    /// endpoints.
+    ///
+    /// The secrets of the mechanism are left out. See
+    /// [`Client::to_builder`].
+    ///
+    /// [`Client::to_builder`]: struct.Client.html#method.to_builder
+    pub fn to_builder(&self) -> Result<ScatterBuilder, Error> {
+        let mut inner = self.config()?;
+        inner.socket_config_mut().redact_secrets();
+        Ok(ScatterBuilder { inner })
+    }
+
+    /// Same as [`to_builder`] except that the secrets of the mechanism are
+    /// carried over to the builder.
+    ///
+    /// [`to_builder`]: #method.to_builder
+    pub fn to_builder_with_secrets(&self) -> Result<ScatterBuilder, Error> {
+        Ok(ScatterBuilder {
+            inner: self.config()?,
+        })
+    }
 }
 
 impl PartialEq for Scatter {
@@ -110,7 +145,7 @@ impl PartialEq for Scatter {
 impl Eq for Scatter {}
 
 impl GetRawSocket for Scatter {
-    fn raw_socket(&self) -> &RawSocket {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
         &self.inner
     }
 }
@@ -162,6 +197,7 @@ impl ScatterConfig {
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct FlatScatterConfig {
     connect: Option<Vec<Endpoint>>,
     bind: Option<Vec<Endpoint>>,
@@ -170,6 +206,20 @@ struct FlatScatterConfig {
     send_high_water_mark: Quantity,
     send_timeout: Period,
     mechanism: Option<Mechanism>,
+    zap_domain: Option<String>,
+    enforce_zap_domain: Option<bool>,
+    ipv6: Option<bool>,
+    max_msg_size: Option<i64>,
+    backlog: Option<i32>,
+    send_buffer: Option<i32>,
+    recv_buffer: Option<i32>,
+    vmci_buffer_size: Option<u64>,
+    vmci_buffer_min_size: Option<u64>,
+    vmci_buffer_max_size: Option<u64>,
+    vmci_connect_timeout: Option<Period>,
+    reconnect_stop: Option<ReconnectStop>,
+    label: Option<String>,
+    retry_interrupted: Option<bool>,
 }
 
 impl From<ScatterConfig> for FlatScatterConfig {
@@ -182,6 +232,20 @@ impl From<ScatterConfig> for FlatScatterConfig {
             heartbeat: socket_config.heartbeat,
             linger: socket_config.linger,
             mechanism: socket_config.mechanism,
+            zap_domain: socket_config.zap_domain,
+            enforce_zap_domain: socket_config.enforce_zap_domain,
+            ipv6: socket_config.ipv6,
+            max_msg_size: socket_config.max_msg_size,
+            backlog: socket_config.backlog,
+            send_buffer: socket_config.send_buffer,
+            recv_buffer: socket_config.recv_buffer,
+            vmci_buffer_size: socket_config.vmci_buffer_size,
+            vmci_buffer_min_size: socket_config.vmci_buffer_min_size,
+            vmci_buffer_max_size: socket_config.vmci_buffer_max_size,
+            vmci_connect_timeout: socket_config.vmci_connect_timeout,
+            reconnect_stop: socket_config.reconnect_stop,
+            label: socket_config.label,
+            retry_interrupted: socket_config.retry_interrupted,
             send_high_water_mark: send_config.send_high_water_mark,
             send_timeout: send_config.send_timeout,
         }
@@ -196,6 +260,22 @@ impl From<FlatScatterConfig> for ScatterConfig {
             heartbeat: flat.heartbeat,
             linger: flat.linger,
             mechanism: flat.mechanism,
+            mechanism_conflict: false,
+            mechanism_redacted: false,
+            zap_domain: flat.zap_domain,
+            enforce_zap_domain: flat.enforce_zap_domain,
+            ipv6: flat.ipv6,
+            max_msg_size: flat.max_msg_size,
+            backlog: flat.backlog,
+            send_buffer: flat.send_buffer,
+            recv_buffer: flat.recv_buffer,
+            vmci_buffer_size: flat.vmci_buffer_size,
+            vmci_buffer_min_size: flat.vmci_buffer_min_size,
+            vmci_buffer_max_size: flat.vmci_buffer_max_size,
+            vmci_connect_timeout: flat.vmci_connect_timeout,
+            reconnect_stop: flat.reconnect_stop,
+            label: flat.label,
+            retry_interrupted: flat.retry_interrupted,
         };
         let send_config = SendConfig {
             send_high_water_mark: flat.send_high_water_mark,
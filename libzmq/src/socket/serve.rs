@@ -0,0 +1,428 @@
+use crate::{
+    core::*, error::*, poll::*, shutdown::ShutdownToken, Msg, RoutingId, Server,
+};
+
+use log::error;
+
+use std::{
+    str::Utf8Error,
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+const SOCKET: PollId = PollId(0);
+const WAKER: PollId = PollId(1);
+
+/// The error returned by the handler of [`Server::serve`].
+///
+/// [`Server::serve`]: struct.Server.html#method.serve
+pub type HandlerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A request received by [`Server::serve`].
+///
+/// [`Server::serve`]: struct.Server.html#method.serve
+#[derive(Debug)]
+pub struct Request {
+    msg: Msg,
+    id: RoutingId,
+}
+
+impl Request {
+    /// Returns the routing id of the client that sent the request.
+    pub fn routing_id(&self) -> RoutingId {
+        self.id
+    }
+
+    /// Returns a reference to the message of the request.
+    pub fn msg(&self) -> &Msg {
+        &self.msg
+    }
+
+    /// Returns the content of the request as bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.msg.as_bytes()
+    }
+
+    /// Returns the content of the request as a `str`.
+    ///
+    /// See [`Msg::to_str`].
+    ///
+    /// [`Msg::to_str`]: struct.Msg.html#method.to_str
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        self.msg.to_str()
+    }
+
+    /// Returns a metadata property of the request, such as `Peer-Address`.
+    ///
+    /// See [`Msg::property`].
+    ///
+    /// [`Msg::property`]: struct.Msg.html#method.property
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.msg.property(name)
+    }
+
+    /// Returns the user id authenticated for the client, if any.
+    ///
+    /// See [`Msg::user_id`].
+    ///
+    /// [`Msg::user_id`]: struct.Msg.html#method.user_id
+    pub fn user_id(&self) -> Option<&str> {
+        self.msg.user_id()
+    }
+
+    /// Consumes the request, returning its message.
+    pub fn into_msg(self) -> Msg {
+        self.msg
+    }
+}
+
+impl Server {
+    /// Runs the request loop of the server until the shutdown is initiated.
+    ///
+    /// Each received request is passed to the handler. If it returns a
+    /// reply, the reply is sent back to the client that sent the request.
+    /// The errors returned by the handler, as well as the failures to send
+    /// a reply, do not stop the loop and are logged. Use [`serve_with`] to
+    /// handle them otherwise.
+    ///
+    /// The loop polls the socket along with a [`Waker`] registered to the
+    /// [`Shutdown`], so that it exits promptly once the shutdown is
+    /// initiated, even if the socket belongs to another context.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`] (if the shutdown was not initiated)
+    /// * [`SocketLimit`]
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # #[cfg(feature = "test-util")]
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{
+    ///     prelude::*, shutdown::Shutdown, test_util::client_server_pair, *,
+    /// };
+    /// use std::{thread, time::Duration};
+    ///
+    /// let (client, server) = client_server_pair();
+    ///
+    /// let shutdown = Shutdown::new(Ctx::new());
+    /// let token = shutdown.token("echo");
+    /// let handle = thread::spawn(move || {
+    ///     server.serve(|request| Ok(Some(request.into_msg())), &token)
+    /// });
+    ///
+    /// client.send("ping")?;
+    /// assert_eq!(client.recv_msg()?.to_str()?, "ping");
+    ///
+    /// shutdown.initiate(Duration::from_secs(1))?;
+    /// handle.join().unwrap()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`serve_with`]: #method.serve_with
+    /// [`Waker`]: poll/struct.Waker.html
+    /// [`Shutdown`]: shutdown/struct.Shutdown.html
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`SocketLimit`]: enum.ErrorKind.html#variant.SocketLimit
+    pub fn serve<F>(
+        &self,
+        handler: F,
+        shutdown: &ShutdownToken,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Request) -> Result<Option<Msg>, HandlerError>,
+    {
+        self.serve_with(handler, log_error, shutdown)
+    }
+
+    /// Same as [`serve`] except that the errors of the requests are passed
+    /// to `on_error` along with the routing id of their client.
+    ///
+    /// A failure to send a reply is reported by its [`ErrorKind`]. The
+    /// replies are sent without blocking, so a reply to a client in mute
+    /// state is dropped and reported as [`WouldBlock`].
+    ///
+    /// [`serve`]: #method.serve
+    /// [`ErrorKind`]: enum.ErrorKind.html
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    pub fn serve_with<F, E>(
+        &self,
+        mut handler: F,
+        mut on_error: E,
+        shutdown: &ShutdownToken,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Request) -> Result<Option<Msg>, HandlerError>,
+        E: FnMut(RoutingId, HandlerError),
+    {
+        if shutdown.is_initiated() {
+            return Ok(());
+        }
+
+        let mut poller = Poller::new();
+        poller.set_retry_interrupted(true);
+        poller.add(self, SOCKET, READABLE)?;
+        shutdown.add_waker(poller.add_waker(WAKER)?);
+
+        let mut events = Events::new();
+        loop {
+            if shutdown.is_initiated() {
+                return Ok(());
+            }
+            if let Err(err) = poller.block(&mut events, None) {
+                return stopped(err, shutdown);
+            }
+
+            // Handle the pending requests, checking for the shutdown in
+            // between.
+            while !shutdown.is_initiated() {
+                let msg = match self.try_recv_msg() {
+                    Ok(msg) => msg,
+                    Err(err) => match err.kind() {
+                        ErrorKind::WouldBlock => break,
+                        ErrorKind::Interrupted => continue,
+                        _ => return stopped(err, shutdown),
+                    },
+                };
+                // A message received by a `Server` always has a routing id.
+                let id = msg.routing_id().unwrap();
+
+                match handler(Request { msg, id }) {
+                    Ok(Some(mut reply)) => {
+                        reply.set_routing_id(id);
+                        if let Err(err) = self.try_send(reply) {
+                            match err.kind() {
+                                ErrorKind::CtxTerminated => {
+                                    return stopped(err.cast(), shutdown)
+                                }
+                                kind => on_error(id, Box::new(kind)),
+                            }
+                        }
+                    }
+                    Ok(None) => (),
+                    Err(err) => on_error(id, err),
+                }
+            }
+        }
+    }
+
+    /// Runs the request loop of [`serve`] in `workers` threads sharing the
+    /// server, until the shutdown is initiated.
+    ///
+    /// Each thread calls `factory` with its index to create its handler.
+    /// The token is shared by the threads and is dropped once the last one
+    /// exits, so that the [`Shutdown`] waits for all of them.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # #[cfg(feature = "test-util")]
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{
+    ///     prelude::*, shutdown::Shutdown, test_util::client_server_pair, *,
+    /// };
+    /// use std::time::Duration;
+    ///
+    /// let (client, server) = client_server_pair();
+    ///
+    /// let shutdown = Shutdown::new(Ctx::new());
+    /// let handles = server.serve_threaded(
+    ///     4,
+    ///     |_| |request: Request| Ok(Some(request.into_msg())),
+    ///     shutdown.token("echo"),
+    /// );
+    ///
+    /// client.send("ping")?;
+    /// assert_eq!(client.recv_msg()?.to_str()?, "ping");
+    ///
+    /// shutdown.initiate(Duration::from_secs(1))?;
+    /// for handle in handles {
+    ///     handle.join().unwrap()?;
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`serve`]: #method.serve
+    /// [`Shutdown`]: shutdown/struct.Shutdown.html
+    pub fn serve_threaded<G, F>(
+        &self,
+        workers: usize,
+        mut factory: G,
+        shutdown: ShutdownToken,
+    ) -> Vec<JoinHandle<Result<(), Error>>>
+    where
+        G: FnMut(usize) -> F,
+        F: FnMut(Request) -> Result<Option<Msg>, HandlerError> + Send + 'static,
+    {
+        let shutdown = Arc::new(shutdown);
+
+        (0..workers)
+            .map(|i| {
+                let server = self.clone();
+                let handler = factory(i);
+                let shutdown = Arc::clone(&shutdown);
+
+                thread::Builder::new()
+                    .name(format!("libzmq-serve-{}", i))
+                    .spawn(move || server.serve(handler, &shutdown))
+                    .unwrap()
+            })
+            .collect()
+    }
+}
+
+// A terminated context only ends the loop gracefully if the shutdown was
+// initiated.
+fn stopped(err: Error, shutdown: &ShutdownToken) -> Result<(), Error> {
+    if err.kind() == ErrorKind::CtxTerminated && shutdown.is_initiated() {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
+
+fn log_error(id: RoutingId, err: HandlerError) {
+    error!("request from {:?} failed: {}", id, err);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, shutdown::Shutdown, test_util::*, *};
+
+    use std::{
+        sync::mpsc,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn test_serve_echo() {
+        let (client, server) = client_server_pair();
+        let shutdown = Shutdown::new(Ctx::new());
+        let token = shutdown.token("echo");
+
+        let handle = thread::spawn(move || {
+            server.serve(|request| Ok(Some(request.into_msg())), &token)
+        });
+
+        for i in 0..4 {
+            client.send(format!("{}", i)).unwrap();
+            let msg = client.recv_msg().unwrap();
+            assert_eq!(msg.to_str().unwrap(), format!("{}", i));
+        }
+
+        shutdown.initiate(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_serve_handler_error() {
+        let (client, server) = client_server_pair();
+        let shutdown = Shutdown::new(Ctx::new());
+        let token = shutdown.token("fallible");
+        let (sender, receiver) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let handler =
+                |request: Request| -> Result<Option<Msg>, HandlerError> {
+                    match request.to_str()? {
+                        "bad" => Err("bad request".into()),
+                        _ => Ok(Some(request.into_msg())),
+                    }
+                };
+            let on_error = |id, err: HandlerError| {
+                sender.send((id, err.to_string())).unwrap();
+            };
+            server.serve_with(handler, on_error, &token)
+        });
+
+        client.send("bad").unwrap();
+        client.send("good").unwrap();
+        let msg = client.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "good");
+
+        let (_, err) = receiver.recv().unwrap();
+        assert_eq!(err, "bad request");
+        assert!(receiver.try_recv().is_err());
+
+        shutdown.initiate(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_serve_reply_mute() {
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .send_high_water_mark(1)
+            .build()
+            .unwrap();
+        let client = ClientBuilder::new()
+            .connect(&addr)
+            .recv_high_water_mark(1)
+            .build()
+            .unwrap();
+
+        let shutdown = Shutdown::new(Ctx::new());
+        let token = shutdown.token("mute");
+        let (sender, receiver) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let on_error = |_, err: HandlerError| {
+                let kind = *err.downcast::<ErrorKind>().unwrap();
+                sender.send(kind).unwrap();
+            };
+            server.serve_with(
+                |request| Ok(Some(request.into_msg())),
+                on_error,
+                &token,
+            )
+        });
+
+        // The client never reads its replies, so it ends up in mute state
+        // instead of blocking the loop.
+        for _ in 0..100 {
+            client.send("").unwrap();
+        }
+        let kind = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(kind, ErrorKind::WouldBlock);
+
+        shutdown.initiate(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_serve_threaded_shutdown() {
+        let (client, server) = client_server_pair();
+        let shutdown = Shutdown::new(Ctx::new());
+
+        let handles = server.serve_threaded(
+            4,
+            |_| |_: Request| Ok(Some("pong".into())),
+            shutdown.token("workers"),
+        );
+
+        client.send("ping").unwrap();
+        client.recv_msg().unwrap();
+
+        // The workers are idle, blocked in their poller.
+        let budget = Duration::from_secs(5);
+        let start = Instant::now();
+        shutdown.initiate(budget).unwrap();
+        assert!(start.elapsed() < budget);
+
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+    }
+}
@@ -1,8 +1,15 @@
-use crate::{addr::Endpoint, auth::*, core::*, error::*, Ctx};
+use crate::{addr::Endpoint, auth::*, core::*, error::*, Ctx, Msg, RoutingId};
 
 use serde::{Deserialize, Serialize};
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 /// A `Server` socket is a socket used for advanced request-reply messaging.
 ///
@@ -33,29 +40,25 @@ use std::sync::Arc;
 ///
 /// # Example
 /// ```
-/// # use failure::Error;
+/// # use std::error::Error;
 /// #
-/// # fn main() -> Result<(), Error> {
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, *};
-/// use std::convert::TryInto;
 ///
-/// let addr: TcpAddr = "127.0.0.1:*".try_into()?;
+/// let addr = InprocAddr::new_unique();
 ///
 /// let server = ServerBuilder::new()
-///     .bind(addr)
+///     .bind(&addr)
 ///     .build()?;
 ///
-/// let bound = server.last_endpoint()?;
-///
 /// let client = ClientBuilder::new()
-///     .connect(bound)
+///     .connect(addr)
 ///     .build()?;
 ///
 /// // The client initiates the conversation so it is assigned a `routing_id`.
 /// client.send("request")?;
-/// let msg = server.recv_msg()?;
+/// let (routing_id, msg) = server.recv_from()?;
 /// assert_eq!("request", msg.to_str()?);
-/// let routing_id = msg.routing_id().unwrap();
 ///
 /// // Using this `routing_id`, we can now route as many replies as we
 /// // want to the client.
@@ -83,9 +86,14 @@ use std::sync::Arc;
 /// [`routing_id`]: struct.Msg.html#method.routing_id
 /// [`set_routing_id`]: struct.Msg.html#method.set_routing_id
 /// [`HostUnreachable`]: enum.ErrorKind.html#variant.host-unreachable
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct Server {
     inner: Arc<RawSocket>,
+    // The routing ids of the known peers, if they are tracked.
+    registry: Arc<Mutex<Option<HashSet<RoutingId>>>>,
+    // Whether the registry is enabled, which spares the lock to the
+    // messages of an untracked server.
+    tracked: Arc<AtomicBool>,
 }
 
 impl Server {
@@ -101,7 +109,11 @@ impl Server {
     pub fn new() -> Result<Self, Error> {
         let inner = Arc::new(RawSocket::new(RawSocketType::Server)?);
 
-        Ok(Self { inner })
+        Ok(Self {
+            inner,
+            registry: Arc::default(),
+            tracked: Arc::default(),
+        })
     }
 
     /// Create a `Server` socket from a specific context.
@@ -118,24 +130,437 @@ impl Server {
     {
         let inner = Arc::new(RawSocket::with_ctx(RawSocketType::Server, ctx)?);
 
-        Ok(Self { inner })
+        Ok(Self {
+            inner,
+            registry: Arc::default(),
+            tracked: Arc::default(),
+        })
     }
 
     /// Returns a reference to the context of the socket.
     pub fn ctx(&self) -> &crate::Ctx {
         self.inner.ctx()
     }
+
+    /// Returns a snapshot of the socket's current configuration.
+    ///
+    /// The option values are read back from the socket, which is useful
+    /// to log its effective configuration. Since the socket does not
+    /// keep track of its endpoints, `connect` and `bind` are always `None`.
+    pub fn config(&self) -> Result<ServerConfig, Error> {
+        Ok(ServerConfig {
+            socket_config: SocketConfig::from_socket(self)?,
+            send_config: SendConfig::from_socket(self)?,
+            recv_config: RecvConfig::from_socket(self)?,
+            track_routing_ids: Some(self.track_routing_ids()),
+        })
+    }
+
+    /// Returns a `ServerBuilder` configured like the socket, but with no
+    /// endpoints.
+    ///
+    /// The secrets of the mechanism are left out. See
+    /// [`Client::to_builder`].
+    ///
+    /// [`Client::to_builder`]: struct.Client.html#method.to_builder
+    pub fn to_builder(&self) -> Result<ServerBuilder, Error> {
+        let mut inner = self.config()?;
+        inner.socket_config_mut().redact_secrets();
+        Ok(ServerBuilder { inner })
+    }
+
+    /// Same as [`to_builder`] except that the secrets of the mechanism are
+    /// carried over to the builder.
+    ///
+    /// [`to_builder`]: #method.to_builder
+    pub fn to_builder_with_secrets(&self) -> Result<ServerBuilder, Error> {
+        Ok(ServerBuilder {
+            inner: self.config()?,
+        })
+    }
+
+    /// Returns `true` if the routing ids of the peers are tracked.
+    pub fn track_routing_ids(&self) -> bool {
+        self.registry.lock().unwrap().is_some()
+    }
+
+    /// Specifies whether the routing ids of the peers are tracked.
+    ///
+    /// When enabled, the routing id of each received message is recorded.
+    /// A routing id is forgotten once a message sent to it fails with
+    /// [`HostUnreachable`]. This allows to [`broadcast`] messages to all
+    /// the known peers.
+    ///
+    /// Since *libzmq* does not report the routing id of a peer that
+    /// disconnects, a disconnected peer is not forgotten until a message
+    /// is sent to it. Until then, its stale routing id is still returned by
+    /// [`routing_ids`] and the next [`broadcast`] reports it as failed.
+    ///
+    /// Disabling the option forgets all the recorded routing ids.
+    ///
+    /// # Default Value
+    /// `false`
+    ///
+    /// [`HostUnreachable`]: enum.ErrorKind.html#variant.HostUnreachable
+    /// [`broadcast`]: #method.broadcast
+    /// [`routing_ids`]: #method.routing_ids
+    pub fn set_track_routing_ids(&self, enabled: bool) {
+        let mut guard = self.registry.lock().unwrap();
+        if !enabled {
+            *guard = None;
+        } else if guard.is_none() {
+            *guard = Some(HashSet::new());
+        }
+        self.tracked.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns a snapshot of the tracked routing ids.
+    ///
+    /// This can include the routing ids of the peers that disconnected,
+    /// see [`set_track_routing_ids`]. Returns an empty list if the routing
+    /// ids are not tracked.
+    ///
+    /// [`set_track_routing_ids`]: #method.set_track_routing_ids
+    pub fn routing_ids(&self) -> Vec<RoutingId> {
+        match *self.registry.lock().unwrap() {
+            Some(ref registry) => registry.iter().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    /// Sends a copy of the message to every tracked peer.
+    ///
+    /// The peers that cannot be reached are forgotten and reported as
+    /// failures, along with any other error. Nothing is sent if the routing
+    /// ids are not [`tracked`].
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, *};
+    ///
+    /// let addr = InprocAddr::new_unique();
+    ///
+    /// let server = ServerBuilder::new()
+    ///     .bind(&addr)
+    ///     .track_routing_ids()
+    ///     .build()?;
+    ///
+    /// let client = ClientBuilder::new().connect(&addr).build()?;
+    ///
+    /// // The server learns about the client once it receives a message.
+    /// client.send("hello")?;
+    /// server.recv_msg()?;
+    ///
+    /// let report = server.broadcast("news");
+    /// assert_eq!(report.delivered().len(), 1);
+    /// assert!(report.failed().is_empty());
+    ///
+    /// assert_eq!(client.recv_msg()?.to_str()?, "news");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`tracked`]: #method.set_track_routing_ids
+    pub fn broadcast<M>(&self, msg: M) -> BroadcastReport
+    where
+        M: Into<Msg>,
+    {
+        let msg = msg.into();
+        let mut report = BroadcastReport::default();
+
+        for id in self.routing_ids() {
+            let mut copy = msg.clone();
+            copy.set_routing_id(id);
+
+            match self.send(copy) {
+                Ok(()) => report.delivered.push(id),
+                Err(err) => report.failed.push((id, err.kind())),
+            }
+        }
+
+        report
+    }
+
+    /// Retrieves a message along with the routing id of its sender.
+    ///
+    /// This is the same as [`recv_msg`], except that the routing id does not
+    /// have to be unwrapped from the message.
+    ///
+    /// # Returned Error Variants
+    /// * [`WouldBlock`] (if `recv_timeout` expires)
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`InvalidMessage`] (if *libzmq* did not set the routing id)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # #[cfg(feature = "test-util")]
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, test_util::client_server_pair, *};
+    ///
+    /// let (client, server) = client_server_pair();
+    ///
+    /// client.send("ping")?;
+    /// let (id, msg) = server.recv_from()?;
+    /// assert_eq!(msg.to_str()?, "ping");
+    ///
+    /// let mut reply: Msg = "pong".into();
+    /// reply.set_routing_id(id);
+    /// server.send(reply)?;
+    ///
+    /// assert_eq!(client.recv_msg()?.to_str()?, "pong");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`recv_msg`]: prelude/trait.RecvMsg.html#method.recv_msg
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    /// [`InvalidMessage`]: enum.ErrorKind.html#variant.InvalidMessage
+    pub fn recv_from(&self) -> Result<(RoutingId, Msg), Error> {
+        let msg = self.recv_msg()?;
+        Ok((routing_id(&msg)?, msg))
+    }
+
+    /// Same as [`recv_from`] but gives up with [`WouldBlock`] if no message
+    /// is received within `timeout`, regardless of the `recv_timeout` of
+    /// the socket.
+    ///
+    /// [`recv_from`]: #method.recv_from
+    /// [`WouldBlock`]: enum.ErrorKind.html#variant.WouldBlock
+    pub fn recv_from_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<(RoutingId, Msg), Error> {
+        let start = Instant::now();
+
+        loop {
+            let remaining = timeout
+                .checked_sub(start.elapsed())
+                .unwrap_or_else(|| Duration::from_secs(0));
+
+            if !self.wait_readable(Some(remaining))? {
+                return Err(Error::new(ErrorKind::WouldBlock));
+            }
+
+            // Another thread might have received the message first, in
+            // which case we wait for the remaining time.
+            match self.try_recv_msg() {
+                Ok(msg) => return Ok((routing_id(&msg)?, msg)),
+                Err(err) => {
+                    if err.kind() != ErrorKind::WouldBlock {
+                        return Err(err);
+                    }
+                }
+            }
+
+            if start.elapsed() >= timeout {
+                return Err(Error::new(ErrorKind::WouldBlock));
+            }
+        }
+    }
+
+    /// Receives all the messages that are immediately available and queues
+    /// them by the routing id of their sender.
+    ///
+    /// Returns the number of messages received, which is zero if none were
+    /// available. This never blocks.
+    ///
+    /// # Returned Error Variants
+    /// * [`CtxTerminated`]
+    /// * [`Interrupted`]
+    /// * [`InvalidMessage`] (if *libzmq* did not set the routing id)
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # #[cfg(feature = "test-util")]
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use libzmq::{prelude::*, test_util::client_server_pair, *};
+    /// use std::collections::{HashMap, VecDeque};
+    ///
+    /// let (client, server) = client_server_pair();
+    ///
+    /// client.send("first")?;
+    /// client.send("second")?;
+    /// assert!(server.wait_readable(None)?);
+    ///
+    /// let mut queues: HashMap<RoutingId, VecDeque<Msg>> = HashMap::new();
+    /// while queues.values().map(VecDeque::len).sum::<usize>() < 2 {
+    ///     server.recv_routed(&mut queues)?;
+    /// }
+    ///
+    /// assert_eq!(queues.len(), 1);
+    /// let queue = queues.values_mut().next().unwrap();
+    /// assert_eq!(queue.pop_front().unwrap().to_str()?, "first");
+    /// assert_eq!(queue.pop_front().unwrap().to_str()?, "second");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`CtxTerminated`]: enum.ErrorKind.html#variant.CtxTerminated
+    /// [`Interrupted`]: enum.ErrorKind.html#variant.Interrupted
+    /// [`InvalidMessage`]: enum.ErrorKind.html#variant.InvalidMessage
+    pub fn recv_routed(
+        &self,
+        map: &mut HashMap<RoutingId, VecDeque<Msg>>,
+    ) -> Result<usize, Error> {
+        let mut count = 0;
+
+        loop {
+            match self.try_recv_msg() {
+                Ok(msg) => {
+                    let id = routing_id(&msg)?;
+                    map.entry(id).or_insert_with(VecDeque::new).push_back(msg);
+                    count += 1;
+                }
+                Err(err) => {
+                    if err.kind() == ErrorKind::WouldBlock {
+                        return Ok(count);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    fn record(&self, msg: &Msg) {
+        if !self.tracked.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Some(ref mut registry) = *self.registry.lock().unwrap() {
+            if let Some(id) = msg.routing_id() {
+                registry.insert(id);
+            }
+        }
+    }
+
+    fn forget(&self, id: RoutingId) {
+        if !self.tracked.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Some(ref mut registry) = *self.registry.lock().unwrap() {
+            registry.remove(&id);
+        }
+    }
+
+    fn check_send(
+        &self,
+        id: Option<RoutingId>,
+        result: Result<(), Error<Msg>>,
+    ) -> Result<(), Error<Msg>> {
+        if let Err(ref err) = result {
+            if let (ErrorKind::HostUnreachable, Some(id)) = (err.kind(), id) {
+                self.forget(id);
+            }
+        }
+        result
+    }
 }
 
+// A message received by a `Server` always has a routing id.
+fn routing_id(msg: &Msg) -> Result<RoutingId, Error> {
+    msg.routing_id().ok_or_else(|| {
+        Error::new(ErrorKind::InvalidMessage {
+            msg: "received message has no routing id",
+        })
+    })
+}
+
+impl PartialEq for Server {
+    fn eq(&self, other: &Server) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for Server {}
+
 impl GetRawSocket for Server {
-    fn raw_socket(&self) -> &RawSocket {
+    fn raw_socket(&self) -> &Arc<RawSocket> {
         &self.inner
     }
 }
 
 impl Socket for Server {}
-impl SendMsg for Server {}
-impl RecvMsg for Server {}
+
+impl SendMsg for Server {
+    fn send<M>(&self, msg: M) -> Result<(), Error<Msg>>
+    where
+        M: Into<Msg>,
+    {
+        let msg = msg.into();
+        let id = msg.routing_id();
+        let raw_socket = self.raw_socket();
+        let retry = raw_socket.retry_interrupted();
+        let result =
+            send_retrying(retry, msg, |msg| send(raw_socket, msg, false));
+        self.check_send(id, result)
+    }
+
+    fn try_send<M>(&self, msg: M) -> Result<(), Error<Msg>>
+    where
+        M: Into<Msg>,
+    {
+        let msg = msg.into();
+        let id = msg.routing_id();
+        self.check_send(id, send(self.raw_socket(), msg, true))
+    }
+}
+
+impl RecvMsg for Server {
+    fn recv(&self, msg: &mut Msg) -> Result<(), Error> {
+        let raw_socket = self.raw_socket();
+        if raw_socket.retry_interrupted() {
+            retry_interrupted(|| recv(raw_socket, msg, false))?;
+        } else {
+            recv(raw_socket, msg, false)?;
+        }
+        self.record(msg);
+        Ok(())
+    }
+
+    fn try_recv(&self, msg: &mut Msg) -> Result<(), Error> {
+        recv(self.raw_socket(), msg, true)?;
+        self.record(msg);
+        Ok(())
+    }
+}
+
+/// The outcome of a [`broadcast`].
+///
+/// [`broadcast`]: struct.Server.html#method.broadcast
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct BroadcastReport {
+    delivered: Vec<RoutingId>,
+    failed: Vec<(RoutingId, ErrorKind)>,
+}
+
+impl BroadcastReport {
+    /// Returns the routing ids of the peers the message was sent to.
+    pub fn delivered(&self) -> &[RoutingId] {
+        &self.delivered
+    }
+
+    /// Returns the routing ids of the peers the message could not be sent
+    /// to, with the kind of error that occured.
+    pub fn failed(&self) -> &[(RoutingId, ErrorKind)] {
+        &self.failed
+    }
+}
 
 unsafe impl Send for Server {}
 unsafe impl Sync for Server {}
@@ -150,6 +575,7 @@ pub struct ServerConfig {
     socket_config: SocketConfig,
     send_config: SendConfig,
     recv_config: RecvConfig,
+    track_routing_ids: Option<bool>,
 }
 
 impl ServerConfig {
@@ -171,7 +597,20 @@ impl ServerConfig {
         Ok(server)
     }
 
+    /// Returns `true` if the routing ids of the peers are tracked.
+    pub fn track_routing_ids(&self) -> bool {
+        self.track_routing_ids.unwrap_or_default()
+    }
+
+    /// Specifies whether the routing ids of the peers are tracked.
+    pub fn set_track_routing_ids(&mut self, cond: bool) {
+        self.track_routing_ids = Some(cond);
+    }
+
     pub fn apply(&self, server: &Server) -> Result<(), Error<usize>> {
+        if let Some(enabled) = self.track_routing_ids {
+            server.set_track_routing_ids(enabled);
+        }
         self.send_config.apply(server).map_err(Error::cast)?;
         self.recv_config.apply(server).map_err(Error::cast)?;
         self.socket_config.apply(server)?;
@@ -184,6 +623,7 @@ impl ServerConfig {
 // https://github.com/serde-rs/serde/issues/1346
 // Wish there was a better way.
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct FlatServerConfig {
     connect: Option<Vec<Endpoint>>,
     bind: Option<Vec<Endpoint>>,
@@ -193,7 +633,22 @@ struct FlatServerConfig {
     send_timeout: Period,
     recv_high_water_mark: Quantity,
     recv_timeout: Period,
+    track_routing_ids: Option<bool>,
     mechanism: Option<Mechanism>,
+    zap_domain: Option<String>,
+    enforce_zap_domain: Option<bool>,
+    ipv6: Option<bool>,
+    max_msg_size: Option<i64>,
+    backlog: Option<i32>,
+    send_buffer: Option<i32>,
+    recv_buffer: Option<i32>,
+    vmci_buffer_size: Option<u64>,
+    vmci_buffer_min_size: Option<u64>,
+    vmci_buffer_max_size: Option<u64>,
+    vmci_connect_timeout: Option<Period>,
+    reconnect_stop: Option<ReconnectStop>,
+    label: Option<String>,
+    retry_interrupted: Option<bool>,
 }
 
 impl From<ServerConfig> for FlatServerConfig {
@@ -207,10 +662,25 @@ impl From<ServerConfig> for FlatServerConfig {
             heartbeat: socket_config.heartbeat,
             linger: socket_config.linger,
             mechanism: socket_config.mechanism,
+            zap_domain: socket_config.zap_domain,
+            enforce_zap_domain: socket_config.enforce_zap_domain,
+            ipv6: socket_config.ipv6,
+            max_msg_size: socket_config.max_msg_size,
+            backlog: socket_config.backlog,
+            send_buffer: socket_config.send_buffer,
+            recv_buffer: socket_config.recv_buffer,
+            vmci_buffer_size: socket_config.vmci_buffer_size,
+            vmci_buffer_min_size: socket_config.vmci_buffer_min_size,
+            vmci_buffer_max_size: socket_config.vmci_buffer_max_size,
+            vmci_connect_timeout: socket_config.vmci_connect_timeout,
+            reconnect_stop: socket_config.reconnect_stop,
+            label: socket_config.label,
+            retry_interrupted: socket_config.retry_interrupted,
             send_high_water_mark: send_config.send_high_water_mark,
             send_timeout: send_config.send_timeout,
             recv_high_water_mark: recv_config.recv_high_water_mark,
             recv_timeout: recv_config.recv_timeout,
+            track_routing_ids: config.track_routing_ids,
         }
     }
 }
@@ -223,6 +693,22 @@ impl From<FlatServerConfig> for ServerConfig {
             heartbeat: flat.heartbeat,
             linger: flat.linger,
             mechanism: flat.mechanism,
+            mechanism_conflict: false,
+            mechanism_redacted: false,
+            zap_domain: flat.zap_domain,
+            enforce_zap_domain: flat.enforce_zap_domain,
+            ipv6: flat.ipv6,
+            max_msg_size: flat.max_msg_size,
+            backlog: flat.backlog,
+            send_buffer: flat.send_buffer,
+            recv_buffer: flat.recv_buffer,
+            vmci_buffer_size: flat.vmci_buffer_size,
+            vmci_buffer_min_size: flat.vmci_buffer_min_size,
+            vmci_buffer_max_size: flat.vmci_buffer_max_size,
+            vmci_connect_timeout: flat.vmci_connect_timeout,
+            reconnect_stop: flat.reconnect_stop,
+            label: flat.label,
+            retry_interrupted: flat.retry_interrupted,
         };
         let send_config = SendConfig {
             send_high_water_mark: flat.send_high_water_mark,
@@ -236,6 +722,7 @@ impl From<FlatServerConfig> for ServerConfig {
             socket_config,
             send_config,
             recv_config,
+            track_routing_ids: flat.track_routing_ids,
         }
     }
 }
@@ -289,6 +776,11 @@ impl ServerBuilder {
         Self::default()
     }
 
+    pub fn track_routing_ids(&mut self) -> &mut Self {
+        self.inner.set_track_routing_ids(true);
+        self
+    }
+
     pub fn build(&self) -> Result<Server, Error<usize>> {
         self.inner.build()
     }
@@ -340,6 +832,14 @@ impl BuildRecv for ServerBuilder {}
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        config::ClientConfig,
+        socket::{monitor, recv_event},
+        Client, InprocAddr, TcpAddr,
+    };
+    use libzmq_sys as sys;
+
+    use std::{convert::TryInto, thread, time::Duration};
 
     #[test]
     fn test_ser_de() {
@@ -349,4 +849,470 @@ mod test {
         let de: ServerConfig = ron::de::from_str(&ron).unwrap();
         assert_eq!(config, de);
     }
+
+    #[test]
+    fn test_ser_de_yaml() {
+        let mut config = ServerConfig::new();
+        config.set_bind(Some(Endpoint::from_zmq("tcp://127.0.0.1:*")));
+        config.set_heartbeat(Some(Heartbeat::new(Duration::from_secs(1))));
+        config.set_recv_high_water_mark(Quantity::Limited(10));
+        config.set_recv_timeout(Period::Finite(Duration::from_millis(300)));
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        let de: ServerConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(config, de);
+    }
+
+    #[test]
+    fn test_de_unknown_field() {
+        let yaml = "recv_timout: 300ms";
+        assert!(serde_yaml::from_str::<ServerConfig>(yaml).is_err());
+    }
+
+    #[test]
+    fn test_de_invalid_endpoint() {
+        let yaml = "bind:\n  - tcp: \"not an addr\"";
+        assert!(serde_yaml::from_str::<ServerConfig>(yaml).is_err());
+    }
+
+    #[test]
+    fn test_build_from_config() {
+        let server_yaml = "
+            bind:
+              - inproc: \"test_build_from_config\"
+            recv_timeout: 1s
+        ";
+        let client_yaml = "
+            connect:
+              - inproc: \"test_build_from_config\"
+            recv_timeout: 1s
+        ";
+
+        let server_config: ServerConfig =
+            serde_yaml::from_str(server_yaml).unwrap();
+        let client_config: ClientConfig =
+            serde_yaml::from_str(client_yaml).unwrap();
+
+        let server = server_config.build().unwrap();
+        let client = client_config.build().unwrap();
+
+        client.send("ping").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+
+        let mut reply: Msg = "pong".into();
+        reply.set_routing_id(msg.routing_id().unwrap());
+        server.send(reply).unwrap();
+
+        let msg = client.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "pong");
+    }
+
+    #[test]
+    fn test_incoming_take() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        for i in 0..5 {
+            client.send(i.to_string()).unwrap();
+        }
+
+        let msgs: Vec<Msg> =
+            server.incoming().take(3).map(Result::unwrap).collect();
+        assert_eq!(msgs.len(), 3);
+        for (i, msg) in msgs.iter().enumerate() {
+            assert_eq!(msg.to_str().unwrap(), i.to_string());
+        }
+
+        // The remaining messages were already queued when we received
+        // the first ones, so they can be drained without blocking.
+        assert_eq!(server.try_incoming().count(), 2);
+        assert_eq!(server.try_incoming().count(), 0);
+    }
+
+    extern "C" fn ignore_signal(_: libc::c_int) {}
+
+    #[test]
+    fn test_retry_interrupted_recv() {
+        // Install a handler without `SA_RESTART` so that the signal
+        // interrupts the blocking receive with `EINTR`.
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = ignore_signal as libc::sighandler_t;
+            assert_eq!(
+                libc::sigaction(libc::SIGUSR1, &action, std::ptr::null_mut()),
+                0
+            );
+        }
+
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .retry_interrupted(true)
+            .build()
+            .unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let handle = thread::spawn(move || {
+            sender.send(unsafe { libc::pthread_self() }).unwrap();
+            server.recv_msg()
+        });
+
+        let thread_id = receiver.recv().unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(unsafe { libc::pthread_kill(thread_id, libc::SIGUSR1) }, 0);
+        thread::sleep(Duration::from_millis(50));
+
+        client.send("msg").unwrap();
+        let msg = handle.join().unwrap().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "msg");
+    }
+
+    #[test]
+    fn test_incoming_ctx_shutdown() {
+        let ctx = Ctx::new();
+        let server = Server::with_ctx(&ctx).unwrap();
+
+        let handle = thread::spawn(move || server.incoming().count());
+
+        thread::sleep(Duration::from_millis(50));
+        ctx.shutdown();
+
+        assert_eq!(handle.join().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_ipv6_loopback() {
+        let addr: TcpAddr = "[::1]:*".try_into().unwrap();
+
+        let server = ServerBuilder::new().ipv6(true).build().unwrap();
+        assert!(server.ipv6().unwrap());
+
+        // Skip if IPv6 is not available on the host.
+        if let Err(err) = server.bind(&addr) {
+            assert_eq!(err.kind(), ErrorKind::AddrNotAvailable);
+            return;
+        }
+
+        let bound = server.last_endpoint().unwrap().unwrap();
+        let client = ClientBuilder::new()
+            .ipv6(true)
+            .connect(bound)
+            .build()
+            .unwrap();
+
+        client.send("ping").unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+    }
+
+    #[test]
+    fn test_max_msg_size() {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        let server = ServerBuilder::new()
+            .max_msg_size(Some(1024))
+            .bind(&addr)
+            .recv_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        assert_eq!(server.max_msg_size().unwrap(), Some(1024));
+
+        let connected = sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED;
+        let disconnected = sys::ZMQ_EVENT_DISCONNECTED;
+        let mut events =
+            monitor(server.raw_socket(), connected | disconnected).unwrap();
+        let timeout = Duration::from_secs(1);
+
+        let bound = server.last_endpoint().unwrap();
+        let client = ClientBuilder::new().connect(bound).build().unwrap();
+        let (event, _) = recv_event(&mut events, timeout).unwrap();
+        assert_eq!(event, connected);
+
+        // The client gets disconnected and the message is never received.
+        client.send(vec![0; 1024 * 1024]).unwrap();
+        let (event, _) = recv_event(&mut events, timeout).unwrap();
+        assert_eq!(event, disconnected);
+        let err = server.recv_msg().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        // Once the client reconnects, messages within the limit still flow.
+        let (event, _) = recv_event(&mut events, timeout).unwrap();
+        assert_eq!(event, connected);
+        client.send(vec![0; 512]).unwrap();
+        server
+            .set_recv_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let msg = server.recv_msg().unwrap();
+        assert_eq!(msg.len(), 512);
+    }
+
+    #[test]
+    fn test_max_msg_size_invalid() {
+        let server = Server::new().unwrap();
+        let err = server.set_max_msg_size(Some(-2)).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_backlog() {
+        let addr: TcpAddr = "127.0.0.1:*".try_into().unwrap();
+
+        // The backlog is applied before the bind regardless of the order
+        // in which the builder methods are called, so the listener is
+        // created with it. Since the OS does not report the backlog of a
+        // listener, we check that it accepts peers.
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .backlog(1)
+            .recv_timeout(Duration::from_secs(1))
+            .build()
+            .unwrap();
+        let bound = server.last_endpoint().unwrap().unwrap();
+
+        let clients: Vec<Client> = (0..4)
+            .map(|_| ClientBuilder::new().connect(&bound).build().unwrap())
+            .collect();
+        for client in &clients {
+            client.send("").unwrap();
+        }
+        for _ in &clients {
+            server.recv_msg().unwrap();
+        }
+
+        let err = server.set_backlog(0).unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+        assert_eq!(server.backlog().unwrap(), 1);
+
+        let err = ServerBuilder::new()
+            .bind(&addr)
+            .backlog(-1)
+            .build()
+            .unwrap_err();
+        match err.kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_readiness() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+        assert!(!server.readiness().unwrap().is_readable());
+
+        client.send("first").unwrap();
+        client.send("second").unwrap();
+
+        // Once the first message is received, we know the second is queued.
+        server.recv_msg().unwrap();
+        assert!(server.readiness().unwrap().is_readable());
+
+        server.recv_msg().unwrap();
+        assert!(!server.readiness().unwrap().is_readable());
+    }
+
+    #[test]
+    fn test_clone_concurrent_send() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let client = client.clone();
+                thread::spawn(move || {
+                    for _ in 0..50 {
+                        client.send("msg").unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(server.incoming().take(100).count(), 100);
+        assert_eq!(server.try_incoming().count(), 0);
+    }
+
+    #[test]
+    fn test_clone_close_once() {
+        let ctx = Ctx::new();
+        let server = Server::with_ctx(&ctx).unwrap();
+        assert_eq!(Arc::strong_count(&server.inner), 1);
+
+        let clone = server.clone();
+        assert_eq!(server, clone);
+        assert_eq!(Arc::strong_count(&server.inner), 2);
+
+        // Options are shared between the handles.
+        clone.set_linger(Some(Duration::from_millis(0))).unwrap();
+        assert_eq!(
+            server.linger().unwrap(),
+            Period::Finite(Duration::from_millis(0))
+        );
+
+        drop(clone);
+        assert_eq!(Arc::strong_count(&server.inner), 1);
+        server.last_endpoint().unwrap();
+
+        // Terminating the context would block forever if the socket
+        // was not closed.
+        drop(server);
+        drop(ctx);
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new()
+            .bind(&addr)
+            .track_routing_ids()
+            .build()
+            .unwrap();
+
+        let clients: Vec<Client> = (0..3)
+            .map(|_| {
+                ClientBuilder::new()
+                    .connect(&addr)
+                    .linger(Period::Finite(Duration::from_millis(0)))
+                    .recv_timeout(Duration::from_millis(200))
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+
+        for client in &clients {
+            client.send("hello").unwrap();
+        }
+        assert_eq!(server.incoming().take(3).count(), 3);
+        assert_eq!(server.routing_ids().len(), 3);
+
+        let mut clients = clients.into_iter();
+        drop(clients.next().unwrap());
+        // Wait for the disconnection to propagate to the server.
+        thread::sleep(Duration::from_millis(100));
+
+        // The disconnected client is only forgotten once a send fails.
+        assert_eq!(server.routing_ids().len(), 3);
+
+        let report = server.broadcast("news");
+        assert_eq!(report.delivered().len(), 2);
+        assert_eq!(report.failed().len(), 1);
+        assert_eq!(report.failed()[0].1, ErrorKind::HostUnreachable);
+
+        // The unreachable client was forgotten.
+        assert_eq!(server.routing_ids().len(), 2);
+
+        for client in clients {
+            assert_eq!(client.recv_msg().unwrap().to_str().unwrap(), "news");
+        }
+    }
+
+    #[test]
+    fn test_broadcast_untracked() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        client.send("hello").unwrap();
+        server.recv_msg().unwrap();
+
+        assert!(server.routing_ids().is_empty());
+        assert_eq!(server.broadcast("news"), BroadcastReport::default());
+    }
+
+    #[test]
+    fn test_recv_from() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        client.send("request").unwrap();
+        let (routing_id, msg) = server.recv_from().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "request");
+        assert_eq!(msg.routing_id(), Some(routing_id));
+
+        let mut msg: Msg = "reply 1".into();
+        msg.set_routing_id(routing_id);
+        server.send(msg).unwrap();
+        let mut msg: Msg = "reply 2".into();
+        msg.set_routing_id(routing_id);
+        server.send(msg).unwrap();
+
+        let mut msg = client.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "reply 1");
+        client.recv(&mut msg).unwrap();
+        assert_eq!(msg.to_str().unwrap(), "reply 2");
+    }
+
+    #[test]
+    fn test_recv_from_timeout() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let client = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let timeout = Duration::from_millis(50);
+        let err = server.recv_from_timeout(timeout).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        client.send("request").unwrap();
+        let (routing_id, msg) = server.recv_from_timeout(timeout).unwrap();
+        assert_eq!(msg.to_str().unwrap(), "request");
+        assert_eq!(msg.routing_id(), Some(routing_id));
+
+        // The socket timeout is left untouched.
+        assert_eq!(server.recv_timeout().unwrap(), Period::Infinite);
+    }
+
+    #[test]
+    fn test_recv_routed() {
+        let addr = InprocAddr::new_unique();
+
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let first = ClientBuilder::new().connect(&addr).build().unwrap();
+        let second = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let mut map = HashMap::new();
+        assert_eq!(server.recv_routed(&mut map).unwrap(), 0);
+        assert!(map.is_empty());
+
+        first.send("a").unwrap();
+        second.send("b").unwrap();
+        first.send("c").unwrap();
+
+        let mut count = 0;
+        while count < 3 {
+            assert!(server.wait_readable(None).unwrap());
+            count += server.recv_routed(&mut map).unwrap();
+        }
+        assert_eq!(count, 3);
+        assert_eq!(map.len(), 2);
+
+        let mut queues: Vec<Vec<&str>> = map
+            .values()
+            .map(|queue| queue.iter().map(|m| m.to_str().unwrap()).collect())
+            .collect();
+        queues.sort();
+        assert_eq!(queues, vec![vec!["a", "c"], vec!["b"]]);
+    }
 }
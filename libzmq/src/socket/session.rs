@@ -0,0 +1,244 @@
+use crate::{core::*, error::*, Msg, RoutingId, Server};
+
+use hashbrown::HashMap;
+
+use std::time::{Duration, Instant};
+
+/// The state that a [`ServerSessions`] keeps for a client.
+///
+/// [`ServerSessions`]: struct.ServerSessions.html
+#[derive(Debug, Clone)]
+pub struct Session<T> {
+    first_seen: Instant,
+    last_seen: Instant,
+    value: T,
+}
+
+impl<T> Session<T> {
+    /// Returns when the first message of the client was received.
+    pub fn first_seen(&self) -> Instant {
+        self.first_seen
+    }
+
+    /// Returns when the last message of the client was received.
+    pub fn last_seen(&self) -> Instant {
+        self.last_seen
+    }
+
+    /// Returns a reference to the user value of the session.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns a mutable reference to the user value of the session.
+    pub fn value_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+/// A `Server` that keeps a session for each client it receives messages
+/// from.
+///
+/// On each received message, the session of the client is created if
+/// needed with the default user value and its last seen timestamp is
+/// updated. The sessions of the clients that went idle can then be evicted
+/// with [`evict_idle`].
+///
+/// Since ØMQ does not notify a `Server` when a client disconnects, the
+/// sessions can only be removed eagerly by the application, for instance
+/// when the client sends a goodbye message, via [`remove`].
+///
+/// # Thread Safety
+/// The sessions are accessed through `&mut self`, so that a reference to
+/// a session can be held without locking. The `ServerSessions` is thus
+/// meant to be owned by the thread receiving the messages. The other
+/// threads can still send replies through a clone of the [`server`].
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # #[cfg(feature = "test-util")]
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, test_util::client_server_pair, *};
+///
+/// let (client, server) = client_server_pair();
+///
+/// // Counts the requests of each client.
+/// let mut sessions: ServerSessions<usize> = ServerSessions::new(server);
+///
+/// client.send("")?;
+/// client.send("")?;
+/// let id = sessions.recv_msg()?.routing_id().unwrap();
+/// *sessions.get_mut(id).unwrap().value_mut() += 1;
+/// let id = sessions.recv_msg()?.routing_id().unwrap();
+/// *sessions.get_mut(id).unwrap().value_mut() += 1;
+///
+/// assert_eq!(sessions.len(), 1);
+/// assert_eq!(*sessions.get(id).unwrap().value(), 2);
+/// #
+/// #     Ok(())
+/// # }
+/// # #[cfg(not(feature = "test-util"))]
+/// # fn main() {}
+/// ```
+///
+/// [`evict_idle`]: #method.evict_idle
+/// [`remove`]: #method.remove
+/// [`server`]: #method.server
+#[derive(Debug)]
+pub struct ServerSessions<T> {
+    server: Server,
+    sessions: HashMap<RoutingId, Session<T>>,
+}
+
+impl<T> ServerSessions<T>
+where
+    T: Default,
+{
+    /// Wraps the `Server`, with no session.
+    pub fn new(server: Server) -> Self {
+        Self {
+            server,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Receives a message, updating the session of its client.
+    ///
+    /// See [`RecvMsg::recv_msg`].
+    ///
+    /// [`RecvMsg::recv_msg`]: prelude/trait.RecvMsg.html#method.recv_msg
+    pub fn recv_msg(&mut self) -> Result<Msg, Error> {
+        let msg = self.server.recv_msg()?;
+        self.touch(&msg);
+
+        Ok(msg)
+    }
+
+    /// Receives a message without blocking, updating the session of its
+    /// client.
+    ///
+    /// See [`RecvMsg::try_recv_msg`].
+    ///
+    /// [`RecvMsg::try_recv_msg`]: prelude/trait.RecvMsg.html#method.try_recv_msg
+    pub fn try_recv_msg(&mut self) -> Result<Msg, Error> {
+        let msg = self.server.try_recv_msg()?;
+        self.touch(&msg);
+
+        Ok(msg)
+    }
+
+    fn touch(&mut self, msg: &Msg) {
+        // A message received by a `Server` always has a routing id.
+        if let Some(id) = msg.routing_id() {
+            let now = Instant::now();
+            self.sessions
+                .entry(id)
+                .or_insert_with(|| Session {
+                    first_seen: now,
+                    last_seen: now,
+                    value: T::default(),
+                })
+                .last_seen = now;
+        }
+    }
+}
+
+impl<T> ServerSessions<T> {
+    /// Returns a reference to the wrapped `Server`.
+    pub fn server(&self) -> &Server {
+        &self.server
+    }
+
+    /// Returns the session of the client, if any.
+    pub fn get(&self, id: RoutingId) -> Option<&Session<T>> {
+        self.sessions.get(&id)
+    }
+
+    /// Returns the session of the client mutably, if any.
+    pub fn get_mut(&mut self, id: RoutingId) -> Option<&mut Session<T>> {
+        self.sessions.get_mut(&id)
+    }
+
+    /// Returns an iterator over the sessions, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (RoutingId, &Session<T>)> {
+        self.sessions.iter().map(|(id, session)| (*id, session))
+    }
+
+    /// Returns the number of sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Returns `true` if there is no session.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Removes the session of the client, returning its user value.
+    pub fn remove(&mut self, id: RoutingId) -> Option<T> {
+        self.sessions.remove(&id).map(|session| session.value)
+    }
+
+    /// Removes the sessions of the clients whose last message was received
+    /// more than `idle` ago, returning their user value.
+    pub fn evict_idle(&mut self, idle: Duration) -> Vec<(RoutingId, T)> {
+        let now = Instant::now();
+        let expired: Vec<RoutingId> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| now.duration_since(session.last_seen) > idle)
+            .map(|(id, _)| *id)
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|id| self.remove(id).map(|value| (id, value)))
+            .collect()
+    }
+
+    /// Returns the wrapped `Server`, dropping the sessions.
+    pub fn into_inner(self) -> Server {
+        self.server
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, *};
+
+    use std::thread;
+
+    #[test]
+    fn test_sessions_evict_idle() {
+        let addr = InprocAddr::new_unique();
+        let server = ServerBuilder::new().bind(&addr).build().unwrap();
+        let idle = ClientBuilder::new().connect(&addr).build().unwrap();
+        let active = ClientBuilder::new().connect(&addr).build().unwrap();
+
+        let mut sessions: ServerSessions<()> = ServerSessions::new(server);
+        idle.send("").unwrap();
+        let idle_id = sessions.recv_msg().unwrap().routing_id().unwrap();
+        active.send("").unwrap();
+        let active_id = sessions.recv_msg().unwrap().routing_id().unwrap();
+        assert_eq!(sessions.len(), 2);
+
+        let window = Duration::from_millis(100);
+        thread::sleep(window);
+        active.send("").unwrap();
+        sessions.recv_msg().unwrap();
+
+        let session = sessions.get(active_id).unwrap();
+        assert!(session.last_seen() > session.first_seen());
+
+        let evicted = sessions.evict_idle(window);
+        assert_eq!(evicted, vec![(idle_id, ())]);
+        assert!(sessions.get(idle_id).is_none());
+        assert_eq!(sessions.iter().count(), 1);
+
+        assert_eq!(sessions.remove(active_id), Some(()));
+        assert!(sessions.is_empty());
+    }
+}
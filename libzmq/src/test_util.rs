@@ -0,0 +1,130 @@
+//! Helpers to set up sockets in tests.
+//!
+//! This requires the `test-util` feature. The sockets are created within
+//! the global context and are connected through unique `inproc` endpoints,
+//! so that the tests running in parallel never collide. Since no port is
+//! involved, dropping the sockets releases everything they use.
+//!
+//! The helpers panic on failure, which is what a test would do anyway.
+//!
+//! # Example
+//! ```
+//! # use std::error::Error;
+//! #
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use libzmq::{prelude::*, test_util::*};
+//!
+//! let (client, server) = client_server_pair();
+//!
+//! client.send("ping")?;
+//! let msg = server.recv_msg()?;
+//! assert_eq!(msg.to_str()?, "ping");
+//! #
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::{prelude::*, *};
+
+use std::{convert::TryInto, thread, time::Duration};
+
+// A group used to check that the groups of a `Dish` were joined.
+const PROBE_GROUP: &Group = group!("__test_util__");
+
+/// Returns a unique `inproc` address.
+///
+/// This is a shorthand for [`InprocAddr::new_unique`].
+///
+/// [`InprocAddr::new_unique`]: ../struct.InprocAddr.html#method.new_unique
+pub fn ephemeral_endpoint() -> InprocAddr {
+    InprocAddr::new_unique()
+}
+
+/// Returns a `Client` connected to a `Server`.
+///
+/// # Panics
+/// If the sockets cannot be created.
+pub fn client_server_pair() -> (Client, Server) {
+    let addr = ephemeral_endpoint();
+    let server = ServerBuilder::new()
+        .bind(&addr)
+        .build()
+        .expect("failed to build server");
+    let client = ClientBuilder::new()
+        .connect(&addr)
+        .build()
+        .expect("failed to build client");
+
+    (client, server)
+}
+
+/// Returns a `Radio` connected to a `Dish` that joined the groups.
+///
+/// Since the groups are joined asynchronously, the messages sent by a
+/// `Radio` right after the join are usually lost. This helper waits until
+/// the joins reached the `Radio`, so that no message is lost.
+///
+/// # Panics
+/// If a group name is invalid or if the sockets cannot be created.
+pub fn radio_dish_pair(groups: &[&str]) -> (Radio, Dish) {
+    let addr = ephemeral_endpoint();
+    let radio = RadioBuilder::new()
+        .bind(&addr)
+        .build()
+        .expect("failed to build radio");
+
+    let groups = groups
+        .iter()
+        .map(|&name| name.try_into())
+        .collect::<Result<Vec<GroupOwned>, _>>()
+        .expect("invalid group name");
+    let dish = DishBuilder::new()
+        .connect(&addr)
+        .join(groups)
+        .build()
+        .expect("failed to build dish");
+
+    // The joins are transmitted in order, so once the probe is received
+    // the previous ones were transmitted as well.
+    dish.join(PROBE_GROUP).expect("failed to join probe group");
+    loop {
+        let mut probe = Msg::new();
+        probe.set_group(PROBE_GROUP);
+        radio.send(probe).expect("failed to send probe");
+
+        match dish.try_recv_msg() {
+            Ok(_) => break,
+            Err(_) => thread::sleep(Duration::from_millis(1)),
+        }
+    }
+    // The `Dish` drops the messages of the groups it left, so the probes
+    // still in flight are never received.
+    dish.leave(PROBE_GROUP)
+        .expect("failed to leave probe group");
+
+    (radio, dish)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_radio_dish_pair() {
+        let (radio, dish) = radio_dish_pair(&["a", "b"]);
+
+        for group in &["a", "b", "c"] {
+            let mut msg = Msg::new();
+            let group: &Group = (*group).try_into().unwrap();
+            msg.set_group(group);
+            radio.send(msg).unwrap();
+        }
+
+        for group in &["a", "b"] {
+            let msg = dish.recv_msg().unwrap();
+            assert_eq!(msg.group().unwrap(), *group);
+        }
+        assert!(dish.try_recv_msg().is_err());
+        assert_eq!(dish.joined().len(), 2);
+    }
+}
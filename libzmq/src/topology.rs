@@ -0,0 +1,427 @@
+//! Declarative wiring of the context and sockets of a node.
+
+use crate::{
+    addr::Endpoint, config::*, error::*, prelude::*, Ctx, InprocAddr,
+    SocketType,
+};
+
+use hashbrown::HashMap;
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+
+use std::{collections::BTreeMap, convert::TryFrom, fmt};
+
+/// The error returned by [`Topology::build`] and [`Node::get`].
+///
+/// It names the section and field of the topology that caused the error.
+///
+/// [`Topology::build`]: struct.Topology.html#method.build
+/// [`Node::get`]: struct.Node.html#method.get
+#[derive(Debug, thiserror::Error)]
+#[error("invalid `{field}` in section `{section}`: {error}")]
+pub struct TopologyError {
+    section: String,
+    field: &'static str,
+    error: Error,
+}
+
+impl TopologyError {
+    fn new<S>(section: S, field: &'static str, error: Error) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            section: section.into(),
+            field,
+            error,
+        }
+    }
+
+    /// Returns the name of the section, which is either `ctx`, `auth` or
+    /// the name of a socket.
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    /// Returns the name of the field within the section.
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// Returns the underlying error.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+}
+
+// The name of the field that caused the error, or `fallback` if the
+// error was not raised by a build step.
+fn field_of<T>(err: &Error<T>, fallback: &'static str) -> &'static str {
+    match err.build_step() {
+        Some(BuildStep::Option { name }) => name,
+        Some(BuildStep::Connect) => "connect",
+        Some(BuildStep::Bind) => "bind",
+        None => fallback,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawSocketSection")]
+struct SocketSection {
+    #[serde(flatten)]
+    config: ConfigType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    connect_to: Vec<String>,
+}
+
+// Since `deny_unknown_fields` is not supported along with `flatten`, the
+// keys left over by the socket config are collected to be rejected.
+#[derive(Deserialize)]
+struct RawSocketSection {
+    #[serde(flatten)]
+    config: ConfigType,
+    #[serde(default)]
+    connect_to: Vec<String>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, IgnoredAny>,
+}
+
+impl TryFrom<RawSocketSection> for SocketSection {
+    type Error = String;
+    fn try_from(raw: RawSocketSection) -> Result<Self, String> {
+        if let Some(key) = raw.unknown.keys().next() {
+            return Err(format!("unknown field `{}`", key));
+        }
+
+        Ok(Self {
+            config: raw.config,
+            connect_to: raw.connect_to,
+        })
+    }
+}
+
+/// A description of a whole node, namely its context, the configuration of
+/// its `AuthServer` and its named sockets.
+///
+/// Each socket section holds the configuration of the socket keyed by its
+/// type, along with an optional `connect_to` field listing the names of
+/// the sockets to connect to. The socket connects to the endpoint last
+/// bound by each named socket, which is bound to a unique `inproc`
+/// address if it has no endpoint. This allows to wire the sockets of a
+/// node without naming any address.
+///
+/// All the sockets are created within the context of the node. Unknown
+/// fields are rejected, including those of the socket sections.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{prelude::*, topology::Topology, *};
+///
+/// let topology: Topology = serde_json::from_str(
+///     r#"{
+///         "ctx": { "io_threads": 1 },
+///         "sockets": {
+///             "frontend": { "server": {} },
+///             "api": { "client": {}, "connect_to": ["frontend"] }
+///         }
+///     }"#,
+/// )?;
+///
+/// let node = topology.build()?;
+/// let frontend: Server = node.get("frontend")?;
+/// let api: Client = node.get("api")?;
+///
+/// api.send("ping")?;
+/// let msg = frontend.recv_msg()?;
+/// assert_eq!(msg.to_str()?, "ping");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Topology {
+    #[serde(default)]
+    ctx: CtxConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auth: Option<AuthConfig>,
+    #[serde(default)]
+    sockets: BTreeMap<String, SocketSection>,
+}
+
+impl Topology {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the configuration of the context.
+    pub fn ctx(&self) -> &CtxConfig {
+        &self.ctx
+    }
+
+    /// Sets the configuration of the context.
+    pub fn set_ctx(&mut self, config: CtxConfig) {
+        self.ctx = config;
+    }
+
+    /// Returns the configuration of the `AuthServer`, if any.
+    pub fn auth(&self) -> Option<&AuthConfig> {
+        self.auth.as_ref()
+    }
+
+    /// Sets the configuration of the `AuthServer`.
+    pub fn set_auth(&mut self, maybe: Option<AuthConfig>) {
+        self.auth = maybe;
+    }
+
+    /// Adds a named socket, replacing the previous socket of that name.
+    pub fn add_socket<S>(&mut self, name: S, config: ConfigType)
+    where
+        S: Into<String>,
+    {
+        self.sockets.insert(
+            name.into(),
+            SocketSection {
+                config,
+                connect_to: vec![],
+            },
+        );
+    }
+
+    /// Connects the socket `name` to the socket `target` once built.
+    ///
+    /// Does nothing if there is no socket `name`.
+    pub fn connect_to<S, T>(&mut self, name: S, target: T)
+    where
+        S: AsRef<str>,
+        T: Into<String>,
+    {
+        if let Some(section) = self.sockets.get_mut(name.as_ref()) {
+            section.connect_to.push(target.into());
+        }
+    }
+
+    /// Builds the context, configures its `AuthServer` and builds the
+    /// sockets before wiring them together.
+    ///
+    /// # Returned Error Variants
+    /// * [`NotFound`] (if a `connect_to` field names an unknown socket)
+    ///
+    /// As well as the errors returned when building the context, the
+    /// `AuthServer` and the sockets.
+    ///
+    /// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+    pub fn build(&self) -> Result<Node, TopologyError> {
+        let ctx = self.ctx.build().map_err(|err| {
+            TopologyError::new("ctx", field_of(&err, "config"), err)
+        })?;
+
+        if let Some(auth) = &self.auth {
+            auth.with_ctx(&ctx).map_err(|err| {
+                TopologyError::new("auth", field_of(&err, "config"), err)
+            })?;
+        }
+
+        let mut sockets = HashMap::new();
+        for (name, section) in &self.sockets {
+            let socket = section.config.with_ctx(&ctx).map_err(|err| {
+                let field = field_of(&err, "type");
+                TopologyError::new(name.as_str(), field, err.cast())
+            })?;
+            sockets.insert(name.to_owned(), socket);
+        }
+
+        for (name, section) in &self.sockets {
+            let wire_err = |err: Error| {
+                TopologyError::new(name.as_str(), "connect_to", err)
+            };
+
+            for target in &section.connect_to {
+                let target = sockets.get(target).ok_or_else(|| {
+                    wire_err(Error::new(ErrorKind::NotFound {
+                        msg: "socket of that name",
+                    }))
+                })?;
+
+                let endpoint = resolve(target).map_err(wire_err)?;
+                sockets[name]
+                    .connect(&endpoint)
+                    .map_err(|err| wire_err(err.cast()))?;
+            }
+        }
+
+        Ok(Node { ctx, sockets })
+    }
+}
+
+// Returns the endpoint last bound by the socket, binding it to a unique
+// inproc address if there is none.
+fn resolve(socket: &SocketType) -> Result<Endpoint, Error> {
+    if let Some(endpoint) = socket.last_endpoint()? {
+        return Ok(endpoint);
+    }
+
+    let endpoint: Endpoint = InprocAddr::new_unique().into();
+    socket.bind(&endpoint).map_err(Error::cast)?;
+
+    Ok(endpoint)
+}
+
+/// The context and sockets built from a [`Topology`].
+///
+/// [`Topology`]: struct.Topology.html
+#[derive(Clone)]
+pub struct Node {
+    ctx: Ctx,
+    sockets: HashMap<String, SocketType>,
+}
+
+impl Node {
+    /// Returns the context of the node.
+    pub fn ctx(&self) -> &Ctx {
+        &self.ctx
+    }
+
+    /// Returns the socket of that name, if any.
+    pub fn socket(&self, name: &str) -> Option<&SocketType> {
+        self.sockets.get(name)
+    }
+
+    /// Returns a handle to the socket of that name, checking that it has
+    /// the expected type.
+    ///
+    /// # Returned Error Variants
+    /// * [`NotFound`] (if there is no socket of that name)
+    /// * [`InvalidInput`] (if the socket has another type)
+    ///
+    /// [`NotFound`]: ../enum.ErrorKind.html#variant.NotFound
+    /// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+    pub fn get<T>(&self, name: &str) -> Result<T, TopologyError>
+    where
+        T: TryFrom<SocketType>,
+    {
+        let socket = self.sockets.get(name).ok_or_else(|| {
+            let err = Error::new(ErrorKind::NotFound {
+                msg: "socket of that name",
+            });
+            TopologyError::new(name, "type", err)
+        })?;
+
+        T::try_from(socket.clone()).map_err(|_| {
+            let err = Error::new(ErrorKind::InvalidInput {
+                msg: "unexpected socket type",
+            });
+            TopologyError::new(name, "type", err)
+        })
+    }
+
+    /// Returns the names of the sockets of the node.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sockets.keys().map(String::as_str)
+    }
+}
+
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names: Vec<&str> = self.names().collect();
+        names.sort_unstable();
+
+        f.debug_struct("Node")
+            .field("ctx", &self.ctx)
+            .field("sockets", &names)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_topology_inproc() {
+        let topology: Topology = toml::from_str(
+            r#"
+            [ctx]
+            io_threads = 1
+
+            [sockets.frontend.server]
+            recv_timeout = "1s"
+
+            [sockets.api]
+            connect_to = ["frontend"]
+
+            [sockets.api.client]
+            send_high_water_mark = 10
+            "#,
+        )
+        .unwrap();
+
+        let node = topology.build().unwrap();
+        let frontend: Server = node.get("frontend").unwrap();
+        let api: Client = node.get("api").unwrap();
+        assert_eq!(frontend.ctx(), node.ctx());
+
+        api.send("ping").unwrap();
+        let msg = frontend.recv_msg().unwrap();
+        assert_eq!(msg.to_str().unwrap(), "ping");
+
+        let err = node.get::<Radio>("api").unwrap_err();
+        assert_eq!((err.section(), err.field()), ("api", "type"));
+        match err.error().kind() {
+            ErrorKind::InvalidInput { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+    }
+
+    #[test]
+    fn test_topology_errors() {
+        let mut topology = Topology::new();
+        topology.add_socket("api", ConfigType::Client(ClientConfig::new()));
+        topology.connect_to("api", "missing");
+
+        let err = topology.build().unwrap_err();
+        assert_eq!((err.section(), err.field()), ("api", "connect_to"));
+        match err.error().kind() {
+            ErrorKind::NotFound { .. } => (),
+            _ => panic!("unexpected error"),
+        }
+
+        let mut config = ServerConfig::new();
+        config.set_zap_domain(Some("".to_owned()));
+        let mut topology = Topology::new();
+        topology.add_socket("frontend", ConfigType::Server(config));
+
+        let err = topology.build().unwrap_err();
+        assert_eq!((err.section(), err.field()), ("frontend", "zap_domain"));
+        assert_eq!(
+            err.to_string(),
+            "invalid `zap_domain` in section `frontend`: failed to set \
+             zap_domain: invalid input: zap domain cannot be empty"
+        );
+
+        let mut config = CtxConfig::new();
+        config.set_thread_affinity(Some(vec![usize::max_value()]));
+        let mut topology = Topology::new();
+        topology.set_ctx(config);
+
+        let err = topology.build().unwrap_err();
+        assert_eq!((err.section(), err.field()), ("ctx", "thread_affinity"));
+    }
+
+    #[test]
+    fn test_topology_unknown_field() {
+        let err = toml::from_str::<Topology>(
+            r#"
+            [sockets.api]
+            conect_to = ["frontend"]
+
+            [sockets.api.client]
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown field `conect_to`"));
+    }
+}
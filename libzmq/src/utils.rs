@@ -1,8 +1,104 @@
 use crate::{core::GetRawSocket, error::*};
 use libzmq_sys as sys;
-use sys::errno;
 
-use std::{ffi, os::raw::*, ptr, str};
+use std::{ffi, fmt, os::raw::*, ptr, str};
+
+/// Encodes binary data into the printable [`Z85`] text representation.
+///
+/// See [`zmq_z85_encode`].
+///
+/// # Usage Contract
+/// * The length of the data must be a multiple of 4 bytes.
+///
+/// Every 4 bytes of data are encoded into 5 chars of text.
+///
+/// # Returned Errors
+/// * [`InvalidInput`] (if the contract is not followed)
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use libzmq::{z85_decode, z85_encode};
+///
+/// let data = [0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B];
+/// let text = z85_encode(&data)?;
+/// assert_eq!(text, "HelloWorld");
+/// assert_eq!(z85_decode(&text)?, data);
+///
+/// assert!(z85_encode(&data[..7]).is_err());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Z85`]: https://rfc.zeromq.org/spec:32/Z85/
+/// [`zmq_z85_encode`]: http://api.zeromq.org/4-2:zmq-z85-encode
+/// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+pub fn z85_encode(data: &[u8]) -> Result<String, Error> {
+    if data.len() % 4 != 0 {
+        return Err(Error::new(ErrorKind::InvalidInput {
+            msg: "data length must be a multiple of 4 bytes",
+        }));
+    }
+
+    // Includes the terminating null byte.
+    let mut text = vec![0u8; data.len() / 4 * 5 + 1];
+    let dest = unsafe {
+        sys::zmq_z85_encode(
+            text.as_mut_ptr() as *mut c_char,
+            data.as_ptr(),
+            data.len(),
+        )
+    };
+    assert!(!dest.is_null());
+
+    text.pop();
+    // The z85 alphabet is ASCII.
+    Ok(unsafe { String::from_utf8_unchecked(text) })
+}
+
+/// Decodes the printable [`Z85`] text representation into binary data.
+///
+/// See [`zmq_z85_decode`].
+///
+/// # Usage Contract
+/// * The length of the text must be a multiple of 5 chars.
+/// * The text must only contain chars of the `Z85` alphabet.
+///
+/// Every 5 chars of text are decoded into 4 bytes of data.
+///
+/// # Returned Errors
+/// * [`InvalidInput`] (if the contract is not followed)
+///
+/// [`Z85`]: https://rfc.zeromq.org/spec:32/Z85/
+/// [`zmq_z85_decode`]: http://api.zeromq.org/4-2:zmq-z85-decode
+/// [`InvalidInput`]: ../enum.ErrorKind.html#variant.InvalidInput
+pub fn z85_decode(text: &str) -> Result<Vec<u8>, Error> {
+    if text.len() % 5 != 0 {
+        return Err(Error::new(ErrorKind::InvalidInput {
+            msg: "text length must be a multiple of 5 chars",
+        }));
+    }
+    let c_str = ffi::CString::new(text).map_err(|_| {
+        Error::new(ErrorKind::InvalidInput {
+            msg: "text cannot contain a nul byte",
+        })
+    })?;
+
+    let mut data = vec![0u8; text.len() / 5 * 4];
+    let dest =
+        unsafe { sys::zmq_z85_decode(data.as_mut_ptr(), c_str.as_ptr()) };
+
+    if dest.is_null() {
+        Err(Error::new(ErrorKind::InvalidInput {
+            msg: "text contains invalid z85 chars",
+        }))
+    } else {
+        Ok(data)
+    }
+}
 
 /// Reports the ØMQ library version.
 ///
@@ -33,20 +129,104 @@ pub fn version() -> (i32, i32, i32) {
     (major, minor, patch)
 }
 
+/// A capability that *libzmq* may have been built with.
+///
+/// The capabilities are queried with [`has`]. A `&str` or `&String`
+/// converts into the matching variant, or into `Other` if it is unknown.
+///
+/// [`has`]: fn.has.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Capability<'a> {
+    /// The `ipc` transport.
+    Ipc,
+    /// The `pgm` and `epgm` transports.
+    Pgm,
+    /// The `tipc` transport.
+    Tipc,
+    /// The `norm` transport.
+    Norm,
+    /// The `vmci` transport.
+    Vmci,
+    /// The `CURVE` security mechanism.
+    Curve,
+    /// The `GSSAPI` security mechanism.
+    Gssapi,
+    /// The draft API, which this crate requires.
+    Draft,
+    /// Any other capability, by the name `zmq_has` expects.
+    Other(&'a str),
+}
+
+impl<'a> Capability<'a> {
+    /// Returns the name of the capability, as expected by `zmq_has`.
+    pub fn as_str(&self) -> &'a str {
+        match self {
+            Capability::Ipc => "ipc",
+            Capability::Pgm => "pgm",
+            Capability::Tipc => "tipc",
+            Capability::Norm => "norm",
+            Capability::Vmci => "vmci",
+            Capability::Curve => "curve",
+            Capability::Gssapi => "gssapi",
+            Capability::Draft => "draft",
+            Capability::Other(name) => name,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Capability<'a> {
+    fn from(name: &'a str) -> Self {
+        match name {
+            "ipc" => Capability::Ipc,
+            "pgm" => Capability::Pgm,
+            "tipc" => Capability::Tipc,
+            "norm" => Capability::Norm,
+            "vmci" => Capability::Vmci,
+            "curve" => Capability::Curve,
+            "gssapi" => Capability::Gssapi,
+            "draft" => Capability::Draft,
+            _ => Capability::Other(name),
+        }
+    }
+}
+
+impl<'a> From<&'a String> for Capability<'a> {
+    fn from(name: &'a String) -> Self {
+        Self::from(name.as_str())
+    }
+}
+
+impl<'a> fmt::Display for Capability<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Check for a ZMQ capability.
 ///
+/// Returns `false` for unknown capabilities, including the names
+/// that contain a nul byte.
+///
 /// See [`zmq_has`].
 ///
 /// [`zmq_has`]: http://api.zeromq.org/4-2:zmq-has
 ///
 /// ```
-/// use libzmq::has;
+/// use libzmq::{has, Capability};
 ///
-/// assert!(has("curve"));
+/// assert!(has(Capability::Curve));
+/// assert!(has("draft"));
+/// assert!(!has(Capability::Other("carrier pigeon")));
 /// ```
-pub fn has(capability: &str) -> bool {
-    let c_str = ffi::CString::new(capability).unwrap();
-    unsafe { sys::zmq_has(c_str.as_ptr()) == 1 }
+pub fn has<'a, C>(capability: C) -> bool
+where
+    C: Into<Capability<'a>>,
+{
+    match ffi::CString::new(capability.into().as_str()) {
+        Ok(c_str) => unsafe { sys::zmq_has(c_str.as_ptr()) == 1 },
+        Err(_) => false,
+    }
 }
 
 /// Start a built-in ØMQ proxy between a frontend and a backend socket.
@@ -65,8 +245,8 @@ pub fn has(capability: &str) -> bool {
 /// # Example
 /// ```
 /// #
-/// # use failure::Error;
-/// # fn main() -> Result<(), Error> {
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
 /// use libzmq::{prelude::*, *};
 /// use std::{thread, convert::TryInto};
 ///
@@ -120,12 +300,66 @@ where
     assert_eq!(rc, -1);
 
     let errno = unsafe { sys::zmq_errno() };
-    let err = {
-        match errno {
-            errno::ETERM => Error::new(ErrorKind::CtxTerminated),
-            _ => panic!(msg_from_errno(errno)),
-        }
-    };
+    let err = from_errno(errno);
 
     Err(err)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_z85_invalid_input() {
+        for len in &[1, 2, 3, 5, 31] {
+            let err = z85_encode(&vec![0; *len]).unwrap_err();
+            match err.kind() {
+                ErrorKind::InvalidInput { .. } => (),
+                _ => panic!("unexpected error"),
+            }
+        }
+
+        for text in &["Hell", "HelloWorl", "Hell\0", "Hello~orld"] {
+            let err = z85_decode(text).unwrap_err();
+            match err.kind() {
+                ErrorKind::InvalidInput { .. } => (),
+                _ => panic!("unexpected error"),
+            }
+        }
+
+        assert_eq!(z85_encode(&[]).unwrap(), "");
+        assert!(z85_decode("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_version() {
+        let (major, minor, patch) = version();
+        assert!(major >= 4);
+        assert!(minor >= 0 && patch >= 0);
+    }
+
+    #[test]
+    fn test_has() {
+        // The crate requires the draft API.
+        assert!(has(Capability::Draft));
+        assert_eq!(has(Capability::Curve), has("curve"));
+        assert!(!has(Capability::Other("unknown")));
+        assert!(!has("nul\0byte"));
+        assert_eq!(has(&"draft".to_owned()), has(Capability::Draft));
+
+        assert_eq!(Capability::from("pgm"), Capability::Pgm);
+        assert_eq!(Capability::from("other"), Capability::Other("other"));
+        assert_eq!(Capability::Gssapi.to_string(), "gssapi");
+    }
+
+    quickcheck! {
+        fn z85_codec_quickcheck(data: Vec<u8>) -> bool {
+            let len = data.len() / 4 * 4;
+            let data = &data[..len];
+
+            let text = z85_encode(data).unwrap();
+            text.len() == len / 4 * 5 && z85_decode(&text).unwrap() == data
+        }
+    }
+}
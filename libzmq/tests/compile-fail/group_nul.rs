@@ -0,0 +1,3 @@
+fn main() {
+    let _group = libzmq::group!("nul\0byte");
+}
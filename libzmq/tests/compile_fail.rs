@@ -0,0 +1,12 @@
+// The expected compiler output depends on the version of rustc. Run with
+// `TRYBUILD=overwrite` to regenerate the `.stderr` files.
+#[test]
+fn test_compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile-fail/*.rs");
+    // These literals only exceed the limit of `libzmq` versions prior to
+    // 4.3.3.
+    if libzmq::MAX_GROUP_SIZE == 15 {
+        t.compile_fail("tests/compile-fail/short-groups/*.rs");
+    }
+}
@@ -0,0 +1,48 @@
+// Checks a same-host `udp` multicast round trip between a `Radio` and a
+// `Dish`. It is ignored by default since it requires an interface with
+// multicast support, run it with:
+// `cargo test -p libzmq --test multicast -- --ignored`.
+
+use libzmq::{prelude::*, *};
+
+use std::{convert::TryInto, thread, time::Duration};
+
+#[test]
+#[ignore]
+fn test_udp_multicast_loop() {
+    let addr: UdpAddr = "239.0.0.1:5555".try_into().unwrap();
+    let group: &Group = "multicast".try_into().unwrap();
+
+    let dish = DishBuilder::new()
+        .multicast_loop(true)
+        .recv_timeout(Duration::from_millis(100))
+        .bind(&addr)
+        .join(group)
+        .build()
+        .unwrap();
+    let radio = RadioBuilder::new()
+        .multicast_loop(true)
+        .connect(&addr)
+        .build()
+        .unwrap();
+
+    // The datagrams sent before the dish joined the multicast group are
+    // lost, so we retry until one comes through.
+    for _ in 0..50 {
+        let mut msg: Msg = "ping".into();
+        msg.set_group(group);
+        radio.send(msg).unwrap();
+
+        match dish.recv_msg() {
+            Ok(msg) => {
+                assert_eq!(msg.group().unwrap(), group);
+                assert_eq!(msg.to_str().unwrap(), "ping");
+                return;
+            }
+            Err(err) => assert_eq!(err.kind(), ErrorKind::WouldBlock),
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    panic!("no datagram was looped back");
+}
@@ -0,0 +1,68 @@
+// Checks that the asynchronous sockets work on an executor other than
+// `tokio`.
+#![cfg(feature = "async-io")]
+
+use libzmq::{
+    aio::{compat::async_io::timeout, *},
+    *,
+};
+
+use std::time::Duration;
+
+#[test]
+fn test_smol_req_rep() {
+    let addr = InprocAddr::new_unique();
+
+    let server: AsyncServer =
+        ServerBuilder::new().bind(&addr).build().unwrap().into();
+
+    smol::block_on(async {
+        let server_task = smol::spawn(async move {
+            for _ in 0..8 {
+                let msg = server.recv().await.unwrap();
+                let mut reply: Msg =
+                    format!("re: {}", msg.to_str().unwrap()).into();
+                reply.set_routing_id(msg.routing_id().unwrap());
+                server.send(reply).await.unwrap();
+            }
+        });
+
+        let mut tasks = Vec::new();
+        for i in 0..8 {
+            let client: AsyncClient =
+                ClientBuilder::new().connect(&addr).build().unwrap().into();
+
+            tasks.push(smol::spawn(async move {
+                client.send(format!("{}", i)).await.unwrap();
+                let msg = client.recv().await.unwrap();
+                assert_eq!(msg.to_str().unwrap(), format!("re: {}", i));
+            }));
+        }
+
+        for task in tasks {
+            task.await;
+        }
+        server_task.await;
+    });
+}
+
+#[test]
+fn test_smol_timeout() {
+    let addr = InprocAddr::new_unique();
+
+    let server: AsyncServer =
+        ServerBuilder::new().bind(&addr).build().unwrap().into();
+    let client: AsyncClient =
+        ClientBuilder::new().connect(&addr).build().unwrap().into();
+
+    smol::block_on(async {
+        let duration = Duration::from_millis(50);
+        let err = timeout(duration, server.recv()).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        // The cancelled future must not have consumed the message.
+        client.send("msg").await.unwrap();
+        let msg = timeout(duration, server.recv()).await.unwrap();
+        assert_eq!(msg.to_str().unwrap(), "msg");
+    });
+}